@@ -0,0 +1,78 @@
+//! Touch events per second through the channel the listeners send on, with
+//! the device info shared between events as the listeners do, and copied into
+//! each event as they did before.
+//!
+//! `cargo bench --bench events`
+
+use std::sync::Arc;
+use std::thread;
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use evdev::BusType;
+use input_device_test::device_class::DeviceClass;
+use input_device_test::event_handler::{AppEvent, DeviceInfo};
+
+const EVENTS: u64 = 100_000;
+
+fn panel() -> DeviceInfo {
+    DeviceInfo {
+        path: "/dev/input/event7".to_string(),
+        name: "eGalax Inc. USB TouchController".to_string(),
+        abs_x_max: Some(4095),
+        abs_y_max: Some(4095),
+        multitouch: false,
+        phys: Some("usb-0000:00:14.0-4/input0".to_string()),
+        vendor: 0x0eef,
+        product: 0x0001,
+        bus: BusType::BUS_USB,
+        pressure_max: None,
+        resolution: None,
+        class: DeviceClass::Touchscreen,
+        learned_range: None,
+        swapped_axes: false,
+        ids: Arc::default(),
+    }
+}
+
+/// Send a drag of `EVENTS` samples from a listener thread and read it on
+/// this one, `info_of` giving each sample its device
+fn drag(info: &Arc<DeviceInfo>, info_of: fn(&Arc<DeviceInfo>) -> Arc<DeviceInfo>) {
+    let (sender, receiver) = crossbeam_channel::unbounded();
+    let info = Arc::clone(info);
+    let listener = thread::spawn(move || {
+        for i in 0..EVENTS {
+            let sample = AppEvent::Touch {
+                x: (i % 4096) as u16,
+                y: (i / 7 % 4096) as u16,
+                timestamp: i as u128,
+                released: false,
+                pressure: None,
+                info: Some(info_of(&info)),
+                keepalive: false,
+            };
+            if sender.send(sample).is_err() {
+                break;
+            }
+        }
+    });
+    let received = receiver.iter().count();
+    listener.join().unwrap();
+    assert_eq!(received as u64, EVENTS);
+}
+
+fn events_per_second(c: &mut Criterion) {
+    let info = Arc::new(panel());
+    let mut group = c.benchmark_group("touch drag");
+    group.throughput(Throughput::Elements(EVENTS));
+    group.sample_size(20);
+    group.bench_function(BenchmarkId::new("device info", "shared"), |b| {
+        b.iter(|| drag(&info, Arc::clone))
+    });
+    group.bench_function(BenchmarkId::new("device info", "copied per event"), |b| {
+        b.iter(|| drag(&info, |info| Arc::new(DeviceInfo::clone(info))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, events_per_second);
+criterion_main!(benches);
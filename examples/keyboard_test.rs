@@ -0,0 +1,48 @@
+//! Runs only the keyboard test in a terminal set up by the caller, the way a
+//! provisioning tool embeds it, and prints the result when it's left.
+
+use color_eyre::Result;
+use crossbeam_channel::unbounded;
+
+use input_device_test::{
+    Nav, RateLimit, Screen,
+    event_handler::{self, DeviceSource},
+    keyboard_test::KeyboardTestScreen,
+    machine_detect::get_computer_model,
+};
+
+fn main() -> Result<()> {
+    color_eyre::install()?;
+
+    let (tx, rx) = unbounded();
+    // Keyboard only, no resting touches to repeat
+    event_handler::spawn_device_listeners(
+        &tx,
+        DeviceSource::system(),
+        RateLimit::default(),
+        None,
+        true,
+    )?;
+
+    let mut terminal = ratatui::init();
+    let mut screen = KeyboardTestScreen::for_model(get_computer_model());
+
+    let result = loop {
+        if let Err(e) = terminal.draw(|f| screen.draw(f)) {
+            ratatui::restore();
+            return Err(e.into());
+        }
+        let event = rx.recv()?;
+        if let Nav::To(_) = screen.handle_event(event) {
+            break screen.take_results();
+        }
+    };
+
+    ratatui::restore();
+
+    match result {
+        Some(result) => println!("{}: {}", result.title(), result.summary()),
+        None => println!("Left before testing any key"),
+    }
+    Ok(())
+}
@@ -0,0 +1,1473 @@
+//! The tool's run loop: the home menu, switching between screens, the help
+//! overlay and collecting results into the session report.
+
+use color_eyre::{Result, eyre::eyre};
+use crossbeam_channel::{Receiver, unbounded};
+use evdev::KeyCode;
+use ratatui::{
+    Frame, Terminal,
+    backend::Backend,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    symbols::border,
+    text::{Line, Text},
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::{
+    Nav, Screen, ScreenId,
+    capture::Capture,
+    cli::Options,
+    device_ids,
+    event_handler::{self, AppEvent},
+    event_queue::EventQueue,
+    event_ring::{self, EventRing},
+    frame_budget::FrameBudget,
+    glyphs,
+    home_menu::{Activated, Entry, Menu},
+    keyboard_test::KeyboardTestScreen,
+    keymap::{Action, KeyMap, KeyRepeat, NavGuard},
+    logging,
+    machine_detect::{ComputerModel, get_computer_model},
+    mouse_test::{MouseSettings, MouseTestScreen},
+    report::{SessionMetadata, SessionReport},
+    resume::{self, ResumeScreen},
+    self_check,
+    session_info::SessionInfoScreen,
+    sim, status_server,
+    terminal_echo::{self, RawState},
+    theme::Theme,
+    touch_nav::{self, CornerEscape},
+    touchscreen_test::TouchscreenTestScreen,
+    trackpad_test::TrackpadTestScreen,
+    watchdog::{self, WatchdogConfig},
+    writer::{self, WriterHandle},
+};
+
+// How long the summary of a finished test stays up
+const SUMMARY_DURATION: Duration = Duration::from_secs(5);
+
+// A failed draw is retried this many times in total, the delay doubling from
+// DRAW_RETRY_DELAY, before the session gives up. The console returns transient
+// errors like EIO during a VT switch.
+const DRAW_ATTEMPTS: u32 = 4;
+const DRAW_RETRY_DELAY: Duration = Duration::from_millis(50);
+
+// The self-check's diagnostic screen gives up waiting for a key after this
+const SELF_CHECK_SCREEN_TIMEOUT: Duration = Duration::from_secs(60);
+
+struct HomeScreen {
+    // Groups and tests, see `home_menu`
+    menu: Menu,
+
+    // Frame area of the last draw, needed to hit-test touches against the menu
+    area: Cell<Rect>,
+    // Menu row under the finger when the current touch went down
+    pressed_row: Option<usize>,
+    touching: bool,
+    // Shown in the top left corner once entered
+    metadata: SessionMetadata,
+    theme: Theme,
+}
+
+/// Geometry of the home screen in one frame
+struct HomeLayout {
+    /// Entries of the level shown, in menu order
+    items: Vec<Rect>,
+    /// Operator and unit details in the top left corner
+    details: Rect,
+}
+
+impl HomeScreen {
+    /// Menu for the given machine, only listing tests its hardware supports
+    fn for_model(model: ComputerModel) -> Self {
+        HomeScreen {
+            menu: Menu::for_model(model),
+            area: Cell::new(Rect::default()),
+            pressed_row: None,
+            touching: false,
+            metadata: SessionMetadata::default(),
+            theme: Theme::default(),
+        }
+    }
+
+    fn with_metadata(mut self, metadata: SessionMetadata) -> Self {
+        self.metadata = metadata;
+        self
+    }
+
+    /// Highlight the selected entry in `theme`'s colors
+    fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Where everything goes in a frame of `area`, shared by drawing and the
+    /// touch hit testing
+    fn layout(&self, area: Rect) -> HomeLayout {
+        // Session details in the top left corner, inside the border
+        let detail_lines = [&self.metadata.operator_id, &self.metadata.unit_serial]
+            .iter()
+            .filter(|detail| detail.is_some())
+            .count() as u16;
+        let mut details = Rect {
+            x: area.x + 2,
+            y: area.y + 1,
+            width: area.width.saturating_sub(4).min(50),
+            height: detail_lines.min(area.height.saturating_sub(2)),
+        };
+        // A terminal too small for both keeps the menu
+        let items = self.item_rects(area);
+        if items.iter().any(|item| item.intersects(details)) {
+            details.height = 0;
+        }
+
+        HomeLayout { items, details }
+    }
+
+    /// Screen rectangle of every entry shown, in menu order
+    fn item_rects(&self, area: Rect) -> Vec<Rect> {
+        let rows = self.menu.entries().len() as u16;
+        let vertical_center = Layout::vertical([Constraint::Length(rows * 3)])
+            .flex(Flex::Center)
+            .split(area)[0];
+
+        let menu_rect = Layout::horizontal([Constraint::Percentage(25)])
+            .flex(Flex::Center)
+            .split(vertical_center)[0];
+
+        (0..rows)
+            .map(|i| {
+                Rect {
+                    y: menu_rect.y + i * 3,
+                    height: 3,
+                    ..menu_rect
+                }
+                .intersection(menu_rect)
+            })
+            .collect()
+    }
+
+    /// Menu row under a raw touch position, placed with the device's axis range
+    /// as its calibration
+    fn touched_row(&self, (x, y): (u32, u32), range: (u32, u32)) -> Option<usize> {
+        let area = self.area.get();
+        let cell = touch_nav::cell_at(x, y, range, area);
+
+        self.layout(area)
+            .items
+            .iter()
+            .position(|rect| rect.contains(cell.into()))
+    }
+
+    fn handle_touch(&mut self, row: Option<usize>, released: bool) -> Nav {
+        if released {
+            let pressed = self.pressed_row.take();
+            self.touching = false;
+
+            // Tap and release within the same entry runs it
+            if let Some(row) = row
+                && pressed == Some(row)
+            {
+                return self.activate(row);
+            }
+            return Nav::Stay;
+        }
+
+        if self.touching {
+            return Nav::Stay;
+        }
+        self.touching = true;
+
+        if let Some(row) = row {
+            // Release coordinates jitter on resistive panels, so a second tap on the
+            // highlighted entry runs it without waiting for the release
+            if row == self.menu.selected() {
+                return self.activate(row);
+            }
+            self.menu.select(row);
+        }
+        self.pressed_row = row;
+
+        Nav::Stay
+    }
+
+    /// Run entry `index` of the level shown, or go into or out of a group
+    fn activate(&mut self, index: usize) -> Nav {
+        match self.menu.activate(index) {
+            Some(Activated::Run(screen)) => Nav::To(screen),
+            Some(Activated::Moved) | None => Nav::Stay,
+        }
+    }
+}
+
+impl Screen for HomeScreen {
+    fn id(&self) -> ScreenId {
+        ScreenId::Home
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        let mut title = Line::from("Input Diagnostics Tool".bold().cyan());
+        if let Some(group) = self.menu.breadcrumb() {
+            title.push_span(" > ".dark_gray());
+            title.push_span(group.bold().cyan());
+        }
+
+        let escape = if self.menu.breadcrumb().is_some() {
+            " back   "
+        } else {
+            " exit   "
+        };
+        let footer = Line::from(vec![
+            "↑/↓".bold().yellow(),
+            " navigate   ".into(),
+            "Enter".bold().yellow(),
+            " open/run   ".into(),
+            "1..9".bold().yellow(),
+            " quick launch   ".into(),
+            "Esc".bold().yellow(),
+            escape.into(),
+            "F1".bold().yellow(),
+            " help".into(),
+        ]);
+
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(footer.centered())
+            .border_set(border::THICK);
+
+        frame.render_widget(block, area);
+        self.area.set(area);
+        let layout = self.layout(area);
+
+        for (i, (entry, rect)) in self.menu.entries().iter().zip(layout.items).enumerate() {
+            let style = if i == self.menu.selected() {
+                self.theme.highlight()
+            } else {
+                Style::default()
+            };
+            let item = Paragraph::new(
+                Text::from(vec![
+                    "".into(),
+                    Line::from(match entry {
+                        Entry::Group { label, .. } => format!("{})  {label} >", i + 1),
+                        entry => format!("{})  {}", i + 1, entry.label()),
+                    }),
+                    "".into(),
+                ])
+                .centered(),
+            )
+            .style(style);
+
+            frame.render_widget(item, rect);
+        }
+
+        let mut details = Vec::new();
+        if let Some(operator) = &self.metadata.operator_id {
+            details.push(Line::from(vec![
+                "Operator: ".dark_gray(),
+                operator.clone().into(),
+            ]));
+        }
+        if let Some(serial) = &self.metadata.unit_serial {
+            details.push(Line::from(vec![
+                "Unit: ".dark_gray(),
+                serial.clone().into(),
+            ]));
+        }
+        if !details.is_empty() {
+            frame.render_widget(Paragraph::new(details), layout.details);
+        }
+    }
+
+    fn handle_event(&mut self, event: AppEvent) -> Nav {
+        if let AppEvent::Touch {
+            x,
+            y,
+            released,
+            ref info,
+            ..
+        } = event
+        {
+            // Devices without a range (e.g. the serial panel) count as uncalibrated
+            let Some((position, range)) = touch_nav::screen_position(x, y, info.as_deref()) else {
+                return Nav::Stay;
+            };
+            let row = self.touched_row(position, range);
+            return self.handle_touch(row, released);
+        }
+
+        if let AppEvent::Key { code, .. } = event {
+            match code {
+                KeyCode::KEY_DOWN => self.menu.move_selection(true),
+                KeyCode::KEY_UP => self.menu.move_selection(false),
+                KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => {
+                    return self.activate(self.menu.selected());
+                }
+                KeyCode::KEY_BACKSPACE => {
+                    self.menu.back();
+                }
+                // Up a level first, out of the tool from the top
+                KeyCode::KEY_ESC if self.menu.back() => {}
+                KeyCode::KEY_ESC => return Nav::To(ScreenId::Exit),
+                KeyCode::KEY_Q => return Nav::To(ScreenId::Exit),
+                _ => {
+                    // Quick launch by number, among the entries shown
+                    if let Some(index) = digit_index(code) {
+                        return self.activate(index);
+                    }
+                }
+            }
+        }
+
+        Nav::Stay
+    }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("↑/↓", "Move the selection"),
+            ("Enter", "Open the selected group or run the selected test"),
+            ("1..9", "Quick launch an entry of the level shown"),
+            ("Tap", "Highlight an entry, tap it again to open or run it"),
+            (
+                "Esc/Backspace",
+                "Back to the top level, Esc there exits the tool",
+            ),
+            ("Q", "Exit the tool"),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "Pick which input device test to run from Input tests. Only tests for hardware present on \
+         this machine model are listed; after plugging hardware in, Rescan hardware under \
+         Diagnostics detects the model again. Settings holds the operator and unit details."
+    }
+
+    fn repeats_navigation(&self) -> bool {
+        true
+    }
+
+    fn reconfigure(&mut self, options: &Options) {
+        self.theme = options.theme;
+    }
+}
+
+/// Show the home menu (or the session info prompt) and run screens until the
+/// user exits, adding each finished test to `report`. When the session fails
+/// (the terminal keeps refusing draws, the event sources are gone) the results
+/// of the screens still open are added before the error is returned.
+pub fn run<B: Backend>(
+    terminal: &mut Terminal<B>,
+    report: &mut SessionReport,
+    options: &Options,
+    log: Option<&WriterHandle>,
+) -> Result<()> {
+    // Replaced when the config files are read again
+    let mut options = options.clone();
+    let first_screen = if options.ask_metadata {
+        ScreenId::SessionInfo
+    } else {
+        ScreenId::Home
+    };
+    // A session interrupted by a reboot is offered first. Demo and hardware
+    // sessions never mix.
+    let saved = options
+        .log_dir
+        .as_deref()
+        .filter(|_| !options.resume_max_age.is_zero())
+        .and_then(|dir| resume::load_saved(dir, options.resume_max_age))
+        .filter(|(saved, _)| saved.demo == report.demo);
+    let mut active_screen: Box<dyn Screen> = match saved {
+        Some((saved, age)) => Box::new(ResumeScreen::new(saved, age, first_screen)),
+        None => create_screen(first_screen, &options, &report.metadata, log),
+    };
+
+    glyphs::select(options.glyphs);
+
+    let (tx, rx) = unbounded();
+
+    // The hardware dependent sources, None in a demo or a replay
+    let mut hardware = if let Some(path) = &options.replay_capture {
+        event_handler::spawn_capture_replay(&tx, Capture::read(path)?, options.key_rate_limit);
+        event_handler::spawn_tick_thread(&tx);
+        None
+    } else if options.demo {
+        sim::spawn_generators(&tx, options.demo_seed);
+        event_handler::spawn_tick_thread(&tx);
+        None
+    } else {
+        Some(event_handler::spawn_device_listeners(
+            &tx,
+            options.device_source(),
+            options.key_rate_limit,
+            options.touch_keepalive,
+            options.serial_baud_sweep,
+        )?)
+    };
+    event_handler::spawn_reload_signal(&tx);
+
+    // The terminal was put in raw mode before this; when it didn't take, keys
+    // typed during the tests end up in the shell after exit
+    let raw_state = RawState::check();
+    if raw_state != RawState::Raw {
+        logging::warn(&format!(
+            "Terminal not in raw mode ({}), keystrokes may reach the shell",
+            raw_state.describe()
+        ));
+    }
+    terminal_echo::record_raw_state(raw_state);
+    event_handler::spawn_terminal_reader(&tx);
+
+    // Behind the Loading view, whose draw is the terminal check. A terminal
+    // that can't draw can't show the diagnostic screen either.
+    draw_with_retry(terminal, log, draw_loading)
+        .map_err(|e| eyre!("startup self-check failed, terminal: {e}"))?;
+    let mut failures: Vec<self_check::Failure> = self_check::check_clock().into_iter().collect();
+    let received = self_check::check_events(&tx, &rx, &mut failures);
+    if !failures.is_empty() {
+        return Err(show_self_check_failures(terminal, &rx, log, &failures));
+    }
+    // Only the producers hold senders from here on, so a closed channel means
+    // every one of them is gone
+    drop(tx);
+
+    // A stall is only worth watching for when there's somewhere to report it
+    if log.is_some() || options.exit_on_stall {
+        watchdog::spawn(
+            rx.clone(),
+            WatchdogConfig {
+                log: log.cloned(),
+                exit_on_stall: options.exit_on_stall,
+            },
+        );
+    }
+
+    let mut exit = false;
+    let mut help_visible = false;
+    // Outcome of the test just left, shown for a few seconds on the next screen
+    let mut last_summary: Option<(String, Instant)> = None;
+    // Preserved screens, at most one per screen id
+    let mut cached_screens: HashMap<ScreenId, Box<dyn Screen>> = HashMap::new();
+    // Report index of each preserved screen's result, replaced when it's left again
+    let mut result_slots: HashMap<ScreenId, usize> = HashMap::new();
+    let mut key_repeat = KeyRepeat::new();
+    let mut corner_escape = CornerEscape::new();
+    let mut nav_guard = NavGuard::new();
+    let mut frame_budget = FrameBudget::new(options.frame_budget, options.degrade_ladder.clone());
+    let mut queue = EventQueue::new();
+    queue.requeue(received);
+    // What the screens saw lately, written out with the dump key
+    let mut event_ring = EventRing::new();
+
+    let outcome: Result<()> = loop {
+        if exit {
+            break Ok(());
+        }
+
+        let draw_started = Instant::now();
+        let drawn = draw_with_retry(terminal, log, |f| {
+            active_screen.draw(f);
+            if options.demo {
+                draw_badge(f, " DEMO MODE - simulated devices ");
+            } else if options.replay_capture.is_some() {
+                draw_badge(f, " REPLAY - recorded capture ");
+            }
+            if let Some((summary, since)) = &last_summary
+                && since.elapsed() < SUMMARY_DURATION
+            {
+                draw_summary_line(f, summary);
+            }
+            if help_visible {
+                draw_help_overlay(f, active_screen.as_ref(), &options.keymap, log);
+            }
+        });
+        if let Err(e) = drawn {
+            break Err(e);
+        }
+        if let Some(change) = frame_budget.record(draw_started.elapsed()) {
+            active_screen.set_degradations(frame_budget.active());
+            last_summary = Some((change.notice(frame_budget.average()), Instant::now()));
+        }
+
+        let next_event = match queue.next(&rx) {
+            Ok(event) => event,
+            Err(_) => break Err(eyre!("every input event source stopped")),
+        };
+        watchdog::received();
+
+        // The report lists the driver and firmware of every device heard from
+        if let Some(info) = next_event.device()
+            && !report.has_device(&info.path)
+        {
+            report.add_device(device_ids::result(info));
+        }
+
+        if let AppEvent::Tick = next_event {
+            status_server::publish(active_screen.id(), active_screen.progress(), report);
+        }
+
+        if let Some(log) = log {
+            log_event(log, &next_event);
+        }
+
+        // Settings taken at startup are replaced, open and preserved screens
+        // are told, keys are translated with the new bindings from here on
+        if let AppEvent::ConfigChanged = next_event {
+            let notice = match options.reload() {
+                Ok(reloaded) => {
+                    options = reloaded;
+                    glyphs::select(options.glyphs);
+                    frame_budget =
+                        FrameBudget::new(options.frame_budget, options.degrade_ladder.clone());
+                    active_screen.set_degradations(frame_budget.active());
+                    active_screen.reconfigure(&options);
+                    for screen in cached_screens.values_mut() {
+                        screen.set_degradations(frame_budget.active());
+                        screen.reconfigure(&options);
+                    }
+                    "Config reloaded".to_string()
+                }
+                // The old settings stay
+                Err(e) => format!("Config not reloaded: {e}"),
+            };
+            last_summary = Some((notice, Instant::now()));
+            continue;
+        }
+
+        settle_finalized(&mut cached_screens, &next_event);
+
+        // The key that switched screens, still held or bouncing
+        if nav_guard.swallows(&next_event) {
+            continue;
+        }
+        // Raw key of the event, to guard if it switches screens
+        let trigger = match next_event {
+            AppEvent::Key {
+                code, timestamp, ..
+            } => Some((code, timestamp)),
+            _ => None,
+        };
+
+        let next_event = if active_screen.wants_raw_keys() {
+            next_event
+        } else {
+            match options.keymap.translate(next_event, !options.raw_keypad) {
+                Some(event) => event,
+                // The default key of a rebound action
+                None => continue,
+            }
+        };
+
+        let steps = match next_event {
+            AppEvent::Key { repeat: true, .. } if active_screen.wants_key_repeats() => 1,
+            _ => key_repeat.steps(&next_event, active_screen.repeats_navigation()),
+        };
+        if steps == 0 {
+            continue;
+        }
+
+        // A long press in the top left corner goes home from anywhere, for units
+        // without a keyboard
+        let escaped = corner_escape.observe(&next_event) && active_screen.id() != ScreenId::Home;
+        if escaped {
+            help_visible = false;
+        }
+
+        // The help overlay is modal: any key closes it and nothing but ticks reaches the screen
+        if help_visible {
+            match next_event {
+                AppEvent::Key { repeat: false, .. } => help_visible = false,
+                AppEvent::Tick => {
+                    active_screen.handle_event(next_event);
+                }
+                _ => {}
+            }
+            continue;
+        }
+
+        if let AppEvent::Key { code, .. } = next_event
+            && is_help_key(code, active_screen.as_ref(), &options.keymap)
+        {
+            help_visible = true;
+            continue;
+        }
+
+        if let AppEvent::Key {
+            code,
+            repeat: false,
+            ..
+        } = next_event
+            && is_dump_key(code, active_screen.as_ref(), &options.keymap)
+        {
+            let notice = dump_events(&event_ring, log, options.log_dir.as_deref());
+            last_summary = Some((notice, Instant::now()));
+            continue;
+        }
+
+        // A configured finish key leaves any test, like Ctrl x4 does
+        let finished = match next_event {
+            AppEvent::Key {
+                code,
+                repeat: false,
+                ..
+            } => options
+                .keymap
+                .keys_for(Action::FinishTest)
+                .is_some_and(|keys| keys.contains(&code)),
+            _ => false,
+        } && active_screen.id() != ScreenId::Home;
+
+        let navigation = if escaped || finished {
+            Nav::To(ScreenId::Home)
+        } else {
+            // An accelerated repeat moves the selection several entries at
+            // once, but stops at a step that leaves the screen: its results
+            // are taken right after, and the summary has to match them
+            event_ring.record(&next_event);
+            let mut navigation = Nav::Stay;
+            for _ in 0..steps {
+                navigation = active_screen.handle_event(next_event.clone());
+                if let Nav::To(_) = navigation {
+                    break;
+                }
+            }
+            navigation
+        };
+
+        if let Nav::To(_) = navigation
+            && let Some(metadata) = active_screen.take_metadata()
+        {
+            report.metadata = metadata;
+        }
+
+        if let Nav::To(_) = navigation
+            && active_screen.id() == ScreenId::Resume
+        {
+            match active_screen.take_restored() {
+                Some(saved) => report.restore(saved),
+                // Starting over abandons the saved session
+                None => {
+                    if let Some(log) = log {
+                        log.remove(&writer::SESSION_STATE);
+                    }
+                }
+            }
+        }
+
+        if let Nav::To(_) = navigation
+            && let Some(summary) =
+                record_results(active_screen.as_mut(), report, &mut result_slots, log)
+        {
+            last_summary = Some((summary, Instant::now()));
+        }
+
+        if let Nav::To(_) = navigation {
+            active_screen.on_exit();
+            nav_guard.arm(trigger);
+        }
+
+        match navigation {
+            Nav::Stay => {}
+            Nav::To(ScreenId::Exit) => {
+                exit = true;
+            }
+            // Only the home menu offers it, so that's where it comes back to
+            Nav::To(ScreenId::Rescan) => {
+                let changes = match &mut hardware {
+                    Some(hardware) => hardware.rescan(),
+                    None if options.demo => vec!["demo devices are simulated".to_string()],
+                    None => vec!["a capture is replayed".to_string()],
+                };
+                let notice = if changes.is_empty() {
+                    "Rescan: no hardware changes".to_string()
+                } else {
+                    format!("Rescan: {}", changes.join(", "))
+                };
+                last_summary = Some((notice, Instant::now()));
+                active_screen = create_screen(ScreenId::Home, &options, &report.metadata, log);
+                active_screen.set_degradations(frame_budget.active());
+            }
+            Nav::To(screen_id) => {
+                let mut next = match cached_screens.remove(&screen_id) {
+                    Some(mut screen) => {
+                        screen.on_enter();
+                        screen
+                    }
+                    None => {
+                        if let Err(e) = draw_with_retry(terminal, log, draw_loading) {
+                            break Err(e);
+                        }
+                        // A fresh screen starts a fresh result
+                        result_slots.remove(&screen_id);
+                        create_screen(screen_id, &options, &report.metadata, log)
+                    }
+                };
+
+                next.set_degradations(frame_budget.active());
+                let previous = std::mem::replace(&mut active_screen, next);
+                if previous.preserve_on_exit() {
+                    cached_screens.insert(previous.id(), previous);
+                }
+            }
+        }
+    };
+
+    // Otherwise the session is complete and there is nothing left to resume
+    if outcome.is_err() {
+        record_open_results(
+            active_screen.as_mut(),
+            &mut cached_screens,
+            report,
+            &mut result_slots,
+            log,
+        );
+    } else if let Some(log) = log {
+        log.remove(&writer::SESSION_STATE);
+    }
+
+    outcome
+}
+
+/// Screens left are finalized: their results were taken and the summary shown
+/// from them, so they get nothing that could change a count. Key releases
+/// still reach them to keep their held keys right.
+fn settle_finalized(cached_screens: &mut HashMap<ScreenId, Box<dyn Screen>>, event: &AppEvent) {
+    if let AppEvent::KeyReleased { .. } = event {
+        for screen in cached_screens.values_mut() {
+            screen.settle(event);
+        }
+    }
+}
+
+/// Whatever the open screens collected when the session ends with an error,
+/// so it still makes it into the report
+fn record_open_results(
+    active_screen: &mut dyn Screen,
+    cached_screens: &mut HashMap<ScreenId, Box<dyn Screen>>,
+    report: &mut SessionReport,
+    result_slots: &mut HashMap<ScreenId, usize>,
+    log: Option<&WriterHandle>,
+) {
+    record_results(active_screen, report, result_slots, log);
+    active_screen.on_exit();
+    for screen in cached_screens.values_mut() {
+        record_results(screen.as_mut(), report, result_slots, log);
+    }
+}
+
+/// Take `screen`'s results into `report` and the result log. Returns the
+/// summary line, None if the screen had nothing to report.
+fn record_results(
+    screen: &mut dyn Screen,
+    report: &mut SessionReport,
+    result_slots: &mut HashMap<ScreenId, usize>,
+    log: Option<&WriterHandle>,
+) -> Option<String> {
+    let result = screen.take_results()?;
+    let summary = format!("{}: {}", result.title(), result.summary());
+
+    if let Some(log) = log
+        && let Ok(json) = serde_json::to_string(&result)
+    {
+        log.append(&writer::RESULT_LOG, json + "\n");
+    }
+
+    // A preserved screen reports its whole history again, so it replaces its
+    // earlier result instead of adding a second one
+    let id = screen.id();
+    match result_slots.get(&id) {
+        Some(&index) => report.replace(index, result),
+        None => {
+            if screen.preserve_on_exit() {
+                result_slots.insert(id, report.results.len());
+            }
+            report.add(result);
+        }
+    }
+
+    // The whole session so far, for resuming after a reboot
+    if let Some(log) = log
+        && let Ok(json) = serde_json::to_string(report)
+    {
+        log.replace(&writer::SESSION_STATE, json);
+    }
+
+    Some(summary)
+}
+
+/// Draw a frame, retrying with backoff when the terminal fails. Each retry
+/// starts from a cleared screen, since a failed draw leaves it half written.
+fn draw_with_retry<B: Backend>(
+    terminal: &mut Terminal<B>,
+    log: Option<&WriterHandle>,
+    mut render: impl FnMut(&mut Frame),
+) -> Result<()> {
+    let mut delay = DRAW_RETRY_DELAY;
+    let mut attempt = 1;
+    loop {
+        let drawn = terminal.draw(|frame| {
+            render(frame);
+            glyphs::apply(frame.buffer_mut());
+        });
+        let error = match drawn {
+            Ok(_) => return Ok(()),
+            Err(e) => e,
+        };
+        if let Some(log) = log {
+            log.append(
+                &writer::FAULT_LOG,
+                format!("terminal draw failed (attempt {attempt}/{DRAW_ATTEMPTS}): {error}\n"),
+            );
+        }
+        if attempt >= DRAW_ATTEMPTS {
+            return Err(eyre!("terminal draw failed {DRAW_ATTEMPTS} times: {error}"));
+        }
+
+        thread::sleep(delay);
+        delay *= 2;
+        attempt += 1;
+        let _ = terminal.clear();
+    }
+}
+
+fn log_event(log: &WriterHandle, event: &AppEvent) {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+
+    match event {
+        // Ticks would drown everything else
+        AppEvent::Tick => {}
+        AppEvent::DeviceFault { info, reason } => {
+            log.append(
+                &writer::FAULT_LOG,
+                format!("{millis} {} ({}): {reason}\n", info.name, info.path),
+            );
+        }
+        event => {
+            log.append(&writer::EVENT_LOG, event_ring::event_line(event));
+        }
+    }
+}
+
+fn draw_summary_line(frame: &mut Frame, summary: &str) {
+    let area = frame.area();
+    let text = format!(" {summary} ");
+    let width = (text.chars().count() as u16).min(area.width);
+
+    let rect = Rect {
+        x: (area.width.saturating_sub(width)) / 2,
+        y: area.height.saturating_sub(2),
+        width,
+        height: 1.min(area.height),
+    };
+
+    frame.render_widget(Clear, rect);
+    frame.render_widget(
+        Paragraph::new(text).style(Style::default().black().on_cyan()),
+        rect,
+    );
+}
+
+// Drawn over the top row of every screen so a demo or replay run is never
+// mistaken for a hardware result
+fn draw_badge(frame: &mut Frame, text: &str) {
+    let area = frame.area();
+    let width = (text.len() as u16).min(area.width);
+
+    let rect = ratatui::layout::Rect {
+        x: area.width.saturating_sub(width + 1),
+        y: 0,
+        width,
+        height: 1.min(area.height),
+    };
+
+    frame.render_widget(
+        Paragraph::new(text).style(Style::default().white().on_red().bold()),
+        rect,
+    );
+}
+
+/// Zero based index for the number keys 1-9
+pub(crate) fn digit_index(code: KeyCode) -> Option<usize> {
+    const DIGITS: [KeyCode; 9] = [
+        KeyCode::KEY_1,
+        KeyCode::KEY_2,
+        KeyCode::KEY_3,
+        KeyCode::KEY_4,
+        KeyCode::KEY_5,
+        KeyCode::KEY_6,
+        KeyCode::KEY_7,
+        KeyCode::KEY_8,
+        KeyCode::KEY_9,
+    ];
+    DIGITS.iter().position(|digit| *digit == code)
+}
+
+fn is_help_key(code: KeyCode, screen: &dyn Screen, keymap: &KeyMap) -> bool {
+    if let Some(keys) = keymap.keys_for(Action::ToggleHelp) {
+        return keys.contains(&code);
+    }
+    match code {
+        KeyCode::KEY_F1 => true,
+        // We only see raw keycodes, so '?' means the key that carries it on US layouts.
+        // The keyboard test needs that key for testing, so only F1 works there.
+        // A focused text field takes it as a character.
+        KeyCode::KEY_SLASH | KeyCode::KEY_QUESTION => {
+            screen.id() != ScreenId::KeyboardTest && !screen.text_focused()
+        }
+        _ => false,
+    }
+}
+
+/// F9 unless every key is under test, or the configured dump keys
+fn is_dump_key(code: KeyCode, screen: &dyn Screen, keymap: &KeyMap) -> bool {
+    if let Some(keys) = keymap.keys_for(Action::DumpEvents) {
+        return keys.contains(&code);
+    }
+    code == KeyCode::KEY_F9 && !screen.wants_raw_keys()
+}
+
+/// Write the last seconds of events to a file of their own in the log
+/// directory, returning the notice naming it
+fn dump_events(ring: &EventRing, log: Option<&WriterHandle>, dir: Option<&Path>) -> String {
+    let (Some(log), Some(dir)) = (log, dir) else {
+        return "Events not dumped: no log directory (--log-dir)".to_string();
+    };
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let topic = event_ring::dump_topic(millis);
+    let dump = ring.dump();
+    let events = dump.lines().count();
+    log.replace(&topic, dump);
+    format!(
+        "{events} events of the last {} s dumped to {}",
+        event_ring::WINDOW.as_secs(),
+        topic.path(dir).display()
+    )
+}
+
+fn draw_help_overlay(
+    frame: &mut Frame,
+    screen: &dyn Screen,
+    keymap: &KeyMap,
+    log: Option<&WriterHandle>,
+) {
+    let area = frame.area();
+    // Written with the configured keys, entries whose keys were all disabled left out
+    let mut help = screen.help();
+    // The event dump works on every screen that leaves F9 alone
+    if keymap.keys_for(Action::DumpEvents).is_some() || !screen.wants_raw_keys() {
+        help.push(("F9", "Write the last 30 s of events to the log directory"));
+    }
+    let bindings: Vec<(String, &str)> = help
+        .into_iter()
+        .map(|(key, description)| (keymap.help_label(key, screen.wants_raw_keys()), description))
+        .filter(|(key, _)| !key.is_empty())
+        .collect();
+
+    let mut lines: Vec<Line> = Vec::new();
+    if !screen.help_text().is_empty() {
+        lines.push(Line::from(screen.help_text()));
+        lines.push(Line::from(""));
+    }
+
+    let key_width = bindings
+        .iter()
+        .map(|(key, _)| key.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (key, description) in &bindings {
+        lines.push(Line::from(vec![
+            format!("{key:>key_width$}").bold().yellow(),
+            "  ".into(),
+            (*description).into(),
+        ]));
+    }
+
+    lines.push(Line::from(""));
+    // Lets a long kiosk session be checked for memory growth
+    if let Some(kb) = resident_memory_kb() {
+        lines.push(
+            Line::from(format!("Memory in use: {:.1} MB", kb as f32 / 1024.0).dark_gray())
+                .centered(),
+        );
+    }
+    // Writes the background writer gave up on, a slow disk shows here first
+    let dropped = log.map(WriterHandle::dropped).unwrap_or_default();
+    if !dropped.is_empty() {
+        let counts: Vec<String> = dropped
+            .iter()
+            .map(|(topic, count)| format!("{topic} {count}"))
+            .collect();
+        lines.push(
+            Line::from(format!("Dropped writes: {}", counts.join(", ")).dark_gray()).centered(),
+        );
+    }
+    lines.push(Line::from("Press any key to close".gray()).centered());
+
+    let width = 64u16.min(area.width.saturating_sub(4));
+    // Leave room for the description wrapping onto a few extra lines
+    let height = (lines.len() as u16 + 5).min(area.height.saturating_sub(2));
+
+    let rect = ratatui::layout::Rect {
+        x: (area.width.saturating_sub(width)) / 2,
+        y: (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    let help = Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::bordered()
+            .title(Line::from(" Help ".bold().cyan()).centered())
+            .border_set(border::THICK),
+    );
+
+    frame.render_widget(Clear, rect);
+    frame.render_widget(help, rect);
+}
+
+/// Resident memory of the process in kB, from /proc
+fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// Keep the self-check's diagnostic screen up until a key or
+/// `SELF_CHECK_SCREEN_TIMEOUT`, and return the error that ends the session
+fn show_self_check_failures<B: Backend>(
+    terminal: &mut Terminal<B>,
+    rx: &Receiver<AppEvent>,
+    log: Option<&WriterHandle>,
+    failures: &[self_check::Failure],
+) -> color_eyre::Report {
+    let described: Vec<String> = failures.iter().map(self_check::Failure::describe).collect();
+    if let Some(log) = log {
+        log.append(
+            &writer::FAULT_LOG,
+            format!("startup self-check failed: {}\n", described.join("; ")),
+        );
+    }
+    let error = eyre!("startup self-check failed, {}", described.join("; "));
+    if let Err(e) = draw_with_retry(terminal, log, |f| self_check::draw_failures(f, failures)) {
+        return error.wrap_err(e);
+    }
+    // Keys may not arrive with the channel broken, hence the timeout
+    let deadline = Instant::now() + SELF_CHECK_SCREEN_TIMEOUT;
+    while let Ok(event) = rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+        if let AppEvent::Key { .. } | AppEvent::TerminalKey { .. } = event {
+            break;
+        }
+    }
+    error
+}
+
+fn draw_loading(frame: &mut Frame) {
+    let v_chunks = Layout::vertical([
+        Constraint::Min(0),
+        Constraint::Length(3),
+        Constraint::Min(0),
+    ])
+    .split(frame.area());
+
+    let h_chunks = Layout::horizontal([
+        Constraint::Min(0),
+        Constraint::Length(20),
+        Constraint::Min(0),
+    ])
+    .split(v_chunks[1]);
+
+    let area = h_chunks[1];
+
+    frame.render_widget(
+        Paragraph::new("Loading...")
+            .centered()
+            .block(Block::bordered()),
+        area,
+    );
+}
+
+fn create_screen(
+    screen_id: ScreenId,
+    options: &Options,
+    metadata: &SessionMetadata,
+    log: Option<&WriterHandle>,
+) -> Box<dyn Screen> {
+    let min = Duration::from_secs;
+    match screen_id {
+        ScreenId::Home => Box::new(
+            HomeScreen::for_model(get_computer_model())
+                .with_metadata(metadata.clone())
+                .with_theme(options.theme),
+        ),
+        ScreenId::SessionInfo => Box::new(SessionInfoScreen::new(metadata)),
+        ScreenId::KeyboardTest => Box::new(
+            KeyboardTestScreen::for_model(get_computer_model())
+                .with_palette(options.press_palette)
+                .with_theme(options.theme)
+                .with_language(options.language)
+                .with_stale_window(options.stale_window)
+                .with_auto_advance(options.auto_advance)
+                .with_min_duration(min(options.min_durations.keyboard_secs))
+                .with_layouts_dir(options.layouts_dir.clone())
+                .with_exclusions(
+                    metadata
+                        .unit_serial
+                        .as_ref()
+                        .and_then(|serial| options.excluded_keys.get(serial).cloned())
+                        .unwrap_or_default(),
+                ),
+        ),
+        ScreenId::MouseTest => {
+            let mut settings = MouseSettings::for_model(get_computer_model());
+            if let Some(sensitivity) = options.mouse_sensitivity {
+                settings.sensitivity = sensitivity;
+            }
+            Box::new(
+                MouseTestScreen::new(settings)
+                    .with_min_duration(min(options.min_durations.mouse_secs)),
+            )
+        }
+        ScreenId::TrackpadTest => Box::new(
+            TrackpadTestScreen::new().with_min_duration(min(options.min_durations.trackpad_secs)),
+        ),
+        ScreenId::TouchscreenTest => {
+            let screen = TouchscreenTestScreen::new(options.calibration_capture, options.panel_mm)
+                .with_min_duration(min(options.min_durations.touchscreen_secs))
+                .with_log(log.cloned())
+                .with_corners(options.corner_margin_mm, options.edge_midpoints)
+                .with_hover_threshold(options.hover_offset_mm)
+                .with_jump_threshold(options.jump_threshold)
+                .with_roi(options.touch_roi)
+                .with_stabilizer(options.touch_stabilizer, options.stabilizer_alpha)
+                .with_theme(options.theme)
+                .with_calibration_matrix(
+                    options.calibration_matrix,
+                    options.calibration_out.clone(),
+                );
+            match options.soak_interval {
+                Some(interval) => Box::new(screen.with_soak(interval)),
+                None => Box::new(screen),
+            }
+        }
+        // Only shown at startup, when there is a saved session
+        ScreenId::Resume | ScreenId::Rescan | ScreenId::Exit => {
+            logging::warn(&format!("Cannot create {screen_id:?} screen"));
+            Box::new(HomeScreen::for_model(get_computer_model()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use ratatui::backend::{ClearType, TestBackend, WindowSize};
+    use ratatui::buffer::Cell as BufferCell;
+    use ratatui::layout::{Position, Size};
+
+    use super::*;
+    use crate::report::{KeyboardResult, ScreenResult};
+    use crate::snapshot::{
+        LAYOUT_SIZES, assert_screen, assert_screen_ascii, assert_snapshot, assert_within_and_apart,
+        panel, press, release, render_colors, touch,
+    };
+    use crate::writer::Writer;
+
+    /// A `TestBackend` whose next `failures` draws fail like a console in a VT
+    /// switch
+    struct FailingBackend {
+        inner: TestBackend,
+        failures: u32,
+        attempts: u32,
+    }
+
+    impl FailingBackend {
+        fn new(failures: u32) -> Self {
+            FailingBackend {
+                inner: TestBackend::new(80, 24),
+                failures,
+                attempts: 0,
+            }
+        }
+    }
+
+    impl Backend for FailingBackend {
+        fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+        where
+            I: Iterator<Item = (u16, u16, &'a BufferCell)>,
+        {
+            self.attempts += 1;
+            if self.failures > 0 {
+                self.failures -= 1;
+                return Err(io::Error::from_raw_os_error(libc::EIO));
+            }
+            self.inner.draw(content)
+        }
+
+        fn hide_cursor(&mut self) -> io::Result<()> {
+            self.inner.hide_cursor()
+        }
+
+        fn show_cursor(&mut self) -> io::Result<()> {
+            self.inner.show_cursor()
+        }
+
+        fn get_cursor_position(&mut self) -> io::Result<Position> {
+            self.inner.get_cursor_position()
+        }
+
+        fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+            self.inner.set_cursor_position(position)
+        }
+
+        fn clear(&mut self) -> io::Result<()> {
+            self.inner.clear()
+        }
+
+        fn clear_region(&mut self, clear_type: ClearType) -> io::Result<()> {
+            self.inner.clear_region(clear_type)
+        }
+
+        fn size(&self) -> io::Result<Size> {
+            self.inner.size()
+        }
+
+        fn window_size(&mut self) -> io::Result<WindowSize> {
+            self.inner.window_size()
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.inner.flush()
+        }
+    }
+
+    #[test]
+    fn transient_draw_failures_are_retried_and_logged() {
+        let dir = std::env::temp_dir().join(format!("keyboard_test-draw-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let writer = Writer::spawn(&dir, writer::DEFAULT_QUEUE_LIMIT).unwrap();
+        let mut terminal = Terminal::new(FailingBackend::new(DRAW_ATTEMPTS - 1)).unwrap();
+
+        let home = HomeScreen::for_model(ComputerModel::DatorBBFält);
+        draw_with_retry(&mut terminal, Some(&writer.handle()), |f| home.draw(f)).unwrap();
+        assert_eq!(terminal.backend().attempts, DRAW_ATTEMPTS);
+        writer.shutdown();
+
+        let faults = std::fs::read_to_string(writer::FAULT_LOG.path(&dir)).unwrap();
+        assert_eq!(faults.lines().count(), DRAW_ATTEMPTS as usize - 1);
+        assert!(faults.starts_with(&format!("terminal draw failed (attempt 1/{DRAW_ATTEMPTS})")));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn results_survive_a_terminal_that_stays_broken() {
+        let mut terminal = Terminal::new(FailingBackend::new(u32::MAX)).unwrap();
+        let mut keyboard = KeyboardTestScreen::for_model(ComputerModel::DatorBBFält);
+        keyboard.handle_event(press(KeyCode::KEY_ENTER, 0));
+        keyboard.handle_event(press(KeyCode::KEY_A, 100));
+        keyboard.handle_event(release(KeyCode::KEY_A, 180));
+
+        let drawn = draw_with_retry(&mut terminal, None, |f| keyboard.draw(f));
+        assert!(drawn.is_err());
+        assert_eq!(terminal.backend().attempts, DRAW_ATTEMPTS);
+
+        let mut report = SessionReport::new();
+        record_open_results(
+            &mut keyboard,
+            &mut HashMap::new(),
+            &mut report,
+            &mut HashMap::new(),
+            None,
+        );
+        let [ScreenResult::Keyboard(result)] = report.results.as_slice() else {
+            panic!("expected the keyboard result, got {:?}", report.results);
+        };
+        assert_eq!(result.total_presses, 1);
+    }
+
+    #[test]
+    fn a_finished_screen_only_settles_its_held_keys() {
+        let mut keyboard = KeyboardTestScreen::for_model(ComputerModel::DatorBBFält);
+        keyboard.handle_event(press(KeyCode::KEY_ENTER, 0));
+        keyboard.handle_event(press(KeyCode::KEY_A, 100));
+        keyboard.handle_event(release(KeyCode::KEY_A, 180));
+        // Still held when F10 finishes the test
+        keyboard.handle_event(press(KeyCode::KEY_B, 200));
+
+        // What the run loop does on F10: results and summary, then the screen is
+        // kept the way a preserved one is
+        let mut report = SessionReport::new();
+        let mut slots = HashMap::new();
+        let summary = record_results(&mut keyboard, &mut report, &mut slots, None).unwrap();
+        keyboard.on_exit();
+        let mut cached: HashMap<ScreenId, Box<dyn Screen>> = HashMap::from([(
+            ScreenId::KeyboardTest,
+            Box::new(keyboard) as Box<dyn Screen>,
+        )]);
+        let ScreenResult::Keyboard(finished) = report.results[0].clone() else {
+            panic!("expected the keyboard result, got {:?}", report.results);
+        };
+        assert_eq!(finished.total_presses, 2);
+
+        // Late events between the finish and the summary
+        let panel = panel();
+        for event in [
+            press(KeyCode::KEY_C, 300),
+            release(KeyCode::KEY_B, 400),
+            touch(&panel, 2000, 2000, false),
+            touch(&panel, 2000, 2000, true),
+        ] {
+            settle_finalized(&mut cached, &event);
+        }
+
+        let screen = cached.get_mut(&ScreenId::KeyboardTest).unwrap();
+        let again = record_results(screen.as_mut(), &mut report, &mut slots, None).unwrap();
+        assert_eq!(again, summary);
+        let Some(ScreenResult::Keyboard(result)) = report.results.last() else {
+            panic!("expected the keyboard result, got {:?}", report.results);
+        };
+        let presses = |result: &KeyboardResult| -> Vec<usize> {
+            result
+                .rows
+                .iter()
+                .flatten()
+                .map(|key| key.presses)
+                .collect()
+        };
+        assert_eq!(result.total_presses, finished.total_presses);
+        assert_eq!(presses(result), presses(&finished));
+        // The summary shown is the report's own
+        assert_eq!(
+            summary,
+            format!(
+                "{}: {}",
+                report.results[1].title(),
+                report.results[1].summary()
+            )
+        );
+
+        // The release reached it: back on the test, B isn't taken for stuck
+        screen.on_enter();
+        screen.handle_event(press(KeyCode::KEY_A, 10_000));
+        let Some(ScreenResult::Keyboard(result)) = screen.take_results() else {
+            panic!("expected the keyboard result");
+        };
+        assert!(
+            result
+                .simultaneous
+                .iter()
+                .all(|keyboard| keyboard.stuck_keys.is_empty()),
+            "{:?}",
+            result.simultaneous
+        );
+    }
+
+    #[test]
+    fn home_snapshots() {
+        let home = HomeScreen::for_model(ComputerModel::DatorBBFält);
+        assert_screen("home", &home);
+        assert_screen_ascii("home", &home);
+    }
+
+    #[test]
+    fn a_config_reload_recolors_the_live_home_screen() {
+        let mut home =
+            HomeScreen::for_model(ComputerModel::DatorBBFält).with_theme(Theme::Standard);
+        let before = render_colors(80, 24, |frame| home.draw(frame));
+        assert_snapshot("home_theme_standard_80x24", &before);
+
+        let mut options = Options::default();
+        options.theme = Theme::ColorBlind;
+        home.reconfigure(&options);
+        let after = render_colors(80, 24, |frame| home.draw(frame));
+        assert_snapshot("home_theme_color-blind_80x24", &after);
+        assert_ne!(before, after);
+    }
+
+    #[test]
+    fn home_input_tests_with_and_without_touchscreen() {
+        for (name, model) in [
+            ("home_input_tests_touchscreen", ComputerModel::DatorBBFält),
+            (
+                "home_input_tests_no_touchscreen",
+                ComputerModel::DatorBärbarRS11,
+            ),
+        ] {
+            let mut home = HomeScreen::for_model(model);
+            home.handle_event(press(KeyCode::KEY_ENTER, 0));
+            assert_screen(name, &home);
+        }
+    }
+
+    #[test]
+    fn home_keys_walk_the_menu_levels() {
+        let mut home = HomeScreen::for_model(ComputerModel::DatorBärbarRS11);
+        let mut key = |code| home.handle_event(press(code, 0));
+        // Enter opens the highlighted group, a digit counts within it
+        assert!(matches!(key(KeyCode::KEY_ENTER), Nav::Stay));
+        assert!(matches!(
+            key(KeyCode::KEY_3),
+            Nav::To(ScreenId::TrackpadTest)
+        ));
+        assert!(matches!(key(KeyCode::KEY_DOWN), Nav::Stay));
+        assert!(matches!(
+            key(KeyCode::KEY_ENTER),
+            Nav::To(ScreenId::MouseTest)
+        ));
+        // Esc goes up a level before it leaves
+        assert!(matches!(key(KeyCode::KEY_ESC), Nav::Stay));
+        assert!(matches!(key(KeyCode::KEY_2), Nav::Stay));
+        assert!(matches!(key(KeyCode::KEY_BACKSPACE), Nav::Stay));
+        assert!(matches!(key(KeyCode::KEY_BACKSPACE), Nav::Stay));
+        assert!(matches!(key(KeyCode::KEY_9), Nav::Stay));
+        assert!(matches!(key(KeyCode::KEY_4), Nav::To(ScreenId::Exit)));
+        assert!(matches!(key(KeyCode::KEY_ESC), Nav::To(ScreenId::Exit)));
+        assert_eq!(home.menu.breadcrumb(), None);
+        assert_eq!(home.menu.selected(), 1);
+    }
+
+    #[test]
+    fn home_layout_stays_inside_and_apart() {
+        for model in [ComputerModel::DatorBBFält, ComputerModel::DatorBärbarRS11] {
+            let mut home = HomeScreen::for_model(model);
+            home.metadata.operator_id = Some("op-17".into());
+            home.metadata.unit_serial = Some("SN12345".into());
+            for (width, height) in LAYOUT_SIZES {
+                let area = Rect::new(0, 0, width, height);
+                let layout = home.layout(area);
+                let mut rects = layout.items.clone();
+                rects.push(layout.details);
+                assert_within_and_apart("home menu", area, &rects);
+                // Entries run down the screen in menu order
+                let shown: Vec<&Rect> = layout.items.iter().filter(|r| !r.is_empty()).collect();
+                assert!(shown.windows(2).all(|pair| pair[0].y < pair[1].y));
+            }
+        }
+    }
+
+    #[test]
+    fn the_help_key_is_typed_into_a_focused_field() {
+        let keymap = KeyMap::default();
+        let mut screen = SessionInfoScreen::new(&SessionMetadata {
+            operator_id: None,
+            unit_serial: Some("SN1".to_string()),
+        });
+        assert!(!is_help_key(KeyCode::KEY_SLASH, &screen, &keymap));
+        assert!(is_help_key(KeyCode::KEY_F1, &screen, &keymap));
+
+        screen.handle_event(press(KeyCode::KEY_ESC, 0));
+        assert!(is_help_key(KeyCode::KEY_SLASH, &screen, &keymap));
+    }
+}
@@ -0,0 +1,106 @@
+//! Countdown that leaves a test by itself once it has passed. Operators finish
+//! a unit and walk off without pressing the finish key, and the line stalls.
+//! When a test's pass condition turns true the countdown starts and is drawn
+//! over the test; when it runs out the screen leaves the way a manual finish
+//! does, so the results are handed over the same way. Any key stops it to
+//! review the result instead. Turned off with `"auto_advance": false` in the
+//! config file for sites that review every unit by hand.
+
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+};
+
+pub const DEFAULT_COUNTDOWN: Duration = Duration::from_secs(3);
+
+/// Countdown of one test, fed its pass condition on every tick
+pub struct AutoAdvance {
+    enabled: bool,
+    countdown: Duration,
+    started: Option<Instant>,
+    // Stopped by the operator, until the test stops passing
+    cancelled: bool,
+}
+
+impl AutoAdvance {
+    pub fn new(enabled: bool) -> Self {
+        AutoAdvance {
+            enabled,
+            countdown: DEFAULT_COUNTDOWN,
+            started: None,
+            cancelled: false,
+        }
+    }
+
+    /// Turn the countdown on or off, e.g. on a config reload. Turning it off
+    /// stops a running countdown.
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+        if !enabled {
+            self.started = None;
+        }
+    }
+
+    /// Whether the test currently passes. Starts the countdown when it turns
+    /// true, and drops it or a cancel when it turns false again.
+    pub fn update(&mut self, passed: bool) {
+        if !passed {
+            self.started = None;
+            self.cancelled = false;
+        } else if self.enabled && !self.cancelled && self.started.is_none() {
+            self.started = Some(Instant::now());
+        }
+    }
+
+    /// Stop a running countdown. Returns whether one was running, so the key
+    /// that stopped it isn't taken as test input.
+    pub fn cancel(&mut self) -> bool {
+        let running = self.started.take().is_some();
+        self.cancelled |= running;
+        running
+    }
+
+    /// Time left, None while not counting down
+    pub fn remaining(&self) -> Option<Duration> {
+        self.started
+            .map(|started| self.countdown.saturating_sub(started.elapsed()))
+    }
+
+    /// The countdown ran out, time to leave the test
+    pub fn expired(&self) -> bool {
+        self.remaining().is_some_and(|left| left.is_zero())
+    }
+
+    /// The countdown in a box centered over `area`, nothing while not counting down
+    pub fn draw(&self, frame: &mut Frame, area: Rect, passed: &str) {
+        let Some(left) = self.remaining() else {
+            return;
+        };
+        let seconds = left.as_secs() + u64::from(left.subsec_nanos() > 0);
+        let lines = vec![
+            Line::from(passed.to_string().bold()).centered(),
+            Line::from(format!("Returning in {seconds} s").bold()).centered(),
+            Line::from("Any key to stay and review").centered(),
+        ];
+        let width = (lines.iter().map(Line::width).max().unwrap_or(0) as u16 + 4).min(area.width);
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let rect = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, rect);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().border_style(Style::default().green()))
+                .style(Style::default().bg(Color::Black).fg(Color::Green)),
+            rect,
+        );
+    }
+}
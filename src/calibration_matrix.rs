@@ -0,0 +1,377 @@
+//! libinput calibration matrix. The OS image calibrates touchscreens with a
+//! `LIBINPUT_CALIBRATION_MATRIX` udev property: the top two rows of a 3x3
+//! matrix taking the raw position, normalized to 0-1 over the device's axis
+//! range, to the screen position, also 0-1. The touchscreen test's mapping is
+//! axis aligned (corner window, inverted axes, swapped axes), so it converts to
+//! a matrix without rotation or shear and back.
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use color_eyre::{Result, eyre::eyre};
+
+use crate::saturation::AxisLimits;
+use crate::touch_roi::Roi;
+
+// Coefficients this close to zero count as zero when reading a matrix back
+const EPSILON: f32 = 1e-4;
+
+/// One calibrated axis: the raw window spanning the screen, in the panel's
+/// orientation, and whether raw values run against the screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AxisSpan {
+    pub min: f32,
+    pub max: f32,
+    pub invert: bool,
+}
+
+/// The touchscreen test's calibration as plain numbers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearMapping {
+    /// Screen X comes from raw Y and screen Y from raw X
+    pub swap_axes: bool,
+    pub x: AxisSpan,
+    pub y: AxisSpan,
+}
+
+/// a, b, c, d, e, f of the libinput matrix, screen x = a·x + b·y + c and
+/// screen y = d·x + e·y + f on normalized coordinates
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CalibrationMatrix(pub [f32; 6]);
+
+impl CalibrationMatrix {
+    /// Six numbers separated by commas or spaces, as given on the command line
+    /// or copied from a udev rule
+    pub fn parse(text: &str) -> Result<Self> {
+        let values = text
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|value| !value.is_empty())
+            .map(|value| {
+                value
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .ok_or_else(|| eyre!("invalid matrix value '{value}'"))
+            })
+            .collect::<Result<Vec<f32>>>()?;
+        let values: [f32; 6] = values
+            .try_into()
+            .map_err(|values: Vec<f32>| eyre!("a matrix has 6 values, got {}", values.len()))?;
+        Ok(CalibrationMatrix(values))
+    }
+
+    /// The matrix doing what `mapping` does, for a device with raw `limits`
+    pub fn from_mapping(mapping: &LinearMapping, limits: AxisLimits) -> Self {
+        let range = |(min, max): (u16, u16)| (min as f32, max as f32);
+        let (raw_x, raw_y) = (range(limits.x), range(limits.y));
+        // Screen X is fed by raw Y when the axes are swapped, and the other way round
+        let (source_x, source_y) = if mapping.swap_axes {
+            (raw_y, raw_x)
+        } else {
+            (raw_x, raw_y)
+        };
+        let (scale_x, offset_x) = axis_coefficients(mapping.x, source_x);
+        let (scale_y, offset_y) = axis_coefficients(mapping.y, source_y);
+        if mapping.swap_axes {
+            CalibrationMatrix([0.0, scale_x, offset_x, scale_y, 0.0, offset_y])
+        } else {
+            CalibrationMatrix([scale_x, 0.0, offset_x, 0.0, scale_y, offset_y])
+        }
+    }
+
+    /// The mapping doing what the matrix does on a device with raw `limits`.
+    /// Fails for a matrix that rotates or shears, which the test can't follow.
+    pub fn to_mapping(&self, limits: AxisLimits) -> Result<LinearMapping> {
+        let [a, b, c, d, e, f] = self.0;
+        let zero = |v: f32| v.abs() < EPSILON;
+        let swap_axes = if zero(b) && zero(d) && !zero(a) && !zero(e) {
+            false
+        } else if zero(a) && zero(e) && !zero(b) && !zero(d) {
+            true
+        } else {
+            return Err(eyre!(
+                "matrix {self} rotates or shears, only axis aligned calibrations are supported"
+            ));
+        };
+
+        let range = |(min, max): (u16, u16)| (min as f32, max as f32);
+        let (raw_x, raw_y) = (range(limits.x), range(limits.y));
+        Ok(if swap_axes {
+            LinearMapping {
+                swap_axes,
+                x: axis_span(b, c, raw_y),
+                y: axis_span(d, f, raw_x),
+            }
+        } else {
+            LinearMapping {
+                swap_axes,
+                x: axis_span(a, c, raw_x),
+                y: axis_span(e, f, raw_y),
+            }
+        })
+    }
+
+    /// Write the matrix as a shell assignment the imaging scripts source. A
+    /// matrix over a `learned_range` is normalized to that range rather than
+    /// one the device reports, so the range is written with it as
+    /// `TOUCH_RAW_RANGE` (X min, X max, Y min, Y max). A region of interest
+    /// other than the whole panel goes with it as `TOUCH_ROI` (left, top,
+    /// right, bottom, 0-1 of the screen). The jump threshold the operator
+    /// settled on is `TOUCH_JUMP_THRESHOLD`, in calibrated units like
+    /// `thresholds.jump` in the config.
+    pub fn write(
+        &self,
+        path: &Path,
+        learned_range: Option<AxisLimits>,
+        roi: Roi,
+        jump_threshold: f32,
+    ) -> Result<()> {
+        let mut text = format!("LIBINPUT_CALIBRATION_MATRIX=\"{self}\"\n");
+        if let Some(AxisLimits { x, y }) = learned_range {
+            text.push_str(&format!(
+                "TOUCH_RAW_RANGE=\"{} {} {} {}\"\n",
+                x.0, x.1, y.0, y.1
+            ));
+        }
+        if !roi.is_full() {
+            text.push_str(&format!("TOUCH_ROI=\"{roi}\"\n"));
+        }
+        text.push_str(&format!("TOUCH_JUMP_THRESHOLD=\"{jump_threshold}\"\n"));
+        fs::write(path, text).map_err(|e| eyre!("cannot write {}: {e}", path.display()))
+    }
+}
+
+/// The six values separated by spaces, as udev takes them
+impl fmt::Display for CalibrationMatrix {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let values: Vec<String> = self
+            .0
+            .iter()
+            // Four decimals are well under a raw unit, and -0 reads as a typo
+            .map(|v| ((v * 10_000.0).round() / 10_000.0 + 0.0).to_string())
+            .collect();
+        write!(f, "{}", values.join(" "))
+    }
+}
+
+/// Scale and offset taking a normalized raw value over `source` to the screen
+fn axis_coefficients(span: AxisSpan, (source_min, source_max): (f32, f32)) -> (f32, f32) {
+    let width = (span.max - span.min).max(1.0);
+    let scale = (source_max - source_min) / width;
+    let offset = (source_min - span.min) / width;
+    if span.invert {
+        (-scale, 1.0 - offset)
+    } else {
+        (scale, offset)
+    }
+}
+
+/// Inverse of `axis_coefficients`
+fn axis_span(scale: f32, offset: f32, (source_min, source_max): (f32, f32)) -> AxisSpan {
+    let width = (source_max - source_min) / scale.abs();
+    let invert = scale < 0.0;
+    let min = if invert {
+        source_min - (1.0 - offset) * width
+    } else {
+        source_min - offset * width
+    };
+    AxisSpan {
+        min,
+        max: min + width,
+        invert,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A panel whose axes don't start at 0 and differ in range
+    const LIMITS: AxisLimits = AxisLimits {
+        x: (200, 3900),
+        y: (50, 4000),
+    };
+
+    /// Every combination of swapped and inverted axes, with corner windows
+    /// inside the raw range as a real calibration gives
+    fn mappings() -> Vec<LinearMapping> {
+        let mut mappings = Vec::new();
+        for swap_axes in [false, true] {
+            for invert_x in [false, true] {
+                for invert_y in [false, true] {
+                    mappings.push(LinearMapping {
+                        swap_axes,
+                        x: AxisSpan {
+                            min: 310.0,
+                            max: 3720.0,
+                            invert: invert_x,
+                        },
+                        y: AxisSpan {
+                            min: 180.0,
+                            max: 3850.0,
+                            invert: invert_y,
+                        },
+                    });
+                }
+            }
+        }
+        mappings
+    }
+
+    /// Where `matrix` puts the raw position on a 0-1 screen
+    fn apply(matrix: &CalibrationMatrix, (x, y): (f32, f32), limits: AxisLimits) -> (f32, f32) {
+        let normalize = |v: f32, (min, max): (u16, u16)| (v - min as f32) / (max - min) as f32;
+        let (x, y) = (normalize(x, limits.x), normalize(y, limits.y));
+        let [a, b, c, d, e, f] = matrix.0;
+        (a * x + b * y + c, d * x + e * y + f)
+    }
+
+    /// Where `mapping` puts the raw position on a 0-1 screen
+    fn place(mapping: &LinearMapping, (x, y): (f32, f32)) -> (f32, f32) {
+        let (source_x, source_y) = if mapping.swap_axes { (y, x) } else { (x, y) };
+        let along = |v: f32, span: AxisSpan| {
+            let t = (v - span.min) / (span.max - span.min);
+            if span.invert { 1.0 - t } else { t }
+        };
+        (along(source_x, mapping.x), along(source_y, mapping.y))
+    }
+
+    fn assert_close(actual: f32, expected: f32, what: &str) {
+        assert!(
+            (actual - expected).abs() < 1e-3,
+            "{what}: {actual} != {expected}"
+        );
+    }
+
+    #[test]
+    fn the_matrix_places_touches_where_the_mapping_does() {
+        for mapping in mappings() {
+            let matrix = CalibrationMatrix::from_mapping(&mapping, LIMITS);
+            for raw in [
+                (200.0, 50.0),
+                (3900.0, 4000.0),
+                (310.0, 3850.0),
+                (1234.0, 2345.0),
+            ] {
+                let (x, y) = apply(&matrix, raw, LIMITS);
+                let (expected_x, expected_y) = place(&mapping, raw);
+                let what = format!("{mapping:?} at {raw:?}");
+                assert_close(x, expected_x, &what);
+                assert_close(y, expected_y, &what);
+            }
+        }
+    }
+
+    #[test]
+    fn mappings_survive_the_round_trip() {
+        for mapping in mappings() {
+            let matrix = CalibrationMatrix::from_mapping(&mapping, LIMITS);
+            // Through the text too, as the udev rule and --calibration-matrix carry it
+            let matrix = CalibrationMatrix::parse(&matrix.to_string()).unwrap();
+            let back = matrix.to_mapping(LIMITS).unwrap();
+            assert_eq!(back.swap_axes, mapping.swap_axes);
+            for (back, span) in [(back.x, mapping.x), (back.y, mapping.y)] {
+                assert_eq!(back.invert, span.invert, "{mapping:?}");
+                // Four decimals of a 0-1 value are under a raw unit
+                assert!((back.min - span.min).abs() < 1.0, "{mapping:?}: {back:?}");
+                assert!((back.max - span.max).abs() < 1.0, "{mapping:?}: {back:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn the_usual_libinput_matrices_read_as_rotations() {
+        let full = AxisLimits {
+            x: (0, 4095),
+            y: (0, 4095),
+        };
+        // Identity, then 90°, 180° and 270° clockwise as the libinput docs give them
+        for (text, swap_axes, invert_x, invert_y) in [
+            ("1 0 0 0 1 0", false, false, false),
+            ("0 -1 1 1 0 0", true, true, false),
+            ("-1 0 1 0 -1 1", false, true, true),
+            ("0 1 0 -1 0 1", true, false, true),
+        ] {
+            let mapping = CalibrationMatrix::parse(text)
+                .unwrap()
+                .to_mapping(full)
+                .unwrap();
+            assert_eq!(
+                (mapping.swap_axes, mapping.x.invert, mapping.y.invert),
+                (swap_axes, invert_x, invert_y),
+                "{text}"
+            );
+            for span in [mapping.x, mapping.y] {
+                assert_close(span.min, 0.0, text);
+                assert_close(span.max, 4095.0, text);
+            }
+        }
+    }
+
+    #[test]
+    fn rotated_or_sheared_matrices_are_refused() {
+        for text in ["0.7 -0.7 0.5 0.7 0.7 0", "1 0.2 0 0 1 0", "0 0 0 0 0 0"] {
+            let matrix = CalibrationMatrix::parse(text).unwrap();
+            assert!(matrix.to_mapping(LIMITS).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn parsing_takes_commas_or_spaces_and_six_finite_values() {
+        let expected = CalibrationMatrix([1.0, 0.0, -0.5, 0.0, 1.25, 0.0]);
+        assert_eq!(
+            CalibrationMatrix::parse("1,0,-0.5,0,1.25,0").unwrap(),
+            expected
+        );
+        assert_eq!(
+            CalibrationMatrix::parse(" 1 0 -0.5  0 1.25 0\n").unwrap(),
+            expected
+        );
+        assert_eq!(
+            CalibrationMatrix::parse("1, 0, -0.5, 0, 1.25, 0").unwrap(),
+            expected
+        );
+
+        for text in [
+            "1 0 0 0 1",
+            "1 0 0 0 1 0 0",
+            "1 0 0 0 1 x",
+            "1 0 NaN 0 1 0",
+            "inf 0 0 0 1 0",
+        ] {
+            assert!(CalibrationMatrix::parse(text).is_err(), "{text}");
+        }
+    }
+
+    #[test]
+    fn display_rounds_and_drops_negative_zero() {
+        let matrix = CalibrationMatrix([1.000_04, -0.0, 0.123_456, -0.000_01, -1.0, 1.0]);
+        assert_eq!(matrix.to_string(), "1 0 0.1235 0 -1 1");
+    }
+
+    #[test]
+    fn write_adds_the_learned_range_and_roi_when_there_are_any_and_the_jump_threshold() {
+        let dir = std::env::temp_dir().join(format!("keyboard_test-matrix-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("calibration.env");
+        let matrix = CalibrationMatrix([1.0, 0.0, 0.0, 0.0, 1.0, 0.0]);
+
+        matrix.write(&path, None, Roi::FULL, 50.0).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "LIBINPUT_CALIBRATION_MATRIX=\"1 0 0 0 1 0\"\nTOUCH_JUMP_THRESHOLD=\"50\"\n"
+        );
+
+        let roi = Roi::new([0.0, 0.1, 1.0, 0.9]).unwrap();
+        matrix.write(&path, Some(LIMITS), roi, 65.0).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "LIBINPUT_CALIBRATION_MATRIX=\"1 0 0 0 1 0\"\n\
+             TOUCH_RAW_RANGE=\"200 3900 50 4000\"\n\
+             TOUCH_ROI=\"0.000 0.100 1.000 0.900\"\n\
+             TOUCH_JUMP_THRESHOLD=\"65\"\n"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,162 @@
+//! `--print-capabilities`: what this binary supports, as JSON for the imaging
+//! pipeline that generates per-model config files. Everything is read from the
+//! tables the tests themselves use, so the output can't drift from them.
+
+use serde::Serialize;
+
+use crate::keyboard_layouts::{self, LAYOUT_OPTIONS};
+use crate::machine_detect::ComputerModel;
+use crate::report::SCHEMA_VERSION;
+use crate::serial_touch::{PROTOCOLS, SERIAL_PORT};
+
+#[derive(Debug, Serialize)]
+pub struct Capabilities {
+    pub layouts: Vec<LayoutCapability>,
+    pub models: Vec<ModelCapability>,
+    pub serial_protocols: Vec<String>,
+    pub report_schema_version: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LayoutCapability {
+    pub name: String,
+    pub keys: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ModelCapability {
+    /// The `ComputerModel` variant
+    pub name: String,
+    pub touchscreen: bool,
+    /// Port of the serial touchscreen, None for models without one
+    pub serial_touch_port: Option<String>,
+    pub trackpad: bool,
+    pub mouse: bool,
+    pub swap_touch_axes: bool,
+    /// Layout the keyboard test preselects, one of `layouts`
+    pub suggested_layout: String,
+}
+
+impl Capabilities {
+    pub fn collect() -> Self {
+        Capabilities {
+            layouts: LAYOUT_OPTIONS
+                .iter()
+                .map(|(name, layout, _)| LayoutCapability {
+                    name: name.to_string(),
+                    keys: keyboard_layouts::key_count(layout),
+                })
+                .collect(),
+            models: ComputerModel::ALL
+                .iter()
+                .map(|&model| ModelCapability {
+                    name: format!("{model:?}"),
+                    touchscreen: model.has_touchscreen(),
+                    serial_touch_port: model
+                        .has_serial_touchscreen()
+                        .then(|| SERIAL_PORT.to_string()),
+                    trackpad: model.has_trackpad(),
+                    mouse: model.has_mouse(),
+                    swap_touch_axes: model.swaps_touch_axes(),
+                    suggested_layout: LAYOUT_OPTIONS[keyboard_layouts::suggested_layout(model)]
+                        .0
+                        .to_string(),
+                })
+                .collect(),
+            serial_protocols: PROTOCOLS.iter().map(|name| name.to_string()).collect(),
+            report_schema_version: SCHEMA_VERSION,
+        }
+    }
+}
+
+/// Print the capabilities as JSON. Returns the exit code.
+pub fn print_capabilities() -> i32 {
+    match serde_json::to_string_pretty(&Capabilities::collect()) {
+        Ok(json) => {
+            println!("{json}");
+            0
+        }
+        Err(e) => {
+            println!("Cannot serialize the capabilities: {e}");
+            1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use super::*;
+
+    /// The JSON `--print-capabilities` prints, parsed back
+    fn printed() -> Value {
+        let json = serde_json::to_string_pretty(&Capabilities::collect()).unwrap();
+        serde_json::from_str(&json).unwrap()
+    }
+
+    /// Where `model` is in `ComputerModel::ALL`. A model added without being
+    /// listed there stops this from compiling.
+    fn listed_at(model: ComputerModel) -> usize {
+        match model {
+            ComputerModel::DatorBBFält => 0,
+            ComputerModel::DatorBBFältGPS => 1,
+            ComputerModel::DatorBärbarRS11 => 2,
+            ComputerModel::DatorBärbarCMBRF8 => 3,
+            ComputerModel::DatorBBFältG2 => 4,
+            ComputerModel::EjKänd => 5,
+        }
+    }
+
+    #[test]
+    fn layouts_match_the_layout_table() {
+        let printed = printed();
+        let layouts = printed["layouts"].as_array().unwrap();
+        assert_eq!(layouts.len(), LAYOUT_OPTIONS.len());
+        for (printed, (name, layout, _)) in layouts.iter().zip(LAYOUT_OPTIONS) {
+            assert_eq!(printed["name"], *name);
+            // Every cell of the layout, the lower halves of two-row keys left out
+            let cells: Vec<_> = layout
+                .iter()
+                .flat_map(|section| section.iter())
+                .flat_map(|block| block.iter())
+                .flat_map(|row| row.iter())
+                .collect();
+            let lower = cells.iter().filter(|key| key.is_continuation()).count();
+            assert_eq!(printed["keys"], cells.len() - lower, "{name}");
+        }
+    }
+
+    #[test]
+    fn models_match_the_model_profiles() {
+        let printed = printed();
+        let models = printed["models"].as_array().unwrap();
+        assert_eq!(models.len(), ComputerModel::ALL.len());
+        let layout_names: Vec<&str> = LAYOUT_OPTIONS.iter().map(|(name, ..)| *name).collect();
+        for (index, (printed, model)) in models.iter().zip(ComputerModel::ALL).enumerate() {
+            assert_eq!(listed_at(model), index);
+            assert_eq!(printed["name"], format!("{model:?}"));
+            assert_eq!(printed["touchscreen"], model.has_touchscreen());
+            assert_eq!(printed["trackpad"], model.has_trackpad());
+            assert_eq!(printed["mouse"], model.has_mouse());
+            assert_eq!(printed["swap_touch_axes"], model.swaps_touch_axes());
+            let port = model.has_serial_touchscreen().then_some(SERIAL_PORT);
+            assert_eq!(printed["serial_touch_port"].as_str(), port, "{model:?}");
+            let suggested = printed["suggested_layout"].as_str().unwrap();
+            assert!(layout_names.contains(&suggested), "{model:?}: {suggested}");
+        }
+    }
+
+    #[test]
+    fn protocols_and_schema_version_match() {
+        let printed = printed();
+        let protocols: Vec<&str> = printed["serial_protocols"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|name| name.as_str().unwrap())
+            .collect();
+        assert_eq!(protocols, PROTOCOLS);
+        assert_eq!(printed["report_schema_version"], SCHEMA_VERSION);
+    }
+}
@@ -0,0 +1,670 @@
+//! Raw captures of one evdev device for the digitizer vendor. Everything else
+//! in the tool works on `AppEvent`s, which already went through our touch
+//! assembly, deduplication and flood limit; a capture keeps every kernel event
+//! of the device verbatim instead, read without the resync evdev does after a
+//! SYN_DROPPED, so the vendor sees exactly what the driver sent.
+//!
+//! `--capture <device> <path>` records until Ctrl-C or the device goes away,
+//! `--dump-capture <path>` prints a capture as text and `--replay-capture
+//! <path>` runs the screens on one, through the same listener state machine
+//! as a live device (see `event_handler::spawn_capture_replay`).
+//!
+//! File format, integers little-endian:
+//!
+//! ```text
+//! "KTCAPTUR"  magic, 8 bytes
+//! u16         format version, 1
+//! u32         header length, then the header as JSON (`CaptureHeader`)
+//! records     u16 record length, then the record: u64 unix time in µs,
+//!             u16 type, u16 code, i32 value
+//! ```
+//!
+//! Readers skip record bytes past the fields they know, so fields can be added
+//! without breaking older dumps. A record cut short at the end of the file (a
+//! power cut mid-write) is dropped and the capture marked truncated.
+
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use color_eyre::{Result, eyre::eyre};
+use evdev::raw_stream::RawDevice;
+use evdev::{
+    AbsoluteAxisCode, BusType, EventType, InputEvent, KeyCode, MiscCode, RelativeAxisCode,
+    SynchronizationCode,
+};
+use serde::{Deserialize, Serialize};
+use signal_hook::consts::{SIGINT, SIGTERM};
+
+use crate::device_class::DeviceClass;
+use crate::event_handler::{self, DeviceInfo, DeviceSource};
+use crate::machine_detect::get_computer_model;
+use crate::probe::{EXIT_FOUND, EXIT_NOTHING_FOUND};
+use crate::writer::{self, FsyncPolicy, Priority, Topic, Writer};
+
+const MAGIC: &[u8; 8] = b"KTCAPTUR";
+const VERSION: u16 = 1;
+
+/// Bytes of the record fields this version writes
+const RECORD_LEN: usize = 16;
+
+/// Identity and axis ranges of the captured device, and the machine's quirks
+/// the listener needs to interpret it the same way
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CaptureHeader {
+    pub name: String,
+    pub path: String,
+    pub phys: Option<String>,
+    pub vendor: u16,
+    pub product: u16,
+    pub bus: u16,
+    /// touchscreen, trackpad or other, see `DeviceClass`
+    pub class: String,
+    /// The machine model the capture was taken on
+    pub model: String,
+    /// The device reports BTN_TOUCH
+    pub reports_touch: bool,
+    /// The model swaps the touch axes, see `machine_detect`, replayed as the
+    /// device's `swapped_axes` hint
+    pub swap_axes: bool,
+    pub axes: Vec<CaptureAxis>,
+    /// Unix time in µs the capture started
+    pub started_us: u64,
+}
+
+/// One absolute axis as the driver advertised it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaptureAxis {
+    pub code: u16,
+    pub minimum: i32,
+    pub maximum: i32,
+    pub fuzz: i32,
+    pub flat: i32,
+    pub resolution: i32,
+}
+
+/// A kernel event as it was read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CapturedEvent {
+    /// Unix time in µs the kernel stamped the event with
+    pub time_us: u64,
+    pub type_: u16,
+    pub code: u16,
+    pub value: i32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Capture {
+    pub header: CaptureHeader,
+    pub events: Vec<CapturedEvent>,
+    /// The last record was cut short and left out
+    pub truncated: bool,
+}
+
+impl CaptureHeader {
+    fn of(device: &RawDevice, info: &DeviceInfo) -> Self {
+        let axes = device
+            .get_absinfo()
+            .map(|axes| {
+                axes.map(|(code, axis)| CaptureAxis {
+                    code: code.0,
+                    minimum: axis.minimum(),
+                    maximum: axis.maximum(),
+                    fuzz: axis.fuzz(),
+                    flat: axis.flat(),
+                    resolution: axis.resolution(),
+                })
+                .collect()
+            })
+            .unwrap_or_default();
+        let model = get_computer_model();
+        CaptureHeader {
+            name: info.name.clone(),
+            path: info.path.clone(),
+            phys: info.phys.clone(),
+            vendor: info.vendor,
+            product: info.product,
+            bus: info.bus.0,
+            class: class_name(info.class).to_string(),
+            model: format!("{model:?}"),
+            reports_touch: device
+                .supported_keys()
+                .is_some_and(|keys| keys.contains(KeyCode::BTN_TOUCH)),
+            swap_axes: model.swaps_touch_axes(),
+            axes,
+            started_us: unix_micros(SystemTime::now()),
+        }
+    }
+
+    /// The device as the listeners would have found it
+    pub fn device_info(&self) -> DeviceInfo {
+        let axis = |code: AbsoluteAxisCode| self.axes.iter().find(|axis| axis.code == code.0);
+        let resolution = match (axis(AbsoluteAxisCode::ABS_X), axis(AbsoluteAxisCode::ABS_Y)) {
+            (Some(x), Some(y)) if x.resolution > 0 && y.resolution > 0 => {
+                Some((x.resolution, y.resolution))
+            }
+            _ => None,
+        };
+        DeviceInfo {
+            path: self.path.clone(),
+            name: self.name.clone(),
+            abs_x_max: axis(AbsoluteAxisCode::ABS_X).map(|axis| axis.maximum),
+            abs_y_max: axis(AbsoluteAxisCode::ABS_Y).map(|axis| axis.maximum),
+            multitouch: axis(AbsoluteAxisCode::ABS_MT_POSITION_X).is_some(),
+            phys: self.phys.clone(),
+            vendor: self.vendor,
+            product: self.product,
+            bus: BusType(self.bus),
+            pressure_max: axis(AbsoluteAxisCode::ABS_PRESSURE).map(|axis| axis.maximum),
+            resolution,
+            class: match self.class.as_str() {
+                "touchscreen" => DeviceClass::Touchscreen,
+                "trackpad" => DeviceClass::Trackpad,
+                _ => DeviceClass::Other,
+            },
+            learned_range: None,
+            swapped_axes: self.swap_axes,
+            ids: Arc::default(),
+        }
+    }
+
+    /// Magic, version and the length-prefixed header, what a capture file starts with
+    pub fn encode(&self) -> Vec<u8> {
+        let json = serde_json::to_vec(self).expect("the header always serializes");
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 6 + json.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&json);
+        bytes
+    }
+}
+
+impl CapturedEvent {
+    pub fn from_input(event: &InputEvent) -> Self {
+        CapturedEvent {
+            time_us: unix_micros(event.timestamp()),
+            type_: event.event_type().0,
+            code: event.code(),
+            value: event.value(),
+        }
+    }
+
+    /// The kernel event again, stamped `shift_us` later than it was
+    pub fn to_input(self, shift_us: i64) -> InputEvent {
+        let time_us = self.time_us.saturating_add_signed(shift_us);
+        InputEvent::from(libc::input_event {
+            time: libc::timeval {
+                tv_sec: (time_us / 1_000_000) as libc::time_t,
+                tv_usec: (time_us % 1_000_000) as libc::suseconds_t,
+            },
+            type_: self.type_,
+            code: self.code,
+            value: self.value,
+        })
+    }
+
+    /// The length-prefixed record
+    pub fn encode(&self) -> [u8; 2 + RECORD_LEN] {
+        let mut bytes = [0; 2 + RECORD_LEN];
+        bytes[..2].copy_from_slice(&(RECORD_LEN as u16).to_le_bytes());
+        bytes[2..10].copy_from_slice(&self.time_us.to_le_bytes());
+        bytes[10..12].copy_from_slice(&self.type_.to_le_bytes());
+        bytes[12..14].copy_from_slice(&self.code.to_le_bytes());
+        bytes[14..18].copy_from_slice(&self.value.to_le_bytes());
+        bytes
+    }
+}
+
+impl Capture {
+    pub fn read(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)
+            .map_err(|e| eyre!("cannot read capture {}: {e}", path.display()))?;
+        Capture::parse(&bytes).map_err(|e| eyre!("{}: {e}", path.display()))
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let rest = bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| eyre!("not a capture file"))?;
+        let (version, rest) = take_u16(rest).ok_or_else(|| eyre!("capture header cut short"))?;
+        if version != VERSION {
+            return Err(eyre!("unsupported capture version {version}"));
+        }
+        let (header_len, rest) = rest
+            .split_first_chunk::<4>()
+            .ok_or_else(|| eyre!("capture header cut short"))?;
+        let header_len = u32::from_le_bytes(*header_len) as usize;
+        if rest.len() < header_len {
+            return Err(eyre!("capture header cut short"));
+        }
+        let (json, mut rest) = rest.split_at(header_len);
+        let header: CaptureHeader =
+            serde_json::from_slice(json).map_err(|e| eyre!("invalid capture header: {e}"))?;
+
+        let mut events = Vec::new();
+        let mut truncated = false;
+        while !rest.is_empty() {
+            let Some((len, after)) = take_u16(rest) else {
+                truncated = true;
+                break;
+            };
+            let len = len as usize;
+            if after.len() < len {
+                truncated = true;
+                break;
+            }
+            let (record, after) = after.split_at(len);
+            if len < RECORD_LEN {
+                return Err(eyre!(
+                    "capture record of {len} bytes, expected {RECORD_LEN}"
+                ));
+            }
+            events.push(CapturedEvent {
+                time_us: u64::from_le_bytes(record[0..8].try_into().unwrap()),
+                type_: u16::from_le_bytes(record[8..10].try_into().unwrap()),
+                code: u16::from_le_bytes(record[10..12].try_into().unwrap()),
+                value: i32::from_le_bytes(record[12..16].try_into().unwrap()),
+            });
+            rest = after;
+        }
+
+        Ok(Capture {
+            header,
+            events,
+            truncated,
+        })
+    }
+}
+
+/// Record every event of the device named `device` (its node, e.g.
+/// /dev/input/event5, or part of its name) to `path` until Ctrl-C or until the
+/// device goes away. Returns the exit code.
+pub fn record(source: &DeviceSource, device: &str, path: &Path) -> i32 {
+    let info = match find_device(source, device) {
+        Ok(info) => info,
+        Err(e) => {
+            println!("{e}");
+            return EXIT_NOTHING_FOUND;
+        }
+    };
+    let mut raw = match RawDevice::open(&info.path) {
+        Ok(raw) => raw,
+        Err(e) => {
+            println!("Cannot open {}: {e}", info.path);
+            return EXIT_NOTHING_FOUND;
+        }
+    };
+
+    let dir = path
+        .parent()
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let writer = match Writer::spawn(dir, writer::DEFAULT_QUEUE_LIMIT) {
+        Ok(writer) => writer,
+        Err(e) => {
+            println!("{e}");
+            return EXIT_NOTHING_FOUND;
+        }
+    };
+    let topic = capture_topic(path);
+    let log = writer.handle();
+    log.replace(&topic, CaptureHeader::of(&raw, &info).encode());
+
+    // Ctrl-C ends the capture instead of the process, so the queue gets written
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in [SIGINT, SIGTERM] {
+        if let Err(e) = signal_hook::flag::register(signal, stop.clone()) {
+            println!("Cannot catch signal {signal}: {e}");
+        }
+    }
+
+    println!("Capturing {} ({}), Ctrl-C to stop...", info.path, info.name);
+    let count = Arc::new(AtomicU64::new(0));
+    let reader = {
+        let count = count.clone();
+        let log = log.clone();
+        thread::spawn(move || {
+            loop {
+                let events = match raw.fetch_events() {
+                    Ok(events) => events,
+                    Err(e) => {
+                        println!("Device went away: {e}");
+                        break;
+                    }
+                };
+                let mut bytes = Vec::new();
+                let mut batch = 0;
+                for event in events {
+                    bytes.extend_from_slice(&CapturedEvent::from_input(&event).encode());
+                    batch += 1;
+                }
+                log.append(&topic, bytes);
+                count.fetch_add(batch, Ordering::Relaxed);
+            }
+        })
+    };
+    // The reader blocks in the kernel, so it's left behind when stopped
+    while !stop.load(Ordering::Relaxed) && !reader.is_finished() {
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    writer.shutdown();
+    println!(
+        "{} events written to {}",
+        count.load(Ordering::Relaxed),
+        topic.path(dir).display()
+    );
+    for (_, dropped) in log.dropped() {
+        println!("{dropped} batches could not be written, the capture has gaps");
+    }
+    EXIT_FOUND
+}
+
+/// Print the capture at `path` as text, one event per line. Returns the exit code.
+pub fn dump(path: &Path) -> i32 {
+    let capture = match Capture::read(path) {
+        Ok(capture) => capture,
+        Err(e) => {
+            println!("{e}");
+            return EXIT_NOTHING_FOUND;
+        }
+    };
+
+    let header = &capture.header;
+    println!(
+        "{} at {} ({:04x}:{:04x}, bus {:?}), {} on {}",
+        header.name,
+        header.path,
+        header.vendor,
+        header.product,
+        BusType(header.bus),
+        header.class,
+        header.model
+    );
+    for axis in &header.axes {
+        println!(
+            "  {:<22} {}..{} fuzz {} flat {} resolution {}",
+            format!("{:?}", AbsoluteAxisCode(axis.code)),
+            axis.minimum,
+            axis.maximum,
+            axis.fuzz,
+            axis.flat,
+            axis.resolution
+        );
+    }
+    println!("{} events", capture.events.len());
+
+    for event in &capture.events {
+        let offset = event.time_us as i64 - header.started_us as i64;
+        println!(
+            "{:>12.6}  {:<3}  {:<22} {}",
+            offset as f64 / 1e6,
+            type_name(event.type_),
+            code_name(event.type_, event.code),
+            event.value
+        );
+    }
+    if capture.truncated {
+        println!("(last record cut short)");
+    }
+    EXIT_FOUND
+}
+
+/// The device whose node is `device`, or the only one whose name contains it
+fn find_device(source: &DeviceSource, device: &str) -> Result<DeviceInfo> {
+    let (devices, _) = event_handler::scan_devices(source)
+        .map_err(|e| eyre!("cannot read {}: {e}", source.dir.display()))?;
+    let infos: Vec<DeviceInfo> = devices.into_iter().map(|(_, info)| info).collect();
+    if let Some(info) = infos.iter().find(|info| info.path == device) {
+        return Ok(info.clone());
+    }
+    let needle = device.to_lowercase();
+    let matching: Vec<&DeviceInfo> = infos
+        .iter()
+        .filter(|info| info.name.to_lowercase().contains(&needle))
+        .collect();
+    match matching.as_slice() {
+        [info] => Ok((*info).clone()),
+        [] => Err(eyre!("no input device matches '{device}'")),
+        several => Err(eyre!(
+            "'{device}' matches several devices: {}",
+            several
+                .iter()
+                .map(|info| format!("{} ({})", info.path, info.name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+// A writer topic for `path`. Topic names are static; leaking one name per
+// capture run is fine.
+fn capture_topic(path: &Path) -> Topic {
+    let part = |part: Option<&std::ffi::OsStr>, default: &str| -> &'static str {
+        let part = part.map_or(default.to_string(), |part| {
+            part.to_string_lossy().into_owned()
+        });
+        Box::leak(part.into_boxed_str())
+    };
+    Topic {
+        name: part(path.file_stem(), "capture"),
+        extension: part(path.extension(), "kcap"),
+        // Dropping batches would defeat the point of a lossless capture
+        priority: Priority::High,
+        fsync: FsyncPolicy::Never,
+        max_bytes: 0,
+        keep: 0,
+    }
+}
+
+fn class_name(class: DeviceClass) -> &'static str {
+    match class {
+        DeviceClass::Touchscreen => "touchscreen",
+        DeviceClass::Trackpad => "trackpad",
+        DeviceClass::Other => "other",
+    }
+}
+
+fn type_name(type_: u16) -> String {
+    match EventType(type_) {
+        EventType::SYNCHRONIZATION => "SYN".to_string(),
+        EventType::KEY => "KEY".to_string(),
+        EventType::RELATIVE => "REL".to_string(),
+        EventType::ABSOLUTE => "ABS".to_string(),
+        EventType::MISC => "MSC".to_string(),
+        _ => format!("{type_:#x}"),
+    }
+}
+
+fn code_name(type_: u16, code: u16) -> String {
+    match EventType(type_) {
+        EventType::SYNCHRONIZATION => format!("{:?}", SynchronizationCode(code)),
+        EventType::KEY => format!("{:?}", KeyCode(code)),
+        EventType::RELATIVE => format!("{:?}", RelativeAxisCode(code)),
+        EventType::ABSOLUTE => format!("{:?}", AbsoluteAxisCode(code)),
+        EventType::MISC => format!("{:?}", MiscCode(code)),
+        _ => format!("{code:#x}"),
+    }
+}
+
+fn take_u16(bytes: &[u8]) -> Option<(u16, &[u8])> {
+    let (value, rest) = bytes.split_first_chunk::<2>()?;
+    Some((u16::from_le_bytes(*value), rest))
+}
+
+fn unix_micros(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header() -> CaptureHeader {
+        CaptureHeader {
+            name: "Sim panel".to_string(),
+            path: "/dev/input/event7".to_string(),
+            phys: Some("usb-0000:00:14.0-4/input0".to_string()),
+            vendor: 0x0eef,
+            product: 0x0001,
+            bus: 3,
+            class: "touchscreen".to_string(),
+            model: "DatorBBFält".to_string(),
+            reports_touch: true,
+            swap_axes: false,
+            axes: vec![CaptureAxis {
+                code: AbsoluteAxisCode::ABS_X.0,
+                minimum: 0,
+                maximum: 4095,
+                fuzz: 0,
+                flat: 0,
+                resolution: 12,
+            }],
+            started_us: 1_700_000_000_000_000,
+        }
+    }
+
+    fn events() -> Vec<CapturedEvent> {
+        [
+            (EventType::KEY.0, KeyCode::BTN_TOUCH.0, 1),
+            (EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_X.0, 2048),
+            (EventType::ABSOLUTE.0, AbsoluteAxisCode::ABS_Y.0, -1),
+            (EventType::SYNCHRONIZATION.0, 0, 0),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (type_, code, value))| CapturedEvent {
+            time_us: 1_700_000_000_000_000 + i as u64 * 8000,
+            type_,
+            code,
+            value,
+        })
+        .collect()
+    }
+
+    fn file_bytes() -> Vec<u8> {
+        let mut bytes = header().encode();
+        for event in events() {
+            bytes.extend_from_slice(&event.encode());
+        }
+        bytes
+    }
+
+    #[test]
+    fn a_capture_written_through_the_writer_reads_back_the_same() {
+        let dir =
+            std::env::temp_dir().join(format!("keyboard_test-capture-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("panel.kcap");
+
+        // As `record` writes it: the header, then the records in batches
+        let writer = Writer::spawn(&dir, writer::DEFAULT_QUEUE_LIMIT).unwrap();
+        let topic = capture_topic(&path);
+        let log = writer.handle();
+        log.replace(&topic, header().encode());
+        for batch in events().chunks(3) {
+            log.append(
+                &topic,
+                batch
+                    .iter()
+                    .flat_map(CapturedEvent::encode)
+                    .collect::<Vec<u8>>(),
+            );
+        }
+        writer.shutdown();
+
+        assert_eq!(topic.path(&dir), path);
+        let capture = Capture::read(&path).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        assert_eq!(
+            capture,
+            Capture {
+                header: header(),
+                events: events(),
+                truncated: false,
+            }
+        );
+        assert_eq!(
+            capture.header.device_info().resolution,
+            None,
+            "ABS_Y isn't advertised"
+        );
+        assert_eq!(capture.header.device_info().abs_x_max, Some(4095));
+    }
+
+    #[test]
+    fn a_record_cut_short_is_dropped_and_marked() {
+        let bytes = file_bytes();
+        let records_from = header().encode().len();
+        let record = 2 + RECORD_LEN;
+
+        // Every cut inside the last record keeps the three before it
+        for cut in 1..record {
+            let capture = Capture::parse(&bytes[..bytes.len() - cut]).unwrap();
+            assert_eq!(capture.events, events()[..3], "cut by {cut}");
+            assert!(capture.truncated, "cut by {cut}");
+        }
+
+        // Any length at all parses or fails cleanly, the record fields are
+        // only read from a whole record of at least RECORD_LEN bytes
+        for len in 0..bytes.len() {
+            let parsed = Capture::parse(&bytes[..len]);
+            if len < records_from {
+                assert!(parsed.is_err(), "{len} bytes");
+            } else {
+                let capture = parsed.unwrap();
+                assert_eq!(capture.events.len(), (len - records_from) / record);
+                assert_eq!(
+                    capture.truncated,
+                    !(len - records_from).is_multiple_of(record)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn record_lengths_other_than_this_version_writes() {
+        let event = events()[1];
+        let mut bytes = header().encode();
+
+        // A later version's longer record: the fields known are read, the rest skipped
+        let mut longer = event.encode().to_vec();
+        longer[..2].copy_from_slice(&(RECORD_LEN as u16 + 4).to_le_bytes());
+        longer.extend_from_slice(&[0xaa; 4]);
+        bytes.extend_from_slice(&longer);
+        bytes.extend_from_slice(&event.encode());
+        let capture = Capture::parse(&bytes).unwrap();
+        assert_eq!(capture.events, [event, event]);
+        assert!(!capture.truncated);
+
+        // A whole record too short for the fields is corrupt, not truncated
+        let mut short = header().encode();
+        short.extend_from_slice(&8u16.to_le_bytes());
+        short.extend_from_slice(&[0; 8]);
+        assert_eq!(
+            Capture::parse(&short).unwrap_err().to_string(),
+            "capture record of 8 bytes, expected 16"
+        );
+    }
+
+    #[test]
+    fn headers_that_dont_parse() {
+        let error = |bytes: &[u8]| Capture::parse(bytes).unwrap_err().to_string();
+        assert_eq!(error(b"not a capture"), "not a capture file");
+
+        let mut bytes = file_bytes();
+        bytes[8..10].copy_from_slice(&2u16.to_le_bytes());
+        assert_eq!(error(&bytes), "unsupported capture version 2");
+
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION.to_le_bytes());
+        bytes.extend_from_slice(&2u32.to_le_bytes());
+        bytes.extend_from_slice(b"{]");
+        assert!(error(&bytes).starts_with("invalid capture header: "));
+    }
+}
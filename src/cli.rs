@@ -0,0 +1,627 @@
+use color_eyre::{Result, eyre::eyre};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::calibration_matrix::CalibrationMatrix;
+use crate::config::{Config, MinDurationConfig};
+use crate::event_handler::{DEFAULT_TOUCH_KEEPALIVE, DeviceSource};
+use crate::frame_budget::{DEFAULT_FRAME_BUDGET, DEFAULT_LADDER, Degradation};
+use crate::glyphs::GlyphSet;
+use crate::key_rate::RateLimit;
+use crate::keyboard_test::DEFAULT_STALE_WINDOW;
+use crate::keymap::KeyMap;
+use crate::language::Language;
+use crate::logging;
+use crate::machine_detect::get_computer_model;
+use crate::pen_hover::DEFAULT_HOVER_OFFSET_MM;
+use crate::press_colors::PressPalette;
+use crate::report::ReportFormat;
+use crate::resume::DEFAULT_RESUME_MAX_AGE;
+use crate::stabilizer::{DEFAULT_ALPHA, Filter};
+use crate::theme::Theme;
+use crate::touch_corners::DEFAULT_CORNER_MARGIN_MM;
+use crate::touch_roi::Roi;
+use crate::touchscreen_test::{CaptureMode, DEFAULT_JUMP_THRESHOLD};
+use crate::units::parse_panel_size;
+
+/// Command line options
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Where to write the session report, without extension
+    pub report_path: Option<PathBuf>,
+    /// Which report formats to write, in the order requested
+    pub report_formats: Vec<ReportFormat>,
+    /// Feed the screens from simulated devices instead of real hardware
+    pub demo: bool,
+    /// Seed for the simulated devices, same seed gives the same event sequences
+    pub demo_seed: u64,
+    /// Only listen to input devices whose name contains this text
+    pub device_filter: Option<String>,
+    /// When a keyboard counts as flooding and gets throttled
+    pub key_rate_limit: RateLimit,
+    /// How touchscreen calibration corners are captured, from the config file
+    /// (usually its model section) unless the flag is given
+    pub calibration_capture: CaptureMode,
+    /// Directory for the event, fault and result logs, no logging without it
+    pub log_dir: Option<PathBuf>,
+    /// Physical touch panel size (width, height) in mm, for devices that don't report
+    /// their axis resolution. From the config file, usually its model section.
+    pub panel_mm: Option<(f32, f32)>,
+    /// Mouse test starting sensitivity, replacing the model default
+    pub mouse_sensitivity: Option<f32>,
+    /// Don't treat keypad Enter and arrows as navigation keys
+    pub raw_keypad: bool,
+    /// Start with a prompt for the operator ID and unit serial
+    pub ask_metadata: bool,
+    /// Restore the terminal and exit when the event pipeline stalls, instead of freezing
+    pub exit_on_stall: bool,
+    /// How the keyboard test colors keys by press count
+    pub press_palette: PressPalette,
+    /// Colors and cues of the test states, see `theme`
+    pub theme: Theme,
+    /// Silence after which the keyboard test flags a key as stale
+    pub stale_window: Duration,
+    /// Start a touchscreen soak once calibrated, with a snapshot this often
+    pub soak_interval: Option<Duration>,
+    /// libinput matrix to calibrate the touchscreen with instead of the corner holds
+    pub calibration_matrix: Option<CalibrationMatrix>,
+    /// Write the libinput matrix of each finished touchscreen calibration here
+    pub calibration_out: Option<PathBuf>,
+    /// Touchscreen corner touch-downs have to land within this many mm of the edges
+    pub corner_margin_mm: f32,
+    /// Pen hover-to-touch offset over which a digitizer is flagged, in mm
+    pub hover_offset_mm: f32,
+    /// Touch steps longer than this in calibrated units are jumps, from the config file
+    pub jump_threshold: f32,
+    /// Require touch-downs at the edge midpoints as well as the corners
+    pub edge_midpoints: bool,
+    /// Part of the touchscreen coverage and corners are checked in, from the config file
+    pub touch_roi: Option<Roi>,
+    /// Jitter filter of the touchscreen test's display, from the config file
+    pub touch_stabilizer: Option<Filter>,
+    /// Moving average weight B in the touchscreen test picks the filter with
+    pub stabilizer_alpha: f32,
+    /// Repeat the last sample of a resting touch this often, None never
+    pub touch_keepalive: Option<Duration>,
+    /// Sweep the serial touch baud rates when frames don't parse, from the config file
+    pub serial_baud_sweep: bool,
+    /// Average frame time above which rendering is degraded, from the config file
+    pub frame_budget: Duration,
+    /// Rendering features given up over budget, in order, from the config file
+    pub degrade_ladder: Vec<Degradation>,
+    /// Unicode or ASCII drawing, from the config file
+    pub glyphs: GlyphSet,
+    /// Language of the keycap labels, see `language`
+    pub language: Language,
+    /// Leave a passed test after a countdown, from the config file
+    pub auto_advance: bool,
+    /// Address of the read-only status endpoint, from the config file
+    pub status_listen: Option<String>,
+    /// Shortest time each test has to run, from the config file
+    pub min_durations: MinDurationConfig,
+    /// Keys known to be broken per unit serial, from the config file
+    pub excluded_keys: BTreeMap<String, BTreeMap<String, String>>,
+    /// Directory of custom keyboard layout files, from the config file
+    pub layouts_dir: Option<PathBuf>,
+    /// Print the input devices and exit, without the TUI
+    pub list_devices: bool,
+    /// Listen on the touch devices this long, print what arrived and exit, without the TUI
+    pub probe_touch: Option<Duration>,
+    /// Run the startup self-check and exit, without the TUI (`--check self`)
+    pub self_check: bool,
+    /// Validate this report file and exit, without the TUI
+    pub validate_report: Option<PathBuf>,
+    /// Record the raw events of a device (node or name) to a file until Ctrl-C,
+    /// without the TUI, see `capture`
+    pub capture: Option<(String, PathBuf)>,
+    /// Print this capture as text and exit, without the TUI
+    pub dump_capture: Option<PathBuf>,
+    /// Feed the screens from this capture instead of the hardware
+    pub replay_capture: Option<PathBuf>,
+    /// Print the supported layouts, models and protocols as JSON and exit
+    pub print_capabilities: bool,
+    /// Offer to resume a saved session at most this old, zero never offers it
+    pub resume_max_age: Duration,
+    /// Config file with site settings, over the system and user ones
+    pub config_path: Option<PathBuf>,
+    /// The config files merged, before the command line flags
+    pub config: Config,
+    /// Print the effective configuration as TOML and exit
+    pub print_config: bool,
+    // The arguments parsed, to parse again over reloaded config files
+    args: Vec<String>,
+    /// Key bindings from the config file
+    pub keymap: KeyMap,
+}
+
+impl Options {
+    pub fn parse() -> Result<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    fn parse_from(args: impl IntoIterator<Item = String>) -> Result<Self> {
+        let mut options = Options {
+            demo_seed: 1,
+            resume_max_age: DEFAULT_RESUME_MAX_AGE,
+            stale_window: DEFAULT_STALE_WINDOW,
+            corner_margin_mm: DEFAULT_CORNER_MARGIN_MM,
+            hover_offset_mm: DEFAULT_HOVER_OFFSET_MM,
+            jump_threshold: DEFAULT_JUMP_THRESHOLD,
+            stabilizer_alpha: DEFAULT_ALPHA,
+            touch_keepalive: Some(DEFAULT_TOUCH_KEEPALIVE),
+            serial_baud_sweep: true,
+            frame_budget: DEFAULT_FRAME_BUDGET,
+            degrade_ladder: DEFAULT_LADDER.to_vec(),
+            ..Options::default()
+        };
+        let args: Vec<String> = args.into_iter().collect();
+        options.args = args.clone();
+
+        // The config files go under the flags, so they're read before the flags are parsed
+        if let Some(index) = args.iter().position(|arg| arg == "--config") {
+            let path = args
+                .get(index + 1)
+                .ok_or_else(|| eyre!("--config requires a path"))?;
+            options.config_path = Some(PathBuf::from(path));
+        }
+        let model = format!("{:?}", get_computer_model());
+        let (config, warnings) = Config::load_layered(options.config_path.as_deref(), &model)?;
+        for warning in warnings {
+            logging::warn(&warning);
+        }
+        options.apply_config(config)?;
+
+        let mut formats_flagged = false;
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--report" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--report requires a path"))?;
+                    options.report_path = Some(PathBuf::from(path));
+                }
+                "--report-format" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| eyre!("--report-format requires json or html"))?;
+                    // The flags replace the config file's formats
+                    if !formats_flagged {
+                        options.report_formats.clear();
+                        formats_flagged = true;
+                    }
+                    // Accept both "--report-format json,html" and repeated flags
+                    for name in value.split(',') {
+                        let format = ReportFormat::from_name(name.trim())
+                            .ok_or_else(|| eyre!("unknown report format '{name}'"))?;
+                        if !options.report_formats.contains(&format) {
+                            options.report_formats.push(format);
+                        }
+                    }
+                }
+                "--demo" => options.demo = true,
+                "--demo-seed" => {
+                    let seed = args
+                        .next()
+                        .ok_or_else(|| eyre!("--demo-seed requires a number"))?;
+                    options.demo_seed = seed
+                        .parse()
+                        .map_err(|_| eyre!("invalid --demo-seed '{seed}'"))?;
+                    options.demo = true;
+                }
+                "--device-filter" => {
+                    let text = args
+                        .next()
+                        .ok_or_else(|| eyre!("--device-filter requires a device name"))?;
+                    options.device_filter = Some(text);
+                }
+                "--max-key-rate" => {
+                    let rate = args
+                        .next()
+                        .ok_or_else(|| eyre!("--max-key-rate requires presses per second"))?;
+                    options.key_rate_limit.max_per_sec = rate
+                        .parse()
+                        .ok()
+                        .filter(|rate| *rate > 0)
+                        .ok_or_else(|| eyre!("invalid --max-key-rate '{rate}'"))?;
+                }
+                "--calibration-capture" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| eyre!("--calibration-capture requires release or auto"))?;
+                    options.calibration_capture = CaptureMode::from_name(&name)
+                        .ok_or_else(|| eyre!("unknown calibration capture mode '{name}'"))?;
+                }
+                "--log-dir" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--log-dir requires a directory"))?;
+                    options.log_dir = Some(PathBuf::from(path));
+                }
+                "--panel-mm" => {
+                    let size = args
+                        .next()
+                        .ok_or_else(|| eyre!("--panel-mm requires a size like 217x136"))?;
+                    options.panel_mm = Some(
+                        parse_panel_size(&size)
+                            .ok_or_else(|| eyre!("invalid --panel-mm '{size}'"))?,
+                    );
+                }
+                "--mouse-sensitivity" => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| eyre!("--mouse-sensitivity requires a number"))?;
+                    options.mouse_sensitivity = Some(
+                        value
+                            .parse()
+                            .ok()
+                            .filter(|s: &f32| (0.1..=5.0).contains(s))
+                            .ok_or_else(|| eyre!("invalid --mouse-sensitivity '{value}'"))?,
+                    );
+                }
+                "--raw-keypad" => options.raw_keypad = true,
+                "--ask-metadata" => options.ask_metadata = true,
+                "--exit-on-stall" => options.exit_on_stall = true,
+                "--press-colors" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| eyre!("--press-colors requires ramp or cycle"))?;
+                    options.press_palette = PressPalette::from_name(&name)
+                        .ok_or_else(|| eyre!("unknown press color palette '{name}'"))?;
+                }
+                "--press-ramp" => {
+                    let steps = args
+                        .next()
+                        .ok_or_else(|| eyre!("--press-ramp requires a number of presses"))?;
+                    let steps = steps
+                        .parse()
+                        .ok()
+                        .filter(|steps| *steps > 0)
+                        .ok_or_else(|| eyre!("invalid --press-ramp '{steps}'"))?;
+                    options.press_palette = PressPalette::Ramp { steps };
+                }
+                "--theme" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| eyre!("--theme requires standard or color-blind"))?;
+                    options.theme =
+                        Theme::from_name(&name).ok_or_else(|| eyre!("unknown theme '{name}'"))?;
+                }
+                "--language" => {
+                    let name = args
+                        .next()
+                        .ok_or_else(|| eyre!("--language requires en or sv"))?;
+                    options.language = Language::from_name(&name)
+                        .ok_or_else(|| eyre!("unknown language '{name}'"))?;
+                }
+                "--stale-window" => {
+                    let minutes = args
+                        .next()
+                        .ok_or_else(|| eyre!("--stale-window requires a number of minutes"))?;
+                    options.stale_window = minutes
+                        .parse()
+                        .ok()
+                        .filter(|m: &f32| *m > 0.0 && m.is_finite())
+                        .map(|m| Duration::from_secs_f32(m * 60.0))
+                        .ok_or_else(|| eyre!("invalid --stale-window '{minutes}'"))?;
+                }
+                "--soak" => {
+                    let minutes = args
+                        .next()
+                        .ok_or_else(|| eyre!("--soak requires a number of minutes"))?;
+                    options.soak_interval = Some(
+                        minutes
+                            .parse()
+                            .ok()
+                            .filter(|m: &f32| *m > 0.0 && m.is_finite())
+                            .map(|m| Duration::from_secs_f32(m * 60.0))
+                            .ok_or_else(|| eyre!("invalid --soak '{minutes}'"))?,
+                    );
+                }
+                "--corner-margin" => {
+                    let mm = args
+                        .next()
+                        .ok_or_else(|| eyre!("--corner-margin requires a distance in mm"))?;
+                    options.corner_margin_mm = mm
+                        .parse()
+                        .ok()
+                        .filter(|m: &f32| *m > 0.0 && m.is_finite())
+                        .ok_or_else(|| eyre!("invalid --corner-margin '{mm}'"))?;
+                }
+                "--hover-offset" => {
+                    let mm = args
+                        .next()
+                        .ok_or_else(|| eyre!("--hover-offset requires a distance in mm"))?;
+                    options.hover_offset_mm = mm
+                        .parse()
+                        .ok()
+                        .filter(|m: &f32| *m > 0.0 && m.is_finite())
+                        .ok_or_else(|| eyre!("invalid --hover-offset '{mm}'"))?;
+                }
+                "--edge-midpoints" => options.edge_midpoints = true,
+                "--calibration-matrix" => {
+                    let values = args.next().ok_or_else(|| {
+                        eyre!("--calibration-matrix requires six values like 1,0,0,0,1,0")
+                    })?;
+                    options.calibration_matrix = Some(
+                        CalibrationMatrix::parse(&values)
+                            .map_err(|e| eyre!("invalid --calibration-matrix: {e}"))?,
+                    );
+                }
+                "--calibration-out" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--calibration-out requires a path"))?;
+                    options.calibration_out = Some(PathBuf::from(path));
+                }
+                "--touch-keepalive" => {
+                    let ms = args
+                        .next()
+                        .ok_or_else(|| eyre!("--touch-keepalive requires milliseconds"))?;
+                    let ms: u64 = ms
+                        .parse()
+                        .map_err(|_| eyre!("invalid --touch-keepalive '{ms}'"))?;
+                    // 0 turns the keep-alive off
+                    options.touch_keepalive = (ms > 0).then(|| Duration::from_millis(ms));
+                }
+                "--list-devices" => options.list_devices = true,
+                "--print-capabilities" => options.print_capabilities = true,
+                "--probe-touch" => {
+                    let seconds = args
+                        .next()
+                        .ok_or_else(|| eyre!("--probe-touch requires a number of seconds"))?;
+                    options.probe_touch = Some(
+                        seconds
+                            .parse()
+                            .ok()
+                            .filter(|s: &f32| *s > 0.0 && s.is_finite())
+                            .map(Duration::from_secs_f32)
+                            .ok_or_else(|| eyre!("invalid --probe-touch '{seconds}'"))?,
+                    );
+                }
+                "--check" => match args.next().as_deref() {
+                    Some("self") => options.self_check = true,
+                    _ => return Err(eyre!("--check requires self")),
+                },
+                "--validate-report" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--validate-report requires a path"))?;
+                    options.validate_report = Some(PathBuf::from(path));
+                }
+                "--capture" => {
+                    let (Some(device), Some(path)) = (args.next(), args.next()) else {
+                        return Err(eyre!("--capture requires a device and a path"));
+                    };
+                    options.capture = Some((device, PathBuf::from(path)));
+                }
+                "--dump-capture" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--dump-capture requires a path"))?;
+                    options.dump_capture = Some(PathBuf::from(path));
+                }
+                "--replay-capture" => {
+                    let path = args
+                        .next()
+                        .ok_or_else(|| eyre!("--replay-capture requires a path"))?;
+                    options.replay_capture = Some(PathBuf::from(path));
+                }
+                "--resume-max-age" => {
+                    let hours = args
+                        .next()
+                        .ok_or_else(|| eyre!("--resume-max-age requires a number of hours"))?;
+                    options.resume_max_age = hours
+                        .parse()
+                        .ok()
+                        .filter(|h: &f32| *h >= 0.0 && h.is_finite())
+                        .map(|h| Duration::from_secs_f32(h * 3600.0))
+                        .ok_or_else(|| eyre!("invalid --resume-max-age '{hours}'"))?;
+                }
+                // Already read, before the flags
+                "--config" => {
+                    args.next();
+                }
+                "--print-config" => options.print_config = true,
+                other => return Err(eyre!("unknown argument '{other}'")),
+            }
+        }
+
+        // Asking for a format implies wanting a report, and vice versa
+        if options.report_path.is_some() && options.report_formats.is_empty() {
+            options.report_formats.push(ReportFormat::Json);
+        }
+        if !options.report_formats.is_empty() && options.report_path.is_none() {
+            options.report_path = Some(PathBuf::from("input_device_test_report"));
+        }
+
+        Ok(options)
+    }
+
+    /// Read the config files again and apply the same flags over them
+    pub fn reload(&self) -> Result<Self> {
+        Self::parse_from(self.args.clone())
+    }
+
+    /// Take the settings of the merged config files, checked like their flags
+    fn apply_config(&mut self, config: Config) -> Result<()> {
+        let (keymap, warnings) = KeyMap::from_config(&config.keybindings);
+        for warning in warnings {
+            logging::warn(&warning);
+        }
+        self.keymap = keymap;
+        self.serial_baud_sweep = config.serial.baud_sweep;
+        self.frame_budget = Duration::from_millis(config.render.frame_budget_ms);
+        self.degrade_ladder = Vec::new();
+        for name in &config.render.degrade {
+            match Degradation::from_name(name) {
+                Some(step) => self.degrade_ladder.push(step),
+                None => logging::warn(&format!("Unknown render degradation '{name}', ignored")),
+            }
+        }
+        self.glyphs = GlyphSet::from_name(&config.render.glyphs).unwrap_or_else(|| {
+            logging::warn(&format!(
+                "Unknown glyph set '{}', using auto",
+                config.render.glyphs
+            ));
+            GlyphSet::Auto
+        });
+        self.language = Language::from_name(&config.render.language).ok_or_else(|| {
+            eyre!(
+                "unknown config render.language '{}'",
+                config.render.language
+            )
+        })?;
+        self.auto_advance = config.auto_advance;
+        self.status_listen = config.status.listen.clone();
+        self.min_durations = config.min_durations.clone();
+        self.excluded_keys = config.excluded_keys.clone();
+        self.layouts_dir = config.keyboard.layouts_dir.clone();
+
+        let thresholds = &config.thresholds;
+        let minutes = thresholds.stale_window_min;
+        if !(minutes > 0.0 && minutes.is_finite()) {
+            return Err(eyre!(
+                "invalid config thresholds.stale_window_min '{minutes}'"
+            ));
+        }
+        self.stale_window = Duration::from_secs_f32(minutes * 60.0);
+        let margin = thresholds.corner_margin_mm;
+        if !(margin > 0.0 && margin.is_finite()) {
+            return Err(eyre!(
+                "invalid config thresholds.corner_margin_mm '{margin}'"
+            ));
+        }
+        self.corner_margin_mm = margin;
+        let offset = thresholds.hover_offset_mm;
+        if !(offset > 0.0 && offset.is_finite()) {
+            return Err(eyre!(
+                "invalid config thresholds.hover_offset_mm '{offset}'"
+            ));
+        }
+        self.hover_offset_mm = offset;
+        let jump = thresholds.jump;
+        if !(jump > 0.0 && jump.is_finite()) {
+            return Err(eyre!("invalid config thresholds.jump '{jump}'"));
+        }
+        self.jump_threshold = jump;
+        let keepalive = thresholds.touch_keepalive_ms;
+        self.touch_keepalive = (keepalive > 0).then(|| Duration::from_millis(keepalive));
+        if thresholds.max_key_rate == 0 {
+            return Err(eyre!("invalid config thresholds.max_key_rate '0'"));
+        }
+        self.key_rate_limit.max_per_sec = thresholds.max_key_rate;
+
+        self.touch_roi = match config.touchscreen.roi {
+            Some(edges) => {
+                Some(Roi::new(edges).map_err(|e| eyre!("invalid config touchscreen.roi: {e}"))?)
+            }
+            None => None,
+        };
+        self.stabilizer_alpha = config.touchscreen.stabilizer_alpha.unwrap_or(DEFAULT_ALPHA);
+        if !(self.stabilizer_alpha > 0.0 && self.stabilizer_alpha <= 1.0) {
+            return Err(eyre!(
+                "invalid config touchscreen.stabilizer_alpha '{}'",
+                self.stabilizer_alpha
+            ));
+        }
+        self.panel_mm = match config.touchscreen.panel_mm {
+            Some([width, height])
+                if width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite() =>
+            {
+                Some((width, height))
+            }
+            Some(size) => {
+                return Err(eyre!("invalid config touchscreen.panel_mm '{size:?}'"));
+            }
+            None => None,
+        };
+        self.calibration_capture = match &config.touchscreen.calibration_capture {
+            Some(name) => CaptureMode::from_name(name)
+                .ok_or_else(|| eyre!("unknown config touchscreen.calibration_capture '{name}'"))?,
+            None => CaptureMode::default(),
+        };
+        self.touch_stabilizer = match &config.touchscreen.stabilizer {
+            Some(name) => Some(
+                Filter::from_name(name, self.stabilizer_alpha)
+                    .map_err(|e| eyre!("invalid config touchscreen.stabilizer: {e}"))?,
+            ),
+            None => None,
+        };
+
+        let theme = &config.theme;
+        self.press_palette = match PressPalette::from_name(&theme.press_colors) {
+            Some(PressPalette::Ramp { .. }) if theme.press_ramp > 0 => PressPalette::Ramp {
+                steps: theme.press_ramp,
+            },
+            Some(PressPalette::Ramp { .. }) => {
+                return Err(eyre!("invalid config theme.press_ramp '0'"));
+            }
+            Some(palette) => palette,
+            None => {
+                return Err(eyre!(
+                    "unknown config theme.press_colors '{}'",
+                    theme.press_colors
+                ));
+            }
+        };
+        self.theme = Theme::from_name(&theme.colors)
+            .ok_or_else(|| eyre!("unknown config theme.colors '{}'", theme.colors))?;
+
+        self.report_path = config.report.path.clone();
+        self.report_formats = Vec::new();
+        for name in &config.report.formats {
+            let format = ReportFormat::from_name(name)
+                .ok_or_else(|| eyre!("unknown config report.formats entry '{name}'"))?;
+            if !self.report_formats.contains(&format) {
+                self.report_formats.push(format);
+            }
+        }
+
+        self.config = config;
+        Ok(())
+    }
+
+    /// The config files with the flags applied, what the tool runs with
+    pub fn effective_config(&self) -> Config {
+        let mut config = self.config.clone();
+        config.thresholds.stale_window_min = self.stale_window.as_secs_f32() / 60.0;
+        config.thresholds.corner_margin_mm = self.corner_margin_mm;
+        config.thresholds.hover_offset_mm = self.hover_offset_mm;
+        config.thresholds.jump = self.jump_threshold;
+        config.thresholds.touch_keepalive_ms = self
+            .touch_keepalive
+            .map_or(0, |keepalive| keepalive.as_millis() as u64);
+        config.thresholds.max_key_rate = self.key_rate_limit.max_per_sec;
+        config.touchscreen.panel_mm = self.panel_mm.map(|(width, height)| [width, height]);
+        config.touchscreen.calibration_capture = Some(self.calibration_capture.name().to_string());
+        match self.press_palette {
+            PressPalette::Ramp { steps } => {
+                config.theme.press_colors = "ramp".to_string();
+                config.theme.press_ramp = steps;
+            }
+            PressPalette::Cycle => config.theme.press_colors = "cycle".to_string(),
+        }
+        config.theme.colors = self.theme.name().to_string();
+        config.render.language = self.language.name().to_string();
+        config.report.path = self.report_path.clone();
+        config.report.formats = self
+            .report_formats
+            .iter()
+            .map(|format| format.extension().to_string())
+            .collect();
+        // The model section is already merged in
+        config.models.clear();
+        config
+    }
+
+    /// The real input devices, narrowed down by `--device-filter`
+    pub fn device_source(&self) -> DeviceSource {
+        let source = DeviceSource::system();
+        match self.device_filter.clone() {
+            Some(text) => source.with_filter(move |info| info.name.contains(&text)),
+            None => source,
+        }
+    }
+}
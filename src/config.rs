@@ -0,0 +1,709 @@
+//! Site configuration: key bindings, serial touch, rendering, thresholds, the
+//! touchscreen's region of interest and jitter filter, custom keyboard layouts, key colors, reports, the status endpoint, test durations and test flow, e.g.
+//!
+//! ```toml
+//! auto_advance = true
+//!
+//! [keybindings]
+//! finish_test = ["KEY_F10"]
+//! quick_select = []
+//!
+//! [serial]
+//! baud_sweep = false
+//!
+//! [render]
+//! frame_budget_ms = 80
+//! degrade = ["trail_lines"]
+//! glyphs = "ascii"
+//! language = "sv"
+//!
+//! [thresholds]
+//! stale_window_min = 5
+//! corner_margin_mm = 4
+//! jump = 40
+//!
+//! [touchscreen]
+//! roi = [0.0, 0.08, 1.0, 1.0]
+//! stabilizer = "ema"
+//! stabilizer_alpha = 0.3
+//! panel_mm = [217, 136]
+//! calibration_capture = "release"
+//!
+//! [keyboard]
+//! layouts_dir = "/etc/keyboard_test/layouts"
+//!
+//! [theme]
+//! press_colors = "cycle"
+//! colors = "color-blind"
+//!
+//! [report]
+//! path = "/var/log/units/report"
+//! formats = ["json", "html"]
+//!
+//! [status]
+//! listen = "unix:/run/keyboard_test.sock"
+//!
+//! [min_durations]
+//! keyboard_secs = 300
+//!
+//! [excluded_keys.SN1234]
+//! F5 = "Awaiting parts"
+//!
+//! [models."DatorBBFältGPS".serial]
+//! baud_sweep = true
+//!
+//! [models."DatorBBFältG2".touchscreen]
+//! panel_mm = [256, 160]
+//! calibration_capture = "auto"
+//! ```
+//!
+//! Read in layers, each overriding the keys it sets: [`SYSTEM_CONFIG`], the
+//! user's `~/.config/keyboard_test/config.toml`, the file given with
+//! `--config`, then the section of `models` naming this machine's model.
+//! Command line flags override all of them. Unknown keys are warned about with
+//! the closest known key, a value of the wrong type is an error naming the file
+//! and key.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use color_eyre::{Result, eyre::eyre};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::frame_budget::{DEFAULT_FRAME_BUDGET, DEFAULT_LADDER};
+use crate::glyphs::GlyphSet;
+use crate::key_rate::RateLimit;
+use crate::keyboard_test::DEFAULT_STALE_WINDOW;
+use crate::language::Language;
+use crate::pen_hover::DEFAULT_HOVER_OFFSET_MM;
+use crate::press_colors::DEFAULT_RAMP_STEPS;
+use crate::theme::Theme;
+use crate::touch_corners::DEFAULT_CORNER_MARGIN_MM;
+use crate::touchscreen_test::DEFAULT_JUMP_THRESHOLD;
+
+/// Machine-wide config, the first layer
+pub const SYSTEM_CONFIG: &str = "/etc/keyboard_test.toml";
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    /// Action name to evdev key names, see `keymap::Action`
+    pub keybindings: BTreeMap<String, Vec<String>>,
+    pub serial: SerialConfig,
+    pub render: RenderConfig,
+    pub thresholds: ThresholdConfig,
+    pub touchscreen: TouchscreenConfig,
+    pub keyboard: KeyboardConfig,
+    pub theme: ThemeConfig,
+    pub report: ReportConfig,
+    pub status: StatusConfig,
+    pub min_durations: MinDurationConfig,
+    /// Leave a test by itself a few seconds after it passed, see `auto_advance`
+    pub auto_advance: bool,
+    /// Unit serial to the keys known to be broken on that unit, key label or
+    /// code name to reason, see `key_exclusions`
+    pub excluded_keys: BTreeMap<String, BTreeMap<String, String>>,
+    /// Model name (as `--print-capabilities` lists them) to settings that
+    /// override the rest of the config on that model
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub models: BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SerialConfig {
+    /// Try other baud rates when the serial touch frames don't parse. Sites
+    /// with a known-good rate can turn it off to skip the sweep.
+    pub baud_sweep: bool,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        SerialConfig { baud_sweep: true }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct RenderConfig {
+    /// Average frame time above which rendering is degraded
+    pub frame_budget_ms: u64,
+    /// Names of the features to give up, in order, see `frame_budget::Degradation`.
+    /// An empty list never degrades.
+    pub degrade: Vec<String>,
+    /// "auto", "unicode" or "ascii", see `glyphs`
+    pub glyphs: String,
+    /// "en" or "sv", the keycap labels drawn, see `language`
+    pub language: String,
+}
+
+impl Default for RenderConfig {
+    fn default() -> Self {
+        RenderConfig {
+            frame_budget_ms: DEFAULT_FRAME_BUDGET.as_millis() as u64,
+            degrade: DEFAULT_LADDER
+                .iter()
+                .map(|step| step.name().to_string())
+                .collect(),
+            glyphs: GlyphSet::Auto.name().to_string(),
+            language: Language::English.name().to_string(),
+        }
+    }
+}
+
+/// Limits that have command line flags of the same name
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThresholdConfig {
+    /// `--stale-window`, minutes
+    pub stale_window_min: f32,
+    /// `--corner-margin`
+    pub corner_margin_mm: f32,
+    /// `--touch-keepalive`, 0 turns the repeats off
+    pub touch_keepalive_ms: u64,
+    /// `--max-key-rate`, presses per second
+    pub max_key_rate: u32,
+    /// `--hover-offset`, mm
+    pub hover_offset_mm: f32,
+    /// Touch steps longer than this many calibrated units (0-999 across the
+    /// panel) count as jumps. ←/→ in the jump view tune it, `--calibration-out`
+    /// writes the tuned value to copy back here.
+    pub jump: f32,
+}
+
+impl Default for ThresholdConfig {
+    fn default() -> Self {
+        ThresholdConfig {
+            stale_window_min: DEFAULT_STALE_WINDOW.as_secs_f32() / 60.0,
+            corner_margin_mm: DEFAULT_CORNER_MARGIN_MM,
+            touch_keepalive_ms: crate::event_handler::DEFAULT_TOUCH_KEEPALIVE.as_millis() as u64,
+            max_key_rate: RateLimit::default().max_per_sec,
+            hover_offset_mm: DEFAULT_HOVER_OFFSET_MM,
+            jump: DEFAULT_JUMP_THRESHOLD,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct TouchscreenConfig {
+    /// Part of the panel not behind the bezel: left, top, right and bottom as
+    /// 0-1 of the calibrated space, see `touch_roi`. Unset is the whole panel.
+    pub roi: Option<[f32; 4]>,
+    /// Jitter filter shown next to the raw samples, "ema" or "median3", see
+    /// `stabilizer`. Unset starts without one, B in the test picks one.
+    pub stabilizer: Option<String>,
+    /// Weight of the newest sample in the "ema" filter, (0, 1]
+    pub stabilizer_alpha: Option<f32>,
+    /// `--panel-mm`, width and height in mm for touch devices that don't report
+    /// an axis resolution. Differs per model, so usually set in a model section.
+    pub panel_mm: Option<[f32; 2]>,
+    /// `--calibration-capture`, "release" or "auto", see
+    /// `touchscreen_test::CaptureMode`. Auto suits units used with gloves.
+    pub calibration_capture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct KeyboardConfig {
+    /// Directory of custom layout files, listed after the built-in layouts,
+    /// see `custom_layouts`
+    pub layouts_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ThemeConfig {
+    /// `--press-colors`, ramp or cycle
+    pub press_colors: String,
+    /// `--press-ramp`, presses along the ramp
+    pub press_ramp: usize,
+    /// `--theme`, standard or color-blind, see `theme::Theme`
+    pub colors: String,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        ThemeConfig {
+            press_colors: "ramp".to_string(),
+            press_ramp: DEFAULT_RAMP_STEPS,
+            colors: Theme::default().name().to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// `--report`, without extension
+    pub path: Option<PathBuf>,
+    /// `--report-format`, json and html
+    pub formats: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct StatusConfig {
+    /// Where the read-only status endpoint listens, "unix:<path>" or a TCP
+    /// address like "127.0.0.1:7070", see `status_server`. Unset keeps it off.
+    pub listen: Option<String>,
+}
+
+/// Shortest time each test has to run to pass, in seconds, 0 for no minimum.
+/// A test passing earlier doesn't auto-advance before then.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct MinDurationConfig {
+    pub keyboard_secs: u64,
+    pub mouse_secs: u64,
+    pub touchscreen_secs: u64,
+    pub trackpad_secs: u64,
+}
+
+impl Config {
+    /// The layers merged for `model`, with warnings about unknown keys. The
+    /// system and user files are optional, `explicit` (from `--config`) has
+    /// to exist.
+    pub fn load_layered(explicit: Option<&Path>, model: &str) -> Result<(Self, Vec<String>)> {
+        let mut layers: Vec<PathBuf> = [Some(PathBuf::from(SYSTEM_CONFIG)), user_config()]
+            .into_iter()
+            .flatten()
+            .filter(|path| path.exists())
+            .collect();
+        layers.extend(explicit.map(Path::to_path_buf));
+        Config::load_layers(&layers, model)
+    }
+
+    /// The files of `layers` merged in order, then the section for `model`
+    fn load_layers(layers: &[PathBuf], model: &str) -> Result<(Self, Vec<String>)> {
+        let mut warnings = Vec::new();
+        let mut merged = Value::Object(Map::new());
+        for path in layers {
+            merge(&mut merged, read_layer(path, &mut warnings)?);
+        }
+        if let Some(section) = merged.get("models").and_then(|models| models.get(model)) {
+            let section = section.clone();
+            merge(&mut merged, section);
+        }
+
+        let config = serde_json::from_value(merged).map_err(|e| eyre!("invalid config: {e}"))?;
+        Ok((config, warnings))
+    }
+
+    /// Print the config as TOML, for `--print-config`. Returns the exit code.
+    pub fn print(&self) -> i32 {
+        match toml::to_string_pretty(self) {
+            Ok(text) => {
+                print!("{text}");
+                0
+            }
+            Err(e) => {
+                println!("Cannot serialize the config: {e}");
+                1
+            }
+        }
+    }
+}
+
+/// `$XDG_CONFIG_HOME/keyboard_test/config.toml`, or the same under `~/.config`
+fn user_config() -> Option<PathBuf> {
+    user_config_under(env::var_os("XDG_CONFIG_HOME"), env::var_os("HOME"))
+}
+
+fn user_config_under(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    let base = xdg_config_home
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home.map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join("keyboard_test").join("config.toml"))
+}
+
+/// Parse one file and check its keys and values against the known settings
+fn read_layer(path: &Path, warnings: &mut Vec<String>) -> Result<Value> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| eyre!("cannot read config {}: {e}", path.display()))?;
+    let table: toml::Table =
+        toml::from_str(&text).map_err(|e| eyre!("invalid config {}: {e}", path.display()))?;
+    // The layers are merged and checked as JSON values
+    let value = serde_json::to_value(table)?;
+
+    let known = serde_json::to_value(Config::default())?;
+    check(&value, &known, &mut Vec::new(), path, warnings)?;
+    if let Some(models) = value.get("models").and_then(Value::as_object) {
+        for (model, section) in models {
+            let mut key = vec!["models".to_string(), model.clone()];
+            check_section(section, &known, &mut key, path, warnings)?;
+        }
+    }
+    Ok(value)
+}
+
+/// Check a model section, which takes the same keys as the top level
+fn check_section(
+    section: &Value,
+    known: &Value,
+    key: &mut Vec<String>,
+    path: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    if !section.is_object() {
+        return Err(eyre!(
+            "invalid config {}: {} is not a table",
+            path.display(),
+            key.join(".")
+        ));
+    }
+    let prefix = key.len();
+    check(section, known, key, path, warnings).map_err(|e| {
+        // Type errors of the section are reported as if at the top level
+        eyre!("{e} (in {})", key[..prefix].join("."))
+    })
+}
+
+/// Warn about keys of `value` missing from `known` and fail on values that
+/// don't deserialize where they are
+fn check(
+    value: &Value,
+    known: &Value,
+    key: &mut Vec<String>,
+    path: &Path,
+    warnings: &mut Vec<String>,
+) -> Result<()> {
+    let (Some(fields), Some(known_fields)) = (value.as_object(), known.as_object()) else {
+        return check_value(value, key, path);
+    };
+    for (name, field) in fields {
+        key.push(name.clone());
+        match known_fields.get(name) {
+            // Model sections are checked on their own
+            _ if key.len() == 1 && name == "models" => {}
+            // Maps with names chosen by the site, like the key bindings' actions,
+            // checked entry by entry to name the one that's wrong
+            Some(Value::Object(known_map)) if known_map.is_empty() => match field.as_object() {
+                Some(entries) => {
+                    for (entry, value) in entries {
+                        key.push(entry.clone());
+                        check_value(value, key, path)?;
+                        key.pop();
+                    }
+                }
+                None => check_value(field, key, path)?,
+            },
+            Some(known_field) => check(field, known_field, key, path, warnings)?,
+            None => warnings.push(unknown_key(key, known_fields, path)),
+        }
+        key.pop();
+    }
+    Ok(())
+}
+
+/// Deserialize `value` alone at `key` to find a type error there
+fn check_value(value: &Value, key: &[String], path: &Path) -> Result<()> {
+    // Model sections hold top level keys, so the key is built from after the model name
+    let start = if key.first().is_some_and(|first| first == "models") {
+        2.min(key.len())
+    } else {
+        0
+    };
+    let sparse = key[start..]
+        .iter()
+        .rev()
+        .fold(value.clone(), |inner, name| {
+            Value::Object(Map::from_iter([(name.clone(), inner)]))
+        });
+    serde_json::from_value::<Config>(sparse)
+        .map(|_| ())
+        .map_err(|e| {
+            eyre!(
+                "invalid config {}: {}: {e}",
+                path.display(),
+                key[start..].join(".")
+            )
+        })
+}
+
+fn unknown_key(key: &[String], known_fields: &Map<String, Value>, path: &Path) -> String {
+    let name = &key[key.len() - 1];
+    let mut message = format!(
+        "Unknown config key '{}' in {}, ignored",
+        key.join("."),
+        path.display()
+    );
+    let nearest = known_fields
+        .keys()
+        .map(|known| (edit_distance(name, known), known))
+        .min();
+    // Only a near miss is worth suggesting
+    if let Some((distance, known)) = nearest
+        && distance <= name.chars().count().max(known.chars().count()) / 2
+    {
+        message.push_str(&format!(", did you mean '{known}'?"));
+    }
+    message
+}
+
+/// Levenshtein distance, counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = (diagonal + usize::from(ca != *cb))
+                .min(above + 1)
+                .min(row[j] + 1);
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// Lay `layer` over `base`: objects merge key by key, anything else replaces
+fn merge(base: &mut Value, layer: Value) {
+    match (base, layer) {
+        (Value::Object(base), Value::Object(layer)) => {
+            for (name, value) in layer {
+                match base.get_mut(&name) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(name, value);
+                    }
+                }
+            }
+        }
+        (base, layer) => *base = layer,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory of config files for test `name`
+    struct Files(PathBuf);
+
+    impl Files {
+        fn new(name: &str) -> Self {
+            let dir = env::temp_dir().join(format!(
+                "keyboard_test-config-{name}-{}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Files(dir)
+        }
+
+        fn write(&self, name: &str, text: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, text).unwrap();
+            path
+        }
+    }
+
+    impl Drop for Files {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn load(layers: &[PathBuf], model: &str) -> (Config, Vec<String>) {
+        Config::load_layers(layers, model).unwrap()
+    }
+
+    #[test]
+    fn later_files_override_the_keys_they_set() {
+        let files = Files::new("layers");
+        let system = files.write(
+            "system.toml",
+            "[serial]\nbaud_sweep = false\n[render]\nframe_budget_ms = 80\nglyphs = \"unicode\"\n",
+        );
+        let user = files.write(
+            "user.toml",
+            "[render]\nframe_budget_ms = 60\n[theme]\ncolors = \"color-blind\"\n",
+        );
+        let explicit = files.write(
+            "site.toml",
+            "auto_advance = true\n[render]\nglyphs = \"ascii\"\n",
+        );
+
+        let (config, warnings) = load(&[system, user, explicit], "DatorBBFält");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert!(!config.serial.baud_sweep);
+        assert_eq!(config.render.frame_budget_ms, 60);
+        assert_eq!(config.render.glyphs, "ascii");
+        assert_eq!(config.theme.colors, "color-blind");
+        assert!(config.auto_advance);
+        // Untouched keys keep their defaults
+        assert_eq!(config.render.language, RenderConfig::default().language);
+        assert_eq!(config.render.degrade, RenderConfig::default().degrade);
+    }
+
+    #[test]
+    fn the_model_section_overrides_the_files_on_that_model() {
+        let files = Files::new("models");
+        let system = files.write(
+            "system.toml",
+            "[serial]\nbaud_sweep = false\n[models.\"DatorBBFältGPS\".serial]\nbaud_sweep = true\n",
+        );
+        // A later file's top level doesn't beat the model section
+        let explicit = files.write("site.toml", "[serial]\nbaud_sweep = false\n");
+        let layers = [system, explicit];
+
+        assert!(load(&layers, "DatorBBFältGPS").0.serial.baud_sweep);
+        assert!(!load(&layers, "DatorBBFält").0.serial.baud_sweep);
+
+        // The panel size is a property of the model
+        let panel = files.write(
+            "panel.toml",
+            "[touchscreen]\ncalibration_capture = \"release\"\n\
+             [models.\"DatorBBFältG2\".touchscreen]\npanel_mm = [256, 160]\n\
+             calibration_capture = \"auto\"\n",
+        );
+        let layers = [panel];
+        let g2 = load(&layers, "DatorBBFältG2").0.touchscreen;
+        assert_eq!(g2.panel_mm, Some([256.0, 160.0]));
+        assert_eq!(g2.calibration_capture.as_deref(), Some("auto"));
+        let other = load(&layers, "DatorBBFält").0.touchscreen;
+        assert_eq!(other.panel_mm, None);
+        assert_eq!(other.calibration_capture.as_deref(), Some("release"));
+    }
+
+    #[test]
+    fn unknown_keys_are_warned_about_with_the_nearest_known_one() {
+        let files = Files::new("unknown");
+        let path = files.write(
+            "site.toml",
+            "xyzzy = 1\n[serail]\nbaud_sweep = false\n[render]\nframe_budget = 80\n\
+             [models.\"DatorBBFält\".thresholds]\nmax_key_rat = 40\n",
+        );
+        let (config, warnings) = load(std::slice::from_ref(&path), "DatorBBFält");
+        let file = path.display();
+        assert_eq!(
+            warnings,
+            [
+                format!(
+                    "Unknown config key 'render.frame_budget' in {file}, ignored, did you mean 'frame_budget_ms'?"
+                ),
+                format!("Unknown config key 'serail' in {file}, ignored, did you mean 'serial'?"),
+                format!("Unknown config key 'xyzzy' in {file}, ignored"),
+                format!(
+                    "Unknown config key 'models.DatorBBFält.thresholds.max_key_rat' in {file}, ignored, did you mean 'max_key_rate'?"
+                ),
+            ]
+        );
+        // The rest of the file still counts
+        assert_eq!(
+            config.render.frame_budget_ms,
+            RenderConfig::default().frame_budget_ms
+        );
+        assert!(config.serial.baud_sweep);
+    }
+
+    #[test]
+    fn type_errors_name_the_file_and_key() {
+        let files = Files::new("types");
+        for (text, key) in [
+            (
+                "[render]\nframe_budget_ms = \"fast\"\n",
+                "render.frame_budget_ms",
+            ),
+            ("auto_advance = \"yes\"\n", "auto_advance"),
+            (
+                "[keybindings]\nfinish_test = \"KEY_F10\"\n",
+                "keybindings.finish_test",
+            ),
+            ("[touchscreen]\nroi = [0.0, 1.0]\n", "touchscreen.roi"),
+            (
+                "[models.\"DatorBBFältGPS\".thresholds]\nmax_key_rate = -3\n",
+                "thresholds.max_key_rate",
+            ),
+        ] {
+            let path = files.write("site.toml", text);
+            let error = Config::load_layers(std::slice::from_ref(&path), "DatorBBFält")
+                .unwrap_err()
+                .to_string();
+            assert!(
+                error.starts_with(&format!("invalid config {}: {key}: ", path.display())),
+                "{text}: {error}"
+            );
+        }
+
+        let path = files.write("site.toml", "[models]\n\"DatorBBFältGPS\" = 3\n");
+        let error = Config::load_layers(&[path], "DatorBBFält").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .ends_with("models.DatorBBFältGPS is not a table"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn unreadable_or_broken_files_name_the_file() {
+        let files = Files::new("broken");
+        let path = files.write("site.toml", "[render\nglyphs = \"ascii\"\n");
+        let error = Config::load_layers(std::slice::from_ref(&path), "DatorBBFält").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(&format!("invalid config {}: ", path.display()))
+        );
+
+        let missing = files.0.join("missing.toml");
+        let error = Config::load_layers(std::slice::from_ref(&missing), "DatorBBFält").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with(&format!("cannot read config {}", missing.display()))
+        );
+    }
+
+    #[test]
+    fn the_printed_config_reads_back_the_same() {
+        let files = Files::new("print");
+        let site = files.write(
+            "site.toml",
+            "[touchscreen]\nroi = [0.0, 0.08, 1.0, 1.0]\n[excluded_keys.SN1234]\nF5 = \"Awaiting parts\"\n\
+             [models.\"DatorBBFältGPS\".serial]\nbaud_sweep = false\n",
+        );
+        let (config, _) = load(&[site], "DatorBBFält");
+        let printed = files.write("printed.toml", &toml::to_string_pretty(&config).unwrap());
+
+        let (read_back, warnings) = load(&[printed], "DatorBBFält");
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(
+            serde_json::to_value(&read_back).unwrap(),
+            serde_json::to_value(&config).unwrap()
+        );
+    }
+
+    #[test]
+    fn the_user_file_goes_by_xdg_then_home() {
+        let under = |xdg: Option<&str>, home: Option<&str>| {
+            user_config_under(xdg.map(OsString::from), home.map(OsString::from))
+        };
+        assert_eq!(
+            under(Some("/xdg"), Some("/home/op")),
+            Some(PathBuf::from("/xdg/keyboard_test/config.toml"))
+        );
+        assert_eq!(
+            under(Some(""), Some("/home/op")),
+            Some(PathBuf::from("/home/op/.config/keyboard_test/config.toml"))
+        );
+        assert_eq!(under(None, None), None);
+    }
+
+    #[test]
+    fn near_misses_are_a_few_edits_away() {
+        assert_eq!(edit_distance("serail", "serial"), 2);
+        assert_eq!(edit_distance("frame_budget", "frame_budget_ms"), 3);
+        assert_eq!(edit_distance("Å", "A"), 1);
+        assert_eq!(edit_distance("", "theme"), 5);
+    }
+}
@@ -0,0 +1,439 @@
+//! Keyboard layouts read from the TOML files of a directory
+//! (`keyboard.layouts_dir` in the config), for keyboards the built-in tables
+//! don't cover, e.g.
+//!
+//! ```toml
+//! name = "Rugged 84"
+//! # The main block, the keys of each row left to right
+//! rows = [
+//!     ["KEY_ESC", "KEY_F1", "KEY_F2"],
+//!     ["KEY_A", { label = "Enter", codes = ["KEY_ENTER", "KEY_KPENTER"], any_of = true }],
+//! ]
+//!
+//! # Blocks drawn side by side below the main one, e.g. the arrows
+//! [[blocks]]
+//! rows = [["KEY_UP"], ["KEY_LEFT", "KEY_DOWN", "KEY_RIGHT"]]
+//! ```
+//!
+//! A key given by its code name alone is labelled with the name after `KEY_`.
+//! The layout selection polls the files and reads the changed ones again, a
+//! file that no longer parses keeps its last good version until it does.
+//! Layouts are leaked to be `'static` like the built-in ones, once per edit.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+use color_eyre::{Result, eyre::eyre};
+use evdev::KeyCode;
+use serde::Deserialize;
+
+use crate::keyboard_layouts::{Key, KeyLayout, KeyPart, KeyShape, KeyboardLayout};
+use crate::logging;
+
+// Ticks come ten times a second, the directory is listed far less often
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LayoutSpec {
+    name: Option<String>,
+    rows: Vec<Vec<KeySpec>>,
+    #[serde(default)]
+    blocks: Vec<BlockSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BlockSpec {
+    rows: Vec<Vec<KeySpec>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeySpec {
+    Code(String),
+    Key(KeyTable),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyTable {
+    label: String,
+    label_sv: Option<String>,
+    codes: Vec<String>,
+    #[serde(default)]
+    any_of: bool,
+}
+
+impl KeySpec {
+    fn key(self) -> Result<Key> {
+        let (label, label_sv, names, any_of) = match self {
+            KeySpec::Code(name) => {
+                let label = name.strip_prefix("KEY_").unwrap_or(&name).to_string();
+                (label, None, vec![name], false)
+            }
+            KeySpec::Key(table) => (table.label, table.label_sv, table.codes, table.any_of),
+        };
+        if names.is_empty() {
+            return Err(eyre!("key '{label}' has no codes"));
+        }
+        let codes = names
+            .iter()
+            .map(|name| {
+                name.parse::<KeyCode>()
+                    .map_err(|_| eyre!("unknown key code '{name}'"))
+            })
+            .collect::<Result<Vec<KeyCode>>>()?;
+        Ok(Key {
+            label: label.leak(),
+            label_sv: label_sv.map(|label| &*label.leak()),
+            codes: codes.leak(),
+            any_of,
+            part: KeyPart::Whole,
+            shape: KeyShape::Plain,
+        })
+    }
+}
+
+/// The rows of one block, `what` names it in errors
+fn block(rows: Vec<Vec<KeySpec>>, what: &str) -> Result<KeyLayout> {
+    if rows.is_empty() {
+        return Err(eyre!("{what} has no rows"));
+    }
+    let rows = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            if row.is_empty() {
+                return Err(eyre!("{what} row {} has no keys", i + 1));
+            }
+            let keys = row
+                .into_iter()
+                .map(KeySpec::key)
+                .collect::<Result<Vec<Key>>>()
+                .map_err(|e| eyre!("{what} row {}: {e}", i + 1))?;
+            Ok(&*keys.leak())
+        })
+        .collect::<Result<Vec<&'static [Key]>>>()?;
+    Ok(rows.leak())
+}
+
+/// Name and keys of a layout file's text, named `fallback` when it has no name
+pub fn parse_layout(text: &str, fallback: &str) -> Result<(&'static str, KeyboardLayout)> {
+    let spec: LayoutSpec = toml::from_str(text).map_err(|e| {
+        // One line for the preview, the message without toml's excerpt
+        match e.span() {
+            Some(span) => {
+                let line = text[..span.start].matches('\n').count() + 1;
+                eyre!("line {line}: {}", e.message().trim_end())
+            }
+            None => eyre!("{}", e.message().trim_end()),
+        }
+    })?;
+    let main = block(spec.rows, "rows")?;
+    let blocks = spec
+        .blocks
+        .into_iter()
+        .enumerate()
+        .map(|(i, spec)| block(spec.rows, &format!("block {}", i + 1)))
+        .collect::<Result<Vec<KeyLayout>>>()?;
+
+    let mut sections: Vec<&'static [KeyLayout]> = vec![vec![main].leak()];
+    if !blocks.is_empty() {
+        sections.push(blocks.leak());
+    }
+    let name = spec.name.unwrap_or_else(|| fallback.to_string());
+    Ok((name.leak(), sections.leak()))
+}
+
+/// One file of the layouts directory
+pub struct CustomLayout {
+    pub path: PathBuf,
+    // When the file was read last, it's read again once that changes
+    modified: Option<SystemTime>,
+    /// Name and keys of the latest version that parsed, kept while the file on
+    /// disk doesn't
+    pub layout: Option<(&'static str, KeyboardLayout)>,
+    /// Why the file on disk doesn't parse
+    pub error: Option<String>,
+}
+
+impl CustomLayout {
+    /// The layout's name, the file's until a version of it parsed
+    pub fn name(&self) -> String {
+        match self.layout {
+            Some((name, _)) => name.to_string(),
+            None => file_stem(&self.path),
+        }
+    }
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// The `*.toml` files of a directory, read again when they change
+pub struct CustomLayouts {
+    dir: Option<PathBuf>,
+    // Sorted by path, which is the order they're listed in
+    files: Vec<CustomLayout>,
+    next_poll: Option<Instant>,
+}
+
+impl Default for CustomLayouts {
+    fn default() -> Self {
+        CustomLayouts::new(None)
+    }
+}
+
+impl CustomLayouts {
+    /// The layouts of `dir` as they are now, none without a directory
+    pub fn new(dir: Option<PathBuf>) -> Self {
+        let mut layouts = CustomLayouts {
+            dir,
+            files: Vec::new(),
+            next_poll: None,
+        };
+        layouts.reload();
+        layouts
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn files(&self) -> &[CustomLayout] {
+        &self.files
+    }
+
+    /// Take the directory of reloaded config files, returning the names of the
+    /// layouts read again like `reload`
+    pub fn set_dir(&mut self, dir: Option<PathBuf>) -> Vec<&'static str> {
+        if dir != self.dir {
+            self.dir = dir;
+            self.files.clear();
+        }
+        self.reload()
+    }
+
+    /// `reload` at most once every POLL_INTERVAL
+    pub fn poll(&mut self, now: Instant) -> Vec<&'static str> {
+        if self.dir.is_none() || self.next_poll.is_some_and(|next| now < next) {
+            return Vec::new();
+        }
+        self.next_poll = Some(now + POLL_INTERVAL);
+        self.reload()
+    }
+
+    /// List the directory again and read the files new or changed since,
+    /// returning the names of those that parsed. Files gone are dropped, a
+    /// missing directory lists nothing.
+    pub fn reload(&mut self) -> Vec<&'static str> {
+        let Some(dir) = &self.dir else {
+            return Vec::new();
+        };
+        let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+
+        let mut reloaded = Vec::new();
+        let mut files = Vec::with_capacity(paths.len());
+        for path in paths {
+            let modified = fs::metadata(&path)
+                .and_then(|metadata| metadata.modified())
+                .ok();
+            let mut file = match self.files.iter().position(|file| file.path == path) {
+                Some(i) => self.files.swap_remove(i),
+                None => CustomLayout {
+                    path,
+                    modified: None,
+                    layout: None,
+                    error: None,
+                },
+            };
+            if file.modified.is_none() || file.modified != modified {
+                file.modified = modified;
+                let read = fs::read_to_string(&file.path)
+                    .map_err(|e| eyre!("cannot read: {e}"))
+                    .and_then(|text| parse_layout(&text, &file_stem(&file.path)));
+                match read {
+                    Ok(layout) => {
+                        reloaded.push(layout.0);
+                        file.layout = Some(layout);
+                        file.error = None;
+                    }
+                    Err(e) => {
+                        logging::warn(&format!("Layout {} not loaded: {e}", file.path.display()));
+                        file.error = Some(e.to_string());
+                    }
+                }
+            }
+            files.push(file);
+        }
+        self.files = files;
+        reloaded
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keyboard_layouts;
+
+    const RUGGED: &str = r#"
+name = "Rugged 84"
+rows = [
+    ["KEY_ESC", "KEY_F1"],
+    ["KEY_A", { label = "Enter", codes = ["KEY_ENTER", "KEY_KPENTER"], any_of = true }],
+]
+
+[[blocks]]
+rows = [["KEY_UP"], ["KEY_LEFT", "KEY_DOWN", "KEY_RIGHT"]]
+"#;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(what: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "keyboard_test-layouts-{what}-{}",
+                std::process::id()
+            ));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        /// Write `text` to `name`, dated `secs` after the epoch so that every
+        /// edit is seen whatever the file system's time resolution
+        fn write(&self, name: &str, text: &str, secs: u64) {
+            let path = self.0.join(name);
+            fs::write(&path, text).unwrap();
+            let file = fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn a_layout_file_gives_the_main_block_and_the_blocks_below() {
+        let (name, layout) = parse_layout(RUGGED, "rugged").unwrap();
+        assert_eq!(name, "Rugged 84");
+        assert_eq!(layout.len(), 2);
+        assert_eq!(layout[1].len(), 1);
+        assert_eq!(keyboard_layouts::key_count(layout), 8);
+
+        let esc = layout[0][0][0][0];
+        assert_eq!(esc.label, "ESC");
+        assert_eq!(esc.codes, &[KeyCode::KEY_ESC]);
+        let enter = layout[0][0][1][1];
+        assert_eq!(enter.label, "Enter");
+        assert_eq!(enter.codes, &[KeyCode::KEY_ENTER, KeyCode::KEY_KPENTER]);
+        assert!(enter.any_of);
+
+        // Without a name the file's is used, without blocks there's one section
+        let (name, layout) = parse_layout(r#"rows = [["KEY_A"]]"#, "spare").unwrap();
+        assert_eq!(name, "spare");
+        assert_eq!(layout.len(), 1);
+    }
+
+    #[test]
+    fn mistakes_name_where_they_are() {
+        let error = |text: &str| parse_layout(text, "x").unwrap_err().to_string();
+
+        assert_eq!(
+            error(r#"rows = [["KEY_A"], ["KEY_B", "KEY_NOPE"]]"#),
+            "rows row 2: unknown key code 'KEY_NOPE'"
+        );
+        assert_eq!(error("rows = [[\"KEY_A\"], []]"), "rows row 2 has no keys");
+        assert_eq!(error("rows = []"), "rows has no rows");
+        assert_eq!(
+            error("rows = [[\"KEY_A\"]]\n[[blocks]]\nrows = [[{ label = \"Fn\", codes = [] }]]"),
+            "block 1 row 1: key 'Fn' has no codes"
+        );
+        assert!(error("rows = [[\"KEY_A\"]]\ncolour = 1").contains("unknown field `colour`"));
+        assert_eq!(
+            error("rows = [\"KEY_A\"]"),
+            "line 1: invalid type: string \"KEY_A\", expected a sequence"
+        );
+        assert_eq!(
+            error("name = \"R\"\nrows = ["),
+            "line 2: unclosed array, expected `]`"
+        );
+    }
+
+    #[test]
+    fn changed_files_are_read_again_and_a_broken_edit_keeps_the_last_good_one() {
+        let dir = TempDir::new("reload");
+        dir.write("rugged.toml", RUGGED, 1000);
+        dir.write("notes.txt", "not a layout", 1000);
+        let mut layouts = CustomLayouts::new(Some(dir.0.clone()));
+        assert_eq!(layouts.files().len(), 1);
+        assert_eq!(layouts.files()[0].name(), "Rugged 84");
+
+        // Nothing changed, nothing read
+        assert!(layouts.reload().is_empty());
+
+        dir.write(
+            "rugged.toml",
+            &RUGGED.replace("Rugged 84", "Rugged 85"),
+            1001,
+        );
+        assert_eq!(layouts.reload(), vec!["Rugged 85"]);
+        assert_eq!(layouts.files()[0].name(), "Rugged 85");
+
+        dir.write("rugged.toml", "rows = [[\"KEY_NOPE\"]]", 1002);
+        assert!(layouts.reload().is_empty());
+        let file = &layouts.files()[0];
+        assert_eq!(file.name(), "Rugged 85");
+        assert_eq!(keyboard_layouts::key_count(file.layout.unwrap().1), 8);
+        assert_eq!(
+            file.error.as_deref(),
+            Some("rows row 1: unknown key code 'KEY_NOPE'")
+        );
+
+        // A file that never parsed is listed by its file name
+        dir.write("a_spare.toml", "rows = [", 1000);
+        assert!(layouts.reload().is_empty());
+        assert_eq!(layouts.files()[0].name(), "a_spare");
+        assert!(layouts.files()[0].layout.is_none());
+
+        fs::remove_file(dir.0.join("a_spare.toml")).unwrap();
+        dir.write("rugged.toml", RUGGED, 1003);
+        assert_eq!(layouts.reload(), vec!["Rugged 84"]);
+        assert_eq!(layouts.files().len(), 1);
+        assert_eq!(layouts.files()[0].error, None);
+    }
+
+    #[test]
+    fn polls_are_spaced_and_a_missing_directory_lists_nothing() {
+        let dir = TempDir::new("poll");
+        dir.write("rugged.toml", RUGGED, 1000);
+        let mut layouts = CustomLayouts::new(Some(dir.0.clone()));
+        let start = Instant::now();
+        assert!(layouts.poll(start).is_empty());
+
+        dir.write("rugged.toml", RUGGED, 1001);
+        assert!(layouts.poll(start + POLL_INTERVAL / 2).is_empty());
+        assert_eq!(layouts.poll(start + POLL_INTERVAL), vec!["Rugged 84"]);
+
+        assert!(layouts.set_dir(Some(dir.0.join("missing"))).is_empty());
+        assert!(layouts.files().is_empty());
+        assert!(CustomLayouts::new(None).poll(start).is_empty());
+    }
+}
@@ -0,0 +1,227 @@
+//! Tells touchscreens from trackpads. Both report absolute X/Y and BTN_TOUCH,
+//! so without this the Cypress trackpad's events reach the touchscreen test as
+//! if it were a panel. The kernel's input properties settle it when the driver
+//! sets them, the finger tool buttons otherwise.
+
+use evdev::{AbsoluteAxisCode, Device, KeyCode, PropType};
+
+/// What kind of absolute pointing device an input device is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeviceClass {
+    /// Touches land where they are on the display (touch panels, pen digitizers)
+    Touchscreen,
+    /// Touches move a pointer (trackpads, touchpads)
+    Trackpad,
+    /// Keyboards, mice and everything without absolute positions
+    #[default]
+    Other,
+}
+
+/// The capabilities the classification looks at, separate from `Device` so it
+/// can be reasoned about with fabricated sets
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Capabilities {
+    /// INPUT_PROP_DIRECT
+    pub direct: bool,
+    /// INPUT_PROP_POINTER
+    pub pointer: bool,
+    /// ABS_X/ABS_Y or their multi-touch counterparts
+    pub abs_xy: bool,
+    pub btn_touch: bool,
+    pub btn_left: bool,
+    pub tool_finger: bool,
+    pub tool_doubletap: bool,
+    pub tool_pen: bool,
+}
+
+impl Capabilities {
+    pub fn of(device: &Device) -> Self {
+        let has_key = |code| {
+            device
+                .supported_keys()
+                .is_some_and(|keys| keys.contains(code))
+        };
+        let has_axis = |code| {
+            device
+                .supported_absolute_axes()
+                .is_some_and(|axes| axes.contains(code))
+        };
+
+        Capabilities {
+            direct: device.properties().contains(PropType::DIRECT),
+            pointer: device.properties().contains(PropType::POINTER),
+            abs_xy: (has_axis(AbsoluteAxisCode::ABS_X) && has_axis(AbsoluteAxisCode::ABS_Y))
+                || has_axis(AbsoluteAxisCode::ABS_MT_POSITION_X),
+            btn_touch: has_key(KeyCode::BTN_TOUCH),
+            btn_left: has_key(KeyCode::BTN_LEFT),
+            tool_finger: has_key(KeyCode::BTN_TOOL_FINGER),
+            tool_doubletap: has_key(KeyCode::BTN_TOOL_DOUBLETAP),
+            tool_pen: has_key(KeyCode::BTN_TOOL_PEN),
+        }
+    }
+}
+
+pub fn classify(caps: &Capabilities) -> DeviceClass {
+    if !caps.abs_xy {
+        return DeviceClass::Other;
+    }
+
+    // The properties are authoritative when the driver sets them
+    if caps.direct {
+        return DeviceClass::Touchscreen;
+    }
+    if caps.pointer {
+        return DeviceClass::Trackpad;
+    }
+
+    // Older drivers set neither: trackpads count fingers and usually have a
+    // button, touch panels and pens don't
+    if caps.tool_pen {
+        DeviceClass::Touchscreen
+    } else if caps.tool_doubletap || (caps.tool_finger && caps.btn_left) {
+        DeviceClass::Trackpad
+    } else if caps.btn_touch {
+        DeviceClass::Touchscreen
+    } else {
+        DeviceClass::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The Cypress pad of the field units, an old driver without properties
+    const CYPRESS_PAD: Capabilities = Capabilities {
+        direct: false,
+        pointer: false,
+        abs_xy: true,
+        btn_touch: true,
+        btn_left: true,
+        tool_finger: true,
+        tool_doubletap: true,
+        tool_pen: false,
+    };
+
+    /// The USB panel of the GPS model, properties set by hid-multitouch
+    const GPS_PANEL: Capabilities = Capabilities {
+        direct: true,
+        pointer: false,
+        abs_xy: true,
+        btn_touch: true,
+        btn_left: false,
+        tool_finger: true,
+        tool_doubletap: false,
+        tool_pen: false,
+    };
+
+    /// The laptops' touchpads, a current driver setting INPUT_PROP_POINTER
+    const LAPTOP_PAD: Capabilities = Capabilities {
+        direct: false,
+        pointer: true,
+        abs_xy: true,
+        btn_touch: true,
+        btn_left: true,
+        tool_finger: true,
+        tool_doubletap: true,
+        tool_pen: false,
+    };
+
+    const MOUSE: Capabilities = Capabilities {
+        direct: false,
+        pointer: false,
+        abs_xy: false,
+        btn_touch: false,
+        btn_left: true,
+        tool_finger: false,
+        tool_doubletap: false,
+        tool_pen: false,
+    };
+
+    #[test]
+    fn the_machine_models_devices() {
+        let keyboard = Capabilities::default();
+        let models = [
+            // The panel is on the serial port, evdev only sees the pad
+            (
+                "DatorBBFält",
+                vec![
+                    (CYPRESS_PAD, DeviceClass::Trackpad),
+                    (keyboard, DeviceClass::Other),
+                ],
+            ),
+            (
+                "DatorBBFältGPS",
+                vec![
+                    (GPS_PANEL, DeviceClass::Touchscreen),
+                    (keyboard, DeviceClass::Other),
+                ],
+            ),
+            (
+                "DatorBärbarRS11",
+                vec![
+                    (LAPTOP_PAD, DeviceClass::Trackpad),
+                    (MOUSE, DeviceClass::Other),
+                    (keyboard, DeviceClass::Other),
+                ],
+            ),
+            (
+                "DatorBärbarCMBRF8",
+                vec![
+                    (CYPRESS_PAD, DeviceClass::Trackpad),
+                    (MOUSE, DeviceClass::Other),
+                    (keyboard, DeviceClass::Other),
+                ],
+            ),
+        ];
+        for (model, devices) in models {
+            for (caps, class) in devices {
+                assert_eq!(classify(&caps), class, "{model}: {caps:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn properties_win_over_the_buttons() {
+        // A panel that also reports a doubletap tool is still a panel
+        let panel = Capabilities {
+            tool_doubletap: true,
+            btn_left: true,
+            ..GPS_PANEL
+        };
+        assert_eq!(classify(&panel), DeviceClass::Touchscreen);
+        let pad = Capabilities {
+            tool_pen: true,
+            ..LAPTOP_PAD
+        };
+        assert_eq!(classify(&pad), DeviceClass::Trackpad);
+    }
+
+    #[test]
+    fn old_drivers_fall_back_to_the_tools() {
+        let bare = Capabilities {
+            abs_xy: true,
+            ..Capabilities::default()
+        };
+        let pen = Capabilities {
+            tool_pen: true,
+            btn_touch: true,
+            ..bare
+        };
+        let panel = Capabilities {
+            btn_touch: true,
+            tool_finger: true,
+            ..bare
+        };
+        let single_finger_pad = Capabilities {
+            tool_finger: true,
+            btn_left: true,
+            ..bare
+        };
+        assert_eq!(classify(&pen), DeviceClass::Touchscreen);
+        assert_eq!(classify(&panel), DeviceClass::Touchscreen);
+        assert_eq!(classify(&single_finger_pad), DeviceClass::Trackpad);
+        // Absolute axes alone, e.g. a joystick
+        assert_eq!(classify(&bare), DeviceClass::Other);
+    }
+}
@@ -0,0 +1,239 @@
+//! Driver and firmware identifiers of the input devices, what the touch
+//! controller vendor asks for first in a bug report. All of it comes from
+//! sysfs: the kernel driver bound to the device's parent, the `uniq` string
+//! and input id version of the input device, and for USB devices the
+//! descriptor strings and bcdDevice of the USB device it hangs off. The sysfs
+//! directory is a parameter, see `DeviceSource::sysfs`.
+
+use std::fs;
+use std::path::Path;
+
+use crate::event_handler::DeviceInfo;
+use crate::report::{DeviceResult, UsbStrings};
+
+/// Where the kernel lists input devices, one entry per event node
+pub const SYS_CLASS_INPUT: &str = "/sys/class/input";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceIds {
+    /// Kernel driver of the device's parent, e.g. usbhid or atkbd
+    pub driver: Option<String>,
+    /// Unique id the driver reports, often a serial number, empty for most
+    pub uniq: Option<String>,
+    /// Input id version in hex; HID devices report their bcdDevice here
+    pub version: Option<String>,
+    /// Descriptor strings when the device is on USB
+    pub usb: Option<UsbStrings>,
+}
+
+/// The identifiers of event node `node` (e.g. "event5") listed under `sysfs`.
+/// Whatever can't be read stays None.
+pub fn read(sysfs: &Path, node: &str) -> DeviceIds {
+    // <sysfs>/event5/device is the input device (input12), its device the
+    // parent the driver is bound to (a USB interface, a serio port)
+    let input = sysfs.join(node).join("device");
+    let parent = input.join("device");
+
+    let driver = fs::read_link(parent.join("driver"))
+        .ok()
+        .and_then(|link| Some(link.file_name()?.to_string_lossy().into_owned()));
+
+    DeviceIds {
+        driver,
+        uniq: read_trim(&input.join("uniq")),
+        version: read_trim(&input.join("id").join("version")),
+        usb: usb_strings(&parent),
+    }
+}
+
+/// The report entry of `info`
+pub fn result(info: &DeviceInfo) -> DeviceResult {
+    DeviceResult {
+        path: info.path.clone(),
+        name: info.name.clone(),
+        id: format!("{:04x}:{:04x}", info.vendor, info.product),
+        bus: format!("{}", info.bus),
+        driver: info.ids.driver.clone(),
+        uniq: info.ids.uniq.clone(),
+        version: info.ids.version.clone(),
+        usb: info.ids.usb.clone(),
+    }
+}
+
+// The USB device is the nearest ancestor with an idVendor file; a USB
+// interface's own directory doesn't have one
+fn usb_strings(parent: &Path) -> Option<UsbStrings> {
+    let parent = fs::canonicalize(parent).ok()?;
+    let usb = parent
+        .ancestors()
+        .find(|dir| dir.join("idVendor").is_file())?;
+    Some(UsbStrings {
+        manufacturer: read_trim(&usb.join("manufacturer")),
+        product: read_trim(&usb.join("product")),
+        serial: read_trim(&usb.join("serial")),
+        bcd_device: read_trim(&usb.join("bcdDevice")),
+    })
+}
+
+fn read_trim(path: &Path) -> Option<String> {
+    fs::read_to_string(path)
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::sim::sim_device;
+
+    /// A sysfs tree for test `name`, laid out and linked like the kernel's
+    struct Sysfs(PathBuf);
+
+    impl Sysfs {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir()
+                .join(format!("keyboard_test-sysfs-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(root.join("class/input")).unwrap();
+            Sysfs(root)
+        }
+
+        fn path(&self, path: &str) -> PathBuf {
+            self.0.join(path)
+        }
+
+        /// Files in directory `dir`, created with its parents
+        fn files(&self, dir: &str, files: &[(&str, &str)]) {
+            let dir = self.path(dir);
+            for (name, text) in files {
+                let path = dir.join(name);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, text).unwrap();
+            }
+            fs::create_dir_all(dir).unwrap();
+        }
+
+        fn link(&self, from: &str, to: &str) {
+            let from = self.path(from);
+            fs::create_dir_all(self.path(to)).unwrap();
+            fs::create_dir_all(from.parent().unwrap()).unwrap();
+            symlink(self.path(to), from).unwrap();
+        }
+
+        /// Event node `node` of input device `input`, its parent `parent`
+        /// bound to `driver`
+        fn event_node(&self, node: &str, input: &str, parent: &str, driver: &str) {
+            self.link(
+                &format!("{parent}/driver"),
+                &format!("bus/drivers/{driver}"),
+            );
+            self.link(&format!("{input}/device"), parent);
+            self.link(&format!("{input}/{node}/device"), input);
+            self.link(&format!("class/input/{node}"), &format!("{input}/{node}"));
+        }
+
+        fn sysfs(&self) -> PathBuf {
+            self.path("class/input")
+        }
+    }
+
+    impl Drop for Sysfs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn a_usb_touch_panel_has_its_descriptor_strings() {
+        let sysfs = Sysfs::new("usb-touch");
+        let usb = "devices/pci0000:00/0000:00:14.0/usb1/1-2";
+        let hid = format!("{usb}/1-2:1.0/0003:0EEF:C000.0001");
+        let input = format!("{hid}/input/input12");
+        sysfs.files(
+            usb,
+            &[
+                ("idVendor", "0eef\n"),
+                ("idProduct", "c000\n"),
+                ("manufacturer", "eGalax Inc.\n"),
+                ("product", "eGalaxTouch EXC3000-0368-41.00.00\n"),
+                ("serial", "EXC3000-0815\n"),
+                ("bcdDevice", "0100\n"),
+            ],
+        );
+        // HID devices keep the uniq file, empty
+        sysfs.files(&input, &[("uniq", "\n"), ("id/version", "0100\n")]);
+        sysfs.event_node("event5", &input, &hid, "hid-multitouch");
+
+        let ids = read(&sysfs.sysfs(), "event5");
+        assert_eq!(
+            ids,
+            DeviceIds {
+                driver: Some("hid-multitouch".to_string()),
+                uniq: None,
+                version: Some("0100".to_string()),
+                usb: Some(UsbStrings {
+                    manufacturer: Some("eGalax Inc.".to_string()),
+                    product: Some("eGalaxTouch EXC3000-0368-41.00.00".to_string()),
+                    serial: Some("EXC3000-0815".to_string()),
+                    bcd_device: Some("0100".to_string()),
+                }),
+            }
+        );
+
+        let info = DeviceInfo {
+            vendor: 0x0eef,
+            product: 0xc000,
+            ids: Arc::new(ids),
+            ..sim_device(
+                "/dev/input/event5",
+                "eGalax Inc. eGalaxTouch EXC3000",
+                Some(4095),
+            )
+        };
+        let entry = result(&info);
+        assert_eq!(entry.id, "0eef:c000");
+        assert_eq!(entry.driver.as_deref(), Some("hid-multitouch"));
+        assert_eq!(
+            entry.usb.and_then(|usb| usb.serial).as_deref(),
+            Some("EXC3000-0815")
+        );
+    }
+
+    #[test]
+    fn a_ps2_keyboard_has_a_driver_and_no_usb_strings() {
+        let sysfs = Sysfs::new("ps2-keyboard");
+        let serio = "devices/platform/i8042/serio0";
+        let input = format!("{serio}/input/input3");
+        // No uniq file at all on serio devices
+        sysfs.files(
+            &input,
+            &[
+                ("name", "AT Translated Set 2 keyboard\n"),
+                ("id/version", "ab41\n"),
+            ],
+        );
+        sysfs.event_node("event2", &input, serio, "atkbd");
+
+        let ids = read(&sysfs.sysfs(), "event2");
+        assert_eq!(
+            ids,
+            DeviceIds {
+                driver: Some("atkbd".to_string()),
+                uniq: None,
+                version: Some("ab41".to_string()),
+                usb: None,
+            }
+        );
+    }
+
+    #[test]
+    fn a_node_missing_from_sysfs_has_no_ids() {
+        let sysfs = Sysfs::new("missing");
+        assert_eq!(read(&sysfs.sysfs(), "event9"), DeviceIds::default());
+    }
+}
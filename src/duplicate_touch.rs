@@ -0,0 +1,120 @@
+//! Detects one physical panel reported by several input devices at once, e.g.
+//! through the serial port and a USB HID bridge. Every touch then arrives twice
+//! with slightly different coordinates.
+
+use std::collections::HashMap;
+
+use crate::event_handler::DeviceInfo;
+
+/// Samples from two devices closer together than this count as simultaneous
+const DUPLICATE_WINDOW_MS: u128 = 50;
+/// Maximum distance between normalized (0.0-1.0) positions to count as the same touch
+const DUPLICATE_DISTANCE: f32 = 0.1;
+/// Correlated samples needed before reporting, so one coincidence isn't enough
+const DUPLICATE_MIN_HITS: u32 = 5;
+
+struct DeviceTrack {
+    name: String,
+    // Axis ranges, from the device when it reports them, otherwise observed
+    range_x: (u16, u16),
+    range_y: (u16, u16),
+    last: Option<(u128, u16, u16)>,
+}
+
+impl DeviceTrack {
+    fn new(info: &DeviceInfo) -> Self {
+        let reported = |max: Option<i32>| max.filter(|m| *m > 0).map(|m| (0, m as u16));
+        DeviceTrack {
+            name: info.name.clone(),
+            range_x: reported(info.abs_x_max).unwrap_or((u16::MAX, 0)),
+            range_y: reported(info.abs_y_max).unwrap_or((u16::MAX, 0)),
+            last: None,
+        }
+    }
+
+    fn observe(&mut self, x: u16, y: u16, timestamp: u128) {
+        self.range_x = (self.range_x.0.min(x), self.range_x.1.max(x));
+        self.range_y = (self.range_y.0.min(y), self.range_y.1.max(y));
+        self.last = Some((timestamp, x, y));
+    }
+
+    fn normalized(&self, x: u16, y: u16) -> Option<(f32, f32)> {
+        let norm = |v: u16, (lo, hi): (u16, u16)| {
+            (hi > lo).then(|| (v.saturating_sub(lo)) as f32 / (hi - lo) as f32)
+        };
+        Some((norm(x, self.range_x)?, norm(y, self.range_y)?))
+    }
+}
+
+#[derive(Default)]
+pub struct DuplicateTouchDetector {
+    devices: HashMap<String, DeviceTrack>,
+    hits: HashMap<(String, String), u32>,
+    detected: Option<(String, String)>,
+}
+
+impl DuplicateTouchDetector {
+    pub fn new() -> Self {
+        DuplicateTouchDetector::default()
+    }
+
+    /// Feed one touch sample, from any device
+    pub fn observe(&mut self, info: &DeviceInfo, x: u16, y: u16, timestamp: u128) {
+        // Looked up before inserting, the path is only copied for a new device
+        if !self.devices.contains_key(&info.path) {
+            self.devices
+                .insert(info.path.clone(), DeviceTrack::new(info));
+        }
+        let Some(track) = self.devices.get_mut(&info.path) else {
+            return;
+        };
+        track.observe(x, y, timestamp);
+        let Some(position) = track.normalized(x, y) else {
+            return;
+        };
+
+        for (path, other) in &self.devices {
+            if path == &info.path {
+                continue;
+            }
+            let Some((other_time, ox, oy)) = other.last else {
+                continue;
+            };
+            if timestamp.abs_diff(other_time) > DUPLICATE_WINDOW_MS {
+                continue;
+            }
+            let Some(other_position) = other.normalized(ox, oy) else {
+                continue;
+            };
+
+            let dx = position.0 - other_position.0;
+            let dy = position.1 - other_position.1;
+            if (dx * dx + dy * dy).sqrt() > DUPLICATE_DISTANCE {
+                continue;
+            }
+
+            // Same pair regardless of which device reported last
+            let pair = if info.path < *path {
+                (info.path.clone(), path.clone())
+            } else {
+                (path.clone(), info.path.clone())
+            };
+            let hits = self.hits.entry(pair.clone()).or_insert(0);
+            *hits += 1;
+
+            if *hits >= DUPLICATE_MIN_HITS && self.detected.is_none() {
+                let name = |p: &str| {
+                    self.devices
+                        .get(p)
+                        .map_or(p.to_string(), |d| d.name.clone())
+                };
+                self.detected = Some((name(&pair.0), name(&pair.1)));
+            }
+        }
+    }
+
+    /// Names of the two devices reporting the same panel, once detected
+    pub fn detected(&self) -> Option<&(String, String)> {
+        self.detected.as_ref()
+    }
+}
@@ -1,46 +1,93 @@
+//! Input from the devices. Device discovery is in `devices`, the per-device
+//! state machine in `listener`, hotplug in `hotplug` and the event types in
+//! `events`; this module starts the threads that put them together.
+
+mod devices;
+mod events;
+mod hotplug;
+mod listener;
+
 use color_eyre::Result;
 use color_eyre::eyre::eyre;
 use crossbeam_channel::Sender;
-use evdev::{Device, EventSummary, KeyCode};
-use std::collections::HashSet;
+use evdev::{Device, KeyCode};
+use signal_hook::{consts::SIGHUP, iterator::Signals};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::sync::{Arc, Mutex};
-use std::{fs, vec};
-use std::{thread, time::Duration};
-
-use crate::machine_detect::{ComputerModel, get_computer_model};
-use crate::serial_touch;
-
-#[derive(Debug, Clone)]
-pub struct DeviceInfo {
-    pub path: String,
-    pub name: String,
-    pub abs_x_max: Option<i32>,
-    pub abs_y_max: Option<i32>,
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::capture::Capture;
+use crate::device_class::DeviceClass;
+use crate::key_dedup::KeyDeduplicator;
+use crate::key_rate::RateLimit;
+use crate::listener_health;
+use crate::logging;
+use crate::machine_detect::{get_computer_model, redetect_computer_model};
+use crate::serial_touch::{self, SerialReader};
+use crate::watchdog;
+
+pub(crate) use devices::scan_devices;
+pub use devices::{DeviceFilter, DeviceSource};
+pub use events::{AppEvent, DeviceInfo, MAX_TOUCH_SLOTS, TouchContact};
+pub use listener::DEFAULT_TOUCH_KEEPALIVE;
+
+use devices::{get_devices, unreadable_dir};
+use listener::{Listener, RestingTouch, spawn_touch_keepalive};
+
+/// Start listening to every device in `source`, the serial touchscreen on models
+/// that have one, and the tick thread. Events arrive on `tx` until the process ends.
+/// `serial_baud_sweep` lets the serial reader try other rates when frames don't parse.
+/// The returned sources redo the hardware dependent part on `rescan`.
+pub fn spawn_device_listeners(
+    tx: &Sender<AppEvent>,
+    source: DeviceSource,
+    key_limit: RateLimit,
+    touch_keepalive: Option<Duration>,
+    serial_baud_sweep: bool,
+) -> Result<HardwareSources> {
+    let evdev = start_evdev_listeners(tx, source, key_limit, touch_keepalive)?;
+
+    let serial = serial_touch::spawn_reader(tx.clone(), serial_baud_sweep).ok();
+
+    spawn_tick_thread(tx);
+
+    Ok(HardwareSources {
+        tx: tx.clone(),
+        serial_baud_sweep,
+        serial,
+        evdev,
+    })
+}
+
+/// Listeners for every evdev device in `source`, plus hotplug pickup of devices added
+/// later. A touch resting longer than `touch_keepalive` gets its last sample repeated.
+pub fn spawn_evdev_listeners(
+    tx: &Sender<AppEvent>,
+    source: DeviceSource,
+    key_limit: RateLimit,
+    touch_keepalive: Option<Duration>,
+) -> Result<()> {
+    start_evdev_listeners(tx, source, key_limit, touch_keepalive).map(|_| ())
 }
 
-#[derive(Debug)]
-pub enum AppEvent {
-    Key {
-        code: KeyCode,
-        info: DeviceInfo,
-    },
-    Mouse {
-        x: i16,
-        y: i16,
-        info: DeviceInfo,
-    },
-    Touch {
-        x: u16,
-        y: u16,
-        timestamp: u128,
-        released: bool,
-        info: Option<DeviceInfo>,
-    },
-    Tick,
+/// The evdev side of the listeners, kept for a rescan
+struct EvdevListeners {
+    source: DeviceSource,
+    active_devices: Arc<Mutex<HashSet<String>>>,
+    spawn_listener: Box<dyn Fn(Device, DeviceInfo) + Send>,
+    // Devices per class at the last scan, for the rescan's diff
+    classes: BTreeMap<&'static str, usize>,
 }
 
-pub fn spawn_device_listeners(tx: &Sender<AppEvent>) -> Result<()> {
-    let devices = get_devices();
+fn start_evdev_listeners(
+    tx: &Sender<AppEvent>,
+    source: DeviceSource,
+    key_limit: RateLimit,
+    touch_keepalive: Option<Duration>,
+) -> Result<EvdevListeners> {
+    let devices = get_devices(&source).map_err(|e| unreadable_dir(&source, &e))?;
+    let classes = count_classes(devices.iter().map(|(_, info)| info));
 
     if devices.is_empty() {
         return Err(eyre!(
@@ -51,154 +98,319 @@ pub fn spawn_device_listeners(tx: &Sender<AppEvent>) -> Result<()> {
     // Track active device paths to avoid duplicate listeners
     let active_devices = Arc::new(Mutex::new(HashSet::new()));
 
+    // Shared between all listeners so nodes of one keyboard can be deduplicated
+    let key_dedup = Arc::new(Mutex::new(KeyDeduplicator::new()));
+
     // Spawn initial device listeners
     for (dev, info) in devices {
         let path = info.path.clone();
         if let Ok(mut set) = active_devices.lock() {
             set.insert(path.clone());
         }
-        spawn_device_listener(dev, info, tx.clone(), active_devices.clone());
+        spawn_device_listener(
+            dev,
+            info,
+            tx.clone(),
+            active_devices.clone(),
+            key_dedup.clone(),
+            key_limit,
+            touch_keepalive,
+        );
     }
 
     // Spawn hotswap monitor thread
     let tx_clone = tx.clone();
     let active_devices_clone = active_devices.clone();
+    let spawn_listener = {
+        let tx = tx.clone();
+        let active_devices = active_devices.clone();
+        move |dev, info| {
+            spawn_device_listener(
+                dev,
+                info,
+                tx.clone(),
+                active_devices.clone(),
+                key_dedup.clone(),
+                key_limit,
+                touch_keepalive,
+            )
+        }
+    };
+    let hotplug_source = source.clone();
+    let hotplug_listener = spawn_listener.clone();
     thread::spawn(move || {
-        hotswap_monitor(tx_clone, active_devices_clone);
+        hotplug::hotswap_monitor(
+            tx_clone,
+            active_devices_clone,
+            hotplug_source,
+            hotplug_listener,
+        );
     });
 
-    let tx_clone = tx.clone();
-    let _ = serial_touch::spawn_reader(tx_clone);
+    Ok(EvdevListeners {
+        source,
+        active_devices,
+        spawn_listener: Box::new(spawn_listener),
+        classes,
+    })
+}
+
+/// Devices per class, named for the rescan's diff
+fn count_classes<'a>(infos: impl Iterator<Item = &'a DeviceInfo>) -> BTreeMap<&'static str, usize> {
+    let mut classes = BTreeMap::new();
+    for info in infos {
+        let name = match info.class {
+            DeviceClass::Touchscreen => "touchscreen",
+            DeviceClass::Trackpad => "trackpad",
+            DeviceClass::Other => "other device",
+        };
+        *classes.entry(name).or_insert(0) += 1;
+    }
+    classes
+}
+
+/// The event sources that follow the hardware found at startup. Hotplug picks
+/// up evdev devices by itself, but the model and with it the serial reader are
+/// only decided once; `rescan` decides them again.
+pub struct HardwareSources {
+    tx: Sender<AppEvent>,
+    serial_baud_sweep: bool,
+    serial: Option<SerialReader>,
+    evdev: EvdevListeners,
+}
+
+impl HardwareSources {
+    /// Detect the model again, start or stop the serial reader to match it and
+    /// listen to devices no listener has yet. Returns what changed, e.g.
+    /// "+1 touchscreen, serial reader started", empty when nothing did.
+    pub fn rescan(&mut self) -> Vec<String> {
+        let mut changes = Vec::new();
+
+        let previous = get_computer_model();
+        let model = redetect_computer_model();
+        if model != previous {
+            changes.push(format!("model {previous:?} -> {model:?}"));
+        }
 
-    // Spawn timer thread for regular UI updates (needed for hold progress during calibration)
+        match (self.serial.take(), model.has_serial_touchscreen()) {
+            (Some(reader), false) => {
+                // The thread ends by itself, no need to hold the menu up for it
+                let _ = reader.stop();
+                changes.push("serial reader stopped".to_string());
+            }
+            (None, true) => {
+                match serial_touch::spawn_reader(self.tx.clone(), self.serial_baud_sweep) {
+                    Ok(reader) => {
+                        self.serial = Some(reader);
+                        changes.push("serial reader started".to_string());
+                    }
+                    Err(e) => changes.push(format!("serial reader not started: {e}")),
+                }
+            }
+            (reader, _) => self.serial = reader,
+        }
+
+        let evdev = &mut self.evdev;
+        let devices = match get_devices(&evdev.source) {
+            Ok(devices) => devices,
+            Err(e) => {
+                changes.push(format!("devices not rescanned: {e}"));
+                return changes;
+            }
+        };
+        let classes = count_classes(devices.iter().map(|(_, info)| info));
+        let names: BTreeSet<&str> = evdev
+            .classes
+            .keys()
+            .chain(classes.keys())
+            .copied()
+            .collect();
+        for name in names {
+            let before = evdev.classes.get(name).copied().unwrap_or(0) as i64;
+            let after = classes.get(name).copied().unwrap_or(0) as i64;
+            if after != before {
+                changes.push(format!("{:+} {name}", after - before));
+            }
+        }
+        evdev.classes = classes;
+
+        // Hotplug would get to them on its next poll
+        for (dev, info) in devices {
+            let new = evdev
+                .active_devices
+                .lock()
+                .is_ok_and(|mut active| active.insert(info.path.clone()));
+            if new {
+                (evdev.spawn_listener)(dev, info);
+            }
+        }
+
+        changes
+    }
+}
+
+/// Spawn timer thread for regular UI updates (needed for hold progress during calibration)
+pub fn spawn_tick_thread(tx: &Sender<AppEvent>) {
     let tx_timer = tx.clone();
-    thread::spawn(move || {
-        loop {
-            thread::sleep(Duration::from_millis(100)); // 10 times per second
-            let _ = tx_timer.send(AppEvent::Tick);
+    thread::Builder::new()
+        .name("tick".to_string())
+        .spawn(move || {
+            loop {
+                thread::sleep(Duration::from_millis(100)); // 10 times per second
+                watchdog::tick_sent();
+                let _ = tx_timer.send(AppEvent::Tick);
+            }
+        })
+        .expect("failed to spawn the tick thread");
+}
+
+/// Feed the events of `capture` through a `Listener` as if its device were
+/// attached, with the pauses they came with. The timestamps are moved to the
+/// replay's start, so the screens measure the same intervals on today's clock.
+pub fn spawn_capture_replay(tx: &Sender<AppEvent>, capture: Capture, key_limit: RateLimit) {
+    let tx = tx.clone();
+    let spawned = thread::Builder::new()
+        .name("replay".to_string())
+        .spawn(move || {
+            let header = &capture.header;
+            let info = Arc::new(header.device_info());
+            let mut state = Listener::new(
+                Arc::clone(&info),
+                header.reports_touch,
+                Arc::new(Mutex::new(KeyDeduplicator::new())),
+                key_limit,
+            );
+
+            let started = Instant::now();
+            let first_us = capture.events.first().map_or(0, |event| event.time_us);
+            let now_us = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_micros() as i64;
+            let shift_us = now_us - first_us as i64;
+            let mut out = Vec::new();
+            for event in &capture.events {
+                let due = Duration::from_micros(event.time_us.saturating_sub(first_us));
+                if let Some(wait) = due.checked_sub(started.elapsed()) {
+                    thread::sleep(wait);
+                }
+                watchdog::device_active(&info);
+                state.process(event.to_input(shift_us), &mut out);
+                for event in out.drain(..) {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        logging::warn(&format!("Cannot start the replay thread: {e}"));
+    }
+}
+
+/// Turn SIGHUP into `AppEvent::ConfigChanged`, the usual way to have a
+/// service read its config again
+pub fn spawn_reload_signal(tx: &Sender<AppEvent>) {
+    let mut signals = match Signals::new([SIGHUP]) {
+        Ok(signals) => signals,
+        Err(e) => {
+            logging::warn(&format!("Cannot watch for SIGHUP, no config reloads: {e}"));
+            return;
         }
-    });
+    };
+    let tx = tx.clone();
+    let spawned = thread::Builder::new()
+        .name("sighup".to_string())
+        .spawn(move || {
+            for _ in signals.forever() {
+                if tx.send(AppEvent::ConfigChanged).is_err() {
+                    break;
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        logging::warn(&format!("Cannot start the SIGHUP thread: {e}"));
+    }
+}
 
-    Ok(())
+/// Read the terminal's own input as `AppEvent::TerminalKey`. Nothing typed is
+/// left queued for the shell, and the keyboard test can tell when keys reach
+/// the terminal the way they shouldn't in raw mode.
+pub fn spawn_terminal_reader(tx: &Sender<AppEvent>) {
+    let tx = tx.clone();
+    let spawned = thread::Builder::new()
+        .name("terminal".to_string())
+        .spawn(move || {
+            // Without a terminal to read, read fails and the thread ends
+            while let Ok(event) = crossterm::event::read() {
+                if let crossterm::event::Event::Key(key) = event
+                    && key.kind == crossterm::event::KeyEventKind::Press
+                    && let crossterm::event::KeyCode::Char(c) = key.code
+                    && tx.send(AppEvent::TerminalKey { c }).is_err()
+                {
+                    break;
+                }
+            }
+        });
+    if let Err(e) = spawned {
+        logging::warn(&format!("Cannot start the terminal input thread: {e}"));
+    }
 }
 
+/// Read `dev` on its own thread until it goes away, feeding its events through
+/// a `Listener`
 fn spawn_device_listener(
     mut dev: Device,
     info: DeviceInfo,
     tx: Sender<AppEvent>,
     active_devices: Arc<Mutex<HashSet<String>>>,
+    key_dedup: Arc<Mutex<KeyDeduplicator>>,
+    key_limit: RateLimit,
+    touch_keepalive: Option<Duration>,
 ) {
+    // Every event of the device shares this one copy
+    let info = Arc::new(info);
     let path = info.path.clone();
-    thread::spawn(move || {
+    // Named after the node (e.g. "event3") so a stuck listener shows up in watchdog dumps
+    let thread_name = path.rsplit('/').next().unwrap_or("evdev").to_string();
+    let listener = thread::Builder::new().name(thread_name.clone());
+    let spawned = listener.spawn(move || {
         thread::sleep(Duration::from_millis(100)); // Allow some stagger time
+        listener_health::running(&info);
+
+        let reports_touch = dev
+            .supported_keys()
+            .is_some_and(|keys| keys.contains(KeyCode::BTN_TOUCH));
+        let mut state = Listener::new(Arc::clone(&info), reports_touch, key_dedup, key_limit);
+
+        // Controllers that only report changes go quiet under a resting finger,
+        // the keep-alive thread repeats the last sample meanwhile
+        let resting = Arc::new(Mutex::new(RestingTouch::default()));
+        if let Some(interval) = touch_keepalive
+            && info.class == DeviceClass::Touchscreen
+        {
+            spawn_touch_keepalive(
+                Arc::downgrade(&resting),
+                interval,
+                format!("{thread_name}-keepalive"),
+                Arc::clone(&info),
+                tx.clone(),
+            );
+        }
 
-        // USB touchscreen/stylus state tracking
-        let mut touch_x: u16 = 0;
-        let mut touch_y: u16 = 0;
-        let mut is_touching: bool = false; // Track whether stylus/finger is actually touching
-        #[allow(unused_assignments)]
-        let mut tool_in_range: bool = false; // Track whether tool (pen/finger) is in range
-        let mut coords_updated: bool = false; // Track if coordinates were updated in this event batch
+        // The kernel reports SYN_DROPPED when its buffer overflowed because we
+        // read too slowly. evdev discards the broken frame, re-reads key and axis
+        // state by ioctl and replays the differences as events ending in a
+        // SYN_REPORT, so the listener's touch state and coordinates are put right.
+        // The resync restamps the cached state, which is how a drop shows up here.
+        let mut synced_at = dev.cached_state().timestamp();
+        let mut drops: u32 = 0;
+        // Reused for every kernel event, see `Listener::process`
+        let mut out = Vec::new();
 
         loop {
-            match dev.fetch_events() {
-                Ok(events) => {
-                    for event in events {
-                        match event.destructure() {
-                            EventSummary::Key(_, code, value) => {
-                                // Handle various touch/stylus button codes
-                                match code {
-                                    // BTN_TOUCH: Actual contact with surface (both finger and stylus)
-                                    KeyCode::BTN_TOUCH => {
-                                        is_touching = value != 0;
-                                        if !is_touching {
-                                            // Released - send release event
-                                            // Note: Some devices send BTN_TOOL_* events, others don't
-                                            _ = tx.send(get_touch_event(
-                                                touch_x,
-                                                touch_y,
-                                                true,
-                                                Some(info.clone()),
-                                            ));
-                                        }
-                                    }
-                                    // BTN_TOOL_PEN, BTN_TOOL_FINGER: Tool in range but not necessarily touching
-                                    KeyCode::BTN_TOOL_PEN | KeyCode::BTN_TOOL_FINGER => {
-                                        tool_in_range = value != 0;
-                                        if !tool_in_range && is_touching {
-                                            // Tool left range - send release event
-                                            is_touching = false;
-                                            _ = tx.send(get_touch_event(
-                                                touch_x,
-                                                touch_y,
-                                                true,
-                                                Some(info.clone()),
-                                            ));
-                                        }
-                                    }
-                                    // Regular key presses (only on press, not release)
-                                    _ => {
-                                        if value == 1 {
-                                            _ = tx.send(AppEvent::Key {
-                                                code,
-                                                info: info.clone(),
-                                            });
-                                        }
-                                    }
-                                }
-                            }
-                            // Handle USB touchscreen/stylus absolute axis events
-                            EventSummary::AbsoluteAxis(_, abs_code, value) => match abs_code {
-                                evdev::AbsoluteAxisCode::ABS_X => {
-                                    touch_x = value as u16;
-                                    coords_updated = true;
-                                }
-                                evdev::AbsoluteAxisCode::ABS_Y => {
-                                    touch_y = value as u16;
-                                    coords_updated = true;
-                                }
-                                // Ignore other axis events (pressure, tilt, etc.)
-                                _ => {}
-                            },
-                            // EV_SYN marks the end of a complete event frame
-                            EventSummary::Synchronization(_, sync_code, _) => {
-                                if sync_code == evdev::SynchronizationCode::SYN_REPORT {
-                                    // Send touch event only once per complete frame, if coordinates changed
-                                    if is_touching && coords_updated {
-                                        _ = tx.send(get_touch_event(
-                                            touch_x,
-                                            touch_y,
-                                            false,
-                                            Some(info.clone()),
-                                        ));
-                                        coords_updated = false;
-                                    }
-                                }
-                            }
-                            // Handle mouse movement events
-                            EventSummary::RelativeAxis(_, rel_code, value) => {
-                                if rel_code == evdev::RelativeAxisCode::REL_X {
-                                    // X movement
-                                    _ = tx.send(AppEvent::Mouse {
-                                        x: value as i16,
-                                        y: 0,
-                                        info: info.clone(),
-                                    });
-                                } else if rel_code == evdev::RelativeAxisCode::REL_Y {
-                                    // Y movement
-                                    _ = tx.send(AppEvent::Mouse {
-                                        x: 0,
-                                        y: value as i16,
-                                        info: info.clone(),
-                                    });
-                                }
-                            }
-                            _ => {
-                                continue;
-                            }
-                        }
-                    }
-                }
+            let events = match dev.fetch_events() {
+                Ok(events) => events,
                 Err(e) => {
                     // Device disconnected or error occurred
                     // Error 19 (ENODEV - No such device) means device was unplugged
@@ -206,121 +418,52 @@ fn spawn_device_listener(
                         || e.kind() == std::io::ErrorKind::Other
                         || e.raw_os_error() == Some(19); // ENODEV
 
-                    if !is_disconnect {
-                        eprintln!("Error fetching events from device {}: {}", info.name, e);
+                    if is_disconnect {
+                        listener_health::removed(&info);
+                    } else {
+                        logging::warn(&format!(
+                            "Error fetching events from device {}: {}",
+                            info.name, e
+                        ));
+                        listener_health::exited(&info, e.kind());
                     }
                     // Remove from active devices set
                     if let Ok(mut set) = active_devices.lock() {
                         set.remove(&path);
                     }
+                    _ = tx.send(AppEvent::DeviceRemoved {
+                        info: Arc::clone(&info),
+                    });
                     break; // Exit the loop on error
                 }
-            }
-        }
-    });
-}
-
-fn get_touch_event(x: u16, y: u16, released: bool, info: Option<DeviceInfo>) -> AppEvent {
-    let timestamp = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
-
-    // For some reason, on the GPS touchpad, x is y and y is x
-    if get_computer_model() == ComputerModel::DatorBBFältGPS {
-        AppEvent::Touch {
-            x: y,
-            y: x,
-            timestamp,
-            released,
-            info,
-        }
-    } else {
-        AppEvent::Touch {
-            x,
-            y,
-            timestamp,
-            released,
-            info,
-        }
-    }
-}
-
-fn hotswap_monitor(tx: Sender<AppEvent>, active_devices: Arc<Mutex<HashSet<String>>>) {
-    loop {
-        thread::sleep(Duration::from_secs(2)); // Check every 2 seconds
-
-        let devices = get_devices();
-
-        for (dev, info) in devices {
-            let path = info.path.clone();
-
-            // Check if this device is already being monitored
-            let is_new = if let Ok(set) = active_devices.lock() {
-                !set.contains(&path)
-            } else {
-                false
             };
-
-            if is_new {
-                // New device detected, spawn listener for it
-                if let Ok(mut set) = active_devices.lock() {
-                    set.insert(path.clone());
+            watchdog::device_active(&info);
+            for event in events {
+                state.process(event, &mut out);
+                for event in out.drain(..) {
+                    if let AppEvent::Touch {
+                        keepalive: false, ..
+                    } = event
+                        && let Ok(mut resting) = resting.lock()
+                    {
+                        resting.sample = state.resting_sample();
+                        resting.sent = Instant::now();
+                    }
+                    _ = tx.send(event);
                 }
-                spawn_device_listener(dev, info, tx.clone(), active_devices.clone());
             }
-        }
-    }
-}
-
-fn get_devices() -> Vec<(Device, DeviceInfo)> {
-    let mut devices: Vec<(Device, DeviceInfo)> = vec![];
-
-    let dir = fs::read_dir("/dev/input").expect("Failed to read /dev/input directory");
-
-    for entry in dir.filter_map(Result::ok) {
-        if !entry.file_name().to_string_lossy().starts_with("event") {
-            continue;
-        }
-
-        match Device::open(entry.path()) {
-            Ok(device) => {
-                let name = device.name().unwrap_or("Unknown").to_string();
-
-                // Query absolute axis information for touchscreens/touchpads
-                let abs_x_max = device.get_abs_state().ok().and_then(|abs_state| {
-                    abs_state
-                        .get(evdev::AbsoluteAxisCode::ABS_X.0 as usize)
-                        .map(|info| info.maximum)
-                });
-
-                let abs_y_max = device.get_abs_state().ok().and_then(|abs_state| {
-                    abs_state
-                        .get(evdev::AbsoluteAxisCode::ABS_Y.0 as usize)
-                        .map(|info| info.maximum)
+            let resynced = dev.cached_state().timestamp();
+            if resynced != synced_at {
+                synced_at = resynced;
+                drops += 1;
+                _ = tx.send(AppEvent::EventsDropped {
+                    info: Arc::clone(&info),
+                    total: drops,
                 });
-
-                devices.push((
-                    device,
-                    DeviceInfo {
-                        path: entry.path().to_string_lossy().to_string(),
-                        name,
-                        abs_x_max,
-                        abs_y_max,
-                    },
-                ))
             }
-            Err(error) => {
-                // Ignore devices that cannot be opened
-                eprintln!(
-                    "Could not open device {}: {}",
-                    entry.path().to_string_lossy(),
-                    error
-                );
-                continue;
-            } // Skip devices that cannot be opened
         }
+    });
+    if let Err(e) = spawned {
+        logging::warn(&format!("Cannot start a listener thread: {e}"));
     }
-
-    return devices;
 }
@@ -0,0 +1,251 @@
+//! Finding the input devices: the event nodes of a directory, opened and
+//! described, filtered by the device source. The directory is part of the
+//! source, so tests and probes can point it somewhere else than /dev/input.
+
+use color_eyre::eyre::eyre;
+use evdev::Device;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::DeviceInfo;
+use crate::device_class::{self, Capabilities};
+use crate::device_ids::{self, SYS_CLASS_INPUT};
+use crate::logging;
+use crate::machine_detect::get_computer_model;
+
+/// Opened devices, and the nodes that failed to open with the error
+pub(crate) type DeviceScan = (Vec<(Device, DeviceInfo)>, Vec<(String, io::Error)>);
+
+pub type DeviceFilter = Arc<dyn Fn(&DeviceInfo) -> bool + Send + Sync>;
+
+/// Which input devices to listen to: the directory holding the event nodes and an
+/// optional predicate on the device identity. Lets the listeners be scoped to a
+/// known set of devices instead of everything on the machine.
+#[derive(Clone)]
+pub struct DeviceSource {
+    pub dir: PathBuf,
+    /// The sysfs class directory of the nodes, for their driver and firmware
+    /// identifiers, see `device_ids`
+    pub sysfs: PathBuf,
+    pub filter: Option<DeviceFilter>,
+}
+
+impl DeviceSource {
+    /// Every device under /dev/input
+    pub fn system() -> Self {
+        DeviceSource {
+            dir: PathBuf::from("/dev/input"),
+            sysfs: PathBuf::from(SYS_CLASS_INPUT),
+            filter: None,
+        }
+    }
+
+    pub fn with_filter(
+        mut self,
+        filter: impl Fn(&DeviceInfo) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.filter = Some(Arc::new(filter));
+        self
+    }
+
+    fn accepts(&self, info: &DeviceInfo) -> bool {
+        self.filter.as_ref().is_none_or(|filter| filter(info))
+    }
+}
+
+pub(super) fn get_devices(source: &DeviceSource) -> io::Result<Vec<(Device, DeviceInfo)>> {
+    let (devices, failures) = scan_devices(source)?;
+
+    // Ignore devices that cannot be opened
+    for (path, error) in failures {
+        logging::warn(&format!("Could not open device {path}: {error}"));
+    }
+
+    Ok(devices)
+}
+
+/// Why the device directory couldn't be listed, with what to do about it
+pub(super) fn unreadable_dir(source: &DeviceSource, error: &io::Error) -> color_eyre::Report {
+    let dir = source.dir.display();
+    match error.kind() {
+        io::ErrorKind::NotFound => eyre!(
+            "{dir} doesn't exist, no input devices in this environment (a container without /dev/input passed in?)"
+        ),
+        io::ErrorKind::PermissionDenied => eyre!(
+            "cannot list {dir}: permission denied, run as root or add the user to the input group"
+        ),
+        _ => eyre!("cannot list {dir}: {error}"),
+    }
+}
+
+/// Open every event node in `source` the filter accepts. Returns the devices and
+/// the nodes that couldn't be opened with the reason, usually missing permissions.
+pub(crate) fn scan_devices(source: &DeviceSource) -> io::Result<DeviceScan> {
+    let mut devices: Vec<(Device, DeviceInfo)> = vec![];
+    let mut failures: Vec<(String, io::Error)> = vec![];
+
+    let dir = fs::read_dir(&source.dir)?;
+
+    for entry in dir.filter_map(Result::ok) {
+        if !entry.file_name().to_string_lossy().starts_with("event") {
+            continue;
+        }
+
+        match Device::open(entry.path()) {
+            Ok(device) => {
+                let name = device.name().unwrap_or("Unknown").to_string();
+
+                // Query absolute axis information for touchscreens/touchpads
+                let abs_x_max = device.get_abs_state().ok().and_then(|abs_state| {
+                    abs_state
+                        .get(evdev::AbsoluteAxisCode::ABS_X.0 as usize)
+                        .map(|info| info.maximum)
+                });
+
+                let abs_y_max = device.get_abs_state().ok().and_then(|abs_state| {
+                    abs_state
+                        .get(evdev::AbsoluteAxisCode::ABS_Y.0 as usize)
+                        .map(|info| info.maximum)
+                });
+
+                // Only trust the pressure range if the axis is actually advertised
+                let pressure_max = device
+                    .supported_absolute_axes()
+                    .is_some_and(|axes| axes.contains(evdev::AbsoluteAxisCode::ABS_PRESSURE))
+                    .then(|| {
+                        device.get_abs_state().ok().and_then(|abs_state| {
+                            abs_state
+                                .get(evdev::AbsoluteAxisCode::ABS_PRESSURE.0 as usize)
+                                .map(|info| info.maximum)
+                        })
+                    })
+                    .flatten();
+
+                // Drivers that don't know the panel size report a resolution of 0
+                let resolution = device.get_abs_state().ok().and_then(|abs_state| {
+                    let x = abs_state.get(evdev::AbsoluteAxisCode::ABS_X.0 as usize)?;
+                    let y = abs_state.get(evdev::AbsoluteAxisCode::ABS_Y.0 as usize)?;
+                    (x.resolution > 0 && y.resolution > 0).then_some((x.resolution, y.resolution))
+                });
+
+                let multitouch = device
+                    .supported_absolute_axes()
+                    .is_some_and(|axes| axes.contains(evdev::AbsoluteAxisCode::ABS_MT_POSITION_X));
+
+                let info = DeviceInfo {
+                    path: entry.path().to_string_lossy().to_string(),
+                    name,
+                    abs_x_max,
+                    abs_y_max,
+                    multitouch,
+                    phys: device.physical_path().map(str::to_string),
+                    vendor: device.input_id().vendor(),
+                    product: device.input_id().product(),
+                    bus: device.input_id().bus_type(),
+                    pressure_max,
+                    resolution,
+                    class: device_class::classify(&Capabilities::of(&device)),
+                    learned_range: None,
+                    swapped_axes: get_computer_model().swaps_touch_axes(),
+                    ids: Arc::new(device_ids::read(
+                        &source.sysfs,
+                        &entry.file_name().to_string_lossy(),
+                    )),
+                };
+
+                if source.accepts(&info) {
+                    devices.push((device, info));
+                }
+            }
+            Err(error) => {
+                failures.push((entry.path().to_string_lossy().to_string(), error));
+            }
+        }
+    }
+
+    Ok((devices, failures))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::*;
+
+    fn source(dir: PathBuf) -> DeviceSource {
+        DeviceSource {
+            dir,
+            sysfs: PathBuf::from("/nonexistent"),
+            filter: None,
+        }
+    }
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(what: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!(
+                "keyboard_test-devices-{what}-{}",
+                std::process::id()
+            ));
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::set_permissions(&self.0, fs::Permissions::from_mode(0o755));
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn a_missing_directory_is_an_error_saying_so() {
+        let source = source(PathBuf::from("/nonexistent/input"));
+        let error = get_devices(&source).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::NotFound);
+        assert!(
+            unreadable_dir(&source, &error)
+                .to_string()
+                .starts_with("/nonexistent/input doesn't exist")
+        );
+    }
+
+    #[test]
+    fn an_unlistable_directory_is_an_error_naming_the_permissions() {
+        let dir = TempDir::new("locked");
+        let source = source(dir.0.clone());
+        let denied = io::Error::from(io::ErrorKind::PermissionDenied);
+        assert!(
+            unreadable_dir(&source, &denied)
+                .to_string()
+                .ends_with("permission denied, run as root or add the user to the input group")
+        );
+
+        fs::set_permissions(&dir.0, fs::Permissions::from_mode(0o000)).unwrap();
+        // Root lists it anyway, there's nothing to refuse then
+        if unsafe { libc::geteuid() } == 0 {
+            assert!(get_devices(&source).unwrap().is_empty());
+            return;
+        }
+        let error = get_devices(&source).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::PermissionDenied);
+    }
+
+    #[test]
+    fn nodes_that_dont_open_are_skipped() {
+        let dir = TempDir::new("nodes");
+        // Not a device, the open fails on the ioctls
+        fs::write(dir.0.join("event0"), "").unwrap();
+        fs::write(dir.0.join("mouse0"), "").unwrap();
+
+        let (devices, failures) = scan_devices(&source(dir.0.clone())).unwrap();
+        assert!(devices.is_empty());
+        let failed: Vec<&str> = failures.iter().map(|(path, _)| path.as_str()).collect();
+        assert_eq!(failed, [dir.0.join("event0").to_string_lossy()]);
+        assert!(get_devices(&source(dir.0.clone())).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,186 @@
+//! What the device listeners, the serial reader and the tick thread send the
+//! screens, and the identity of the device attached to it.
+
+use evdev::{BusType, KeyCode};
+use std::sync::Arc;
+
+use crate::device_class::DeviceClass;
+use crate::device_ids::DeviceIds;
+use crate::saturation::AxisLimits;
+
+/// Identity and axis ranges of an input device. Its events share one copy
+/// behind an `Arc`, built when the device was opened.
+#[derive(Debug, Clone)]
+pub struct DeviceInfo {
+    pub path: String,
+    pub name: String,
+    pub abs_x_max: Option<i32>,
+    pub abs_y_max: Option<i32>,
+    pub multitouch: bool,
+    /// Physical path (e.g. "usb-0000:00:14.0-1/input0"), shared prefix across nodes of one device
+    pub phys: Option<String>,
+    pub vendor: u16,
+    pub product: u16,
+    /// Bus the device is connected through, e.g. USB or i8042 (PS/2)
+    pub bus: BusType,
+    /// Maximum of the ABS_PRESSURE axis, None if the device doesn't report pressure
+    pub pressure_max: Option<i32>,
+    /// Resolution of the X and Y axes in units per millimetre, None if not reported
+    pub resolution: Option<(i32, i32)>,
+    /// Touchscreen, trackpad or neither, decides which events its touches become
+    pub class: DeviceClass,
+    /// Raw range seen in the samples, for panels that don't report one (the
+    /// serial panel), see `range_learning`
+    pub learned_range: Option<AxisLimits>,
+    /// The model profile says the panel reports X as Y and Y as X (the GPS
+    /// panel). Events carry the raw axes; the hint seeds the touchscreen
+    /// calibration, which detects the orientation itself, and places touches
+    /// on screens without one
+    pub swapped_axes: bool,
+    /// Driver and firmware identifiers from sysfs, shared with the copies
+    /// screens keep of a device they picked
+    pub ids: Arc<DeviceIds>,
+}
+
+/// A single active contact in a multi-touch frame
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TouchContact {
+    pub slot: usize,
+    pub x: u16,
+    pub y: u16,
+    /// The device flagged the contact as a palm (ABS_MT_TOOL_TYPE = MT_TOOL_PALM)
+    pub palm: bool,
+}
+
+// Upper bound on tracked multi-touch slots; panels we ship report at most 10
+pub const MAX_TOUCH_SLOTS: usize = 10;
+
+/// Everything the screens react to, sent by the device listeners and the tick thread
+#[derive(Debug, Clone)]
+pub enum AppEvent {
+    Key {
+        code: KeyCode,
+        info: Arc<DeviceInfo>,
+        /// Autorepeat of a held key rather than a new press
+        repeat: bool,
+        /// Unix time in ms the kernel stamped the event with
+        timestamp: u128,
+    },
+    Mouse {
+        x: i16,
+        y: i16,
+        info: Arc<DeviceInfo>,
+    },
+    Touch {
+        x: u16,
+        y: u16,
+        timestamp: u128,
+        released: bool,
+        /// Raw ABS_PRESSURE value of the contact, if the device reports one
+        pressure: Option<i32>,
+        info: Option<Arc<DeviceInfo>>,
+        /// Repeat of the last sample while the finger rests without moving, see
+        /// `--touch-keepalive`. Not a new sample of the panel.
+        keepalive: bool,
+    },
+    MultiTouch {
+        contacts: Vec<TouchContact>,
+        timestamp: u128,
+        info: Arc<DeviceInfo>,
+    },
+    /// Fingers on a trackpad-class device, kept apart from touchscreen events so
+    /// a trackpad can't be calibrated as a panel by accident
+    Trackpad {
+        contacts: Vec<TouchContact>,
+        /// The pad senses a finger at all (BTN_TOUCH). A contact left over while
+        /// this is false is a slot whose lift was missed.
+        touching: bool,
+        timestamp: u128,
+        info: Arc<DeviceInfo>,
+    },
+    /// A pen over a digitizer without touching it, raw coordinates like
+    /// `Touch`. Sent when the hover position changes and once with `left` when
+    /// the pen goes out of range.
+    PenHover {
+        x: u16,
+        y: u16,
+        left: bool,
+        info: Arc<DeviceInfo>,
+    },
+    /// A key came up. Only screens that track held keys look at these.
+    KeyReleased {
+        code: KeyCode,
+        info: Arc<DeviceInfo>,
+        timestamp: u128,
+    },
+    /// A device misbehaves badly enough that its events are being dropped
+    DeviceFault {
+        info: Arc<DeviceInfo>,
+        reason: String,
+    },
+    /// The kernel dropped events of a device because they weren't read in time
+    /// (SYN_DROPPED). Its state was re-read, but strokes may have gaps, and
+    /// frequent drops mean this program is too slow to keep up.
+    EventsDropped {
+        info: Arc<DeviceInfo>,
+        /// Drops on the device since its listener started
+        total: u32,
+    },
+    /// Baud rate the serial touch reader settled on and the share of bytes that
+    /// made valid frames at it
+    SerialStatus {
+        info: Arc<DeviceInfo>,
+        baud: u32,
+        valid_ratio: f32,
+        /// The configured rate was rejected and the rates were swept
+        swept: bool,
+    },
+    /// A device showed up after startup
+    DeviceAdded {
+        info: Arc<DeviceInfo>,
+    },
+    /// A device disappeared, e.g. unplugged or its unit swapped out
+    DeviceRemoved {
+        info: Arc<DeviceInfo>,
+    },
+    /// What is known about a device changed, e.g. its learned range grew
+    DeviceUpdated {
+        info: Arc<DeviceInfo>,
+    },
+    /// A character typed into the terminal itself. Raw mode hands keys over
+    /// one at a time; see `terminal_echo` for what it means when keys of the
+    /// tested keyboard show up here held back.
+    TerminalKey {
+        c: char,
+    },
+    /// The config files were changed and should be read again (SIGHUP)
+    ConfigChanged,
+    /// Probe of the startup self-check, never reaches a screen
+    SelfCheck,
+    Tick,
+}
+
+impl AppEvent {
+    /// The device the event came from, if it carries one
+    pub fn device(&self) -> Option<&DeviceInfo> {
+        match self {
+            AppEvent::Key { info, .. }
+            | AppEvent::KeyReleased { info, .. }
+            | AppEvent::Mouse { info, .. }
+            | AppEvent::MultiTouch { info, .. }
+            | AppEvent::Trackpad { info, .. }
+            | AppEvent::PenHover { info, .. }
+            | AppEvent::DeviceFault { info, .. }
+            | AppEvent::EventsDropped { info, .. }
+            | AppEvent::SerialStatus { info, .. }
+            | AppEvent::DeviceAdded { info }
+            | AppEvent::DeviceRemoved { info }
+            | AppEvent::DeviceUpdated { info } => Some(info),
+            AppEvent::Touch { info, .. } => info.as_deref(),
+            AppEvent::TerminalKey { .. }
+            | AppEvent::ConfigChanged
+            | AppEvent::SelfCheck
+            | AppEvent::Tick => None,
+        }
+    }
+}
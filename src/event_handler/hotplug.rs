@@ -0,0 +1,118 @@
+//! Devices plugged in after startup. The device directory is scanned every
+//! couple of seconds, or right away when a dead listener is restarted by hand,
+//! and every node without a listener gets one.
+
+use crossbeam_channel::Sender;
+use evdev::Device;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::devices::{DeviceSource, get_devices, unreadable_dir};
+use super::{AppEvent, DeviceInfo};
+use crate::listener_health;
+use crate::logging;
+
+// How often the hotplug monitor looks for new devices
+const HOTSWAP_INTERVAL: Duration = Duration::from_secs(2);
+// Longest wait between scans while the device directory can't be read, the
+// interval doubles up to it
+const HOTSWAP_MAX_BACKOFF: Duration = Duration::from_secs(60);
+// How soon a restart asked for by hand is picked up
+const RESTART_POLL: Duration = Duration::from_millis(100);
+
+/// Watch `source` for devices missing from `active_devices`, announce each
+/// with `DeviceAdded` and hand it to `spawn_listener`. Runs until the process
+/// ends.
+pub(super) fn hotswap_monitor(
+    tx: Sender<AppEvent>,
+    active_devices: Arc<Mutex<HashSet<String>>>,
+    source: DeviceSource,
+    spawn_listener: impl Fn(Device, DeviceInfo),
+) {
+    let mut last_scan = Instant::now();
+    // Grows while the directory can't be read, back to HOTSWAP_INTERVAL once it can
+    let mut interval = HOTSWAP_INTERVAL;
+    loop {
+        thread::sleep(RESTART_POLL);
+
+        // Check every 2 seconds, or right away when a dead listener is restarted by hand
+        // Reopening is all a restart takes, the dead listener's path is no longer active
+        let restart = listener_health::take_restart();
+        if !restart && last_scan.elapsed() < interval {
+            continue;
+        }
+        last_scan = Instant::now();
+
+        let devices = match get_devices(&source) {
+            Ok(devices) => devices,
+            Err(e) => {
+                // Logged when the directory goes away, not on every retry
+                if interval == HOTSWAP_INTERVAL {
+                    logging::warn(&format!(
+                        "{}, retrying with longer intervals",
+                        unreadable_dir(&source, &e)
+                    ));
+                }
+                interval = next_interval(interval, false);
+                continue;
+            }
+        };
+        if interval != HOTSWAP_INTERVAL {
+            logging::warn(&format!("{} readable again", source.dir.display()));
+        }
+        interval = next_interval(interval, true);
+
+        for (dev, info) in devices {
+            let path = info.path.clone();
+
+            // Check if this device is already being monitored
+            let is_new = if let Ok(set) = active_devices.lock() {
+                !set.contains(&path)
+            } else {
+                false
+            };
+
+            if is_new {
+                // New device detected, spawn listener for it
+                if let Ok(mut set) = active_devices.lock() {
+                    set.insert(path.clone());
+                }
+                _ = tx.send(AppEvent::DeviceAdded {
+                    info: Arc::new(info.clone()),
+                });
+                spawn_listener(dev, info);
+            }
+        }
+    }
+}
+
+/// The wait before the next scan, after one that could (`readable`) or
+/// couldn't list the device directory
+fn next_interval(interval: Duration, readable: bool) -> Duration {
+    if readable {
+        HOTSWAP_INTERVAL
+    } else {
+        (interval * 2).min(HOTSWAP_MAX_BACKOFF)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_interval_doubles_up_to_a_minute_while_unreadable() {
+        let mut interval = HOTSWAP_INTERVAL;
+        let mut waits = Vec::new();
+        for _ in 0..7 {
+            interval = next_interval(interval, false);
+            waits.push(interval.as_secs());
+        }
+        assert_eq!(waits, [4, 8, 16, 32, 60, 60, 60]);
+
+        assert_eq!(next_interval(interval, true), HOTSWAP_INTERVAL);
+        assert_eq!(next_interval(HOTSWAP_INTERVAL, true), HOTSWAP_INTERVAL);
+    }
+}
@@ -0,0 +1,1211 @@
+//! The state machine of one evdev device. Key presses go through the node
+//! deduplication and the flood limit, BTN_TOUCH and the axes are assembled
+//! into touch frames on SYN_REPORT, multi-touch slots into contact lists, a
+//! hovering pen into hover events, relative axes into mouse motion.
+//! `Listener::process` takes one kernel event and adds what goes to the
+//! screens to a buffer, the thread reading the device lives in `event_handler`.
+
+use crossbeam_channel::Sender;
+use evdev::{EventSummary, InputEvent, KeyCode};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::{AppEvent, DeviceInfo, MAX_TOUCH_SLOTS, TouchContact};
+use crate::device_class::DeviceClass;
+use crate::key_dedup::KeyDeduplicator;
+use crate::key_rate::{KeyRateLimiter, RateLimit, RateVerdict};
+use crate::logging;
+
+/// Time after which a resting touch gets its last sample repeated
+pub const DEFAULT_TOUCH_KEEPALIVE: Duration = Duration::from_millis(100);
+
+// ABS_MT_TOOL_TYPE value for a palm, from linux/input.h
+const MT_TOOL_PALM: i32 = 2;
+
+/// Touch, multi-touch and key state of one device between kernel events
+pub struct Listener {
+    // Shared with every event the device sends, built once when it was opened
+    info: Arc<DeviceInfo>,
+    // Pads without BTN_TOUCH count as touching while any slot holds a contact
+    reports_touch: bool,
+    // Shared between all listeners so nodes of one keyboard can be deduplicated
+    key_dedup: Arc<Mutex<KeyDeduplicator>>,
+    // Flood protection, per listener so one broken keyboard can't silence another
+    key_rate: KeyRateLimiter,
+
+    // USB touchscreen/stylus state tracking
+    touch_x: u16,
+    touch_y: u16,
+    is_touching: bool,           // Track whether stylus/finger is actually touching
+    pen_in_range: bool,          // BTN_TOOL_PEN, the pen hovers while it's up without contact
+    pen_left: bool,              // Pen went out of range in this event batch
+    coords_updated: bool,        // Track if coordinates were updated in this event batch
+    touch_changed: bool,         // Touch went down or up in this event batch
+    touch_pressure: Option<i32>, // Last ABS_PRESSURE value, if the device has one
+
+    // Multi-touch (protocol B) slot tracking
+    mt_slot: usize,
+    mt_slots: [Option<TouchContact>; MAX_TOUCH_SLOTS],
+    mt_updated: bool,
+}
+
+impl Listener {
+    pub fn new(
+        info: Arc<DeviceInfo>,
+        reports_touch: bool,
+        key_dedup: Arc<Mutex<KeyDeduplicator>>,
+        key_limit: RateLimit,
+    ) -> Self {
+        Listener {
+            info,
+            reports_touch,
+            key_dedup,
+            key_rate: KeyRateLimiter::new(key_limit),
+            touch_x: 0,
+            touch_y: 0,
+            is_touching: false,
+            pen_in_range: false,
+            pen_left: false,
+            coords_updated: false,
+            touch_changed: false,
+            touch_pressure: None,
+            mt_slot: 0,
+            mt_slots: [None; MAX_TOUCH_SLOTS],
+            mt_updated: false,
+        }
+    }
+
+    /// Raw sample of the touch that is down, for the keep-alive repeats
+    pub fn resting_sample(&self) -> Option<(u16, u16, Option<i32>)> {
+        self.is_touching
+            .then_some((self.touch_x, self.touch_y, self.touch_pressure))
+    }
+
+    /// Feed one kernel event, adding the events it completes to `out`. Most
+    /// events only change the state, a SYN_REPORT sends the frame they made up.
+    /// The caller drains `out` and reuses it, a touch drag sends thousands of
+    /// frames a second.
+    pub fn process(&mut self, event: InputEvent, out: &mut Vec<AppEvent>) {
+        match event.destructure() {
+            EventSummary::Key(_, code, value) => self.key(code, value, event, out),
+            EventSummary::AbsoluteAxis(_, abs_code, value) => self.axis(abs_code, value),
+            // EV_SYN marks the end of a complete event frame
+            EventSummary::Synchronization(_, evdev::SynchronizationCode::SYN_REPORT, _) => {
+                self.report(out)
+            }
+            // Handle mouse movement events
+            EventSummary::RelativeAxis(_, rel_code, value) => {
+                if rel_code == evdev::RelativeAxisCode::REL_X {
+                    // X movement
+                    out.push(AppEvent::Mouse {
+                        x: value as i16,
+                        y: 0,
+                        info: Arc::clone(&self.info),
+                    });
+                } else if rel_code == evdev::RelativeAxisCode::REL_Y {
+                    // Y movement
+                    out.push(AppEvent::Mouse {
+                        x: 0,
+                        y: value as i16,
+                        info: Arc::clone(&self.info),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Handle various touch/stylus button codes and regular keys
+    fn key(&mut self, code: KeyCode, value: i32, event: InputEvent, out: &mut Vec<AppEvent>) {
+        let info = &self.info;
+        match code {
+            // BTN_TOUCH: Actual contact with surface (both finger and stylus)
+            KeyCode::BTN_TOUCH => {
+                // The press or release goes out on SYN_REPORT, with the
+                // coordinates of this frame or else the last known ones.
+                // A tap on the same spot as the last one has no ABS events.
+                self.touch_changed |= (value != 0) != self.is_touching;
+                self.is_touching = value != 0;
+                // Trackpad frames carry the touch state too
+                self.mt_updated |= info.multitouch && info.class == DeviceClass::Trackpad;
+            }
+            // BTN_TOOL_PEN, BTN_TOOL_FINGER: Tool in range but not necessarily touching
+            KeyCode::BTN_TOOL_PEN | KeyCode::BTN_TOOL_FINGER => {
+                let tool_in_range = value != 0;
+                if code == KeyCode::BTN_TOOL_PEN {
+                    self.pen_left |= self.pen_in_range && !tool_in_range;
+                    self.pen_in_range = tool_in_range;
+                }
+                if !tool_in_range && self.is_touching {
+                    // Tool left range - released on SYN_REPORT
+                    // Note: Some devices send BTN_TOOL_* events, others don't
+                    self.is_touching = false;
+                    self.touch_changed = true;
+                }
+            }
+            // Regular key presses, autorepeat and releases
+            _ => {
+                // Another node of the same keyboard may already have sent this press
+                let is_duplicate = self
+                    .key_dedup
+                    .lock()
+                    .is_ok_and(|mut dedup| !dedup.accept(info, code, value, event.timestamp()));
+                if is_duplicate {
+                    return;
+                }
+
+                let timestamp = unix_millis(event.timestamp());
+                if value == 1 {
+                    match self.key_rate.check(code, event.timestamp()) {
+                        RateVerdict::Pass => out.push(AppEvent::Key {
+                            code,
+                            info: Arc::clone(info),
+                            repeat: false,
+                            timestamp,
+                        }),
+                        RateVerdict::Drop => {}
+                        RateVerdict::Fault { rate, code } => out.push(AppEvent::DeviceFault {
+                            info: Arc::clone(info),
+                            reason: format!(
+                                "{code:?} flooding at {rate} presses/s, events throttled"
+                            ),
+                        }),
+                    }
+                } else if value == 2 {
+                    // Repeats aren't presses, so they don't count towards the rate
+                    out.push(AppEvent::Key {
+                        code,
+                        info: Arc::clone(info),
+                        repeat: true,
+                        timestamp,
+                    });
+                } else if value == 0 {
+                    out.push(AppEvent::KeyReleased {
+                        code,
+                        info: Arc::clone(info),
+                        timestamp,
+                    });
+                }
+            }
+        }
+    }
+
+    /// Handle USB touchscreen/stylus absolute axis events
+    fn axis(&mut self, abs_code: evdev::AbsoluteAxisCode, value: i32) {
+        match abs_code {
+            evdev::AbsoluteAxisCode::ABS_X => {
+                self.touch_x = value as u16;
+                self.coords_updated = true;
+            }
+            evdev::AbsoluteAxisCode::ABS_Y => {
+                self.touch_y = value as u16;
+                self.coords_updated = true;
+            }
+            evdev::AbsoluteAxisCode::ABS_MT_SLOT => {
+                self.mt_slot = (value.max(0) as usize).min(MAX_TOUCH_SLOTS - 1);
+            }
+            evdev::AbsoluteAxisCode::ABS_MT_TRACKING_ID => {
+                // -1 means the contact in this slot was lifted
+                if value < 0 {
+                    self.mt_slots[self.mt_slot] = None;
+                } else if self.mt_slots[self.mt_slot].is_none() {
+                    self.mt_slots[self.mt_slot] = Some(TouchContact {
+                        slot: self.mt_slot,
+                        x: 0,
+                        y: 0,
+                        palm: false,
+                    });
+                }
+                self.mt_updated = true;
+            }
+            evdev::AbsoluteAxisCode::ABS_MT_POSITION_X => {
+                if let Some(contact) = self.mt_slots[self.mt_slot].as_mut() {
+                    contact.x = value as u16;
+                    self.mt_updated = true;
+                }
+            }
+            evdev::AbsoluteAxisCode::ABS_MT_POSITION_Y => {
+                if let Some(contact) = self.mt_slots[self.mt_slot].as_mut() {
+                    contact.y = value as u16;
+                    self.mt_updated = true;
+                }
+            }
+            evdev::AbsoluteAxisCode::ABS_MT_TOOL_TYPE => {
+                if let Some(contact) = self.mt_slots[self.mt_slot].as_mut() {
+                    contact.palm = value == MT_TOOL_PALM;
+                    self.mt_updated = true;
+                }
+            }
+            evdev::AbsoluteAxisCode::ABS_PRESSURE => {
+                self.touch_pressure = Some(value);
+                self.coords_updated = true;
+            }
+            // Ignore other axis events (tilt, etc.)
+            _ => {}
+        }
+    }
+
+    /// The frame is complete, send what changed in it
+    fn report(&mut self, out: &mut Vec<AppEvent>) {
+        // Send touch event only once per complete frame, if coordinates
+        // changed or the touch went down or up
+        if (self.is_touching && self.coords_updated) || self.touch_changed {
+            out.extend(single_touch_event(
+                self.touch_x,
+                self.touch_y,
+                !self.is_touching,
+                self.touch_pressure,
+                &self.info,
+                false,
+            ));
+        }
+        // Hovering pens are sent for the hover-to-touch offset
+        if self.info.class == DeviceClass::Touchscreen
+            && ((self.pen_in_range && !self.is_touching && self.coords_updated) || self.pen_left)
+        {
+            out.push(AppEvent::PenHover {
+                x: self.touch_x,
+                y: self.touch_y,
+                left: self.pen_left,
+                info: Arc::clone(&self.info),
+            });
+        }
+        self.pen_left = false;
+        self.coords_updated = false;
+        self.touch_changed = false;
+
+        // Multi-touch frames are sent whenever any slot changed
+        if self.mt_updated {
+            let contacts: Vec<TouchContact> = self.mt_slots.iter().flatten().copied().collect();
+            let touching = if self.reports_touch {
+                self.is_touching
+            } else {
+                !contacts.is_empty()
+            };
+            out.push(get_multitouch_event(
+                contacts,
+                touching,
+                Arc::clone(&self.info),
+            ));
+            self.mt_updated = false;
+        }
+    }
+}
+
+/// Last sample of a touch that is down, for the keep-alive thread
+pub(super) struct RestingTouch {
+    pub sample: Option<(u16, u16, Option<i32>)>,
+    pub sent: Instant,
+}
+
+impl Default for RestingTouch {
+    fn default() -> Self {
+        RestingTouch {
+            sample: None,
+            sent: Instant::now(),
+        }
+    }
+}
+
+/// Repeat the last sample of a touch that is down every `interval` without a new
+/// one, so calibration holds don't depend on coordinate noise. Ends with the
+/// listener owning `resting`.
+pub(super) fn spawn_touch_keepalive(
+    resting: Weak<Mutex<RestingTouch>>,
+    interval: Duration,
+    name: String,
+    info: Arc<DeviceInfo>,
+    tx: Sender<AppEvent>,
+) {
+    let spawned = thread::Builder::new().name(name).spawn(move || {
+        loop {
+            thread::sleep(interval / 2);
+            let Some(resting) = resting.upgrade() else {
+                break;
+            };
+            let Ok(mut resting) = resting.lock() else {
+                break;
+            };
+            if let Some((x, y, pressure)) = resting.sample
+                && resting.sent.elapsed() >= interval
+            {
+                if let Some(event) = single_touch_event(x, y, false, pressure, &info, true)
+                    && tx.send(event).is_err()
+                {
+                    break;
+                }
+                resting.sent = Instant::now();
+            }
+        }
+    });
+    if let Err(e) = spawned {
+        logging::warn(&format!("Cannot start a touch keep-alive thread: {e}"));
+    }
+}
+
+// Kernel event time as unix ms, the scale the touch events use
+fn unix_millis(time: std::time::SystemTime) -> u128 {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+fn get_touch_event(
+    x: u16,
+    y: u16,
+    released: bool,
+    pressure: Option<i32>,
+    info: Option<Arc<DeviceInfo>>,
+    keepalive: bool,
+) -> AppEvent {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    AppEvent::Touch {
+        x,
+        y,
+        timestamp,
+        released,
+        pressure,
+        info,
+        keepalive,
+    }
+}
+
+/// The event for a single touch sample: a touchscreen sample, or a one finger
+/// frame for a trackpad without multi-touch slots. None for trackpads with
+/// slots, whose frames come from those.
+fn single_touch_event(
+    x: u16,
+    y: u16,
+    released: bool,
+    pressure: Option<i32>,
+    info: &Arc<DeviceInfo>,
+    keepalive: bool,
+) -> Option<AppEvent> {
+    match info.class {
+        DeviceClass::Trackpad if info.multitouch => None,
+        DeviceClass::Trackpad => {
+            let contacts = if released {
+                vec![]
+            } else {
+                vec![TouchContact {
+                    slot: 0,
+                    x,
+                    y,
+                    palm: false,
+                }]
+            };
+            Some(get_multitouch_event(contacts, !released, Arc::clone(info)))
+        }
+        _ => Some(get_touch_event(
+            x,
+            y,
+            released,
+            pressure,
+            Some(Arc::clone(info)),
+            keepalive,
+        )),
+    }
+}
+
+/// A multi-touch frame, routed to the trackpad event for trackpad-class devices
+fn get_multitouch_event(
+    contacts: Vec<TouchContact>,
+    touching: bool,
+    info: Arc<DeviceInfo>,
+) -> AppEvent {
+    let timestamp = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    if info.class == DeviceClass::Trackpad {
+        AppEvent::Trackpad {
+            contacts,
+            touching,
+            timestamp,
+            info,
+        }
+    } else {
+        AppEvent::MultiTouch {
+            contacts,
+            timestamp,
+            info,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::cell::Cell;
+
+    use evdev::{AbsoluteAxisCode, EventType, SynchronizationCode};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::capture::CapturedEvent;
+    use crate::ghost_keys::GhostKeys;
+    use crate::sim::sim_device;
+    use crate::simultaneous_keys::SimultaneousKeys;
+
+    /// A kernel event stamped `ms` after the epoch
+    fn event(type_: EventType, code: u16, value: i32, ms: u64) -> InputEvent {
+        CapturedEvent {
+            time_us: ms * 1000,
+            type_: type_.0,
+            code,
+            value,
+        }
+        .to_input(0)
+    }
+
+    fn key(code: KeyCode, value: i32, ms: u64) -> InputEvent {
+        event(EventType::KEY, code.0, value, ms)
+    }
+
+    fn abs(code: AbsoluteAxisCode, value: i32) -> InputEvent {
+        event(EventType::ABSOLUTE, code.0, value, 0)
+    }
+
+    fn syn() -> InputEvent {
+        event(
+            EventType::SYNCHRONIZATION,
+            SynchronizationCode::SYN_REPORT.0,
+            0,
+            0,
+        )
+    }
+
+    fn listener(info: DeviceInfo, dedup: &Arc<Mutex<KeyDeduplicator>>) -> Listener {
+        Listener::new(
+            Arc::new(info),
+            true,
+            Arc::clone(dedup),
+            RateLimit::default(),
+        )
+    }
+
+    fn touchscreen(multitouch: bool) -> DeviceInfo {
+        DeviceInfo {
+            class: DeviceClass::Touchscreen,
+            multitouch,
+            ..sim_device("/dev/input/event7", "Property panel", Some(4095))
+        }
+    }
+
+    fn trackpad(multitouch: bool) -> DeviceInfo {
+        DeviceInfo {
+            class: DeviceClass::Trackpad,
+            multitouch,
+            ..sim_device("/dev/input/event9", "Direct trackpad", Some(1500))
+        }
+    }
+
+    /// What `events` sent, the frames closed with SYN_REPORT by the caller
+    fn run(listener: &mut Listener, events: &[InputEvent]) -> Vec<AppEvent> {
+        let mut out = Vec::new();
+        for event in events {
+            listener.process(*event, &mut out);
+        }
+        out
+    }
+
+    /// (x, y, released) of the touches in `events`, hovers and others left out
+    fn touches(events: &[AppEvent]) -> Vec<(u16, u16, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                AppEvent::Touch { x, y, released, .. } => Some((*x, *y, *released)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// (x, y, left) of the pen hovers in `events`
+    fn hovers(events: &[AppEvent]) -> Vec<(u16, u16, bool)> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                AppEvent::PenHover { x, y, left, .. } => Some((*x, *y, *left)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn direct(info: DeviceInfo) -> Listener {
+        listener(info, &Arc::new(Mutex::new(KeyDeduplicator::new())))
+    }
+
+    #[test]
+    fn a_frame_is_sent_once_with_its_last_position() {
+        let mut panel = direct(touchscreen(false));
+        let down = [
+            key(KeyCode::BTN_TOUCH, 1, 0),
+            abs(AbsoluteAxisCode::ABS_X, 100),
+            abs(AbsoluteAxisCode::ABS_Y, 200),
+            abs(AbsoluteAxisCode::ABS_X, 110),
+            abs(AbsoluteAxisCode::ABS_PRESSURE, 40),
+        ];
+        assert!(run(&mut panel, &down).is_empty());
+        let sent = run(&mut panel, &[syn()]);
+        assert_eq!(touches(&sent), [(110, 200, false)]);
+        let AppEvent::Touch { pressure, .. } = sent[0] else {
+            unreachable!()
+        };
+        assert_eq!(pressure, Some(40));
+
+        // An empty frame sends nothing, a lift sends the last position
+        assert!(run(&mut panel, &[syn()]).is_empty());
+        let sent = run(&mut panel, &[key(KeyCode::BTN_TOUCH, 0, 0), syn()]);
+        assert_eq!(touches(&sent), [(110, 200, true)]);
+        assert_eq!(panel.resting_sample(), None);
+    }
+
+    #[test]
+    fn a_tap_on_the_last_spot_comes_without_axis_events() {
+        let mut panel = direct(touchscreen(false));
+        run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 300),
+                abs(AbsoluteAxisCode::ABS_Y, 400),
+                syn(),
+                key(KeyCode::BTN_TOUCH, 0, 0),
+                syn(),
+            ],
+        );
+        // The kernel leaves out axes that didn't change
+        let sent = run(&mut panel, &[key(KeyCode::BTN_TOUCH, 1, 0), syn()]);
+        assert_eq!(touches(&sent), [(300, 400, false)]);
+        assert_eq!(panel.resting_sample(), Some((300, 400, None)));
+    }
+
+    #[test]
+    fn moves_without_contact_are_not_touches() {
+        let mut panel = direct(touchscreen(false));
+        let sent = run(
+            &mut panel,
+            &[
+                abs(AbsoluteAxisCode::ABS_X, 10),
+                abs(AbsoluteAxisCode::ABS_Y, 20),
+                syn(),
+            ],
+        );
+        assert!(sent.is_empty(), "{sent:?}");
+    }
+
+    #[test]
+    fn a_hovering_pen_is_sent_as_hover_until_it_touches() {
+        let mut panel = direct(touchscreen(false));
+        let sent = run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOOL_PEN, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 500),
+                abs(AbsoluteAxisCode::ABS_Y, 600),
+                syn(),
+            ],
+        );
+        assert_eq!(hovers(&sent), [(500, 600, false)]);
+        assert!(touches(&sent).is_empty());
+
+        let sent = run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 505),
+                syn(),
+            ],
+        );
+        assert_eq!(touches(&sent), [(505, 600, false)]);
+        assert!(hovers(&sent).is_empty());
+
+        // Lifted but still in range: hovering again
+        let sent = run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOUCH, 0, 0),
+                abs(AbsoluteAxisCode::ABS_Y, 590),
+                syn(),
+            ],
+        );
+        assert_eq!(touches(&sent), [(505, 590, true)]);
+        assert_eq!(hovers(&sent), [(505, 590, false)]);
+    }
+
+    #[test]
+    fn the_pen_leaving_range_releases_the_touch() {
+        let mut panel = direct(touchscreen(false));
+        run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOOL_PEN, 1, 0),
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 700),
+                abs(AbsoluteAxisCode::ABS_Y, 800),
+                syn(),
+            ],
+        );
+        // Some pens drop out of range without lifting first
+        let sent = run(&mut panel, &[key(KeyCode::BTN_TOOL_PEN, 0, 0), syn()]);
+        assert_eq!(touches(&sent), [(700, 800, true)]);
+        assert_eq!(hovers(&sent), [(700, 800, true)]);
+        assert_eq!(panel.resting_sample(), None);
+
+        // The lift that follows is no second release
+        let sent = run(&mut panel, &[key(KeyCode::BTN_TOUCH, 0, 0), syn()]);
+        assert!(sent.is_empty(), "{sent:?}");
+    }
+
+    #[test]
+    fn a_finger_tool_leaving_releases_without_hover() {
+        let mut panel = direct(touchscreen(false));
+        run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOOL_FINGER, 1, 0),
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 50),
+                syn(),
+            ],
+        );
+        let sent = run(&mut panel, &[key(KeyCode::BTN_TOOL_FINGER, 0, 0), syn()]);
+        assert_eq!(touches(&sent), [(50, 0, true)]);
+        assert!(hovers(&sent).is_empty());
+    }
+
+    #[test]
+    fn hovers_are_only_for_touchscreens() {
+        let mut tablet = direct(DeviceInfo {
+            class: DeviceClass::Other,
+            ..touchscreen(false)
+        });
+        let sent = run(
+            &mut tablet,
+            &[
+                key(KeyCode::BTN_TOOL_PEN, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 500),
+                syn(),
+                key(KeyCode::BTN_TOOL_PEN, 0, 0),
+                syn(),
+            ],
+        );
+        assert!(sent.is_empty(), "{sent:?}");
+    }
+
+    #[test]
+    fn a_single_touch_trackpad_sends_one_finger_frames() {
+        let mut pad = direct(trackpad(false));
+        let sent = run(
+            &mut pad,
+            &[
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 30),
+                abs(AbsoluteAxisCode::ABS_Y, 40),
+                syn(),
+                key(KeyCode::BTN_TOUCH, 0, 0),
+                syn(),
+            ],
+        );
+        let frames: Vec<(Vec<TouchContact>, bool)> = sent
+            .into_iter()
+            .map(|event| match event {
+                AppEvent::Trackpad {
+                    contacts, touching, ..
+                } => (contacts, touching),
+                other => panic!("not a trackpad frame: {other:?}"),
+            })
+            .collect();
+        let finger = TouchContact {
+            slot: 0,
+            x: 30,
+            y: 40,
+            palm: false,
+        };
+        assert_eq!(frames, [(vec![finger], true), (vec![], false)]);
+    }
+
+    #[test]
+    fn a_multitouch_trackpad_sends_its_slots_and_marks_palms() {
+        let mut pad = direct(trackpad(true));
+        let sent = run(
+            &mut pad,
+            &[
+                abs(AbsoluteAxisCode::ABS_MT_SLOT, 0),
+                abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, 1),
+                abs(AbsoluteAxisCode::ABS_MT_POSITION_X, 100),
+                abs(AbsoluteAxisCode::ABS_MT_POSITION_Y, 200),
+                abs(AbsoluteAxisCode::ABS_MT_SLOT, 1),
+                abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, 2),
+                abs(AbsoluteAxisCode::ABS_MT_TOOL_TYPE, MT_TOOL_PALM),
+                abs(AbsoluteAxisCode::ABS_MT_POSITION_X, 900),
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                // The single touch axes mirror slot 0, no touch event of their own
+                abs(AbsoluteAxisCode::ABS_X, 100),
+                syn(),
+            ],
+        );
+        let [
+            AppEvent::Trackpad {
+                contacts, touching, ..
+            },
+        ] = sent.as_slice()
+        else {
+            panic!("not one trackpad frame: {sent:?}");
+        };
+        assert!(touching);
+        assert_eq!(
+            contacts
+                .iter()
+                .map(|c| (c.slot, c.palm))
+                .collect::<Vec<_>>(),
+            [(0, false), (1, true)]
+        );
+
+        // Slot numbers past the tracked ones land in the last slot
+        let sent = run(
+            &mut pad,
+            &[
+                abs(AbsoluteAxisCode::ABS_MT_SLOT, 99),
+                abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, 3),
+                syn(),
+            ],
+        );
+        let [AppEvent::Trackpad { contacts, .. }] = sent.as_slice() else {
+            panic!("not one trackpad frame: {sent:?}");
+        };
+        assert_eq!(contacts.last().unwrap().slot, MAX_TOUCH_SLOTS - 1);
+    }
+
+    #[test]
+    fn pads_without_btn_touch_touch_while_a_slot_is_held() {
+        let mut pad = Listener::new(
+            Arc::new(trackpad(true)),
+            false,
+            Arc::new(Mutex::new(KeyDeduplicator::new())),
+            RateLimit::default(),
+        );
+        let touching = |sent: Vec<AppEvent>| match sent.as_slice() {
+            [AppEvent::Trackpad { touching, .. }] => *touching,
+            _ => panic!("not one trackpad frame: {sent:?}"),
+        };
+        let down = run(
+            &mut pad,
+            &[abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, 5), syn()],
+        );
+        assert!(touching(down));
+        let up = run(
+            &mut pad,
+            &[abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, -1), syn()],
+        );
+        assert!(!touching(up));
+    }
+
+    #[test]
+    fn swapped_panels_send_raw_axes_with_the_hint() {
+        // The GPS model's panel has X and Y the other way round
+        let gps = DeviceInfo {
+            swapped_axes: crate::machine_detect::ComputerModel::DatorBBFältGPS.swaps_touch_axes(),
+            abs_y_max: Some(2047),
+            ..touchscreen(false)
+        };
+        let mut panel = direct(gps);
+        let sent = run(
+            &mut panel,
+            &[
+                key(KeyCode::BTN_TOUCH, 1, 0),
+                abs(AbsoluteAxisCode::ABS_X, 4000),
+                abs(AbsoluteAxisCode::ABS_Y, 10),
+                syn(),
+            ],
+        );
+        // Turned where the touch is placed on the screen, not in the listener
+        let [AppEvent::Touch { x, y, info, .. }] = sent.as_slice() else {
+            panic!("not one touch: {sent:?}");
+        };
+        assert_eq!((*x, *y), (4000, 10));
+        let info = info.as_deref().unwrap();
+        assert!(info.swapped_axes);
+        assert_eq!(
+            crate::touch_nav::screen_position(*x, *y, Some(info)),
+            Some(((10, 4000), (2047, 4095)))
+        );
+    }
+
+    #[test]
+    fn relative_axes_are_mouse_motion() {
+        let mut mouse = direct(sim_device("/dev/input/event4", "Direct mouse", None));
+        let sent = run(
+            &mut mouse,
+            &[
+                event(EventType::RELATIVE, evdev::RelativeAxisCode::REL_X.0, -3, 0),
+                event(EventType::RELATIVE, evdev::RelativeAxisCode::REL_Y.0, 7, 0),
+                event(
+                    EventType::RELATIVE,
+                    evdev::RelativeAxisCode::REL_WHEEL.0,
+                    1,
+                    0,
+                ),
+                syn(),
+            ],
+        );
+        let motion: Vec<(i16, i16)> = sent
+            .iter()
+            .map(|event| match event {
+                AppEvent::Mouse { x, y, .. } => (*x, *y),
+                other => panic!("not mouse motion: {other:?}"),
+            })
+            .collect();
+        assert_eq!(motion, [(-3, 0), (0, 7)]);
+    }
+
+    #[test]
+    fn a_flooding_key_is_throttled_with_one_fault() {
+        let limit = RateLimit {
+            max_per_sec: 5,
+            sustain: Duration::ZERO,
+        };
+        let mut keyboard = Listener::new(
+            Arc::new(sim_device("/dev/input/event3", "Direct keyboard", None)),
+            true,
+            Arc::new(Mutex::new(KeyDeduplicator::new())),
+            limit,
+        );
+        // A second and a half at 100 presses/s, the rate is judged once a second
+        let mut out = Vec::new();
+        for i in 0..150 {
+            keyboard.process(key(KeyCode::KEY_LEFTSHIFT, 1, 1000 + i * 10), &mut out);
+            keyboard.process(key(KeyCode::KEY_LEFTSHIFT, 0, 1005 + i * 10), &mut out);
+        }
+        let faults = out
+            .iter()
+            .filter(|event| matches!(event, AppEvent::DeviceFault { .. }))
+            .count();
+        let presses = out
+            .iter()
+            .filter(|event| matches!(event, AppEvent::Key { .. }))
+            .count();
+        assert_eq!(faults, 1);
+        assert!(presses < 150, "{presses} presses went through");
+        // The releases still go out, nothing stays held
+        let releases = out
+            .iter()
+            .filter(|event| matches!(event, AppEvent::KeyReleased { .. }))
+            .count();
+        assert_eq!(releases, 150);
+    }
+
+    /// One single-touch frame: whether BTN_TOUCH flips, then the axes it moves
+    #[derive(Debug, Clone)]
+    struct Frame {
+        flip: bool,
+        x: Option<u16>,
+        y: Option<u16>,
+        pressure: Option<i32>,
+    }
+
+    fn frames() -> impl Strategy<Value = Vec<Frame>> {
+        let frame = (
+            any::<bool>(),
+            proptest::option::of(0..4096u16),
+            proptest::option::of(0..4096u16),
+            proptest::option::of(0..256i32),
+        )
+            .prop_map(|(flip, x, y, pressure)| Frame {
+                flip,
+                x,
+                y,
+                pressure,
+            });
+        proptest::collection::vec(frame, 0..64)
+    }
+
+    /// What a slot does in a multi-touch frame
+    #[derive(Debug, Clone, Copy)]
+    enum SlotChange {
+        Still,
+        Down(u16, u16),
+        Move(u16, u16),
+        Up,
+    }
+
+    fn slot_frames() -> impl Strategy<Value = Vec<[SlotChange; 3]>> {
+        let change = prop_oneof![
+            2 => Just(SlotChange::Still),
+            1 => (0..4096u16, 0..4096u16).prop_map(|(x, y)| SlotChange::Down(x, y)),
+            2 => (0..4096u16, 0..4096u16).prop_map(|(x, y)| SlotChange::Move(x, y)),
+            1 => Just(SlotChange::Up),
+        ];
+        proptest::collection::vec(proptest::array::uniform3(change), 0..48)
+    }
+
+    /// A key action on one of a few keys, `gap` ms after the previous one
+    fn key_actions() -> impl Strategy<Value = Vec<(usize, bool, u64, u64, bool)>> {
+        // (key, repeat rather than flip, gap, second node's lag, second node first)
+        proptest::collection::vec(
+            (0..3usize, any::<bool>(), 10..60u64, 0..=2u64, any::<bool>()),
+            0..64,
+        )
+    }
+
+    // Counts the allocations of each thread, so the event path can be checked
+    // for ones made per event
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            // Not there while the thread is torn down
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            unsafe { System.alloc(layout) }
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            unsafe { System.dealloc(ptr, layout) }
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    #[test]
+    fn the_event_path_allocates_per_device_not_per_event() {
+        let dedup = Arc::new(Mutex::new(KeyDeduplicator::new()));
+        let mut panel = listener(touchscreen(false), &dedup);
+        let keyboard = DeviceInfo {
+            phys: Some("usb-0000:00:14.0-1/input0".to_string()),
+            vendor: 0x1234,
+            ..sim_device("/dev/input/event3", "Industrial keyboard", None)
+        };
+        let mut keys = listener(keyboard, &dedup);
+        let mut held = SimultaneousKeys::new();
+        let mut ghosts = GhostKeys::new();
+        let mut out = Vec::with_capacity(16);
+        let mut frame = Vec::with_capacity(4);
+
+        // A drag lifted every 100 samples, 1 ms apart, with a key typed every 50
+        let mut drag = |from: u64, to: u64| {
+            let before = ALLOCATIONS.with(Cell::get);
+            let mut touches = 0;
+            for ms in from..to {
+                frame.clear();
+                if ms % 100 == 0 {
+                    frame.push(key(KeyCode::BTN_TOUCH, 1, ms));
+                }
+                frame.push(abs(AbsoluteAxisCode::ABS_X, (ms % 4096) as i32));
+                frame.push(abs(AbsoluteAxisCode::ABS_Y, (ms / 7 % 4096) as i32));
+                if ms % 100 == 99 {
+                    frame.push(key(KeyCode::BTN_TOUCH, 0, ms));
+                }
+                frame.push(syn());
+                for event in &frame {
+                    panel.process(*event, &mut out);
+                }
+                match ms % 50 {
+                    0 => keys.process(key(KeyCode::KEY_A, 1, ms), &mut out),
+                    10 => keys.process(key(KeyCode::KEY_A, 0, ms), &mut out),
+                    _ => {}
+                }
+                for event in out.drain(..) {
+                    match event {
+                        AppEvent::Touch { .. } => touches += 1,
+                        AppEvent::Key {
+                            code,
+                            info,
+                            timestamp,
+                            ..
+                        } => {
+                            held.press(&info, code, timestamp);
+                            ghosts.press(&info, code, timestamp);
+                        }
+                        AppEvent::KeyReleased { code, info, .. } => {
+                            held.release(&info, code);
+                            ghosts.release(&info, code);
+                        }
+                        other => panic!("{other:?}"),
+                    }
+                }
+            }
+            (ALLOCATIONS.with(Cell::get) - before, touches)
+        };
+
+        let (first, _) = drag(0, 1000);
+        let (rest, touches) = drag(1000, 101_000);
+        assert_eq!(touches, 100_000);
+        assert!(first > 0);
+        assert_eq!(rest, 0, "{rest} allocations over {touches} touches");
+        assert_eq!(Arc::strong_count(&panel.info), 1);
+        assert_eq!(held.max(), 1);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn single_touch_frames_follow_the_contact(frames in frames()) {
+            let dedup = Arc::new(Mutex::new(KeyDeduplicator::new()));
+            let mut listener = listener(touchscreen(false), &dedup);
+            let mut out = Vec::new();
+            let (mut touching, mut x, mut y) = (false, 0, 0);
+
+            for frame in frames {
+                if frame.flip {
+                    touching = !touching;
+                    listener.process(key(KeyCode::BTN_TOUCH, touching as i32, 0), &mut out);
+                }
+                if let Some(value) = frame.x {
+                    x = value;
+                    listener.process(abs(AbsoluteAxisCode::ABS_X, value as i32), &mut out);
+                }
+                if let Some(value) = frame.y {
+                    y = value;
+                    listener.process(abs(AbsoluteAxisCode::ABS_Y, value as i32), &mut out);
+                }
+                if let Some(value) = frame.pressure {
+                    listener.process(abs(AbsoluteAxisCode::ABS_PRESSURE, value), &mut out);
+                }
+                prop_assert!(out.is_empty(), "sent before SYN_REPORT: {out:?}");
+                listener.process(syn(), &mut out);
+
+                let moved = frame.x.is_some() || frame.y.is_some() || frame.pressure.is_some();
+                if frame.flip || (touching && moved) {
+                    // One event per frame, down while the contact is and up once
+                    prop_assert_eq!(out.len(), 1, "{:?}", out);
+                    let AppEvent::Touch { x: sent_x, y: sent_y, released, .. } = out[0] else {
+                        panic!("not a touch: {:?}", out[0]);
+                    };
+                    prop_assert_eq!(released, !touching);
+                    prop_assert_eq!((sent_x, sent_y), (x, y));
+                } else {
+                    prop_assert!(out.is_empty(), "{out:?}");
+                }
+                prop_assert_eq!(listener.resting_sample().is_some(), touching);
+                out.clear();
+            }
+        }
+
+        #[test]
+        fn multitouch_frames_follow_the_slots(frames in slot_frames()) {
+            let dedup = Arc::new(Mutex::new(KeyDeduplicator::new()));
+            let mut listener = listener(touchscreen(true), &dedup);
+            let mut out = Vec::new();
+            let mut slots: [Option<(u16, u16)>; 3] = [None; 3];
+            let mut next_id = 0;
+
+            for frame in frames {
+                let mut changed = false;
+                for (slot, change) in frame.into_iter().enumerate() {
+                    // Structurally valid: a new id only for an empty slot, a lift
+                    // or move only for a held one
+                    let (down, position) = match (change, slots[slot]) {
+                        (SlotChange::Down(x, y), None) => (true, Some((x, y))),
+                        (SlotChange::Move(x, y), Some(_)) => (false, Some((x, y))),
+                        (SlotChange::Up, Some(_)) => (false, None),
+                        _ => continue,
+                    };
+                    listener.process(abs(AbsoluteAxisCode::ABS_MT_SLOT, slot as i32), &mut out);
+                    if down {
+                        next_id += 1;
+                        listener.process(abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, next_id), &mut out);
+                    }
+                    match position {
+                        Some((x, y)) => {
+                            listener.process(abs(AbsoluteAxisCode::ABS_MT_POSITION_X, x as i32), &mut out);
+                            listener.process(abs(AbsoluteAxisCode::ABS_MT_POSITION_Y, y as i32), &mut out);
+                        }
+                        None => listener.process(abs(AbsoluteAxisCode::ABS_MT_TRACKING_ID, -1), &mut out),
+                    }
+                    slots[slot] = position;
+                    changed = true;
+                }
+                listener.process(syn(), &mut out);
+
+                if changed {
+                    let expected: Vec<TouchContact> = slots
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(slot, held)| {
+                            held.map(|(x, y)| TouchContact { slot, x, y, palm: false })
+                        })
+                        .collect();
+                    prop_assert_eq!(out.len(), 1, "{:?}", out);
+                    let AppEvent::MultiTouch { contacts, .. } = &out[0] else {
+                        panic!("not a multi-touch frame: {:?}", out[0]);
+                    };
+                    prop_assert_eq!(contacts, &expected);
+                } else {
+                    prop_assert!(out.is_empty(), "{out:?}");
+                }
+                out.clear();
+            }
+        }
+
+        #[test]
+        fn keys_read_from_two_nodes_press_and_release_in_turn(actions in key_actions()) {
+            // Both nodes of one keyboard report every key, a few ms apart
+            let phys = |input: &str| DeviceInfo {
+                phys: Some(format!("usb-0000:00:14.0-1/{input}")),
+                ..sim_device(&format!("/dev/input/{input}"), "Property keyboard", None)
+            };
+            let dedup = Arc::new(Mutex::new(KeyDeduplicator::new()));
+            let mut nodes = [listener(phys("input0"), &dedup), listener(phys("input1"), &dedup)];
+            let codes = [KeyCode::KEY_A, KeyCode::KEY_LEFTSHIFT, KeyCode::KEY_ENTER];
+            let mut held = [false; 3];
+            let mut presses = 0;
+            let mut out = Vec::new();
+            let mut now = 1_000;
+
+            for (index, repeat, gap, lag, second_first) in actions {
+                now += gap;
+                let value = match (repeat && held[index], held[index]) {
+                    (true, _) => 2,
+                    (false, true) => 0,
+                    (false, false) => 1,
+                };
+                held[index] = value != 0;
+                presses += (value == 1) as usize;
+
+                let order = if second_first { [1, 0] } else { [0, 1] };
+                for node in order {
+                    let time = if node == 1 { now + lag } else { now };
+                    nodes[node].process(key(codes[index], value, time), &mut out);
+                }
+            }
+
+            // Every press has a release before the next press of its key
+            let mut down = [false; 3];
+            let mut sent_presses = 0;
+            for event in &out {
+                match event {
+                    AppEvent::Key { code, repeat: false, .. } => {
+                        let index = codes.iter().position(|c| c == code).unwrap();
+                        prop_assert!(!down[index], "{code:?} pressed twice: {out:?}");
+                        down[index] = true;
+                        sent_presses += 1;
+                    }
+                    AppEvent::KeyReleased { code, .. } => {
+                        let index = codes.iter().position(|c| c == code).unwrap();
+                        prop_assert!(down[index], "{code:?} released while up: {out:?}");
+                        down[index] = false;
+                    }
+                    AppEvent::Key { code, repeat: true, .. } => {
+                        let index = codes.iter().position(|c| c == code).unwrap();
+                        prop_assert!(down[index], "{code:?} repeating while up: {out:?}");
+                    }
+                    other => panic!("unexpected {other:?}"),
+                }
+            }
+            prop_assert_eq!(sent_presses, presses);
+            prop_assert_eq!(down, held);
+        }
+    }
+}
@@ -0,0 +1,177 @@
+//! The run loop's intake. The tick thread sends whether or not the loop keeps
+//! up, so after a stall (a slow draw, a blocked report write) dozens of ticks
+//! sit in the channel among the input events. Handled one by one they make
+//! hold progress and trail expiry jump ahead in a burst, and input that
+//! arrived meanwhile waits behind them. Each drain takes what is queued, keeps
+//! the input events in their order and puts a single tick after them.
+
+use std::collections::VecDeque;
+
+use crossbeam_channel::{Receiver, RecvError};
+
+use crate::event_handler::AppEvent;
+
+/// Most events taken in one drain, so a flooding device can't keep a drain going
+const MAX_DRAIN: usize = 1024;
+
+#[derive(Default)]
+pub struct EventQueue {
+    pending: VecDeque<AppEvent>,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue::default()
+    }
+
+    /// The next event to handle, waiting for one when none is left from the
+    /// last drain. Fails once every sender is gone.
+    pub fn next(&mut self, rx: &Receiver<AppEvent>) -> Result<AppEvent, RecvError> {
+        if self.pending.is_empty() {
+            let first = rx.recv()?;
+            let queued = rx.try_iter().take(MAX_DRAIN - 1);
+            self.pending = drain_order(std::iter::once(first).chain(queued));
+        }
+        Ok(self
+            .pending
+            .pop_front()
+            .expect("a drain holds at least one event"))
+    }
+
+    /// Hand out `events` before anything still in the channel, e.g. what
+    /// arrived while the self-check waited for its probe
+    pub fn requeue(&mut self, events: Vec<AppEvent>) {
+        self.pending.extend(drain_order(events));
+    }
+}
+
+/// `events` in handling order: everything but the ticks as it came, then one
+/// tick if there was any
+fn drain_order(events: impl IntoIterator<Item = AppEvent>) -> VecDeque<AppEvent> {
+    let mut ticked = false;
+    let mut ordered: VecDeque<AppEvent> = events
+        .into_iter()
+        .filter(|event| {
+            let tick = matches!(event, AppEvent::Tick);
+            ticked |= tick;
+            !tick
+        })
+        .collect();
+    if ticked {
+        ordered.push_back(AppEvent::Tick);
+    }
+    ordered
+}
+
+#[cfg(test)]
+mod tests {
+    use crossbeam_channel::unbounded;
+    use evdev::KeyCode;
+
+    use super::*;
+    use crate::snapshot::press;
+
+    /// The key code of a press, None for a tick
+    fn code(event: AppEvent) -> Option<KeyCode> {
+        match event {
+            AppEvent::Key { code, .. } => Some(code),
+            AppEvent::Tick => None,
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    const KEYS: [KeyCode; 5] = [
+        KeyCode::KEY_A,
+        KeyCode::KEY_B,
+        KeyCode::KEY_C,
+        KeyCode::KEY_D,
+        KeyCode::KEY_E,
+    ];
+
+    #[test]
+    fn a_stall_drains_to_the_keys_in_order_and_one_tick() {
+        let (tx, rx) = unbounded();
+        // 50 ticks piled up during a stall, the keys among them
+        for i in 0..50 {
+            tx.send(AppEvent::Tick).unwrap();
+            if i % 10 == 5 {
+                tx.send(press(KEYS[i / 10], i as u128)).unwrap();
+            }
+        }
+
+        let mut queue = EventQueue::new();
+        let drained: Vec<Option<KeyCode>> =
+            (0..6).map(|_| code(queue.next(&rx).unwrap())).collect();
+        let mut expected: Vec<Option<KeyCode>> = KEYS.iter().copied().map(Some).collect();
+        expected.push(None);
+        assert_eq!(drained, expected);
+        assert!(rx.is_empty());
+        assert!(queue.pending.is_empty());
+
+        // The next tick is the next drain's
+        tx.send(AppEvent::Tick).unwrap();
+        assert_eq!(code(queue.next(&rx).unwrap()), None);
+    }
+
+    #[test]
+    fn a_drain_without_ticks_adds_none() {
+        let (tx, rx) = unbounded();
+        for (i, key) in KEYS.into_iter().enumerate() {
+            tx.send(press(key, i as u128)).unwrap();
+        }
+        let mut queue = EventQueue::new();
+        let drained: Vec<Option<KeyCode>> =
+            (0..5).map(|_| code(queue.next(&rx).unwrap())).collect();
+        assert_eq!(drained, KEYS.map(Some));
+        assert!(queue.pending.is_empty());
+    }
+
+    #[test]
+    fn a_flood_is_taken_in_bounded_drains() {
+        let (tx, rx) = unbounded();
+        for i in 0..MAX_DRAIN + 100 {
+            tx.send(AppEvent::Tick).unwrap();
+            tx.send(press(KeyCode::KEY_LEFTSHIFT, i as u128)).unwrap();
+        }
+        let mut queue = EventQueue::new();
+        queue.next(&rx).unwrap();
+        // Half of the first MAX_DRAIN events were ticks, merged into one
+        assert_eq!(queue.pending.len(), MAX_DRAIN / 2);
+        assert!(matches!(queue.pending.back(), Some(AppEvent::Tick)));
+        assert_eq!(rx.len(), 2 * (MAX_DRAIN + 100) - MAX_DRAIN);
+    }
+
+    #[test]
+    fn requeued_events_come_before_the_channel() {
+        let (tx, rx) = unbounded();
+        tx.send(press(KeyCode::KEY_Z, 10)).unwrap();
+        let mut queue = EventQueue::new();
+        queue.requeue(vec![
+            AppEvent::Tick,
+            press(KeyCode::KEY_A, 1),
+            AppEvent::Tick,
+            press(KeyCode::KEY_B, 2),
+        ]);
+        let drained: Vec<Option<KeyCode>> =
+            (0..4).map(|_| code(queue.next(&rx).unwrap())).collect();
+        assert_eq!(
+            drained,
+            [
+                Some(KeyCode::KEY_A),
+                Some(KeyCode::KEY_B),
+                None,
+                Some(KeyCode::KEY_Z)
+            ]
+        );
+    }
+
+    #[test]
+    fn the_queue_fails_once_the_senders_are_gone() {
+        let (tx, rx) = unbounded();
+        tx.send(AppEvent::Tick).unwrap();
+        drop(tx);
+        let mut queue = EventQueue::new();
+        assert!(matches!(queue.next(&rx), Ok(AppEvent::Tick)));
+        assert!(queue.next(&rx).is_err());
+    }
+}
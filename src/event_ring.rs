@@ -0,0 +1,241 @@
+//! The last few seconds of input, kept in memory all the time. A glitch is
+//! usually noticed a moment after it happened, too late to start a log, so
+//! F9 writes what the screens just saw to a file of its own in the log
+//! directory (see `app::run`). Lines are those of the event log, ticks left
+//! out as there. Events are kept as they came and only formatted for a dump,
+//! recording one mustn't allocate on a touch drag.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::event_handler::AppEvent;
+use crate::writer::{FsyncPolicy, Priority, Topic};
+
+/// How far back the ring reaches
+pub const WINDOW: Duration = Duration::from_secs(30);
+
+// A touch flood fills the window with far more than anyone reads, so the ring
+// is capped by count as well
+pub const MAX_EVENTS: usize = 20_000;
+
+// Ten-finger frames weigh several times a key, so the count alone doesn't
+// bound the memory
+const MAX_BYTES: usize = 4 * 1024 * 1024;
+
+#[derive(Default)]
+pub struct EventRing {
+    // Each event with when it was recorded and its unix time in ms, oldest first
+    events: VecDeque<(Instant, u128, AppEvent)>,
+    // What the kept events take, see `footprint`
+    bytes: usize,
+}
+
+impl EventRing {
+    pub fn new() -> Self {
+        EventRing::default()
+    }
+
+    /// Keep `event`, dropping what fell out of the window or over the caps
+    pub fn record(&mut self, event: &AppEvent) {
+        self.record_at(Instant::now(), unix_millis(), event);
+    }
+
+    fn record_at(&mut self, now: Instant, millis: u128, event: &AppEvent) {
+        if let AppEvent::Tick = event {
+            return;
+        }
+        let size = footprint(event);
+        // The oldest slot is reused once the ring is full
+        while let Some((recorded, ..)) = self.events.front() {
+            let expired = now.saturating_duration_since(*recorded) > WINDOW;
+            let full = self.events.len() >= MAX_EVENTS || self.bytes + size > MAX_BYTES;
+            if !expired && !full {
+                break;
+            }
+            if let Some((.., dropped)) = self.events.pop_front() {
+                self.bytes -= footprint(&dropped);
+            }
+        }
+        self.bytes += size;
+        self.events.push_back((now, millis, event.clone()));
+    }
+
+    /// The events still within the window, oldest first, as the event log
+    /// would have them
+    pub fn dump(&self) -> String {
+        self.dump_at(Instant::now())
+    }
+
+    fn dump_at(&self, now: Instant) -> String {
+        self.events
+            .iter()
+            .filter(|(recorded, ..)| now.saturating_duration_since(*recorded) <= WINDOW)
+            .map(|(_, millis, event)| format_line(*millis, event))
+            .collect()
+    }
+}
+
+// Bytes a kept event takes: its slot and what it owns on the heap. The device
+// info is shared with the listener, so it isn't counted.
+fn footprint(event: &AppEvent) -> usize {
+    let owned = match event {
+        AppEvent::MultiTouch { contacts, .. } | AppEvent::Trackpad { contacts, .. } => {
+            std::mem::size_of_val(contacts.as_slice())
+        }
+        AppEvent::DeviceFault { reason, .. } => reason.len(),
+        _ => 0,
+    };
+    std::mem::size_of::<(Instant, u128, AppEvent)>() + owned
+}
+
+/// One line of the event log: unix time in ms, then the event
+pub fn event_line(event: &AppEvent) -> String {
+    format_line(unix_millis(), event)
+}
+
+fn format_line(millis: u128, event: &AppEvent) -> String {
+    format!("{millis} {event:?}\n")
+}
+
+fn unix_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// File a dump taken at `unix_ms` goes to, `events-dump-<unix_ms>.log`
+pub fn dump_topic(unix_ms: u128) -> Topic {
+    Topic {
+        // One file per dump, and dumps take an operator's keypress each
+        name: Box::leak(format!("events-dump-{unix_ms}").into_boxed_str()),
+        extension: "log",
+        priority: Priority::High,
+        fsync: FsyncPolicy::EveryWrite,
+        max_bytes: 0,
+        keep: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use evdev::KeyCode;
+
+    use super::*;
+    use crate::event_handler::{MAX_TOUCH_SLOTS, TouchContact};
+    use crate::snapshot::{panel, press, release};
+
+    fn frame(timestamp: u128) -> AppEvent {
+        AppEvent::MultiTouch {
+            contacts: (0..MAX_TOUCH_SLOTS)
+                .map(|slot| TouchContact {
+                    slot,
+                    x: 100 * slot as u16,
+                    y: 200,
+                    palm: false,
+                })
+                .collect(),
+            timestamp,
+            info: panel(),
+        }
+    }
+
+    #[test]
+    fn a_dump_has_the_event_log_lines_without_ticks() {
+        let mut ring = EventRing::new();
+        let start = Instant::now();
+        let events = [
+            press(KeyCode::KEY_A, 1),
+            AppEvent::Tick,
+            release(KeyCode::KEY_A, 2),
+            frame(3),
+            AppEvent::Tick,
+        ];
+        for (i, event) in events.iter().enumerate() {
+            ring.record_at(
+                start + Duration::from_millis(i as u64),
+                1000 + i as u128,
+                event,
+            );
+        }
+
+        let dump = ring.dump_at(start + Duration::from_secs(1));
+        let expected = [(1000, &events[0]), (1002, &events[2]), (1003, &events[3])]
+            .map(|(millis, event)| format_line(millis, event))
+            .concat();
+        assert_eq!(dump, expected);
+    }
+
+    #[test]
+    fn events_older_than_the_window_are_left_out_and_dropped() {
+        let mut ring = EventRing::new();
+        let start = Instant::now();
+        for second in 0..40 {
+            let at = start + Duration::from_secs(second);
+            ring.record_at(at, second as u128 * 1000, &press(KeyCode::KEY_A, 0));
+        }
+        // Recorded at 9 s and later are within 30 s of 39 s
+        assert_eq!(ring.events.len(), 31);
+        let dump = ring.dump_at(start + Duration::from_secs(39));
+        assert_eq!(dump.lines().count(), 31);
+        assert!(dump.starts_with("9000 "), "{dump}");
+
+        // Nothing is recorded for a while, the dump still only reaches back 30 s
+        let dump = ring.dump_at(start + Duration::from_secs(60));
+        assert_eq!(dump.lines().count(), 10);
+        assert!(dump.starts_with("30000 "), "{dump}");
+        assert_eq!(ring.dump_at(start + Duration::from_secs(100)), "");
+    }
+
+    #[test]
+    fn a_key_flood_is_capped_by_count() {
+        let mut ring = EventRing::new();
+        let now = Instant::now();
+        for i in 0..MAX_EVENTS + 5000 {
+            ring.record_at(now, i as u128, &press(KeyCode::KEY_A, 0));
+        }
+        assert_eq!(ring.events.len(), MAX_EVENTS);
+        let dump = ring.dump_at(now);
+        assert_eq!(dump.lines().count(), MAX_EVENTS);
+        // The oldest went first
+        assert!(dump.starts_with("5000 "));
+        assert!(dump.ends_with(&format_line(
+            (MAX_EVENTS + 4999) as u128,
+            &press(KeyCode::KEY_A, 0)
+        )));
+    }
+
+    #[test]
+    fn a_touch_flood_is_capped_by_bytes() {
+        let mut ring = EventRing::new();
+        let now = Instant::now();
+        for i in 0..MAX_EVENTS * 2 {
+            ring.record_at(now, i as u128, &frame(i as u128));
+            assert!(ring.bytes <= MAX_BYTES);
+        }
+        let kept = ring.events.len();
+        assert!(kept < MAX_EVENTS, "{kept} frames kept");
+        assert_eq!(kept, MAX_BYTES / footprint(&frame(0)));
+        assert_eq!(
+            ring.bytes,
+            ring.events
+                .iter()
+                .map(|(.., event)| footprint(event))
+                .sum::<usize>()
+        );
+
+        // Keys recorded after take the room of the frames they push out
+        for i in 0..MAX_EVENTS {
+            ring.record_at(now, i as u128, &press(KeyCode::KEY_A, 0));
+        }
+        assert_eq!(ring.events.len(), MAX_EVENTS);
+        assert!(matches!(
+            ring.events.front(),
+            Some((.., AppEvent::Key { .. }))
+        ));
+        assert_eq!(
+            ring.bytes,
+            MAX_EVENTS * std::mem::size_of::<(Instant, u128, AppEvent)>()
+        );
+    }
+}
@@ -0,0 +1,283 @@
+//! Frame time budget. The slowest units take over 100 ms a frame with every
+//! trail feature on, and input lags behind. The run loop times each draw, and
+//! while the rolling average stays over budget the expensive rendering is
+//! turned off one step at a time, cheapest loss first. Steps come back once
+//! frames are fast again.
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+pub const DEFAULT_FRAME_BUDGET: Duration = Duration::from_millis(50);
+
+/// Steps in the order they are taken
+pub const DEFAULT_LADDER: [Degradation; 3] = [
+    Degradation::TrailColors,
+    Degradation::TrailLines,
+    Degradation::TrailLength,
+];
+
+// Frames the average is taken over. A decision also waits this many frames
+// after the previous one, so a step gets to show its effect.
+const WINDOW: usize = 10;
+
+// A step comes back once the average falls below this share of the budget.
+// Well under the budget, or the step would flap on and off.
+const RECOVER_SHARE: f32 = 0.5;
+
+/// A rendering feature given up to stay within the budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Degradation {
+    /// Trail drawn in one color instead of fading by stroke age
+    TrailColors,
+    /// Samples plotted as points instead of joined by lines
+    TrailLines,
+    /// Trail cut to a quarter of its length
+    TrailLength,
+}
+
+impl Degradation {
+    /// Name in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Degradation::TrailColors => "trail_colors",
+            Degradation::TrailLines => "trail_lines",
+            Degradation::TrailLength => "trail_length",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        DEFAULT_LADDER
+            .into_iter()
+            .find(|degradation| degradation.name() == name)
+    }
+
+    /// What the operator loses, for the notices
+    pub fn description(self) -> &'static str {
+        match self {
+            Degradation::TrailColors => "trail colors",
+            Degradation::TrailLines => "trail lines",
+            Degradation::TrailLength => "full trail length",
+        }
+    }
+}
+
+/// A step taken or undone by `FrameBudget::record`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetChange {
+    Degraded(Degradation),
+    Restored(Degradation),
+}
+
+impl BudgetChange {
+    /// One-line notice for the operator
+    pub fn notice(self, average: Duration) -> String {
+        match self {
+            BudgetChange::Degraded(step) => format!(
+                "Frames take {} ms, {} off",
+                average.as_millis(),
+                step.description()
+            ),
+            BudgetChange::Restored(step) => {
+                format!("Frames fast again, {} back", step.description())
+            }
+        }
+    }
+}
+
+/// Rolling frame times and how far down the ladder rendering currently is
+pub struct FrameBudget {
+    budget: Duration,
+    ladder: Vec<Degradation>,
+    // Steps of the ladder taken, from the start
+    level: usize,
+    frames: VecDeque<Duration>,
+    // Frames since the last step taken or undone
+    since_change: usize,
+}
+
+impl FrameBudget {
+    /// An empty `ladder` never degrades anything
+    pub fn new(budget: Duration, ladder: Vec<Degradation>) -> Self {
+        FrameBudget {
+            budget,
+            ladder,
+            level: 0,
+            frames: VecDeque::with_capacity(WINDOW),
+            since_change: 0,
+        }
+    }
+
+    /// Record how long a frame took, returning a step taken or undone because of it
+    pub fn record(&mut self, frame_time: Duration) -> Option<BudgetChange> {
+        if self.frames.len() == WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame_time);
+        self.since_change += 1;
+        if self.since_change < WINDOW {
+            return None;
+        }
+
+        let average = self.average();
+        let change = if average > self.budget && self.level < self.ladder.len() {
+            self.level += 1;
+            BudgetChange::Degraded(self.ladder[self.level - 1])
+        } else if average < self.budget.mul_f32(RECOVER_SHARE) && self.level > 0 {
+            self.level -= 1;
+            BudgetChange::Restored(self.ladder[self.level])
+        } else {
+            return None;
+        };
+        self.since_change = 0;
+        Some(change)
+    }
+
+    /// Average over the last frames
+    pub fn average(&self) -> Duration {
+        match self.frames.len() {
+            0 => Duration::ZERO,
+            count => self.frames.iter().sum::<Duration>() / count as u32,
+        }
+    }
+
+    /// Steps currently taken
+    pub fn active(&self) -> &[Degradation] {
+        &self.ladder[..self.level]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    /// Every change `frames` frame times bring about, with the frame it came on
+    fn changes(
+        budget: &mut FrameBudget,
+        frames: impl IntoIterator<Item = Duration>,
+    ) -> Vec<(usize, BudgetChange)> {
+        frames
+            .into_iter()
+            .enumerate()
+            .filter_map(|(frame, time)| budget.record(time).map(|change| (frame, change)))
+            .collect()
+    }
+
+    fn default_budget() -> FrameBudget {
+        FrameBudget::new(DEFAULT_FRAME_BUDGET, DEFAULT_LADDER.to_vec())
+    }
+
+    #[test]
+    fn fast_frames_keep_everything() {
+        let mut budget = default_budget();
+        assert!(changes(&mut budget, [20 * MS; 100]).is_empty());
+        assert!(budget.active().is_empty());
+    }
+
+    #[test]
+    fn slow_frames_go_down_the_ladder_a_window_apart() {
+        let mut budget = default_budget();
+        let changes = changes(&mut budget, [120 * MS; 100]);
+        assert_eq!(
+            changes,
+            [
+                (WINDOW - 1, BudgetChange::Degraded(Degradation::TrailColors)),
+                (
+                    2 * WINDOW - 1,
+                    BudgetChange::Degraded(Degradation::TrailLines)
+                ),
+                (
+                    3 * WINDOW - 1,
+                    BudgetChange::Degraded(Degradation::TrailLength)
+                ),
+            ]
+        );
+        assert_eq!(budget.active(), DEFAULT_LADDER);
+    }
+
+    #[test]
+    fn a_lone_slow_frame_is_averaged_away() {
+        let mut budget = default_budget();
+        let frames = (0..50).map(|frame| if frame == 25 { 200 * MS } else { 10 * MS });
+        assert!(changes(&mut budget, frames).is_empty());
+    }
+
+    #[test]
+    fn steps_come_back_in_reverse_once_frames_are_well_under_budget() {
+        let mut budget = default_budget();
+        changes(&mut budget, [120 * MS; 3 * WINDOW]);
+
+        // Under budget but not by enough to risk flapping
+        assert!(changes(&mut budget, [40 * MS; 5 * WINDOW]).is_empty());
+        assert_eq!(budget.active().len(), 3);
+
+        let restored: Vec<BudgetChange> = changes(&mut budget, [10 * MS; 5 * WINDOW])
+            .into_iter()
+            .map(|(_, change)| change)
+            .collect();
+        assert_eq!(
+            restored,
+            [
+                BudgetChange::Restored(Degradation::TrailLength),
+                BudgetChange::Restored(Degradation::TrailLines),
+                BudgetChange::Restored(Degradation::TrailColors),
+            ]
+        );
+        assert!(budget.active().is_empty());
+    }
+
+    #[test]
+    fn frames_hovering_at_the_budget_settle() {
+        // Degrading to just under budget stays there rather than oscillating
+        let mut budget = default_budget();
+        let frames = (0..200).map(|frame| if frame < WINDOW { 60 * MS } else { 45 * MS });
+        let changes = changes(&mut budget, frames);
+        assert_eq!(
+            changes,
+            [(WINDOW - 1, BudgetChange::Degraded(Degradation::TrailColors))]
+        );
+    }
+
+    #[test]
+    fn the_configured_ladder_and_budget_are_followed() {
+        let mut budget = FrameBudget::new(
+            20 * MS,
+            vec![Degradation::TrailLength, Degradation::TrailColors],
+        );
+        let changes = changes(&mut budget, [30 * MS; 100]);
+        let steps: Vec<BudgetChange> = changes.into_iter().map(|(_, change)| change).collect();
+        assert_eq!(
+            steps,
+            [
+                BudgetChange::Degraded(Degradation::TrailLength),
+                BudgetChange::Degraded(Degradation::TrailColors),
+            ]
+        );
+
+        let mut never = FrameBudget::new(20 * MS, Vec::new());
+        assert!(never.record(500 * MS).is_none());
+        assert!(never.active().is_empty());
+    }
+
+    #[test]
+    fn average_covers_the_last_window() {
+        let mut budget = default_budget();
+        assert_eq!(budget.average(), Duration::ZERO);
+        for _ in 0..WINDOW {
+            budget.record(100 * MS);
+        }
+        for _ in 0..WINDOW / 2 {
+            budget.record(20 * MS);
+        }
+        assert_eq!(budget.average(), 60 * MS);
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for step in DEFAULT_LADDER {
+            assert_eq!(Degradation::from_name(step.name()), Some(step));
+        }
+        assert_eq!(Degradation::from_name("braille"), None);
+    }
+}
@@ -0,0 +1,280 @@
+//! Ghost keys noticed in passing. A keyboard without full rollover reports a
+//! key nobody pressed when three keys of its matrix forming the corners of a
+//! rectangle are held: the phantom arrives in the same scan as the key that
+//! closed the rectangle. Here a press landing within a few ms of another while
+//! at least two keys are held is a candidate, and once released it counts
+//! against the keys that were held. A key pressed alone anywhere in the
+//! session is a real key, so fast typing rolling over three keys, whose keys
+//! are all typed on their own as well, doesn't show up.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use evdev::KeyCode;
+
+use crate::event_handler::DeviceInfo;
+use crate::key_dedup::PhysicalDevices;
+use crate::report::GhostSuspectResult;
+use crate::simultaneous_keys::{STUCK_AFTER_MS, is_button};
+
+// Keys held besides the candidate for a press to be one
+const GHOST_MIN_HELD: usize = 2;
+
+// A phantom comes with the scan of the key that triggered it, real presses
+// rolling over each other are tens of ms apart
+const GHOST_WINDOW_MS: u128 = 12;
+
+// Held sets and phantoms listed, most frequent first
+const MAX_LISTED: usize = 10;
+
+#[derive(Default)]
+struct Keyboard {
+    name: String,
+    // Held key to the time it went down
+    held: HashMap<KeyCode, u128>,
+    // Latest press, when it was and how many other keys were held then
+    last_press: Option<(KeyCode, u128, usize)>,
+    // Down candidates, with the other keys held at the burst
+    pending: HashMap<KeyCode, Vec<KeyCode>>,
+    // Keys pressed with nothing else held, real keys whatever else happens
+    pressed_alone: HashSet<KeyCode>,
+    // Released candidates by held keys and candidate
+    suspects: BTreeMap<(Vec<KeyCode>, KeyCode), usize>,
+}
+
+impl Keyboard {
+    /// Held keys other than `code`, in code order
+    fn others(&self, code: KeyCode) -> Vec<KeyCode> {
+        let mut others: Vec<KeyCode> = self
+            .held
+            .keys()
+            .copied()
+            .filter(|held| *held != code)
+            .collect();
+        others.sort_by_key(|code| code.0);
+        others
+    }
+
+    fn press(&mut self, code: KeyCode, timestamp: u128) {
+        // A lost release would make every later press look like a burst
+        self.held
+            .retain(|_, down| timestamp.saturating_sub(*down) <= STUCK_AFTER_MS);
+
+        let held_before = self.others(code).len();
+        if held_before == 0 {
+            self.pressed_alone.insert(code);
+        }
+        self.held.insert(code, timestamp);
+
+        let burst = self.last_press.filter(|(last, at, _)| {
+            *last != code && timestamp.saturating_sub(*at) <= GHOST_WINDOW_MS
+        });
+        if let Some((last, _, last_held_before)) = burst {
+            // Keys of one scan arrive in code order, so the phantom may be
+            // either of the two
+            if held_before >= GHOST_MIN_HELD {
+                self.pending.insert(code, self.others(code));
+            }
+            if last_held_before >= GHOST_MIN_HELD && self.held.contains_key(&last) {
+                self.pending.insert(last, self.others(last));
+            }
+        }
+        self.last_press = Some((code, timestamp, held_before));
+    }
+
+    fn release(&mut self, code: KeyCode) {
+        self.held.remove(&code);
+        if let Some(held) = self.pending.remove(&code) {
+            *self.suspects.entry((held, code)).or_insert(0) += 1;
+        }
+    }
+
+    /// Suspects whose key was never pressed alone, most frequent first
+    fn suspects(&self) -> Vec<(&[KeyCode], KeyCode, usize)> {
+        let mut suspects: Vec<(&[KeyCode], KeyCode, usize)> = self
+            .suspects
+            .iter()
+            .filter(|((_, phantom), _)| !self.pressed_alone.contains(phantom))
+            .map(|((held, phantom), count)| (held.as_slice(), *phantom, *count))
+            .collect();
+        suspects.sort_by_key(|(_, _, count)| std::cmp::Reverse(*count));
+        suspects
+    }
+}
+
+/// Suspected ghost keys of every keyboard seen
+#[derive(Default)]
+pub struct GhostKeys {
+    keyboards: PhysicalDevices<Keyboard>,
+}
+
+impl GhostKeys {
+    pub fn new() -> Self {
+        GhostKeys::default()
+    }
+
+    fn keyboard(&mut self, info: &DeviceInfo) -> &mut Keyboard {
+        self.keyboards.get_or_insert_with(info, || Keyboard {
+            name: info.name.clone(),
+            ..Keyboard::default()
+        })
+    }
+
+    /// A key went down at `timestamp` (ms). Autorepeat isn't a press.
+    pub fn press(&mut self, info: &DeviceInfo, code: KeyCode, timestamp: u128) {
+        if is_button(code) {
+            return;
+        }
+        self.keyboard(info).press(code, timestamp);
+    }
+
+    pub fn release(&mut self, info: &DeviceInfo, code: KeyCode) {
+        if is_button(code) {
+            return;
+        }
+        self.keyboard(info).release(code);
+    }
+
+    /// Suspects still standing over every keyboard, for the header
+    pub fn count(&self) -> usize {
+        self.keyboards
+            .values()
+            .map(|keyboard| keyboard.suspects().len())
+            .sum()
+    }
+
+    pub fn clear(&mut self) {
+        self.keyboards.clear();
+    }
+
+    /// The most frequent suspects of each keyboard
+    pub fn results(&self) -> Vec<GhostSuspectResult> {
+        self.keyboards
+            .values()
+            .flat_map(|keyboard| {
+                keyboard
+                    .suspects()
+                    .into_iter()
+                    .take(MAX_LISTED)
+                    .map(|(held, phantom, count)| GhostSuspectResult {
+                        device: keyboard.name.clone(),
+                        held: held.iter().map(|code| format!("{code:?}")).collect(),
+                        phantom: format!("{phantom:?}"),
+                        count,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::sim_device;
+    use crate::simultaneous_keys::SimultaneousKeys;
+
+    // One session on two keyboards, listed as `--dump-capture` prints it: slow
+    // typing, the text again rolled over fast, A S W held three times, J K I
+    // once, and on the NKRO one a Ctrl+Shift+T chord. The office keyboard's
+    // matrix adds Q to A S W and U to J K I, in the scan of the closing key.
+    const GHOSTING: &str = include_str!("../tests/fixtures/keys_ghosting.txt");
+    const NKRO: &str = include_str!("../tests/fixtures/keys_nkro.txt");
+
+    /// The key events of a listing as (ms, code, value)
+    fn recording(dump: &str) -> Vec<(u128, KeyCode, i32)> {
+        dump.lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [seconds, "KEY", code, value] = fields[..] else {
+                    return None;
+                };
+                let ms = (seconds.parse::<f64>().ok()? * 1000.0).round() as u128;
+                Some((ms, code.parse().ok()?, value.parse().ok()?))
+            })
+            .collect()
+    }
+
+    fn analyze(events: &[(u128, KeyCode, i32)]) -> GhostKeys {
+        let info = sim_device("/dev/input/event3", "Recorded keyboard", None);
+        let mut ghosts = GhostKeys::new();
+        for &(ms, code, value) in events {
+            match value {
+                1 => ghosts.press(&info, code, ms),
+                0 => ghosts.release(&info, code),
+                _ => {}
+            }
+        }
+        ghosts
+    }
+
+    fn suspects(ghosts: &GhostKeys) -> Vec<(Vec<String>, String, usize)> {
+        ghosts
+            .results()
+            .into_iter()
+            .map(|suspect| (suspect.held, suspect.phantom, suspect.count))
+            .collect()
+    }
+
+    fn names(codes: &[&str]) -> Vec<String> {
+        codes.iter().map(|code| code.to_string()).collect()
+    }
+
+    #[test]
+    fn the_ghosting_keyboard_shows_its_phantoms() {
+        let ghosts = analyze(&recording(GHOSTING));
+        assert_eq!(
+            suspects(&ghosts),
+            [
+                (names(&["KEY_W", "KEY_A", "KEY_S"]), "KEY_Q".to_string(), 3),
+                (names(&["KEY_I", "KEY_J", "KEY_K"]), "KEY_U".to_string(), 1),
+            ]
+        );
+        assert_eq!(ghosts.count(), 2);
+        assert!(
+            ghosts
+                .results()
+                .iter()
+                .all(|suspect| suspect.device == "Recorded keyboard")
+        );
+    }
+
+    #[test]
+    fn the_nkro_keyboard_and_fast_typing_show_nothing() {
+        let events = recording(NKRO);
+        assert!(events.len() > 100);
+        let ghosts = analyze(&events);
+        assert_eq!(ghosts.count(), 0, "{:?}", suspects(&ghosts));
+
+        // The fast part alone rolls over three keys at a time
+        let typing: Vec<_> = events
+            .iter()
+            .copied()
+            .filter(|(ms, ..)| (5500..7000).contains(ms))
+            .collect();
+        assert_eq!(analyze(&typing).count(), 0);
+        let info = sim_device("/dev/input/event3", "Recorded keyboard", None);
+        let mut held = SimultaneousKeys::new();
+        for &(ms, code, value) in &typing {
+            match value {
+                1 => held.press(&info, code, ms),
+                _ => held.release(&info, code),
+            }
+        }
+        assert_eq!(held.max(), 3);
+    }
+
+    #[test]
+    fn a_suspect_pressed_alone_later_is_a_real_key() {
+        let mut events = recording(GHOSTING);
+        let end = events.last().unwrap().0;
+        events.push((end + 500, KeyCode::KEY_Q, 1));
+        events.push((end + 600, KeyCode::KEY_Q, 0));
+        assert_eq!(
+            suspects(&analyze(&events)),
+            [(names(&["KEY_I", "KEY_J", "KEY_K"]), "KEY_U".to_string(), 1)]
+        );
+
+        let mut ghosts = analyze(&recording(GHOSTING));
+        ghosts.clear();
+        assert_eq!(ghosts.count(), 0);
+    }
+}
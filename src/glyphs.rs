@@ -0,0 +1,167 @@
+//! Glyphs the terminal can show. The framebuffer console font of the field
+//! image has no box drawing, block or braille characters, they come out blank
+//! or as question marks, and the calibration marker can't be found. On such a
+//! console every frame is rewritten to ASCII before it goes out: lines become
+//! `-` `|` `=` and `+`, blocks `#` and `.`, arrows and triangles `^ v < >`.
+//! Screens keep drawing Unicode and don't need to know.
+//!
+//! The set comes from `render.glyphs` in the config: "unicode", "ascii" or
+//! "auto", which picks ASCII on the Linux console (TERM=linux, or a
+//! framebuffer without X or Wayland).
+
+use std::env;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use ratatui::buffer::Buffer;
+
+// Set when frames are rewritten to ASCII, see `select`
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GlyphSet {
+    /// Decide from the environment at startup
+    #[default]
+    Auto,
+    Unicode,
+    Ascii,
+}
+
+impl GlyphSet {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(GlyphSet::Auto),
+            "unicode" => Some(GlyphSet::Unicode),
+            "ascii" => Some(GlyphSet::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GlyphSet::Auto => "auto",
+            GlyphSet::Unicode => "unicode",
+            GlyphSet::Ascii => "ascii",
+        }
+    }
+
+    /// Auto resolved against the environment
+    fn resolve(self) -> Self {
+        if self != GlyphSet::Auto {
+            return self;
+        }
+        let term = env::var("TERM").unwrap_or_default();
+        let graphical = ["DISPLAY", "WAYLAND_DISPLAY"]
+            .iter()
+            .any(|var| env::var_os(var).is_some());
+        if term == "linux" || (!graphical && term.is_empty() && Path::new("/dev/fb0").exists()) {
+            GlyphSet::Ascii
+        } else {
+            GlyphSet::Unicode
+        }
+    }
+}
+
+/// Use `set` for the frames drawn from now on
+pub fn select(set: GlyphSet) {
+    ASCII.store(set.resolve() == GlyphSet::Ascii, Ordering::Relaxed);
+}
+
+/// Rewrite a drawn frame to ASCII when that set is selected, called by the run
+/// loop after every screen drew
+pub fn apply(buffer: &mut Buffer) {
+    if ASCII.load(Ordering::Relaxed) {
+        to_ascii(buffer);
+    }
+}
+
+/// Rewrite every cell of `buffer` the console font can't show
+pub fn to_ascii(buffer: &mut Buffer) {
+    for cell in &mut buffer.content {
+        let mut chars = cell.symbol().chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && let Some(ascii) = ascii(c)
+        {
+            cell.set_char(ascii);
+        }
+    }
+}
+
+/// ASCII stand-in for a character the console font lacks, None for characters
+/// to keep (ASCII itself, and Latin-1 letters like Å Ä Ö, which the font has)
+fn ascii(c: char) -> Option<char> {
+    let replacement = match c {
+        // Double lines stay recognizable as the calibration marker
+        '═' => '=',
+        '║' => '|',
+        '─' | '━' | '┄' | '┅' | '┈' | '┉' | '╌' | '╍' | '╴' | '╶' | '╸' | '╺' => {
+            '-'
+        }
+        '│' | '┃' | '┆' | '┇' | '┊' | '┋' | '╎' | '╏' | '╵' | '╷' | '╹' | '╻' => {
+            '|'
+        }
+        // Corners, tees and crosses, single, thick, double and rounded
+        '\u{250C}'..='\u{254B}' | '\u{2552}'..='\u{256C}' | '╭' | '╮' | '╯' | '╰' => '+',
+        '█' | '▓' | '▇' | '▆' | '▅' => '#',
+        '▒' | '▄' | '▃' => ':',
+        '░' | '▂' | '▁' => '.',
+        // Braille dots, the empty pattern stays empty
+        '\u{2800}' => ' ',
+        '\u{2801}'..='\u{28FF}' => '.',
+        '↑' | '▲' => '^',
+        '↓' | '▼' => 'v',
+        '←' | '◀' | '⌫' => '<',
+        '→' | '▶' | '►' | '›' => '>',
+        '◤' | '◥' | '◣' | '◢' => '+',
+        '●' | '•' => '*',
+        '○' => 'o',
+        '✓' => '+',
+        '✗' => 'x',
+        '╱' => '/',
+        '…' => '.',
+        _ => return None,
+    };
+    Some(replacement)
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::layout::Rect;
+
+    use super::*;
+
+    /// `text` on one row, rewritten to ASCII
+    fn rewritten(text: &str) -> String {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, text.chars().count() as u16, 1));
+        buffer.set_string(0, 0, text, ratatui::style::Style::default());
+        to_ascii(&mut buffer);
+        buffer.content.iter().map(|cell| cell.symbol()).collect()
+    }
+
+    #[test]
+    fn boxes_blocks_and_arrows_become_ascii() {
+        assert_eq!(rewritten("┌─┐│└┘╔═╗║╭╮╰╯┼┬┴├┤"), "+-+|+++=+|+++++++++");
+        assert_eq!(rewritten("█▓▒░▄▁"), "##:.:.");
+        assert_eq!(rewritten("↑↓←→▲▼◀▶⌫"), "^v<>^v<><");
+        assert_eq!(rewritten("●•○✓✗╱…"), "**o+x/.");
+        // The empty braille pattern is a blank, any dot a dot
+        assert_eq!(rewritten("\u{2800}\u{2801}\u{28FF}"), " ..");
+    }
+
+    #[test]
+    fn ascii_and_latin_1_are_kept() {
+        let text = "Tangentbord ÅÄÖ åäö 0-9 [x] é";
+        assert_eq!(rewritten(text), text);
+    }
+
+    #[test]
+    fn set_names_round_trip() {
+        for set in [GlyphSet::Auto, GlyphSet::Unicode, GlyphSet::Ascii] {
+            assert_eq!(GlyphSet::from_name(set.name()), Some(set));
+        }
+        assert_eq!(GlyphSet::from_name("braille"), None);
+        // Only Auto looks at the environment
+        assert_eq!(GlyphSet::Ascii.resolve(), GlyphSet::Ascii);
+        assert_eq!(GlyphSet::Unicode.resolve(), GlyphSet::Unicode);
+    }
+}
@@ -0,0 +1,232 @@
+//! Guided keyboard verification: the operator is asked for one key at a time
+//! instead of pressing keys freely.
+
+use std::time::{Duration, Instant};
+
+use evdev::KeyCode;
+
+use crate::keyboard_layouts::{Key, KeyboardLayout};
+use crate::sim::Rng;
+
+/// A target not pressed within this time is marked missing (or mismatched if
+/// other keys arrived meanwhile) and the run moves on
+pub const GUIDED_KEY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOrder {
+    /// Reading order of the layout
+    Layout,
+    /// Shuffled, so operators have to look instead of pressing from memory
+    Random,
+    /// Only the keys that failed in the previous pass
+    FailedOnly,
+}
+
+impl KeyOrder {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyOrder::Layout => "layout",
+            KeyOrder::Random => "random",
+            KeyOrder::FailedOnly => "failed keys only",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyOutcome {
+    Pending,
+    Passed,
+    /// Nothing arrived before the timeout
+    Missing,
+    /// Only other keys arrived before the timeout
+    Mismatched,
+}
+
+impl KeyOutcome {
+    pub fn name(self) -> &'static str {
+        match self {
+            KeyOutcome::Pending => "pending",
+            KeyOutcome::Passed => "passed",
+            KeyOutcome::Missing => "missing",
+            KeyOutcome::Mismatched => "mismatched",
+        }
+    }
+
+    fn failed(self) -> bool {
+        matches!(self, KeyOutcome::Missing | KeyOutcome::Mismatched)
+    }
+}
+
+pub struct GuidedKey {
+    pub key: Key,
+    /// Presses while this key was the target, including wrong keys, over all passes
+    pub attempts: u32,
+    pub outcome: KeyOutcome,
+    // Presses in the current pass, to tell missing from mismatched
+    pass_attempts: u32,
+    // Codes of the key seen while it was the target
+    seen: Vec<KeyCode>,
+}
+
+pub struct GuidedRun {
+    pub order: KeyOrder,
+    pub keys: Vec<GuidedKey>,
+    // Indices into `keys` in the order they are asked for
+    sequence: Vec<usize>,
+    position: usize,
+    target_since: Instant,
+}
+
+impl GuidedRun {
+    /// Every key of the layout, in reading order or shuffled with `seed`
+    pub fn new(layout: KeyboardLayout, order: KeyOrder, seed: u64, now: Instant) -> Self {
+        let keys: Vec<GuidedKey> = layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .filter(|key| !key.is_continuation())
+            .map(|key| GuidedKey {
+                key: *key,
+                attempts: 0,
+                outcome: KeyOutcome::Pending,
+                pass_attempts: 0,
+                seen: Vec::new(),
+            })
+            .collect();
+
+        let mut sequence: Vec<usize> = (0..keys.len()).collect();
+        if order == KeyOrder::Random {
+            shuffle(&mut sequence, seed);
+        }
+
+        GuidedRun {
+            order,
+            keys,
+            sequence,
+            position: 0,
+            target_since: now,
+        }
+    }
+
+    /// Another pass over the keys that failed in this one, keeping their order.
+    /// Failed keys `skipped` keeps out keep their outcome.
+    pub fn retest_failed(&mut self, now: Instant, skipped: impl Fn(&Key) -> bool) {
+        self.sequence.retain(|&index| {
+            let key = &self.keys[index];
+            key.outcome.failed() && !skipped(&key.key)
+        });
+        for &index in &self.sequence {
+            let key = &mut self.keys[index];
+            key.outcome = KeyOutcome::Pending;
+            key.pass_attempts = 0;
+            key.seen.clear();
+        }
+        self.order = KeyOrder::FailedOnly;
+        self.position = 0;
+        self.target_since = now;
+    }
+
+    /// Leave keys out of the pass before it starts, e.g. keys known to be broken
+    pub fn skip(&mut self, skipped: impl Fn(&Key) -> bool) {
+        if self.position > 0 {
+            return;
+        }
+        self.sequence
+            .retain(|&index| !skipped(&self.keys[index].key));
+    }
+
+    pub fn target(&self) -> Option<&GuidedKey> {
+        self.sequence
+            .get(self.position)
+            .map(|&index| &self.keys[index])
+    }
+
+    /// (keys asked so far, keys in this pass)
+    pub fn progress(&self) -> (usize, usize) {
+        (self.position, self.sequence.len())
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.position >= self.sequence.len()
+    }
+
+    pub fn time_left(&self, now: Instant) -> Duration {
+        GUIDED_KEY_TIMEOUT.saturating_sub(now.duration_since(self.target_since))
+    }
+
+    /// Keys of this pass with the given outcome
+    pub fn count(&self, outcome: KeyOutcome) -> usize {
+        self.sequence
+            .iter()
+            .filter(|&&index| self.keys[index].outcome == outcome)
+            .count()
+    }
+
+    pub fn any_failed(&self) -> bool {
+        self.keys.iter().any(|key| key.outcome.failed())
+    }
+
+    /// Keys missing or mismatched in their last pass
+    pub fn failed_keys(&self) -> impl Iterator<Item = &Key> {
+        self.keys
+            .iter()
+            .filter(|key| key.outcome.failed())
+            .map(|key| &key.key)
+    }
+
+    pub fn outcome_of(&self, key: &Key) -> Option<KeyOutcome> {
+        self.keys
+            .iter()
+            .find(|guided| guided.key.is_same_key(key))
+            .map(|guided| guided.outcome)
+    }
+
+    /// Feed one key press. The target passes once all its codes (or any one, for
+    /// "any of" keys) have been seen.
+    pub fn press(&mut self, code: KeyCode, now: Instant) {
+        let Some(&index) = self.sequence.get(self.position) else {
+            return;
+        };
+        let target = &mut self.keys[index];
+        target.attempts += 1;
+        target.pass_attempts += 1;
+
+        if target.key.codes.contains(&code) && !target.seen.contains(&code) {
+            target.seen.push(code);
+        }
+        if target.key.is_complete(|code| target.seen.contains(&code)) {
+            target.outcome = KeyOutcome::Passed;
+            self.advance(now);
+        }
+    }
+
+    /// Give up on the target once it timed out
+    pub fn tick(&mut self, now: Instant) {
+        if self.is_finished() || self.time_left(now) > Duration::ZERO {
+            return;
+        }
+
+        let target = &mut self.keys[self.sequence[self.position]];
+        target.outcome = if target.pass_attempts > 0 {
+            KeyOutcome::Mismatched
+        } else {
+            KeyOutcome::Missing
+        };
+        self.advance(now);
+    }
+
+    fn advance(&mut self, now: Instant) {
+        self.position += 1;
+        self.target_since = now;
+    }
+}
+
+// Fisher-Yates
+fn shuffle(items: &mut [usize], seed: u64) {
+    let mut rng = Rng::new(seed);
+    for i in (1..items.len()).rev() {
+        let j = (rng.next_u64() % (i as u64 + 1)) as usize;
+        items.swap(i, j);
+    }
+}
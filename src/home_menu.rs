@@ -0,0 +1,328 @@
+//! The home screen's menu as data, apart from drawing it: groups of entries
+//! one level deep, built from the tests this machine's hardware supports.
+//! Only one level is shown at a time, so the list fits the 25-line console
+//! and the quick-launch digits 1-9 go to the shown entries. A group with no
+//! entry left on this hardware isn't listed.
+
+use crate::ScreenId;
+use crate::machine_detect::ComputerModel;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Entry {
+    Screen {
+        label: &'static str,
+        screen: ScreenId,
+    },
+    Group {
+        label: &'static str,
+        entries: Vec<Entry>,
+    },
+    /// Last row of a group, up to the top level for touch-only units
+    Back,
+}
+
+impl Entry {
+    fn screen(label: &'static str, screen: ScreenId) -> Self {
+        Entry::Screen { label, screen }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Entry::Screen { label, .. } | Entry::Group { label, .. } => label,
+            Entry::Back => "Back",
+        }
+    }
+}
+
+/// Which tests the hardware allows, from the model profile
+#[derive(Debug, Clone, Copy)]
+pub struct Hardware {
+    pub mouse: bool,
+    pub touchscreen: bool,
+    pub trackpad: bool,
+}
+
+impl Hardware {
+    pub fn of(model: ComputerModel) -> Self {
+        Hardware {
+            mouse: model.has_mouse(),
+            touchscreen: model.has_touchscreen(),
+            trackpad: model.has_trackpad(),
+        }
+    }
+}
+
+/// What choosing an entry did
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activated {
+    /// Went into a group or back up, the menu shows another level
+    Moved,
+    Run(ScreenId),
+}
+
+pub struct Menu {
+    top: Vec<Entry>,
+    // Index in `top` of the group shown, None at the top level
+    open: Option<usize>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn for_hardware(hardware: Hardware) -> Self {
+        let input_tests = [
+            (true, "Keyboard Test", ScreenId::KeyboardTest),
+            (hardware.mouse, "Mouse Test", ScreenId::MouseTest),
+            (
+                hardware.touchscreen,
+                "Touchscreen Test",
+                ScreenId::TouchscreenTest,
+            ),
+            (hardware.trackpad, "Trackpad Test", ScreenId::TrackpadTest),
+        ];
+        let groups = [
+            (
+                "Input tests",
+                input_tests
+                    .into_iter()
+                    .filter(|(present, ..)| *present)
+                    .map(|(_, label, screen)| Entry::screen(label, screen))
+                    .collect(),
+            ),
+            (
+                "Diagnostics",
+                vec![Entry::screen("Rescan hardware", ScreenId::Rescan)],
+            ),
+            (
+                "Settings",
+                vec![Entry::screen("Session details", ScreenId::SessionInfo)],
+            ),
+        ];
+
+        let mut top: Vec<Entry> = groups
+            .into_iter()
+            .filter(|(_, entries): &(_, Vec<Entry>)| !entries.is_empty())
+            .map(|(label, mut entries)| {
+                entries.push(Entry::Back);
+                Entry::Group { label, entries }
+            })
+            .collect();
+        top.push(Entry::screen("Exit", ScreenId::Exit));
+        Menu {
+            top,
+            open: None,
+            selected: 0,
+        }
+    }
+
+    pub fn for_model(model: ComputerModel) -> Self {
+        Menu::for_hardware(Hardware::of(model))
+    }
+
+    /// Entries of the level shown
+    pub fn entries(&self) -> &[Entry] {
+        match self.open.map(|index| &self.top[index]) {
+            Some(Entry::Group { entries, .. }) => entries,
+            _ => &self.top,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Label of the open group, None at the top level
+    pub fn breadcrumb(&self) -> Option<&'static str> {
+        self.open.map(|index| self.top[index].label())
+    }
+
+    pub fn move_selection(&mut self, down: bool) {
+        let len = self.entries().len();
+        self.selected = if down {
+            (self.selected + 1) % len
+        } else {
+            (self.selected + len - 1) % len
+        };
+    }
+
+    /// Highlight entry `index` of the level shown, if there is one
+    pub fn select(&mut self, index: usize) {
+        if index < self.entries().len() {
+            self.selected = index;
+        }
+    }
+
+    /// Choose entry `index` of the level shown: into a group, back up or the
+    /// screen to run. None when there is no such entry.
+    pub fn activate(&mut self, index: usize) -> Option<Activated> {
+        match self.entries().get(index)? {
+            Entry::Screen { screen, .. } => Some(Activated::Run(*screen)),
+            Entry::Group { .. } => {
+                self.open = Some(index);
+                self.selected = 0;
+                Some(Activated::Moved)
+            }
+            Entry::Back => {
+                self.back();
+                Some(Activated::Moved)
+            }
+        }
+    }
+
+    /// Up to the top level with the group left highlighted. False when
+    /// already there.
+    pub fn back(&mut self) -> bool {
+        match self.open.take() {
+            Some(index) => {
+                self.selected = index;
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EVERYTHING: Hardware = Hardware {
+        mouse: true,
+        touchscreen: true,
+        trackpad: true,
+    };
+
+    fn labels(menu: &Menu) -> Vec<&'static str> {
+        menu.entries().iter().map(Entry::label).collect()
+    }
+
+    /// Labels of the input tests on `hardware`
+    fn input_tests(hardware: Hardware) -> Vec<&'static str> {
+        let mut menu = Menu::for_hardware(hardware);
+        assert_eq!(menu.activate(0), Some(Activated::Moved));
+        assert_eq!(menu.breadcrumb(), Some("Input tests"));
+        labels(&menu)
+    }
+
+    #[test]
+    fn the_top_level_lists_the_groups_then_exit() {
+        let menu = Menu::for_hardware(EVERYTHING);
+        assert_eq!(
+            labels(&menu),
+            ["Input tests", "Diagnostics", "Settings", "Exit"]
+        );
+        assert_eq!(menu.breadcrumb(), None);
+        assert_eq!(menu.selected(), 0);
+    }
+
+    #[test]
+    fn input_tests_follow_the_hardware() {
+        assert_eq!(
+            input_tests(EVERYTHING),
+            [
+                "Keyboard Test",
+                "Mouse Test",
+                "Touchscreen Test",
+                "Trackpad Test",
+                "Back"
+            ]
+        );
+        // A keyboard is always there to test
+        let nothing = Hardware {
+            mouse: false,
+            touchscreen: false,
+            trackpad: false,
+        };
+        assert_eq!(input_tests(nothing), ["Keyboard Test", "Back"]);
+        let touch_only = Hardware {
+            touchscreen: true,
+            ..nothing
+        };
+        assert_eq!(
+            input_tests(touch_only),
+            ["Keyboard Test", "Touchscreen Test", "Back"]
+        );
+    }
+
+    #[test]
+    fn every_model_gets_the_tests_its_profile_allows() {
+        for model in ComputerModel::ALL {
+            let tests = input_tests(Hardware::of(model));
+            for (present, label) in [
+                (model.has_mouse(), "Mouse Test"),
+                (model.has_touchscreen(), "Touchscreen Test"),
+                (model.has_trackpad(), "Trackpad Test"),
+            ] {
+                assert_eq!(tests.contains(&label), present, "{model:?} {label}");
+            }
+        }
+        // The GPS unit has neither mouse nor trackpad, the RS11 no touchscreen
+        assert_eq!(
+            input_tests(Hardware::of(ComputerModel::DatorBBFältGPS)),
+            ["Keyboard Test", "Touchscreen Test", "Back"]
+        );
+        assert_eq!(
+            input_tests(Hardware::of(ComputerModel::DatorBärbarRS11)),
+            ["Keyboard Test", "Mouse Test", "Trackpad Test", "Back"]
+        );
+    }
+
+    #[test]
+    fn the_selection_wraps_within_the_level_shown() {
+        let mut menu = Menu::for_hardware(EVERYTHING);
+        menu.move_selection(false);
+        assert_eq!(menu.selected(), 3);
+        menu.move_selection(true);
+        assert_eq!(menu.selected(), 0);
+
+        menu.activate(1);
+        assert_eq!(labels(&menu), ["Rescan hardware", "Back"]);
+        assert_eq!(menu.selected(), 0);
+        menu.move_selection(true);
+        menu.move_selection(true);
+        assert_eq!(menu.selected(), 0);
+
+        // Only the entries shown can be picked
+        menu.select(3);
+        assert_eq!(menu.selected(), 0);
+        assert_eq!(menu.activate(2), None);
+        menu.select(1);
+        assert_eq!(menu.selected(), 1);
+    }
+
+    #[test]
+    fn groups_open_and_close_with_the_group_left_highlighted() {
+        let mut menu = Menu::for_hardware(EVERYTHING);
+        assert!(!menu.back());
+        assert_eq!(menu.activate(2), Some(Activated::Moved));
+        assert_eq!(menu.breadcrumb(), Some("Settings"));
+        assert_eq!(
+            menu.activate(0),
+            Some(Activated::Run(ScreenId::SessionInfo))
+        );
+        // Running a test stays on its level
+        assert_eq!(menu.breadcrumb(), Some("Settings"));
+
+        // The Back row, for touch-only units
+        assert_eq!(menu.activate(1), Some(Activated::Moved));
+        assert_eq!(menu.breadcrumb(), None);
+        assert_eq!(menu.selected(), 2);
+
+        menu.activate(0);
+        assert!(menu.back());
+        assert_eq!((menu.breadcrumb(), menu.selected()), (None, 0));
+        assert_eq!(menu.activate(3), Some(Activated::Run(ScreenId::Exit)));
+    }
+
+    #[test]
+    fn indexes_count_within_the_level_shown() {
+        let mut menu = Menu::for_hardware(EVERYTHING);
+        menu.activate(0);
+        assert_eq!(
+            menu.activate(2),
+            Some(Activated::Run(ScreenId::TouchscreenTest))
+        );
+        menu.back();
+        menu.activate(1);
+        assert_eq!(menu.activate(0), Some(Activated::Run(ScreenId::Rescan)));
+    }
+}
@@ -0,0 +1,285 @@
+//! Keyboard autorepeat settings (EV_REP). The repeat test writes presets to the
+//! keyboard and measures whether held keys follow them. Every device changed is
+//! remembered with its original settings, which go back when the keyboard test
+//! is left, the session ends or the tool panics.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::{Mutex, Once};
+
+use evdev::{AutoRepeat, Device, KeyCode};
+use once_cell::sync::Lazy;
+
+use crate::{event_handler::DeviceInfo, logging, report::RepeatCheck};
+
+/// Offered in the repeat test: the usual desktop rate and a deliberately slow one
+pub const PRESETS: [RepeatSettings; 2] = [
+    RepeatSettings {
+        delay_ms: 250,
+        period_ms: 33,
+    },
+    RepeatSettings {
+        delay_ms: 1000,
+        period_ms: 500,
+    },
+];
+
+// The kernel's repeat timer ticks in jiffies (4 ms at HZ=250), and the tick
+// thread adds a little on top
+const DELAY_TOLERANCE_MS: u32 = 20;
+const PERIOD_TOLERANCE_MS: u32 = 5;
+const RELATIVE_TOLERANCE: f32 = 0.15;
+
+// Repeats kept per hold, plenty for a median
+const MAX_REPEATS: usize = 500;
+
+// Settings of every device changed, from before the first change, by path
+static ORIGINALS: Lazy<Mutex<HashMap<String, RepeatSettings>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+static PANIC_RESTORE: Once = Once::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RepeatSettings {
+    /// Hold time before the first repeat
+    pub delay_ms: u32,
+    /// Time between repeats
+    pub period_ms: u32,
+}
+
+impl RepeatSettings {
+    pub fn rate_hz(&self) -> f32 {
+        1000.0 / self.period_ms.max(1) as f32
+    }
+
+    /// e.g. "250 ms / 30.3 Hz"
+    pub fn label(&self) -> String {
+        format!("{} ms / {:.1} Hz", self.delay_ms, self.rate_hz())
+    }
+
+    /// Measured settings close enough to configured ones
+    pub fn tracks(&self, configured: &RepeatSettings) -> bool {
+        let close = |measured: u32, configured: u32, absolute: u32| {
+            let allowed = absolute.max((configured as f32 * RELATIVE_TOLERANCE) as u32);
+            measured.abs_diff(configured) <= allowed
+        };
+        close(self.delay_ms, configured.delay_ms, DELAY_TOLERANCE_MS)
+            && close(self.period_ms, configured.period_ms, PERIOD_TOLERANCE_MS)
+    }
+}
+
+/// The device's autorepeat settings, None when it can't be opened or has no EV_REP
+pub fn read_settings(path: &str) -> Option<RepeatSettings> {
+    let repeat = Device::open(path).ok()?.get_auto_repeat()?;
+    Some(RepeatSettings {
+        delay_ms: repeat.delay,
+        period_ms: repeat.period,
+    })
+}
+
+/// Write new settings to the device, remembering the original ones for `restore_all`
+pub fn write_settings(path: &str, settings: RepeatSettings) -> io::Result<()> {
+    let mut device = Device::open(path)?;
+    let original = device
+        .get_auto_repeat()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no autorepeat"))?;
+
+    device.update_auto_repeat(&AutoRepeat {
+        delay: settings.delay_ms,
+        period: settings.period_ms,
+    })?;
+
+    originals()
+        .entry(path.to_string())
+        .or_insert(RepeatSettings {
+            delay_ms: original.delay,
+            period_ms: original.period,
+        });
+    PANIC_RESTORE.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            restore_all();
+            previous(info);
+        }));
+    });
+    Ok(())
+}
+
+/// Put the device's original settings back, if they were changed
+pub fn restore(path: &str) {
+    let original = originals().remove(path);
+    if let Some(original) = original {
+        write_original(path, original);
+    }
+}
+
+/// Put the original settings back on every device changed
+pub fn restore_all() {
+    let changed: Vec<(String, RepeatSettings)> = originals().drain().collect();
+    for (path, original) in changed {
+        write_original(&path, original);
+    }
+}
+
+fn write_original(path: &str, original: RepeatSettings) {
+    let written = Device::open(path).and_then(|mut device| {
+        device.update_auto_repeat(&AutoRepeat {
+            delay: original.delay_ms,
+            period: original.period_ms,
+        })
+    });
+    if let Err(e) = written {
+        logging::warn(&format!(
+            "Cannot restore the autorepeat settings of {path}: {e}"
+        ));
+    }
+}
+
+// A panic while the lock was held must not keep the settings from going back
+fn originals() -> std::sync::MutexGuard<'static, HashMap<String, RepeatSettings>> {
+    ORIGINALS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// One held key: the press and the repeats that followed, as event timestamps
+struct Hold {
+    code: KeyCode,
+    pressed_ms: u64,
+    repeats: Vec<u64>,
+}
+
+impl Hold {
+    /// Delay to the first repeat and the median time between repeats, once
+    /// there are at least two
+    fn measured(&self) -> Option<RepeatSettings> {
+        let [first, _, ..] = self.repeats[..] else {
+            return None;
+        };
+        let mut gaps: Vec<u64> = self
+            .repeats
+            .windows(2)
+            .map(|w| w[1].saturating_sub(w[0]))
+            .collect();
+        gaps.sort_unstable();
+        Some(RepeatSettings {
+            delay_ms: first.saturating_sub(self.pressed_ms) as u32,
+            period_ms: gaps[gaps.len() / 2] as u32,
+        })
+    }
+}
+
+/// Repeat test of the keyboard first pressed: its settings, the presets written
+/// to it and the measured holds
+pub struct RepeatTest {
+    device: Option<DeviceInfo>,
+    /// Settings the keyboard is set to, None without EV_REP
+    configured: Option<RepeatSettings>,
+    /// The keyboard refused a new setting
+    rejected: bool,
+    hold: Option<Hold>,
+    checks: Vec<RepeatCheck>,
+}
+
+impl RepeatTest {
+    pub fn new() -> Self {
+        RepeatTest {
+            device: None,
+            configured: None,
+            rejected: false,
+            hold: None,
+            checks: Vec::new(),
+        }
+    }
+
+    pub fn device(&self) -> Option<&DeviceInfo> {
+        self.device.as_ref()
+    }
+
+    pub fn configured(&self) -> Option<RepeatSettings> {
+        self.configured
+    }
+
+    /// Settings can't be changed on this keyboard
+    pub fn not_adjustable(&self) -> bool {
+        self.rejected || (self.device.is_some() && self.configured.is_none())
+    }
+
+    /// A press from `info`. Number keys pick a preset (0 the original settings),
+    /// any other key starts a new measured hold.
+    pub fn press(&mut self, code: KeyCode, info: &DeviceInfo, timestamp: u64) {
+        if self.device.is_none() {
+            self.configured = read_settings(&info.path);
+            self.device = Some(info.clone());
+        }
+        if self.device.as_ref().is_some_and(|d| d.path != info.path) {
+            return;
+        }
+        self.finish_hold();
+
+        let path = info.path.clone();
+        let preset = match code {
+            KeyCode::KEY_1 => Some(PRESETS[0]),
+            KeyCode::KEY_2 => Some(PRESETS[1]),
+            KeyCode::KEY_0 => {
+                restore(&path);
+                self.configured = read_settings(&path);
+                return;
+            }
+            _ => None,
+        };
+        match preset {
+            Some(preset) if !self.not_adjustable() => match write_settings(&path, preset) {
+                Ok(()) => self.configured = Some(preset),
+                Err(_) => self.rejected = true,
+            },
+            Some(_) => {}
+            None => {
+                self.hold = Some(Hold {
+                    code,
+                    pressed_ms: timestamp,
+                    repeats: Vec::new(),
+                });
+            }
+        }
+    }
+
+    /// An autorepeat event, continuing the hold of the same key
+    pub fn repeat(&mut self, code: KeyCode, info: &DeviceInfo, timestamp: u64) {
+        if let Some(hold) = &mut self.hold
+            && hold.code == code
+            && self.device.as_ref().is_some_and(|d| d.path == info.path)
+            && hold.repeats.len() < MAX_REPEATS
+        {
+            hold.repeats.push(timestamp);
+        }
+    }
+
+    /// Settings measured from the current hold so far
+    pub fn measured(&self) -> Option<RepeatSettings> {
+        self.hold.as_ref()?.measured()
+    }
+
+    /// Record the current hold as a check against the configured settings
+    pub fn finish_hold(&mut self) {
+        let Some(hold) = self.hold.take() else {
+            return;
+        };
+        if let (Some(measured), Some(configured), Some(device)) =
+            (hold.measured(), self.configured, &self.device)
+        {
+            self.checks.push(RepeatCheck {
+                device: device.name.clone(),
+                key: format!("{:?}", hold.code),
+                configured_delay_ms: configured.delay_ms,
+                configured_period_ms: configured.period_ms,
+                measured_delay_ms: measured.delay_ms,
+                measured_period_ms: measured.period_ms,
+                within_tolerance: measured.tracks(&configured),
+            });
+        }
+    }
+
+    pub fn checks(&self) -> &[RepeatCheck] {
+        &self.checks
+    }
+}
@@ -0,0 +1,263 @@
+//! Collapses duplicate key events from keyboards that enumerate as several
+//! event nodes reporting the same keycodes.
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use evdev::KeyCode;
+
+use crate::event_handler::DeviceInfo;
+
+/// Events closer together than this are considered the same physical key event
+pub const DEDUP_WINDOW: Duration = Duration::from_millis(5);
+
+/// Identity of the physical device an event node belongs to.
+///
+/// Nodes of one device share vendor/product and their phys path only differs
+/// in the trailing `/inputN`. Devices without a phys path are never grouped.
+pub fn physical_device_key(info: &DeviceInfo) -> String {
+    match phys_base(info) {
+        Some(base) => format!("{:04x}:{:04x}:{}", info.vendor, info.product, base),
+        None => info.path.clone(),
+    }
+}
+
+/// Whether both nodes belong to one physical device, as `physical_device_key`
+/// would tell without building the keys
+pub fn same_physical_device(a: &DeviceInfo, b: &DeviceInfo) -> bool {
+    match (phys_base(a), phys_base(b)) {
+        (Some(base_a), Some(base_b)) => {
+            a.vendor == b.vendor && a.product == b.product && base_a == base_b
+        }
+        (None, None) => a.path == b.path,
+        _ => false,
+    }
+}
+
+/// The phys path without the trailing `/inputN`, None without a phys path
+fn phys_base(info: &DeviceInfo) -> Option<&str> {
+    let phys = info.phys.as_deref().filter(|phys| !phys.is_empty())?;
+    Some(match phys.rsplit_once("/input") {
+        Some((base, suffix)) if suffix.chars().all(|c| c.is_ascii_digit()) => base,
+        _ => phys,
+    })
+}
+
+/// State kept per physical device. Devices are told apart with
+/// `same_physical_device`, so finding one on every event builds no key. Kept
+/// in the order the devices were first seen.
+pub struct PhysicalDevices<T> {
+    // A node of each device, as first seen, with its state
+    devices: Vec<(DeviceInfo, T)>,
+}
+
+impl<T> Default for PhysicalDevices<T> {
+    fn default() -> Self {
+        PhysicalDevices {
+            devices: Vec::new(),
+        }
+    }
+}
+
+impl<T> PhysicalDevices<T> {
+    /// State of the device `info` is a node of, made with `new` the first
+    /// time the device is seen
+    pub fn get_or_insert_with(&mut self, info: &DeviceInfo, new: impl FnOnce() -> T) -> &mut T {
+        let index = match self
+            .devices
+            .iter()
+            .position(|(seen, _)| same_physical_device(seen, info))
+        {
+            Some(index) => index,
+            None => {
+                self.devices.push((info.clone(), new()));
+                self.devices.len() - 1
+            }
+        };
+        &mut self.devices[index].1
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.devices.iter().map(|(_, state)| state)
+    }
+
+    pub fn clear(&mut self) {
+        self.devices.clear();
+    }
+}
+
+struct LastEvent {
+    node: String,
+    time: SystemTime,
+}
+
+#[derive(Default)]
+pub struct KeyDeduplicator {
+    last: PhysicalDevices<HashMap<(KeyCode, i32), LastEvent>>,
+}
+
+impl KeyDeduplicator {
+    pub fn new() -> Self {
+        KeyDeduplicator::default()
+    }
+
+    /// Returns true if the event should be forwarded, false if another node of the
+    /// same physical device already reported it within `DEDUP_WINDOW`.
+    pub fn accept(
+        &mut self,
+        info: &DeviceInfo,
+        code: KeyCode,
+        value: i32,
+        time: SystemTime,
+    ) -> bool {
+        let events = self.last.get_or_insert_with(info, HashMap::new);
+        let Some(last) = events.get_mut(&(code, value)) else {
+            events.insert(
+                (code, value),
+                LastEvent {
+                    node: info.path.clone(),
+                    time,
+                },
+            );
+            return true;
+        };
+
+        if last.node != info.path {
+            // Nodes are read on different threads, so either event may be stamped first
+            let apart = match time.duration_since(last.time) {
+                Ok(apart) => apart,
+                Err(e) => e.duration(),
+            };
+            if apart <= DEDUP_WINDOW {
+                return false;
+            }
+            // Into the buffer of the node it replaces
+            last.node.clone_from(&info.path);
+        }
+        last.time = time;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::sim_device;
+
+    fn node(path: &str, phys: Option<&str>, vendor: u16) -> DeviceInfo {
+        DeviceInfo {
+            phys: phys.map(str::to_string),
+            vendor,
+            product: 0x0101,
+            ..sim_device(path, "Industrial keyboard", None)
+        }
+    }
+
+    fn at(ms: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(ms)
+    }
+
+    /// Which events of the stream get through
+    fn accepted(stream: &[(&DeviceInfo, KeyCode, i32, u64)]) -> Vec<bool> {
+        let mut dedup = KeyDeduplicator::new();
+        stream
+            .iter()
+            .map(|(info, code, value, ms)| dedup.accept(info, *code, *value, at(*ms)))
+            .collect()
+    }
+
+    #[test]
+    fn nodes_of_one_keyboard_collapse() {
+        let a = node(
+            "/dev/input/event3",
+            Some("usb-0000:00:14.0-1/input0"),
+            0x1234,
+        );
+        let b = node(
+            "/dev/input/event4",
+            Some("usb-0000:00:14.0-1/input1"),
+            0x1234,
+        );
+        let stream = [
+            (&a, KeyCode::KEY_A, 1, 1000),
+            (&b, KeyCode::KEY_A, 1, 1002),
+            (&b, KeyCode::KEY_A, 0, 1100),
+            // Stamped before the other node's copy, read later
+            (&a, KeyCode::KEY_A, 0, 1097),
+        ];
+        assert_eq!(accepted(&stream), [true, false, true, false]);
+    }
+
+    #[test]
+    fn copies_outside_the_window_are_kept() {
+        let a = node("/dev/input/event3", Some("usb-1/input0"), 0x1234);
+        let b = node("/dev/input/event4", Some("usb-1/input1"), 0x1234);
+        let stream = [
+            (&a, KeyCode::KEY_A, 1, 1000),
+            (
+                &b,
+                KeyCode::KEY_A,
+                1,
+                1000 + DEDUP_WINDOW.as_millis() as u64 + 1,
+            ),
+        ];
+        assert_eq!(accepted(&stream), [true, true]);
+    }
+
+    #[test]
+    fn one_node_is_never_deduplicated_against_itself() {
+        let a = node("/dev/input/event3", Some("usb-1/input0"), 0x1234);
+        let stream = [
+            (&a, KeyCode::KEY_A, 1, 1000),
+            (&a, KeyCode::KEY_A, 0, 1001),
+            (&a, KeyCode::KEY_A, 1, 1002),
+        ];
+        assert_eq!(accepted(&stream), [true, true, true]);
+    }
+
+    #[test]
+    fn separate_keyboards_pressing_together_both_count() {
+        let first = node("/dev/input/event3", Some("usb-1/input0"), 0x1234);
+        let second = node("/dev/input/event5", Some("usb-2/input0"), 0x1234);
+        let other_vendor = node("/dev/input/event6", Some("usb-1/input1"), 0x4321);
+        let stream = [
+            (&first, KeyCode::KEY_ENTER, 1, 1000),
+            (&second, KeyCode::KEY_ENTER, 1, 1000),
+            (&other_vendor, KeyCode::KEY_ENTER, 1, 1001),
+        ];
+        assert_eq!(accepted(&stream), [true, true, true]);
+    }
+
+    #[test]
+    fn nodes_without_phys_are_not_grouped() {
+        let a = node("/dev/input/event3", None, 0x1234);
+        let b = node("/dev/input/event4", Some(""), 0x1234);
+        let stream = [(&a, KeyCode::KEY_A, 1, 1000), (&b, KeyCode::KEY_A, 1, 1000)];
+        assert_eq!(accepted(&stream), [true, true]);
+        assert!(!same_physical_device(&a, &b));
+    }
+
+    #[test]
+    fn grouping_matches_the_key() {
+        let nodes = [
+            node("/dev/input/event3", Some("usb-1/input0"), 0x1234),
+            node("/dev/input/event4", Some("usb-1/input1"), 0x1234),
+            node("/dev/input/event5", Some("usb-1/input1"), 0x4321),
+            node("/dev/input/event6", Some("usb-2/input0"), 0x1234),
+            node("/dev/input/event7", Some("isa0060/serio0/input0"), 0x1234),
+            node("/dev/input/event8", None, 0x1234),
+        ];
+        for a in &nodes {
+            for b in &nodes {
+                assert_eq!(
+                    same_physical_device(a, b),
+                    physical_device_key(a) == physical_device_key(b),
+                    "{} and {}",
+                    a.path,
+                    b.path
+                );
+            }
+        }
+        assert!(same_physical_device(&nodes[0], &nodes[1]));
+    }
+}
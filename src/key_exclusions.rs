@@ -0,0 +1,122 @@
+//! Keys documented as broken on the unit under test, left out of the keyboard
+//! test's pass criteria. A repair depot retests units whose broken key waits
+//! for parts; with the key counted the test can never pass and the burn-in
+//! numbers carry a dead key. An excluded key doesn't count towards completion,
+//! isn't asked for in a guided run, and is listed with its reason in the
+//! report.
+//!
+//! Keys are excluded after a guided run, each with a reason from `REASONS` and
+//! a confirmation, or ahead of the test from the config, keyed by unit serial:
+//! `{"excluded_keys": {"SN1234": {"F5": "Awaiting parts"}}}`. A key is named by
+//! its label or one of its codes ("KEY_F5").
+
+use std::collections::BTreeMap;
+
+use crate::keyboard_layouts::{Key, KeyboardLayout};
+use crate::report::ExcludedKeyResult;
+
+/// Reasons an operator can pick from
+pub const REASONS: [&str; 3] = [
+    "Awaiting parts",
+    "Documented damage",
+    "Not fitted on this unit",
+];
+
+struct Exclusion {
+    key: Key,
+    reason: String,
+    // From the config rather than marked during the test
+    preseeded: bool,
+}
+
+#[derive(Default)]
+pub struct KeyExclusions {
+    excluded: Vec<Exclusion>,
+}
+
+impl KeyExclusions {
+    pub fn new() -> Self {
+        KeyExclusions::default()
+    }
+
+    /// Exclusions of `layout` from the config's entries for the unit, key name
+    /// to reason. Returns the names that match no key of the layout.
+    pub fn seeded(
+        layout: KeyboardLayout,
+        entries: &BTreeMap<String, String>,
+    ) -> (Self, Vec<String>) {
+        let keys: Vec<Key> = layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .filter(|key| !key.is_continuation())
+            .copied()
+            .collect();
+
+        let mut exclusions = KeyExclusions::new();
+        let mut unknown = Vec::new();
+        for (name, reason) in entries {
+            let found = keys.iter().find(|key| {
+                key.label.eq_ignore_ascii_case(name)
+                    || key.codes.iter().any(|code| format!("{code:?}") == *name)
+            });
+            match found {
+                Some(key) if !exclusions.is_excluded(key) => exclusions.excluded.push(Exclusion {
+                    key: *key,
+                    reason: reason.clone(),
+                    preseeded: true,
+                }),
+                Some(_) => {}
+                None => unknown.push(name.clone()),
+            }
+        }
+        (exclusions, unknown)
+    }
+
+    pub fn exclude(&mut self, key: &Key, reason: &str) {
+        if !self.is_excluded(key) {
+            self.excluded.push(Exclusion {
+                key: *key,
+                reason: reason.to_string(),
+                preseeded: false,
+            });
+        }
+    }
+
+    /// Count the key again
+    pub fn include(&mut self, key: &Key) {
+        self.excluded
+            .retain(|exclusion| !exclusion.key.is_same_key(key));
+    }
+
+    pub fn is_excluded(&self, key: &Key) -> bool {
+        self.reason(key).is_some()
+    }
+
+    pub fn reason(&self, key: &Key) -> Option<&str> {
+        self.excluded
+            .iter()
+            .find(|exclusion| exclusion.key.is_same_key(key))
+            .map(|exclusion| exclusion.reason.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.excluded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.excluded.is_empty()
+    }
+
+    pub fn results(&self) -> Vec<ExcludedKeyResult> {
+        self.excluded
+            .iter()
+            .map(|exclusion| ExcludedKeyResult {
+                label: exclusion.key.label.to_string(),
+                reason: exclusion.reason.clone(),
+                preseeded: exclusion.preseeded,
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,67 @@
+//! Press order check for matrix fault diagnosis. The depot has operators press
+//! every key in the layout's reading order; a key whose first press arrives out
+//! of that order usually sits on crossed matrix lines.
+
+use evdev::KeyCode;
+
+use crate::keyboard_layouts::{Key, KeyboardLayout};
+
+/// Keys of the layout in reading order, the order guided runs ask for them in
+pub fn reading_order(layout: KeyboardLayout) -> Vec<Key> {
+    layout
+        .iter()
+        .flat_map(|section| section.iter())
+        .flat_map(|block| block.iter())
+        .flat_map(|row| row.iter())
+        .filter(|key| !key.is_continuation())
+        .copied()
+        .collect()
+}
+
+/// Pressed keys in the order their first press arrived, as indices into `keys`.
+/// A key counts from the first press of any of its codes.
+pub fn press_order(keys: &[Key], first_presses: &[KeyCode]) -> Vec<usize> {
+    let mut order = Vec::new();
+    for code in first_presses {
+        if let Some(index) = keys.iter().position(|key| key.codes.contains(code))
+            && !order.contains(&index)
+        {
+            order.push(index);
+        }
+    }
+    order
+}
+
+/// The entries of `order` that break the reading order: everything off the
+/// longest run that is in order, so a single misplaced key flags just that key
+pub fn out_of_order(order: &[usize]) -> Vec<usize> {
+    if order.is_empty() {
+        return Vec::new();
+    }
+
+    // Longest increasing subsequence, quadratic is plenty for a keyboard
+    let mut length = vec![1usize; order.len()];
+    let mut previous: Vec<Option<usize>> = vec![None; order.len()];
+    for i in 0..order.len() {
+        for j in 0..i {
+            if order[j] < order[i] && length[j] + 1 > length[i] {
+                length[i] = length[j] + 1;
+                previous[i] = Some(j);
+            }
+        }
+    }
+
+    let mut in_order = vec![false; order.len()];
+    let mut at = (0..order.len()).max_by_key(|&i| (length[i], std::cmp::Reverse(i)));
+    while let Some(i) = at {
+        in_order[i] = true;
+        at = previous[i];
+    }
+
+    order
+        .iter()
+        .zip(in_order)
+        .filter(|(_, in_order)| !in_order)
+        .map(|(position, _)| *position)
+        .collect()
+}
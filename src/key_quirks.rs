@@ -0,0 +1,165 @@
+//! Known keyboard quirks by bus and vendor. The internal keyboards on the older
+//! models are PS/2 ("AT Translated Set 2"): Pause sends no release, and some Fn
+//! combinations send KEY_UNKNOWN besides their real code. Those codes aren't
+//! faults, so the keyboard test doesn't list them as unexpected and the report
+//! notes the quirk instead.
+
+use evdev::{BusType, KeyCode};
+
+use crate::event_handler::DeviceInfo;
+
+/// Behavior expected from one kind of keyboard
+#[derive(Debug)]
+pub struct KeyQuirk {
+    pub name: &'static str,
+    pub bus: BusType,
+    /// None matches any vendor on the bus
+    pub vendor: Option<u16>,
+    /// Keys that send a press but never a release
+    pub no_release: &'static [KeyCode],
+    /// Codes the keyboard sends on its own next to real presses
+    pub phantom: &'static [KeyCode],
+}
+
+/// The quirk table, first match wins
+pub const KEY_QUIRKS: &[KeyQuirk] = &[KeyQuirk {
+    name: "AT Translated Set 2",
+    bus: BusType::BUS_I8042,
+    vendor: Some(0x0001),
+    no_release: &[KeyCode::KEY_PAUSE],
+    phantom: &[KeyCode::KEY_UNKNOWN],
+}];
+
+impl KeyQuirk {
+    pub fn matches(&self, info: &DeviceInfo) -> bool {
+        self.bus == info.bus && self.vendor.is_none_or(|vendor| vendor == info.vendor)
+    }
+
+    pub fn is_phantom(&self, code: KeyCode) -> bool {
+        self.phantom.contains(&code)
+    }
+
+    pub fn has_no_release(&self, code: KeyCode) -> bool {
+        self.no_release.contains(&code)
+    }
+
+    /// One line for the report, e.g. "AT Translated Set 2: no release for KEY_PAUSE, ..."
+    pub fn describe(&self) -> String {
+        let codes = |codes: &[KeyCode]| {
+            codes
+                .iter()
+                .map(|code| format!("{code:?}"))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut parts = Vec::new();
+        if !self.no_release.is_empty() {
+            parts.push(format!("no release for {}", codes(self.no_release)));
+        }
+        if !self.phantom.is_empty() {
+            parts.push(format!("phantom {} expected", codes(self.phantom)));
+        }
+        format!("{}: {}", self.name, parts.join(", "))
+    }
+}
+
+/// The quirk entry for a device, None for keyboards that behave
+pub fn quirk_for(info: &DeviceInfo) -> Option<&'static KeyQuirk> {
+    KEY_QUIRKS.iter().find(|quirk| quirk.matches(info))
+}
+
+/// Short name of the bus a device is connected through
+pub fn bus_label(bus: BusType) -> String {
+    match bus {
+        BusType::BUS_I8042 => "PS/2".to_string(),
+        bus => bus.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::Screen;
+    use crate::event_handler::AppEvent;
+    use crate::keyboard_test::KeyboardTestScreen;
+    use crate::machine_detect::ComputerModel;
+    use crate::report::ScreenResult;
+    use crate::sim::sim_device;
+
+    fn keyboard(bus: BusType, vendor: u16) -> DeviceInfo {
+        DeviceInfo {
+            bus,
+            vendor,
+            ..sim_device("/dev/input/event2", "AT Translated Set 2 keyboard", None)
+        }
+    }
+
+    fn key(info: &Arc<DeviceInfo>, code: KeyCode, timestamp: u128, down: bool) -> AppEvent {
+        let info = Arc::clone(info);
+        if down {
+            AppEvent::Key {
+                code,
+                info,
+                repeat: false,
+                timestamp,
+            }
+        } else {
+            AppEvent::KeyReleased {
+                code,
+                info,
+                timestamp,
+            }
+        }
+    }
+
+    #[test]
+    fn the_at_keyboard_is_matched_by_bus_and_vendor() {
+        let quirk = quirk_for(&keyboard(BusType::BUS_I8042, 0x0001)).unwrap();
+        assert_eq!(quirk.name, "AT Translated Set 2");
+        assert!(quirk.has_no_release(KeyCode::KEY_PAUSE));
+        assert!(quirk.is_phantom(KeyCode::KEY_UNKNOWN));
+        assert!(!quirk.has_no_release(KeyCode::KEY_A));
+        assert_eq!(
+            quirk.describe(),
+            "AT Translated Set 2: no release for KEY_PAUSE, phantom KEY_UNKNOWN expected"
+        );
+
+        assert!(quirk_for(&keyboard(BusType::BUS_USB, 0x0001)).is_none());
+        assert!(quirk_for(&keyboard(BusType::BUS_I8042, 0x0002)).is_none());
+        assert_eq!(bus_label(BusType::BUS_I8042), "PS/2");
+    }
+
+    #[test]
+    fn pause_on_an_at_keyboard_never_goes_stuck() {
+        let at = Arc::new(keyboard(BusType::BUS_I8042, 0x0001));
+        let usb = Arc::new(DeviceInfo {
+            path: "/dev/input/event5".to_string(),
+            ..keyboard(BusType::BUS_USB, 0x046d)
+        });
+        let stuck_keys = |info: &Arc<DeviceInfo>| {
+            let mut screen = KeyboardTestScreen::for_model(ComputerModel::DatorBBFält);
+            screen.handle_event(key(info, KeyCode::KEY_ENTER, 0, true));
+            screen.handle_event(key(info, KeyCode::KEY_ENTER, 50, false));
+            // Pause sends its press only, the next keys come long after
+            screen.handle_event(key(info, KeyCode::KEY_PAUSE, 1000, true));
+            for (i, code) in [KeyCode::KEY_A, KeyCode::KEY_B].into_iter().enumerate() {
+                let at = 7000 + i as u128 * 6000;
+                screen.handle_event(key(info, code, at, true));
+                screen.handle_event(key(info, code, at + 80, false));
+            }
+            let Some(ScreenResult::Keyboard(result)) = screen.take_results() else {
+                panic!("no keyboard result");
+            };
+            result
+                .simultaneous
+                .iter()
+                .flat_map(|keyboard| keyboard.stuck_keys.clone())
+                .collect::<Vec<_>>()
+        };
+        assert!(stuck_keys(&at).is_empty(), "{:?}", stuck_keys(&at));
+        // The same press from a keyboard without the quirk lost its release
+        assert_eq!(stuck_keys(&usb), ["KEY_PAUSE"]);
+    }
+}
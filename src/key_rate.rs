@@ -0,0 +1,236 @@
+//! Per-device key press rate tracking, used to throttle keyboards whose
+//! controller floods events (e.g. thousands of KEY_LEFTSHIFT per second).
+
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use evdev::KeyCode;
+
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+/// When a device counts as flooding
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Key presses per second above which a device is considered flooding
+    pub max_per_sec: u32,
+    /// How long the rate must be exceeded before throttling starts
+    pub sustain: Duration,
+}
+
+impl Default for RateLimit {
+    fn default() -> Self {
+        RateLimit {
+            max_per_sec: 200,
+            sustain: Duration::from_secs(2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateVerdict {
+    /// Forward the event
+    Pass,
+    /// Drop the event, the device is throttled
+    Drop,
+    /// Drop the event and report the device, sent once when throttling starts and
+    /// again every window it keeps flooding
+    Fault { rate: u32, code: KeyCode },
+}
+
+/// Rate tracking for one device. Every listener owns one, so a flooding keyboard
+/// never throttles a healthy one.
+pub struct KeyRateLimiter {
+    limit: RateLimit,
+    window_start: Option<SystemTime>,
+    count: u32,
+    // Start of the first window in the current run of over-limit windows
+    over_since: Option<SystemTime>,
+    throttled: bool,
+    // Most pressed key in the current window, the one to blame in a fault
+    codes: HashMap<KeyCode, u32>,
+}
+
+impl KeyRateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        KeyRateLimiter {
+            limit,
+            window_start: None,
+            count: 0,
+            over_since: None,
+            throttled: false,
+            codes: HashMap::new(),
+        }
+    }
+
+    /// Count one key press at `time` and decide what to do with it. Takes the event
+    /// time explicitly so the logic runs the same on recorded input.
+    pub fn check(&mut self, code: KeyCode, time: SystemTime) -> RateVerdict {
+        let window_start = *self.window_start.get_or_insert(time);
+
+        let mut fault = None;
+
+        let elapsed = time.duration_since(window_start).unwrap_or_default();
+        if elapsed >= RATE_WINDOW {
+            // Close the window, normalizing in case events paused for a while
+            let per_sec = (self.count as f32 / elapsed.as_secs_f32()) as u32;
+
+            if per_sec > self.limit.max_per_sec {
+                let since = *self.over_since.get_or_insert(window_start);
+                let sustained =
+                    time.duration_since(since).unwrap_or_default() >= self.limit.sustain;
+                if sustained {
+                    self.throttled = true;
+                    let worst = self
+                        .codes
+                        .iter()
+                        .max_by_key(|(_, count)| **count)
+                        .map_or(code, |(code, _)| *code);
+                    fault = Some(RateVerdict::Fault {
+                        rate: per_sec,
+                        code: worst,
+                    });
+                }
+            } else {
+                // A calm window ends the flood
+                self.over_since = None;
+                self.throttled = false;
+            }
+
+            self.window_start = Some(time);
+            self.count = 0;
+            self.codes.clear();
+        }
+
+        self.count += 1;
+        *self.codes.entry(code).or_insert(0) += 1;
+
+        match fault {
+            Some(fault) => fault,
+            None if self.throttled => RateVerdict::Drop,
+            None => RateVerdict::Pass,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(ms: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(ms)
+    }
+
+    /// Verdicts for presses of `code` at the given times (ms)
+    fn verdicts(
+        limiter: &mut KeyRateLimiter,
+        code: KeyCode,
+        times: impl IntoIterator<Item = u64>,
+    ) -> Vec<(u64, RateVerdict)> {
+        times
+            .into_iter()
+            .map(|ms| (ms, limiter.check(code, at(ms))))
+            .collect()
+    }
+
+    #[test]
+    fn typing_speed_is_never_throttled() {
+        let mut limiter = KeyRateLimiter::new(RateLimit::default());
+        // 20 presses a second for ten seconds, fast typing
+        let seen = verdicts(&mut limiter, KeyCode::KEY_A, (0..200).map(|i| i * 50));
+        assert!(
+            seen.iter()
+                .all(|(_, verdict)| *verdict == RateVerdict::Pass)
+        );
+    }
+
+    #[test]
+    fn sustained_flood_faults_then_drops() {
+        let mut limiter = KeyRateLimiter::new(RateLimit::default());
+        let seen = verdicts(&mut limiter, KeyCode::KEY_LEFTSHIFT, 0..3500);
+
+        // Over the limit from the first window, but only sustained after 2 s
+        assert!(seen[..2000].iter().all(|(_, v)| *v == RateVerdict::Pass));
+        let fault = RateVerdict::Fault {
+            rate: 1000,
+            code: KeyCode::KEY_LEFTSHIFT,
+        };
+        assert_eq!(seen[2000], (2000, fault));
+        assert!(
+            seen[2001..3000]
+                .iter()
+                .all(|(_, v)| *v == RateVerdict::Drop)
+        );
+        // Reported again every window it keeps flooding
+        assert_eq!(seen[3000], (3000, fault));
+    }
+
+    #[test]
+    fn short_burst_is_tolerated() {
+        let mut limiter = KeyRateLimiter::new(RateLimit::default());
+        // One second at 1000/s, then typing speed
+        let mut times: Vec<u64> = (0..1000).collect();
+        times.extend((0..40).map(|i| 1000 + i * 100));
+        let seen = verdicts(&mut limiter, KeyCode::KEY_A, times);
+        assert!(
+            seen.iter()
+                .all(|(_, verdict)| *verdict == RateVerdict::Pass)
+        );
+    }
+
+    #[test]
+    fn calm_window_ends_the_throttle() {
+        let mut limiter = KeyRateLimiter::new(RateLimit::default());
+        let mut times: Vec<u64> = (0..2500).collect();
+        times.extend((0..20).map(|i| 2700 + i * 200));
+        let seen = verdicts(&mut limiter, KeyCode::KEY_A, times);
+
+        let first_pass = seen[2000..]
+            .iter()
+            .position(|(_, verdict)| *verdict == RateVerdict::Pass)
+            .map(|index| seen[2000 + index].0);
+        // The window holding the tail of the flood is still over the limit
+        assert_eq!(first_pass, Some(4100));
+        assert!(
+            seen.iter()
+                .filter(|(ms, _)| *ms >= 4100)
+                .all(|(_, verdict)| *verdict == RateVerdict::Pass)
+        );
+    }
+
+    #[test]
+    fn fault_names_the_most_pressed_key() {
+        let mut limiter = KeyRateLimiter::new(RateLimit::default());
+        let mut fault = None;
+        for ms in 0..=2000 {
+            let code = if ms % 4 == 0 {
+                KeyCode::KEY_A
+            } else {
+                KeyCode::KEY_LEFTSHIFT
+            };
+            fault = Some(limiter.check(code, at(ms)));
+        }
+        assert!(matches!(
+            fault,
+            Some(RateVerdict::Fault {
+                code: KeyCode::KEY_LEFTSHIFT,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn limiters_are_independent() {
+        let mut flooding = KeyRateLimiter::new(RateLimit::default());
+        let mut healthy = KeyRateLimiter::new(RateLimit::default());
+        for ms in 0..2500 {
+            flooding.check(KeyCode::KEY_LEFTSHIFT, at(ms));
+            if ms % 100 == 0 {
+                assert_eq!(healthy.check(KeyCode::KEY_A, at(ms)), RateVerdict::Pass);
+            }
+        }
+        assert_eq!(
+            flooding.check(KeyCode::KEY_LEFTSHIFT, at(2500)),
+            RateVerdict::Drop
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Two keyboards tested side by side, the depot's check for "is it the
+//! keyboard or the motherboard": a known-good USB keyboard plugged in next to
+//! the suspect internal one, the same keys pressed on both. Each keyboard
+//! counts its own presses, and the keys complete on one but not the other are
+//! what to look at. Keyboards are matched as physical devices, so the nodes
+//! of one keyboard count together.
+
+use std::collections::HashMap;
+
+use evdev::KeyCode;
+
+use crate::event_handler::DeviceInfo;
+use crate::key_dedup::same_physical_device;
+use crate::keyboard_layouts::{Key, KeyboardLayout};
+use crate::report::{ComparedKeyboardResult, KeyboardComparisonResult};
+
+/// One side of the comparison
+pub struct ComparedKeyboard {
+    pub info: DeviceInfo,
+    presses: HashMap<KeyCode, usize>,
+}
+
+impl ComparedKeyboard {
+    fn new(info: DeviceInfo) -> Self {
+        ComparedKeyboard {
+            info,
+            presses: HashMap::new(),
+        }
+    }
+
+    pub fn presses(&self, code: KeyCode) -> usize {
+        self.presses.get(&code).copied().unwrap_or(0)
+    }
+
+    fn is_complete(&self, key: &Key) -> bool {
+        key.is_complete(|code| self.presses(code) > 0)
+    }
+}
+
+pub struct KeyboardComparison {
+    sides: [ComparedKeyboard; 2],
+}
+
+impl KeyboardComparison {
+    pub fn new(a: DeviceInfo, b: DeviceInfo) -> Self {
+        KeyboardComparison {
+            sides: [ComparedKeyboard::new(a), ComparedKeyboard::new(b)],
+        }
+    }
+
+    /// A is the first keyboard picked, B the second
+    pub fn side(&self, index: usize) -> &ComparedKeyboard {
+        &self.sides[index]
+    }
+
+    /// Whether `info` is one of the two keyboards
+    pub fn is_compared(&self, info: &DeviceInfo) -> bool {
+        self.sides
+            .iter()
+            .any(|side| same_physical_device(&side.info, info))
+    }
+
+    /// Count a press on the keyboard `info` belongs to. False for any other
+    /// keyboard.
+    pub fn press(&mut self, info: &DeviceInfo, code: KeyCode) -> bool {
+        let Some(side) = self
+            .sides
+            .iter_mut()
+            .find(|side| same_physical_device(&side.info, info))
+        else {
+            return false;
+        };
+        *side.presses.entry(code).or_insert(0) += 1;
+        true
+    }
+
+    /// Keys of `layout` complete on side `index` but not on the other one
+    pub fn only_on(&self, layout: KeyboardLayout, index: usize) -> Vec<Key> {
+        let (this, other) = (&self.sides[index], &self.sides[1 - index]);
+        layout_keys(layout)
+            .filter(|key| this.is_complete(key) && !other.is_complete(key))
+            .collect()
+    }
+
+    pub fn result(&self, layout: KeyboardLayout) -> KeyboardComparisonResult {
+        let labels = |keys: Vec<Key>| keys.iter().map(|key| key.label.to_string()).collect();
+        let side = |side: &ComparedKeyboard| ComparedKeyboardResult {
+            device: side.info.name.clone(),
+            path: side.info.path.clone(),
+            keys_total: layout_keys(layout).count(),
+            keys_pressed: layout_keys(layout)
+                .filter(|key| side.is_complete(key))
+                .count(),
+            total_presses: side.presses.values().sum(),
+            not_pressed: layout_keys(layout)
+                .filter(|key| !side.is_complete(key))
+                .map(|key| key.label.to_string())
+                .collect(),
+        };
+        KeyboardComparisonResult {
+            a: side(&self.sides[0]),
+            b: side(&self.sides[1]),
+            only_a: labels(self.only_on(layout, 0)),
+            only_b: labels(self.only_on(layout, 1)),
+        }
+    }
+}
+
+/// Physical keys of a layout, the lower cell of a two-row key left out
+fn layout_keys(layout: KeyboardLayout) -> impl Iterator<Item = Key> {
+    layout
+        .iter()
+        .flat_map(|section| section.iter())
+        .flat_map(|block| block.iter())
+        .flat_map(|row| row.iter())
+        .filter(|key| !key.is_continuation())
+        .copied()
+}
@@ -1,745 +1,1195 @@
 use evdev::KeyCode;
 
+use crate::language::Language;
 use crate::machine_detect::ComputerModel;
 
-pub type KeyLayout = &'static [&'static [(&'static str, &'static [KeyCode])]];
+/// One cell on the drawn keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    /// Names the key in reports, logs and the exclusion list, and is drawn
+    /// unless the UI language has a label of its own
+    pub label: &'static str,
+    /// What the Swedish keycap says, where it differs from `label`
+    pub label_sv: Option<&'static str>,
+    /// Keycodes the physical key can send, e.g. the digit and the numpad code of
+    /// a key with an Fn layer
+    pub codes: &'static [KeyCode],
+    /// The key is complete when any one of the codes has been seen, instead of all
+    pub any_of: bool,
+    /// Which part of a key spanning two rows this cell is
+    pub part: KeyPart,
+    /// Outline other than the cell's rectangle
+    pub shape: KeyShape,
+}
+
+/// Outline hint for keys that aren't rectangles on the physical keyboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyShape {
+    Plain,
+    /// ISO Enter: the upper cell reaches further left than the lower one, the
+    /// two are drawn as one L
+    TallEnter,
+    /// Caps Lock with a stepped cap, one column narrower on top
+    Stepped,
+}
+
+/// Keys taller than a row (ISO Enter, numpad + and Enter) are two cells in
+/// consecutive rows with the same label and codes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyPart {
+    Whole,
+    /// Carries the label, counts as the key
+    Upper,
+    /// Drawn joined to the upper part, skipped wherever keys are counted
+    Lower,
+}
+
+impl Key {
+    /// The lower cell of a two-row key, which is only drawn
+    pub fn is_continuation(&self) -> bool {
+        self.part == KeyPart::Lower
+    }
+
+    /// The same key drawn with `shape`
+    const fn shaped(self, shape: KeyShape) -> Key {
+        Key { shape, ..self }
+    }
+
+    /// The same key with a Swedish keycap label
+    const fn sv(self, label: &'static str) -> Key {
+        Key {
+            label_sv: Some(label),
+            ..self
+        }
+    }
+
+    /// The label drawn for `language`, the default label when it has none
+    pub fn display_label(&self, language: Language) -> &'static str {
+        match language {
+            Language::English => self.label,
+            Language::Swedish => self.label_sv.unwrap_or(self.label),
+        }
+    }
+
+    /// Whether both cells belong to the same physical key
+    pub fn is_same_key(&self, other: &Key) -> bool {
+        self.label == other.label && self.codes == other.codes && self.any_of == other.any_of
+    }
+
+    /// True when the key has been fully tested
+    pub fn is_complete(&self, pressed: impl Fn(KeyCode) -> bool) -> bool {
+        if self.any_of {
+            self.codes.iter().any(|code| pressed(*code))
+        } else {
+            self.codes.iter().all(|code| pressed(*code))
+        }
+    }
+}
+
+/// A key where every code has to be pressed
+const fn key(label: &'static str, codes: &'static [KeyCode]) -> Key {
+    Key {
+        label,
+        label_sv: None,
+        codes,
+        any_of: false,
+        part: KeyPart::Whole,
+        shape: KeyShape::Plain,
+    }
+}
+
+/// Upper cell of a key spanning this row and the next
+const fn upper_half(label: &'static str, codes: &'static [KeyCode]) -> Key {
+    Key {
+        part: KeyPart::Upper,
+        ..key(label, codes)
+    }
+}
+
+/// Lower cell of the key started by `upper_half` in the row above
+const fn lower_half(label: &'static str, codes: &'static [KeyCode]) -> Key {
+    Key {
+        part: KeyPart::Lower,
+        ..key(label, codes)
+    }
+}
+
+/// A key where pressing any one of its codes is enough, for alternatives that
+/// don't each need testing
+#[allow(dead_code)] // No built-in layout needs it yet
+const fn any_key(label: &'static str, codes: &'static [KeyCode]) -> Key {
+    Key {
+        label,
+        label_sv: None,
+        codes,
+        any_of: true,
+        part: KeyPart::Whole,
+        shape: KeyShape::Plain,
+    }
+}
+
+pub type KeyLayout = &'static [&'static [Key]];
 
 pub type KeyboardLayout = &'static [&'static [KeyLayout]];
 
 const DATOR_BB_FÄLT_OLD_LAYOUT: KeyboardLayout = &[
-    &[&[&[("Sleep knapp på datorn", &[KeyCode::KEY_SLEEP])]]],
+    &[&[&[key("Sleep knapp på datorn", &[KeyCode::KEY_SLEEP])]]],
     &[&[
         &[
-            ("ESC", &[KeyCode::KEY_ESC]),
-            ("F1", &[KeyCode::KEY_F1]),
-            ("F2", &[KeyCode::KEY_F2]),
-            ("F3", &[KeyCode::KEY_F3]),
-            ("F4", &[KeyCode::KEY_F4]),
-            ("F5", &[KeyCode::KEY_F5]),
-            ("F6", &[KeyCode::KEY_F6]),
-            ("F7", &[KeyCode::KEY_F7]),
-            ("F8", &[KeyCode::KEY_F8]),
-            ("F9", &[KeyCode::KEY_F9]),
-            ("F10", &[KeyCode::KEY_F10]),
-            ("F11", &[KeyCode::KEY_F11]),
-            ("F12", &[KeyCode::KEY_F12]),
-            ("Prt Sc", &[KeyCode::KEY_SYSRQ]),
-            ("Scrl Lk", &[KeyCode::KEY_SCROLLLOCK]),
-            ("Pause", &[KeyCode::KEY_PAUSE]),
+            key("ESC", &[KeyCode::KEY_ESC]),
+            key("F1", &[KeyCode::KEY_F1]),
+            key("F2", &[KeyCode::KEY_F2]),
+            key("F3", &[KeyCode::KEY_F3]),
+            key("F4", &[KeyCode::KEY_F4]),
+            key("F5", &[KeyCode::KEY_F5]),
+            key("F6", &[KeyCode::KEY_F6]),
+            key("F7", &[KeyCode::KEY_F7]),
+            key("F8", &[KeyCode::KEY_F8]),
+            key("F9", &[KeyCode::KEY_F9]),
+            key("F10", &[KeyCode::KEY_F10]),
+            key("F11", &[KeyCode::KEY_F11]),
+            key("F12", &[KeyCode::KEY_F12]),
+            key("Prt Sc", &[KeyCode::KEY_SYSRQ]),
+            key("Scrl Lk", &[KeyCode::KEY_SCROLLLOCK]),
+            key("Pause", &[KeyCode::KEY_PAUSE]),
         ],
         &[
-            ("§", &[KeyCode::KEY_GRAVE]),
-            ("1", &[KeyCode::KEY_1]),
-            ("2", &[KeyCode::KEY_2]),
-            ("3", &[KeyCode::KEY_3]),
-            ("4", &[KeyCode::KEY_4]),
-            ("5", &[KeyCode::KEY_5]),
-            ("6", &[KeyCode::KEY_6]),
-            ("7", &[KeyCode::KEY_7]),
-            ("8", &[KeyCode::KEY_8]),
-            ("9", &[KeyCode::KEY_9]),
-            ("0", &[KeyCode::KEY_0]),
-            ("+", &[KeyCode::KEY_MINUS]),
-            ("`", &[KeyCode::KEY_EQUAL]),
-            ("Backspace", &[KeyCode::KEY_BACKSPACE]),
-            ("Home", &[KeyCode::KEY_HOME]),
+            key("§", &[KeyCode::KEY_GRAVE]),
+            key("1", &[KeyCode::KEY_1]),
+            key("2", &[KeyCode::KEY_2]),
+            key("3", &[KeyCode::KEY_3]),
+            key("4", &[KeyCode::KEY_4]),
+            key("5", &[KeyCode::KEY_5]),
+            key("6", &[KeyCode::KEY_6]),
+            key("7", &[KeyCode::KEY_7]),
+            key("8", &[KeyCode::KEY_8]),
+            key("9", &[KeyCode::KEY_9]),
+            key("0", &[KeyCode::KEY_0]),
+            key("+", &[KeyCode::KEY_MINUS]),
+            key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+            key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
+            key("Home", &[KeyCode::KEY_HOME]),
         ],
         &[
-            ("Tab", &[KeyCode::KEY_TAB]),
-            ("Q", &[KeyCode::KEY_Q]),
-            ("W", &[KeyCode::KEY_W]),
-            ("E", &[KeyCode::KEY_E]),
-            ("R", &[KeyCode::KEY_R]),
-            ("T", &[KeyCode::KEY_T]),
-            ("Y", &[KeyCode::KEY_Y]),
-            ("U", &[KeyCode::KEY_U]),
-            ("I", &[KeyCode::KEY_I]),
-            ("O", &[KeyCode::KEY_O]),
-            ("P", &[KeyCode::KEY_P]),
-            ("Å", &[KeyCode::KEY_LEFTBRACE]),
-            ("^", &[KeyCode::KEY_RIGHTBRACE]),
-            ("'", &[KeyCode::KEY_BACKSLASH]),
-            ("PgUp", &[KeyCode::KEY_PAGEUP]),
+            key("Tab", &[KeyCode::KEY_TAB]),
+            key("Q", &[KeyCode::KEY_Q]),
+            key("W", &[KeyCode::KEY_W]),
+            key("E", &[KeyCode::KEY_E]),
+            key("R", &[KeyCode::KEY_R]),
+            key("T", &[KeyCode::KEY_T]),
+            key("Y", &[KeyCode::KEY_Y]),
+            key("U", &[KeyCode::KEY_U]),
+            key("I", &[KeyCode::KEY_I]),
+            key("O", &[KeyCode::KEY_O]),
+            key("P", &[KeyCode::KEY_P]),
+            key("Å", &[KeyCode::KEY_LEFTBRACE]),
+            key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+            key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+            key("PgUp", &[KeyCode::KEY_PAGEUP]),
         ],
         &[
-            ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-            ("A", &[KeyCode::KEY_A]),
-            ("S", &[KeyCode::KEY_S]),
-            ("D", &[KeyCode::KEY_D]),
-            ("F", &[KeyCode::KEY_F]),
-            ("G", &[KeyCode::KEY_G]),
-            ("H", &[KeyCode::KEY_H]),
-            ("J", &[KeyCode::KEY_J]),
-            ("K", &[KeyCode::KEY_K]),
-            ("L", &[KeyCode::KEY_L]),
-            ("Ö", &[KeyCode::KEY_SEMICOLON]),
-            ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-            ("Enter", &[KeyCode::KEY_ENTER]),
-            ("PgDn", &[KeyCode::KEY_PAGEDOWN]),
+            key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+            key("A", &[KeyCode::KEY_A]),
+            key("S", &[KeyCode::KEY_S]),
+            key("D", &[KeyCode::KEY_D]),
+            key("F", &[KeyCode::KEY_F]),
+            key("G", &[KeyCode::KEY_G]),
+            key("H", &[KeyCode::KEY_H]),
+            key("J", &[KeyCode::KEY_J]),
+            key("K", &[KeyCode::KEY_K]),
+            key("L", &[KeyCode::KEY_L]),
+            key("Ö", &[KeyCode::KEY_SEMICOLON]),
+            key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+            key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
+            key("PgDn", &[KeyCode::KEY_PAGEDOWN]),
         ],
         &[
-            ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-            ("Z", &[KeyCode::KEY_Z]),
-            ("X", &[KeyCode::KEY_X]),
-            ("C", &[KeyCode::KEY_C]),
-            ("V", &[KeyCode::KEY_V]),
-            ("B", &[KeyCode::KEY_B]),
-            ("N", &[KeyCode::KEY_N]),
-            ("M", &[KeyCode::KEY_M]),
-            (",", &[KeyCode::KEY_COMMA]),
-            (".", &[KeyCode::KEY_DOT]),
-            ("-", &[KeyCode::KEY_SLASH]),
-            ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
-            ("↑", &[KeyCode::KEY_UP]),
-            ("End", &[KeyCode::KEY_END]),
+            key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+            key("Z", &[KeyCode::KEY_Z]),
+            key("X", &[KeyCode::KEY_X]),
+            key("C", &[KeyCode::KEY_C]),
+            key("V", &[KeyCode::KEY_V]),
+            key("B", &[KeyCode::KEY_B]),
+            key("N", &[KeyCode::KEY_N]),
+            key("M", &[KeyCode::KEY_M]),
+            key(",", &[KeyCode::KEY_COMMA]),
+            key(".", &[KeyCode::KEY_DOT]),
+            key("-", &[KeyCode::KEY_SLASH]),
+            key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
+            key("↑", &[KeyCode::KEY_UP]),
+            key("End", &[KeyCode::KEY_END]),
         ],
         &[
-            ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-            ("LWin", &[KeyCode::KEY_LEFTMETA]),
-            ("Alt", &[KeyCode::KEY_LEFTALT]),
-            ("<", &[KeyCode::KEY_102ND]),
-            ("Space", &[KeyCode::KEY_SPACE]),
-            ("AltGr", &[KeyCode::KEY_RIGHTALT]),
-            ("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
-            ("MENU", &[KeyCode::KEY_COMPOSE]),
-            ("Ins", &[KeyCode::KEY_INSERT]),
-            ("Del", &[KeyCode::KEY_DELETE]),
-            ("←", &[KeyCode::KEY_LEFT]),
-            ("↓", &[KeyCode::KEY_DOWN]),
-            ("→", &[KeyCode::KEY_RIGHT]),
+            key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+            key("LWin", &[KeyCode::KEY_LEFTMETA]),
+            key("Alt", &[KeyCode::KEY_LEFTALT]),
+            key("<", &[KeyCode::KEY_102ND]),
+            key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+            key("AltGr", &[KeyCode::KEY_RIGHTALT]),
+            key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+            key("MENU", &[KeyCode::KEY_COMPOSE]),
+            key("Ins", &[KeyCode::KEY_INSERT]),
+            key("Del", &[KeyCode::KEY_DELETE]),
+            key("←", &[KeyCode::KEY_LEFT]),
+            key("↓", &[KeyCode::KEY_DOWN]),
+            key("→", &[KeyCode::KEY_RIGHT]),
         ],
     ]],
 ];
 
 const DATOR_BB_FÄLT_NY_LAYOUT: KeyboardLayout = &[
     &[&[&[
-        ("F1", &[KeyCode::KEY_F1]),
-        ("F2", &[KeyCode::KEY_F2]),
-        ("F3", &[KeyCode::KEY_F3]),
-        ("F4", &[KeyCode::KEY_F4]),
-        ("F5", &[KeyCode::KEY_F5]),
-        ("F6", &[KeyCode::KEY_F6]),
-        ("Sleep", &[KeyCode::KEY_SLEEP]),
+        key("F1", &[KeyCode::KEY_F1]),
+        key("F2", &[KeyCode::KEY_F2]),
+        key("F3", &[KeyCode::KEY_F3]),
+        key("F4", &[KeyCode::KEY_F4]),
+        key("F5", &[KeyCode::KEY_F5]),
+        key("F6", &[KeyCode::KEY_F6]),
+        key("Sleep", &[KeyCode::KEY_SLEEP]),
     ]]],
     &[&[
         &[
-            ("`", &[KeyCode::KEY_GRAVE]),
-            ("1", &[KeyCode::KEY_1]),
-            ("2", &[KeyCode::KEY_2]),
-            ("3", &[KeyCode::KEY_3]),
-            ("4", &[KeyCode::KEY_4]),
-            ("5", &[KeyCode::KEY_5]),
-            ("6", &[KeyCode::KEY_6]),
-            ("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
-            ("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
-            ("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
-            ("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
-            ("+", &[KeyCode::KEY_MINUS]),
-            ("`", &[KeyCode::KEY_EQUAL]),
-            ("Backspace", &[KeyCode::KEY_BACKSPACE]),
+            key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+            key("1", &[KeyCode::KEY_1]),
+            key("2", &[KeyCode::KEY_2]),
+            key("3", &[KeyCode::KEY_3]),
+            key("4", &[KeyCode::KEY_4]),
+            key("5", &[KeyCode::KEY_5]),
+            key("6", &[KeyCode::KEY_6]),
+            key("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
+            key("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
+            key("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
+            key("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
+            key("+", &[KeyCode::KEY_MINUS]),
+            key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+            key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
         ],
         &[
-            ("Tab", &[KeyCode::KEY_TAB]),
-            ("Q", &[KeyCode::KEY_Q]),
-            ("W", &[KeyCode::KEY_W]),
-            ("E", &[KeyCode::KEY_E]),
-            ("R", &[KeyCode::KEY_R]),
-            ("T", &[KeyCode::KEY_T]),
-            ("Y", &[KeyCode::KEY_Y]),
-            ("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
-            ("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
-            ("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
-            ("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
-            ("Å", &[KeyCode::KEY_LEFTBRACE]),
-            ("^", &[KeyCode::KEY_RIGHTBRACE]),
-            ("'", &[KeyCode::KEY_BACKSLASH]),
+            key("Tab", &[KeyCode::KEY_TAB]),
+            key("Q", &[KeyCode::KEY_Q]),
+            key("W", &[KeyCode::KEY_W]),
+            key("E", &[KeyCode::KEY_E]),
+            key("R", &[KeyCode::KEY_R]),
+            key("T", &[KeyCode::KEY_T]),
+            key("Y", &[KeyCode::KEY_Y]),
+            key("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
+            key("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
+            key("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
+            key("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
+            key("Å", &[KeyCode::KEY_LEFTBRACE]),
+            key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+            key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
         ],
         &[
-            ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-            ("A", &[KeyCode::KEY_A]),
-            ("S", &[KeyCode::KEY_S]),
-            ("D", &[KeyCode::KEY_D]),
-            ("F", &[KeyCode::KEY_F]),
-            ("G", &[KeyCode::KEY_G]),
-            ("H", &[KeyCode::KEY_H]),
-            ("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
-            ("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
-            ("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
-            ("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
-            ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-            ("Enter", &[KeyCode::KEY_ENTER]),
+            key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+            key("A", &[KeyCode::KEY_A]),
+            key("S", &[KeyCode::KEY_S]),
+            key("D", &[KeyCode::KEY_D]),
+            key("F", &[KeyCode::KEY_F]),
+            key("G", &[KeyCode::KEY_G]),
+            key("H", &[KeyCode::KEY_H]),
+            key("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
+            key("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
+            key("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
+            key("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
+            key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+            key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
         ],
         &[
-            ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-            ("Z", &[KeyCode::KEY_Z]),
-            ("X", &[KeyCode::KEY_X]),
-            ("C", &[KeyCode::KEY_C]),
-            ("V", &[KeyCode::KEY_V]),
-            ("B", &[KeyCode::KEY_B]),
-            ("N", &[KeyCode::KEY_N]),
-            ("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
-            (",", &[KeyCode::KEY_COMMA]),
-            (".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
-            ("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
-            ("↑", &[KeyCode::KEY_UP]),
-            ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
+            key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+            key("Z", &[KeyCode::KEY_Z]),
+            key("X", &[KeyCode::KEY_X]),
+            key("C", &[KeyCode::KEY_C]),
+            key("V", &[KeyCode::KEY_V]),
+            key("B", &[KeyCode::KEY_B]),
+            key("N", &[KeyCode::KEY_N]),
+            key("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
+            key(",", &[KeyCode::KEY_COMMA]),
+            key(".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
+            key("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
+            key("↑", &[KeyCode::KEY_UP]),
+            key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
         ],
         &[
-            ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-            ("LWin", &[KeyCode::KEY_LEFTMETA]),
-            ("Alt", &[KeyCode::KEY_LEFTALT]),
-            ("<", &[KeyCode::KEY_102ND]),
-            ("Space", &[KeyCode::KEY_SPACE]),
-            ("MENU", &[KeyCode::KEY_COMPOSE]),
-            ("Del", &[KeyCode::KEY_DELETE]),
-            ("NumLk", &[KeyCode::KEY_NUMLOCK]),
-            ("←", &[KeyCode::KEY_LEFT]),
-            ("↓", &[KeyCode::KEY_DOWN]),
-            ("→", &[KeyCode::KEY_RIGHT]),
+            key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+            key("LWin", &[KeyCode::KEY_LEFTMETA]),
+            key("Alt", &[KeyCode::KEY_LEFTALT]),
+            key("<", &[KeyCode::KEY_102ND]),
+            key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+            key("MENU", &[KeyCode::KEY_COMPOSE]),
+            key("Del", &[KeyCode::KEY_DELETE]),
+            key("NumLk", &[KeyCode::KEY_NUMLOCK]),
+            key("←", &[KeyCode::KEY_LEFT]),
+            key("↓", &[KeyCode::KEY_DOWN]),
+            key("→", &[KeyCode::KEY_RIGHT]),
         ],
         &[
-            ("Mouse Left", &[KeyCode::BTN_LEFT]),
-            ("Mouse Right", &[KeyCode::BTN_RIGHT]),
+            key("Mouse Left", &[KeyCode::BTN_LEFT]),
+            key("Mouse Right", &[KeyCode::BTN_RIGHT]),
         ],
     ]],
 ];
 
 const DATOR_BB_FÄLT_GPS: KeyboardLayout = &[&[&[
     &[
-        ("Tab", &[KeyCode::KEY_TAB]),
-        ("Q", &[KeyCode::KEY_Q]),
-        ("W", &[KeyCode::KEY_W]),
-        ("E", &[KeyCode::KEY_E]),
-        ("R", &[KeyCode::KEY_R]),
-        ("T", &[KeyCode::KEY_T]),
-        ("Y", &[KeyCode::KEY_Y]),
-        ("U", &[KeyCode::KEY_U]),
-        ("I", &[KeyCode::KEY_I]),
-        ("O", &[KeyCode::KEY_O]),
-        ("P", &[KeyCode::KEY_P]),
-        ("+", &[KeyCode::KEY_MINUS]),
-        ("`", &[KeyCode::KEY_EQUAL]),
-        ("Å", &[KeyCode::KEY_LEFTBRACE]),
-        ("^", &[KeyCode::KEY_RIGHTBRACE]),
-        ("'", &[KeyCode::KEY_BACKSLASH]),
-        ("7", &[KeyCode::KEY_7]),
-        ("8", &[KeyCode::KEY_8]),
-        ("9", &[KeyCode::KEY_9]),
-        ("Del", &[KeyCode::KEY_DELETE]),
-    ],
-    &[
-        ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-        ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-        ("A", &[KeyCode::KEY_A]),
-        ("S", &[KeyCode::KEY_S]),
-        ("D", &[KeyCode::KEY_D]),
-        ("F", &[KeyCode::KEY_F]),
-        ("G", &[KeyCode::KEY_G]),
-        ("H", &[KeyCode::KEY_H]),
-        ("J", &[KeyCode::KEY_J]),
-        ("K", &[KeyCode::KEY_K]),
-        ("L", &[KeyCode::KEY_L]),
-        (",", &[KeyCode::KEY_COMMA]),
-        (".", &[KeyCode::KEY_DOT]),
-        ("-", &[KeyCode::KEY_SLASH]),
-        ("↑", &[KeyCode::KEY_UP]),
-        ("Ö", &[KeyCode::KEY_SEMICOLON]),
-        ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-        ("4", &[KeyCode::KEY_4]),
-        ("5", &[KeyCode::KEY_5]),
-        ("6", &[KeyCode::KEY_6]),
-        ("Enter", &[KeyCode::KEY_ENTER]),
-    ],
-    &[
-        ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-        ("Alt", &[KeyCode::KEY_LEFTALT]),
-        ("LWin", &[KeyCode::KEY_LEFTMETA]),
-        ("Z", &[KeyCode::KEY_Z]),
-        ("X", &[KeyCode::KEY_X]),
-        ("C", &[KeyCode::KEY_C]),
-        ("V", &[KeyCode::KEY_V]),
-        ("B", &[KeyCode::KEY_B]),
-        ("N", &[KeyCode::KEY_N]),
-        ("M", &[KeyCode::KEY_M]),
-        ("Space", &[KeyCode::KEY_SPACE]),
-        ("<", &[KeyCode::KEY_102ND]),
-        ("←", &[KeyCode::KEY_LEFT]),
-        ("↓", &[KeyCode::KEY_DOWN]),
-        ("→", &[KeyCode::KEY_RIGHT]),
-        ("`", &[KeyCode::KEY_GRAVE]),
-        ("1", &[KeyCode::KEY_1]),
-        ("2", &[KeyCode::KEY_2]),
-        ("3", &[KeyCode::KEY_3]),
-        ("0", &[KeyCode::KEY_0]),
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("+", &[KeyCode::KEY_MINUS]),
+        key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+        key("Å", &[KeyCode::KEY_LEFTBRACE]),
+        key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+        key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("Del", &[KeyCode::KEY_DELETE]),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("-", &[KeyCode::KEY_SLASH]),
+        key("↑", &[KeyCode::KEY_UP]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
+        key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("0", &[KeyCode::KEY_0]),
     ],
 ]]];
 
 const DATOR_BÄRBAR_RS11: KeyboardLayout = &[&[&[
     &[
-        ("ESC", &[KeyCode::KEY_ESC]),
-        ("F1", &[KeyCode::KEY_F1]),
-        ("F2", &[KeyCode::KEY_F2]),
-        ("F3", &[KeyCode::KEY_F3]),
-        ("F4", &[KeyCode::KEY_F4]),
-        ("F5", &[KeyCode::KEY_F5]),
-        ("F6", &[KeyCode::KEY_F6]),
-        ("F7", &[KeyCode::KEY_F7]),
-        ("F8", &[KeyCode::KEY_F8]),
-        ("F9", &[KeyCode::KEY_F9]),
-        ("F10", &[KeyCode::KEY_F10]),
-        ("F11", &[KeyCode::KEY_F11]),
-        ("F12", &[KeyCode::KEY_F12]),
-        ("PrtSc", &[KeyCode::KEY_SYSRQ]),
-        ("Ins", &[KeyCode::KEY_INSERT]),
-        ("Pause", &[KeyCode::KEY_PAUSE]),
-        ("Del", &[KeyCode::KEY_DELETE]),
-    ],
-    &[
-        ("`", &[KeyCode::KEY_GRAVE]),
-        ("1", &[KeyCode::KEY_1]),
-        ("2", &[KeyCode::KEY_2]),
-        ("3", &[KeyCode::KEY_3]),
-        ("4", &[KeyCode::KEY_4]),
-        ("5", &[KeyCode::KEY_5]),
-        ("6", &[KeyCode::KEY_6]),
-        ("7", &[KeyCode::KEY_7]),
-        ("8", &[KeyCode::KEY_8]),
-        ("9", &[KeyCode::KEY_9]),
-        ("0", &[KeyCode::KEY_0]),
-        ("+", &[KeyCode::KEY_MINUS]),
-        ("`", &[KeyCode::KEY_EQUAL]),
-        ("Backspace", &[KeyCode::KEY_BACKSPACE]),
-    ],
-    &[
-        ("Tab", &[KeyCode::KEY_TAB]),
-        ("Q", &[KeyCode::KEY_Q]),
-        ("W", &[KeyCode::KEY_W]),
-        ("E", &[KeyCode::KEY_E]),
-        ("R", &[KeyCode::KEY_R]),
-        ("T", &[KeyCode::KEY_T]),
-        ("Y", &[KeyCode::KEY_Y]),
-        ("U", &[KeyCode::KEY_U]),
-        ("I", &[KeyCode::KEY_I]),
-        ("O", &[KeyCode::KEY_O]),
-        ("P", &[KeyCode::KEY_P]),
-        ("Å", &[KeyCode::KEY_LEFTBRACE]),
-        ("^", &[KeyCode::KEY_RIGHTBRACE]),
-        ("Enter", &[KeyCode::KEY_ENTER]),
-    ],
-    &[
-        ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-        ("A", &[KeyCode::KEY_A]),
-        ("S", &[KeyCode::KEY_S]),
-        ("D", &[KeyCode::KEY_D]),
-        ("F", &[KeyCode::KEY_F]),
-        ("G", &[KeyCode::KEY_G]),
-        ("H", &[KeyCode::KEY_H]),
-        ("J", &[KeyCode::KEY_J]),
-        ("K", &[KeyCode::KEY_K]),
-        ("L", &[KeyCode::KEY_L]),
-        ("Ö", &[KeyCode::KEY_SEMICOLON]),
-        ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-        ("'", &[KeyCode::KEY_BACKSLASH]),
-    ],
-    &[
-        ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-        ("<", &[KeyCode::KEY_102ND]),
-        ("Z", &[KeyCode::KEY_Z]),
-        ("X", &[KeyCode::KEY_X]),
-        ("C", &[KeyCode::KEY_C]),
-        ("V", &[KeyCode::KEY_V]),
-        ("B", &[KeyCode::KEY_B]),
-        ("N", &[KeyCode::KEY_N]),
-        ("M", &[KeyCode::KEY_M]),
-        (",", &[KeyCode::KEY_COMMA]),
-        (".", &[KeyCode::KEY_DOT]),
-        ("-", &[KeyCode::KEY_SLASH]),
-        ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
-        ("↑", &[KeyCode::KEY_UP]),
-    ],
-    &[
-        ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-        ("LWin", &[KeyCode::KEY_LEFTMETA]),
-        ("Alt", &[KeyCode::KEY_LEFTALT]),
-        ("Space", &[KeyCode::KEY_SPACE]),
-        ("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
-        ("MENU", &[KeyCode::KEY_COMPOSE]),
-        ("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
-        ("←", &[KeyCode::KEY_LEFT]),
-        ("↓", &[KeyCode::KEY_DOWN]),
-        ("→", &[KeyCode::KEY_RIGHT]),
-    ],
-    &[
-        ("Mouse Left", &[KeyCode::BTN_LEFT]),
-        ("Mouse Right", &[KeyCode::BTN_RIGHT]),
+        key("ESC", &[KeyCode::KEY_ESC]),
+        key("F1", &[KeyCode::KEY_F1]),
+        key("F2", &[KeyCode::KEY_F2]),
+        key("F3", &[KeyCode::KEY_F3]),
+        key("F4", &[KeyCode::KEY_F4]),
+        key("F5", &[KeyCode::KEY_F5]),
+        key("F6", &[KeyCode::KEY_F6]),
+        key("F7", &[KeyCode::KEY_F7]),
+        key("F8", &[KeyCode::KEY_F8]),
+        key("F9", &[KeyCode::KEY_F9]),
+        key("F10", &[KeyCode::KEY_F10]),
+        key("F11", &[KeyCode::KEY_F11]),
+        key("F12", &[KeyCode::KEY_F12]),
+        key("PrtSc", &[KeyCode::KEY_SYSRQ]),
+        key("Ins", &[KeyCode::KEY_INSERT]),
+        key("Pause", &[KeyCode::KEY_PAUSE]),
+        key("Del", &[KeyCode::KEY_DELETE]),
+    ],
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("0", &[KeyCode::KEY_0]),
+        key("+", &[KeyCode::KEY_MINUS]),
+        key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("Å", &[KeyCode::KEY_LEFTBRACE]),
+        key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+        key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("-", &[KeyCode::KEY_SLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
+        key("↑", &[KeyCode::KEY_UP]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("MENU", &[KeyCode::KEY_COMPOSE]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
+    ],
+    &[
+        key("Mouse Left", &[KeyCode::BTN_LEFT]),
+        key("Mouse Right", &[KeyCode::BTN_RIGHT]),
     ],
 ]]];
 
 const DATOR_BÄRBAR_CM_B_RF8: KeyboardLayout = &[&[&[
     &[
-        ("ESC", &[KeyCode::KEY_ESC]),
-        ("F1", &[KeyCode::KEY_F1]),
-        ("F2", &[KeyCode::KEY_F2]),
-        ("F3", &[KeyCode::KEY_F3]),
-        ("F4", &[KeyCode::KEY_F4]),
-        ("F5", &[KeyCode::KEY_F5]),
-        ("F6", &[KeyCode::KEY_F6]),
-        ("F7", &[KeyCode::KEY_F7]),
-        ("F8", &[KeyCode::KEY_F8]),
-        ("F9", &[KeyCode::KEY_F9]),
-        ("F10", &[KeyCode::KEY_F10]),
-        ("F11", &[KeyCode::KEY_F11]),
-        ("F12", &[KeyCode::KEY_F12]),
-        ("NumLck", &[KeyCode::KEY_NUMLOCK]),
-        ("PrtSc", &[KeyCode::KEY_SYSRQ]),
-        ("ScrollLck", &[KeyCode::KEY_SCROLLLOCK]),
-        ("Pause", &[KeyCode::KEY_PAUSE]),
-    ],
-    &[
-        ("`", &[KeyCode::KEY_GRAVE]),
-        ("1", &[KeyCode::KEY_1]),
-        ("2", &[KeyCode::KEY_2]),
-        ("3", &[KeyCode::KEY_3]),
-        ("4", &[KeyCode::KEY_4]),
-        ("5", &[KeyCode::KEY_5]),
-        ("6", &[KeyCode::KEY_6]),
-        ("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
-        ("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
-        ("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
-        ("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
-        ("+", &[KeyCode::KEY_MINUS]),
-        ("`", &[KeyCode::KEY_EQUAL]),
-        ("Backspace", &[KeyCode::KEY_BACKSPACE]),
-        ("Home", &[KeyCode::KEY_HOME]),
-    ],
-    &[
-        ("Tab", &[KeyCode::KEY_TAB]),
-        ("Q", &[KeyCode::KEY_Q]),
-        ("W", &[KeyCode::KEY_W]),
-        ("E", &[KeyCode::KEY_E]),
-        ("R", &[KeyCode::KEY_R]),
-        ("T", &[KeyCode::KEY_T]),
-        ("Y", &[KeyCode::KEY_Y]),
-        ("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
-        ("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
-        ("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
-        ("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
-        ("Å", &[KeyCode::KEY_LEFTBRACE]),
-        ("^", &[KeyCode::KEY_RIGHTBRACE]),
-        ("'", &[KeyCode::KEY_BACKSLASH]),
-        ("PgUp", &[KeyCode::KEY_PAGEUP]),
-    ],
-    &[
-        ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-        ("A", &[KeyCode::KEY_A]),
-        ("S", &[KeyCode::KEY_S]),
-        ("D", &[KeyCode::KEY_D]),
-        ("F", &[KeyCode::KEY_F]),
-        ("G", &[KeyCode::KEY_G]),
-        ("H", &[KeyCode::KEY_H]),
-        ("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
-        ("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
-        ("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
-        ("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
-        ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-        ("Enter", &[KeyCode::KEY_ENTER, KeyCode::KEY_KPENTER]),
-        ("PgDn", &[KeyCode::KEY_PAGEDOWN]),
-    ],
-    &[
-        ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-        ("Z", &[KeyCode::KEY_Z]),
-        ("X", &[KeyCode::KEY_X]),
-        ("C", &[KeyCode::KEY_C]),
-        ("V", &[KeyCode::KEY_V]),
-        ("B", &[KeyCode::KEY_B]),
-        ("N", &[KeyCode::KEY_N]),
-        ("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
-        (",", &[KeyCode::KEY_COMMA]),
-        (".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
-        ("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
-        ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
-        ("↑", &[KeyCode::KEY_UP]),
-        ("End", &[KeyCode::KEY_END]),
-    ],
-    &[
-        ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-        ("LWin", &[KeyCode::KEY_LEFTMETA]),
-        ("Alt", &[KeyCode::KEY_LEFTALT]),
-        ("<", &[KeyCode::KEY_102ND]),
-        ("Space", &[KeyCode::KEY_SPACE]),
-        ("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
-        ("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
-        ("MENU", &[KeyCode::KEY_COMPOSE]),
-        ("Ins", &[KeyCode::KEY_INSERT]),
-        ("Del", &[KeyCode::KEY_DELETE]),
-        ("←", &[KeyCode::KEY_LEFT]),
-        ("↓", &[KeyCode::KEY_DOWN]),
-        ("→", &[KeyCode::KEY_RIGHT]),
-    ],
-    &[
-        ("Mouse Left", &[KeyCode::BTN_LEFT]),
-        ("Mouse Right", &[KeyCode::BTN_RIGHT]),
+        key("ESC", &[KeyCode::KEY_ESC]),
+        key("F1", &[KeyCode::KEY_F1]),
+        key("F2", &[KeyCode::KEY_F2]),
+        key("F3", &[KeyCode::KEY_F3]),
+        key("F4", &[KeyCode::KEY_F4]),
+        key("F5", &[KeyCode::KEY_F5]),
+        key("F6", &[KeyCode::KEY_F6]),
+        key("F7", &[KeyCode::KEY_F7]),
+        key("F8", &[KeyCode::KEY_F8]),
+        key("F9", &[KeyCode::KEY_F9]),
+        key("F10", &[KeyCode::KEY_F10]),
+        key("F11", &[KeyCode::KEY_F11]),
+        key("F12", &[KeyCode::KEY_F12]),
+        key("NumLck", &[KeyCode::KEY_NUMLOCK]),
+        key("PrtSc", &[KeyCode::KEY_SYSRQ]),
+        key("ScrollLck", &[KeyCode::KEY_SCROLLLOCK]),
+        key("Pause", &[KeyCode::KEY_PAUSE]),
+    ],
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
+        key("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
+        key("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
+        key("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
+        key("+", &[KeyCode::KEY_MINUS]),
+        key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
+        key("Home", &[KeyCode::KEY_HOME]),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
+        key("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
+        key("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
+        key("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
+        key("Å", &[KeyCode::KEY_LEFTBRACE]),
+        key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+        key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+        key("PgUp", &[KeyCode::KEY_PAGEUP]),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
+        key("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
+        key("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("Enter", &[KeyCode::KEY_ENTER, KeyCode::KEY_KPENTER]),
+        key("PgDn", &[KeyCode::KEY_PAGEDOWN]),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
+        key("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
+        key("↑", &[KeyCode::KEY_UP]),
+        key("End", &[KeyCode::KEY_END]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+        key("MENU", &[KeyCode::KEY_COMPOSE]),
+        key("Ins", &[KeyCode::KEY_INSERT]),
+        key("Del", &[KeyCode::KEY_DELETE]),
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
+    ],
+    &[
+        key("Mouse Left", &[KeyCode::BTN_LEFT]),
+        key("Mouse Right", &[KeyCode::BTN_RIGHT]),
     ],
 ]]];
 
 const TANGENTBORD_KE89B: KeyLayout = &[
     &[
-        ("ESC", &[KeyCode::KEY_ESC]),
-        ("F1", &[KeyCode::KEY_F1]),
-        ("F2", &[KeyCode::KEY_F2]),
-        ("F3", &[KeyCode::KEY_F3]),
-        ("F4", &[KeyCode::KEY_F4]),
-        ("F5", &[KeyCode::KEY_F5]),
-        ("F6", &[KeyCode::KEY_F6]),
-        ("F7", &[KeyCode::KEY_F7]),
-        ("F8", &[KeyCode::KEY_F8]),
-        ("F9", &[KeyCode::KEY_F9]),
-        ("F10", &[KeyCode::KEY_F10]),
-        ("F11", &[KeyCode::KEY_F11]),
-        ("F12", &[KeyCode::KEY_F12]),
-        ("Num", &[KeyCode::KEY_NUMLOCK]),
-        ("Prt Sc", &[KeyCode::KEY_SYSRQ]),
-        ("ScrollLck", &[KeyCode::KEY_SCROLLLOCK]),
-        ("Pause", &[KeyCode::KEY_PAUSE]),
-    ],
-    &[
-        ("`", &[KeyCode::KEY_GRAVE]),
-        ("1", &[KeyCode::KEY_1]),
-        ("2", &[KeyCode::KEY_2]),
-        ("3", &[KeyCode::KEY_3]),
-        ("4", &[KeyCode::KEY_4]),
-        ("5", &[KeyCode::KEY_5]),
-        ("6", &[KeyCode::KEY_6]),
-        ("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
-        ("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
-        ("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
-        ("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
-        ("+", &[KeyCode::KEY_MINUS]),
-        ("`", &[KeyCode::KEY_EQUAL]),
-        ("Backspace", &[KeyCode::KEY_BACKSPACE]),
-        ("Home", &[KeyCode::KEY_HOME]),
-    ],
-    &[
-        ("Tab", &[KeyCode::KEY_TAB]),
-        ("Q", &[KeyCode::KEY_Q]),
-        ("W", &[KeyCode::KEY_W]),
-        ("E", &[KeyCode::KEY_E]),
-        ("R", &[KeyCode::KEY_R]),
-        ("T", &[KeyCode::KEY_T]),
-        ("Y", &[KeyCode::KEY_Y]),
-        ("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
-        ("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
-        ("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
-        ("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
-        ("Å", &[KeyCode::KEY_LEFTBRACE]),
-        ("^", &[KeyCode::KEY_RIGHTBRACE]),
-        ("'", &[KeyCode::KEY_BACKSLASH]),
-        ("PgUp", &[KeyCode::KEY_PAGEUP]),
-    ],
-    &[
-        ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-        ("A", &[KeyCode::KEY_A]),
-        ("S", &[KeyCode::KEY_S]),
-        ("D", &[KeyCode::KEY_D]),
-        ("F", &[KeyCode::KEY_F]),
-        ("G", &[KeyCode::KEY_G]),
-        ("H", &[KeyCode::KEY_H]),
-        ("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
-        ("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
-        ("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
-        ("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
-        ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-        ("Enter", &[KeyCode::KEY_ENTER]),
-        ("PgDn", &[KeyCode::KEY_PAGEDOWN]),
-    ],
-    &[
-        ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-        ("Z", &[KeyCode::KEY_Z]),
-        ("X", &[KeyCode::KEY_X]),
-        ("C", &[KeyCode::KEY_C]),
-        ("V", &[KeyCode::KEY_V]),
-        ("B", &[KeyCode::KEY_B]),
-        ("N", &[KeyCode::KEY_N]),
-        ("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
-        (",", &[KeyCode::KEY_COMMA]),
-        (".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
-        ("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
-        ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
-        ("↑", &[KeyCode::KEY_UP]),
-        ("End", &[KeyCode::KEY_END]),
-    ],
-    &[
-        ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-        ("LWin", &[KeyCode::KEY_LEFTMETA]),
-        ("Alt", &[KeyCode::KEY_LEFTALT]),
-        ("<", &[KeyCode::KEY_102ND]),
-        ("Space", &[KeyCode::KEY_SPACE]),
-        ("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
-        ("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
-        ("MENU", &[KeyCode::KEY_COMPOSE]),
-        ("Ins", &[KeyCode::KEY_INSERT]),
-        ("Del", &[KeyCode::KEY_DELETE]),
-        ("←", &[KeyCode::KEY_LEFT]),
-        ("↓", &[KeyCode::KEY_DOWN]),
-        ("→", &[KeyCode::KEY_RIGHT]),
+        key("ESC", &[KeyCode::KEY_ESC]),
+        key("F1", &[KeyCode::KEY_F1]),
+        key("F2", &[KeyCode::KEY_F2]),
+        key("F3", &[KeyCode::KEY_F3]),
+        key("F4", &[KeyCode::KEY_F4]),
+        key("F5", &[KeyCode::KEY_F5]),
+        key("F6", &[KeyCode::KEY_F6]),
+        key("F7", &[KeyCode::KEY_F7]),
+        key("F8", &[KeyCode::KEY_F8]),
+        key("F9", &[KeyCode::KEY_F9]),
+        key("F10", &[KeyCode::KEY_F10]),
+        key("F11", &[KeyCode::KEY_F11]),
+        key("F12", &[KeyCode::KEY_F12]),
+        key("Num", &[KeyCode::KEY_NUMLOCK]),
+        key("Prt Sc", &[KeyCode::KEY_SYSRQ]),
+        key("ScrollLck", &[KeyCode::KEY_SCROLLLOCK]),
+        key("Pause", &[KeyCode::KEY_PAUSE]),
+    ],
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7, KeyCode::KEY_KP7]),
+        key("8", &[KeyCode::KEY_8, KeyCode::KEY_KP8]),
+        key("9", &[KeyCode::KEY_9, KeyCode::KEY_KP9]),
+        key("0", &[KeyCode::KEY_0, KeyCode::KEY_KPASTERISK]),
+        key("+", &[KeyCode::KEY_MINUS]),
+        key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
+        key("Home", &[KeyCode::KEY_HOME]),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U, KeyCode::KEY_KP4]),
+        key("I", &[KeyCode::KEY_I, KeyCode::KEY_KP5]),
+        key("O", &[KeyCode::KEY_O, KeyCode::KEY_KP6]),
+        key("P", &[KeyCode::KEY_P, KeyCode::KEY_KPMINUS]),
+        key("Å", &[KeyCode::KEY_LEFTBRACE]),
+        key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+        key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+        key("PgUp", &[KeyCode::KEY_PAGEUP]),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J, KeyCode::KEY_KP1]),
+        key("K", &[KeyCode::KEY_K, KeyCode::KEY_KP2]),
+        key("L", &[KeyCode::KEY_L, KeyCode::KEY_KP3]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON, KeyCode::KEY_KPPLUS]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
+        key("PgDn", &[KeyCode::KEY_PAGEDOWN]),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M, KeyCode::KEY_KP0]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT, KeyCode::KEY_KPDOT]),
+        key("-", &[KeyCode::KEY_SLASH, KeyCode::KEY_KPSLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
+        key("↑", &[KeyCode::KEY_UP]),
+        key("End", &[KeyCode::KEY_END]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+        key("MENU", &[KeyCode::KEY_COMPOSE]),
+        key("Ins", &[KeyCode::KEY_INSERT]),
+        key("Del", &[KeyCode::KEY_DELETE]),
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
     ],
 ];
 
 const DATOR_BB_FÄLT_G2: KeyLayout = &[&[
-    ("A2", &[KeyCode::new(657)]),
-    ("-", &[KeyCode::KEY_BRIGHTNESSDOWN]),
-    ("+", &[KeyCode::KEY_BRIGHTNESSUP]),
-    ("Win & Rotate", &[KeyCode::KEY_LEFTMETA]),
-    ("A3", &[KeyCode::new(658)]),
+    key("A2", &[KeyCode::new(657)]),
+    key("-", &[KeyCode::KEY_BRIGHTNESSDOWN]),
+    key("+", &[KeyCode::KEY_BRIGHTNESSUP]),
+    key("Win & Rotate", &[KeyCode::KEY_LEFTMETA]),
+    key("A3", &[KeyCode::new(658)]),
 ]];
 
-const DATOR_BB_FÄLT_G2_PLUS: KeyboardLayout = &[&[&DATOR_BB_FÄLT_G2], &[&TANGENTBORD_KE89B]];
+const DATOR_BB_FÄLT_G2_PLUS: KeyboardLayout = &[&[DATOR_BB_FÄLT_G2], &[TANGENTBORD_KE89B]];
 
 const DEFAULT_MAIN_LAYOUT: KeyLayout = &[
     &[
-        ("ESC", &[KeyCode::KEY_ESC]),
-        ("F1", &[KeyCode::KEY_F1]),
-        ("F2", &[KeyCode::KEY_F2]),
-        ("F3", &[KeyCode::KEY_F3]),
-        ("F4", &[KeyCode::KEY_F4]),
-        ("F5", &[KeyCode::KEY_F5]),
-        ("F6", &[KeyCode::KEY_F6]),
-        ("F7", &[KeyCode::KEY_F7]),
-        ("F8", &[KeyCode::KEY_F8]),
-        ("F9", &[KeyCode::KEY_F9]),
-        ("F10", &[KeyCode::KEY_F10]),
-        ("F11", &[KeyCode::KEY_F11]),
-        ("F12", &[KeyCode::KEY_F12]),
-    ],
-    &[
-        ("`", &[KeyCode::KEY_GRAVE]),
-        ("1", &[KeyCode::KEY_1]),
-        ("2", &[KeyCode::KEY_2]),
-        ("3", &[KeyCode::KEY_3]),
-        ("4", &[KeyCode::KEY_4]),
-        ("5", &[KeyCode::KEY_5]),
-        ("6", &[KeyCode::KEY_6]),
-        ("7", &[KeyCode::KEY_7]),
-        ("8", &[KeyCode::KEY_8]),
-        ("9", &[KeyCode::KEY_9]),
-        ("0", &[KeyCode::KEY_0]),
-        ("+", &[KeyCode::KEY_MINUS]),
-        ("`", &[KeyCode::KEY_EQUAL]),
-        ("Backspace", &[KeyCode::KEY_BACKSPACE]),
-    ],
-    &[
-        ("Tab", &[KeyCode::KEY_TAB]),
-        ("Q", &[KeyCode::KEY_Q]),
-        ("W", &[KeyCode::KEY_W]),
-        ("E", &[KeyCode::KEY_E]),
-        ("R", &[KeyCode::KEY_R]),
-        ("T", &[KeyCode::KEY_T]),
-        ("Y", &[KeyCode::KEY_Y]),
-        ("U", &[KeyCode::KEY_U]),
-        ("I", &[KeyCode::KEY_I]),
-        ("O", &[KeyCode::KEY_O]),
-        ("P", &[KeyCode::KEY_P]),
-        ("Å", &[KeyCode::KEY_LEFTBRACE]),
-        ("^", &[KeyCode::KEY_RIGHTBRACE]),
-        ("Enter", &[KeyCode::KEY_ENTER]),
-    ],
-    &[
-        ("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
-        ("A", &[KeyCode::KEY_A]),
-        ("S", &[KeyCode::KEY_S]),
-        ("D", &[KeyCode::KEY_D]),
-        ("F", &[KeyCode::KEY_F]),
-        ("G", &[KeyCode::KEY_G]),
-        ("H", &[KeyCode::KEY_H]),
-        ("J", &[KeyCode::KEY_J]),
-        ("K", &[KeyCode::KEY_K]),
-        ("L", &[KeyCode::KEY_L]),
-        ("Ö", &[KeyCode::KEY_SEMICOLON]),
-        ("Ä", &[KeyCode::KEY_APOSTROPHE]),
-        ("'", &[KeyCode::KEY_BACKSLASH]),
-    ],
-    &[
-        ("Shift", &[KeyCode::KEY_LEFTSHIFT]),
-        ("<", &[KeyCode::KEY_102ND]),
-        ("Z", &[KeyCode::KEY_Z]),
-        ("X", &[KeyCode::KEY_X]),
-        ("C", &[KeyCode::KEY_C]),
-        ("V", &[KeyCode::KEY_V]),
-        ("B", &[KeyCode::KEY_B]),
-        ("N", &[KeyCode::KEY_N]),
-        ("M", &[KeyCode::KEY_M]),
-        (",", &[KeyCode::KEY_COMMA]),
-        (".", &[KeyCode::KEY_DOT]),
-        ("-", &[KeyCode::KEY_SLASH]),
-        ("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
-    ],
-    &[
-        ("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
-        ("LWin", &[KeyCode::KEY_LEFTMETA]),
-        ("Alt", &[KeyCode::KEY_LEFTALT]),
-        ("Space", &[KeyCode::KEY_SPACE]),
-        ("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
-        ("RWin", &[KeyCode::KEY_RIGHTMETA]),
-        ("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+        key("ESC", &[KeyCode::KEY_ESC]),
+        key("F1", &[KeyCode::KEY_F1]),
+        key("F2", &[KeyCode::KEY_F2]),
+        key("F3", &[KeyCode::KEY_F3]),
+        key("F4", &[KeyCode::KEY_F4]),
+        key("F5", &[KeyCode::KEY_F5]),
+        key("F6", &[KeyCode::KEY_F6]),
+        key("F7", &[KeyCode::KEY_F7]),
+        key("F8", &[KeyCode::KEY_F8]),
+        key("F9", &[KeyCode::KEY_F9]),
+        key("F10", &[KeyCode::KEY_F10]),
+        key("F11", &[KeyCode::KEY_F11]),
+        key("F12", &[KeyCode::KEY_F12]),
+    ],
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]).sv("§"),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("0", &[KeyCode::KEY_0]),
+        key("+", &[KeyCode::KEY_MINUS]),
+        key("`", &[KeyCode::KEY_EQUAL]).sv("´ `"),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]).sv("Backsteg"),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("Å", &[KeyCode::KEY_LEFTBRACE]),
+        key("^", &[KeyCode::KEY_RIGHTBRACE]).sv("¨ ^"),
+        key("Enter", &[KeyCode::KEY_ENTER]).sv("Retur"),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("'", &[KeyCode::KEY_BACKSLASH]).sv("' *"),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]).sv("Skift"),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("-", &[KeyCode::KEY_SLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]).sv("Skift"),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("Space", &[KeyCode::KEY_SPACE]).sv("Mellanslag"),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("RWin", &[KeyCode::KEY_RIGHTMETA]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
     ],
 ];
 
 const DEFAULT_SIDE_LAYOUT: KeyLayout = &[
     &[
-        ("Insert", &[KeyCode::KEY_INSERT]),
-        ("Home", &[KeyCode::KEY_HOME]),
-        ("Page Up", &[KeyCode::KEY_PAGEUP]),
+        key("Insert", &[KeyCode::KEY_INSERT]),
+        key("Home", &[KeyCode::KEY_HOME]),
+        key("Page Up", &[KeyCode::KEY_PAGEUP]),
     ],
     &[
-        ("Delete", &[KeyCode::KEY_DELETE]),
-        ("End", &[KeyCode::KEY_END]),
-        ("Page Down", &[KeyCode::KEY_PAGEDOWN]),
+        key("Delete", &[KeyCode::KEY_DELETE]),
+        key("End", &[KeyCode::KEY_END]),
+        key("Page Down", &[KeyCode::KEY_PAGEDOWN]),
     ],
-    &[("↑", &[KeyCode::KEY_UP])],
+    &[key("↑", &[KeyCode::KEY_UP])],
     &[
-        ("←", &[KeyCode::KEY_LEFT]),
-        ("↓", &[KeyCode::KEY_DOWN]),
-        ("→", &[KeyCode::KEY_RIGHT]),
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
     ],
     &[
-        ("Mouse Left", &[KeyCode::BTN_LEFT]),
-        ("Mouse Right", &[KeyCode::BTN_RIGHT]),
+        key("Mouse Left", &[KeyCode::BTN_LEFT]),
+        key("Mouse Right", &[KeyCode::BTN_RIGHT]),
     ],
 ];
 
 const DEFAULT_NUMPAD_LAYOUT: KeyLayout = &[
     &[
-        ("Num Lock", &[KeyCode::KEY_NUMLOCK]),
-        ("/", &[KeyCode::KEY_KPSLASH]),
-        ("*", &[KeyCode::KEY_KPASTERISK]),
-        ("-", &[KeyCode::KEY_KPMINUS]),
+        key("Num Lock", &[KeyCode::KEY_NUMLOCK]),
+        key("/", &[KeyCode::KEY_KPSLASH]),
+        key("*", &[KeyCode::KEY_KPASTERISK]),
+        key("-", &[KeyCode::KEY_KPMINUS]),
     ],
     &[
-        ("7", &[KeyCode::KEY_KP7]),
-        ("8", &[KeyCode::KEY_KP8]),
-        ("9", &[KeyCode::KEY_KP9]),
-        ("+", &[KeyCode::KEY_KPPLUS]),
+        key("7", &[KeyCode::KEY_KP7]),
+        key("8", &[KeyCode::KEY_KP8]),
+        key("9", &[KeyCode::KEY_KP9]),
+        key("+", &[KeyCode::KEY_KPPLUS]),
     ],
     &[
-        ("4", &[KeyCode::KEY_KP4]),
-        ("5", &[KeyCode::KEY_KP5]),
-        ("6", &[KeyCode::KEY_KP6]),
+        key("4", &[KeyCode::KEY_KP4]),
+        key("5", &[KeyCode::KEY_KP5]),
+        key("6", &[KeyCode::KEY_KP6]),
     ],
     &[
-        ("1", &[KeyCode::KEY_KP1]),
-        ("2", &[KeyCode::KEY_KP2]),
-        ("3", &[KeyCode::KEY_KP3]),
+        key("1", &[KeyCode::KEY_KP1]),
+        key("2", &[KeyCode::KEY_KP2]),
+        key("3", &[KeyCode::KEY_KP3]),
     ],
     &[
-        ("0", &[KeyCode::KEY_KP0]),
-        (".", &[KeyCode::KEY_KPDOT]),
-        ("Enter", &[KeyCode::KEY_KPENTER]),
+        key("0", &[KeyCode::KEY_KP0]),
+        key(".", &[KeyCode::KEY_KPDOT]),
+        key("Enter", &[KeyCode::KEY_KPENTER]),
     ],
 ];
 
 const DEFAULT_KEYBOARD: KeyboardLayout = &[
-    &[&DEFAULT_MAIN_LAYOUT],
-    &[&DEFAULT_SIDE_LAYOUT, &DEFAULT_NUMPAD_LAYOUT],
+    &[DEFAULT_MAIN_LAYOUT],
+    &[DEFAULT_SIDE_LAYOUT, DEFAULT_NUMPAD_LAYOUT],
+];
+
+// Function row shared by the standard layouts
+const FUNCTION_ROW: &[Key] = &[
+    key("ESC", &[KeyCode::KEY_ESC]),
+    key("F1", &[KeyCode::KEY_F1]),
+    key("F2", &[KeyCode::KEY_F2]),
+    key("F3", &[KeyCode::KEY_F3]),
+    key("F4", &[KeyCode::KEY_F4]),
+    key("F5", &[KeyCode::KEY_F5]),
+    key("F6", &[KeyCode::KEY_F6]),
+    key("F7", &[KeyCode::KEY_F7]),
+    key("F8", &[KeyCode::KEY_F8]),
+    key("F9", &[KeyCode::KEY_F9]),
+    key("F10", &[KeyCode::KEY_F10]),
+    key("F11", &[KeyCode::KEY_F11]),
+    key("F12", &[KeyCode::KEY_F12]),
+];
+
+// 104-key ANSI US: backslash above a one-row Enter, no key between Shift and Z
+const ANSI_US_MAIN_LAYOUT: KeyLayout = &[
+    FUNCTION_ROW,
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("0", &[KeyCode::KEY_0]),
+        key("-", &[KeyCode::KEY_MINUS]),
+        key("=", &[KeyCode::KEY_EQUAL]),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("[", &[KeyCode::KEY_LEFTBRACE]),
+        key("]", &[KeyCode::KEY_RIGHTBRACE]),
+        key("\\", &[KeyCode::KEY_BACKSLASH]),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key(";", &[KeyCode::KEY_SEMICOLON]),
+        key("'", &[KeyCode::KEY_APOSTROPHE]),
+        key("Enter", &[KeyCode::KEY_ENTER]),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("/", &[KeyCode::KEY_SLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("Space", &[KeyCode::KEY_SPACE]),
+        key("RAlt", &[KeyCode::KEY_RIGHTALT]),
+        key("RWin", &[KeyCode::KEY_RIGHTMETA]),
+        key("Menu", &[KeyCode::KEY_COMPOSE]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+    ],
+];
+
+// 105-key ISO UK: two-row Enter, # next to it and the extra \ key (KEY_102ND)
+// next to the short left Shift
+const ISO_UK_MAIN_LAYOUT: KeyLayout = &[
+    FUNCTION_ROW,
+    &[
+        key("`", &[KeyCode::KEY_GRAVE]),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("0", &[KeyCode::KEY_0]),
+        key("-", &[KeyCode::KEY_MINUS]),
+        key("=", &[KeyCode::KEY_EQUAL]),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Y", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("[", &[KeyCode::KEY_LEFTBRACE]),
+        key("]", &[KeyCode::KEY_RIGHTBRACE]),
+        upper_half("Enter", &[KeyCode::KEY_ENTER]).shaped(KeyShape::TallEnter),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]).shaped(KeyShape::Stepped),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key(";", &[KeyCode::KEY_SEMICOLON]),
+        key("'", &[KeyCode::KEY_APOSTROPHE]),
+        key("#", &[KeyCode::KEY_BACKSLASH]),
+        lower_half("Enter", &[KeyCode::KEY_ENTER]).shaped(KeyShape::TallEnter),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]),
+        key("\\", &[KeyCode::KEY_102ND]),
+        key("Z", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("/", &[KeyCode::KEY_SLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("Space", &[KeyCode::KEY_SPACE]),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("RWin", &[KeyCode::KEY_RIGHTMETA]),
+        key("Menu", &[KeyCode::KEY_COMPOSE]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+    ],
+];
+
+// ISO German, legends by position: Z and Y swapped, umlauts on the US
+// punctuation keys
+const ISO_DE_MAIN_LAYOUT: KeyLayout = &[
+    FUNCTION_ROW,
+    &[
+        key("^", &[KeyCode::KEY_GRAVE]),
+        key("1", &[KeyCode::KEY_1]),
+        key("2", &[KeyCode::KEY_2]),
+        key("3", &[KeyCode::KEY_3]),
+        key("4", &[KeyCode::KEY_4]),
+        key("5", &[KeyCode::KEY_5]),
+        key("6", &[KeyCode::KEY_6]),
+        key("7", &[KeyCode::KEY_7]),
+        key("8", &[KeyCode::KEY_8]),
+        key("9", &[KeyCode::KEY_9]),
+        key("0", &[KeyCode::KEY_0]),
+        key("ß", &[KeyCode::KEY_MINUS]),
+        key("´", &[KeyCode::KEY_EQUAL]),
+        key("Backspace", &[KeyCode::KEY_BACKSPACE]),
+    ],
+    &[
+        key("Tab", &[KeyCode::KEY_TAB]),
+        key("Q", &[KeyCode::KEY_Q]),
+        key("W", &[KeyCode::KEY_W]),
+        key("E", &[KeyCode::KEY_E]),
+        key("R", &[KeyCode::KEY_R]),
+        key("T", &[KeyCode::KEY_T]),
+        key("Z", &[KeyCode::KEY_Y]),
+        key("U", &[KeyCode::KEY_U]),
+        key("I", &[KeyCode::KEY_I]),
+        key("O", &[KeyCode::KEY_O]),
+        key("P", &[KeyCode::KEY_P]),
+        key("Ü", &[KeyCode::KEY_LEFTBRACE]),
+        key("+", &[KeyCode::KEY_RIGHTBRACE]),
+        upper_half("Enter", &[KeyCode::KEY_ENTER]).shaped(KeyShape::TallEnter),
+    ],
+    &[
+        key("CapsLock", &[KeyCode::KEY_CAPSLOCK]).shaped(KeyShape::Stepped),
+        key("A", &[KeyCode::KEY_A]),
+        key("S", &[KeyCode::KEY_S]),
+        key("D", &[KeyCode::KEY_D]),
+        key("F", &[KeyCode::KEY_F]),
+        key("G", &[KeyCode::KEY_G]),
+        key("H", &[KeyCode::KEY_H]),
+        key("J", &[KeyCode::KEY_J]),
+        key("K", &[KeyCode::KEY_K]),
+        key("L", &[KeyCode::KEY_L]),
+        key("Ö", &[KeyCode::KEY_SEMICOLON]),
+        key("Ä", &[KeyCode::KEY_APOSTROPHE]),
+        key("#", &[KeyCode::KEY_BACKSLASH]),
+        lower_half("Enter", &[KeyCode::KEY_ENTER]).shaped(KeyShape::TallEnter),
+    ],
+    &[
+        key("Shift", &[KeyCode::KEY_LEFTSHIFT]),
+        key("<", &[KeyCode::KEY_102ND]),
+        key("Y", &[KeyCode::KEY_Z]),
+        key("X", &[KeyCode::KEY_X]),
+        key("C", &[KeyCode::KEY_C]),
+        key("V", &[KeyCode::KEY_V]),
+        key("B", &[KeyCode::KEY_B]),
+        key("N", &[KeyCode::KEY_N]),
+        key("M", &[KeyCode::KEY_M]),
+        key(",", &[KeyCode::KEY_COMMA]),
+        key(".", &[KeyCode::KEY_DOT]),
+        key("-", &[KeyCode::KEY_SLASH]),
+        key("RShift", &[KeyCode::KEY_RIGHTSHIFT]),
+    ],
+    &[
+        key("LCtrl", &[KeyCode::KEY_LEFTCTRL]),
+        key("LWin", &[KeyCode::KEY_LEFTMETA]),
+        key("Alt", &[KeyCode::KEY_LEFTALT]),
+        key("Space", &[KeyCode::KEY_SPACE]),
+        key("Alt Gr", &[KeyCode::KEY_RIGHTALT]),
+        key("RWin", &[KeyCode::KEY_RIGHTMETA]),
+        key("Menu", &[KeyCode::KEY_COMPOSE]),
+        key("RCtrl", &[KeyCode::KEY_RIGHTCTRL]),
+    ],
+];
+
+// Navigation cluster of a full-size keyboard, with the keys above it
+const STANDARD_SIDE_LAYOUT: KeyLayout = &[
+    &[
+        key("Prt Sc", &[KeyCode::KEY_SYSRQ]),
+        key("Scrl Lk", &[KeyCode::KEY_SCROLLLOCK]),
+        key("Pause", &[KeyCode::KEY_PAUSE]),
+    ],
+    &[
+        key("Insert", &[KeyCode::KEY_INSERT]),
+        key("Home", &[KeyCode::KEY_HOME]),
+        key("Page Up", &[KeyCode::KEY_PAGEUP]),
+    ],
+    &[
+        key("Delete", &[KeyCode::KEY_DELETE]),
+        key("End", &[KeyCode::KEY_END]),
+        key("Page Down", &[KeyCode::KEY_PAGEDOWN]),
+    ],
+    &[key("↑", &[KeyCode::KEY_UP])],
+    &[
+        key("←", &[KeyCode::KEY_LEFT]),
+        key("↓", &[KeyCode::KEY_DOWN]),
+        key("→", &[KeyCode::KEY_RIGHT]),
+    ],
+];
+
+// Full-size numpad with the two-row + and Enter
+const STANDARD_NUMPAD_LAYOUT: KeyLayout = &[
+    &[
+        key("Num Lock", &[KeyCode::KEY_NUMLOCK]),
+        key("/", &[KeyCode::KEY_KPSLASH]),
+        key("*", &[KeyCode::KEY_KPASTERISK]),
+        key("-", &[KeyCode::KEY_KPMINUS]),
+    ],
+    &[
+        key("7", &[KeyCode::KEY_KP7]),
+        key("8", &[KeyCode::KEY_KP8]),
+        key("9", &[KeyCode::KEY_KP9]),
+        upper_half("+", &[KeyCode::KEY_KPPLUS]),
+    ],
+    &[
+        key("4", &[KeyCode::KEY_KP4]),
+        key("5", &[KeyCode::KEY_KP5]),
+        key("6", &[KeyCode::KEY_KP6]),
+        lower_half("+", &[KeyCode::KEY_KPPLUS]),
+    ],
+    &[
+        key("1", &[KeyCode::KEY_KP1]),
+        key("2", &[KeyCode::KEY_KP2]),
+        key("3", &[KeyCode::KEY_KP3]),
+        upper_half("Enter", &[KeyCode::KEY_KPENTER]),
+    ],
+    &[
+        key("0", &[KeyCode::KEY_KP0]),
+        key(".", &[KeyCode::KEY_KPDOT]),
+        lower_half("Enter", &[KeyCode::KEY_KPENTER]),
+    ],
+];
+
+const ANSI_US_KEYBOARD: KeyboardLayout = &[
+    &[ANSI_US_MAIN_LAYOUT],
+    &[STANDARD_SIDE_LAYOUT, STANDARD_NUMPAD_LAYOUT],
+];
+
+const ISO_UK_KEYBOARD: KeyboardLayout = &[
+    &[ISO_UK_MAIN_LAYOUT],
+    &[STANDARD_SIDE_LAYOUT, STANDARD_NUMPAD_LAYOUT],
+];
+
+const ISO_DE_KEYBOARD: KeyboardLayout = &[
+    &[ISO_DE_MAIN_LAYOUT],
+    &[STANDARD_SIDE_LAYOUT, STANDARD_NUMPAD_LAYOUT],
 ];
 
 // A list entry is (display name, layout)
@@ -771,4 +1221,28 @@ pub const LAYOUT_OPTIONS: &[(&str, KeyboardLayout, Option<ComputerModel>)] = &[
         DATOR_BB_FÄLT_G2_PLUS,
         Some(ComputerModel::DatorBBFältG2),
     ),
+    // Replacement USB keyboards
+    ("ANSI US (104)", ANSI_US_KEYBOARD, None),
+    ("ISO UK (105)", ISO_UK_KEYBOARD, None),
+    ("ISO German (105)", ISO_DE_KEYBOARD, None),
 ];
+
+/// Index into `LAYOUT_OPTIONS` of the layout built into `model`, the first
+/// entry when it has none of its own
+pub fn suggested_layout(model: ComputerModel) -> usize {
+    LAYOUT_OPTIONS
+        .iter()
+        .position(|option| option.2 == Some(model))
+        .unwrap_or(0)
+}
+
+/// Physical keys of a layout, the lower cell of a two-row key not counted again
+pub fn key_count(layout: KeyboardLayout) -> usize {
+    layout
+        .iter()
+        .flat_map(|section| section.iter())
+        .flat_map(|block| block.iter())
+        .flat_map(|row| row.iter())
+        .filter(|key| !key.is_continuation())
+        .count()
+}
@@ -4,60 +4,643 @@ use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span},
-    widgets::{Block, Paragraph},
+    widgets::{Block, Borders, Clear, Padding, Paragraph, Wrap},
 };
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
     Nav, Screen, ScreenId,
-    event_handler::AppEvent,
-    keyboard_layouts::{KeyboardLayout, LAYOUT_OPTIONS},
-    machine_detect::get_computer_model,
+    auto_advance::AutoAdvance,
+    cli::Options,
+    custom_layouts::CustomLayouts,
+    event_handler::{AppEvent, DeviceInfo},
+    ghost_keys::GhostKeys,
+    guided_keys::{GuidedRun, KeyOrder, KeyOutcome},
+    key_autorepeat::{self, PRESETS, RepeatSettings, RepeatTest},
+    key_dedup::same_physical_device,
+    key_exclusions::{self, KeyExclusions},
+    key_order,
+    key_quirks::{self, KeyQuirk},
+    keyboard_compare::{ComparedKeyboard, KeyboardComparison},
+    keyboard_layouts::{self, Key, KeyPart, KeyShape, KeyboardLayout, LAYOUT_OPTIONS},
+    language::Language,
+    logging,
+    machine_detect::ComputerModel,
+    other_keyboards::OtherKeyboards,
+    power::PowerMonitor,
+    press_colors::PressPalette,
+    report::{GuidedResult, KeyResult, KeyboardResult, ScreenResult},
+    simultaneous_keys::SimultaneousKeys,
+    terminal_echo::{self, EchoDetector, RawState},
+    test_timer::TestTimer,
+    theme::{KeyState, Theme},
+    touch_nav::{TouchAction, TouchButtons},
 };
 
-const COLOR_LIST: [Color; 5] = [
-    Color::Green,
-    Color::Yellow,
-    Color::Red,
-    Color::Blue,
-    Color::Magenta,
-];
+// How long a device fault banner stays up after the last fault report
+const FAULT_BANNER_DURATION: Duration = Duration::from_secs(3);
+
+// How long "layout 'X' reloaded" stays on the layout selection
+const LAYOUTS_NOTICE_DURATION: Duration = Duration::from_secs(3);
+
+/// A key goes stale when other keys kept firing this long after its last event
+pub const DEFAULT_STALE_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// Codes listed in the header of an ordered test
+const RECENT_CODES: usize = 10;
+
+// A press this soon after the key's release is the switch bouncing, not a finger
+const CHATTER_MS: u128 = 30;
+
+// Orders offered when starting a guided run, failed keys only comes from a finished run
+const GUIDED_ORDERS: [KeyOrder; 2] = [KeyOrder::Layout, KeyOrder::Random];
+
+// Narrower than this, the two keyboards of a comparison go one above the other
+const COMPARE_SIDE_BY_SIDE_WIDTH: u16 = 120;
+
+/// Geometry of the keyboard test in one frame
+struct ScreenLayout {
+    /// Device fault banner on top, while one is shown
+    fault_banner: Option<Rect>,
+    /// Power source change banner, below the fault banner
+    power_banner: Option<Rect>,
+    /// Keystrokes reaching the terminal, below the power banner
+    echo_banner: Option<Rect>,
+    header: Rect,
+    /// Between header and footer: the lists, or the legend above the keyboard
+    body: Rect,
+    legend: Rect,
+    footer: Rect,
+    /// Every key cell, empty when the keyboard isn't shown
+    keys: Vec<(Key, Rect)>,
+    /// Frame and key cells of each keyboard of a comparison, A first
+    compared: Vec<(Rect, Vec<(Key, Rect)>)>,
+}
 
 enum KeyboardTestMode {
     SelectLayout { selected: usize },
+    SelectOrder { selected: usize },
     Testing,
+    // One key at a time, see guided_keys
+    Guided(GuidedRun),
+    // Free test measuring held keys against the keyboard's autorepeat settings
+    Repeat,
+    // Free test on two keyboards at once, each counting its own presses
+    Compare(Box<KeyboardComparison>),
 }
 
+/// Marking a key as known-broken after a guided run, one step at a time
+enum ExclusionStep {
+    /// Picking from the failed and already excluded keys
+    ChooseKey {
+        selected: usize,
+    },
+    ChooseReason {
+        key: Key,
+        selected: usize,
+    },
+    /// Waiting for Y, a None reason counts the key again
+    Confirm {
+        key: Key,
+        reason: Option<&'static str>,
+    },
+}
+
+/// Keyboard test: pick a layout, then press every key freely or one at a time
+/// in a guided run
 pub struct KeyboardTestScreen {
     ctrl_presses: usize,
     pressed_keys: HashMap<KeyCode, usize>,
     last_key_press: Option<AppEvent>,
     keyboard_layout: KeyboardLayout,
+    layout_name: &'static str,
     mode: KeyboardTestMode,
+
+    // Key presses in the last second, for the keys/s readout
+    recent_presses: VecDeque<Instant>,
+    peak_keys_per_sec: usize,
+    // Latest device fault: (device name, reason, when)
+    device_fault: Option<(String, String, Instant)>,
+    palette: PressPalette,
+    theme: Theme,
+    language: Language,
+    touch_buttons: TouchButtons,
+    // Codes a quirky keyboard is known to send on its own, kept out of the presses
+    phantom_presses: HashMap<KeyCode, usize>,
+    // Most keys held at once per keyboard, from presses and releases
+    simultaneous: SimultaneousKeys,
+    // Presses that looked like ghosting, from presses and releases too
+    ghosts: GhostKeys,
+    // Panel listing them over the finished guided test, toggled with G
+    show_ghosts: bool,
+    // Event timestamp (ms) of each code's latest release
+    last_releases: HashMap<KeyCode, u128>,
+    // Codes that came again within CHATTER_MS of their release
+    chatter: HashSet<KeyCode>,
+    // Latest key event timestamp and when it arrived, to tell the event clock's time
+    event_clock: Option<(u128, Instant)>,
+    // Quirks of the keyboards pressed so far, for the report
+    quirks_seen: Vec<&'static KeyQuirk>,
+    // Event timestamps (unix ms) of the first and last press of each code
+    key_times: HashMap<KeyCode, (u64, u64)>,
+    stale_window: Duration,
+    // Stale keys list instead of the keyboard, on the finished guided run
+    show_stale: bool,
+    // Ordered test: keys show the order of their first press instead of the count,
+    // checked against the reading order
+    ordering: bool,
+    // Codes in the order of their first press
+    first_presses: Vec<KeyCode>,
+    // Latest codes received, oldest first
+    recent_codes: VecDeque<KeyCode>,
+    power: PowerMonitor,
+    // Autorepeat settings of each keyboard pressed, None without EV_REP
+    repeat_settings: HashMap<String, Option<RepeatSettings>>,
+    repeat: RepeatTest,
+    // Back to the home menu a moment after every key passed
+    auto_advance: AutoAdvance,
+    // The keyboard picked with K, the others' keys are only tallied. None tests every keyboard.
+    tested_keyboard: Option<DeviceInfo>,
+    // Keyboards picked with C, the comparison starts with the second one
+    compare_picks: Vec<DeviceInfo>,
+    other_keyboards: OtherKeyboards,
+    // Panel listing the other keyboards over the test, toggled with D
+    show_other_keyboards: bool,
+    // Restarted with the layout, auto-advance waits for its minimum
+    timer: TestTimer,
+    // Keys left out of the pass criteria, see key_exclusions
+    exclusions: KeyExclusions,
+    // Config entries for the unit, key name to reason, applied to every layout
+    preseeded_exclusions: BTreeMap<String, String>,
+    // The key picker over a finished guided run, opened with X
+    exclusion_step: Option<ExclusionStep>,
+    // Tested keys showing up on the terminal, see `terminal_echo`
+    echo: EchoDetector,
+    // Layouts of the site's files, listed after the built-in ones
+    custom_layouts: CustomLayouts,
+    // Which layouts were read again and when, shown on the selection for a moment
+    layouts_notice: Option<(String, Instant)>,
 }
 
 impl KeyboardTestScreen {
-    pub fn new() -> Self {
-        let suggested_index = LAYOUT_OPTIONS
-            .iter()
-            .position(|option| {
-                if let Some(model) = option.2 {
-                    model == get_computer_model()
-                } else {
-                    false
-                }
-            })
-            .unwrap_or(0);
+    /// Start on the layout selection with the layout matching `model` preselected
+    pub fn for_model(model: ComputerModel) -> Self {
+        let suggested_index = keyboard_layouts::suggested_layout(model);
 
         KeyboardTestScreen {
             ctrl_presses: 0,
             pressed_keys: HashMap::new(),
             last_key_press: None,
             keyboard_layout: LAYOUT_OPTIONS[suggested_index].1,
+            layout_name: LAYOUT_OPTIONS[suggested_index].0,
             mode: KeyboardTestMode::SelectLayout {
                 selected: suggested_index,
             },
+            recent_presses: VecDeque::new(),
+            peak_keys_per_sec: 0,
+            device_fault: None,
+            palette: PressPalette::default(),
+            theme: Theme::default(),
+            language: Language::default(),
+            touch_buttons: TouchButtons::new(&[TouchAction::Back]),
+            phantom_presses: HashMap::new(),
+            simultaneous: SimultaneousKeys::new(),
+            ghosts: GhostKeys::new(),
+            show_ghosts: false,
+            last_releases: HashMap::new(),
+            chatter: HashSet::new(),
+            event_clock: None,
+            quirks_seen: Vec::new(),
+            key_times: HashMap::new(),
+            stale_window: DEFAULT_STALE_WINDOW,
+            show_stale: false,
+            ordering: false,
+            first_presses: Vec::new(),
+            recent_codes: VecDeque::new(),
+            power: PowerMonitor::new(),
+            repeat_settings: HashMap::new(),
+            repeat: RepeatTest::new(),
+            auto_advance: AutoAdvance::new(false),
+            tested_keyboard: None,
+            compare_picks: Vec::new(),
+            other_keyboards: OtherKeyboards::new(),
+            show_other_keyboards: false,
+            timer: TestTimer::start(),
+            exclusions: KeyExclusions::new(),
+            preseeded_exclusions: BTreeMap::new(),
+            exclusion_step: None,
+            echo: EchoDetector::new(*terminal_echo::raw_state() == RawState::Raw),
+            custom_layouts: CustomLayouts::default(),
+            layouts_notice: None,
+        }
+    }
+
+    /// Color keys by press count with `palette` instead of the default ramp
+    pub fn with_palette(mut self, palette: PressPalette) -> Self {
+        self.palette = palette;
+        self
+    }
+
+    /// Show key states in `theme`'s colors
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// Draw the keycap labels of `language`
+    pub fn with_language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    /// Count keys as stale after `window` without events while others keep firing
+    pub fn with_stale_window(mut self, window: Duration) -> Self {
+        self.stale_window = window;
+        self
+    }
+
+    /// Leave the test by itself a few seconds after every key passed, see `auto_advance`
+    pub fn with_auto_advance(mut self, enabled: bool) -> Self {
+        self.auto_advance = AutoAdvance::new(enabled);
+        self
+    }
+
+    /// Don't auto-advance before the test ran for `min`
+    pub fn with_min_duration(mut self, min: Duration) -> Self {
+        self.timer.set_min(min);
+        self
+    }
+
+    /// Leave the keys named in `entries` out of the pass criteria, key label or
+    /// code name to reason
+    pub fn with_exclusions(mut self, entries: BTreeMap<String, String>) -> Self {
+        self.preseeded_exclusions = entries;
+        self
+    }
+
+    /// Offer the layouts of the TOML files in `dir` after the built-in ones,
+    /// see `custom_layouts`
+    pub fn with_layouts_dir(mut self, dir: Option<PathBuf>) -> Self {
+        self.custom_layouts = CustomLayouts::new(dir);
+        self
+    }
+
+    /// Entries of the layout selection, the built-in layouts then the custom ones
+    fn layout_count(&self) -> usize {
+        LAYOUT_OPTIONS.len() + self.custom_layouts.files().len()
+    }
+
+    /// Name and keys of entry `index` of the layout selection, None for a
+    /// custom layout file that never parsed
+    fn layout_option(&self, index: usize) -> Option<(&'static str, KeyboardLayout)> {
+        match LAYOUT_OPTIONS.get(index) {
+            Some((name, layout, _)) => Some((name, layout)),
+            None => self.custom_layouts.files()[index - LAYOUT_OPTIONS.len()].layout,
+        }
+    }
+
+    /// Take the custom layouts read again. Only the selection shows it, a test
+    /// keeps the layout it started with.
+    fn layouts_reloaded(&mut self, reloaded: Vec<&'static str>, manual: bool) {
+        let count = self.layout_count();
+        let KeyboardTestMode::SelectLayout { selected } = &mut self.mode else {
+            return;
+        };
+        // A file removed from the end of the list
+        *selected = (*selected).min(count - 1);
+        let notice = match &reloaded[..] {
+            [] if manual => "no layout reloaded".to_string(),
+            [] => return,
+            [name] => format!("layout '{name}' reloaded"),
+            names => {
+                let names: Vec<String> = names.iter().map(|name| format!("'{name}'")).collect();
+                format!("layouts {} reloaded", names.join(", "))
+            }
+        };
+        self.layouts_notice = Some((notice, Instant::now()));
+    }
+
+    /// Lock in a layout and clear everything counted so far. False leaves
+    /// everything as it was for a layout file that never parsed.
+    fn start_layout(&mut self, index: usize) -> bool {
+        let Some((name, layout)) = self.layout_option(index) else {
+            return false;
+        };
+        self.keyboard_layout = layout;
+        self.layout_name = name;
+        self.pressed_keys.clear();
+        self.last_key_press = None;
+        self.ctrl_presses = 0;
+        self.recent_presses.clear();
+        self.peak_keys_per_sec = 0;
+        self.phantom_presses.clear();
+        self.simultaneous.clear();
+        self.ghosts.clear();
+        self.show_ghosts = false;
+        self.last_releases.clear();
+        self.chatter.clear();
+        self.quirks_seen.clear();
+        self.key_times.clear();
+        self.show_stale = false;
+        self.ordering = false;
+        self.first_presses.clear();
+        self.recent_codes.clear();
+        self.power.start();
+        self.timer.restart();
+        self.repeat = RepeatTest::new();
+        self.auto_advance.update(false);
+        self.other_keyboards.clear();
+        self.exclusion_step = None;
+        let (exclusions, unknown) =
+            KeyExclusions::seeded(self.keyboard_layout, &self.preseeded_exclusions);
+        self.exclusions = exclusions;
+        if !unknown.is_empty() {
+            logging::warn(&format!(
+                "Excluded keys not on the {} layout: {}",
+                self.layout_name,
+                unknown.join(", ")
+            ));
+        }
+        true
+    }
+
+    /// Keys are being tested rather than choosing what to do
+    fn counts_keys(&self) -> bool {
+        match &self.mode {
+            KeyboardTestMode::Testing | KeyboardTestMode::Repeat | KeyboardTestMode::Compare(_) => {
+                true
+            }
+            KeyboardTestMode::Guided(run) => !run.is_finished(),
+            _ => false,
+        }
+    }
+
+    /// A key from a keyboard that isn't the one under test. Only tallied, D
+    /// toggles the panel and Ctrl x4 still leaves. Returns true to leave.
+    fn other_keyboard_key(&mut self, info: &DeviceInfo, code: KeyCode) -> bool {
+        self.other_keyboards.record(info, code);
+        if code == KeyCode::KEY_D {
+            self.show_other_keyboards = !self.show_other_keyboards;
+        }
+        if code == KeyCode::KEY_LEFTCTRL || code == KeyCode::KEY_RIGHTCTRL {
+            self.ctrl_presses += 1;
+        } else {
+            self.ctrl_presses = 0;
+        }
+        self.ctrl_presses >= 4
+    }
+
+    fn layout_index(&self) -> usize {
+        (0..self.layout_count())
+            .position(|index| {
+                self.layout_option(index)
+                    .is_some_and(|(name, _)| name == self.layout_name)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Count a key press during a test, returning true once Ctrl was pressed four times
+    /// in a row to leave
+    fn count_press(&mut self, event: AppEvent) -> bool {
+        let AppEvent::Key {
+            code,
+            ref info,
+            repeat,
+            timestamp,
+        } = event
+        else {
+            return false;
+        };
+
+        if !self.repeat_settings.contains_key(&info.path) {
+            self.repeat_settings
+                .insert(info.path.clone(), key_autorepeat::read_settings(&info.path));
+        }
+
+        let quirk = key_quirks::quirk_for(info);
+        if let Some(quirk) = quirk {
+            if !self.quirks_seen.iter().any(|seen| seen.name == quirk.name) {
+                self.quirks_seen.push(quirk);
+            }
+            if quirk.is_phantom(code) {
+                *self.phantom_presses.entry(code).or_insert(0) += 1;
+                self.simultaneous.phantom(info, code, timestamp);
+                return false;
+            }
+        }
+
+        if !repeat
+            && self
+                .last_releases
+                .get(&code)
+                .is_some_and(|released| timestamp.saturating_sub(*released) <= CHATTER_MS)
+        {
+            self.chatter.insert(code);
+        }
+
+        // Keys held on purpose in the repeat test would only show up as stuck
+        if !matches!(self.mode, KeyboardTestMode::Repeat) {
+            self.simultaneous.press(info, code, timestamp);
+            // A key the keyboard never sends a release for would stay held
+            let no_release = quirk.is_some_and(|quirk| quirk.has_no_release(code));
+            if !repeat && !no_release {
+                self.ghosts.press(info, code, timestamp);
+            }
+        }
+
+        if code == KeyCode::KEY_LEFTCTRL || code == KeyCode::KEY_RIGHTCTRL {
+            self.ctrl_presses += 1;
+        } else {
+            self.ctrl_presses = 0;
+        }
+
+        if self.ctrl_presses >= 4 {
+            return true;
+        }
+
+        if !self.pressed_keys.contains_key(&code) {
+            self.first_presses.push(code);
+        }
+        self.recent_codes.push_back(code);
+        if self.recent_codes.len() > RECENT_CODES {
+            self.recent_codes.pop_front();
+        }
+        *self.pressed_keys.entry(code).or_insert(0) += 1;
+        let timestamp = timestamp as u64;
+        self.key_times
+            .entry(code)
+            .and_modify(|(_, last)| *last = timestamp)
+            .or_insert((timestamp, timestamp));
+        self.last_key_press = Some(event);
+
+        let now = Instant::now();
+        self.recent_presses.push_back(now);
+        while self
+            .recent_presses
+            .front()
+            .is_some_and(|time| now.duration_since(*time) >= Duration::from_secs(1))
+        {
+            self.recent_presses.pop_front();
+        }
+        self.peak_keys_per_sec = self.peak_keys_per_sec.max(self.recent_presses.len());
+        false
+    }
+
+    /// The event clock now (ms): the latest key event's timestamp plus the
+    /// time since it arrived. Demo events don't stamp unix time, so the wall
+    /// clock can't stand in.
+    fn event_now(&self) -> Option<u128> {
+        self.event_clock
+            .map(|(timestamp, arrived)| timestamp + arrived.elapsed().as_millis())
+    }
+
+    fn keys_per_sec(&self) -> usize {
+        self.recent_presses
+            .iter()
+            .filter(|time| time.elapsed() < Duration::from_secs(1))
+            .count()
+    }
+
+    fn presses(&self, code: KeyCode) -> usize {
+        self.pressed_keys.get(&code).copied().unwrap_or(0)
+    }
+
+    /// Every key of the layout reached its target: all pressed in a free test,
+    /// all passed in a finished guided run
+    fn passed(&self) -> bool {
+        match &self.mode {
+            KeyboardTestMode::Testing => self
+                .keyboard_layout
+                .iter()
+                .flat_map(|section| section.iter())
+                .flat_map(|block| block.iter())
+                .flat_map(|row| row.iter())
+                .filter(|key| !key.is_continuation() && !self.exclusions.is_excluded(key))
+                .all(|key| key.is_complete(|kc| self.presses(kc) > 0)),
+            KeyboardTestMode::Guided(run) => run.is_finished() && !self.any_counted_failed(run),
+            _ => false,
+        }
+    }
+
+    /// A key failed the guided run and isn't excluded
+    fn any_counted_failed(&self, run: &GuidedRun) -> bool {
+        run.failed_keys()
+            .any(|key| !self.exclusions.is_excluded(key))
+    }
+
+    /// Keys the exclusion picker offers: failed in the guided run, or excluded
+    fn exclusion_candidates(&self) -> Vec<Key> {
+        let KeyboardTestMode::Guided(run) = &self.mode else {
+            return Vec::new();
+        };
+        let mut keys: Vec<Key> = run.failed_keys().copied().collect();
+        for key in key_order::reading_order(self.keyboard_layout) {
+            if self.exclusions.is_excluded(&key) && !keys.iter().any(|k| k.is_same_key(&key)) {
+                keys.push(key);
+            }
         }
+        keys
+    }
+
+    /// A key while the exclusion picker is open
+    fn exclusion_key(&mut self, code: KeyCode) {
+        let Some(step) = self.exclusion_step.take() else {
+            return;
+        };
+        self.exclusion_step = match step {
+            ExclusionStep::ChooseKey { selected } => {
+                let candidates = self.exclusion_candidates();
+                let count = candidates.len().max(1);
+                match code {
+                    KeyCode::KEY_DOWN => Some(ExclusionStep::ChooseKey {
+                        selected: (selected + 1) % count,
+                    }),
+                    KeyCode::KEY_UP => Some(ExclusionStep::ChooseKey {
+                        selected: (selected + count - 1) % count,
+                    }),
+                    KeyCode::KEY_ENTER => candidates.get(selected).map(|&key| {
+                        if self.exclusions.is_excluded(&key) {
+                            ExclusionStep::Confirm { key, reason: None }
+                        } else {
+                            ExclusionStep::ChooseReason { key, selected: 0 }
+                        }
+                    }),
+                    KeyCode::KEY_ESC | KeyCode::KEY_Q | KeyCode::KEY_X => None,
+                    _ => Some(ExclusionStep::ChooseKey { selected }),
+                }
+            }
+            ExclusionStep::ChooseReason { key, selected } => {
+                let count = key_exclusions::REASONS.len();
+                match code {
+                    KeyCode::KEY_DOWN => Some(ExclusionStep::ChooseReason {
+                        key,
+                        selected: (selected + 1) % count,
+                    }),
+                    KeyCode::KEY_UP => Some(ExclusionStep::ChooseReason {
+                        key,
+                        selected: (selected + count - 1) % count,
+                    }),
+                    KeyCode::KEY_ENTER => Some(ExclusionStep::Confirm {
+                        key,
+                        reason: Some(key_exclusions::REASONS[selected]),
+                    }),
+                    KeyCode::KEY_ESC | KeyCode::KEY_Q => {
+                        Some(ExclusionStep::ChooseKey { selected: 0 })
+                    }
+                    _ => Some(ExclusionStep::ChooseReason { key, selected }),
+                }
+            }
+            // Anything but Y cancels, nothing changes by accident
+            ExclusionStep::Confirm { key, reason } => {
+                if code == KeyCode::KEY_Y {
+                    match reason {
+                        Some(reason) => self.exclusions.exclude(&key, reason),
+                        None => self.exclusions.include(&key),
+                    }
+                }
+                None
+            }
+        };
+    }
+
+    /// Codes whose last press is more than the stale window older than the newest
+    /// press of any key, with how much older, longest silent first. A key that
+    /// worked at the start of a burn-in and then stopped ends up here.
+    fn stale_keys(&self) -> Vec<(KeyCode, Duration)> {
+        let Some(newest) = self.key_times.values().map(|(_, last)| *last).max() else {
+            return Vec::new();
+        };
+        let mut stale: Vec<(KeyCode, Duration)> = self
+            .key_times
+            .iter()
+            .map(|(code, (_, last))| (*code, Duration::from_millis(newest - last)))
+            .filter(|(_, silent)| *silent > self.stale_window)
+            .collect();
+        stale.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.0.cmp(&b.0.0)));
+        stale
+    }
+
+    /// Pressed keys in the order of their first press, with their ordinal from 1
+    /// and whether they broke the reading order
+    fn press_ordinals(&self) -> Vec<(Key, usize, bool)> {
+        let keys = key_order::reading_order(self.keyboard_layout);
+        let order = key_order::press_order(&keys, &self.first_presses);
+        let flagged = key_order::out_of_order(&order);
+        order
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| (keys[index], i + 1, flagged.contains(&index)))
+            .collect()
+    }
+
+    fn active_fault(&self) -> Option<(&str, &str)> {
+        self.device_fault
+            .as_ref()
+            .filter(|(_, _, time)| time.elapsed() < FAULT_BANNER_DURATION)
+            .map(|(device, reason, _)| (device.as_str(), reason.as_str()))
     }
 }
 
@@ -67,46 +650,277 @@ impl Screen for KeyboardTestScreen {
     }
 
     fn draw(&self, frame: &mut Frame) {
-        let chunks = Layout::vertical([
-            Constraint::Length(3),
-            Constraint::Min(5),
-            Constraint::Length(1),
-        ])
-        .split(frame.area());
+        let layout = self.layout(frame.area());
+
+        // A flooding device gets a banner on top, whatever the mode
+        if let (Some((device, reason)), Some(banner)) = (self.active_fault(), layout.fault_banner) {
+            let text = Line::from(vec![
+                " DEVICE FAULT ".bold(),
+                format!(" {device}: {reason}").into(),
+            ]);
+            let p = Paragraph::new(text)
+                .block(Block::bordered())
+                .style(Style::default().bg(Color::Red).fg(Color::White));
+            frame.render_widget(p, banner);
+        }
+        if let Some(banner) = layout.power_banner {
+            let text = Line::from(vec![
+                " POWER SOURCE CHANGED ".bold(),
+                " results don't compare with a test on one source".into(),
+            ]);
+            let p = Paragraph::new(text)
+                .block(Block::bordered())
+                .style(Style::default().bg(Color::Red).fg(Color::White));
+            frame.render_widget(p, banner);
+        }
+        if let Some(banner) = layout.echo_banner {
+            let text = Line::from(vec![
+                " KEYS REACH THE TERMINAL ".bold(),
+                " raw mode isn't in effect, typed keys may run in the shell after exit. Ctrl x4 aborts the test".into(),
+            ]);
+            let p = Paragraph::new(text)
+                .block(Block::bordered())
+                .style(Style::default().bg(Color::Red).fg(Color::White));
+            frame.render_widget(p, banner);
+        }
 
         match self.mode {
             KeyboardTestMode::SelectLayout { selected } => {
-                self.draw_layout_header(frame, chunks[0]);
-                self.draw_layout_list(frame, chunks[1], selected);
-                self.draw_select_footer(frame, chunks[2]);
+                self.draw_layout_header(frame, layout.header);
+                // Sites with layout files see what the selected one holds
+                if self.custom_layouts.dir().is_some() {
+                    let [list, preview] =
+                        Layout::horizontal([Constraint::Fill(1), Constraint::Fill(2)])
+                            .areas(layout.body);
+                    self.draw_layout_list(frame, list, selected);
+                    self.draw_layout_preview(frame, preview, selected);
+                } else {
+                    self.draw_layout_list(frame, layout.body, selected);
+                }
+                self.draw_select_footer(frame, layout.footer);
+            }
+            KeyboardTestMode::SelectOrder { selected } => {
+                self.draw_layout_header(frame, layout.header);
+                self.draw_order_list(frame, layout.body, selected);
+                self.draw_select_footer(frame, layout.footer);
             }
             KeyboardTestMode::Testing => {
-                self.draw_header(frame, chunks[0]);
-                self.draw_keyboard(frame, chunks[1]);
-                self.draw_footer(frame, chunks[2]);
+                self.draw_header(frame, layout.header);
+                self.draw_legend(frame, layout.legend);
+                self.draw_keyboard(frame, &layout.keys, &|code| self.presses(code));
+                self.draw_footer(frame, layout.footer);
+            }
+            KeyboardTestMode::Guided(ref run) if self.show_stale && run.is_finished() => {
+                self.draw_guided_header(frame, layout.header, run);
+                self.draw_stale_keys(frame, layout.body);
+                self.draw_footer(frame, layout.footer);
             }
+            KeyboardTestMode::Guided(ref run) => {
+                self.draw_guided_header(frame, layout.header, run);
+                self.draw_legend(frame, layout.legend);
+                self.draw_keyboard(frame, &layout.keys, &|code| self.presses(code));
+                self.draw_footer(frame, layout.footer);
+            }
+            KeyboardTestMode::Repeat => {
+                self.draw_repeat_header(frame, layout.header);
+                self.draw_repeat_test(frame, layout.body);
+                self.draw_footer(frame, layout.footer);
+            }
+            KeyboardTestMode::Compare(ref comparison) => {
+                self.draw_compare_header(frame, layout.header, comparison);
+                self.draw_compare_summary(frame, layout.legend, comparison);
+                for (index, (area, keys)) in layout.compared.iter().enumerate() {
+                    self.draw_compared_keyboard(frame, *area, keys, comparison, index);
+                }
+                self.draw_footer(frame, layout.footer);
+            }
+        }
+        if let Some(grid) = layout
+            .keys
+            .iter()
+            .map(|(_, rect)| *rect)
+            .reduce(Rect::union)
+        {
+            self.auto_advance.draw(frame, grid, "All keys passed");
+        }
+        if self.show_other_keyboards {
+            self.draw_other_keyboards(frame, layout.body);
+        }
+        if self.show_ghosts
+            && matches!(self.mode, KeyboardTestMode::Guided(ref run) if run.is_finished())
+        {
+            self.draw_ghosts(frame, layout.body);
         }
+        if let Some(step) = &self.exclusion_step {
+            self.draw_exclusion_step(frame, layout.body, step);
+        }
+        self.touch_buttons.draw(frame);
     }
 
     fn handle_event(&mut self, event: AppEvent) -> Nav {
+        if let AppEvent::DeviceFault { info, reason } = event {
+            self.device_fault = Some((info.name.clone(), reason, Instant::now()));
+            return Nav::Stay;
+        }
+        if let AppEvent::TerminalKey { c } = event {
+            let leaking = self.echo.leaking();
+            self.echo.terminal(c, Instant::now());
+            if !leaking && self.echo.leaking() {
+                logging::warn(&format!(
+                    "Keys of the keyboard test reach the terminal ({}), they may run in the shell after exit",
+                    terminal_echo::raw_state().describe()
+                ));
+            }
+            return Nav::Stay;
+        }
+        if let AppEvent::Key {
+            code,
+            repeat: false,
+            ..
+        } = event
+            && self.counts_keys()
+        {
+            self.echo.key(code, Instant::now());
+        }
+        if let AppEvent::Key { timestamp, .. } | AppEvent::KeyReleased { timestamp, .. } = event {
+            self.event_clock = Some((timestamp, Instant::now()));
+        }
+        if let AppEvent::KeyReleased {
+            code,
+            info,
+            timestamp,
+        } = event
+        {
+            self.simultaneous.release(&info, code);
+            self.ghosts.release(&info, code);
+            self.last_releases.insert(code, timestamp);
+            return Nav::Stay;
+        }
+        if let AppEvent::Tick = event {
+            // The files are only watched while a layout is being picked
+            if let KeyboardTestMode::SelectLayout { .. } = self.mode {
+                let reloaded = self.custom_layouts.poll(Instant::now());
+                self.layouts_reloaded(reloaded, false);
+            }
+            self.power.tick();
+            let passed = self.passed() && self.timer.min_met();
+            self.auto_advance.update(passed);
+            if self.auto_advance.expired() {
+                return Nav::To(ScreenId::Home);
+            }
+        }
+        if let AppEvent::Key {
+            code,
+            ref info,
+            repeat,
+            ..
+        } = event
+            && self.counts_keys()
+            && self
+                .tested_keyboard
+                .as_ref()
+                .is_some_and(|tested| !same_physical_device(tested, info))
+        {
+            if !repeat && self.other_keyboard_key(info, code) {
+                return Nav::To(ScreenId::Home);
+            }
+            return Nav::Stay;
+        }
+        if let AppEvent::Key {
+            code,
+            repeat: false,
+            ..
+        } = event
+            && self.exclusion_step.is_some()
+        {
+            self.exclusion_key(code);
+            return Nav::Stay;
+        }
+        // A key during the countdown stays on the test to review it
+        if let AppEvent::Key { repeat: false, .. } = event
+            && self.auto_advance.cancel()
+        {
+            return Nav::Stay;
+        }
+        if let AppEvent::Touch { .. } = event {
+            if self.touch_buttons.handle_raw_touch(&event) == Some(TouchAction::Back) {
+                return Nav::To(ScreenId::Home);
+            }
+            return Nav::Stay;
+        }
+
+        let layout_count = self.layout_count();
         match &mut self.mode {
             KeyboardTestMode::SelectLayout { selected } => {
-                match event {
-                    AppEvent::Key { code, .. } => match code {
+                if let AppEvent::Key { code, ref info, .. } = event {
+                    match code {
                         KeyCode::KEY_DOWN => {
-                            *selected = (*selected + 1) % LAYOUT_OPTIONS.len();
+                            *selected = (*selected + 1) % layout_count;
                         }
                         KeyCode::KEY_UP => {
-                            *selected =
-                                (*selected + LAYOUT_OPTIONS.len() - 1) % LAYOUT_OPTIONS.len();
+                            *selected = (*selected + layout_count - 1) % layout_count;
                         }
                         KeyCode::KEY_ENTER => {
                             // Lock in the chosen layout and start the test
-                            self.keyboard_layout = LAYOUT_OPTIONS[*selected].1;
-                            self.pressed_keys.clear();
-                            self.last_key_press = None;
-                            self.ctrl_presses = 0;
-                            self.mode = KeyboardTestMode::Testing;
+                            let index = *selected;
+                            if self.start_layout(index) {
+                                self.mode = KeyboardTestMode::Testing;
+                            }
+                        }
+                        KeyCode::KEY_G => {
+                            // Guided run, pick the key order first
+                            let index = *selected;
+                            if self.start_layout(index) {
+                                self.mode = KeyboardTestMode::SelectOrder { selected: 0 };
+                            }
+                        }
+                        KeyCode::KEY_O => {
+                            // Free test, pressing the keys in reading order
+                            let index = *selected;
+                            if self.start_layout(index) {
+                                self.ordering = true;
+                                self.mode = KeyboardTestMode::Testing;
+                            }
+                        }
+                        KeyCode::KEY_R => {
+                            let index = *selected;
+                            if self.start_layout(index) {
+                                self.mode = KeyboardTestMode::Repeat;
+                            }
+                        }
+                        KeyCode::KEY_F5 => {
+                            // In case a change went unnoticed, e.g. on a network mount
+                            let reloaded = self.custom_layouts.reload();
+                            self.layouts_reloaded(reloaded, true);
+                        }
+                        KeyCode::KEY_K => {
+                            // The keyboard K is pressed on, again on it tests every keyboard
+                            let same = self
+                                .tested_keyboard
+                                .as_ref()
+                                .is_some_and(|tested| same_physical_device(tested, info));
+                            self.tested_keyboard = (!same).then(|| DeviceInfo::clone(info));
+                        }
+                        KeyCode::KEY_C => {
+                            // Picks the keyboard C is pressed on, again on it drops it
+                            let before = self.compare_picks.len();
+                            self.compare_picks
+                                .retain(|picked| !same_physical_device(picked, info));
+                            if self.compare_picks.len() == before {
+                                self.compare_picks.push(DeviceInfo::clone(info));
+                            }
+                            if let [a, b] = &self.compare_picks[..] {
+                                let comparison = KeyboardComparison::new(a.clone(), b.clone());
+                                let index = *selected;
+                                if self.start_layout(index) {
+                                    self.compare_picks.clear();
+                                    self.tested_keyboard = None;
+                                    self.mode = KeyboardTestMode::Compare(Box::new(comparison));
+                                }
+                            }
+                        }
+                        KeyCode::KEY_D => {
+                            self.show_other_keyboards = !self.show_other_keyboards;
                         }
                         KeyCode::KEY_ESC | KeyCode::KEY_Q => {
                             return Nav::To(ScreenId::Home);
@@ -122,179 +936,1390 @@ impl Screen for KeyboardTestScreen {
                             // Any other key resets the Ctrl counter
                             self.ctrl_presses = 0;
                         }
-                    },
-                    _ => {}
+                    }
+                }
+                Nav::Stay
+            }
+
+            KeyboardTestMode::SelectOrder { selected } => {
+                if let AppEvent::Key { code, .. } = event {
+                    match code {
+                        KeyCode::KEY_DOWN => *selected = (*selected + 1) % GUIDED_ORDERS.len(),
+                        KeyCode::KEY_UP => {
+                            *selected = (*selected + GUIDED_ORDERS.len() - 1) % GUIDED_ORDERS.len()
+                        }
+                        KeyCode::KEY_ENTER => {
+                            let seed = std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .unwrap_or_default()
+                                .as_nanos() as u64;
+                            let mut run = GuidedRun::new(
+                                self.keyboard_layout,
+                                GUIDED_ORDERS[*selected],
+                                seed,
+                                Instant::now(),
+                            );
+                            run.skip(|key| self.exclusions.is_excluded(key));
+                            self.mode = KeyboardTestMode::Guided(run);
+                        }
+                        KeyCode::KEY_ESC | KeyCode::KEY_Q => {
+                            self.mode = KeyboardTestMode::SelectLayout {
+                                selected: self.layout_index(),
+                            };
+                        }
+                        _ => {}
+                    }
                 }
-                return Nav::Stay;
+                Nav::Stay
             }
 
             KeyboardTestMode::Testing => {
+                if self.count_press(event) {
+                    return Nav::To(ScreenId::Home);
+                }
+                Nav::Stay
+            }
+
+            KeyboardTestMode::Compare(comparison) => {
+                if let AppEvent::Key {
+                    code,
+                    ref info,
+                    repeat,
+                    ..
+                } = event
+                    && !comparison.is_compared(info)
+                {
+                    // A third keyboard is only tallied, like the others of a K test
+                    if !repeat && self.other_keyboard_key(info, code) {
+                        return Nav::To(ScreenId::Home);
+                    }
+                    return Nav::Stay;
+                }
+                if let AppEvent::Key {
+                    code,
+                    ref info,
+                    repeat: false,
+                    ..
+                } = event
+                {
+                    comparison.press(info, code);
+                }
+                if self.count_press(event) {
+                    return Nav::To(ScreenId::Home);
+                }
+                Nav::Stay
+            }
+
+            KeyboardTestMode::Repeat => {
+                if let AppEvent::Key {
+                    code,
+                    ref info,
+                    repeat,
+                    timestamp,
+                } = event
+                {
+                    if repeat {
+                        self.repeat.repeat(code, info, timestamp as u64);
+                        return Nav::Stay;
+                    }
+                    self.repeat.press(code, info, timestamp as u64);
+                }
+                if self.count_press(event) {
+                    return Nav::To(ScreenId::Home);
+                }
+                Nav::Stay
+            }
+
+            KeyboardTestMode::Guided(run) => {
+                let now = Instant::now();
                 match event {
-                    AppEvent::Key { code, .. } => {
-                        if code == KeyCode::KEY_LEFTCTRL || code == KeyCode::KEY_RIGHTCTRL {
-                            self.ctrl_presses += 1;
-                        } else {
-                            self.ctrl_presses = 0;
+                    AppEvent::Tick => run.tick(now),
+                    // The run is over, so keys are free for the follow-up choices
+                    AppEvent::Key { code, .. } if run.is_finished() => match code {
+                        KeyCode::KEY_R
+                            if run
+                                .failed_keys()
+                                .any(|key| !self.exclusions.is_excluded(key)) =>
+                        {
+                            run.retest_failed(now, |key| self.exclusions.is_excluded(key));
                         }
-
-                        if self.ctrl_presses >= 4 {
+                        KeyCode::KEY_X => {
+                            self.exclusion_step = Some(ExclusionStep::ChooseKey { selected: 0 })
+                        }
+                        KeyCode::KEY_S => self.show_stale = !self.show_stale,
+                        KeyCode::KEY_D => self.show_other_keyboards = !self.show_other_keyboards,
+                        KeyCode::KEY_G => self.show_ghosts = !self.show_ghosts,
+                        KeyCode::KEY_ENTER => self.mode = KeyboardTestMode::Testing,
+                        KeyCode::KEY_ESC | KeyCode::KEY_Q => return Nav::To(ScreenId::Home),
+                        _ => {}
+                    },
+                    AppEvent::Key { code, .. } => {
+                        run.press(code, now);
+                        if self.count_press(event) {
                             return Nav::To(ScreenId::Home);
                         }
-
-                        *self.pressed_keys.entry(code).or_insert(0) += 1;
-                        self.last_key_press = Some(event);
                     }
                     _ => {}
                 }
-                return Nav::Stay;
+                Nav::Stay
             }
         }
     }
-}
 
-impl KeyboardTestScreen {
-    fn draw_layout_header(&self, frame: &mut Frame, area: Rect) {
-        let title = Line::from(vec![
-            "Keyboard Test".bold().cyan(),
-            " | ".into(),
-            "Select keyboard layout".gray(),
-        ]);
-        let p = Paragraph::new(title).block(Block::bordered());
-        frame.render_widget(p, area);
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        match self.mode {
+            KeyboardTestMode::SelectLayout { .. } => vec![
+                ("↑/↓", "Select a keyboard layout"),
+                ("F5", "Read the custom layout files again"),
+                ("Enter", "Start the test with the selected layout"),
+                ("G", "Guided test, asking for one key at a time"),
+                (
+                    "R",
+                    "Repeat test: measure held keys against the autorepeat settings",
+                ),
+                (
+                    "O",
+                    "Ordered test: press the keys in reading order, each key shows when it came",
+                ),
+                (
+                    "K",
+                    "Test only the keyboard K is pressed on, again on it for all keyboards",
+                ),
+                (
+                    "C",
+                    "Compare two keyboards: C on each, the test starts with the second",
+                ),
+                ("D", "Show the keys counted from other keyboards"),
+                ("Q/Esc", "Back to the home menu"),
+                ("Ctrl x4", "Back to the home menu"),
+                ("Tap Back", "Back to the home menu, from any mode"),
+            ],
+            KeyboardTestMode::SelectOrder { .. } => vec![
+                ("↑/↓", "Select the order keys are asked for"),
+                ("Enter", "Start the guided test"),
+                ("Q/Esc", "Back to the layout selection"),
+            ],
+            KeyboardTestMode::Guided(ref run) if run.is_finished() => vec![
+                ("R", "Test the failed keys again"),
+                (
+                    "X",
+                    "Exclude a failed key known to be broken, or count an excluded key again",
+                ),
+                ("S", "Show keys that went quiet while others kept firing"),
+                ("D", "Show the keys counted from other keyboards"),
+                ("G", "Show keys that only came while others were held"),
+                ("Enter", "Continue with a free test"),
+                ("Q/Esc", "Back to the home menu"),
+            ],
+            KeyboardTestMode::Guided(_) => vec![
+                (
+                    "Highlighted key",
+                    "Press it, unpressed keys time out after 10 s",
+                ),
+                ("Ctrl x4", "Finish the test and go back"),
+                ("F1", "This help"),
+            ],
+            KeyboardTestMode::Repeat => vec![
+                (
+                    "Hold a key",
+                    "Measures the delay and rate of its autorepeat",
+                ),
+                ("1", "Set the keyboard to 250 ms / 30 Hz"),
+                ("2", "Set the keyboard to 1000 ms / 2 Hz"),
+                ("0", "Back to the keyboard's original settings"),
+                ("Ctrl x4", "Finish the test and go back"),
+                ("F1", "This help"),
+            ],
+            KeyboardTestMode::Compare(_) => vec![
+                (
+                    "Any key",
+                    "Counted on the layout of the keyboard it was pressed on",
+                ),
+                (
+                    "Pressed on A but not B",
+                    "Keys complete on one keyboard and not on the other",
+                ),
+                (
+                    "Narrow terminal",
+                    "The two keyboards go one above the other",
+                ),
+                ("Ctrl x4", "Finish the test and go back"),
+                ("Tap Back", "Back to the home menu"),
+                ("F1", "This help"),
+            ],
+            KeyboardTestMode::Testing if self.ordering => vec![
+                ("Any key", "Counted and colored on the layout"),
+                (
+                    "Number on a key",
+                    "Order of its first press, red when it broke the reading order",
+                ),
+                ("Ctrl x4", "Finish the test and go back"),
+                ("Tap Back", "Back to the home menu"),
+                ("F1", "This help"),
+            ],
+            KeyboardTestMode::Testing => vec![
+                ("Any key", "Counted and colored on the layout"),
+                ("●/○", "Codes of a multi-code key seen / not seen yet"),
+                (
+                    "N stale",
+                    "Keys silent for the stale window while others kept firing",
+                ),
+                (
+                    "D on another keyboard",
+                    "Show the keys counted from other keyboards",
+                ),
+                ("Ctrl x4", "Finish the test and go back"),
+                ("Tap Back", "Back to the home menu"),
+                ("F1", "This help"),
+            ],
+        }
     }
 
-    fn draw_layout_list(&self, frame: &mut Frame, area: Rect, selected: usize) {
-        // Render a simple list with the selected entry highlighted
-        let mut lines: Vec<Line> = Vec::with_capacity(LAYOUT_OPTIONS.len());
-        for (i, (name, ..)) in LAYOUT_OPTIONS.iter().enumerate() {
-            let marker = if i == selected { "› " } else { "  " };
-            let line = if i == selected {
-                Line::from(vec![Span::raw(marker), Span::raw(*name).bold().yellow()])
-            } else {
-                Line::from(vec![Span::raw(marker), Span::raw(*name)])
-            };
-            lines.push(line);
-        }
+    fn help_text(&self) -> &'static str {
+        "Press every key on the keyboard and check that the matching cell lights up. \
+         Colors go from green to red with the number of presses, see the legend under the header. \
+         Every key except F1 is under test, so leaving is done with Ctrl pressed four times in a row."
+    }
 
-        let p = Paragraph::new(lines)
-            .block(Block::bordered().title("Available layouts"))
-            .scroll((0, 0));
-        frame.render_widget(p, area);
+    fn repeats_navigation(&self) -> bool {
+        matches!(
+            self.mode,
+            KeyboardTestMode::SelectLayout { .. } | KeyboardTestMode::SelectOrder { .. }
+        )
     }
 
-    fn draw_select_footer(&self, frame: &mut Frame, area: Rect) {
-        let help = Line::from(vec![
-            "Use ".into(),
-            "↑/↓".bold(),
-            " to select • ".into(),
-            "Enter".bold(),
-            " to start test • ".into(),
-            "Ctrl x4".bold(),
-            " or ".into(),
-            "Q/Esc".bold(),
-            " to go back".into(),
-        ])
-        .centered();
+    fn wants_raw_keys(&self) -> bool {
+        // Keypad keys are under test like any other once a layout is picked
+        matches!(
+            self.mode,
+            KeyboardTestMode::Testing
+                | KeyboardTestMode::Guided(_)
+                | KeyboardTestMode::Repeat
+                | KeyboardTestMode::Compare(_)
+        )
+    }
 
-        let p = Paragraph::new(help);
-        frame.render_widget(p, area);
+    fn wants_key_repeats(&self) -> bool {
+        matches!(self.mode, KeyboardTestMode::Repeat)
     }
 
-    fn draw_header(&self, frame: &mut Frame, area: Rect) {
-        let last_pressed = match &self.last_key_press {
-            Some(AppEvent::Key { code, info }) => {
-                format!("Last pressed: {:?} from {}", code, info.name)
-            }
-            _ => "Last pressed: (none)".to_string(),
-        };
+    fn on_exit(&mut self) {
+        // Whatever happened, the keyboards leave with the settings they came with
+        key_autorepeat::restore_all();
+    }
+
+    fn settle(&mut self, event: &AppEvent) {
+        // A key held while leaving would otherwise count as stuck on return
+        if let AppEvent::KeyReleased { code, info, .. } = event {
+            self.simultaneous.release(info, *code);
+            self.ghosts.release(info, *code);
+        }
+    }
+
+    fn reconfigure(&mut self, options: &Options) {
+        // Counts are kept, only how they're shown and judged changes
+        self.palette = options.press_palette;
+        self.theme = options.theme;
+        self.language = options.language;
+        self.stale_window = options.stale_window;
+        self.auto_advance.set_enabled(options.auto_advance);
+        self.timer
+            .set_min(Duration::from_secs(options.min_durations.keyboard_secs));
+        // The layout files are read again with the config, like F5 on the selection
+        let reloaded = self.custom_layouts.set_dir(options.layouts_dir.clone());
+        self.layouts_reloaded(reloaded, false);
+    }
+
+    fn progress(&self) -> Option<f32> {
+        let keys: Vec<_> = self
+            .keyboard_layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .filter(|key| !key.is_continuation() && !self.exclusions.is_excluded(key))
+            .collect();
+        let complete = keys
+            .iter()
+            .filter(|key| key.is_complete(|kc| self.presses(kc) > 0))
+            .count();
+        Some(complete as f32 / keys.len().max(1) as f32)
+    }
+
+    fn take_results(&mut self) -> Option<ScreenResult> {
+        // Nothing was tested while still picking a layout
+        if let KeyboardTestMode::SelectLayout { .. } | KeyboardTestMode::SelectOrder { .. } =
+            self.mode
+        {
+            return None;
+        }
+
+        self.repeat.finish_hold();
+        let guided = match &self.mode {
+            KeyboardTestMode::Guided(run) => Some(run),
+            _ => None,
+        };
+
+        let rows: Vec<Vec<KeyResult>> = self
+            .keyboard_layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .map(|row| {
+                row.iter()
+                    .filter(|key| !key.is_continuation())
+                    .map(|key| KeyResult {
+                        label: key.label.to_string(),
+                        codes: key.codes.iter().map(|kc| format!("{:?}", kc)).collect(),
+                        presses: key.codes.iter().map(|kc| self.presses(*kc)).sum(),
+                        complete: key.is_complete(|kc| self.presses(kc) > 0),
+                        attempts: guided.and_then(|run| {
+                            run.keys
+                                .iter()
+                                .find(|guided| guided.key == *key)
+                                .map(|guided| guided.attempts)
+                        }),
+                        outcome: guided
+                            .and_then(|run| run.outcome_of(key))
+                            .map(|outcome| outcome.name().to_string()),
+                        first_seen_ms: key
+                            .codes
+                            .iter()
+                            .filter_map(|kc| self.key_times.get(kc))
+                            .map(|(first, _)| *first)
+                            .min(),
+                        last_seen_ms: key
+                            .codes
+                            .iter()
+                            .filter_map(|kc| self.key_times.get(kc))
+                            .map(|(_, last)| *last)
+                            .max(),
+                        missing: if key.any_of {
+                            Vec::new()
+                        } else {
+                            key.codes
+                                .iter()
+                                .filter(|kc| self.presses(**kc) == 0)
+                                .map(|kc| format!("{:?}", kc))
+                                .collect()
+                        },
+                        excluded: self.exclusions.reason(key).map(str::to_string),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let keys_total = rows.iter().map(|row| row.len()).sum();
+        let keys_pressed = rows.iter().flatten().filter(|key| key.complete).count();
+
+        let in_layout: HashSet<KeyCode> = self
+            .keyboard_layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .flat_map(|key| key.codes.iter().copied())
+            .collect();
+        let code_list = |codes: Vec<(&KeyCode, &usize)>| {
+            let mut codes: Vec<(KeyCode, usize)> = codes
+                .into_iter()
+                .map(|(code, count)| (*code, *count))
+                .collect();
+            codes.sort_by_key(|(code, _)| code.0);
+            codes
+                .into_iter()
+                .map(|(code, count)| format!("{code:?} x{count}"))
+                .collect()
+        };
+        let unexpected_keys = code_list(
+            self.pressed_keys
+                .iter()
+                .filter(|(code, _)| !in_layout.contains(code))
+                .collect(),
+        );
+        let phantom_keys = code_list(self.phantom_presses.iter().collect());
+        let ordinals = self.press_ordinals();
+        let stale_keys = self
+            .stale_keys()
+            .into_iter()
+            .map(|(code, silent)| format!("{code:?} silent {} s", silent.as_secs()))
+            .collect();
 
-        let title = Line::from(vec![
+        Some(ScreenResult::Keyboard(KeyboardResult {
+            layout: self.layout_name.to_string(),
+            keys_total,
+            keys_pressed,
+            total_presses: self.pressed_keys.values().sum(),
+            peak_keys_per_sec: self.peak_keys_per_sec,
+            guided: guided.map(|run| GuidedResult {
+                order: run.order.name().to_string(),
+                passed: run.count(KeyOutcome::Passed),
+                missing: run.count(KeyOutcome::Missing),
+                mismatched: run.count(KeyOutcome::Mismatched),
+            }),
+            rows,
+            excluded_keys: self.exclusions.results(),
+            unexpected_keys,
+            phantom_keys,
+            stale_window_secs: self.stale_window.as_secs(),
+            stale_keys,
+            press_order: ordinals
+                .iter()
+                .map(|(key, ..)| key.label.to_string())
+                .collect(),
+            out_of_order: self.ordering.then(|| {
+                ordinals
+                    .iter()
+                    .filter(|(_, _, flagged)| *flagged)
+                    .map(|(key, ordinal, _)| format!("{} (#{ordinal})", key.label))
+                    .collect()
+            }),
+            quirks: self
+                .quirks_seen
+                .iter()
+                .map(|quirk| quirk.describe())
+                .collect(),
+            repeat_checks: self.repeat.checks().to_vec(),
+            simultaneous: self.simultaneous.results(),
+            ghost_suspects: self.ghosts.results(),
+            tested_keyboard: self.tested_keyboard.as_ref().map(|info| info.name.clone()),
+            other_keyboards: self.other_keyboards.results(),
+            power: self.power.result(),
+            timing: Some(self.timer.timing()),
+            comparison: match &self.mode {
+                KeyboardTestMode::Compare(comparison) => {
+                    Some(comparison.result(self.keyboard_layout))
+                }
+                _ => None,
+            },
+        }))
+    }
+}
+
+impl KeyboardTestScreen {
+    fn draw_layout_header(&self, frame: &mut Frame, area: Rect) {
+        let keyboard = match (&self.tested_keyboard, self.compare_picks.first()) {
+            (_, Some(picked)) => {
+                format!("comparing {}, C on the other keyboard", picked.name).yellow()
+            }
+            (Some(info), None) => format!("testing {} only", info.name).yellow(),
+            (None, None) => "all keyboards, K on one to test only it".gray(),
+        };
+        let mut title = Line::from(vec![
             "Keyboard Test".bold().cyan(),
             " | ".into(),
-            last_pressed.gray(),
+            "Select keyboard layout".gray(),
+            " | ".into(),
+            keyboard,
         ]);
-
+        if let Some((notice, _)) = self
+            .layouts_notice
+            .as_ref()
+            .filter(|(_, time)| time.elapsed() < LAYOUTS_NOTICE_DURATION)
+        {
+            title.push_span(" | ");
+            title.push_span(notice.as_str().green());
+        }
         let p = Paragraph::new(title).block(Block::bordered());
+        frame.render_widget(p, area);
+    }
 
+    fn draw_layout_list(&self, frame: &mut Frame, area: Rect, selected: usize) {
+        // Render a simple list with the selected entry highlighted
+        let names = LAYOUT_OPTIONS
+            .iter()
+            .map(|(name, ..)| (name.to_string(), false))
+            .chain(
+                self.custom_layouts
+                    .files()
+                    .iter()
+                    .map(|file| (file.name(), file.error.is_some())),
+            );
+        let mut lines: Vec<Line> = Vec::with_capacity(self.layout_count());
+        for (i, (name, broken)) in names.enumerate() {
+            let marker = if i == selected { "› " } else { "  " };
+            let mut line = if i == selected {
+                Line::from(vec![Span::raw(marker), Span::raw(name).bold().yellow()])
+            } else {
+                Line::from(vec![Span::raw(marker), Span::raw(name)])
+            };
+            if broken {
+                line.push_span(" (file has errors)".red());
+            }
+            lines.push(line);
+        }
+
+        let p = Paragraph::new(lines)
+            .block(Block::bordered().title("Available layouts"))
+            .scroll((0, 0));
         frame.render_widget(p, area);
     }
 
-    fn draw_keyboard(&self, frame: &mut Frame, area: Rect) {
-        let vertical_chunks =
-            Layout::vertical(self.keyboard_layout.iter().map(|_| Constraint::Fill(1))).split(area);
+    /// Where the selected layout comes from, its rows of keys, and why its file
+    /// doesn't parse
+    fn draw_layout_preview(&self, frame: &mut Frame, area: Rect, selected: usize) {
+        let mut lines = Vec::new();
+        let file = selected
+            .checked_sub(LAYOUT_OPTIONS.len())
+            .map(|index| &self.custom_layouts.files()[index]);
+        match file {
+            Some(file) => lines.push(Line::from(file.path.display().to_string()).gray()),
+            None => lines.push(Line::from("Built in").gray()),
+        }
+        if let Some(error) = file.and_then(|file| file.error.as_ref()) {
+            lines.push(Line::from(format!("Not reloaded: {error}")).red());
+            if file.is_some_and(|file| file.layout.is_some()) {
+                lines.push(Line::from("Showing the last version that parsed").yellow());
+            }
+        }
+        if let Some((_, layout)) = self.layout_option(selected) {
+            lines.push(Line::from(format!(
+                "{} keys",
+                keyboard_layouts::key_count(layout)
+            )));
+            for row in layout
+                .iter()
+                .flat_map(|section| section.iter())
+                .flat_map(|block| block.iter())
+            {
+                let labels: Vec<&str> = row
+                    .iter()
+                    .filter(|key| !key.is_continuation())
+                    .map(|key| key.display_label(self.language))
+                    .collect();
+                lines.push(Line::from(labels.join(" ")));
+            }
+        }
 
-        self.draw_key_grid(frame, vertical_chunks[0], self.keyboard_layout[0][0]);
+        let p = Paragraph::new(lines)
+            .block(Block::bordered().title("Preview"))
+            .wrap(Wrap { trim: false });
+        frame.render_widget(p, area);
+    }
 
-        if vertical_chunks.len() < 2 {
+    fn draw_order_list(&self, frame: &mut Frame, area: Rect, selected: usize) {
+        let lines: Vec<Line> = GUIDED_ORDERS
+            .iter()
+            .enumerate()
+            .map(|(i, order)| {
+                let name = match order {
+                    KeyOrder::Random => "Random order",
+                    _ => "Layout order",
+                };
+                if i == selected {
+                    Line::from(vec![Span::raw("› "), Span::raw(name).bold().yellow()])
+                } else {
+                    Line::from(vec![Span::raw("  "), Span::raw(name)])
+                }
+            })
+            .collect();
+
+        let p = Paragraph::new(lines).block(
+            Block::bordered().title(format!("Guided test on {} - key order", self.layout_name)),
+        );
+        frame.render_widget(p, area);
+    }
+
+    fn draw_select_footer(&self, frame: &mut Frame, area: Rect) {
+        let help = Line::from(vec![
+            "Use ".into(),
+            "↑/↓".bold(),
+            " to select • ".into(),
+            "Enter".bold(),
+            " to start test • ".into(),
+            "G".bold(),
+            " guided test • ".into(),
+            "O".bold(),
+            " ordered test • ".into(),
+            "R".bold(),
+            " repeat test • ".into(),
+            "Ctrl x4".bold(),
+            " or ".into(),
+            "Q/Esc".bold(),
+            " to go back".into(),
+        ])
+        .centered();
+
+        let p = Paragraph::new(help);
+        frame.render_widget(p, area);
+    }
+
+    fn draw_header(&self, frame: &mut Frame, area: Rect) {
+        if self.ordering {
+            self.draw_ordering_header(frame, area);
             return;
         }
+        let last_pressed = match &self.last_key_press {
+            Some(AppEvent::Key { code, info, .. }) => format!(
+                "Last pressed: {:?} from {} ({})",
+                code,
+                info.name,
+                key_quirks::bus_label(info.bus)
+            ),
+            _ => "Last pressed: (none)".to_string(),
+        };
 
-        let horizontal_constraints = self.keyboard_layout[1].iter().map(|_| Constraint::Min(0));
+        let mut title = vec![
+            "Keyboard Test".bold().cyan(),
+            " | ".into(),
+            format!("{} keys/s", self.keys_per_sec()).yellow(),
+            " | ".into(),
+        ];
+        let simultaneous = self.simultaneous.max();
+        if simultaneous > 0 {
+            title.push(format!("max simultaneous: {simultaneous}").yellow());
+            title.push(" | ".into());
+        }
+        let ghosts = self.ghosts.count();
+        if ghosts > 0 {
+            title.push(format!("possible ghosting: {ghosts}").red());
+            title.push(" | ".into());
+        }
+        title.push(last_pressed.gray());
+        let quirk = match &self.last_key_press {
+            Some(AppEvent::Key { info, .. }) => key_quirks::quirk_for(info),
+            _ => None,
+        };
+        if let Some(quirk) = quirk {
+            title.push(format!(" | known {} quirks", quirk.name).yellow());
+        }
+        let stale = self.stale_keys().len();
+        if stale > 0 {
+            title.push(format!(" | {stale} stale").red().bold());
+        }
+        let repeat = match &self.last_key_press {
+            Some(AppEvent::Key { info, .. }) => self.repeat_settings.get(&info.path).copied(),
+            _ => None,
+        };
+        if let Some(settings) = repeat.flatten() {
+            title.push(format!(" | repeat {}", settings.label()).gray());
+        }
+        title.extend(self.other_keyboards_label());
+        if !self.exclusions.is_empty() {
+            title.push(format!(" | {} excluded", self.exclusions.len()).dark_gray());
+        }
+        title.push(" | ".into());
+        title.push(self.timer.label());
+        title.extend(self.power_label());
+        let title = Line::from(title);
 
-        let horizontal_chunks = Layout::horizontal(horizontal_constraints)
-            .spacing(2)
-            .split(vertical_chunks[1]);
+        let p = Paragraph::new(title).block(Block::bordered());
 
-        for (i, layout) in self.keyboard_layout[1].iter().enumerate() {
-            self.draw_key_grid(frame, horizontal_chunks[i], layout);
-        }
+        frame.render_widget(p, area);
     }
 
-    fn draw_key_grid(&self, frame: &mut Frame, area: Rect, keys: &[&[(&str, &[KeyCode])]]) {
-        let key_height = 3;
-        let row_spacing = 0;
-        let column_spacing = 0;
+    fn draw_repeat_header(&self, frame: &mut Frame, area: Rect) {
+        let keyboard = match self.repeat.device() {
+            Some(info) => info.name.clone(),
+            None => "press a key on the keyboard to test".to_string(),
+        };
+        let mut title = vec![
+            "Repeat Test".bold().cyan(),
+            " | ".into(),
+            keyboard.gray(),
+            " | ".into(),
+            self.timer.label(),
+        ];
+        title.extend(self.power_label());
+        let p = Paragraph::new(Line::from(title)).block(Block::bordered());
+        frame.render_widget(p, area);
+    }
 
-        let row_constraints = keys.iter().map(|_| Constraint::Length(key_height));
+    fn draw_repeat_test(&self, frame: &mut Frame, area: Rect) {
+        let configured = self.repeat.configured();
+        let mut lines = vec![match configured {
+            Some(settings) => Line::from(vec![
+                "Configured: ".into(),
+                settings.label().yellow(),
+                format!(" (every {} ms)", settings.period_ms).gray(),
+            ]),
+            None if self.repeat.device().is_some() => {
+                Line::from("Configured: no autorepeat settings".red())
+            }
+            None => Line::from("Configured: -".gray()),
+        }];
+        if self.repeat.not_adjustable() {
+            lines.push(Line::from("Settings: not adjustable".red().bold()));
+        }
 
-        let vchunks = Layout::vertical(row_constraints)
-            .spacing(row_spacing)
-            .split(area);
+        lines.push(Line::from(match (self.repeat.measured(), configured) {
+            (Some(measured), Some(configured)) if measured.tracks(&configured) => vec![
+                "Measured:   ".into(),
+                measured.label().green(),
+                "  ✓ follows the settings".green(),
+            ],
+            (Some(measured), Some(_)) => vec![
+                "Measured:   ".into(),
+                measured.label().red(),
+                "  ✗ off the settings".red().bold(),
+            ],
+            (Some(measured), None) => vec!["Measured:   ".into(), measured.label().yellow()],
+            (None, _) => vec!["Measured:   hold a key until it repeats".gray()],
+        }));
 
-        for (i, row) in keys.iter().enumerate() {
-            let row_area = vchunks[i];
+        lines.push(Line::from(""));
+        let preset = |preset: RepeatSettings| format!(" {} • ", preset.label());
+        lines.push(Line::from(vec![
+            "1".bold(),
+            preset(PRESETS[0]).into(),
+            "2".bold(),
+            preset(PRESETS[1]).into(),
+            "0".bold(),
+            " original settings".into(),
+        ]));
 
-            let h_constraints = row
+        let checks = self.repeat.checks();
+        if !checks.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from("Holds:".bold()));
+        }
+        let shown = area.height.saturating_sub(lines.len() as u16 + 2) as usize;
+        for check in checks.iter().rev().take(shown).rev() {
+            let text = format!(
+                "{}: {} ms / every {} ms, set {} ms / every {} ms",
+                check.key,
+                check.measured_delay_ms,
+                check.measured_period_ms,
+                check.configured_delay_ms,
+                check.configured_period_ms
+            );
+            lines.push(Line::from(if check.within_tolerance {
+                text.green()
+            } else {
+                text.red()
+            }));
+        }
+
+        let p = Paragraph::new(lines).block(Block::bordered().title("Autorepeat"));
+        frame.render_widget(p, area);
+    }
+
+    fn draw_compare_header(&self, frame: &mut Frame, area: Rect, comparison: &KeyboardComparison) {
+        let mut title = vec![
+            "Keyboard Comparison".bold().cyan(),
+            " | ".into(),
+            format!("A: {}", comparison.side(0).info.name).yellow(),
+            " | ".into(),
+            format!("B: {}", comparison.side(1).info.name).yellow(),
+        ];
+        title.extend(self.other_keyboards_label());
+        title.push(" | ".into());
+        title.push(self.timer.label());
+        title.extend(self.power_label());
+        let p = Paragraph::new(Line::from(title)).block(Block::bordered());
+        frame.render_widget(p, area);
+    }
+
+    /// The keys telling the two keyboards apart, in place of the legend
+    fn draw_compare_summary(&self, frame: &mut Frame, area: Rect, comparison: &KeyboardComparison) {
+        let only = |index: usize| {
+            let keys = comparison.only_on(self.keyboard_layout, index);
+            let count = format!("{}", keys.len());
+            let labels: Vec<&str> = keys
                 .iter()
-                .map(|(label, _)| Constraint::Min(label.len() as u16 + 2));
+                .map(|key| key.display_label(self.language))
+                .collect();
+            let count = if keys.is_empty() {
+                count.green()
+            } else {
+                count.red().bold()
+            };
+            (count, labels.join(" "))
+        };
+        let (only_a, labels_a) = only(0);
+        let (only_b, labels_b) = only(1);
+        let mut spans = vec!["Pressed on A but not B: ".into(), only_a];
+        if !labels_a.is_empty() {
+            spans.push(format!(" ({labels_a})").gray());
+        }
+        spans.push(" • on B but not A: ".into());
+        spans.push(only_b);
+        if !labels_b.is_empty() {
+            spans.push(format!(" ({labels_b})").gray());
+        }
+        frame.render_widget(Paragraph::new(Line::from(spans)).centered(), area);
+    }
 
-            let hchunks = Layout::horizontal(h_constraints)
-                .spacing(column_spacing)
-                .split(row_area);
+    fn draw_compared_keyboard(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        keys: &[(Key, Rect)],
+        comparison: &KeyboardComparison,
+        index: usize,
+    ) {
+        let side: &ComparedKeyboard = comparison.side(index);
+        let name = if index == 0 { "A" } else { "B" };
+        let block = Block::bordered().title(format!(" {name}: {} ", side.info.name));
+        frame.render_widget(block, area);
+        self.draw_keyboard(frame, keys, &|code| side.presses(code));
+    }
 
-            for (i, (label, keycodes)) in row.iter().enumerate() {
-                let key_rect = hchunks[i];
+    /// " | other keyboards: 3 events, last KEY_A from 'USB Keyboard'" for the
+    /// headers, None until a keyboard left out sent a key
+    fn other_keyboards_label(&self) -> Option<Span<'static>> {
+        let (code, name) = self.other_keyboards.latest()?;
+        Some(
+            format!(
+                " | other keyboards: {} events, last {code:?} from '{name}'",
+                self.other_keyboards.total()
+            )
+            .dark_gray(),
+        )
+    }
 
-                // Check if any of the keycodes for this button have been pressed
-                let press_count = keycodes
+    /// Keyboards left out with their event counts, over the bottom right of `area`
+    /// The exclusion picker, centered over the finished guided run
+    fn draw_exclusion_step(&self, frame: &mut Frame, area: Rect, step: &ExclusionStep) {
+        let choices = |items: Vec<String>, selected: usize| -> Vec<Line> {
+            items
+                .into_iter()
+                .enumerate()
+                .map(|(i, item)| {
+                    if i == selected {
+                        Line::from(format!("› {item}")).yellow().bold()
+                    } else {
+                        Line::from(format!("  {item}"))
+                    }
+                })
+                .collect()
+        };
+        let (title, mut lines) = match step {
+            ExclusionStep::ChooseKey { selected } => {
+                let keys = self
+                    .exclusion_candidates()
+                    .into_iter()
+                    .map(|key| match self.exclusions.reason(&key) {
+                        Some(reason) => {
+                            format!("{} (excluded: {reason})", key.display_label(self.language))
+                        }
+                        None => key.display_label(self.language).to_string(),
+                    })
+                    .collect();
+                (" Exclude which key? ", choices(keys, *selected))
+            }
+            ExclusionStep::ChooseReason { key, selected } => {
+                let reasons = key_exclusions::REASONS
                     .iter()
-                    .map(|kc| self.pressed_keys.get(kc).unwrap_or(&0))
-                    .sum::<usize>();
+                    .map(|reason| reason.to_string())
+                    .collect();
+                let mut lines = vec![Line::from(format!(
+                    "Why is {} broken?",
+                    key.display_label(self.language)
+                ))];
+                lines.extend(choices(reasons, *selected));
+                (" Reason ", lines)
+            }
+            ExclusionStep::Confirm { key, reason } => {
+                let question = match reason {
+                    Some(reason) => format!(
+                        "Exclude {} from the test ({reason})?",
+                        key.display_label(self.language)
+                    ),
+                    None => format!(
+                        "Count {} in the test again?",
+                        key.display_label(self.language)
+                    ),
+                };
+                (
+                    " Confirm ",
+                    vec![
+                        Line::from(question),
+                        Line::from("Y confirm • any other key cancels".gray()),
+                    ],
+                )
+            }
+        };
+        if lines.is_empty() {
+            lines.push(Line::from("no failed keys".gray()));
+        }
+        if !matches!(step, ExclusionStep::Confirm { .. }) {
+            lines.push(Line::from("↑/↓ select • Enter choose • Esc back".gray()));
+        }
+
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(24) as u16 + 4;
+        let height = lines.len() as u16 + 2;
+        let panel = Rect {
+            x: area.x + area.width.saturating_sub(width) / 2,
+            y: area.y + area.height.saturating_sub(height) / 2,
+            width,
+            height,
+        }
+        .intersection(area);
+        frame.render_widget(Clear, panel);
+        let p = Paragraph::new(lines).block(
+            Block::bordered()
+                .title(title)
+                .padding(Padding::horizontal(1)),
+        );
+        frame.render_widget(p, panel);
+    }
+
+    fn draw_other_keyboards(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = self
+            .other_keyboards
+            .devices()
+            .map(|(name, events)| Line::from(format!("{events:>6}  {name}")))
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("no keys from other keyboards".gray()));
+        }
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(20) as u16 + 2;
+        let height = lines.len() as u16 + 2;
+        let panel = Rect {
+            x: area.right().saturating_sub(width),
+            y: area.bottom().saturating_sub(height),
+            width,
+            height,
+        }
+        .intersection(area);
+        frame.render_widget(Clear, panel);
+        let p = Paragraph::new(lines).block(Block::bordered().title(" Other keyboards "));
+        frame.render_widget(p, panel);
+    }
+
+    fn draw_ghosts(&self, frame: &mut Frame, area: Rect) {
+        let mut lines: Vec<Line> = self
+            .ghosts
+            .results()
+            .iter()
+            .map(|suspect| {
+                Line::from(format!(
+                    "{:>4}x  {} with {} held",
+                    suspect.count,
+                    suspect.phantom,
+                    suspect.held.join(" ")
+                ))
+            })
+            .collect();
+        if lines.is_empty() {
+            lines.push(Line::from("no presses that looked like ghosting".gray()));
+        }
+        let width = lines.iter().map(Line::width).max().unwrap_or(0).max(20) as u16 + 2;
+        let height = lines.len() as u16 + 2;
+        let panel = Rect {
+            x: area.x,
+            y: area.bottom().saturating_sub(height),
+            width,
+            height,
+        }
+        .intersection(area);
+        frame.render_widget(Clear, panel);
+        let p = Paragraph::new(lines).block(Block::bordered().title(" Possible ghosting "));
+        frame.render_widget(p, panel);
+    }
+
+    /// " | battery 54%" for the headers, None without power supply entries
+    fn power_label(&self) -> Option<Span<'static>> {
+        self.power
+            .current()
+            .map(|state| format!(" | {}", state.label()).gray())
+    }
+
+    fn draw_ordering_header(&self, frame: &mut Frame, area: Rect) {
+        let recent: Vec<String> = self
+            .recent_codes
+            .iter()
+            .map(|code| {
+                let name = format!("{code:?}");
+                name.strip_prefix("KEY_").unwrap_or(&name).to_string()
+            })
+            .collect();
+        let out_of_order = self
+            .press_ordinals()
+            .iter()
+            .filter(|(_, _, flagged)| *flagged)
+            .count();
+
+        let mut title = vec![
+            "Ordered Test".bold().cyan(),
+            " | Last: ".into(),
+            recent.join(" ").yellow(),
+        ];
+        if out_of_order > 0 {
+            title.push(format!(" | {out_of_order} out of order").red().bold());
+        }
+        title.push(" | ".into());
+        title.push(self.timer.label());
+        title.extend(self.power_label());
+
+        let p = Paragraph::new(Line::from(title)).block(Block::bordered());
+        frame.render_widget(p, area);
+    }
+
+    fn draw_guided_header(&self, frame: &mut Frame, area: Rect, run: &GuidedRun) {
+        let (done, total) = run.progress();
+
+        let mut title = match run.target() {
+            Some(target) => {
+                let codes = if target.key.codes.len() > 1 && !target.key.any_of {
+                    format!(" (all of {:?})", target.key.codes)
+                } else {
+                    String::new()
+                };
+                Line::from(vec![
+                    "Guided Test".bold().cyan(),
+                    format!(" | {}/{} | ", done + 1, total).into(),
+                    "Press: ".into(),
+                    target.key.display_label(self.language).bold().yellow(),
+                    codes.gray(),
+                    format!(" | {}s", run.time_left(Instant::now()).as_secs() + 1).gray(),
+                ])
+            }
+            None => {
+                let retest = if self.any_counted_failed(run) {
+                    " • R re-test failed keys"
+                } else {
+                    ""
+                };
+                let exclude = if run.any_failed() || !self.exclusions.is_empty() {
+                    " • X exclude keys"
+                } else {
+                    ""
+                };
+                let stale = if self.show_stale {
+                    " • S keyboard"
+                } else {
+                    " • S stale keys"
+                };
+                Line::from(vec![
+                    "Guided Test done".bold().cyan(),
+                    " | ".into(),
+                    format!("{} passed ", run.count(KeyOutcome::Passed)).green(),
+                    format!("{} missing ", run.count(KeyOutcome::Missing)).red(),
+                    format!("{} mismatched", run.count(KeyOutcome::Mismatched)).magenta(),
+                    format!(" | Enter free test{retest}{exclude}{stale}").gray(),
+                ])
+            }
+        };
+        title.push_span(" | ");
+        title.push_span(self.timer.label());
+
+        let p = Paragraph::new(title).block(Block::bordered());
+        frame.render_widget(p, area);
+    }
+
+    fn draw_stale_keys(&self, frame: &mut Frame, area: Rect) {
+        let stale = self.stale_keys();
+        let window = self.stale_window.as_secs() / 60;
+        let mut lines = vec![
+            Line::from(format!(
+                "Keys silent for over {window} min while other keys kept firing"
+            ))
+            .bold(),
+            Line::from(""),
+        ];
+        if stale.is_empty() {
+            lines.push(Line::from(
+                "None, every key pressed so far is still firing".green(),
+            ));
+        }
+        for (code, silent) in stale {
+            lines.push(Line::from(vec![
+                format!("{code:?}").red().bold(),
+                format!(
+                    "  last seen {} min before the newest press",
+                    silent.as_secs() / 60
+                )
+                .gray(),
+            ]));
+        }
+
+        let p = Paragraph::new(lines).block(Block::bordered().title(" Stale keys "));
+        frame.render_widget(p, area);
+    }
+
+    fn draw_legend(&self, frame: &mut Frame, area: Rect) {
+        let mut spans: Vec<Span> = vec!["Presses: ".gray()];
+        for (label, color) in self.theme.press_legend(self.palette) {
+            spans.push(Span::styled(
+                format!(" {label} "),
+                Style::default().bg(color).black(),
+            ));
+        }
+        spans.push(
+            match self.palette {
+                PressPalette::Ramp { .. } => "  higher counts are shown on the key",
+                PressPalette::Cycle => "  then the colors repeat",
+            }
+            .gray(),
+        );
+        spans.push("  ".into());
+        let (partial, _) = self.theme.key(KeyState::Partial, self.palette);
+        spans.push(Span::styled("partial", partial));
+        let (chatter, _) = self.theme.key(KeyState::Chatter, self.palette);
+        spans.push("  ".into());
+        spans.push(Span::styled(" ! chatter ", chatter));
+        let (stuck, _) = self.theme.key(KeyState::Stuck, self.palette);
+        spans.push("  ".into());
+        spans.push(Span::styled(" stuck blinks ", stuck));
+        if !self.exclusions.is_empty() {
+            spans.push("  ".into());
+            spans.push("╱╱ excluded".dark_gray());
+        }
+
+        frame.render_widget(Paragraph::new(Line::from(spans)).centered(), area);
+    }
+
+    /// Where everything goes in a frame of `area`, computed apart from the
+    /// drawing so touch handling can use the same rectangles
+    fn layout(&self, area: Rect) -> ScreenLayout {
+        let mut rest = area;
+        let mut banner = |shown: bool| {
+            shown.then(|| {
+                let [banner, below] =
+                    Layout::vertical([Constraint::Length(3), Constraint::Min(0)]).areas(rest);
+                rest = below;
+                banner
+            })
+        };
+        let fault_banner = banner(self.active_fault().is_some());
+        let power_banner = banner(self.power.changed());
+        let echo_banner = banner(self.echo.leaking());
+
+        let [header, body, footer] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .areas(rest);
+        // The keyboard gives up a line for the press count legend
+        let [legend, keyboard] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(body);
 
-                self.draw_key(frame, key_rect, label, &press_count);
+        let keys = match &self.mode {
+            KeyboardTestMode::Testing => self.key_rects(keyboard, false),
+            KeyboardTestMode::Guided(run) if !(self.show_stale && run.is_finished()) => {
+                self.key_rects(keyboard, false)
             }
+            _ => Vec::new(),
+        };
+        let compared = match &self.mode {
+            KeyboardTestMode::Compare(_) => self.compared_rects(keyboard),
+            _ => Vec::new(),
+        };
+
+        ScreenLayout {
+            fault_banner,
+            power_banner,
+            echo_banner,
+            header,
+            body,
+            legend,
+            footer,
+            keys,
+            compared,
         }
     }
 
-    fn draw_key(&self, frame: &mut Frame, area: Rect, label: &str, press_count: &usize) {
-        let key_style = if *press_count == 0 {
-            Style::default()
+    /// The two keyboards of a comparison in `area`, side by side at half width
+    /// or one above the other when the terminal is too narrow for that
+    fn compared_rects(&self, area: Rect) -> Vec<(Rect, Vec<(Key, Rect)>)> {
+        let halves = if area.width >= COMPARE_SIDE_BY_SIDE_WIDTH {
+            Layout::horizontal([Constraint::Fill(1); 2])
+                .spacing(1)
+                .split(area)
         } else {
-            Style::default()
-                .bg(COLOR_LIST[(press_count - 1) % 5])
-                .black()
+            Layout::vertical([Constraint::Fill(1); 2]).split(area)
         };
+        halves
+            .iter()
+            .map(|half| (*half, self.key_rects(Block::bordered().inner(*half), true)))
+            .collect()
+    }
 
-        let block = Block::bordered().style(key_style);
+    /// Cell of every key of the layout drawn in `area`: the main block on top,
+    /// the blocks of the second row (arrows, numpad) side by side below it.
+    /// `compact` shares out the width instead of giving every label its room.
+    fn key_rects(&self, area: Rect, compact: bool) -> Vec<(Key, Rect)> {
+        let vertical_chunks =
+            Layout::vertical(self.keyboard_layout.iter().map(|_| Constraint::Fill(1))).split(area);
+
+        let mut keys = Vec::new();
+        key_grid_rects(
+            vertical_chunks[0],
+            self.keyboard_layout[0][0],
+            self.language,
+            compact,
+            &mut keys,
+        );
+
+        if vertical_chunks.len() < 2 {
+            return keys;
+        }
+
+        let horizontal_constraints = self.keyboard_layout[1].iter().map(|_| Constraint::Min(0));
+
+        let horizontal_chunks = Layout::horizontal(horizontal_constraints)
+            .spacing(2)
+            .split(vertical_chunks[1]);
 
+        for (i, layout) in self.keyboard_layout[1].iter().enumerate() {
+            key_grid_rects(
+                horizontal_chunks[i],
+                layout,
+                self.language,
+                compact,
+                &mut keys,
+            );
+        }
+        keys
+    }
+
+    fn draw_keyboard(
+        &self,
+        frame: &mut Frame,
+        keys: &[(Key, Rect)],
+        presses: &dyn Fn(KeyCode) -> usize,
+    ) {
+        let ordinals = if self.ordering {
+            self.press_ordinals()
+        } else {
+            Vec::new()
+        };
+        // Keys that didn't fit the terminal get an empty cell on its edge
+        for (key, rect) in keys.iter().filter(|(_, rect)| !rect.is_empty()) {
+            self.draw_key(frame, *rect, key, &ordinals, presses);
+            match key.shape {
+                KeyShape::Plain => {}
+                KeyShape::TallEnter if key.part == KeyPart::Upper => {
+                    let lower = keys.iter().find(|(other, other_rect)| {
+                        other.is_continuation()
+                            && other.is_same_key(key)
+                            && other_rect.y == rect.bottom()
+                    });
+                    if let Some((_, lower)) = lower {
+                        draw_tall_enter_joint(frame, *rect, *lower);
+                    }
+                }
+                KeyShape::TallEnter => {}
+                KeyShape::Stepped => draw_step(frame, *rect, key.display_label(self.language)),
+            }
+        }
+    }
+
+    /// One key cell, colored by the count `presses` gives for each code
+    fn draw_key(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        key: &Key,
+        ordinals: &[(Key, usize, bool)],
+        presses: &dyn Fn(KeyCode) -> usize,
+    ) {
+        let label = key.display_label(self.language);
+        let press_count: usize = key.codes.iter().map(|kc| presses(*kc)).sum();
+
+        let guided = match &self.mode {
+            KeyboardTestMode::Guided(run) => Some(run),
+            _ => None,
+        };
+        let is_target = guided
+            .and_then(|run| run.target())
+            .is_some_and(|target| target.key.is_same_key(key));
+        let outcome = guided.and_then(|run| run.outcome_of(key));
+
+        let now = self.event_now();
+        let state = if self.exclusions.is_excluded(key) {
+            KeyState::Excluded
+        } else if is_target {
+            KeyState::Target
+        } else if outcome == Some(KeyOutcome::Missing) {
+            KeyState::Missing
+        } else if outcome == Some(KeyOutcome::Mismatched) {
+            KeyState::Mismatched
+        } else if now.is_some_and(|now| {
+            key.codes
+                .iter()
+                .any(|kc| self.simultaneous.is_stuck(*kc, now))
+        }) {
+            KeyState::Stuck
+        } else if key.codes.iter().any(|kc| self.chatter.contains(kc)) {
+            KeyState::Chatter
+        } else if press_count == 0 {
+            KeyState::Untested
+        } else if !key.is_complete(|kc| presses(kc) > 0) {
+            KeyState::Partial
+        } else {
+            KeyState::Pressed(press_count)
+        };
+        let (key_style, decoration) = self.theme.key(state, self.palette);
+        let key_style = decoration.apply(key_style);
+
+        // The two cells of a two-row key leave out the border between them
+        let borders = match key.part {
+            KeyPart::Whole => Borders::ALL,
+            KeyPart::Upper => Borders::ALL - Borders::BOTTOM,
+            KeyPart::Lower => Borders::ALL - Borders::TOP,
+        };
+        let mut block = Block::new().borders(borders).style(key_style);
+        let ordinal = ordinals
+            .iter()
+            .find(|(ordered, ..)| ordered.is_same_key(key))
+            .filter(|_| !key.is_continuation());
+        if let Some((_, ordinal, flagged)) = ordinal {
+            // An ordered test shows when the key came instead of how often
+            let ordinal = Line::from(ordinal.to_string()).right_aligned();
+            block = block.title_top(if *flagged {
+                ordinal.red().bold()
+            } else {
+                ordinal
+            });
+        } else if press_count > 0
+            && self.palette.is_clamped(press_count)
+            && !key.is_continuation()
+            && !self.ordering
+        {
+            // Past the end of the ramp the color stays put, so the count goes on the border
+            block = block.title_top(Line::from(press_count.to_string()).right_aligned());
+        }
+
+        let inner = block.inner(area);
         frame.render_widget(block, area);
+        if decoration.hatch {
+            // Hatched, so a key left out doesn't read as one not pressed yet
+            let hatch = vec![Line::from("╱".repeat(inner.width as usize)); inner.height as usize];
+            frame.render_widget(Paragraph::new(hatch).dark_gray(), inner);
+        }
 
-        let key_label = Line::from(label);
+        // One marker per code on the bottom border, filled once that code fired
+        if key.codes.len() > 1 && !key.any_of && key.part != KeyPart::Upper && area.height >= 2 {
+            let markers: String = key
+                .codes
+                .iter()
+                .map(|kc| if presses(*kc) > 0 { '●' } else { '○' })
+                .collect();
+            let width = (key.codes.len() as u16).min(area.width);
+            let marker_position = Rect {
+                x: area.x + (area.width.saturating_sub(width)) / 2,
+                y: area.y + area.height - 1,
+                width,
+                height: 1,
+            };
+            frame.render_widget(Paragraph::new(markers), marker_position);
+        }
 
+        // The label goes on the upper cell only
+        if key.is_continuation() {
+            return;
+        }
+        // A compact cell can be narrower than its label. The label is cut
+        // rather than the cue, which is what tells the state without color.
+        let room = area.width.saturating_sub(2);
+        let label = match decoration.suffix {
+            Some(suffix) => {
+                let cut = fit_label(label, room.saturating_sub(1));
+                fit_label(&format!("{cut}{suffix}"), room)
+            }
+            None => fit_label(label, room),
+        };
+        let key_label = Line::from(label.as_str());
+
+        let width = label_width(&label);
         let text_position = Rect {
-            x: area.x + (area.width.saturating_sub(label.len() as u16)) / 2,
+            x: area.x + (area.width.saturating_sub(width)) / 2,
             y: area.y + (area.height / 2),
-            width: label.len() as u16,
+            width,
             height: 1,
         };
 
@@ -307,7 +2332,9 @@ impl KeyboardTestScreen {
         let help = Line::from(vec![
             "Press CTRL ".into(),
             format!("{}", 4 - self.ctrl_presses).yellow().bold(),
-            " times in a row to quit".into(),
+            " times in a row to quit • ".into(),
+            "F1".bold(),
+            " help".into(),
         ])
         .centered();
 
@@ -315,3 +2342,591 @@ impl KeyboardTestScreen {
         frame.render_widget(p, area);
     }
 }
+
+/// Close the upper cell of an ISO Enter where it reaches past the lower cell,
+/// so the open edge between the two only spans the lower one and the pair
+/// reads as one L-shaped key:
+///
+/// ```text
+/// ┌──────┐
+/// │Enter │
+/// └─┐    │
+///   │    │
+/// ```
+fn draw_tall_enter_joint(frame: &mut Frame, upper: Rect, lower: Rect) {
+    if upper.height < 2 || lower.x <= upper.x || lower.x >= upper.right() {
+        return;
+    }
+    let y = upper.bottom() - 1;
+    let buffer = frame.buffer_mut();
+    for x in upper.x..=lower.x {
+        let symbol = if x == upper.x {
+            "└"
+        } else if x == lower.x {
+            "┐"
+        } else {
+            "─"
+        };
+        if let Some(cell) = buffer.cell_mut((x, y)) {
+            cell.set_symbol(symbol);
+        }
+    }
+}
+
+/// Notch the top right corner of a stepped Caps Lock, when the label leaves room:
+///
+/// ```text
+/// ┌──────┐
+/// │Caps  └┐
+/// └───────┘
+/// ```
+fn draw_step(frame: &mut Frame, area: Rect, label: &str) {
+    // The step takes the last two columns, which have to be clear of the label
+    let width = label_width(label);
+    let label_end = (area.width.saturating_sub(width)) / 2 + width;
+    if area.height < 3 || label_end + 2 > area.width {
+        return;
+    }
+    let (right, y) = (area.right() - 1, area.y);
+    let buffer = frame.buffer_mut();
+    if let Some(cell) = buffer.cell_mut((right, y)) {
+        cell.reset();
+    }
+    // A press count on the border keeps its last digit
+    if let Some(cell) = buffer.cell_mut((right - 1, y))
+        && cell.symbol() == "─"
+    {
+        cell.set_symbol("┐");
+    }
+    if let Some(cell) = buffer.cell_mut((right - 1, y + 1)) {
+        cell.set_symbol("└");
+    }
+    if let Some(cell) = buffer.cell_mut((right, y + 1)) {
+        cell.set_symbol("┐");
+    }
+}
+
+/// Rows of keys in `area`, each key as wide as its label in `language` allows.
+/// Compact rows, and rows too narrow for their labels, fill the width with keys
+/// in proportion to their labels instead.
+fn key_grid_rects(
+    area: Rect,
+    keys: &[&[Key]],
+    language: Language,
+    compact: bool,
+    rects: &mut Vec<(Key, Rect)>,
+) {
+    let key_height = 3;
+    let row_spacing = 0;
+
+    let row_constraints = keys.iter().map(|_| Constraint::Length(key_height));
+
+    let vchunks = Layout::vertical(row_constraints)
+        .spacing(row_spacing)
+        .split(area);
+
+    for (i, row) in keys.iter().enumerate() {
+        let widths: Vec<u16> = row
+            .iter()
+            .map(|key| {
+                let width = label_width(key.display_label(language));
+                match key.shape {
+                    // Room for the step next to the label
+                    KeyShape::Stepped => width + 4,
+                    _ => width + 2,
+                }
+            })
+            .collect();
+        let cells = if compact || widths.iter().sum::<u16>() > vchunks[i].width {
+            shared_out(vchunks[i], &widths)
+        } else {
+            levelled(vchunks[i], &widths)
+        };
+        rects.extend(row.iter().copied().zip(cells));
+    }
+}
+
+/// `area` split left to right into cells as wide as `widths` in proportion.
+/// Worked out here rather than by the layout solver, which takes seconds over
+/// a row of twenty keys
+fn shared_out(area: Rect, widths: &[u16]) -> Vec<Rect> {
+    let total = widths
+        .iter()
+        .map(|&width| u32::from(width))
+        .sum::<u32>()
+        .max(1);
+    let edge = |before: u32| area.x + (u32::from(area.width) * before / total) as u16;
+    let mut before = 0;
+    cells(
+        area,
+        widths.iter().map(|&width| {
+            let left = edge(before);
+            before += u32::from(width);
+            edge(before) - left
+        }),
+    )
+}
+
+/// `area` split left to right into cells at least as wide as `widths`, the
+/// room left over going to the narrowest until they are level
+fn levelled(area: Rect, widths: &[u16]) -> Vec<Rect> {
+    let filled = |level: u16| -> u32 {
+        widths
+            .iter()
+            .map(|&width| u32::from(width.max(level)))
+            .sum()
+    };
+    let mut level = 0;
+    while level < area.width && filled(level + 1) <= u32::from(area.width) {
+        level += 1;
+    }
+    // What doesn't divide evenly is spread over the cells at the level
+    let spare = u32::from(area.width).saturating_sub(filled(level));
+    let at_level = widths.iter().filter(|&&width| width <= level).count() as u32;
+    let mut nth = 0;
+    cells(
+        area,
+        widths.iter().map(|&width| {
+            if width > level {
+                return width;
+            }
+            nth += 1;
+            let extra = nth * spare / at_level.max(1) - (nth - 1) * spare / at_level.max(1);
+            level + extra as u16
+        }),
+    )
+}
+
+/// Cells of `widths` side by side from the left of `area`
+fn cells(area: Rect, widths: impl Iterator<Item = u16>) -> Vec<Rect> {
+    let mut x = area.x;
+    widths
+        .map(|width| {
+            let cell = Rect { x, width, ..area };
+            x += width;
+            cell
+        })
+        .collect()
+}
+
+/// Columns a label takes on the terminal. Not its length in bytes: Å, ´ and ¨
+/// are two bytes but one column, and the cell would come out too wide
+fn label_width(label: &str) -> u16 {
+    UnicodeWidthStr::width(label) as u16
+}
+
+/// `label` cut to `width` columns
+fn fit_label(label: &str, width: u16) -> String {
+    let mut used = 0;
+    label
+        .chars()
+        .take_while(|c| {
+            used += c.width().unwrap_or(0) as u16;
+            used <= width
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::machine_detect::ComputerModel;
+    use crate::snapshot::{
+        LAYOUT_SIZES, assert_screen, assert_screen_ascii, assert_snapshot, assert_within_and_apart,
+        press, release, render, render_colors,
+    };
+
+    // No battery readout, whatever machine the tests run on
+    fn screen() -> KeyboardTestScreen {
+        let mut screen = KeyboardTestScreen::for_model(ComputerModel::DatorBBFält);
+        screen.power = PowerMonitor::with_dir("/nonexistent");
+        screen
+    }
+
+    #[test]
+    fn layout_selection_snapshots() {
+        assert_screen("keyboard_selection", &screen());
+    }
+
+    /// Press and release every key of the layout under test, from `at` on
+    fn press_every_key(screen: &mut KeyboardTestScreen, at: u128) {
+        let codes: Vec<KeyCode> = screen
+            .keyboard_layout
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .filter(|key| !key.is_continuation())
+            .flat_map(|key| key.codes.iter().copied())
+            .collect();
+        for (i, code) in codes.into_iter().enumerate() {
+            let at = at + i as u128 * 100;
+            screen.handle_event(press(code, at));
+            screen.handle_event(release(code, at + 50));
+        }
+    }
+
+    #[test]
+    fn a_passed_test_waits_for_its_minimum_duration_to_advance() {
+        let mut screen = screen()
+            .with_auto_advance(true)
+            .with_min_duration(Duration::from_secs(300));
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        screen.handle_event(release(KeyCode::KEY_ENTER, 50));
+        press_every_key(&mut screen, 1000);
+        assert!(screen.passed());
+
+        screen.handle_event(AppEvent::Tick);
+        assert!(screen.auto_advance.remaining().is_none());
+
+        screen.timer.advance(Duration::from_secs(300));
+        screen.handle_event(AppEvent::Tick);
+        assert!(screen.auto_advance.remaining().is_some());
+    }
+
+    #[test]
+    fn custom_layouts_are_reloaded_on_the_selection_only() {
+        struct TempDir(PathBuf);
+        impl Drop for TempDir {
+            fn drop(&mut self) {
+                let _ = std::fs::remove_dir_all(&self.0);
+            }
+        }
+        let dir = TempDir(std::env::temp_dir().join(format!(
+            "keyboard_test-custom-layouts-{}",
+            std::process::id()
+        )));
+        std::fs::create_dir_all(&dir.0).unwrap();
+        // Dated apart, every edit is seen whatever the file system's time resolution
+        let write = |name: &str, text: &str, secs: u64| {
+            let path = dir.0.join(name);
+            std::fs::write(&path, text).unwrap();
+            let file = std::fs::File::options().write(true).open(&path).unwrap();
+            file.set_modified(std::time::UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        let text = |screen: &KeyboardTestScreen| render(160, 30, |frame| screen.draw(frame));
+
+        write(
+            "rugged.toml",
+            "name = \"Rugged\"\nrows = [[\"KEY_A\", \"KEY_B\"]]",
+            1000,
+        );
+        let mut screen = screen().with_layouts_dir(Some(dir.0.clone()));
+        let rugged = LAYOUT_OPTIONS.len();
+        screen.mode = KeyboardTestMode::SelectLayout { selected: rugged };
+        let shown = text(&screen);
+        assert!(shown.contains("› Rugged"), "{shown}");
+        assert!(shown.contains("2 keys"), "{shown}");
+
+        // A broken edit keeps the layout, the preview says why
+        write("rugged.toml", "rows = [[\"KEY_NOPE\"]]", 1001);
+        screen.handle_event(press(KeyCode::KEY_F5, 0));
+        let shown = text(&screen);
+        assert!(shown.contains("no layout reloaded"), "{shown}");
+        assert!(shown.contains("Rugged (file has errors)"), "{shown}");
+        assert!(
+            shown.contains("Not reloaded: rows row 1: unknown key code 'KEY_NOPE'"),
+            "{shown}"
+        );
+        assert!(
+            shown.contains("Showing the last version that parsed"),
+            "{shown}"
+        );
+
+        // A file that never parsed can't be started
+        write("spare.toml", "rows = [", 1000);
+        screen.handle_event(AppEvent::Tick);
+        screen.mode = KeyboardTestMode::SelectLayout {
+            selected: rugged + 1,
+        };
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        assert!(matches!(screen.mode, KeyboardTestMode::SelectLayout { .. }));
+
+        write(
+            "rugged.toml",
+            "name = \"Rugged 2\"\nrows = [[\"KEY_A\"]]",
+            1002,
+        );
+        screen.mode = KeyboardTestMode::SelectLayout { selected: rugged };
+        screen.handle_event(press(KeyCode::KEY_F5, 0));
+        let shown = text(&screen);
+        assert!(shown.contains("layout 'Rugged 2' reloaded"), "{shown}");
+        assert!(shown.contains("1 keys"), "{shown}");
+
+        // A config reload reads the files again, the running test keeps its layout
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        write(
+            "rugged.toml",
+            "name = \"Rugged 3\"\nrows = [[\"KEY_B\"]]",
+            1003,
+        );
+        let mut options = Options::default();
+        options.layouts_dir = Some(dir.0.clone());
+        screen.reconfigure(&options);
+        assert_eq!(screen.custom_layouts.files()[0].name(), "Rugged 3");
+        assert!(matches!(screen.mode, KeyboardTestMode::Testing));
+        assert_eq!(screen.layout_name, "Rugged 2");
+        assert_eq!(screen.keyboard_layout[0][0][0][0].codes, &[KeyCode::KEY_A]);
+        assert!(!screen.layouts_notice.unwrap().0.contains("Rugged 3"));
+    }
+
+    #[test]
+    fn a_config_reload_recolors_the_running_test() {
+        let mut screen = screen().with_theme(Theme::Standard);
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        for (i, code) in [KeyCode::KEY_Q, KeyCode::KEY_W].into_iter().enumerate() {
+            let at = 1000 + i as u128 * 200;
+            screen.handle_event(press(code, at));
+            screen.handle_event(release(code, at + 80));
+        }
+        screen.recent_presses.clear();
+        screen.timer.pause();
+        let before = render_colors(80, 24, |frame| screen.draw(frame));
+        assert_snapshot("keyboard_theme_standard_80x24", &before);
+
+        // The presses stay, only their colors change
+        let mut options = Options::default();
+        options.theme = Theme::ColorBlind;
+        screen.reconfigure(&options);
+        screen.timer.pause();
+        let after = render_colors(80, 24, |frame| screen.draw(frame));
+        assert_snapshot("keyboard_theme_color-blind_80x24", &after);
+        assert_ne!(before, after);
+        assert_eq!(screen.presses(KeyCode::KEY_Q), 1);
+    }
+
+    #[test]
+    fn testing_snapshots() {
+        let mut screen = screen();
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        screen.power = PowerMonitor::with_dir("/nonexistent");
+        let keys = [KeyCode::KEY_Q, KeyCode::KEY_W, KeyCode::KEY_SPACE];
+        for (i, code) in keys.into_iter().enumerate() {
+            let at = 1000 + i as u128 * 200;
+            screen.handle_event(press(code, at));
+            screen.handle_event(release(code, at + 80));
+        }
+        // Twice for the second color of the ramp, one still held
+        screen.handle_event(press(KeyCode::KEY_W, 2000));
+        screen.handle_event(release(KeyCode::KEY_W, 2080));
+        screen.handle_event(press(KeyCode::KEY_E, 2200));
+        // keys/s and the elapsed time go by the wall clock and a debug build
+        // takes over a second to draw both sizes, so they read 0 in the snapshots
+        screen.recent_presses.clear();
+        screen.timer.pause();
+        assert_screen("keyboard_testing", &screen);
+        assert_screen_ascii("keyboard_testing", &screen);
+    }
+
+    #[test]
+    fn swedish_testing_snapshots() {
+        let mut screen = screen().with_language(Language::Swedish);
+        screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        // Å, Ö and Skift, keys whose labels aren't ASCII or differ in Swedish
+        let keys = [
+            KeyCode::KEY_LEFTBRACE,
+            KeyCode::KEY_SEMICOLON,
+            KeyCode::KEY_LEFTSHIFT,
+        ];
+        for (i, code) in keys.into_iter().enumerate() {
+            let at = 1000 + i as u128 * 200;
+            screen.handle_event(press(code, at));
+            screen.handle_event(release(code, at + 80));
+        }
+        screen.recent_presses.clear();
+        screen.timer.pause();
+        assert_screen("keyboard_testing_sv", &screen);
+    }
+
+    #[test]
+    fn labels_are_measured_in_columns_not_bytes() {
+        assert_eq!(label_width("Å"), 1);
+        assert_eq!(label_width("´ `"), 3);
+        assert_eq!(label_width("¨ ^"), 3);
+        assert_eq!(label_width("§"), 1);
+        assert_eq!(fit_label("ÅÄÖ", 2), "ÅÄ");
+        assert_eq!(fit_label("Backsteg", 4), "Back");
+
+        // Every label sits centered in its cell, as wide as its columns
+        for language in [Language::English, Language::Swedish] {
+            let mut screen = screen().with_language(language);
+            screen.mode = KeyboardTestMode::Testing;
+            screen.timer.pause();
+            let area = Rect::new(0, 0, 200, 50);
+            let keys = screen.layout(area).keys;
+            let text = render(area.width, area.height, |frame| screen.draw(frame));
+            let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+            for (key, rect) in &keys {
+                if key.is_continuation() || key.shape != KeyShape::Plain {
+                    continue;
+                }
+                let label = key.display_label(language);
+                let width = label_width(label);
+                assert!(rect.width >= width + 2, "{label:?} in {rect:?}");
+                let start = (rect.x + (rect.width - width) / 2) as usize;
+                let row = &rows[(rect.y + rect.height / 2) as usize];
+                let shown: String = row[start..start + label.chars().count()].iter().collect();
+                assert_eq!(shown, label, "{language:?} at {rect:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn enter_key_snapshots() {
+        for (layout, widths) in [
+            ("ISO UK (105)", &[64, 90, 120, 160][..]),
+            ("ISO German (105)", &[120][..]),
+            ("ANSI US (104)", &[64, 90, 120, 160][..]),
+        ] {
+            let mut screen = screen();
+            let index = LAYOUT_OPTIONS
+                .iter()
+                .position(|(name, ..)| *name == layout)
+                .unwrap();
+            screen.start_layout(index);
+            screen.mode = KeyboardTestMode::Testing;
+            screen.timer.pause();
+
+            for &width in widths {
+                let area = Rect::new(0, 0, width, 40);
+                let keys = screen.layout(area).keys;
+                // ANSI's one-row Enter is cut out with the backslash above it
+                let enter: Vec<Rect> = keys
+                    .iter()
+                    .filter(|(key, _)| {
+                        key.shape == KeyShape::TallEnter
+                            || (layout.starts_with("ANSI")
+                                && matches!(
+                                    key.codes,
+                                    [KeyCode::KEY_BACKSLASH | KeyCode::KEY_ENTER]
+                                ))
+                    })
+                    .map(|(_, rect)| *rect)
+                    .collect();
+                let [upper, lower] = enter[..] else {
+                    panic!("{layout}: Enter in {} cells", enter.len());
+                };
+                // The Enter cells and the two keys left of each
+                let left_of = |cell: Rect| {
+                    let mut left: Vec<u16> = keys
+                        .iter()
+                        .filter(|(_, rect)| rect.y == cell.y && rect.right() <= cell.x)
+                        .map(|(_, rect)| rect.x)
+                        .collect();
+                    left.sort();
+                    left.iter().rev().nth(1).copied().unwrap_or(cell.x)
+                };
+                let left = left_of(upper).min(left_of(lower));
+                let right = upper.right().max(lower.right());
+                let text = render(area.width, area.height, |frame| screen.draw(frame));
+                let cut: String = text
+                    .lines()
+                    .skip(upper.y as usize)
+                    .take((lower.bottom() - upper.y) as usize)
+                    .map(|line| {
+                        let line: String = line
+                            .chars()
+                            .skip(left as usize)
+                            .take((right - left) as usize)
+                            .collect();
+                        line.trim_end().to_string() + "\n"
+                    })
+                    .collect();
+                // One key, labelled once
+                assert_eq!(
+                    cut.matches("Enter").count(),
+                    1,
+                    "{layout} at {width}:\n{cut}"
+                );
+                let name = layout.split(' ').take(2).collect::<Vec<_>>().join("_");
+                assert_snapshot(
+                    &format!("keyboard_enter_{}_{width}", name.to_lowercase()),
+                    &cut,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn every_key_of_ansi_us_completes_the_layout() {
+        let mut screen = screen();
+        let index = LAYOUT_OPTIONS
+            .iter()
+            .position(|(name, ..)| *name == "ANSI US (104)")
+            .unwrap();
+        screen.start_layout(index);
+        screen.mode = KeyboardTestMode::Testing;
+        assert_eq!(keyboard_layouts::key_count(screen.keyboard_layout), 104);
+        assert!(!screen.passed());
+
+        press_every_key(&mut screen, 1000);
+        assert!(screen.passed());
+        assert_eq!(screen.progress(), Some(1.0));
+        let Some(ScreenResult::Keyboard(result)) = screen.take_results() else {
+            panic!("expected a keyboard result");
+        };
+        assert_eq!(result.keys_pressed, 104);
+        assert_eq!(result.total_presses, 104);
+    }
+
+    #[test]
+    fn testing_layout_stays_inside_and_apart() {
+        for (index, (what, _, _)) in LAYOUT_OPTIONS.iter().enumerate() {
+            let mut screen = screen();
+            screen.start_layout(index);
+            screen.mode = KeyboardTestMode::Testing;
+            for (width, height) in LAYOUT_SIZES {
+                let area = Rect::new(0, 0, width, height);
+                let layout = screen.layout(area);
+                assert_within_and_apart(what, area, &[layout.header, layout.body, layout.footer]);
+                assert_within_and_apart(what, layout.body, &[layout.legend]);
+                let keyboard = Rect {
+                    y: layout.legend.bottom(),
+                    height: layout.body.bottom() - layout.legend.bottom(),
+                    ..layout.body
+                };
+                let keys: Vec<Rect> = layout.keys.iter().map(|(_, rect)| *rect).collect();
+                assert_within_and_apart(what, keyboard, &keys);
+            }
+        }
+    }
+
+    #[test]
+    fn warning_cues_survive_narrow_keys() {
+        for (index, (what, _, _)) in LAYOUT_OPTIONS.iter().enumerate() {
+            let mut screen = screen();
+            screen.start_layout(index);
+            screen.mode = KeyboardTestMode::Testing;
+            screen.timer.pause();
+            for (width, height) in LAYOUT_SIZES {
+                let area = Rect::new(0, 0, width, height);
+                let keys = screen.layout(area).keys;
+                screen.chatter = keys
+                    .iter()
+                    .flat_map(|(key, _)| key.codes.iter().copied())
+                    .collect();
+                let text = render(width, height, |frame| screen.draw(frame));
+                let rows: Vec<Vec<char>> =
+                    text.lines().map(|line| line.chars().collect()).collect();
+
+                // Every key with room for a label shows the `!`, cut labels included
+                for (key, rect) in &keys {
+                    if key.is_continuation() || rect.is_empty() || rect.width < 3 {
+                        continue;
+                    }
+                    let row = &rows[(rect.y + rect.height / 2) as usize];
+                    let cells = row
+                        .get(rect.x as usize..(rect.right() as usize).min(row.len()))
+                        .unwrap_or_default();
+                    assert!(
+                        cells.contains(&'!'),
+                        "{what}: {} at {width}x{height} lost its cue: {:?}",
+                        key.label,
+                        cells.iter().collect::<String>()
+                    );
+                }
+            }
+        }
+    }
+}
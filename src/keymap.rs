@@ -0,0 +1,570 @@
+//! How keys reach the screens as navigation: keypad keys mapped onto their
+//! navigation equivalents and held keys repeating in menus. The field keyboards
+//! are numpad-centric and operators reach for keypad Enter and the keypad arrows,
+//! which evdev reports as KEY_KPENTER, KEY_KP8 and so on whatever the NumLock state.
+//! On top of that sites can rebind actions in the config file's `keybindings`.
+
+use std::collections::BTreeMap;
+
+use evdev::KeyCode;
+
+use crate::event_handler::AppEvent;
+
+// Repeats of a held Up/Down before the selection starts moving faster, and how
+// far it moves per repeat from then on
+const ACCELERATE_AFTER: u32 = 10;
+const ACCELERATED_STEPS: usize = 3;
+
+// After a screen switch, a second press of the key that caused it within this
+// many ms is the same keystroke bouncing into the new screen
+const NAV_GRACE_MS: u128 = 200;
+
+/// The navigation key a keypad key stands for, other keys unchanged. Keypad
+/// 2/4/6/8 become arrows, so they no longer quick-select by number.
+pub fn navigation_key(code: KeyCode) -> KeyCode {
+    match code {
+        KeyCode::KEY_KPENTER => KeyCode::KEY_ENTER,
+        KeyCode::KEY_KP8 => KeyCode::KEY_UP,
+        KeyCode::KEY_KP2 => KeyCode::KEY_DOWN,
+        KeyCode::KEY_KP4 => KeyCode::KEY_LEFT,
+        KeyCode::KEY_KP6 => KeyCode::KEY_RIGHT,
+        other => other,
+    }
+}
+
+/// Which autorepeat events get through. Only list-style screens honor them, and
+/// only for Up/Down, everything else (the keyboard test counting presses in
+/// particular) sees one event per physical press.
+#[derive(Default)]
+pub struct KeyRepeat {
+    // Key being held and how many repeats it has sent
+    held: Option<(KeyCode, u32)>,
+}
+
+impl KeyRepeat {
+    pub fn new() -> Self {
+        KeyRepeat::default()
+    }
+
+    /// How many times to deliver `event`: once for anything but a key repeat, 0
+    /// to drop it, and for a held Up/Down on a screen that honors repeats 1 or,
+    /// once held for a while, `ACCELERATED_STEPS`
+    pub fn steps(&mut self, event: &AppEvent, honor_repeats: bool) -> usize {
+        let AppEvent::Key { code, repeat, .. } = *event else {
+            return 1;
+        };
+        if !repeat {
+            self.held = None;
+            return 1;
+        }
+        if !honor_repeats || !matches!(code, KeyCode::KEY_UP | KeyCode::KEY_DOWN) {
+            return 0;
+        }
+
+        let count = match self.held {
+            Some((held, count)) if held == code => count + 1,
+            _ => 1,
+        };
+        self.held = Some((code, count));
+
+        if count > ACCELERATE_AFTER {
+            ACCELERATED_STEPS
+        } else {
+            1
+        }
+    }
+}
+
+/// Keeps the key that switched screens from reaching the new screen as its own:
+/// Enter from the home menu would otherwise count as tested in the keyboard
+/// test, a number key would pick a touchscreen device. Its autorepeat is dropped
+/// for as long as it's held, and a press of it within `NAV_GRACE_MS`. Any other
+/// key ends the guard.
+#[derive(Default)]
+pub struct NavGuard {
+    // Raw code of the key and the kernel timestamp of its press
+    guarded: Option<(KeyCode, u128)>,
+}
+
+impl NavGuard {
+    pub fn new() -> Self {
+        NavGuard::default()
+    }
+
+    /// A screen switch, caused by the key `code` pressed at `timestamp` or by
+    /// something other than a key
+    pub fn arm(&mut self, key: Option<(KeyCode, u128)>) {
+        self.guarded = key;
+    }
+
+    /// Whether `event`, before any key translation, is the guarded key and
+    /// should be dropped
+    pub fn swallows(&mut self, event: &AppEvent) -> bool {
+        let AppEvent::Key {
+            code,
+            repeat,
+            timestamp,
+            ..
+        } = *event
+        else {
+            return false;
+        };
+        let Some((guarded, pressed_at)) = self.guarded else {
+            return false;
+        };
+
+        if code == guarded && (repeat || timestamp.saturating_sub(pressed_at) < NAV_GRACE_MS) {
+            return true;
+        }
+        // Repeats stop when a key is released, so this is a new keystroke
+        self.guarded = None;
+        false
+    }
+}
+
+/// What a key does, for rebinding in the config file. The screens match the
+/// default keys, so a configured key is turned into the default before they see it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Select,
+    Quit,
+    Reset,
+    Recalibrate,
+    ClearTrail,
+    QuickSelect,
+    ToggleHelp,
+    FinishTest,
+    DumpEvents,
+}
+
+const ACTIONS: [Action; 13] = [
+    Action::NavigateUp,
+    Action::NavigateDown,
+    Action::NavigateLeft,
+    Action::NavigateRight,
+    Action::Select,
+    Action::Quit,
+    Action::Reset,
+    Action::Recalibrate,
+    Action::ClearTrail,
+    Action::QuickSelect,
+    Action::ToggleHelp,
+    Action::FinishTest,
+    Action::DumpEvents,
+];
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::NavigateUp => "navigate_up",
+            Action::NavigateDown => "navigate_down",
+            Action::NavigateLeft => "navigate_left",
+            Action::NavigateRight => "navigate_right",
+            Action::Select => "select",
+            Action::Quit => "quit",
+            Action::Reset => "reset",
+            Action::Recalibrate => "recalibrate",
+            Action::ClearTrail => "clear_trail",
+            Action::QuickSelect => "quick_select",
+            Action::ToggleHelp => "toggle_help",
+            Action::FinishTest => "finish_test",
+            Action::DumpEvents => "dump_events",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTIONS.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Keys the screens match for the action. The n-th configured key stands in
+    /// for the n-th default, extra keys for the last one.
+    fn default_keys(self) -> &'static [KeyCode] {
+        match self {
+            Action::NavigateUp => &[KeyCode::KEY_UP],
+            Action::NavigateDown => &[KeyCode::KEY_DOWN],
+            Action::NavigateLeft => &[KeyCode::KEY_LEFT],
+            Action::NavigateRight => &[KeyCode::KEY_RIGHT],
+            Action::Select => &[KeyCode::KEY_ENTER],
+            Action::Quit => &[KeyCode::KEY_Q, KeyCode::KEY_ESC],
+            Action::Reset => &[KeyCode::KEY_R],
+            Action::Recalibrate => &[KeyCode::KEY_T],
+            Action::ClearTrail => &[KeyCode::KEY_C],
+            Action::QuickSelect => &[
+                KeyCode::KEY_1,
+                KeyCode::KEY_2,
+                KeyCode::KEY_3,
+                KeyCode::KEY_4,
+                KeyCode::KEY_5,
+                KeyCode::KEY_6,
+                KeyCode::KEY_7,
+                KeyCode::KEY_8,
+                KeyCode::KEY_9,
+            ],
+            // The run loop handles these itself, see `KeyMap::keys_for`
+            Action::ToggleHelp | Action::FinishTest | Action::DumpEvents => &[],
+        }
+    }
+
+    /// How the default keys are written in the screens' help entries
+    fn help_tokens(self) -> &'static [&'static str] {
+        match self {
+            Action::NavigateUp => &["↑"],
+            Action::NavigateDown => &["↓"],
+            Action::NavigateLeft => &["←"],
+            Action::NavigateRight => &["→"],
+            Action::Select => &["Enter"],
+            Action::Quit => &["Q", "Esc"],
+            Action::Reset => &["R"],
+            Action::Recalibrate => &["T"],
+            Action::ClearTrail => &["C"],
+            Action::QuickSelect => &["1-9", "1..9"],
+            Action::ToggleHelp => &["F1"],
+            Action::FinishTest => &["Ctrl x4"],
+            Action::DumpEvents => &["F9"],
+        }
+    }
+
+    /// Help, finish and the event dump work on every screen, the keyboard test included
+    fn is_global(self) -> bool {
+        matches!(
+            self,
+            Action::ToggleHelp | Action::FinishTest | Action::DumpEvents
+        )
+    }
+}
+
+/// Actions rebound by the config file, everything else keeps its default keys
+#[derive(Debug, Clone, Default)]
+pub struct KeyMap {
+    rebound: Vec<(Action, Vec<KeyCode>)>,
+}
+
+impl KeyMap {
+    /// Build the map from the config file's `keybindings`, action name to evdev
+    /// key names. An empty list disables the action. Returns a warning for every
+    /// name that isn't understood, those entries are left out.
+    pub fn from_config(bindings: &BTreeMap<String, Vec<String>>) -> (Self, Vec<String>) {
+        let mut keymap = KeyMap::default();
+        let mut warnings = Vec::new();
+
+        for (name, key_names) in bindings {
+            let Some(action) = Action::from_name(name) else {
+                let valid: Vec<&str> = ACTIONS.iter().map(|action| action.name()).collect();
+                warnings.push(format!(
+                    "Unknown action '{name}' in keybindings, valid actions: {}",
+                    valid.join(", ")
+                ));
+                continue;
+            };
+
+            let mut keys = Vec::new();
+            for key_name in key_names {
+                let Ok(code) = key_name.parse::<KeyCode>() else {
+                    warnings.push(format!(
+                        "Unknown key '{key_name}' for '{name}', use evdev names like KEY_F10"
+                    ));
+                    continue;
+                };
+                if let Some((other, _)) = keymap.bound(code) {
+                    warnings.push(format!(
+                        "{key_name} is bound to both '{}' and '{name}', keeping '{}'",
+                        other.name(),
+                        other.name()
+                    ));
+                    continue;
+                }
+                keys.push(code);
+            }
+
+            // Nothing usable left of a non-empty list, so keep the defaults
+            if keys.is_empty() && !key_names.is_empty() {
+                continue;
+            }
+            keymap.rebound.push((action, keys));
+        }
+
+        (keymap, warnings)
+    }
+
+    /// The configured keys of `action`, None while it keeps its defaults
+    pub fn keys_for(&self, action: Action) -> Option<&[KeyCode]> {
+        self.rebound
+            .iter()
+            .find(|(rebound, _)| *rebound == action)
+            .map(|(_, keys)| keys.as_slice())
+    }
+
+    // The rebound action a key is configured for, with its place in the list
+    fn bound(&self, code: KeyCode) -> Option<(Action, usize)> {
+        self.rebound.iter().find_map(|(action, keys)| {
+            keys.iter()
+                .position(|key| *key == code)
+                .map(|index| (*action, index))
+        })
+    }
+
+    /// Turn a configured key into the default key the screens match, and drop
+    /// the default keys of rebound actions. Other keys get the keypad mapping
+    /// when `keypad` is set. None for a dropped key.
+    pub fn translate(&self, event: AppEvent, keypad: bool) -> Option<AppEvent> {
+        let AppEvent::Key {
+            code,
+            info,
+            repeat,
+            timestamp,
+        } = event
+        else {
+            return Some(event);
+        };
+
+        let code = match self.bound(code) {
+            Some((action, index)) if !action.is_global() => {
+                let defaults = action.default_keys();
+                defaults[index.min(defaults.len() - 1)]
+            }
+            Some(_) => code,
+            None if self.replaces(code) => return None,
+            None if keypad => navigation_key(code),
+            None => code,
+        };
+        Some(AppEvent::Key {
+            code,
+            info,
+            repeat,
+            timestamp,
+        })
+    }
+
+    // A default key of a rebound action, taken away from it
+    fn replaces(&self, code: KeyCode) -> bool {
+        self.rebound
+            .iter()
+            .any(|(action, _)| !action.is_global() && action.default_keys().contains(&code))
+    }
+
+    /// A help entry's key column with rebound keys written as configured, e.g.
+    /// "Q/Esc, tap Back" for a quit bound to F10 becomes "F10, tap Back". Only help
+    /// and finish are rebound on screens with raw keys. Empty when every key of
+    /// the entry was disabled.
+    pub fn help_label(&self, label: &str, raw_keys: bool) -> String {
+        if self.rebound.is_empty() {
+            return label.to_string();
+        }
+
+        let mut written: Vec<Action> = Vec::new();
+        let groups: Vec<String> = label
+            .split(", ")
+            .map(|group| {
+                let tokens: Vec<String> = group
+                    .split('/')
+                    .filter_map(|token| {
+                        let rebound = self.rebound.iter().find(|(action, _)| {
+                            action.help_tokens().contains(&token)
+                                && (!raw_keys || action.is_global())
+                        });
+                        let Some((action, keys)) = rebound else {
+                            return Some(token.to_string());
+                        };
+                        if written.contains(action) {
+                            return None;
+                        }
+                        written.push(*action);
+
+                        let mut names: Vec<String> =
+                            keys.iter().map(|key| key_label(*key)).collect();
+                        // Finishing with Ctrl x4 stays, the keys come on top
+                        if *action == Action::FinishTest {
+                            names.insert(0, token.to_string());
+                        }
+                        (!names.is_empty()).then(|| names.join("/"))
+                    })
+                    .collect();
+                tokens.join("/")
+            })
+            .filter(|group| !group.is_empty())
+            .collect();
+        groups.join(", ")
+    }
+}
+
+/// A key as written in the help, e.g. "F10" or "Esc" for KEY_F10 and KEY_ESC
+fn key_label(code: KeyCode) -> String {
+    let name = format!("{code:?}");
+    let name = name.strip_prefix("KEY_").unwrap_or(&name);
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(first) => first.to_string() + &chars.as_str().to_lowercase(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Screen;
+    use crate::keyboard_test::KeyboardTestScreen;
+    use crate::machine_detect::ComputerModel;
+    use crate::report::ScreenResult;
+    use crate::snapshot::{keyboard, press, release};
+
+    fn repeat(code: KeyCode, timestamp: u128) -> AppEvent {
+        AppEvent::Key {
+            code,
+            info: keyboard(),
+            repeat: true,
+            timestamp,
+        }
+    }
+
+    /// Which of `events` get past a guard armed by Enter pressed at 0
+    fn passed(events: Vec<AppEvent>) -> Vec<AppEvent> {
+        let mut guard = NavGuard::new();
+        guard.arm(Some((KeyCode::KEY_ENTER, 0)));
+        events
+            .into_iter()
+            .filter(|event| !guard.swallows(event))
+            .collect()
+    }
+
+    /// Steps of `count` repeats of `code` on a screen honoring them
+    fn held(keys: &mut KeyRepeat, code: KeyCode, count: usize) -> Vec<usize> {
+        (0..count)
+            .map(|i| keys.steps(&repeat(code, i as u128 * 33), true))
+            .collect()
+    }
+
+    #[test]
+    fn a_held_arrow_speeds_up_after_a_while() {
+        let mut keys = KeyRepeat::new();
+        assert_eq!(keys.steps(&press(KeyCode::KEY_DOWN, 0), true), 1);
+        let steps = held(&mut keys, KeyCode::KEY_DOWN, 15);
+        let (slow, fast) = steps.split_at(ACCELERATE_AFTER as usize);
+        assert!(slow.iter().all(|steps| *steps == 1), "{steps:?}");
+        assert!(
+            fast.iter().all(|steps| *steps == ACCELERATED_STEPS),
+            "{steps:?}"
+        );
+        // 10 rows, then 3 a repeat
+        assert_eq!(steps.iter().sum::<usize>(), 10 + 5 * 3);
+    }
+
+    #[test]
+    fn a_new_key_starts_slow_again() {
+        let mut keys = KeyRepeat::new();
+        held(&mut keys, KeyCode::KEY_DOWN, 12);
+
+        // Up held straight after Down, without a press in between
+        assert_eq!(held(&mut keys, KeyCode::KEY_UP, 1), [1]);
+
+        // Down pressed again after its release
+        held(&mut keys, KeyCode::KEY_DOWN, 12);
+        assert_eq!(keys.steps(&press(KeyCode::KEY_DOWN, 0), true), 1);
+        assert_eq!(held(&mut keys, KeyCode::KEY_DOWN, 1), [1]);
+
+        // Releases and other events don't end the hold
+        held(&mut keys, KeyCode::KEY_DOWN, 11);
+        assert_eq!(keys.steps(&release(KeyCode::KEY_A, 0), true), 1);
+        assert_eq!(keys.steps(&AppEvent::Tick, true), 1);
+        assert_eq!(held(&mut keys, KeyCode::KEY_DOWN, 1), [ACCELERATED_STEPS]);
+    }
+
+    #[test]
+    fn repeats_are_dropped_where_they_are_not_honored() {
+        let mut keys = KeyRepeat::new();
+        // The keyboard test counts one press per keystroke
+        assert_eq!(keys.steps(&press(KeyCode::KEY_DOWN, 0), false), 1);
+        for i in 0..20 {
+            assert_eq!(keys.steps(&repeat(KeyCode::KEY_DOWN, i * 33), false), 0);
+        }
+        // Only Up and Down repeat in lists
+        assert_eq!(held(&mut keys, KeyCode::KEY_ENTER, 3), [0, 0, 0]);
+        assert_eq!(held(&mut keys, KeyCode::KEY_LEFT, 3), [0, 0, 0]);
+        // Neither started a hold
+        assert_eq!(held(&mut keys, KeyCode::KEY_DOWN, 1), [1]);
+    }
+
+    #[test]
+    fn the_held_navigation_key_is_dropped() {
+        let passed = passed(vec![
+            repeat(KeyCode::KEY_ENTER, 500),
+            repeat(KeyCode::KEY_ENTER, 533),
+            repeat(KeyCode::KEY_ENTER, 5_000),
+        ]);
+        assert!(passed.is_empty(), "{passed:?}");
+    }
+
+    #[test]
+    fn a_bounce_within_the_grace_is_dropped_and_a_later_press_is_not() {
+        let passed = passed(vec![
+            press(KeyCode::KEY_ENTER, 40),
+            press(KeyCode::KEY_ENTER, NAV_GRACE_MS - 1),
+            press(KeyCode::KEY_ENTER, NAV_GRACE_MS),
+            // The guard is over, a bounce of the new press is the screen's
+            press(KeyCode::KEY_ENTER, NAV_GRACE_MS + 10),
+        ]);
+        let times: Vec<u128> = passed
+            .iter()
+            .map(|event| match event {
+                AppEvent::Key { timestamp, .. } => *timestamp,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(times, [NAV_GRACE_MS, NAV_GRACE_MS + 10]);
+    }
+
+    #[test]
+    fn another_key_ends_the_guard() {
+        let passed = passed(vec![
+            press(KeyCode::KEY_A, 20),
+            repeat(KeyCode::KEY_ENTER, 40),
+            press(KeyCode::KEY_ENTER, 60),
+        ]);
+        assert_eq!(passed.len(), 3);
+    }
+
+    #[test]
+    fn releases_and_other_events_pass_without_ending_the_guard() {
+        let passed = passed(vec![
+            release(KeyCode::KEY_ENTER, 50),
+            AppEvent::Tick,
+            repeat(KeyCode::KEY_ENTER, 80),
+        ]);
+        assert!(matches!(
+            passed.as_slice(),
+            [AppEvent::KeyReleased { .. }, AppEvent::Tick]
+        ));
+    }
+
+    #[test]
+    fn switching_without_a_key_guards_nothing() {
+        let mut guard = NavGuard::new();
+        guard.arm(None);
+        assert!(!guard.swallows(&repeat(KeyCode::KEY_ENTER, 10)));
+    }
+
+    #[test]
+    fn enter_from_the_home_menu_is_not_tested_in_the_keyboard_test() {
+        // Enter held on the home menu and bouncing, then the operator confirms
+        // the layout and presses A
+        let mut screen = KeyboardTestScreen::for_model(ComputerModel::DatorBBFält);
+        for event in passed(vec![
+            repeat(KeyCode::KEY_ENTER, 30),
+            repeat(KeyCode::KEY_ENTER, 63),
+            release(KeyCode::KEY_ENTER, 90),
+            press(KeyCode::KEY_ENTER, 150),
+            press(KeyCode::KEY_ENTER, 1_000),
+            press(KeyCode::KEY_A, 1_100),
+            release(KeyCode::KEY_A, 1_180),
+        ]) {
+            screen.handle_event(event);
+        }
+        let Some(ScreenResult::Keyboard(result)) = screen.take_results() else {
+            panic!("expected a keyboard result");
+        };
+        assert_eq!((result.keys_pressed, result.total_presses), (1, 1));
+    }
+}
@@ -0,0 +1,32 @@
+//! Language of the labels drawn on the keyboard. The internal keyboards have
+//! Swedish keycaps ("Skift", "Retur", the dead keys ´ and ¨), and an English
+//! label on the highlighted cell makes the operator look for a different key.
+//! Layout keys carry a Swedish label where the keycap differs, see
+//! `keyboard_layouts::Key::display_label`; the rest of the UI stays English.
+//!
+//! The language comes from `render.language` in the config or `--language`:
+//! "en" or "sv".
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    English,
+    Swedish,
+}
+
+impl Language {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "en" => Some(Language::English),
+            "sv" => Some(Language::Swedish),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "en",
+            Language::Swedish => "sv",
+        }
+    }
+}
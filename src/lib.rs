@@ -0,0 +1,208 @@
+//! Input device diagnostics: keyboard, mouse, touchscreen and trackpad tests
+//! for the field computers, as ratatui screens over evdev input.
+//!
+//! The `input_device_test` binary is a thin wrapper around [`app::run`]. Tools
+//! that embed a single test spawn the listeners with
+//! [`event_handler::spawn_device_listeners`], drive a [`Screen`] such as
+//! [`keyboard_test::KeyboardTestScreen`] from the events and collect its
+//! [`report::ScreenResult`], see `examples/keyboard_test.rs`.
+
+pub mod app;
+mod auto_advance;
+pub mod calibration_matrix;
+pub mod capabilities;
+pub mod capture;
+pub mod cli;
+pub mod config;
+mod custom_layouts;
+pub mod device_class;
+mod device_ids;
+mod duplicate_touch;
+pub mod event_handler;
+mod event_queue;
+mod event_ring;
+mod frame_budget;
+mod ghost_keys;
+mod glyphs;
+mod guided_keys;
+mod home_menu;
+mod key_autorepeat;
+mod key_dedup;
+mod key_exclusions;
+mod key_order;
+mod key_quirks;
+mod key_rate;
+mod keyboard_compare;
+mod keyboard_layouts;
+pub mod keyboard_test;
+mod keymap;
+mod language;
+mod listener_health;
+pub mod logging;
+pub mod machine_detect;
+pub mod mouse_test;
+mod other_keyboards;
+mod pen_hover;
+mod power;
+pub mod press_colors;
+pub mod probe;
+mod range_learning;
+pub mod report;
+mod resume;
+mod sample_stats;
+mod saturation;
+pub mod self_check;
+mod serial_touch;
+mod session_info;
+mod sim;
+mod simultaneous_keys;
+#[cfg(test)]
+mod snapshot;
+mod soak;
+mod stabilizer;
+pub mod status_server;
+mod stroke;
+mod terminal_echo;
+mod test_timer;
+mod text_input;
+pub mod theme;
+mod touch_corners;
+mod touch_keypad;
+mod touch_nav;
+mod touch_roi;
+pub mod touchscreen_test;
+pub mod trackpad_test;
+mod units;
+mod wake_latency;
+mod watchdog;
+pub mod writer;
+
+use ratatui::Frame;
+
+use crate::cli::Options;
+use crate::event_handler::AppEvent;
+use crate::report::{ScreenResult, SessionMetadata, SessionReport};
+
+pub use crate::frame_budget::Degradation;
+pub use crate::key_rate::RateLimit;
+pub use crate::keymap::KeyMap;
+
+/// Every screen of the tool, used to navigate between them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ScreenId {
+    Home,
+    KeyboardTest,
+    MouseTest,
+    TouchscreenTest,
+    TrackpadTest,
+    SessionInfo,
+    Resume,
+    /// Detect the hardware again and come back to a rebuilt home menu
+    Rescan,
+    Exit,
+}
+
+/// One full-terminal view. The run loop draws the active screen, feeds it every
+/// event and switches screens when `handle_event` asks for it.
+pub trait Screen {
+    fn id(&self) -> ScreenId;
+    fn draw(&self, frame: &mut Frame);
+    /// React to one input event or tick, returning where to go next
+    fn handle_event(&mut self, event: AppEvent) -> Nav {
+        let _ = event;
+        Nav::Stay
+    }
+
+    /// Key bindings shown in the help overlay, as (key, description)
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        Vec::new()
+    }
+
+    /// One-paragraph description shown above the bindings in the help overlay
+    fn help_text(&self) -> &'static str {
+        ""
+    }
+
+    /// Hand over the outcome of the test for the session report, called when leaving the screen
+    fn take_results(&mut self) -> Option<ScreenResult> {
+        None
+    }
+
+    /// Share of the test done so far, 0-1, like `ScreenResult::progress`. For
+    /// the status endpoint, None on screens that aren't tests.
+    fn progress(&self) -> Option<f32> {
+        None
+    }
+
+    /// Keep this screen around when leaving it, so coming back continues where it left
+    /// off instead of starting over. The screen must offer its own reset action.
+    fn preserve_on_exit(&self) -> bool {
+        false
+    }
+
+    /// Called when a preserved screen is shown again
+    fn on_enter(&mut self) {}
+
+    /// An event that came while the screen was preserved but not shown, its
+    /// results already taken. Only state mirroring the devices may follow it,
+    /// e.g. which keys are still held, never anything the results count.
+    /// Only key releases are passed on.
+    fn settle(&mut self, event: &AppEvent) {
+        let _ = event;
+    }
+
+    /// Called whenever the screen is left, preserved or not, and when the session
+    /// ends on it. For undoing changes made to devices.
+    fn on_exit(&mut self) {}
+
+    /// Deliver keys exactly as the device sent them, without mapping keypad keys
+    /// onto navigation keys. For screens that test the keys themselves.
+    fn wants_raw_keys(&self) -> bool {
+        false
+    }
+
+    /// A text field has the focus, so printable keys ('?', Q) are typed into it
+    /// rather than acting as shortcuts
+    fn text_focused(&self) -> bool {
+        false
+    }
+
+    /// Holding Up/Down keeps moving through a list, speeding up after a while.
+    /// Other screens never see autorepeat.
+    fn repeats_navigation(&self) -> bool {
+        false
+    }
+
+    /// Deliver the autorepeat of every key, e.g. to measure it
+    fn wants_key_repeats(&self) -> bool {
+        false
+    }
+
+    /// Operator and unit details entered on this screen, taken when leaving it
+    fn take_metadata(&mut self) -> Option<SessionMetadata> {
+        None
+    }
+
+    /// An earlier session the user chose to continue, taken when leaving the screen
+    fn take_restored(&mut self) -> Option<SessionReport> {
+        None
+    }
+
+    /// Rendering features to do without because frames are over budget, see
+    /// `frame_budget`. Called when the set changes and when the screen is shown.
+    fn set_degradations(&mut self, active: &[Degradation]) {
+        let _ = active;
+    }
+
+    /// The config files were read again (SIGHUP), for screens that keep
+    /// settings taken when they were created. Preserved screens are told too.
+    fn reconfigure(&mut self, options: &Options) {
+        let _ = options;
+    }
+}
+
+/// What a screen wants after handling an event
+pub enum Nav {
+    Stay,
+    To(ScreenId),
+}
@@ -0,0 +1,113 @@
+//! State of every device's listener thread. A listener that hits a read error
+//! exits, and its device silently stops contributing events while still being
+//! listed where it was seen. The listeners and the hotplug monitor record here
+//! whether each one is running, exited with an error or saw its device removed,
+//! for the device selection and the watchdog dump to show. A dead listener can
+//! be restarted by hand, which has the hotplug monitor reopen the device at
+//! once instead of on its next scan.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use once_cell::sync::Lazy;
+
+use crate::event_handler::DeviceInfo;
+
+static LISTENERS: Lazy<Mutex<BTreeMap<String, ListenerStatus>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+// The operator asked for dead listeners to be reopened, taken by the hotplug monitor
+static RESTART_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListenerState {
+    Running,
+    /// Reading failed with an error other than the device going away
+    Exited(io::ErrorKind),
+    /// The device was unplugged
+    Removed,
+}
+
+/// Last known state of one device's listener and since when
+#[derive(Debug, Clone)]
+pub struct ListenerStatus {
+    pub name: String,
+    pub state: ListenerState,
+    pub since: Instant,
+}
+
+impl ListenerStatus {
+    /// Short description with the age, e.g. "exited (timed out) 12 s ago"
+    pub fn describe(&self) -> String {
+        let ago = self.since.elapsed().as_secs();
+        match self.state {
+            ListenerState::Running => format!("running for {ago} s"),
+            ListenerState::Exited(kind) => format!("exited ({kind}) {ago} s ago"),
+            ListenerState::Removed => format!("removed {ago} s ago"),
+        }
+    }
+}
+
+fn set(info: &DeviceInfo, state: ListenerState) {
+    if let Ok(mut listeners) = LISTENERS.lock() {
+        listeners.insert(
+            info.path.clone(),
+            ListenerStatus {
+                name: info.name.clone(),
+                state,
+                since: Instant::now(),
+            },
+        );
+    }
+}
+
+/// A listener thread started for the device
+pub fn running(info: &DeviceInfo) {
+    set(info, ListenerState::Running);
+}
+
+/// The device's listener exited on a read error
+pub fn exited(info: &DeviceInfo, kind: io::ErrorKind) {
+    set(info, ListenerState::Exited(kind));
+}
+
+/// The device's listener exited because the device is gone
+pub fn removed(info: &DeviceInfo) {
+    set(info, ListenerState::Removed);
+}
+
+/// Status of the listener of the device at `path`, None if it never had one
+pub fn status(path: &str) -> Option<ListenerStatus> {
+    LISTENERS.lock().ok()?.get(path).cloned()
+}
+
+/// Every listener seen, by device path
+pub fn all() -> Vec<(String, ListenerStatus)> {
+    LISTENERS
+        .lock()
+        .map(|listeners| {
+            listeners
+                .iter()
+                .map(|(path, status)| (path.clone(), status.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Ask for the device at `path` to be reopened now. Returns false when it
+/// has no dead listener, there is nothing to restart then.
+pub fn request_restart(path: &str) -> bool {
+    if !status(path).is_some_and(|status| status.state != ListenerState::Running) {
+        return false;
+    }
+    RESTART_REQUESTED.store(true, Ordering::Relaxed);
+    true
+}
+
+/// Whether a restart was asked for since the last call
+pub fn take_restart() -> bool {
+    RESTART_REQUESTED.swap(false, Ordering::Relaxed)
+}
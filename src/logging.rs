@@ -0,0 +1,22 @@
+//! Where background threads report problems they can only log, like a device
+//! that stopped responding. The messages go to the sink the application
+//! installs and are dropped without one: stderr is usually under the TUI, so
+//! printing there would garble the screen.
+
+use once_cell::sync::OnceCell;
+
+type Sink = Box<dyn Fn(&str) + Send + Sync>;
+
+static SINK: OnceCell<Sink> = OnceCell::new();
+
+/// Route all messages to `sink`. Only the first call has an effect, later ones
+/// return false.
+pub fn set_sink(sink: impl Fn(&str) + Send + Sync + 'static) -> bool {
+    SINK.set(Box::new(sink)).is_ok()
+}
+
+pub(crate) fn warn(message: &str) {
+    if let Some(sink) = SINK.get() {
+        sink(message);
+    }
+}
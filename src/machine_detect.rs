@@ -1,9 +1,11 @@
-use once_cell::sync::OnceCell;
 use std::fs;
 use std::path::Path;
+use std::sync::RwLock;
 
-static COMPUTER_MODEL: OnceCell<Option<ComputerModel>> = OnceCell::new();
+// Detected on first use, detected again by `redetect_computer_model`
+static COMPUTER_MODEL: RwLock<Option<ComputerModel>> = RwLock::new(None);
 
+/// The field computer models the tool knows, detected from DMI
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ComputerModel {
     DatorBBFält,
@@ -14,29 +16,52 @@ pub enum ComputerModel {
     EjKänd,
 }
 
-pub fn has_touchscreen() -> bool {
-    match get_computer_model() {
-        ComputerModel::DatorBärbarCMBRF8 => false,
-        ComputerModel::DatorBärbarRS11 => false,
-        _ => true,
+impl ComputerModel {
+    pub const ALL: [ComputerModel; 6] = [
+        ComputerModel::DatorBBFält,
+        ComputerModel::DatorBBFältGPS,
+        ComputerModel::DatorBärbarRS11,
+        ComputerModel::DatorBärbarCMBRF8,
+        ComputerModel::DatorBBFältG2,
+        ComputerModel::EjKänd,
+    ];
+
+    pub fn has_touchscreen(self) -> bool {
+        !matches!(
+            self,
+            ComputerModel::DatorBärbarCMBRF8 | ComputerModel::DatorBärbarRS11
+        )
     }
-}
 
-pub fn has_serial_touchscreen() -> bool {
-    match get_computer_model() {
-        ComputerModel::DatorBBFält => true,
-        _ => false,
+    pub fn has_serial_touchscreen(self) -> bool {
+        matches!(self, ComputerModel::DatorBBFält)
     }
-}
 
-pub fn has_mouse() -> bool {
-    match get_computer_model() {
-        ComputerModel::DatorBBFältGPS => false,
-        ComputerModel::DatorBBFältG2 => false,
-        _ => true,
+    /// The touch controller reports x as y and y as x, on the GPS touchpad
+    pub fn swaps_touch_axes(self) -> bool {
+        matches!(self, ComputerModel::DatorBBFältGPS)
+    }
+
+    /// Models with a built-in trackpad (the field units use a Cypress pad)
+    pub fn has_trackpad(self) -> bool {
+        !matches!(
+            self,
+            ComputerModel::DatorBBFältGPS | ComputerModel::DatorBBFältG2
+        )
+    }
+
+    pub fn has_mouse(self) -> bool {
+        !matches!(
+            self,
+            ComputerModel::DatorBBFältGPS | ComputerModel::DatorBBFältG2
+        )
     }
 }
 
+pub fn has_serial_touchscreen() -> bool {
+    get_computer_model().has_serial_touchscreen()
+}
+
 fn read_trim<P: AsRef<Path>>(p: P) -> Option<String> {
     fs::read_to_string(p).ok().map(|s| s.trim().to_string())
 }
@@ -48,16 +73,22 @@ fn has_cypress_device() -> bool {
     false
 }
 
+/// Model of this machine, detected once and cached
 pub fn get_computer_model() -> ComputerModel {
-    if let Some(cached) = COMPUTER_MODEL.get().and_then(|opt| *opt) {
+    if let Some(cached) = COMPUTER_MODEL.read().ok().and_then(|cached| *cached) {
         return cached;
     }
+    redetect_computer_model()
+}
 
-    let read_model = read_computer_model();
-
-    COMPUTER_MODEL.set(Some(read_model)).unwrap();
-
-    return read_model;
+/// Detect the model again, replacing the cached one. Hardware plugged in after
+/// startup (the Cypress pad) can change it.
+pub fn redetect_computer_model() -> ComputerModel {
+    let model = read_computer_model();
+    if let Ok(mut cached) = COMPUTER_MODEL.write() {
+        *cached = Some(model);
+    }
+    model
 }
 
 fn read_computer_model() -> ComputerModel {
@@ -88,5 +119,15 @@ fn read_computer_model() -> ComputerModel {
         }
     }
 
-    return model;
+    model
+}
+
+/// The unit's serial number from DMI. Only root can read it, and boards without
+/// one often report a placeholder, both give None.
+pub fn read_product_serial() -> Option<String> {
+    read_trim("/sys/class/dmi/id/product_serial").filter(|serial| {
+        !serial.is_empty()
+            && !serial.eq_ignore_ascii_case("To Be Filled By O.E.M.")
+            && !serial.eq_ignore_ascii_case("Default string")
+    })
 }
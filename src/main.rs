@@ -1,254 +1,130 @@
-mod event_handler;
-mod keyboard_layouts;
-mod keyboard_test;
-mod machine_detect;
-mod mouse_test;
-mod serial_touch;
-mod touchscreen_test;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
 
 use color_eyre::Result;
-use crossbeam_channel::unbounded;
-use evdev::KeyCode;
-use ratatui::{
-    DefaultTerminal, Frame,
-    layout::{Constraint, Flex, Layout},
-    style::{Style, Stylize},
-    symbols::border,
-    text::{Line, Text},
-    widgets::{Block, List, ListItem, Paragraph},
-};
 
-use crate::{
-    event_handler::AppEvent,
-    keyboard_test::KeyboardTestScreen,
-    machine_detect::{has_mouse, has_touchscreen},
-    mouse_test::MouseTestScreen,
-    touchscreen_test::TouchscreenTestScreen,
+use input_device_test::{
+    app, capabilities, capture,
+    cli::Options,
+    logging, probe,
+    report::{self, SessionReport},
+    self_check,
+    status_server::StatusServer,
+    writer::{self, Writer, WriterHandle},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub enum ScreenId {
-    Home,
-    KeyboardTest,
-    MouseTest,
-    TouchscreenTest,
-    Exit,
+/// Where the library's warnings go: the fault log once there is one, and
+/// stderr, held back while the TUI owns the terminal
+struct Warnings {
+    log: Option<WriterHandle>,
+    held: Option<Vec<String>>,
 }
 
-pub trait Screen {
-    fn id(&self) -> ScreenId;
-    fn draw(&self, frame: &mut Frame);
-    fn handle_event(&mut self, event: AppEvent) -> Nav {
-        let _ = event;
-        Nav::Stay
-    }
-}
+static WARNINGS: Mutex<Warnings> = Mutex::new(Warnings {
+    log: None,
+    held: None,
+});
 
-pub enum Nav {
-    Stay,
-    To(ScreenId),
+fn warnings() -> MutexGuard<'static, Warnings> {
+    WARNINGS.lock().unwrap_or_else(PoisonError::into_inner)
 }
 
-struct HomeScreen {
-    selected: usize,
-    menu: Vec<(&'static str, ScreenId)>,
+fn warn(message: &str) {
+    let mut warnings = warnings();
+    if let Some(log) = &warnings.log {
+        log.append(&writer::FAULT_LOG, format!("{message}\n"));
+    }
+    match &mut warnings.held {
+        Some(held) => held.push(message.to_string()),
+        None => eprintln!("{message}"),
+    }
 }
 
-impl HomeScreen {
-    fn new() -> Self {
-        let mut menu = Vec::new();
-        menu.push(("Keyboard Test", ScreenId::KeyboardTest));
-
-        if has_mouse() {
-            menu.push(("Mouse Test", ScreenId::MouseTest));
-        }
+fn main() -> Result<()> {
+    color_eyre::install()?;
+    logging::set_sink(warn);
+    let started = Instant::now();
 
-        if has_touchscreen() {
-            menu.push(("Touchscreen Test", ScreenId::TouchscreenTest));
-        }
+    let options = Options::parse()?;
 
-        menu.push(("Exit", ScreenId::Exit));
-        HomeScreen { selected: 0, menu }
+    // The headless checks print and exit before the terminal is touched
+    if options.print_capabilities {
+        std::process::exit(capabilities::print_capabilities());
     }
-}
-
-impl Screen for HomeScreen {
-    fn id(&self) -> ScreenId {
-        ScreenId::Home
+    if options.print_config {
+        std::process::exit(options.effective_config().print());
     }
-
-    fn draw(&self, frame: &mut Frame) {
-        let area = frame.area();
-
-        let title = Line::from("Input Diagnostics Tool".bold().cyan());
-
-        let footer = Line::from(vec![
-            "↑/↓".bold().yellow(),
-            " navigate   ".into(),
-            "Enter".bold().yellow(),
-            " run   ".into(),
-            "1..9".bold().yellow(),
-            " quick launch   ".into(),
-            "Esc".bold().yellow(),
-            " exit".into(),
-        ]);
-
-        let block = Block::bordered()
-            .title(title.centered())
-            .title_bottom(footer.centered())
-            .border_set(border::THICK);
-
-        frame.render_widget(block, area);
-
-        let vertical_center = Layout::vertical([Constraint::Length(self.menu.len() as u16 * 3)])
-            .flex(Flex::Center)
-            .split(area)[0];
-
-        let menu_rect = Layout::horizontal([Constraint::Percentage(25)])
-            .flex(Flex::Center)
-            .split(vertical_center)[0];
-
-        let menu_items: Vec<ListItem> = self
-            .menu
-            .iter()
-            .enumerate()
-            .map(|(i, (label, _))| {
-                let style = if i == self.selected {
-                    Style::default().black().on_yellow().bold()
-                } else {
-                    Style::default()
-                };
-                ListItem::new(
-                    Text::from(vec![
-                        "".into(),
-                        Line::from(format!("{})  {label}", i + 1)),
-                        "".into(),
-                    ])
-                    .centered(),
-                )
-                .style(style)
-            })
-            .collect();
-
-        let menu = List::new(menu_items);
-
-        frame.render_widget(menu, menu_rect);
+    if options.self_check {
+        std::process::exit(self_check::run_headless());
     }
-
-    fn handle_event(&mut self, event: AppEvent) -> Nav {
-        match event {
-            AppEvent::Key { code, .. } => match code {
-                KeyCode::KEY_DOWN => {
-                    self.selected = (self.selected + 1) % self.menu.len();
-                }
-                KeyCode::KEY_UP => {
-                    self.selected = (self.selected + self.menu.len() - 1) % self.menu.len();
-                }
-                KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => {
-                    return Nav::To(self.menu[self.selected].1);
-                }
-                KeyCode::KEY_ESC => return Nav::To(ScreenId::Exit),
-                KeyCode::KEY_Q => return Nav::To(ScreenId::Exit),
-                KeyCode::KEY_1 => return Nav::To(self.menu[0].1),
-                KeyCode::KEY_2 => return Nav::To(self.menu[1].1),
-                KeyCode::KEY_3 => {
-                    if self.menu.len() > 2 {
-                        return Nav::To(self.menu[2].1);
-                    }
-                }
-                KeyCode::KEY_4 => {
-                    if self.menu.len() > 3 {
-                        return Nav::To(self.menu[3].1);
-                    }
-                }
-                _ => {}
-            },
-            _ => {}
-        }
-
-        Nav::Stay
+    if let Some(path) = &options.validate_report {
+        std::process::exit(report::validate_file(path));
+    }
+    if let Some((device, path)) = &options.capture {
+        std::process::exit(capture::record(&options.device_source(), device, path));
+    }
+    if let Some(path) = &options.dump_capture {
+        std::process::exit(capture::dump(path));
+    }
+    if options.list_devices {
+        std::process::exit(probe::list_devices(&options.device_source()));
+    }
+    if let Some(duration) = options.probe_touch {
+        std::process::exit(probe::probe_touch(
+            &options.device_source(),
+            duration,
+            options.key_rate_limit,
+            options.serial_baud_sweep,
+        ));
     }
-}
 
-fn main() -> Result<()> {
-    color_eyre::install()?;
+    let writer = match &options.log_dir {
+        Some(dir) => Some(Writer::spawn(dir, writer::DEFAULT_QUEUE_LIMIT)?),
+        None => None,
+    };
+    let log = writer.as_ref().map(Writer::handle);
+    warnings().log = log.clone();
 
+    // Started before the terminal is taken over, so a bind failure can be read
+    let status = options
+        .status_listen
+        .as_deref()
+        .and_then(StatusServer::spawn);
+
+    warnings().held = Some(Vec::new());
     let mut terminal = ratatui::init();
 
     terminal.clear()?;
 
-    let result = run(&mut terminal);
-
-    ratatui::restore();
-
-    return result;
-}
-
-fn run(terminal: &mut DefaultTerminal) -> Result<()> {
-    let mut active_screen: Box<dyn Screen> = Box::new(HomeScreen::new());
-
-    let (tx, rx) = unbounded();
+    let mut report = SessionReport::new();
+    report.demo = options.demo || options.replay_capture.is_some();
 
-    event_handler::spawn_device_listeners(&tx)?;
+    let result = app::run(&mut terminal, &mut report, &options, log.as_ref());
+    report.uptime_secs = started.elapsed().as_secs();
 
-    let mut exit = false;
-
-    while !exit {
-        terminal.draw(|f| active_screen.draw(f))?;
-
-        let next_event = rx.recv()?;
+    ratatui::restore();
+    for message in warnings().held.take().unwrap_or_default() {
+        eprintln!("{message}");
+    }
 
-        let navigation = active_screen.handle_event(next_event);
+    if let Some(status) = status {
+        status.shutdown();
+    }
 
-        match navigation {
-            Nav::Stay => {}
-            Nav::To(ScreenId::Exit) => {
-                exit = true;
-            }
-            Nav::To(screen_id) => {
-                terminal.draw(|f| draw_loading(f))?;
-                active_screen = create_screen(screen_id);
-            }
+    if let Some(writer) = writer {
+        warnings().log = None;
+        writer.shutdown();
+        for (topic, count) in log.iter().flat_map(WriterHandle::dropped) {
+            println!("Log '{topic}': {count} entries dropped");
         }
     }
 
-    Ok(())
-}
-
-fn draw_loading(frame: &mut Frame) {
-    let v_chunks = Layout::vertical([
-        Constraint::Min(0),
-        Constraint::Length(3),
-        Constraint::Min(0),
-    ])
-    .split(frame.area());
-
-    let h_chunks = Layout::horizontal([
-        Constraint::Min(0),
-        Constraint::Length(20),
-        Constraint::Min(0),
-    ])
-    .split(v_chunks[1]);
-
-    let area = h_chunks[1];
-
-    frame.render_widget(
-        Paragraph::new("Loading...")
-            .centered()
-            .block(Block::bordered()),
-        area,
-    );
-}
-
-fn create_screen(screen_id: ScreenId) -> Box<dyn Screen> {
-    match screen_id {
-        ScreenId::Home => Box::new(HomeScreen::new()),
-        ScreenId::KeyboardTest => Box::new(KeyboardTestScreen::new()),
-        ScreenId::MouseTest => Box::new(MouseTestScreen::new()),
-        ScreenId::TouchscreenTest => Box::new(TouchscreenTestScreen::new()),
-        ScreenId::Exit => {
-            eprintln!("Cannot create Exit screen");
-            Box::new(HomeScreen::new())
+    // Write the report even if the session ended with an error, whatever was collected is useful
+    if let Some(path) = &options.report_path {
+        for written in report.write(path, &options.report_formats)? {
+            println!("Report written to {}", written.display());
         }
     }
+
+    result
 }
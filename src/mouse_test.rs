@@ -1,32 +1,163 @@
 use evdev::KeyCode;
 use ratatui::{
     Frame,
+    layout::Rect,
     style::{Style, Stylize},
     symbols::border,
-    text::Line,
+    text::{Line, Span},
     widgets::{Block, Paragraph},
 };
 
-use crate::{Nav, Screen, ScreenId, event_handler::AppEvent};
+use std::cell::Cell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
+use crate::{
+    Nav, Screen, ScreenId,
+    event_handler::AppEvent,
+    machine_detect::ComputerModel,
+    power::PowerMonitor,
+    report::{DriftResult, MouseResult, ScreenResult},
+    test_timer::TestTimer,
+    touch_nav::{TouchAction, TouchButtons},
+};
+
+// Gaps longer than this are the operator pausing, not the mouse dropping events
+const MOTION_PAUSE: Duration = Duration::from_millis(500);
+const DRIFT_CHECK_DURATION: Duration = Duration::from_secs(5);
+// Movement (in counts) still accepted while nobody touches the mouse
+const DRIFT_TOLERANCE: f64 = 3.0;
+
+/// Starting values for the mouse test
+#[derive(Debug, Clone, Copy)]
+pub struct MouseSettings {
+    /// Cursor cells per movement count
+    pub sensitivity: f32,
+    /// Start the cursor in the middle of the screen instead of the top left corner
+    pub start_centered: bool,
+    /// Earlier cursor positions drawn behind the cursor, 0 for none
+    pub trail_length: usize,
+}
+
+impl MouseSettings {
+    /// Defaults suited to the model's pointing device
+    pub fn for_model(model: ComputerModel) -> Self {
+        let default = MouseSettings {
+            sensitivity: 0.2,
+            start_centered: true,
+            trail_length: 20,
+        };
+
+        match model {
+            // The trackpoint reports small deltas and barely moves the cursor at 0.2
+            ComputerModel::DatorBärbarRS11 => MouseSettings {
+                sensitivity: 1.0,
+                ..default
+            },
+            _ => default,
+        }
+    }
+}
+
+// Hands-off check in progress: started, motion events, distance
+struct DriftCheck {
+    started: Instant,
+    events: u64,
+    distance: f64,
+}
+
+/// Mouse test: a cursor following the movement, button checks and a drift check
 pub struct MouseTestScreen {
-    cursor_x: f32,
-    cursor_y: f32,
+    settings: MouseSettings,
+    // None until placed at the start position, which depends on the screen size
+    cursor: Option<(f32, f32)>,
+    trail: VecDeque<(u16, u16)>,
+    // Frame area of the last draw, for the start position and clamping
+    area: Cell<Rect>,
     sensitivity: f32,
     event_count: u64,
     left_button_presses: u64,
     right_button_presses: u64,
+
+    total_distance: f64,
+    buttons_verified: u8,
+    last_motion: Option<Instant>,
+    max_gap: Duration,
+    drift_check: Option<DriftCheck>,
+    drift_result: Option<DriftResult>,
+    touch_buttons: TouchButtons,
+    power: PowerMonitor,
+    timer: TestTimer,
 }
 
 impl MouseTestScreen {
-    pub fn new() -> Self {
+    pub fn new(settings: MouseSettings) -> Self {
         MouseTestScreen {
-            cursor_x: 70.0, // Start in middle of typical terminal
-            cursor_y: 20.0,
-            sensitivity: 0.2,
+            settings,
+            cursor: None,
+            trail: VecDeque::new(),
+            area: Cell::new(Rect::default()),
+            sensitivity: settings.sensitivity,
             event_count: 0,
             left_button_presses: 0,
             right_button_presses: 0,
+            total_distance: 0.0,
+            buttons_verified: 0,
+            last_motion: None,
+            max_gap: Duration::ZERO,
+            drift_check: None,
+            drift_result: None,
+            touch_buttons: TouchButtons::new(&[TouchAction::Back]),
+            power: PowerMonitor::new(),
+            timer: TestTimer::start(),
+        }
+    }
+
+    /// Shortest time the test has to run to pass
+    pub fn with_min_duration(mut self, min: Duration) -> Self {
+        self.timer.set_min(min);
+        self
+    }
+
+    fn start_position(&self, area: Rect) -> (f32, f32) {
+        if self.settings.start_centered {
+            (
+                (area.x + area.width / 2) as f32,
+                (area.y + area.height / 2) as f32,
+            )
+        } else {
+            // Just inside the border
+            ((area.x + 1) as f32, (area.y + 1) as f32)
+        }
+    }
+
+    fn cursor_position(&self, area: Rect) -> (f32, f32) {
+        self.cursor.unwrap_or_else(|| self.start_position(area))
+    }
+
+    fn finish_drift_check(&mut self) {
+        if let Some(check) = self
+            .drift_check
+            .take_if(|check| check.started.elapsed() >= DRIFT_CHECK_DURATION)
+        {
+            self.drift_result = Some(DriftResult {
+                duration_ms: check.started.elapsed().as_millis() as u64,
+                events: check.events,
+                distance: check.distance,
+                passed: check.distance <= DRIFT_TOLERANCE,
+            });
+        }
+    }
+
+    fn drift_status(&self) -> Option<Span<'static>> {
+        match (&self.drift_check, &self.drift_result) {
+            (Some(check), _) => {
+                let left = DRIFT_CHECK_DURATION.saturating_sub(check.started.elapsed());
+                Some(format!("| Hands off: {}s ", left.as_secs() + 1).magenta())
+            }
+            (None, Some(result)) if result.passed => Some("| Drift: OK ".green()),
+            (None, Some(_)) => Some("| Drift: FAIL ".red().bold()),
+            (None, None) => None,
         }
     }
 }
@@ -38,21 +169,32 @@ impl Screen for MouseTestScreen {
 
     fn draw(&self, frame: &mut Frame) {
         let area = frame.area();
+        self.area.set(area);
+        let (x, y) = self.cursor_position(area);
 
-        let title = Line::from(vec![
+        let mut title = vec![
             " Mouse Test ".bold().cyan(),
-            format!("| Position: ({:.0}, {:.0}) ", self.cursor_x, self.cursor_y).into(),
+            format!("| Position: ({:.0}, {:.0}) ", x, y).into(),
             format!("| Events: {} ", self.event_count).yellow(),
             format!("| L: {} ", self.left_button_presses).green(),
             format!("| R: {} ", self.right_button_presses).green(),
-        ]);
+            "| ".into(),
+            self.timer.label(),
+            " ".into(),
+        ];
+        title.extend(self.drift_status());
+        let title = Line::from(title);
         let footer = Line::from(vec![
             " ↑/↓".bold().yellow(),
             " sensitivity   ".into(),
             "Space".bold().yellow(),
             " reset   ".into(),
+            "D".bold().yellow(),
+            " drift check   ".into(),
             "Q/Esc".bold().yellow(),
             " exit   ".into(),
+            "F1".bold().yellow(),
+            " help   ".into(),
             format!("Sensitivity: {:.1}x ", self.sensitivity).yellow(),
         ]);
 
@@ -63,14 +205,19 @@ impl Screen for MouseTestScreen {
 
         frame.render_widget(block, area);
 
+        for &(x, y) in &self.trail {
+            if x < area.width && y < area.height {
+                frame.render_widget(
+                    Paragraph::new("·").style(Style::default().dark_gray()),
+                    Rect::new(x, y, 1, 1),
+                );
+            }
+        }
+
         // Draw cursor at the current position
         // Clamp cursor to be within terminal bounds
-        let cursor_x = (self.cursor_x.round() as u16)
-            .max(0)
-            .min(area.width.saturating_sub(1));
-        let cursor_y = (self.cursor_y.round() as u16)
-            .max(0)
-            .min(area.height.saturating_sub(1));
+        let cursor_x = (x.round() as u16).min(area.width.saturating_sub(1));
+        let cursor_y = (y.round() as u16).min(area.height.saturating_sub(1));
 
         // Create a simple cursor symbol
         let cursor = Paragraph::new("X").style(Style::default().bold().yellow());
@@ -84,6 +231,7 @@ impl Screen for MouseTestScreen {
         };
 
         frame.render_widget(cursor, cursor_area);
+        self.touch_buttons.draw(frame);
     }
 
     fn handle_event(&mut self, event: AppEvent) -> Nav {
@@ -101,31 +249,121 @@ impl Screen for MouseTestScreen {
                     self.sensitivity = (self.sensitivity - 0.1).max(0.1);
                 }
                 KeyCode::KEY_SPACE => {
-                    // Reset cursor position
-                    self.cursor_x = 70.0;
-                    self.cursor_y = 20.0;
+                    // Back to the start position
+                    self.cursor = None;
+                    self.trail.clear();
+                }
+                KeyCode::KEY_D => {
+                    // Start the hands-off drift check
+                    self.drift_check = Some(DriftCheck {
+                        started: Instant::now(),
+                        events: 0,
+                        distance: 0.0,
+                    });
+                    self.drift_result = None;
                 }
                 KeyCode::BTN_LEFT => {
                     self.left_button_presses += 1;
+                    self.buttons_verified |= MouseResult::LEFT;
                 }
                 KeyCode::BTN_RIGHT => {
                     self.right_button_presses += 1;
+                    self.buttons_verified |= MouseResult::RIGHT;
+                }
+                KeyCode::BTN_MIDDLE => {
+                    self.buttons_verified |= MouseResult::MIDDLE;
                 }
                 _ => {}
             },
             AppEvent::Mouse { x, y, .. } => {
                 // Update cursor position based on relative mouse movement with sensitivity
                 // x and y are deltas, not absolute positions
-                self.cursor_x += x as f32 * self.sensitivity;
-                self.cursor_y += y as f32 * self.sensitivity;
+                let area = self.area.get();
+                let (cursor_x, cursor_y) = self.cursor_position(area);
+
+                if self.settings.trail_length > 0 {
+                    let cell = (cursor_x.round() as u16, cursor_y.round() as u16);
+                    if self.trail.back() != Some(&cell) {
+                        self.trail.push_back(cell);
+                    }
+                    while self.trail.len() > self.settings.trail_length {
+                        self.trail.pop_front();
+                    }
+                }
 
-                self.cursor_x = self.cursor_x.clamp(0.0, 200.0);
-                self.cursor_y = self.cursor_y.clamp(0.0, 100.0);
+                let max_x = area.right().saturating_sub(1).max(area.x) as f32;
+                let max_y = area.bottom().saturating_sub(1).max(area.y) as f32;
+                self.cursor = Some((
+                    (cursor_x + x as f32 * self.sensitivity).clamp(area.x as f32, max_x),
+                    (cursor_y + y as f32 * self.sensitivity).clamp(area.y as f32, max_y),
+                ));
                 self.event_count += 1;
+
+                let distance = ((x as f64).powi(2) + (y as f64).powi(2)).sqrt();
+                self.total_distance += distance;
+
+                let now = Instant::now();
+                if let Some(last) = self.last_motion {
+                    let gap = now.duration_since(last);
+                    if gap < MOTION_PAUSE {
+                        self.max_gap = self.max_gap.max(gap);
+                    }
+                }
+                self.last_motion = Some(now);
+
+                if let Some(check) = self.drift_check.as_mut() {
+                    check.events += 1;
+                    check.distance += distance;
+                }
+            }
+            AppEvent::Tick => {
+                self.finish_drift_check();
+                self.power.tick();
+            }
+            AppEvent::Touch { .. } => {
+                let action = self.touch_buttons.handle_raw_touch(&event);
+                if action == Some(TouchAction::Back) {
+                    return Nav::To(ScreenId::Home);
+                }
             }
             _ => {}
         }
 
         Nav::Stay
     }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("↑/↓", "Increase/decrease cursor sensitivity"),
+            ("Space", "Move the cursor back to the start position"),
+            ("Left/Right click", "Counted in the header"),
+            ("D", "Hands-off drift check, don't touch the mouse for 5 s"),
+            ("Q/Esc, tap Back", "Back to the home menu"),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "Move the mouse or trackpad and check that the X cursor follows smoothly in all directions, \
+         then click both buttons and check that the counters in the header increase. \
+         The drift check makes sure the cursor stays put while nobody touches the mouse."
+    }
+
+    fn progress(&self) -> Option<f32> {
+        Some(self.buttons_verified.count_ones() as f32 / MouseResult::BUTTONS as f32)
+    }
+
+    fn take_results(&mut self) -> Option<ScreenResult> {
+        Some(ScreenResult::Mouse(MouseResult {
+            total_distance: self.total_distance,
+            event_count: self.event_count,
+            buttons_verified: self.buttons_verified,
+            max_gap_ms: self.max_gap.as_millis() as u64,
+            drift: self.drift_result.clone(),
+            sensitivity: self.sensitivity,
+            start_centered: self.settings.start_centered,
+            trail_length: self.settings.trail_length,
+            power: self.power.result(),
+            timing: Some(self.timer.timing()),
+        }))
+    }
 }
@@ -0,0 +1,88 @@
+//! Key events from keyboards other than the one under test. With a keyboard
+//! picked for the keyboard test, the others' keys don't count, but they are
+//! tallied: an operator typing on the wrong keyboard, or a device injecting
+//! keys, shows up instead of vanishing. Counted per event node, allocating only
+//! for a node's first event.
+
+use evdev::KeyCode;
+
+use crate::event_handler::DeviceInfo;
+use crate::report::OtherKeyboardResult;
+
+struct OtherKeyboard {
+    path: String,
+    name: String,
+    events: u64,
+    last: KeyCode,
+}
+
+#[derive(Default)]
+pub struct OtherKeyboards {
+    devices: Vec<OtherKeyboard>,
+    total: u64,
+    // Index into `devices` of the latest event's node
+    latest: Option<usize>,
+}
+
+impl OtherKeyboards {
+    pub fn new() -> Self {
+        OtherKeyboards::default()
+    }
+
+    pub fn record(&mut self, info: &DeviceInfo, code: KeyCode) {
+        let index = match self
+            .devices
+            .iter()
+            .position(|device| device.path == info.path)
+        {
+            Some(index) => index,
+            None => {
+                self.devices.push(OtherKeyboard {
+                    path: info.path.clone(),
+                    name: info.name.clone(),
+                    events: 0,
+                    last: code,
+                });
+                self.devices.len() - 1
+            }
+        };
+        let device = &mut self.devices[index];
+        device.events += 1;
+        device.last = code;
+        self.total += 1;
+        self.latest = Some(index);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Latest code and the name of the keyboard that sent it
+    pub fn latest(&self) -> Option<(KeyCode, &str)> {
+        let device = &self.devices[self.latest?];
+        Some((device.last, device.name.as_str()))
+    }
+
+    /// Name and event count of every keyboard, in the order they were seen
+    pub fn devices(&self) -> impl Iterator<Item = (&str, u64)> {
+        self.devices
+            .iter()
+            .map(|device| (device.name.as_str(), device.events))
+    }
+
+    pub fn clear(&mut self) {
+        *self = OtherKeyboards::default();
+    }
+
+    pub fn results(&self) -> Vec<OtherKeyboardResult> {
+        self.devices
+            .iter()
+            .map(|device| OtherKeyboardResult {
+                device: device.name.clone(),
+                path: device.path.clone(),
+                events: device.events,
+                last_key: format!("{:?}", device.last),
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,154 @@
+//! Hover-to-touch offset of pen digitizers. A pen in range reports where it
+//! hovers before it touches, and on a bad digitizer the position jumps at the
+//! moment of contact, which ruins handwriting. For every pen-down the distance
+//! from the last hover sample to the first contact sample is kept, and a panel
+//! whose offsets go over the threshold is flagged. Finger-only panels never
+//! hover, they show n/a.
+
+use crate::report::HoverResult;
+use crate::units::{self, MmScale};
+
+/// Largest hover-to-touch offset accepted, when the panel has a mm scale
+pub const DEFAULT_HOVER_OFFSET_MM: f32 = 1.0;
+
+// Threshold without a mm scale, in calibrated units (1% of the range)
+const HOVER_OFFSET_UNITS: f32 = 10.0;
+
+/// One pen-down's offset, calibrated units and mm when the scale was known
+#[derive(Debug, Clone, Copy)]
+struct Offset {
+    units: f32,
+    mm: Option<f32>,
+}
+
+impl Offset {
+    fn over(&self, threshold_mm: f32) -> bool {
+        match self.mm {
+            Some(mm) => mm > threshold_mm,
+            None => self.units > HOVER_OFFSET_UNITS,
+        }
+    }
+}
+
+/// Offsets of the selected device's pen-downs
+pub struct PenHover {
+    threshold_mm: f32,
+    // Calibrated position of the latest hover sample, None while the pen is
+    // out of range or touching
+    last_hover: Option<(u16, u16)>,
+    // The device sent hover samples at all
+    hovers: bool,
+    touches: u32,
+    sum: Offset,
+    min: Option<Offset>,
+    max: Option<Offset>,
+    latest: Option<Offset>,
+    over_threshold: u32,
+}
+
+impl PenHover {
+    pub fn new(threshold_mm: f32) -> Self {
+        PenHover {
+            threshold_mm,
+            last_hover: None,
+            hovers: false,
+            touches: 0,
+            sum: Offset {
+                units: 0.0,
+                mm: Some(0.0),
+            },
+            min: None,
+            max: None,
+            latest: None,
+            over_threshold: 0,
+        }
+    }
+
+    /// A calibrated hover sample, or the pen leaving range with `left`
+    pub fn hover(&mut self, x: u16, y: u16, left: bool) {
+        self.hovers = true;
+        self.last_hover = (!left).then_some((x, y));
+    }
+
+    /// First contact sample of a touch, measured against the hover before it.
+    /// A touch without a hover right before it (a finger) isn't counted.
+    pub fn touch_down(&mut self, x: u16, y: u16, scale: Option<MmScale>) {
+        let Some((hover_x, hover_y)) = self.last_hover.take() else {
+            return;
+        };
+        let (dx, dy) = (x as f32 - hover_x as f32, y as f32 - hover_y as f32);
+        let offset = Offset {
+            units: (dx * dx + dy * dy).sqrt(),
+            mm: scale.map(|scale| scale.distance_mm(dx, dy)),
+        };
+
+        self.touches += 1;
+        self.sum.units += offset.units;
+        self.sum.mm = self.sum.mm.zip(offset.mm).map(|(sum, mm)| sum + mm);
+        if self.min.is_none_or(|min| offset.units < min.units) {
+            self.min = Some(offset);
+        }
+        if self.max.is_none_or(|max| offset.units > max.units) {
+            self.max = Some(offset);
+        }
+        if offset.over(self.threshold_mm) {
+            self.over_threshold += 1;
+        }
+        self.latest = Some(offset);
+    }
+
+    /// The worst pen-down went over the threshold
+    pub fn flagged(&self) -> bool {
+        self.over_threshold > 0
+    }
+
+    /// "2.3 (0.4 mm), avg 1.1 over 12 pen-downs" for the overlay, n/a until
+    /// the device hovered
+    pub fn label(&self) -> String {
+        if !self.hovers {
+            return "n/a".to_string();
+        }
+        match (self.latest, self.average()) {
+            (Some(latest), Some(average)) => format!(
+                "last {}, avg {} over {} pen-downs",
+                units::format_distance(latest.units, latest.mm),
+                units::format_distance(average.units, average.mm),
+                self.touches
+            ),
+            _ => "hovering, no pen-down yet".to_string(),
+        }
+    }
+
+    fn average(&self) -> Option<Offset> {
+        let touches = self.touches as f32;
+        (self.touches > 0).then(|| Offset {
+            units: self.sum.units / touches,
+            mm: self.sum.mm.map(|mm| mm / touches),
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = PenHover::new(self.threshold_mm);
+    }
+
+    /// None for devices that never hovered
+    pub fn result(&self) -> Option<HoverResult> {
+        if !self.hovers {
+            return None;
+        }
+        let average = self.average();
+        Some(HoverResult {
+            pen_downs: self.touches,
+            min: self.min.map(|offset| offset.units),
+            avg: average.map(|offset| offset.units),
+            max: self.max.map(|offset| offset.units),
+            min_mm: self.min.and_then(|offset| offset.mm),
+            avg_mm: average.and_then(|offset| offset.mm),
+            max_mm: self.max.and_then(|offset| offset.mm),
+            threshold_mm: self.threshold_mm,
+            threshold: HOVER_OFFSET_UNITS,
+            over_threshold: self.over_threshold,
+            flagged: self.flagged(),
+        })
+    }
+}
@@ -0,0 +1,280 @@
+//! Power source of the unit under test. Touch panels on the field units are
+//! measurably noisier on battery (charger noise coupling), so the tests show
+//! and report whether they ran on AC or battery, and warn when that changed
+//! halfway through a test. Units without power_supply entries show nothing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::report::PowerResult;
+
+/// Where the kernel lists power supplies
+pub const POWER_SUPPLY_DIR: &str = "/sys/class/power_supply";
+
+// Ticks come ten times a second, sysfs is read far less often
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerSource {
+    Ac,
+    Battery,
+}
+
+impl PowerSource {
+    pub fn name(self) -> &'static str {
+        match self {
+            PowerSource::Ac => "AC",
+            PowerSource::Battery => "battery",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerState {
+    pub source: PowerSource,
+    /// Battery charge in percent, None without a battery
+    pub capacity: Option<u8>,
+}
+
+impl PowerState {
+    /// e.g. "battery 54%" or "AC"
+    pub fn label(&self) -> String {
+        match self.capacity {
+            Some(capacity) => format!("{} {capacity}%", self.source.name()),
+            None => self.source.name().to_string(),
+        }
+    }
+}
+
+/// The power state from the supplies listed in `dir`, None when there are none.
+/// Any online mains or USB supply means AC.
+pub fn read_power(dir: &Path) -> Option<PowerState> {
+    let read = |supply: &Path, name: &str| {
+        fs::read_to_string(supply.join(name))
+            .ok()
+            .map(|value| value.trim().to_string())
+    };
+
+    let mut on_ac = false;
+    let mut has_battery = false;
+    let mut capacity = None;
+    for entry in fs::read_dir(dir).ok()?.flatten() {
+        let supply = entry.path();
+        match read(&supply, "type").as_deref() {
+            Some("Mains") | Some("USB") => {
+                on_ac |= read(&supply, "online").as_deref() == Some("1");
+            }
+            Some("Battery") => {
+                has_battery = true;
+                capacity = capacity.or_else(|| read(&supply, "capacity")?.parse().ok());
+            }
+            _ => {}
+        }
+    }
+
+    let source = if on_ac {
+        PowerSource::Ac
+    } else if has_battery {
+        PowerSource::Battery
+    } else {
+        return None;
+    };
+    Some(PowerState { source, capacity })
+}
+
+/// Power state over one test: read when the test starts and every few seconds
+/// on ticks after that
+pub struct PowerMonitor {
+    dir: PathBuf,
+    at_start: Option<PowerState>,
+    current: Option<PowerState>,
+    // The source changed at some point since the start
+    changed: bool,
+    last_read: Instant,
+}
+
+impl PowerMonitor {
+    pub fn new() -> Self {
+        Self::with_dir(POWER_SUPPLY_DIR)
+    }
+
+    /// Read the supplies from `dir` instead of sysfs
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        let mut monitor = PowerMonitor {
+            dir: dir.into(),
+            at_start: None,
+            current: None,
+            changed: false,
+            last_read: Instant::now(),
+        };
+        monitor.start();
+        monitor
+    }
+
+    /// A test starts, everything before doesn't count
+    pub fn start(&mut self) {
+        self.current = read_power(&self.dir);
+        self.at_start = self.current;
+        self.changed = false;
+        self.last_read = Instant::now();
+    }
+
+    pub fn tick(&mut self) {
+        if self.last_read.elapsed() < POLL_INTERVAL {
+            return;
+        }
+        self.last_read = Instant::now();
+        self.current = read_power(&self.dir);
+
+        let source = |state: Option<PowerState>| state.map(|state| state.source);
+        self.changed |= source(self.current) != source(self.at_start);
+    }
+
+    pub fn current(&self) -> Option<PowerState> {
+        self.current
+    }
+
+    /// The power source changed during the test, so its numbers don't compare
+    /// with tests run on one source
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn result(&self) -> Option<PowerResult> {
+        let label = |state: Option<PowerState>| state.map_or("unknown".to_string(), |s| s.label());
+        if self.at_start.is_none() && self.current.is_none() {
+            return None;
+        }
+        Some(PowerResult {
+            at_start: label(self.at_start),
+            at_end: label(self.current),
+            changed: self.changed,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake power_supply directory
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(what: &str) -> Self {
+            let dir = std::env::temp_dir()
+                .join(format!("keyboard_test-power-{what}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            TempDir(dir)
+        }
+
+        /// A supply named `name` with `files` as (file, value) pairs, sysfs
+        /// style with a trailing newline
+        fn supply(&self, name: &str, files: &[(&str, &str)]) {
+            let supply = self.0.join(name);
+            fs::create_dir_all(&supply).unwrap();
+            for (file, value) in files {
+                fs::write(supply.join(file), format!("{value}\n")).unwrap();
+            }
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// Read again on the next tick instead of after POLL_INTERVAL
+    fn tick_now(monitor: &mut PowerMonitor) {
+        monitor.last_read = Instant::now() - POLL_INTERVAL;
+        monitor.tick();
+    }
+
+    #[test]
+    fn an_online_charger_means_ac_and_the_battery_gives_its_charge() {
+        let dir = TempDir::new("ac");
+        dir.supply("AC", &[("type", "Mains"), ("online", "1")]);
+        dir.supply("BAT0", &[("type", "Battery"), ("capacity", "54")]);
+        let state = read_power(&dir.0).unwrap();
+        assert_eq!(state.source, PowerSource::Ac);
+        assert_eq!(state.capacity, Some(54));
+        assert_eq!(state.label(), "AC 54%");
+
+        dir.supply("AC", &[("online", "0")]);
+        let state = read_power(&dir.0).unwrap();
+        assert_eq!(state.source, PowerSource::Battery);
+        assert_eq!(state.label(), "battery 54%");
+
+        // An online USB supply charges as well, a battery without a capacity
+        // file has no charge to show
+        dir.supply("usb", &[("type", "USB"), ("online", "1")]);
+        fs::remove_file(dir.0.join("BAT0/capacity")).unwrap();
+        assert_eq!(
+            read_power(&dir.0),
+            Some(PowerState {
+                source: PowerSource::Ac,
+                capacity: None
+            })
+        );
+    }
+
+    #[test]
+    fn unplugging_the_charger_during_a_test_is_a_change() {
+        let dir = TempDir::new("unplug");
+        dir.supply("AC", &[("type", "Mains"), ("online", "1")]);
+        dir.supply("BAT0", &[("type", "Battery"), ("capacity", "80")]);
+        let mut monitor = PowerMonitor::with_dir(&dir.0);
+        assert_eq!(monitor.current().unwrap().source, PowerSource::Ac);
+
+        // Not read again before the poll interval
+        dir.supply("AC", &[("online", "0")]);
+        monitor.tick();
+        assert_eq!(monitor.current().unwrap().source, PowerSource::Ac);
+        assert!(!monitor.changed());
+
+        tick_now(&mut monitor);
+        assert_eq!(monitor.current().unwrap().source, PowerSource::Battery);
+        assert!(monitor.changed());
+
+        // Plugged back in, it still changed during the test
+        dir.supply("AC", &[("online", "1")]);
+        tick_now(&mut monitor);
+        assert!(monitor.changed());
+        let result = monitor.result().unwrap();
+        assert_eq!(result.at_start, "AC 80%");
+        assert_eq!(result.at_end, "AC 80%");
+        assert!(result.changed);
+
+        // A new test starts from the state it finds
+        monitor.start();
+        assert!(!monitor.changed());
+
+        // A falling charge isn't a change of source
+        dir.supply("AC", &[("online", "0")]);
+        monitor.start();
+        dir.supply("BAT0", &[("capacity", "79")]);
+        tick_now(&mut monitor);
+        assert_eq!(monitor.current().unwrap().label(), "battery 79%");
+        assert!(!monitor.changed());
+    }
+
+    #[test]
+    fn units_without_supplies_show_nothing() {
+        let dir = TempDir::new("empty");
+        assert_eq!(read_power(&dir.0), None);
+        assert_eq!(read_power(&dir.0.join("missing")), None);
+
+        // Supplies of other types don't count
+        dir.supply("ups", &[("type", "UPS")]);
+        assert_eq!(read_power(&dir.0), None);
+
+        let mut monitor = PowerMonitor::with_dir(dir.0.join("missing"));
+        tick_now(&mut monitor);
+        assert_eq!(monitor.current(), None);
+        assert!(!monitor.changed());
+        assert!(monitor.result().is_none());
+    }
+}
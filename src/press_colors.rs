@@ -0,0 +1,148 @@
+//! Key colors by press count in the keyboard test. The default ramp goes from
+//! green to yellow to red over the first few presses and stays red from there,
+//! so a heavily used key never looks fresh again. The older palette that cycles
+//! through five colors is still available. The color-blind theme (see `theme`)
+//! takes the same counts along blue to white instead.
+
+use ratatui::style::Color;
+
+/// Presses until a key reaches the top of the ramp
+pub const DEFAULT_RAMP_STEPS: usize = 10;
+
+// The original palette, repeating every five presses
+const CYCLE_COLORS: [Color; 5] = [
+    Color::Green,
+    Color::Yellow,
+    Color::Red,
+    Color::Blue,
+    Color::Magenta,
+];
+
+// Cycle and ramp of the color-blind theme, blues to white, no red or green
+const SAFE_CYCLE_COLORS: [Color; 5] = [
+    Color::Rgb(40, 110, 220),
+    Color::Rgb(235, 235, 235),
+    Color::Rgb(120, 170, 240),
+    Color::Rgb(150, 150, 150),
+    Color::Rgb(80, 200, 230),
+];
+
+// Gradient stops of the ramp: green, yellow, red
+const RAMP_STOPS: [(u8, u8, u8); 3] = [(40, 200, 60), (230, 210, 40), (220, 50, 40)];
+const SAFE_RAMP_STOPS: [(u8, u8, u8); 3] = [(40, 110, 220), (120, 170, 240), (235, 235, 235)];
+
+// The legend shows at most this many ramp colors, spread over the ramp
+const LEGEND_MAX_ENTRIES: usize = 10;
+
+/// How press counts map to key colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PressPalette {
+    /// Counts 1..=steps run along the gradient, higher counts stay at the top
+    Ramp { steps: usize },
+    /// The five colors repeating, the old behavior
+    Cycle,
+}
+
+impl Default for PressPalette {
+    fn default() -> Self {
+        PressPalette::Ramp {
+            steps: DEFAULT_RAMP_STEPS,
+        }
+    }
+}
+
+impl PressPalette {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ramp" => Some(PressPalette::default()),
+            "cycle" => Some(PressPalette::Cycle),
+            _ => None,
+        }
+    }
+
+    /// Background for a key pressed `count` times, `count` at least 1
+    pub fn color(&self, count: usize) -> Color {
+        self.color_in(count, &CYCLE_COLORS, &RAMP_STOPS)
+    }
+
+    /// `color` in the color-blind theme's colors
+    pub fn safe_color(&self, count: usize) -> Color {
+        self.color_in(count, &SAFE_CYCLE_COLORS, &SAFE_RAMP_STOPS)
+    }
+
+    fn color_in(&self, count: usize, cycle: &[Color], stops: &[(u8, u8, u8); 3]) -> Color {
+        match *self {
+            PressPalette::Cycle => cycle[(count.max(1) - 1) % cycle.len()],
+            PressPalette::Ramp { steps } => {
+                if steps <= 1 {
+                    return rgb(stops[stops.len() - 1]);
+                }
+                let position = (count.clamp(1, steps) - 1) as f32 / (steps - 1) as f32;
+                ramp_color(stops, position)
+            }
+        }
+    }
+
+    /// Whether `count` is past the end of the ramp, where the color no longer
+    /// tells counts apart and the cell shows the number instead
+    pub fn is_clamped(&self, count: usize) -> bool {
+        match *self {
+            PressPalette::Ramp { steps } => count > steps,
+            PressPalette::Cycle => false,
+        }
+    }
+
+    /// Labels and colors for the legend strip, lowest count first
+    pub fn legend(&self) -> Vec<(String, Color)> {
+        self.legend_in(|count| self.color(count))
+    }
+
+    /// `legend` in the color-blind theme's colors
+    pub fn safe_legend(&self) -> Vec<(String, Color)> {
+        self.legend_in(|count| self.safe_color(count))
+    }
+
+    fn legend_in(&self, color: impl Fn(usize) -> Color) -> Vec<(String, Color)> {
+        match *self {
+            PressPalette::Cycle => (1..=CYCLE_COLORS.len())
+                .map(|count| (count.to_string(), color(count)))
+                .collect(),
+            PressPalette::Ramp { steps } => {
+                let steps = steps.max(1);
+                let entries = steps.min(LEGEND_MAX_ENTRIES);
+                (0..entries)
+                    .map(|i| {
+                        // Evenly spaced counts, always including 1 and the top
+                        let count = if entries == 1 {
+                            steps
+                        } else {
+                            1 + i * (steps - 1) / (entries - 1)
+                        };
+                        let label = if count == steps {
+                            format!("{count}+")
+                        } else {
+                            count.to_string()
+                        };
+                        (label, color(count))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// Color at `position` (0.0..=1.0) along the gradient stops
+fn ramp_color(stops: &[(u8, u8, u8); 3], position: f32) -> Color {
+    let segments = (stops.len() - 1) as f32;
+    let scaled = position.clamp(0.0, 1.0) * segments;
+    let index = (scaled.floor() as usize).min(stops.len() - 2);
+    let t = scaled - index as f32;
+
+    let (from, to) = (stops[index], stops[index + 1]);
+    let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+    Color::Rgb(mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+fn rgb((r, g, b): (u8, u8, u8)) -> Color {
+    Color::Rgb(r, g, b)
+}
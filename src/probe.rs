@@ -0,0 +1,388 @@
+//! Headless device checks for support over SSH: `--list-devices` prints what the
+//! input devices report, `--probe-touch` listens on the touch devices for a while
+//! and prints what arrived. Neither starts the TUI.
+//!
+//! Exit codes: 0 found something, 1 nothing found, 2 devices that couldn't be
+//! opened for lack of permissions and nothing found otherwise.
+
+use std::collections::{BTreeMap, HashSet};
+use std::io;
+use std::time::{Duration, Instant};
+
+use evdev::{AbsoluteAxisCode, Device, KeyCode, RelativeAxisCode};
+
+use crate::device_class::DeviceClass;
+use crate::event_handler::{self, AppEvent, DeviceInfo, DeviceSource};
+use crate::key_rate::RateLimit;
+use crate::machine_detect::has_serial_touchscreen;
+use crate::serial_touch::{self, SERIAL_PORT};
+
+pub const EXIT_FOUND: i32 = 0;
+pub const EXIT_NOTHING_FOUND: i32 = 1;
+pub const EXIT_PERMISSION_DENIED: i32 = 2;
+
+/// Print a table of every input device in `source` with its identity,
+/// capabilities and axis ranges. Returns the exit code.
+pub fn list_devices(source: &DeviceSource) -> i32 {
+    let (devices, denied) = match scan(source) {
+        Ok(scan) => scan,
+        Err(code) => return code,
+    };
+
+    let mut rows: Vec<[String; 6]> = devices
+        .iter()
+        .map(|(device, info)| {
+            [
+                info.path.clone(),
+                info.name.clone(),
+                format!("{:04x}:{:04x}", info.vendor, info.product),
+                driver(info),
+                capabilities(device, info).join(","),
+                axis_ranges(device, info),
+            ]
+        })
+        .collect();
+    if has_serial_touchscreen() {
+        rows.push([
+            SERIAL_PORT.to_string(),
+            serial_touch::serial_device_info(SERIAL_PORT).name,
+            "-".to_string(),
+            "-".to_string(),
+            "touchscreen,serial".to_string(),
+            "-".to_string(),
+        ]);
+    }
+
+    if !rows.is_empty() {
+        print_table(
+            ["PATH", "NAME", "ID", "DRIVER", "CAPABILITIES", "AXES"],
+            &rows,
+        );
+    }
+    exit_code(!rows.is_empty(), &denied)
+}
+
+/// Listen on every touch capable device in `source`, and the serial touchscreen
+/// on models that have one, for `duration`, then print the events seen per
+/// device. Returns the exit code.
+pub fn probe_touch(
+    source: &DeviceSource,
+    duration: Duration,
+    key_limit: RateLimit,
+    serial_baud_sweep: bool,
+) -> i32 {
+    let (devices, denied) = match scan(source) {
+        Ok(scan) => scan,
+        Err(code) => return code,
+    };
+
+    let touch_paths: HashSet<String> = devices
+        .iter()
+        .filter(|(device, _)| is_touch(device))
+        .map(|(_, info)| info.path.clone())
+        .collect();
+    let serial = has_serial_touchscreen();
+
+    if touch_paths.is_empty() && !serial {
+        println!("No touch capable devices found");
+        return exit_code(false, &denied);
+    }
+
+    let mut listening: Vec<&str> = touch_paths.iter().map(String::as_str).collect();
+    if serial {
+        listening.push(SERIAL_PORT);
+    }
+    listening.sort();
+    println!(
+        "Listening on {} for {} s, touch the panel...",
+        listening.join(", "),
+        duration.as_secs_f32()
+    );
+
+    let (tx, rx) = crossbeam_channel::unbounded();
+    if !touch_paths.is_empty() {
+        let touch_source = DeviceSource {
+            dir: source.dir.clone(),
+            sysfs: source.sysfs.clone(),
+            filter: None,
+        }
+        .with_filter(move |info| touch_paths.contains(&info.path));
+        if let Err(e) = event_handler::spawn_evdev_listeners(&tx, touch_source, key_limit, None) {
+            println!("Cannot listen on the touch devices: {e}");
+        }
+    }
+    if serial && let Err(e) = serial_touch::spawn_reader(tx.clone(), serial_baud_sweep) {
+        println!("Cannot listen on {SERIAL_PORT}: {e}");
+    }
+
+    let mut seen: BTreeMap<String, TouchActivity> = BTreeMap::new();
+    let deadline = Instant::now() + duration;
+    while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+        let Ok(event) = rx.recv_timeout(left) else {
+            break;
+        };
+        match event {
+            AppEvent::Touch { x, y, info, .. } => {
+                let name = info.map_or_else(|| "unknown".to_string(), |info| label(&info));
+                seen.entry(name).or_default().record(&[(x, y)]);
+            }
+            AppEvent::MultiTouch { contacts, info, .. }
+            | AppEvent::Trackpad { contacts, info, .. } => {
+                let points: Vec<(u16, u16)> = contacts.iter().map(|c| (c.x, c.y)).collect();
+                seen.entry(label(&info)).or_default().record(&points);
+            }
+            AppEvent::DeviceFault { info, reason } => {
+                println!("Fault on {}: {reason}", label(&info));
+            }
+            AppEvent::EventsDropped { info, total } => {
+                println!(
+                    "Kernel dropped events of {}, {total} times so far",
+                    label(&info)
+                );
+            }
+            AppEvent::SerialStatus {
+                info,
+                baud,
+                valid_ratio,
+                swept,
+            } => {
+                let how = if swept {
+                    "after a sweep"
+                } else {
+                    "as configured"
+                };
+                println!(
+                    "{} at {baud} baud {how}, {:.0}% of bytes in valid frames",
+                    label(&info),
+                    valid_ratio * 100.0
+                );
+            }
+            _ => {}
+        }
+    }
+
+    if seen.is_empty() {
+        println!("No touch events in {} s", duration.as_secs_f32());
+        return EXIT_NOTHING_FOUND;
+    }
+
+    let rows: Vec<[String; 4]> = seen
+        .iter()
+        .map(|(device, activity)| {
+            [
+                device.clone(),
+                activity.events.to_string(),
+                activity.range(),
+                format!("{:.1}/s", activity.rate()),
+            ]
+        })
+        .collect();
+    print_table(["DEVICE", "EVENTS", "RANGE SEEN", "RATE"], &rows);
+    EXIT_FOUND
+}
+
+/// Events from one device during the probe
+#[derive(Default)]
+struct TouchActivity {
+    events: usize,
+    // (min x, max x, min y, max y)
+    range: Option<(u16, u16, u16, u16)>,
+    first: Option<Instant>,
+    last: Option<Instant>,
+}
+
+impl TouchActivity {
+    fn record(&mut self, points: &[(u16, u16)]) {
+        let now = Instant::now();
+        self.events += 1;
+        self.first.get_or_insert(now);
+        self.last = Some(now);
+        for &(x, y) in points {
+            self.range = Some(match self.range {
+                None => (x, x, y, y),
+                Some((x0, x1, y0, y1)) => (x0.min(x), x1.max(x), y0.min(y), y1.max(y)),
+            });
+        }
+    }
+
+    fn range(&self) -> String {
+        match self.range {
+            Some((x0, x1, y0, y1)) => format!("x {x0}..{x1} y {y0}..{y1}"),
+            None => "-".to_string(),
+        }
+    }
+
+    // Events per second between the first and the last one
+    fn rate(&self) -> f32 {
+        let span = match (self.first, self.last) {
+            (Some(first), Some(last)) => last.duration_since(first).as_secs_f32(),
+            _ => 0.0,
+        };
+        if span > 0.0 {
+            (self.events - 1) as f32 / span
+        } else {
+            0.0
+        }
+    }
+}
+
+// Devices that opened, and the paths refused for lack of permissions
+type Opened = (Vec<(Device, DeviceInfo)>, Vec<String>);
+
+// The exit code instead when even the directory can't be read
+fn scan(source: &DeviceSource) -> Result<Opened, i32> {
+    let (devices, failures) = match event_handler::scan_devices(source) {
+        Ok(scan) => scan,
+        Err(e) => {
+            println!("Cannot read {}: {e}", source.dir.display());
+            return Err(if e.kind() == io::ErrorKind::PermissionDenied {
+                EXIT_PERMISSION_DENIED
+            } else {
+                EXIT_NOTHING_FOUND
+            });
+        }
+    };
+
+    let mut denied = Vec::new();
+    for (path, error) in failures {
+        if error.kind() == io::ErrorKind::PermissionDenied {
+            denied.push(path);
+        } else {
+            println!("Could not open {path}: {error}");
+        }
+    }
+    Ok((devices, denied))
+}
+
+fn exit_code(found: bool, denied: &[String]) -> i32 {
+    if !denied.is_empty() {
+        println!(
+            "Permission denied for {} device(s): {} (run as root or add the user to the input group)",
+            denied.len(),
+            denied.join(", ")
+        );
+    }
+    if found {
+        EXIT_FOUND
+    } else if !denied.is_empty() {
+        EXIT_PERMISSION_DENIED
+    } else {
+        println!("No input devices found");
+        EXIT_NOTHING_FOUND
+    }
+}
+
+/// "usbhid v0111 fw 1.02": the driver, the input id version and a USB
+/// device's bcdDevice
+fn driver(info: &DeviceInfo) -> String {
+    let ids = &info.ids;
+    let mut driver = ids.driver.clone().unwrap_or_else(|| "-".to_string());
+    if let Some(version) = &ids.version {
+        driver.push_str(&format!(" v{version}"));
+    }
+    if let Some(bcd) = ids.usb.as_ref().and_then(|usb| usb.bcd_device.as_ref()) {
+        driver.push_str(&format!(" fw {bcd}"));
+    }
+    driver
+}
+
+fn label(info: &DeviceInfo) -> String {
+    format!("{} ({})", info.path, info.name)
+}
+
+/// Touchscreens and touchpads: anything with absolute X or multi-touch positions
+fn is_touch(device: &Device) -> bool {
+    device.supported_absolute_axes().is_some_and(|axes| {
+        axes.contains(AbsoluteAxisCode::ABS_X) || axes.contains(AbsoluteAxisCode::ABS_MT_POSITION_X)
+    })
+}
+
+fn capabilities(device: &Device, info: &DeviceInfo) -> Vec<&'static str> {
+    let has_key = |code: KeyCode| {
+        device
+            .supported_keys()
+            .is_some_and(|keys| keys.contains(code))
+    };
+
+    let mut capabilities = Vec::new();
+    if has_key(KeyCode::KEY_A) || has_key(KeyCode::KEY_ENTER) || has_key(KeyCode::KEY_KP0) {
+        capabilities.push("keyboard");
+    }
+    if device
+        .supported_relative_axes()
+        .is_some_and(|axes| axes.contains(RelativeAxisCode::REL_X))
+    {
+        capabilities.push("pointer");
+    }
+    if has_key(KeyCode::BTN_LEFT) {
+        capabilities.push("buttons");
+    }
+    match info.class {
+        DeviceClass::Touchscreen if has_key(KeyCode::BTN_TOOL_PEN) => capabilities.push("pen"),
+        DeviceClass::Touchscreen => capabilities.push("touchscreen"),
+        DeviceClass::Trackpad => capabilities.push("trackpad"),
+        DeviceClass::Other if is_touch(device) => capabilities.push("touch"),
+        DeviceClass::Other => {}
+    }
+    if info.multitouch {
+        capabilities.push("multitouch");
+    }
+    if info.pressure_max.is_some() {
+        capabilities.push("pressure");
+    }
+    if capabilities.is_empty() {
+        capabilities.push("other");
+    }
+    capabilities
+}
+
+fn axis_ranges(device: &Device, info: &DeviceInfo) -> String {
+    let (Some(axes), Ok(state)) = (device.supported_absolute_axes(), device.get_abs_state()) else {
+        return "-".to_string();
+    };
+
+    let mut ranges: Vec<String> = [
+        (AbsoluteAxisCode::ABS_X, "x"),
+        (AbsoluteAxisCode::ABS_Y, "y"),
+        (AbsoluteAxisCode::ABS_PRESSURE, "pressure"),
+    ]
+    .into_iter()
+    .filter(|(code, _)| axes.contains(*code))
+    .filter_map(|(code, name)| {
+        let axis = state.get(code.0 as usize)?;
+        Some(format!("{name} {}..{}", axis.minimum, axis.maximum))
+    })
+    .collect();
+    if let Some((x, y)) = info.resolution {
+        ranges.push(format!("{x}x{y} units/mm"));
+    }
+
+    if ranges.is_empty() {
+        "-".to_string()
+    } else {
+        ranges.join(", ")
+    }
+}
+
+fn print_table<const N: usize>(header: [&str; N], rows: &[[String; N]]) {
+    let mut widths = header.map(str::len);
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let line = |cells: [&str; N]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        println!("{}", padded.join("  ").trim_end());
+    };
+
+    line(header);
+    for row in rows {
+        line(row.each_ref().map(String::as_str));
+    }
+}
@@ -0,0 +1,95 @@
+//! Raw coordinate range of a panel that doesn't report one, learned from its
+//! samples. The serial protocol carries 14-bit coordinates, but the built-in
+//! panel only uses part of them (roughly 200-3800), so the theoretical range
+//! maps every touch into a small region in the middle. The smallest and largest
+//! value seen on each axis make the range. A sample past the range only moves
+//! the edge part of the way towards it, so a single noise spike barely widens
+//! the range while an edge touched again and again is reached.
+
+use crate::saturation::AxisLimits;
+
+/// Share of the distance to a sample past the range that the edge moves by
+const EXPANSION_RATE: f32 = 0.25;
+
+/// Raw units an edge has to move before the range is reported again, so a
+/// slowly widening range doesn't announce every sample
+const REPORT_STEP: u16 = 16;
+
+/// Range seen on one axis
+#[derive(Debug, Clone, Copy)]
+struct LearnedAxis {
+    min: f32,
+    max: f32,
+}
+
+impl LearnedAxis {
+    fn new(value: u16) -> Self {
+        LearnedAxis {
+            min: value as f32,
+            max: value as f32,
+        }
+    }
+
+    fn observe(&mut self, value: u16) {
+        let value = value as f32;
+        if value < self.min {
+            self.min += (value - self.min) * EXPANSION_RATE;
+        } else if value > self.max {
+            self.max += (value - self.max) * EXPANSION_RATE;
+        }
+    }
+
+    fn limits(&self) -> (u16, u16) {
+        (self.min.round() as u16, self.max.round() as u16)
+    }
+}
+
+/// Range learned from the samples of one panel
+#[derive(Debug, Default)]
+pub struct RangeLearner {
+    // X and Y, None before the first sample
+    axes: Option<(LearnedAxis, LearnedAxis)>,
+    reported: Option<AxisLimits>,
+}
+
+impl RangeLearner {
+    pub fn new() -> Self {
+        RangeLearner::default()
+    }
+
+    /// Feed one raw sample. Returns the range when it moved far enough from the
+    /// one returned last to be worth passing on.
+    pub fn observe(&mut self, x: u16, y: u16) -> Option<AxisLimits> {
+        match &mut self.axes {
+            Some((learned_x, learned_y)) => {
+                learned_x.observe(x);
+                learned_y.observe(y);
+            }
+            None => self.axes = Some((LearnedAxis::new(x), LearnedAxis::new(y))),
+        }
+
+        let limits = self.limits()?;
+        // Nothing reported yet: the range is worth passing on once it has some span
+        let reported = self.reported.unwrap_or(AxisLimits {
+            x: (limits.x.0, limits.x.0),
+            y: (limits.y.0, limits.y.0),
+        });
+        let moved = |(min, max): (u16, u16), (old_min, old_max): (u16, u16)| {
+            min.abs_diff(old_min) >= REPORT_STEP || max.abs_diff(old_max) >= REPORT_STEP
+        };
+        if !moved(limits.x, reported.x) && !moved(limits.y, reported.y) {
+            return None;
+        }
+        self.reported = Some(limits);
+        Some(limits)
+    }
+
+    /// The range so far, None before the first sample
+    pub fn limits(&self) -> Option<AxisLimits> {
+        let (x, y) = self.axes?;
+        Some(AxisLimits {
+            x: x.limits(),
+            y: y.limits(),
+        })
+    }
+}
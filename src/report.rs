@@ -0,0 +1,456 @@
+mod html;
+pub mod schema;
+mod validate;
+
+use color_eyre::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::machine_detect::get_computer_model;
+use crate::test_timer::format_elapsed;
+
+pub use schema::*;
+pub use validate::validate_file;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "json" => Some(ReportFormat::Json),
+            "html" => Some(ReportFormat::Html),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+impl ScreenResult {
+    pub fn title(&self) -> &'static str {
+        match self {
+            ScreenResult::Keyboard(_) => "Keyboard Test",
+            ScreenResult::Touchscreen(_) => "Touchscreen Test",
+            ScreenResult::Mouse(_) => "Mouse Test",
+            ScreenResult::Trackpad(_) => "Trackpad Test",
+        }
+    }
+
+    /// Power state during the test, None on units without power supply entries
+    pub fn power(&self) -> Option<&PowerResult> {
+        match self {
+            ScreenResult::Keyboard(k) => k.power.as_ref(),
+            ScreenResult::Touchscreen(t) => t.power.as_ref(),
+            ScreenResult::Mouse(m) => m.power.as_ref(),
+            ScreenResult::Trackpad(t) => t.power.as_ref(),
+        }
+    }
+
+    pub fn timing(&self) -> Option<&TestTiming> {
+        match self {
+            ScreenResult::Keyboard(k) => k.timing.as_ref(),
+            ScreenResult::Touchscreen(t) => t.timing.as_ref(),
+            ScreenResult::Mouse(m) => m.timing.as_ref(),
+            ScreenResult::Trackpad(t) => t.timing.as_ref(),
+        }
+    }
+
+    /// The test ran at least its minimum duration, true without timing
+    pub fn min_duration_met(&self) -> bool {
+        self.timing()
+            .is_none_or(|timing| timing.duration_ms >= timing.min_duration_ms)
+    }
+
+    /// Share of the test's targets reached, 0-1: keys pressed, cells touched,
+    /// mouse buttons clicked. None for tests without targets.
+    pub fn progress(&self) -> Option<f32> {
+        let share = |done: usize, total: usize| done.min(total) as f32 / total.max(1) as f32;
+        match self {
+            ScreenResult::Keyboard(k) if !k.excluded_keys.is_empty() => {
+                // Excluded keys are no target
+                let counted: Vec<&KeyResult> = k
+                    .rows
+                    .iter()
+                    .flatten()
+                    .filter(|key| key.excluded.is_none())
+                    .collect();
+                let pressed = counted.iter().filter(|key| key.complete).count();
+                Some(share(pressed, counted.len()))
+            }
+            ScreenResult::Keyboard(k) => Some(share(k.keys_pressed, k.keys_total)),
+            // Coverage isn't counted in raw mode
+            ScreenResult::Touchscreen(t) if t.raw_mode => None,
+            ScreenResult::Touchscreen(t) => Some(share(t.cells_touched, t.cells_total)),
+            ScreenResult::Mouse(m) => Some(share(
+                m.buttons_verified.count_ones() as usize,
+                MouseResult::BUTTONS as usize,
+            )),
+            ScreenResult::Trackpad(_) => None,
+        }
+    }
+
+    /// Every target reached, no check failed and the minimum duration run.
+    /// None for tests without targets.
+    pub fn passed(&self) -> Option<bool> {
+        let complete = self.progress()? >= 1.0;
+        Some(
+            complete
+                && self.min_duration_met()
+                && match self {
+                    ScreenResult::Keyboard(k) if !k.excluded_keys.is_empty() => {
+                        k.rows.iter().flatten().all(|key| {
+                            key.excluded.is_some()
+                                || !matches!(key.outcome.as_deref(), Some("missing" | "mismatched"))
+                        })
+                    }
+                    ScreenResult::Keyboard(k) => k
+                        .guided
+                        .as_ref()
+                        .is_none_or(|g| g.missing == 0 && g.mismatched == 0),
+                    ScreenResult::Touchscreen(t) => {
+                        t.corners.as_ref().is_none_or(|corners| corners.passed)
+                    }
+                    ScreenResult::Mouse(m) => m.drift.as_ref().is_none_or(|drift| drift.passed),
+                    ScreenResult::Trackpad(_) => true,
+                },
+        )
+    }
+
+    /// One line describing the outcome, for the report summary and the end-of-test status line
+    pub fn summary(&self) -> String {
+        let mut summary = self.outcome_summary();
+        if let Some(timing) = self.timing() {
+            let ran = format_elapsed(Duration::from_millis(timing.duration_ms));
+            summary.push_str(&format!(", ran {ran}"));
+            if !self.min_duration_met() {
+                let min = format_elapsed(Duration::from_millis(timing.min_duration_ms));
+                summary.push_str(&format!(" (UNDER the {min} minimum)"));
+            }
+        }
+        match self.power() {
+            Some(power) if power.changed => format!(
+                "{summary}, POWER CHANGED ({} to {})",
+                power.at_start, power.at_end
+            ),
+            _ => summary,
+        }
+    }
+
+    fn outcome_summary(&self) -> String {
+        match self {
+            ScreenResult::Keyboard(k) => {
+                // Keys that fired some of their codes, e.g. "7 (KEY_KP7)"
+                let partial: Vec<String> = k
+                    .rows
+                    .iter()
+                    .flatten()
+                    .filter(|key| key.presses > 0 && !key.complete)
+                    .map(|key| format!("{} ({})", key.label, key.missing.join(", ")))
+                    .collect();
+                let partial = if partial.is_empty() {
+                    String::new()
+                } else {
+                    format!(", missing codes: {}", partial.join(", "))
+                };
+                let guided = match &k.guided {
+                    Some(g) => format!(
+                        ", guided ({}): {} missing, {} mismatched",
+                        g.order, g.missing, g.mismatched
+                    ),
+                    None => String::new(),
+                };
+                let unexpected = if k.unexpected_keys.is_empty() {
+                    String::new()
+                } else {
+                    format!(", unexpected keys: {}", k.unexpected_keys.join(", "))
+                };
+                let stale = if k.stale_keys.is_empty() {
+                    String::new()
+                } else {
+                    format!(", stale keys: {}", k.stale_keys.join(", "))
+                };
+                let order = match &k.out_of_order {
+                    Some(keys) if keys.is_empty() => ", pressed in order".to_string(),
+                    Some(keys) => format!(", out of order: {}", keys.join(", ")),
+                    None => String::new(),
+                };
+                let repeat = if k.repeat_checks.is_empty() {
+                    String::new()
+                } else {
+                    format!(
+                        ", repeat {}/{} holds within tolerance",
+                        k.repeat_checks
+                            .iter()
+                            .filter(|check| check.within_tolerance)
+                            .count(),
+                        k.repeat_checks.len()
+                    )
+                };
+                let simultaneous = match k.simultaneous.iter().map(|s| s.max_held).max() {
+                    Some(max) if max > 1 => format!(", max {max} keys held at once"),
+                    _ => String::new(),
+                };
+                let ghosting = if k.ghost_suspects.is_empty() {
+                    String::new()
+                } else {
+                    format!(", {} possible ghost keys", k.ghost_suspects.len())
+                };
+                let excluded = if k.excluded_keys.is_empty() {
+                    String::new()
+                } else {
+                    let keys: Vec<String> = k
+                        .excluded_keys
+                        .iter()
+                        .map(|key| format!("{} ({})", key.label, key.reason))
+                        .collect();
+                    format!(", excluded: {}", keys.join(", "))
+                };
+                let other: u64 = k.other_keyboards.iter().map(|other| other.events).sum();
+                let other = if other > 0 {
+                    format!(", {other} key events from other keyboards")
+                } else {
+                    String::new()
+                };
+                let comparison = match &k.comparison {
+                    Some(c) => format!(
+                        ", A/B ({} / {}): {} only on A, {} only on B",
+                        c.a.device,
+                        c.b.device,
+                        c.only_a.len(),
+                        c.only_b.len()
+                    ),
+                    None => String::new(),
+                };
+                format!(
+                    "{}: {}/{} keys pressed, {} presses{}{}{}{}{}{}{}{}{}{}{}",
+                    k.layout,
+                    k.keys_pressed,
+                    k.keys_total,
+                    k.total_presses,
+                    partial,
+                    excluded,
+                    guided,
+                    unexpected,
+                    stale,
+                    order,
+                    repeat,
+                    simultaneous,
+                    ghosting,
+                    other,
+                    comparison
+                )
+            }
+            ScreenResult::Touchscreen(t) => {
+                let max_jump = match t.max_jump_mm {
+                    Some(mm) if t.total_jumps > 0 => format!(" (max {mm:.1} mm)"),
+                    _ => String::new(),
+                };
+                let saturation: String = t
+                    .saturation
+                    .iter()
+                    .map(|s| format!(", {} saturated", s.edge))
+                    .collect();
+                let sessions = if t.session > 1 {
+                    format!(" (session {})", t.session)
+                } else {
+                    String::new()
+                };
+                let soak = match &t.soak {
+                    Some(soak) => format!(
+                        ", soak of {} min in {} snapshots",
+                        soak.elapsed_secs / 60,
+                        soak.snapshots.len()
+                    ),
+                    None => String::new(),
+                };
+                let corners = match &t.corners {
+                    Some(corners) if corners.passed => {
+                        format!(", {} corners touched", corners.targets.len())
+                    }
+                    Some(corners) => {
+                        let missed: Vec<&str> = corners
+                            .targets
+                            .iter()
+                            .filter(|target| !target.touched)
+                            .map(|target| target.name.as_str())
+                            .collect();
+                        format!(", CORNERS MISSED ({})", missed.join(", "))
+                    }
+                    None => String::new(),
+                };
+                let stabilized = match t.stabilizer.as_deref() {
+                    Some(stabilizer) => {
+                        format!(" ({} after {})", stabilizer.jumps, stabilizer.filter)
+                    }
+                    None => String::new(),
+                };
+                let comparison = match t.comparison.as_deref() {
+                    Some([_, compared]) => {
+                        format!(", {} jumps on {}", compared.jumps, compared.device)
+                    }
+                    _ => String::new(),
+                };
+                // Only a swept link is worth a mention, it means a misconfigured controller
+                let serial = match &t.serial {
+                    Some(serial) if serial.swept => {
+                        format!(", serial swept to {} baud", serial.baud)
+                    }
+                    _ => String::new(),
+                };
+                let wake = match &t.wake {
+                    Some(wake) if wake.flagged => ", FIRST TOUCH AFTER IDLE LOSES SAMPLES",
+                    _ => "",
+                };
+                let hover = match t.hover.as_deref() {
+                    Some(hover) if hover.flagged => format!(
+                        ", PEN HOVER-TO-TOUCH OFFSET over the threshold on {} pen-downs",
+                        hover.over_threshold
+                    ),
+                    _ => String::new(),
+                };
+                let drops = match t.syn_drops {
+                    0 => String::new(),
+                    drops => format!(", events dropped {drops} times"),
+                };
+                let coverage = if t.raw_mode {
+                    "RAW MODE (uncalibrated, raw units)".to_string()
+                } else {
+                    format!("{}/{} cells touched", t.cells_touched, t.cells_total)
+                };
+                let roi = match t.roi {
+                    Some([left, top, right, bottom]) => format!(
+                        " in region {left:.2},{top:.2}-{right:.2},{bottom:.2}, {} masked-area touches",
+                        t.masked_touches
+                    ),
+                    None => String::new(),
+                };
+                format!(
+                    "{} samples, {} jumps{}{}{}, {}{}{}{}{}{}{}{}{}{}",
+                    t.total_samples,
+                    t.total_jumps,
+                    stabilized,
+                    max_jump,
+                    comparison,
+                    coverage,
+                    roi,
+                    corners,
+                    saturation,
+                    serial,
+                    wake,
+                    hover,
+                    drops,
+                    sessions,
+                    soak
+                )
+            }
+            ScreenResult::Mouse(m) => {
+                let drift = match &m.drift {
+                    Some(drift) if drift.passed => "no drift",
+                    Some(_) => "DRIFTING",
+                    None => "drift not checked",
+                };
+                format!(
+                    "{} events, distance {:.0}, buttons {}, {}",
+                    m.event_count,
+                    m.total_distance,
+                    MouseResult::button_names(m.buttons_verified),
+                    drift
+                )
+            }
+            ScreenResult::Trackpad(t) => format!(
+                "max {} fingers, {} clicks, {} taps, {} palm events, {} stuck slots",
+                t.max_fingers, t.clicks, t.taps, t.palm_events, t.cleared_slots
+            ),
+        }
+    }
+}
+
+impl Default for SessionReport {
+    fn default() -> Self {
+        SessionReport::new()
+    }
+}
+
+impl SessionReport {
+    pub fn new() -> Self {
+        SessionReport {
+            schema_version: SCHEMA_VERSION,
+            tool_version: env!("CARGO_PKG_VERSION"),
+            computer_model: format!("{:?}", get_computer_model()),
+            metadata: SessionMetadata::default(),
+            demo: false,
+            started_at: unix_time_secs(),
+            uptime_secs: 0,
+            results: Vec::new(),
+            devices: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, result: ScreenResult) {
+        self.results.push(result);
+    }
+
+    /// Continue an earlier session: its details and results become this one's
+    pub fn restore(&mut self, saved: SessionReport) {
+        self.metadata = saved.metadata;
+        self.started_at = saved.started_at;
+        self.results = saved.results;
+        for device in saved.devices {
+            self.add_device(device);
+        }
+    }
+
+    /// Whether the device at `path` is listed already
+    pub fn has_device(&self, path: &str) -> bool {
+        self.devices.iter().any(|device| device.path == path)
+    }
+
+    /// List a device of the session, once per path
+    pub fn add_device(&mut self, device: DeviceResult) {
+        if !self.has_device(&device.path) {
+            self.devices.push(device);
+        }
+    }
+
+    /// Overwrite an earlier result, e.g. with the newer state of the same screen
+    pub fn replace(&mut self, index: usize, result: ScreenResult) {
+        match self.results.get_mut(index) {
+            Some(slot) => *slot = result,
+            None => self.results.push(result),
+        }
+    }
+
+    /// Write the report once per requested format, returning the written paths
+    pub fn write(&self, base: &Path, formats: &[ReportFormat]) -> Result<Vec<PathBuf>> {
+        let mut written = Vec::new();
+
+        for format in formats {
+            let path = base.with_extension(format.extension());
+            let contents = match format {
+                ReportFormat::Json => serde_json::to_string_pretty(self)?,
+                ReportFormat::Html => html::render(self),
+            };
+            fs::write(&path, contents)?;
+            written.push(path);
+        }
+
+        Ok(written)
+    }
+}
+
+fn unix_time_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,944 @@
+//! Single-file HTML rendering of a `SessionReport`, meant to be opened in a
+//! browser on the spot. Everything is inlined, no external assets.
+
+use std::fmt::Write;
+use std::time::Duration;
+
+use ratatui::style::Color;
+
+use crate::press_colors::PressPalette;
+use crate::test_timer::format_elapsed;
+use crate::units::format_distance;
+
+use super::{
+    DeviceResult, KeyResult, KeyboardResult, MouseResult, PowerResult, ScreenResult, SessionReport,
+    SoakResult, TestTiming, TouchscreenResult, TrackpadResult,
+};
+
+const STYLE: &str = "
+body { font-family: sans-serif; margin: 2em; background: #fafafa; color: #222; }
+h1, h2 { color: #00838f; }
+table { border-collapse: collapse; margin-bottom: 1em; }
+td, th { border: 1px solid #999; padding: 4px 8px; text-align: left; }
+table.heat td { text-align: center; min-width: 2.5em; }
+td.untouched { background: #fff; color: #999; }
+td.partial { background: #fff; color: #000; border: 2px dashed #f44336; }
+span.legend { padding: 2px 6px; color: #000; }
+td.excluded { background: repeating-linear-gradient(45deg, #eee, #eee 4px, #ccc 4px, #ccc 8px); color: #666; }
+svg { border: 1px solid #999; background: #111; }
+";
+
+pub fn render(report: &SessionReport) -> String {
+    let mut out = String::new();
+
+    out.push_str("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\">");
+    let _ = write!(
+        out,
+        "<title>Input Diagnostics Report</title><style>{STYLE}</style></head><body>"
+    );
+
+    out.push_str("<h1>Input Diagnostics Report</h1>");
+    if report.demo {
+        out.push_str(
+            "<p style=\"color:#fff;background:#c62828;padding:8px\"><b>DEMO MODE</b> — \
+             simulated devices, not a hardware result</p>",
+        );
+    }
+    out.push_str("<table>");
+    row(&mut out, "Tool version", report.tool_version);
+    row(&mut out, "Computer model", &report.computer_model);
+    if let Some(operator) = &report.metadata.operator_id {
+        row(&mut out, "Operator", operator);
+    }
+    if let Some(serial) = &report.metadata.unit_serial {
+        row(&mut out, "Unit serial", serial);
+    }
+    row(
+        &mut out,
+        "Started (unix time)",
+        &report.started_at.to_string(),
+    );
+    if report.uptime_secs > 0 {
+        row(
+            &mut out,
+            "Tool uptime",
+            &format_elapsed(Duration::from_secs(report.uptime_secs)),
+        );
+    }
+    out.push_str("</table>");
+
+    // Summary table, one row per test
+    out.push_str("<h2>Summary</h2><table><tr><th>Test</th><th>Result</th></tr>");
+    for result in &report.results {
+        let _ = write!(
+            out,
+            "<tr><td>{}</td><td>{}</td></tr>",
+            result.title(),
+            escape(&result.summary())
+        );
+    }
+    out.push_str("</table>");
+
+    for result in &report.results {
+        match result {
+            ScreenResult::Keyboard(keyboard) => render_keyboard(&mut out, keyboard),
+            ScreenResult::Touchscreen(touch) => render_touchscreen(&mut out, touch),
+            ScreenResult::Mouse(mouse) => render_mouse(&mut out, mouse),
+            ScreenResult::Trackpad(trackpad) => render_trackpad(&mut out, trackpad),
+        }
+    }
+
+    render_devices(&mut out, &report.devices);
+
+    out.push_str("</body></html>\n");
+    out
+}
+
+fn render_keyboard(out: &mut String, keyboard: &KeyboardResult) {
+    // The on-screen test's default ramp, counts past its end stay at the top
+    let palette = PressPalette::default();
+    let _ = write!(
+        out,
+        "<h2>Keyboard Test — {}</h2><table class=\"heat\">",
+        escape(&keyboard.layout)
+    );
+
+    for keys in &keyboard.rows {
+        out.push_str("<tr>");
+        for key in keys {
+            render_key(out, key, palette);
+        }
+        out.push_str("</tr>");
+    }
+    out.push_str("</table><p>Presses: ");
+    for (label, color) in palette.legend() {
+        let _ = write!(
+            out,
+            "<span class=\"legend\" style=\"background:{}\">{label}</span> ",
+            css_color(color)
+        );
+    }
+    out.push_str("higher counts are shown on the key</p>");
+
+    out.push_str("<table>");
+    row(
+        out,
+        "Keys pressed",
+        &format!("{} / {}", keyboard.keys_pressed, keyboard.keys_total),
+    );
+    row(out, "Total presses", &keyboard.total_presses.to_string());
+    row(out, "Peak keys/s", &keyboard.peak_keys_per_sec.to_string());
+    if !keyboard.unexpected_keys.is_empty() {
+        row(out, "Unexpected keys", &keyboard.unexpected_keys.join(", "));
+    }
+    if !keyboard.phantom_keys.is_empty() {
+        row(
+            out,
+            "Phantom keys (quirk)",
+            &keyboard.phantom_keys.join(", "),
+        );
+    }
+    if let Some(out_of_order) = &keyboard.out_of_order {
+        row(out, "Press order", &keyboard.press_order.join(" "));
+        row(
+            out,
+            "Out of order",
+            &if out_of_order.is_empty() {
+                "none, reading order followed".to_string()
+            } else {
+                out_of_order.join(", ")
+            },
+        );
+    }
+    if !keyboard.stale_keys.is_empty() {
+        row(
+            out,
+            &format!("Stale keys (> {} s)", keyboard.stale_window_secs),
+            &keyboard.stale_keys.join(", "),
+        );
+    }
+    for simultaneous in &keyboard.simultaneous {
+        let mut value = format!(
+            "{} ({})",
+            simultaneous.max_held,
+            simultaneous.keys.join(" ")
+        );
+        if !simultaneous.phantom_keys.is_empty() {
+            value.push_str(&format!(
+                ", phantoms meanwhile: {}",
+                simultaneous.phantom_keys.join(", ")
+            ));
+        }
+        if !simultaneous.stuck_keys.is_empty() {
+            value.push_str(&format!(
+                ", left out as stuck: {}",
+                simultaneous.stuck_keys.join(", ")
+            ));
+        }
+        row(
+            out,
+            &format!("Max simultaneous keys, {}", simultaneous.device),
+            &value,
+        );
+    }
+    for suspect in &keyboard.ghost_suspects {
+        row(
+            out,
+            &format!("Possible ghosting, {}", suspect.device),
+            &format!(
+                "{} with {} held ({}x)",
+                suspect.phantom,
+                suspect.held.join(" "),
+                suspect.count
+            ),
+        );
+    }
+    for excluded in &keyboard.excluded_keys {
+        let source = if excluded.preseeded {
+            " (from the config)"
+        } else {
+            ""
+        };
+        row(
+            out,
+            &format!("Excluded key, {}", excluded.label),
+            &format!("{}{source}", excluded.reason),
+        );
+    }
+    if let Some(tested) = &keyboard.tested_keyboard {
+        row(out, "Tested keyboard", tested);
+    }
+    if let Some(comparison) = &keyboard.comparison {
+        for (name, side) in [("A", &comparison.a), ("B", &comparison.b)] {
+            row(
+                out,
+                &format!("Compared keyboard {name}, {}", side.device),
+                &format!(
+                    "{} / {} keys, {} presses{}",
+                    side.keys_pressed,
+                    side.keys_total,
+                    side.total_presses,
+                    if side.not_pressed.is_empty() {
+                        String::new()
+                    } else {
+                        format!(", not pressed: {}", side.not_pressed.join(" "))
+                    }
+                ),
+            );
+        }
+        let keys = |keys: &[String]| {
+            if keys.is_empty() {
+                "none".to_string()
+            } else {
+                keys.join(" ")
+            }
+        };
+        row(out, "Pressed on A but not B", &keys(&comparison.only_a));
+        row(out, "Pressed on B but not A", &keys(&comparison.only_b));
+    }
+    for other in &keyboard.other_keyboards {
+        row(
+            out,
+            &format!("Other keyboard, {}", other.device),
+            &format!("{} events, last {}", other.events, other.last_key),
+        );
+    }
+    for quirk in &keyboard.quirks {
+        row(out, "Known quirk", quirk);
+    }
+    if let Some(guided) = &keyboard.guided {
+        row(out, "Guided order", &guided.order);
+        row(
+            out,
+            "Guided outcome",
+            &format!(
+                "{} passed, {} missing, {} mismatched",
+                guided.passed, guided.missing, guided.mismatched
+            ),
+        );
+    }
+    power_row(out, keyboard.power.as_ref());
+    timing_row(out, keyboard.timing.as_ref());
+    out.push_str("</table>");
+
+    if keyboard.repeat_checks.is_empty() {
+        return;
+    }
+    out.push_str(
+        "<table><tr><th>Keyboard</th><th>Key</th><th>Configured</th><th>Measured</th>\
+         <th>Result</th></tr>",
+    );
+    for check in &keyboard.repeat_checks {
+        let _ = write!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{} ms, every {} ms</td><td>{} ms, every {} ms</td>\
+             <td>{}</td></tr>",
+            escape(&check.device),
+            escape(&check.key),
+            check.configured_delay_ms,
+            check.configured_period_ms,
+            check.measured_delay_ms,
+            check.measured_period_ms,
+            if check.within_tolerance {
+                "within tolerance"
+            } else {
+                "OFF"
+            }
+        );
+    }
+    out.push_str("</table>");
+}
+
+/// One cell of the heat map
+fn render_key(out: &mut String, key: &KeyResult, palette: PressPalette) {
+    let title = escape(&key.codes.join(", "));
+    if let Some(reason) = &key.excluded {
+        let _ = write!(
+            out,
+            "<td class=\"excluded\" title=\"{title}\">{}<br><small>{}</small></td>",
+            escape(&key.label),
+            escape(reason)
+        );
+    } else if key.presses > 0 && !key.complete {
+        let _ = write!(
+            out,
+            "<td class=\"partial\" title=\"{title}\">{}<br><small>missing {}</small></td>",
+            escape(&key.label),
+            escape(&key.missing.join(", "))
+        );
+    } else if key.presses == 0 {
+        let _ = write!(
+            out,
+            "<td class=\"untouched\" title=\"{title}\">{}</td>",
+            escape(&key.label)
+        );
+    } else {
+        let color = css_color(palette.color(key.presses));
+        // The color stops telling counts apart at the top of the ramp
+        let count = if palette.is_clamped(key.presses) {
+            format!("<br><small>{}</small>", key.presses)
+        } else {
+            String::new()
+        };
+        let _ = write!(
+            out,
+            "<td style=\"background:{color}\" title=\"{title}: {} presses\">{}{count}</td>",
+            key.presses,
+            escape(&key.label)
+        );
+    }
+}
+
+fn render_touchscreen(out: &mut String, touch: &TouchscreenResult) {
+    if touch.dropped_sessions > 0 {
+        out.push_str("<h2>Touchscreen Test</h2><table>");
+        row(
+            out,
+            "Dropped sessions",
+            &format!("{} earliest, not kept", touch.dropped_sessions),
+        );
+        out.push_str("</table>");
+    }
+    for session in &touch.earlier_sessions {
+        render_touchscreen_session(out, session);
+    }
+    render_touchscreen_session(out, touch);
+}
+
+fn render_touchscreen_session(out: &mut String, touch: &TouchscreenResult) {
+    if touch.session > 1 {
+        let _ = write!(out, "<h2>Touchscreen Test, session {}</h2>", touch.session);
+    } else {
+        out.push_str("<h2>Touchscreen Test</h2>");
+    }
+
+    // Touch trail, calibrated space is 0..=999 on both axes
+    out.push_str(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 1000 1000\" width=\"500\" height=\"500\">",
+    );
+    for path in &touch.paths {
+        if let [(x, y)] = path.as_slice() {
+            let _ = write!(
+                out,
+                "<circle cx=\"{x}\" cy=\"{y}\" r=\"4\" fill=\"#ffeb3b\"/>"
+            );
+            continue;
+        }
+        out.push_str("<polyline fill=\"none\" stroke=\"#ffeb3b\" stroke-width=\"3\" points=\"");
+        for (x, y) in path {
+            let _ = write!(out, "{x},{y} ");
+        }
+        out.push_str("\"/>");
+    }
+    out.push_str("</svg>");
+
+    let fmt_opt = |v: Option<f32>| v.map_or("-".to_string(), |v| format!("{v:.0}"));
+
+    out.push_str("<table>");
+    row(
+        out,
+        "Device",
+        touch.device.as_deref().unwrap_or("(unknown)"),
+    );
+    if touch.raw_mode {
+        row(
+            out,
+            "Mode",
+            "RAW MODE, uncalibrated, distances in raw units",
+        );
+    }
+    row(out, "Samples", &touch.total_samples.to_string());
+    row(out, "Jumps", &touch.total_jumps.to_string());
+    row(
+        out,
+        "Max jump",
+        &format_distance(touch.max_jump, touch.max_jump_mm),
+    );
+    row(
+        out,
+        "Jump threshold",
+        &format_distance(touch.jump_threshold, touch.jump_threshold_mm),
+    );
+    if let Some(stabilizer) = touch.stabilizer.as_deref() {
+        let filter = match stabilizer.alpha {
+            Some(alpha) => format!("{} (alpha {alpha:.2})", stabilizer.filter),
+            None => stabilizer.filter.clone(),
+        };
+        row(out, "Stabilizer", &filter);
+        row(
+            out,
+            "Jumps after the stabilizer",
+            &format!(
+                "{} of {} (max {:.1})",
+                stabilizer.jumps, touch.total_jumps, stabilizer.max_jump
+            ),
+        );
+    }
+    row(
+        out,
+        "Trail view",
+        if touch.true_aspect {
+            "true panel aspect"
+        } else {
+            "fill terminal"
+        },
+    );
+    row(
+        out,
+        "Max speed",
+        &match touch.max_speed_mm {
+            Some(mm) => format!("{:.0}/s ({mm:.1} mm/s)", touch.max_speed),
+            None => format!("{:.0}/s", touch.max_speed),
+        },
+    );
+    row(
+        out,
+        "Max acceleration",
+        &match touch.max_acceleration_mm {
+            Some(mm) => format!("{:.0}/s² ({mm:.0} mm/s²)", touch.max_acceleration),
+            None => format!("{:.0}/s²", touch.max_acceleration),
+        },
+    );
+    row(out, "Min pinch", &fmt_opt(touch.min_pinch));
+    row(out, "Max pinch", &fmt_opt(touch.max_pinch));
+    row(
+        out,
+        "Pressure threshold",
+        &touch
+            .pressure_threshold
+            .map_or("-".to_string(), |t| t.to_string()),
+    );
+    if !touch.raw_mode {
+        row(
+            out,
+            "Cells touched",
+            &format!("{} / {}", touch.cells_touched, touch.cells_total),
+        );
+    }
+    if let Some([left, top, right, bottom]) = touch.roi {
+        row(
+            out,
+            "Region of interest",
+            &format!("{left:.2}, {top:.2} - {right:.2}, {bottom:.2} of the screen"),
+        );
+        row(
+            out,
+            "Masked-area touches",
+            &touch.masked_touches.to_string(),
+        );
+    }
+    if let Some(corners) = &touch.corners {
+        let margin = match corners.margin_mm {
+            Some(mm) => format!("{mm:.1} mm"),
+            None => format!(
+                "{:.0} x {:.0} units",
+                corners.margin_units.0, corners.margin_units.1
+            ),
+        };
+        let targets: Vec<String> = corners
+            .targets
+            .iter()
+            .map(|target| {
+                let state = if target.touched { "ok" } else { "MISSED" };
+                format!("{} {state}", target.name)
+            })
+            .collect();
+        row(
+            out,
+            &format!("Corners (within {margin})"),
+            &targets.join(", "),
+        );
+    }
+    for (label, stats) in ["Device A", "Device B"]
+        .iter()
+        .zip(touch.comparison.iter().flatten())
+    {
+        let optional = |value: Option<f32>, unit: &str| {
+            value.map_or("-".to_string(), |value| format!("{value:.1}{unit}"))
+        };
+        let value = if stats.calibrated {
+            format!(
+                "{}: {} samples at {}, {} jumps, jitter {}",
+                stats.device,
+                stats.samples,
+                optional(stats.rate_hz, "/s"),
+                stats.jumps,
+                stats.jitter_text()
+            )
+        } else {
+            format!("{}: not calibrated", stats.device)
+        };
+        row(out, label, &value);
+    }
+    if let Some(serial) = &touch.serial {
+        let how = if serial.swept {
+            "found by sweep"
+        } else {
+            "as configured"
+        };
+        row(
+            out,
+            "Serial link",
+            &format!(
+                "{} baud {how}, {:.0}% valid frames",
+                serial.baud,
+                serial.valid_ratio * 100.0
+            ),
+        );
+    }
+    if touch.syn_drops > 0 {
+        row(
+            out,
+            "Dropped events",
+            &format!("{} kernel buffer overflows", touch.syn_drops),
+        );
+    }
+    if let Some(wake) = &touch.wake
+        && wake.wake_touches > 0
+    {
+        let worst = wake.worst_wake_ms.map_or_else(
+            || "no second sample".to_string(),
+            |ms| format!("worst {ms} ms"),
+        );
+        let verdict = if wake.flagged {
+            ", FIRST SAMPLES LOST"
+        } else {
+            ""
+        };
+        row(
+            out,
+            "Touches after idle",
+            &format!(
+                "{} after {} s idle, {worst} to the second sample, {} lost their start{verdict}",
+                wake.wake_touches,
+                wake.idle_gap_ms / 1000,
+                wake.wake_missed_starts
+            ),
+        );
+        // Bucket by bucket, wake touches against the others
+        let mut lower = 0;
+        let buckets: Vec<String> = wake
+            .wake_histogram
+            .iter()
+            .zip(&wake.normal_histogram)
+            .enumerate()
+            .map(|(i, (wakes, normal))| {
+                let range = match wake.bucket_bounds_ms.get(i) {
+                    Some(&upper) => format!("{lower}-{upper} ms"),
+                    None => format!("{lower}+ ms"),
+                };
+                lower = wake.bucket_bounds_ms.get(i).copied().unwrap_or(lower);
+                format!("{range}: {wakes}/{normal}")
+            })
+            .collect();
+        row(out, "Second sample after idle/other", &buckets.join(", "));
+    }
+    row(
+        out,
+        "Hover-to-touch offset",
+        &match touch.hover.as_deref() {
+            None => "n/a, no pen hover reported".to_string(),
+            Some(hover) if hover.pen_downs == 0 => "no pen-down after a hover".to_string(),
+            Some(hover) => {
+                let offset = |units: Option<f32>, mm: Option<f32>| {
+                    units.map_or("-".to_string(), |units| format_distance(units, mm))
+                };
+                let verdict = if hover.flagged {
+                    // The threshold the offsets were held against
+                    let threshold = if hover.max_mm.is_some() {
+                        format!("{:.1} mm", hover.threshold_mm)
+                    } else {
+                        format!("{:.0}", hover.threshold)
+                    };
+                    format!(", {} OVER {threshold}", hover.over_threshold)
+                } else {
+                    String::new()
+                };
+                format!(
+                    "min {}, avg {}, max {} over {} pen-downs{verdict}",
+                    offset(hover.min, hover.min_mm),
+                    offset(hover.avg, hover.avg_mm),
+                    offset(hover.max, hover.max_mm),
+                    hover.pen_downs
+                )
+            }
+        },
+    );
+    row(
+        out,
+        "Saturated edges",
+        &if touch.saturation.is_empty() {
+            "none detected".to_string()
+        } else {
+            touch
+                .saturation
+                .iter()
+                .map(|s| format!("{} ({} samples)", s.edge, s.samples))
+                .collect::<Vec<_>>()
+                .join(", ")
+        },
+    );
+    let evicted = &touch.evicted_strokes;
+    row(
+        out,
+        "Strokes",
+        &(touch.strokes.len() as u32 + evicted.strokes).to_string(),
+    );
+    if evicted.strokes > 0 {
+        row(
+            out,
+            "Older strokes (totals only)",
+            &format!(
+                "{} strokes, {} samples, length {:.0}, {} ms",
+                evicted.strokes, evicted.samples, evicted.length, evicted.duration_ms
+            ),
+        );
+    }
+    row(
+        out,
+        "Duplicate touch devices",
+        &touch
+            .duplicate_devices
+            .as_ref()
+            .map_or("none detected".to_string(), |(a, b)| format!("{a} and {b}")),
+    );
+    power_row(out, touch.power.as_ref());
+    timing_row(out, touch.timing.as_ref());
+    out.push_str("</table>");
+
+    if let Some(soak) = &touch.soak {
+        render_soak(out, soak);
+    }
+
+    if touch.strokes.is_empty() {
+        return;
+    }
+
+    out.push_str(
+        "<table><tr><th>#</th><th>Samples</th><th>Duration (ms)</th><th>Length</th>\
+         <th>Avg speed (/s)</th><th>Max speed (/s)</th><th>Max accel (/s²)</th>\
+         <th>Bounding box</th></tr>",
+    );
+    // Numbered across the whole session, evicted strokes came first
+    let first = evicted.strokes as usize + 1;
+    for (i, stroke) in touch.strokes.iter().enumerate() {
+        let (min_x, min_y, max_x, max_y) = stroke.bounding_box;
+        let _ = write!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.0}</td><td>{:.0}</td><td>{:.0}</td>\
+             <td>{:.0}</td><td>({min_x},{min_y})-({max_x},{max_y})</td></tr>",
+            first + i,
+            stroke.samples,
+            stroke.duration_ms,
+            stroke.length,
+            stroke.average_speed,
+            stroke.max_speed,
+            stroke.max_acceleration
+        );
+    }
+    out.push_str("</table>");
+}
+
+fn render_soak(out: &mut String, soak: &SoakResult) {
+    let _ = write!(
+        out,
+        "<h3>Soak, {} min in {} min intervals</h3>",
+        soak.elapsed_secs / 60,
+        soak.interval_secs / 60
+    );
+    out.push_str(
+        "<table><tr><th>Time (unix ms)</th><th>Elapsed (min)</th><th>Samples</th><th>Samples/s</th>\
+         <th>Jumps</th><th>Saturated</th></tr>",
+    );
+    for snapshot in &soak.snapshots {
+        let _ = write!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{:.1}</td><td>{}</td><td>{}</td></tr>",
+            snapshot.unix_ms,
+            snapshot.elapsed_secs / 60,
+            snapshot.samples,
+            snapshot.sample_rate,
+            snapshot.jumps,
+            snapshot.saturated_samples
+        );
+    }
+    out.push_str("</table>");
+}
+
+fn render_mouse(out: &mut String, mouse: &MouseResult) {
+    out.push_str("<h2>Mouse Test</h2><table>");
+    row(out, "Events", &mouse.event_count.to_string());
+    row(
+        out,
+        "Distance (counts)",
+        &format!("{:.0}", mouse.total_distance),
+    );
+    row(
+        out,
+        "Buttons verified",
+        &MouseResult::button_names(mouse.buttons_verified),
+    );
+    row(out, "Longest gap (ms)", &mouse.max_gap_ms.to_string());
+    let drift = match &mouse.drift {
+        Some(drift) => format!(
+            "{} ({} events, distance {:.0} in {} ms)",
+            if drift.passed { "passed" } else { "FAILED" },
+            drift.events,
+            drift.distance,
+            drift.duration_ms
+        ),
+        None => "not checked".to_string(),
+    };
+    row(out, "Drift check", &drift);
+    row(out, "Sensitivity", &format!("{:.1}x", mouse.sensitivity));
+    row(
+        out,
+        "Start position",
+        if mouse.start_centered {
+            "centered"
+        } else {
+            "top left"
+        },
+    );
+    row(out, "Trail length", &mouse.trail_length.to_string());
+    power_row(out, mouse.power.as_ref());
+    timing_row(out, mouse.timing.as_ref());
+    out.push_str("</table>");
+}
+
+fn render_trackpad(out: &mut String, trackpad: &TrackpadResult) {
+    out.push_str("<h2>Trackpad Test</h2><table>");
+    row(
+        out,
+        "Device",
+        trackpad.device.as_deref().unwrap_or("(unknown)"),
+    );
+    row(out, "Max fingers", &trackpad.max_fingers.to_string());
+    row(out, "Clicks", &trackpad.clicks.to_string());
+    row(out, "Taps", &trackpad.taps.to_string());
+    row(out, "Palm events", &trackpad.palm_events.to_string());
+    row(
+        out,
+        "Stuck slots cleared",
+        &trackpad.cleared_slots.to_string(),
+    );
+    let slots: Vec<String> = trackpad
+        .slot_samples
+        .iter()
+        .enumerate()
+        .filter(|(_, samples)| **samples > 0)
+        .map(|(slot, samples)| format!("{slot}: {samples}"))
+        .collect();
+    row(out, "Samples per slot", &slots.join(", "));
+    power_row(out, trackpad.power.as_ref());
+    timing_row(out, trackpad.timing.as_ref());
+    out.push_str("</table>");
+}
+
+fn render_devices(out: &mut String, devices: &[DeviceResult]) {
+    if devices.is_empty() {
+        return;
+    }
+    out.push_str(
+        "<h2>Devices</h2><table><tr><th>Device</th><th>ID</th><th>Bus</th>\
+         <th>Driver</th><th>Version</th><th>Uniq</th><th>USB</th></tr>",
+    );
+    let or_dash = |value: &Option<String>| escape(value.as_deref().unwrap_or("-"));
+    for device in devices {
+        let usb = device.usb.as_ref().map_or("-".to_string(), |usb| {
+            let parts: Vec<String> = [
+                usb.manufacturer.clone(),
+                usb.product.clone(),
+                usb.serial.as_ref().map(|serial| format!("serial {serial}")),
+                usb.bcd_device
+                    .as_ref()
+                    .map(|bcd| format!("bcdDevice {bcd}")),
+            ]
+            .into_iter()
+            .flatten()
+            .collect();
+            escape(&parts.join(", "))
+        });
+        let _ = write!(
+            out,
+            "<tr><td>{} ({})</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&device.name),
+            escape(&device.path),
+            escape(&device.id),
+            escape(&device.bus),
+            or_dash(&device.driver),
+            or_dash(&device.version),
+            or_dash(&device.uniq),
+            usb
+        );
+    }
+    out.push_str("</table>");
+}
+
+fn power_row(out: &mut String, power: Option<&PowerResult>) {
+    let Some(power) = power else {
+        return;
+    };
+    if power.changed {
+        row(
+            out,
+            "Power",
+            &format!(
+                "CHANGED: {} at start, {} at end",
+                power.at_start, power.at_end
+            ),
+        );
+    } else {
+        row(out, "Power", &power.at_start);
+    }
+}
+
+fn timing_row(out: &mut String, timing: Option<&TestTiming>) {
+    let Some(timing) = timing else {
+        return;
+    };
+    let mut value = format_elapsed(Duration::from_millis(timing.duration_ms));
+    if timing.min_duration_ms > 0 {
+        let min = format_elapsed(Duration::from_millis(timing.min_duration_ms));
+        if timing.duration_ms < timing.min_duration_ms {
+            value.push_str(&format!(", UNDER the {min} minimum"));
+        } else {
+            value.push_str(&format!(", minimum {min}"));
+        }
+    }
+    value.push_str(&format!(
+        " (unix ms {} to {})",
+        timing.started_at_ms, timing.ended_at_ms
+    ));
+    row(out, "Duration", &value);
+}
+
+fn row(out: &mut String, name: &str, value: &str) {
+    let _ = write!(
+        out,
+        "<tr><th>{}</th><td>{}</td></tr>",
+        escape(name),
+        escape(value)
+    );
+}
+
+/// `color` as a CSS value. The press ramp is all RGB, the named colors only
+/// come from the cycling palette.
+fn css_color(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        other => other.to_string().to_lowercase(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(presses: usize) -> KeyResult {
+        KeyResult {
+            label: "A".into(),
+            codes: vec!["KEY_A".into()],
+            presses,
+            complete: presses > 0,
+            missing: Vec::new(),
+            attempts: None,
+            outcome: None,
+            first_seen_ms: None,
+            last_seen_ms: None,
+            excluded: None,
+        }
+    }
+
+    fn cell(presses: usize) -> String {
+        let mut out = String::new();
+        render_key(&mut out, &key(presses), PressPalette::default());
+        out
+    }
+
+    fn background(cell: &str) -> &str {
+        let start = cell.find("background:").unwrap() + "background:".len();
+        &cell[start..start + 7]
+    }
+
+    #[test]
+    fn heavily_pressed_keys_never_look_fresh() {
+        // The old five-color palette came back to green at six presses
+        assert_ne!(background(&cell(6)), background(&cell(1)));
+        let top = background(&cell(10)).to_string();
+        for presses in [11, 50, 1000] {
+            assert_eq!(background(&cell(presses)), top);
+        }
+    }
+
+    #[test]
+    fn counts_are_printed_past_the_ramp() {
+        assert!(!cell(10).contains("<small>"));
+        assert!(cell(11).contains("<small>11</small>"));
+        assert!(cell(3).contains("title=\"KEY_A: 3 presses\""));
+    }
+
+    #[test]
+    fn colors_match_the_on_screen_ramp() {
+        let palette = PressPalette::default();
+        let Color::Rgb(r, g, b) = palette.color(4) else {
+            panic!("the ramp is RGB");
+        };
+        assert_eq!(background(&cell(4)), format!("#{r:02x}{g:02x}{b:02x}"));
+    }
+}
@@ -0,0 +1,680 @@
+//! The JSON report as consumed by the QA database importer. Every type here is
+//! part of the format: renaming or removing a field, or changing its meaning,
+//! needs a new `SCHEMA_VERSION`. Adding one doesn't, as long as reports written
+//! before it still deserialize, so new fields are `Option` or `#[serde(default)]`.
+
+use serde::{Deserialize, Serialize};
+
+/// Version of the report format, written to every report as `schema_version`
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Everything a finished screen hands over for the report
+/// Outcome of one test screen, as handed over when leaving it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "test", rename_all = "snake_case")]
+pub enum ScreenResult {
+    Keyboard(KeyboardResult),
+    Touchscreen(TouchscreenResult),
+    Mouse(MouseResult),
+    Trackpad(TrackpadResult),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyResult {
+    pub label: String,
+    pub codes: Vec<String>,
+    pub presses: usize,
+    /// Every code the key can send has been seen, or any one for "any of" keys
+    pub complete: bool,
+    /// Codes of the key that never fired
+    pub missing: Vec<String>,
+    /// Presses while this was the target of a guided test
+    pub attempts: Option<u32>,
+    /// Guided test outcome: passed, missing, mismatched or pending
+    pub outcome: Option<String>,
+    /// Unix time in ms of the first and last event of any of the key's codes
+    pub first_seen_ms: Option<u64>,
+    pub last_seen_ms: Option<u64>,
+    /// Why the key was left out of the pass criteria, None when it counted
+    #[serde(default)]
+    pub excluded: Option<String>,
+}
+
+/// Outcome of a guided keyboard test, counted over its last pass
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuidedResult {
+    pub order: String,
+    pub passed: usize,
+    pub missing: usize,
+    pub mismatched: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardResult {
+    pub layout: String,
+    pub keys_total: usize,
+    pub keys_pressed: usize,
+    pub total_presses: usize,
+    /// Highest number of presses seen within one second
+    pub peak_keys_per_sec: usize,
+    pub guided: Option<GuidedResult>,
+    /// Key rows in layout reading order, used for the heat map
+    pub rows: Vec<Vec<KeyResult>>,
+    /// Codes pressed that aren't on the layout, with their counts
+    pub unexpected_keys: Vec<String>,
+    /// Codes a keyboard quirk explains, counted apart from the presses
+    pub phantom_keys: Vec<String>,
+    /// Silence after which a key counts as stale while others keep firing
+    pub stale_window_secs: u64,
+    /// Codes that went stale, longest silent first
+    pub stale_keys: Vec<String>,
+    /// Key labels in the order of their first press
+    pub press_order: Vec<String>,
+    /// Keys whose first press broke the reading order, with their place in the
+    /// press order. None unless it was an ordered test.
+    pub out_of_order: Option<Vec<String>>,
+    /// Known quirks of the keyboards used
+    pub quirks: Vec<String>,
+    /// Held keys measured in the repeat test against the configured settings
+    pub repeat_checks: Vec<RepeatCheck>,
+    /// Most keys held at once, per keyboard
+    #[serde(default)]
+    pub simultaneous: Vec<SimultaneousKeysResult>,
+    /// Presses that looked like ghosting during the test, see `ghost_keys`
+    #[serde(default)]
+    pub ghost_suspects: Vec<GhostSuspectResult>,
+    /// The keyboard the test was limited to, None when every keyboard counted
+    #[serde(default)]
+    pub tested_keyboard: Option<String>,
+    /// Key events of the keyboards left out, per event node
+    #[serde(default)]
+    pub other_keyboards: Vec<OtherKeyboardResult>,
+    /// Keys known to be broken, left out of the pass criteria
+    #[serde(default)]
+    pub excluded_keys: Vec<ExcludedKeyResult>,
+    /// Two keyboards compared key by key, None unless it was a comparison
+    #[serde(default)]
+    pub comparison: Option<KeyboardComparisonResult>,
+    pub power: Option<PowerResult>,
+    /// When the test ran and for how long
+    #[serde(default)]
+    pub timing: Option<TestTiming>,
+}
+
+/// Most keys one keyboard had down at the same time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimultaneousKeysResult {
+    pub device: String,
+    pub max_held: usize,
+    /// Codes held at the moment of the maximum
+    pub keys: Vec<String>,
+    /// Codes held so long their release was taken as lost, left out of the count
+    pub stuck_keys: Vec<String>,
+    /// Phantom codes the keyboard sent while several keys were held
+    pub phantom_keys: Vec<String>,
+}
+
+/// A key that only ever came while others were held, in a burst with another
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GhostSuspectResult {
+    pub device: String,
+    /// Codes held when it came
+    pub held: Vec<String>,
+    pub phantom: String,
+    /// How often it came with those held
+    pub count: usize,
+}
+
+/// A key left out of the pass criteria, see `key_exclusions`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedKeyResult {
+    pub label: String,
+    pub reason: String,
+    /// Excluded from the config for the unit serial, not marked during the test
+    pub preseeded: bool,
+}
+
+/// Two keyboards pressed side by side, see `keyboard_compare`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyboardComparisonResult {
+    pub a: ComparedKeyboardResult,
+    pub b: ComparedKeyboardResult,
+    /// Labels of the keys complete on A but not on B
+    pub only_a: Vec<String>,
+    /// Labels of the keys complete on B but not on A
+    pub only_b: Vec<String>,
+}
+
+/// One keyboard of a comparison, counted on its own
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComparedKeyboardResult {
+    pub device: String,
+    pub path: String,
+    pub keys_total: usize,
+    pub keys_pressed: usize,
+    pub total_presses: usize,
+    /// Labels of the keys not complete on this keyboard
+    pub not_pressed: Vec<String>,
+}
+
+/// Key events of a keyboard that wasn't under test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtherKeyboardResult {
+    pub device: String,
+    pub path: String,
+    pub events: u64,
+    pub last_key: String,
+}
+
+/// One held key in the repeat test
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RepeatCheck {
+    pub device: String,
+    pub key: String,
+    pub configured_delay_ms: u32,
+    pub configured_period_ms: u32,
+    pub measured_delay_ms: u32,
+    pub measured_period_ms: u32,
+    pub within_tolerance: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TouchscreenResult {
+    pub device: Option<String>,
+    pub total_samples: u32,
+    pub total_jumps: u32,
+    pub max_jump: f32,
+    /// Max jump in millimetres, None without axis resolution or a panel size
+    pub max_jump_mm: Option<f32>,
+    /// Distance between consecutive samples counted as a jump, in calibrated units
+    pub jump_threshold: f32,
+    pub jump_threshold_mm: Option<f32>,
+    /// The trail was drawn with the panel's physical proportions instead of
+    /// stretched to the terminal
+    pub true_aspect: bool,
+    /// Raw mode: no calibration, the jump statistics in raw device units and
+    /// no coverage or corners
+    #[serde(default)]
+    pub raw_mode: bool,
+    pub min_pinch: Option<f32>,
+    pub max_pinch: Option<f32>,
+    /// Pressure threshold chosen in the pressure graph, None without a pressure axis
+    pub pressure_threshold: Option<i32>,
+    /// Names of two devices found reporting the same physical panel
+    pub duplicate_devices: Option<(String, String)>,
+    /// Edges where raw coordinates were pinned at the axis limit while the finger moved
+    pub saturation: Vec<SaturationResult>,
+    /// Cells inside the region of interest, the whole grid without one
+    pub cells_touched: usize,
+    pub cells_total: usize,
+    /// Region of interest coverage and corners were checked in: left, top,
+    /// right and bottom as 0-1 of the calibrated space. None for the whole panel.
+    #[serde(default)]
+    pub roi: Option<[f32; 4]>,
+    /// Touch-downs outside the region of interest, on the part the bezel masks
+    #[serde(default)]
+    pub masked_touches: usize,
+    /// Calibrated (0-999) touch paths, one per stroke still holding its points
+    pub paths: Vec<Vec<(u16, u16)>>,
+    /// The most recent strokes, older ones are only counted in `evicted_strokes`
+    pub strokes: Vec<StrokeSummary>,
+    pub evicted_strokes: StrokeTotals,
+    /// Number of this session, counting from 1. N or a swapped unit starts the next one.
+    pub session: u32,
+    /// Sessions finished before this one, oldest first. Only the latest few keep
+    /// their paths and strokes, older ones are reduced to totals.
+    pub earlier_sessions: Vec<TouchscreenResult>,
+    /// Earliest sessions left out of `earlier_sessions` to bound memory
+    pub dropped_sessions: u32,
+    pub power: Option<PowerResult>,
+    /// When the test ran and for how long
+    #[serde(default)]
+    pub timing: Option<TestTiming>,
+    /// Time series of an unattended soak run, None without one
+    pub soak: Option<SoakResult>,
+    /// Touch-downs near the corners, None in reports from before the check
+    pub corners: Option<CornerResult>,
+    /// Link of a serial panel, None for evdev panels
+    #[serde(default)]
+    pub serial: Option<SerialResult>,
+    /// Both devices of a two-device comparison, the selected one first. None
+    /// when a single device was tested.
+    #[serde(default)]
+    pub comparison: Option<Vec<DeviceStats>>,
+    /// Times the kernel dropped events of the device because they weren't read
+    /// in time (SYN_DROPPED)
+    #[serde(default)]
+    pub syn_drops: u32,
+    /// Touch-downs after the panel sat idle against the others, None in
+    /// reports from before the check. Boxed to keep `ScreenResult` small.
+    #[serde(default)]
+    pub wake: Option<Box<WakeResult>>,
+    /// Pen hover-to-touch offsets, None for panels that never reported a
+    /// hovering pen. Boxed like `wake`.
+    #[serde(default)]
+    pub hover: Option<Box<HoverResult>>,
+    /// Fastest step between consecutive samples of any stroke, calibrated
+    /// units per second
+    #[serde(default)]
+    pub max_speed: f32,
+    /// Max speed in mm/s, None without a mm scale
+    #[serde(default)]
+    pub max_speed_mm: Option<f32>,
+    /// Largest speed change between consecutive steps of any stroke,
+    /// calibrated units per second squared
+    #[serde(default)]
+    pub max_acceleration: f32,
+    /// Max acceleration in mm/s², approximate when the panel's units aren't square
+    #[serde(default)]
+    pub max_acceleration_mm: Option<f32>,
+    /// Jitter filter shown next to the raw samples and the jumps it still
+    /// made, None without one. Boxed like `wake`.
+    #[serde(default)]
+    pub stabilizer: Option<Box<StabilizerResult>>,
+}
+
+/// A jitter filter of the touchscreen test's display, see `stabilizer`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StabilizerResult {
+    /// "ema" or "median3"
+    pub filter: String,
+    /// Weight of the newest sample, for "ema"
+    pub alpha: Option<f32>,
+    /// Steps of the filtered position over the jump threshold, against
+    /// `total_jumps` of the raw samples
+    pub jumps: u32,
+    pub max_jump: f32,
+}
+
+/// Statistics of one device of a comparison, each against its own calibration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceStats {
+    pub device: String,
+    /// Statistics only start once the device is calibrated
+    pub calibrated: bool,
+    pub samples: u32,
+    pub jumps: u32,
+    pub max_jump: f32,
+    /// Samples per second of touching, None before the first stroke
+    pub rate_hz: Option<f32>,
+    /// Mean distance between samples that aren't jumps, in calibrated units
+    pub jitter: Option<f32>,
+    /// The jitter in millimetres, None without axis resolution or a panel size
+    #[serde(default)]
+    pub jitter_mm: Option<f32>,
+}
+
+impl DeviceStats {
+    /// e.g. "1.4 (0.3 mm)", "-" before there's any
+    pub fn jitter_text(&self) -> String {
+        match (self.jitter, self.jitter_mm) {
+            (Some(jitter), Some(mm)) => format!("{jitter:.1} ({mm:.1} mm)"),
+            (Some(jitter), None) => format!("{jitter:.1}"),
+            (None, _) => "-".to_string(),
+        }
+    }
+}
+
+/// Time from each touch-down to the touch's next sample, for touches after an
+/// idle gap and the others, see `wake_latency`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WakeResult {
+    /// Silence before a touch-down that makes it a wake touch
+    pub idle_gap_ms: u32,
+    /// Upper bounds of the histogram buckets, the last bucket is everything above
+    pub bucket_bounds_ms: Vec<u32>,
+    pub wake_touches: u32,
+    pub wake_histogram: Vec<u32>,
+    /// Wake touch-downs far from where the touch settled, their start was lost
+    pub wake_missed_starts: u32,
+    pub normal_touches: u32,
+    pub normal_histogram: Vec<u32>,
+    pub normal_missed_starts: u32,
+    pub worst_wake_ms: Option<u32>,
+    /// Largest distance of a wake touch-down from where it settled, calibrated units
+    pub worst_wake_offset: f32,
+    /// Wake touches lose their start more often than not, and more often than the others
+    pub flagged: bool,
+}
+
+/// Distance from the last hover sample to the first contact of each pen-down,
+/// see `pen_hover`. Offsets in calibrated units, in mm when the panel has a scale.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoverResult {
+    pub pen_downs: u32,
+    pub min: Option<f32>,
+    pub avg: Option<f32>,
+    pub max: Option<f32>,
+    pub min_mm: Option<f32>,
+    pub avg_mm: Option<f32>,
+    pub max_mm: Option<f32>,
+    /// Offset over which a pen-down counts against the panel, in mm
+    pub threshold_mm: f32,
+    /// The same without a mm scale, in calibrated units
+    pub threshold: f32,
+    pub over_threshold: u32,
+    /// Some pen-down went over the threshold
+    pub flagged: bool,
+}
+
+/// Baud rate the serial touch reader settled on, see `serial_touch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialResult {
+    pub baud: u32,
+    /// Share of bytes that made valid frames at `baud`, 0-1
+    pub valid_ratio: f32,
+    /// The default rate produced garbage and the rates were swept
+    pub swept: bool,
+}
+
+/// Corner checks, see `touch_corners`. Passed once every target got a touch-down.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CornerResult {
+    /// Margin from the edges, None when the panel size was unknown and the
+    /// margin was a fixed share of the calibrated range
+    pub margin_mm: Option<f32>,
+    /// The margin in calibrated units on each axis
+    pub margin_units: (f32, f32),
+    pub targets: Vec<CornerTarget>,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CornerTarget {
+    /// "top-left" through "bottom-right", or "top", "bottom", "left", "right"
+    /// for the edge midpoints
+    pub name: String,
+    pub touched: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakResult {
+    pub interval_secs: u64,
+    pub elapsed_secs: u64,
+    pub snapshots: Vec<SoakSnapshot>,
+}
+
+/// Counters gained during one soak interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSnapshot {
+    /// Unix time in ms the snapshot was taken
+    pub unix_ms: u64,
+    /// Time since the soak started
+    pub elapsed_secs: u64,
+    pub samples: u64,
+    /// Samples per second over the interval
+    pub sample_rate: f32,
+    pub jumps: u64,
+    /// Samples pinned at an axis limit while the finger moved
+    pub saturated_samples: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaturationResult {
+    /// "X-min", "X-max", "Y-min" or "Y-max"
+    pub edge: String,
+    pub samples: u64,
+}
+
+/// Strokes dropped from a long session to bound memory, only their totals are kept
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StrokeTotals {
+    pub strokes: u32,
+    pub samples: u64,
+    pub length: f32,
+    pub duration_ms: u64,
+}
+
+impl StrokeTotals {
+    pub fn add(&mut self, stroke: &StrokeSummary) {
+        self.strokes += 1;
+        self.samples += stroke.samples as u64;
+        self.length += stroke.length;
+        self.duration_ms += stroke.duration_ms;
+    }
+}
+
+/// Derived properties of one touch stroke, in calibrated units
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StrokeSummary {
+    pub samples: u32,
+    pub duration_ms: u64,
+    pub length: f32,
+    /// Calibrated units per second
+    pub average_speed: f32,
+    /// Fastest step between consecutive samples, calibrated units per second
+    #[serde(default)]
+    pub max_speed: f32,
+    /// Largest speed change between consecutive steps, calibrated units per
+    /// second squared
+    #[serde(default)]
+    pub max_acceleration: f32,
+    /// (min_x, min_y, max_x, max_y)
+    pub bounding_box: (u16, u16, u16, u16),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MouseResult {
+    /// Sum of movement in raw counts
+    pub total_distance: f64,
+    pub event_count: u64,
+    /// Buttons seen pressed, see `MouseResult::LEFT` and friends
+    pub buttons_verified: u8,
+    /// Longest gap between movement events while moving, pauses are not counted
+    pub max_gap_ms: u64,
+    pub drift: Option<DriftResult>,
+    /// Sensitivity when the test was left, including adjustments during the test
+    pub sensitivity: f32,
+    pub start_centered: bool,
+    pub trail_length: usize,
+    pub power: Option<PowerResult>,
+    /// When the test ran and for how long
+    #[serde(default)]
+    pub timing: Option<TestTiming>,
+}
+
+impl MouseResult {
+    pub const LEFT: u8 = 1 << 0;
+    pub const RIGHT: u8 = 1 << 1;
+    pub const MIDDLE: u8 = 1 << 2;
+    /// Number of buttons checked
+    pub const BUTTONS: u32 = 3;
+
+    pub fn button_names(mask: u8) -> String {
+        let names: Vec<&str> = [
+            (Self::LEFT, "left"),
+            (Self::RIGHT, "right"),
+            (Self::MIDDLE, "middle"),
+        ]
+        .iter()
+        .filter(|(bit, _)| mask & bit != 0)
+        .map(|(_, name)| *name)
+        .collect();
+
+        if names.is_empty() {
+            "none".to_string()
+        } else {
+            names.join("+")
+        }
+    }
+}
+
+/// Outcome of the hands-off drift check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriftResult {
+    pub duration_ms: u64,
+    pub events: u64,
+    pub distance: f64,
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackpadResult {
+    pub device: Option<String>,
+    pub max_fingers: usize,
+    pub clicks: u64,
+    pub taps: u64,
+    /// Samples per multi-touch slot, index is the slot number
+    pub slot_samples: Vec<u64>,
+    pub palm_events: u64,
+    /// Contacts cleared as stuck after a missed lift, automatically or with C
+    pub cleared_slots: u64,
+    pub power: Option<PowerResult>,
+    /// When the test ran and for how long
+    #[serde(default)]
+    pub timing: Option<TestTiming>,
+}
+
+/// Power source during a test, e.g. "battery 54%". A change halfway through
+/// means the numbers don't compare with tests run on one source.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerResult {
+    pub at_start: String,
+    pub at_end: String,
+    pub changed: bool,
+}
+
+/// Time spent in a test. The duration leaves out time on other screens, so
+/// it can be shorter than from start to end.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestTiming {
+    /// Unix time in ms
+    pub started_at_ms: u64,
+    pub ended_at_ms: u64,
+    pub duration_ms: u64,
+    /// Minimum duration the site requires, 0 for none
+    #[serde(default)]
+    pub min_duration_ms: u64,
+}
+
+/// Everything tested in one run of the tool, written as JSON and/or HTML at exit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionReport {
+    /// `SCHEMA_VERSION` of the tool that wrote the report, 0 for reports from
+    /// before the format was versioned
+    #[serde(default)]
+    pub schema_version: u32,
+    // A restored session is finished by this version
+    #[serde(skip_deserializing)]
+    pub tool_version: &'static str,
+    pub computer_model: String,
+    #[serde(flatten)]
+    pub metadata: SessionMetadata,
+    /// Set when the session ran against simulated devices or a replayed
+    /// capture, never a hardware result
+    pub demo: bool,
+    /// Unix time in seconds when the session started
+    pub started_at: u64,
+    /// Seconds the tool ran for this session, restored sessions not included
+    #[serde(default)]
+    pub uptime_secs: u64,
+    pub results: Vec<ScreenResult>,
+    /// Every input device that sent events during the session, with what
+    /// identifies its driver and firmware
+    #[serde(default)]
+    pub devices: Vec<DeviceResult>,
+}
+
+/// An input device of the session. Results name devices by `name`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeviceResult {
+    pub path: String,
+    pub name: String,
+    /// vendor:product in hex
+    pub id: String,
+    pub bus: String,
+    /// Kernel driver, e.g. usbhid or atkbd
+    pub driver: Option<String>,
+    /// Unique id the driver reports, often a serial number
+    pub uniq: Option<String>,
+    /// Input id version in hex, the bcdDevice on HID devices
+    pub version: Option<String>,
+    /// Descriptor strings of a USB device
+    pub usb: Option<UsbStrings>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsbStrings {
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial: Option<String>,
+    /// Device release number, usually the firmware version
+    pub bcd_device: Option<String>,
+}
+
+/// Who tested which unit, entered at startup with --ask-metadata
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionMetadata {
+    pub operator_id: Option<String>,
+    pub unit_serial: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+
+    use serde_json::Value;
+
+    use super::*;
+
+    fn fixture(name: &str) -> String {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures")
+            .join(name);
+        fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+    }
+
+    #[test]
+    fn the_current_fixture_round_trips() {
+        // A new SCHEMA_VERSION needs a fixture of its own
+        let text = fixture(&format!("report_v{SCHEMA_VERSION}.json"));
+        let report: SessionReport = serde_json::from_str(&text).unwrap();
+        assert_eq!(report.schema_version, SCHEMA_VERSION);
+        assert_eq!(report.validate(), Vec::<String>::new());
+
+        // Every field written is read back, nothing renamed or left out. A
+        // field added since shows up here, add it to the fixture.
+        let mut stored: Value = serde_json::from_str(&text).unwrap();
+        // Through the text, so f32 fields compare at the precision written
+        let mut written: Value =
+            serde_json::from_str(&serde_json::to_string(&report).unwrap()).unwrap();
+        // Always the version of the tool writing the report
+        stored["tool_version"] = Value::Null;
+        written["tool_version"] = Value::Null;
+        assert!(
+            stored == written,
+            "the report format changed:\n{}",
+            serde_json::to_string_pretty(&written).unwrap()
+        );
+    }
+
+    #[test]
+    fn reports_from_before_the_additions_still_deserialize() {
+        // Written before the format was versioned, without any of the fields
+        // added since
+        let report: SessionReport = serde_json::from_str(&fixture("report_v0.json")).unwrap();
+        assert_eq!(report.schema_version, 0);
+        assert_eq!(report.uptime_secs, 0);
+        assert!(report.devices.is_empty());
+
+        let tests: Vec<&str> = report.results.iter().map(ScreenResult::title).collect();
+        assert_eq!(tests.len(), 4, "{tests:?}");
+        let ScreenResult::Keyboard(keyboard) = &report.results[0] else {
+            panic!("the keyboard result comes first");
+        };
+        assert!(keyboard.simultaneous.is_empty());
+        assert!(keyboard.tested_keyboard.is_none());
+        assert_eq!(report.validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn a_newer_schema_is_flagged() {
+        let mut report: SessionReport =
+            serde_json::from_str(&fixture(&format!("report_v{SCHEMA_VERSION}.json"))).unwrap();
+        report.schema_version = SCHEMA_VERSION + 1;
+        let problems = report.validate();
+        assert_eq!(problems.len(), 1);
+        assert!(problems[0].contains("newer than this tool's"));
+    }
+}
@@ -0,0 +1,369 @@
+//! Consistency checks of a report, for `--validate-report` and for importers
+//! that want to reject a damaged or hand-edited file before loading it.
+//!
+//! Exit codes: 0 valid, 1 problems found, 2 the file couldn't be read or parsed.
+
+use std::fs;
+use std::path::Path;
+
+use super::{
+    KeyboardResult, MouseResult, SCHEMA_VERSION, ScreenResult, SessionReport, TouchscreenResult,
+    TrackpadResult,
+};
+
+pub const EXIT_VALID: i32 = 0;
+pub const EXIT_INVALID: i32 = 1;
+pub const EXIT_UNREADABLE: i32 = 2;
+
+// Touch coordinates are calibrated to 0-999 on both axes
+const CALIBRATED_MAX: u16 = 999;
+
+impl SessionReport {
+    /// Internal consistency problems, empty for a valid report. Counts have to
+    /// match the lists they summarize, timestamps have to run forward and
+    /// ratios have to stay within 0-100%.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.schema_version > SCHEMA_VERSION {
+            problems.push(format!(
+                "schema version {} is newer than this tool's {SCHEMA_VERSION}",
+                self.schema_version
+            ));
+        }
+
+        for (index, result) in self.results.iter().enumerate() {
+            let mut found = Vec::new();
+            if let Some(timing) = result.timing()
+                && timing.ended_at_ms < timing.started_at_ms
+            {
+                found.push("timing ended before it started".to_string());
+            }
+            match result {
+                ScreenResult::Keyboard(k) => check_keyboard(k, self.started_at, &mut found),
+                ScreenResult::Touchscreen(t) => check_touchscreen(t, &mut found),
+                ScreenResult::Mouse(m) => check_mouse(m, &mut found),
+                ScreenResult::Trackpad(t) => check_trackpad(t, &mut found),
+            }
+            problems.extend(
+                found
+                    .into_iter()
+                    .map(|problem| format!("results[{index}] {}: {problem}", result.title())),
+            );
+        }
+
+        problems
+    }
+}
+
+/// Parse and validate the report at `path`, printing every problem found.
+/// Returns the exit code.
+pub fn validate_file(path: &Path) -> i32 {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            println!("Cannot read {}: {e}", path.display());
+            return EXIT_UNREADABLE;
+        }
+    };
+    let report: SessionReport = match serde_json::from_str(&text) {
+        Ok(report) => report,
+        Err(e) => {
+            println!("Not a valid report {}: {e}", path.display());
+            return EXIT_UNREADABLE;
+        }
+    };
+
+    let problems = report.validate();
+    if problems.is_empty() {
+        println!(
+            "{}: valid, schema version {}, {} results",
+            path.display(),
+            report.schema_version,
+            report.results.len()
+        );
+        return EXIT_VALID;
+    }
+
+    let noun = if problems.len() == 1 {
+        "problem"
+    } else {
+        "problems"
+    };
+    println!("{}: {} {noun}", path.display(), problems.len());
+    for problem in &problems {
+        println!("  {problem}");
+    }
+    EXIT_INVALID
+}
+
+fn check_keyboard(k: &KeyboardResult, started_at: u64, problems: &mut Vec<String>) {
+    let keys: Vec<_> = k.rows.iter().flatten().collect();
+
+    if k.keys_total != keys.len() {
+        problems.push(format!(
+            "keys_total {} but the rows hold {} keys",
+            k.keys_total,
+            keys.len()
+        ));
+    }
+    let complete = keys.iter().filter(|key| key.complete).count();
+    if k.keys_pressed != complete {
+        problems.push(format!(
+            "keys_pressed {} but {complete} keys are complete",
+            k.keys_pressed
+        ));
+    }
+    if k.keys_pressed > k.keys_total {
+        problems.push(format!(
+            "keys_pressed {} is over 100% of keys_total {}",
+            k.keys_pressed, k.keys_total
+        ));
+    }
+    if let Some(key) = keys.iter().find(|key| key.presses > k.total_presses) {
+        problems.push(format!(
+            "key {} has {} presses of total_presses {}",
+            key.label, key.presses, k.total_presses
+        ));
+    }
+
+    if let Some(guided) = &k.guided {
+        let counted = guided.passed + guided.missing + guided.mismatched;
+        if counted > k.keys_total {
+            problems.push(format!(
+                "guided outcomes count {counted} keys of {}",
+                k.keys_total
+            ));
+        }
+    }
+
+    for excluded in &k.excluded_keys {
+        if !keys
+            .iter()
+            .any(|key| key.label == excluded.label && key.excluded.is_some())
+        {
+            problems.push(format!(
+                "excluded key {} isn't marked excluded in the rows",
+                excluded.label
+            ));
+        }
+    }
+
+    if let Some(out_of_order) = &k.out_of_order
+        && out_of_order.len() > k.press_order.len()
+    {
+        problems.push(format!(
+            "{} keys out of order but only {} in the press order",
+            out_of_order.len(),
+            k.press_order.len()
+        ));
+    }
+
+    for simultaneous in &k.simultaneous {
+        if simultaneous.keys.len() != simultaneous.max_held {
+            problems.push(format!(
+                "{} max_held {} but {} keys listed",
+                simultaneous.device,
+                simultaneous.max_held,
+                simultaneous.keys.len()
+            ));
+        }
+    }
+
+    for key in &keys {
+        if let (Some(first), Some(last)) = (key.first_seen_ms, key.last_seen_ms) {
+            if first > last {
+                problems.push(format!("key {} last seen before first seen", key.label));
+            }
+            if first < started_at * 1000 {
+                problems.push(format!(
+                    "key {} first seen before the session started",
+                    key.label
+                ));
+            }
+        }
+        if key.complete && key.presses == 0 {
+            problems.push(format!("key {} complete without presses", key.label));
+        }
+    }
+}
+
+fn check_touchscreen(t: &TouchscreenResult, problems: &mut Vec<String>) {
+    if t.cells_touched > t.cells_total {
+        problems.push(format!(
+            "cells_touched {} is over 100% of cells_total {}",
+            t.cells_touched, t.cells_total
+        ));
+    }
+    if t.total_jumps > t.total_samples {
+        problems.push(format!(
+            "total_jumps {} above total_samples {}",
+            t.total_jumps, t.total_samples
+        ));
+    }
+    if !t.max_jump.is_finite() || t.max_jump < 0.0 {
+        problems.push(format!("max_jump {} out of range", t.max_jump));
+    }
+    if !t.max_speed.is_finite() || t.max_speed < 0.0 {
+        problems.push(format!("max_speed {} out of range", t.max_speed));
+    }
+    if let Some(stroke) = t.strokes.iter().find(|s| s.max_speed > t.max_speed) {
+        problems.push(format!(
+            "stroke max_speed {} above the session's {}",
+            stroke.max_speed, t.max_speed
+        ));
+    }
+    if let (Some(min), Some(max)) = (t.min_pinch, t.max_pinch)
+        && min > max
+    {
+        problems.push(format!("min_pinch {min} above max_pinch {max}"));
+    }
+
+    if let Some(corners) = &t.corners
+        && corners.passed != corners.targets.iter().all(|target| target.touched)
+    {
+        problems.push("corners passed disagrees with the targets touched".to_string());
+    }
+    for stats in t.comparison.iter().flatten() {
+        if stats.jumps > stats.samples {
+            problems.push(format!(
+                "compared device {} has {} jumps of {} samples",
+                stats.device, stats.jumps, stats.samples
+            ));
+        }
+    }
+    if let Some(wake) = &t.wake {
+        let buckets = wake.bucket_bounds_ms.len() + 1;
+        for (name, histogram, touches, missed) in [
+            (
+                "wake",
+                &wake.wake_histogram,
+                wake.wake_touches,
+                wake.wake_missed_starts,
+            ),
+            (
+                "normal",
+                &wake.normal_histogram,
+                wake.normal_touches,
+                wake.normal_missed_starts,
+            ),
+        ] {
+            if histogram.len() != buckets {
+                problems.push(format!(
+                    "{name} histogram has {} buckets, bounds make {buckets}",
+                    histogram.len()
+                ));
+            }
+            let counted: u32 = histogram.iter().sum();
+            if counted > touches || missed > touches {
+                problems.push(format!(
+                    "{name} histogram counts more than {touches} touches"
+                ));
+            }
+        }
+    }
+    if let Some(hover) = &t.hover {
+        if hover.over_threshold > hover.pen_downs {
+            problems.push(format!(
+                "{} pen-downs over the hover threshold of {}",
+                hover.over_threshold, hover.pen_downs
+            ));
+        }
+        if hover.min > hover.max || hover.avg > hover.max {
+            problems.push("hover offsets: min or avg above max".to_string());
+        }
+    }
+    if let Some(serial) = &t.serial
+        && !(0.0..=1.0).contains(&serial.valid_ratio)
+    {
+        problems.push(format!(
+            "serial valid_ratio {} outside 0-1",
+            serial.valid_ratio
+        ));
+    }
+
+    let outside = t
+        .paths
+        .iter()
+        .flatten()
+        .filter(|(x, y)| *x > CALIBRATED_MAX || *y > CALIBRATED_MAX)
+        .count();
+    if outside > 0 {
+        problems.push(format!("{outside} path points outside 0-{CALIBRATED_MAX}"));
+    }
+    for (index, stroke) in t.strokes.iter().enumerate() {
+        let (min_x, min_y, max_x, max_y) = stroke.bounding_box;
+        if min_x > max_x || min_y > max_y {
+            problems.push(format!("stroke {index} has an inverted bounding box"));
+        }
+    }
+
+    if let Some(soak) = &t.soak {
+        for (index, pair) in soak.snapshots.windows(2).enumerate() {
+            if pair[1].unix_ms < pair[0].unix_ms || pair[1].elapsed_secs < pair[0].elapsed_secs {
+                problems.push(format!(
+                    "soak snapshot {} older than the one before",
+                    index + 1
+                ));
+            }
+        }
+        if let Some(last) = soak.snapshots.last()
+            && last.elapsed_secs > soak.elapsed_secs
+        {
+            problems.push("soak snapshot taken after the soak ended".to_string());
+        }
+        for (index, snapshot) in soak.snapshots.iter().enumerate() {
+            if snapshot.jumps > snapshot.samples || snapshot.saturated_samples > snapshot.samples {
+                problems.push(format!(
+                    "soak snapshot {index} counts more than its samples"
+                ));
+            }
+        }
+    }
+
+    let mut session = 0;
+    for earlier in &t.earlier_sessions {
+        if earlier.session <= session {
+            problems.push(format!("earlier session {} out of order", earlier.session));
+        }
+        session = earlier.session;
+        check_touchscreen(earlier, problems);
+    }
+    if !t.earlier_sessions.is_empty() && t.session <= session {
+        problems.push(format!(
+            "session {} not after its earlier sessions",
+            t.session
+        ));
+    }
+}
+
+fn check_mouse(m: &MouseResult, problems: &mut Vec<String>) {
+    let known = MouseResult::LEFT | MouseResult::RIGHT | MouseResult::MIDDLE;
+    if m.buttons_verified & !known != 0 {
+        problems.push(format!(
+            "unknown buttons_verified bits {:#b}",
+            m.buttons_verified
+        ));
+    }
+    if !m.total_distance.is_finite() || m.total_distance < 0.0 {
+        problems.push(format!("total_distance {} out of range", m.total_distance));
+    }
+    if let Some(drift) = &m.drift
+        && drift.events > m.event_count
+    {
+        problems.push(format!(
+            "drift check counts {} events of {}",
+            drift.events, m.event_count
+        ));
+    }
+}
+
+fn check_trackpad(t: &TrackpadResult, problems: &mut Vec<String>) {
+    if t.max_fingers > t.slot_samples.len() {
+        problems.push(format!(
+            "max_fingers {} with samples in {} slots",
+            t.max_fingers,
+            t.slot_samples.len()
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Picking up an interrupted session. With `--log-dir` the session so far is
+//! saved after every finished test (see `writer::SESSION_STATE`); a unit that
+//! reboots mid session (thermal trips in the climate chamber) finds it on the
+//! next start and is offered to continue instead of redoing every test.
+
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+use evdev::KeyCode;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::Stylize,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Paragraph, Wrap},
+};
+
+use crate::{
+    Nav, Screen, ScreenId,
+    event_handler::AppEvent,
+    report::SessionReport,
+    touch_nav::{TouchAction, TouchButtons},
+    writer::SESSION_STATE,
+};
+
+/// Saved sessions older than this are not offered, unless `--resume-max-age` says otherwise
+pub const DEFAULT_RESUME_MAX_AGE: Duration = Duration::from_secs(12 * 60 * 60);
+
+/// The session saved in `dir`, with its age, if there is one with results that
+/// is younger than `max_age`
+pub fn load_saved(dir: &Path, max_age: Duration) -> Option<(SessionReport, Duration)> {
+    let path = SESSION_STATE.path(dir);
+    let age = fs::metadata(&path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+        .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+    if age > max_age {
+        return None;
+    }
+
+    let saved: SessionReport = serde_json::from_slice(&fs::read(&path).ok()?).ok()?;
+    (!saved.results.is_empty()).then_some((saved, age))
+}
+
+/// Startup prompt offering to continue the saved session
+pub struct ResumeScreen {
+    saved: Option<SessionReport>,
+    age: Duration,
+    // Where a fresh session starts, e.g. the session info prompt
+    fresh_start: ScreenId,
+    resumed: bool,
+    touch_buttons: TouchButtons,
+}
+
+impl ResumeScreen {
+    pub fn new(saved: SessionReport, age: Duration, fresh_start: ScreenId) -> Self {
+        ResumeScreen {
+            saved: Some(saved),
+            age,
+            fresh_start,
+            resumed: false,
+            touch_buttons: TouchButtons::new(&[TouchAction::Resume, TouchAction::Discard]),
+        }
+    }
+
+    fn resume(&mut self) -> Nav {
+        self.resumed = true;
+        // The operator and serial come back with the results
+        Nav::To(ScreenId::Home)
+    }
+}
+
+impl Screen for ResumeScreen {
+    fn id(&self) -> ScreenId {
+        ScreenId::Resume
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        let footer = Line::from(vec![
+            " Enter".bold().yellow(),
+            " resume   ".into(),
+            "Esc".bold().yellow(),
+            " start over   ".into(),
+            "F1".bold().yellow(),
+            " help ".into(),
+        ]);
+        let block = Block::bordered()
+            .title(Line::from(" Unfinished Session ".bold().cyan()).centered())
+            .title_bottom(footer.centered())
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut lines = vec![
+            Line::from(format!(
+                "A session saved {} min ago was not finished.",
+                self.age.as_secs() / 60
+            )),
+            Line::from(""),
+        ];
+        if let Some(saved) = &self.saved {
+            let value = |value: &Option<String>| value.clone().unwrap_or_else(|| "-".into());
+            lines.push(Line::from(vec![
+                "Operator: ".dark_gray(),
+                value(&saved.metadata.operator_id).into(),
+                "   Unit: ".dark_gray(),
+                value(&saved.metadata.unit_serial).into(),
+            ]));
+            lines.push(Line::from(""));
+            lines.push(Line::from("Tests done:".bold()));
+            for result in &saved.results {
+                lines.push(Line::from(format!(
+                    "  {}: {}",
+                    result.title(),
+                    result.summary()
+                )));
+            }
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(
+            "Resume to keep these results in the report and continue with the next test.".gray(),
+        ));
+
+        let [text] = Layout::horizontal([Constraint::Max(90)])
+            .flex(Flex::Center)
+            .areas(inner);
+        let [text] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+            .flex(Flex::Center)
+            .areas(text);
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: false }), text);
+        self.touch_buttons.draw(frame);
+    }
+
+    fn handle_event(&mut self, event: AppEvent) -> Nav {
+        match event {
+            AppEvent::Key { code, .. } => match code {
+                KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => self.resume(),
+                KeyCode::KEY_ESC | KeyCode::KEY_Q => Nav::To(self.fresh_start),
+                _ => Nav::Stay,
+            },
+            AppEvent::Touch { .. } => match self.touch_buttons.handle_raw_touch(&event) {
+                Some(TouchAction::Resume) => self.resume(),
+                Some(TouchAction::Discard) => Nav::To(self.fresh_start),
+                _ => Nav::Stay,
+            },
+            _ => Nav::Stay,
+        }
+    }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("Enter, tap Resume", "Continue the saved session"),
+            ("Esc, tap Start over", "Discard it and start a new session"),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "The last session ended before it was finished, e.g. because the unit rebooted. \
+         Resuming keeps the tests already done in the report, starting over deletes them."
+    }
+
+    fn take_restored(&mut self) -> Option<SessionReport> {
+        if self.resumed {
+            self.saved.take()
+        } else {
+            None
+        }
+    }
+}
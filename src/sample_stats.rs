@@ -0,0 +1,117 @@
+//! Robust center of the samples collected while a calibration corner is held.
+//! A plain mean gets dragged by a single noise spike at the edge of the hold
+//! tolerance, so each axis drops its highest and lowest samples first.
+
+/// Share of samples dropped at each end of an axis before averaging
+pub const TRIM_FRACTION: f32 = 0.10;
+
+/// Fewer samples than this and the hold was too short, or the panel isn't
+/// reporting, to trust the capture
+pub const MIN_CAPTURE_SAMPLES: usize = 8;
+
+/// Where a corner was taken and how steady the finger was
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CornerCapture {
+    pub x: u16,
+    pub y: u16,
+    /// Samples collected during the hold
+    pub samples: usize,
+    /// Range of the samples kept after trimming, per axis
+    pub spread: (u16, u16),
+}
+
+/// Trimmed mean and spread of one axis, None without values
+pub fn trimmed_mean(values: &[u16]) -> Option<(u16, u16)> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let mut sorted = values.to_vec();
+    sorted.sort_unstable();
+    // Rounded, so a hold of the minimum length still drops one sample per end
+    let trim = (sorted.len() as f32 * TRIM_FRACTION).round() as usize;
+    let kept = &sorted[trim..sorted.len() - trim];
+
+    let sum: u64 = kept.iter().map(|v| *v as u64).sum();
+    let mean = (sum as f64 / kept.len() as f64).round() as u16;
+    let spread = kept[kept.len() - 1] - kept[0];
+    Some((mean, spread))
+}
+
+/// The corner for a hold's samples, or the sample count when there are too
+/// few to accept it
+pub fn capture(samples: &[(u16, u16)]) -> Result<CornerCapture, usize> {
+    if samples.len() < MIN_CAPTURE_SAMPLES {
+        return Err(samples.len());
+    }
+
+    let xs: Vec<u16> = samples.iter().map(|(x, _)| *x).collect();
+    let ys: Vec<u16> = samples.iter().map(|(_, y)| *y).collect();
+    let (Some((x, spread_x)), Some((y, spread_y))) = (trimmed_mean(&xs), trimmed_mean(&ys)) else {
+        return Err(samples.len());
+    };
+
+    Ok(CornerCapture {
+        x,
+        y,
+        samples: samples.len(),
+        spread: (spread_x, spread_y),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `n` samples jittering up to ±`jitter` around (`x`, `y`), from a fixed
+    /// sequence so the runs repeat
+    fn noisy(x: u16, y: u16, jitter: u16, n: usize) -> Vec<(u16, u16)> {
+        let mut state: u32 = 12345;
+        let mut next = || {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 16) % (2 * jitter as u32 + 1)
+        };
+        (0..n)
+            .map(|_| {
+                let dx = next() as u16;
+                let dy = next() as u16;
+                (x - jitter + dx, y - jitter + dy)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn noise_averages_out() {
+        let corner = capture(&noisy(400, 3700, 12, 60)).unwrap();
+        assert!(corner.x.abs_diff(400) <= 3, "{corner:?}");
+        assert!(corner.y.abs_diff(3700) <= 3, "{corner:?}");
+        assert_eq!(corner.samples, 60);
+        assert!(corner.spread.0 <= 24 && corner.spread.1 <= 24);
+    }
+
+    #[test]
+    fn a_spike_is_trimmed_away() {
+        let mut samples = noisy(2000, 2000, 5, 20);
+        samples[7] = (4095, 0);
+        samples[13] = (0, 4095);
+        let corner = capture(&samples).unwrap();
+        assert!(corner.x.abs_diff(2000) <= 2, "{corner:?}");
+        assert!(corner.y.abs_diff(2000) <= 2, "{corner:?}");
+        // Both spikes sat at the ends and went with the trim
+        assert!(corner.spread.0 <= 10 && corner.spread.1 <= 10);
+    }
+
+    #[test]
+    fn the_minimum_hold_still_trims_one_per_end() {
+        let values = [100, 100, 100, 100, 100, 100, 100, 1000];
+        assert_eq!(values.len(), MIN_CAPTURE_SAMPLES);
+        assert_eq!(trimmed_mean(&values), Some((100, 0)));
+    }
+
+    #[test]
+    fn too_few_samples_report_their_count() {
+        let samples = noisy(2000, 2000, 5, MIN_CAPTURE_SAMPLES - 1);
+        assert_eq!(capture(&samples), Err(MIN_CAPTURE_SAMPLES - 1));
+        assert_eq!(trimmed_mean(&[]), None);
+    }
+}
@@ -0,0 +1,204 @@
+//! Detects touch coordinates pinned at an axis limit, a common digitizer fault:
+//! the finger moves along the panel but X stays at 4095. Works on raw
+//! coordinates, before calibration could clamp them.
+
+use crate::event_handler::DeviceInfo;
+
+/// Consecutive samples on a limit needed before they count, so a stroke that
+/// merely crosses the edge isn't reported
+const SATURATION_MIN_RUN: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edge {
+    XMin,
+    XMax,
+    YMin,
+    YMax,
+}
+
+impl Edge {
+    pub const ALL: [Edge; 4] = [Edge::XMin, Edge::XMax, Edge::YMin, Edge::YMax];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Edge::XMin => "X-min",
+            Edge::XMax => "X-max",
+            Edge::YMin => "Y-min",
+            Edge::YMax => "Y-max",
+        }
+    }
+}
+
+/// Raw (min, max) of both axes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AxisLimits {
+    pub x: (u16, u16),
+    pub y: (u16, u16),
+}
+
+impl AxisLimits {
+    /// The range the device reports, None when it doesn't. evdev touch axes start
+    /// at 0 in practice, so only the maximum is read.
+    pub fn from_device(info: &DeviceInfo) -> Option<Self> {
+        let max = |max: Option<i32>| {
+            max.filter(|m| *m > 0)
+                .map(|m| m.min(u16::MAX as i32) as u16)
+        };
+        Some(AxisLimits {
+            x: (0, max(info.abs_x_max)?),
+            y: (0, max(info.abs_y_max)?),
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct SaturationDetector {
+    previous: Option<(u16, u16)>,
+    // Current run of samples on each edge, in `Edge::ALL` order
+    runs: [u32; 4],
+    counts: [u64; 4],
+}
+
+impl SaturationDetector {
+    pub fn new() -> Self {
+        SaturationDetector::default()
+    }
+
+    /// Feed one raw sample. A sample on a limit continues the edge's run only while
+    /// the other axis keeps changing, a finger resting in a corner isn't a fault.
+    pub fn observe(&mut self, x: u16, y: u16, released: bool, limits: AxisLimits) {
+        if released {
+            self.previous = None;
+            self.runs = [0; 4];
+            return;
+        }
+
+        let (moved_x, moved_y) = match self.previous {
+            Some((px, py)) => (px != x, py != y),
+            None => (false, false),
+        };
+
+        for (i, edge) in Edge::ALL.into_iter().enumerate() {
+            let (on_limit, other_moved) = match edge {
+                Edge::XMin => (x == limits.x.0, moved_y),
+                Edge::XMax => (x == limits.x.1, moved_y),
+                Edge::YMin => (y == limits.y.0, moved_x),
+                Edge::YMax => (y == limits.y.1, moved_x),
+            };
+
+            if !on_limit {
+                self.runs[i] = 0;
+                continue;
+            }
+            // The first sample on the limit starts a run, later ones only extend it
+            // when the finger is moving along the edge
+            if self.runs[i] > 0 && !other_moved {
+                continue;
+            }
+
+            self.runs[i] += 1;
+            if self.runs[i] == SATURATION_MIN_RUN {
+                self.counts[i] += SATURATION_MIN_RUN as u64;
+            } else if self.runs[i] > SATURATION_MIN_RUN {
+                self.counts[i] += 1;
+            }
+        }
+
+        self.previous = Some((x, y));
+    }
+
+    /// Saturated samples per edge, only edges with any
+    pub fn detected(&self) -> Vec<(Edge, u64)> {
+        Edge::ALL
+            .into_iter()
+            .zip(self.counts)
+            .filter(|(_, count)| *count > 0)
+            .collect()
+    }
+
+    pub fn reset(&mut self) {
+        *self = SaturationDetector::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: AxisLimits = AxisLimits {
+        x: (0, 4095),
+        y: (0, 4095),
+    };
+
+    /// A stroke of `n` samples along `edge`, the other axis stepping by 10
+    fn along(edge: Edge, n: u16) -> Vec<(u16, u16)> {
+        (0..n)
+            .map(|i| {
+                let other = 1000 + i * 10;
+                match edge {
+                    Edge::XMin => (0, other),
+                    Edge::XMax => (4095, other),
+                    Edge::YMin => (other, 0),
+                    Edge::YMax => (other, 4095),
+                }
+            })
+            .collect()
+    }
+
+    fn detect(samples: &[(u16, u16)]) -> Vec<(Edge, u64)> {
+        let mut detector = SaturationDetector::new();
+        for &(x, y) in samples {
+            detector.observe(x, y, false, LIMITS);
+        }
+        detector.detected()
+    }
+
+    #[test]
+    fn a_stroke_pinned_to_each_edge_is_counted() {
+        for edge in Edge::ALL {
+            assert_eq!(detect(&along(edge, 10)), [(edge, 10)], "{}", edge.name());
+        }
+    }
+
+    #[test]
+    fn crossing_an_edge_is_not_a_fault() {
+        for edge in Edge::ALL {
+            let short = along(edge, SATURATION_MIN_RUN as u16 - 1);
+            assert!(detect(&short).is_empty(), "{}", edge.name());
+        }
+        // Into the edge and straight back out
+        assert!(detect(&[(100, 500), (0, 510), (100, 520)]).is_empty());
+    }
+
+    #[test]
+    fn resting_in_a_corner_is_not_a_fault() {
+        let resting = [(4095, 4095); 20];
+        assert!(detect(&resting).is_empty());
+    }
+
+    #[test]
+    fn a_release_breaks_the_run() {
+        let mut detector = SaturationDetector::new();
+        for (i, &(x, y)) in along(Edge::XMax, 4).iter().enumerate() {
+            detector.observe(x, y, false, LIMITS);
+            if i == 1 {
+                detector.observe(x, y, true, LIMITS);
+            }
+        }
+        assert!(detector.detected().is_empty());
+    }
+
+    #[test]
+    fn limits_come_from_the_device_range() {
+        let mut info = crate::sim::sim_device("sim://panel", "Panel", Some(1023));
+        assert_eq!(
+            AxisLimits::from_device(&info),
+            Some(AxisLimits {
+                x: (0, 1023),
+                y: (0, 1023),
+            })
+        );
+        info.abs_y_max = None;
+        assert_eq!(AxisLimits::from_device(&info), None);
+    }
+}
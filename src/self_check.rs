@@ -0,0 +1,185 @@
+//! Startup self-check of what every screen silently relies on: the event
+//! channel delivers, the tick thread ticks, the terminal draws and the
+//! monotonic clock moves. A tick thread that didn't start leaves calibration
+//! holds that never progress and nothing pointing at the cause, so a failure
+//! gets a screen of its own naming the subsystem. Runs behind the Loading view
+//! before the first screen, and headless with `--check self`.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, RecvTimeoutError, Sender, unbounded};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::Stylize,
+    text::Line,
+    widgets::{Block, Clear, Paragraph, Wrap},
+};
+
+use crate::event_handler::{self, AppEvent};
+
+/// How long the probe and the first tick may take to arrive
+pub const EVENT_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// What a check looks at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Subsystem {
+    Terminal,
+    Clock,
+    Channel,
+    Tick,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 4] = [
+        Subsystem::Terminal,
+        Subsystem::Clock,
+        Subsystem::Channel,
+        Subsystem::Tick,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Subsystem::Terminal => "terminal",
+            Subsystem::Clock => "monotonic clock",
+            Subsystem::Channel => "event channel",
+            Subsystem::Tick => "tick thread",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Failure {
+    pub subsystem: Subsystem,
+    pub detail: String,
+}
+
+impl Failure {
+    pub fn new(subsystem: Subsystem, detail: impl Into<String>) -> Self {
+        Failure {
+            subsystem,
+            detail: detail.into(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.subsystem.name(), self.detail)
+    }
+}
+
+/// Whether the monotonic clock moves over a short sleep
+pub fn check_clock() -> Option<Failure> {
+    let before = Instant::now();
+    thread::sleep(Duration::from_millis(2));
+    let after = Instant::now();
+    (after <= before).then(|| Failure::new(Subsystem::Clock, "did not advance over a 2 ms sleep"))
+}
+
+/// Send a probe through `tx` and wait for it and a tick to come out of `rx`.
+/// The sources are already running, so whatever else arrives meanwhile is
+/// handed back in order, to be handled as if it had just come.
+pub fn check_events(
+    tx: &Sender<AppEvent>,
+    rx: &Receiver<AppEvent>,
+    failures: &mut Vec<Failure>,
+) -> Vec<AppEvent> {
+    let mut received = Vec::new();
+    let mut probed = false;
+    let mut ticked = false;
+    if tx.send(AppEvent::SelfCheck).is_err() {
+        failures.push(Failure::new(
+            Subsystem::Channel,
+            "the probe could not be sent",
+        ));
+        return received;
+    }
+    let deadline = Instant::now() + EVENT_TIMEOUT;
+    while !(probed && ticked) {
+        match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(AppEvent::SelfCheck) => probed = true,
+            Ok(AppEvent::Tick) => ticked = true,
+            Ok(event) => received.push(event),
+            Err(RecvTimeoutError::Timeout) => break,
+            Err(RecvTimeoutError::Disconnected) => {
+                failures.push(Failure::new(Subsystem::Channel, "closed"));
+                return received;
+            }
+        }
+    }
+    let timeout = EVENT_TIMEOUT.as_millis();
+    if !probed {
+        failures.push(Failure::new(
+            Subsystem::Channel,
+            format!("the probe did not come back within {timeout} ms"),
+        ));
+    }
+    if ticked {
+        received.push(AppEvent::Tick);
+    } else {
+        failures.push(Failure::new(
+            Subsystem::Tick,
+            format!("no tick within {timeout} ms, the thread may not have started"),
+        ));
+    }
+    received
+}
+
+/// The checks on their own, without the TUI: a channel and tick thread of
+/// their own and the terminal's size instead of a draw. Prints a line per
+/// subsystem and returns the exit code, 0 when all passed and 1 otherwise.
+pub fn run_headless() -> i32 {
+    let mut failures: Vec<Failure> = Vec::new();
+    match ratatui::crossterm::terminal::size() {
+        Ok((0, _) | (_, 0)) => failures.push(Failure::new(Subsystem::Terminal, "has no size")),
+        Ok(_) => {}
+        Err(e) => failures.push(Failure::new(
+            Subsystem::Terminal,
+            format!("size unreadable: {e}"),
+        )),
+    }
+    failures.extend(check_clock());
+    let (tx, rx) = unbounded();
+    event_handler::spawn_tick_thread(&tx);
+    check_events(&tx, &rx, &mut failures);
+
+    for subsystem in Subsystem::ALL {
+        match failures.iter().find(|f| f.subsystem == subsystem) {
+            Some(failure) => println!("{:<16} FAILED, {}", subsystem.name(), failure.detail),
+            None => println!("{:<16} ok", subsystem.name()),
+        }
+    }
+    if failures.is_empty() { 0 } else { 1 }
+}
+
+/// The diagnostic screen shown instead of the home menu when a check failed
+pub fn draw_failures(frame: &mut Frame, failures: &[Failure]) {
+    let mut lines = vec![
+        Line::from("Startup self-check failed".bold().red()),
+        Line::from(""),
+    ];
+    lines.extend(failures.iter().map(|failure| {
+        Line::from(vec![
+            format!("{}: ", failure.subsystem.name()).bold(),
+            failure.detail.clone().into(),
+        ])
+    }));
+    lines.push(Line::from(""));
+    lines.push(Line::from(
+        "The tests would not work reliably. Press any key to exit.".gray(),
+    ));
+
+    let [area] = Layout::vertical([Constraint::Length(lines.len() as u16 + 2)])
+        .flex(Flex::Center)
+        .areas(frame.area());
+    let [area] = Layout::horizontal([Constraint::Max(70)])
+        .flex(Flex::Center)
+        .areas(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Self-check ")),
+        area,
+    );
+}
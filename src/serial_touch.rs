@@ -1,38 +1,150 @@
-use crate::{event_handler::AppEvent, machine_detect::has_serial_touchscreen};
+use crate::{
+    device_class::DeviceClass,
+    event_handler::{AppEvent, DeviceInfo},
+    logging,
+    machine_detect::has_serial_touchscreen,
+    range_learning::RangeLearner,
+    watchdog,
+};
 
-use std::{thread, time::Duration};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 
 use crossbeam_channel::Sender;
+use evdev::BusType;
 
 use color_eyre::{Result, eyre::eyre};
 
+/// Port the serial touchscreen is wired to on the models that have one
+pub(crate) const SERIAL_PORT: &str = "/dev/ttyS3";
+
+/// Names of the `TouchProtocol` implementations, for `--print-capabilities`
+pub(crate) const PROTOCOLS: [&str; 1] = [Decoder::NAME];
+
+// Rate the controllers ship with
+const DEFAULT_BAUD: u32 = 19200;
+// Tried in turn when the frames don't parse at the current rate
+const SWEEP_RATES: [u32; 4] = [9600, 19200, 38400, 57600];
+// Time a rate gets before its frames are scored
+const SCORE_DURATION: Duration = Duration::from_secs(2);
+// Bytes a score needs to mean anything, five frames. The panel only streams
+// while pressed, so scoring waits for a touch.
+const MIN_SCORED_BYTES: u64 = 25;
+// Share of bytes in valid frames below which the rate counts as wrong
+const MIN_VALID_RATIO: f32 = 0.8;
+
+/// Bytes a protocol decoder has seen and how many of them made valid frames
+#[derive(Debug, Clone, Copy, Default)]
+struct FrameStats {
+    bytes: u64,
+    valid_bytes: u64,
+}
+
+impl FrameStats {
+    /// Share of bytes in valid frames, None before there are enough bytes
+    fn valid_ratio(&self) -> Option<f32> {
+        (self.bytes >= MIN_SCORED_BYTES).then(|| self.valid_bytes as f32 / self.bytes as f32)
+    }
+}
+
+/// A serial touch protocol: bytes in, touch events out. Decoders keep score of
+/// the bytes that made valid frames, which is how the reader tells a wrong baud
+/// rate from a working one whatever the protocol.
+trait TouchProtocol {
+    fn feed(&mut self, byte: u8) -> Option<AppEvent>;
+
+    /// The port was lost, see `Decoder::disconnect`
+    fn disconnect(&mut self) -> Option<AppEvent>;
+
+    fn frame_stats(&self) -> FrameStats;
+
+    fn reset_stats(&mut self);
+
+    fn info(&self) -> &DeviceInfo;
+
+    /// The device identity when its learned range changed since the last call,
+    /// for `AppEvent::DeviceUpdated`
+    fn take_update(&mut self) -> Option<Arc<DeviceInfo>>;
+
+    /// Forget the learned range, e.g. after samples decoded at a wrong baud rate
+    fn reset_range(&mut self);
+}
+
+/// The 5 byte protocol of the built-in panel: a 0xFF (coordinates) or 0xBF
+/// (touch toggle) header, then Y and X as two 7-bit bytes each
 struct Decoder {
     state: u8,
     y_hi: u8,
     y_lo: u8,
     x_hi: u8,
 
-    is_touching: bool,
+    // None after a reconnect until the first frames tell whether the panel is pressed
+    is_touching: Option<bool>,
+    last_position: Option<(u16, u16)>,
+    // Shared with the events, copied only when the learned range changes
+    info: Arc<DeviceInfo>,
+    stats: FrameStats,
+    range: RangeLearner,
+    // `info.learned_range` changed and wasn't passed on yet
+    range_changed: bool,
 }
 
 impl Decoder {
-    fn new() -> Self {
+    const NAME: &str = "ff-bf-5byte";
+
+    fn new(path: &str) -> Self {
         Decoder {
             state: 0,
             y_hi: 0,
             y_lo: 0,
             x_hi: 0,
-            is_touching: false,
+            is_touching: Some(false),
+            last_position: None,
+            info: Arc::new(serial_device_info(path)),
+            stats: FrameStats::default(),
+            range: RangeLearner::new(),
+            range_changed: false,
         }
     }
 
+    fn touch_event(&self, x: u16, y: u16, released: bool) -> AppEvent {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis();
+        AppEvent::Touch {
+            x,
+            y,
+            timestamp,
+            released,
+            pressure: None,
+            info: Some(Arc::clone(&self.info)),
+            keepalive: false,
+        }
+    }
+}
+
+impl TouchProtocol for Decoder {
     fn feed(&mut self, byte: u8) -> Option<AppEvent> {
+        self.stats.bytes += 1;
+        // Data bytes are 7 bits, a high bit within a frame is a header or noise,
+        // e.g. at the wrong baud rate. The frame is lost, the byte may start the next.
+        if self.state != 0 && byte & 0x80 != 0 {
+            self.state = 0;
+        }
+
         match self.state {
             0 => {
                 if byte == 0xFF {
+                    // Coordinates are only streamed while the panel is pressed
+                    self.is_touching.get_or_insert(true);
                     self.state = 1;
                 } else if byte == 0xBF {
-                    self.is_touching = !self.is_touching;
+                    // Touch state toggle. With the state unknown after a reconnect, a
+                    // toggle before any coordinates is most likely a new touch
+                    self.is_touching = Some(!self.is_touching.unwrap_or(false));
                     self.state = 1;
                 }
             }
@@ -53,17 +165,14 @@ impl Decoder {
                 let x = ((self.x_hi as u16) << 7) | (x_lo as u16);
                 let y = ((self.y_hi as u16) << 7) | (self.y_lo as u16);
                 self.state = 0;
-                let timestamp = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis();
-                return Some(AppEvent::Touch {
-                    x,
-                    y,
-                    timestamp,
-                    released: !self.is_touching,
-                    info: None,
-                });
+                self.last_position = Some((x, y));
+                self.stats.valid_bytes += 5;
+                let released = self.is_touching != Some(true);
+                if !released && let Some(limits) = self.range.observe(x, y) {
+                    Arc::make_mut(&mut self.info).learned_range = Some(limits);
+                    self.range_changed = true;
+                }
+                return Some(self.touch_event(x, y, released));
             }
             _ => {
                 self.state = 0; // Reset on unexpected state
@@ -71,9 +180,138 @@ impl Decoder {
         }
         None
     }
+
+    /// Forget the frame in progress and the touch state when the port is lost.
+    /// Returns a release for a touch that was down, so downstream state (trail,
+    /// calibration hold) doesn't wait for a release that will never come.
+    fn disconnect(&mut self) -> Option<AppEvent> {
+        self.state = 0;
+        let was_touching = self.is_touching == Some(true);
+        self.is_touching = None;
+
+        let (x, y) = self.last_position.take()?;
+        was_touching.then(|| self.touch_event(x, y, true))
+    }
+
+    fn frame_stats(&self) -> FrameStats {
+        self.stats
+    }
+
+    fn reset_stats(&mut self) {
+        self.stats = FrameStats::default();
+    }
+
+    fn info(&self) -> &DeviceInfo {
+        &self.info
+    }
+
+    fn take_update(&mut self) -> Option<Arc<DeviceInfo>> {
+        std::mem::take(&mut self.range_changed).then(|| Arc::clone(&self.info))
+    }
+
+    fn reset_range(&mut self) {
+        self.range = RangeLearner::new();
+        self.range_changed = Arc::make_mut(&mut self.info).learned_range.take().is_some();
+    }
+}
+
+/// Identity for events from the serial panel, so it can be selected and filtered
+/// like any evdev device. The protocol doesn't report an axis range, the reader
+/// learns it from the samples instead (`learned_range`).
+pub(crate) fn serial_device_info(path: &str) -> DeviceInfo {
+    DeviceInfo {
+        path: path.to_string(),
+        name: format!("Serial touchscreen ({path})"),
+        abs_x_max: None,
+        abs_y_max: None,
+        multitouch: false,
+        phys: None,
+        vendor: 0,
+        product: 0,
+        bus: BusType::BUS_RS232,
+        pressure_max: None,
+        resolution: None,
+        class: DeviceClass::Touchscreen,
+        learned_range: None,
+        swapped_axes: false,
+        ids: Arc::default(),
+    }
 }
 
-pub fn spawn_reader(tx: Sender<AppEvent>) -> Result<std::thread::JoinHandle<()>> {
+fn open_port(
+    path: &str,
+    baud: u32,
+    timeout: Duration,
+) -> serialport::Result<Box<dyn serialport::SerialPort>> {
+    serialport::new(path, baud)
+        .timeout(timeout)
+        .data_bits(serialport::DataBits::Eight)
+        .parity(serialport::Parity::None)
+        .stop_bits(serialport::StopBits::One)
+        .flow_control(serialport::FlowControl::None)
+        .open()
+}
+
+/// Read each of `SWEEP_RATES` for `SCORE_DURATION` and return the rate with the
+/// largest share of valid frames and that share. Events decoded meanwhile are
+/// dropped. None when no rate saw enough bytes to be scored.
+fn sweep_baud(path: &str, decoder: &mut impl TouchProtocol) -> Option<(u32, f32)> {
+    let mut best: Option<(u32, f32)> = None;
+    let mut buffer = [0u8; 256];
+    for rate in SWEEP_RATES {
+        let Ok(mut port) = open_port(path, rate, Duration::from_millis(100)) else {
+            continue;
+        };
+        decoder.reset_stats();
+        let started = Instant::now();
+        while started.elapsed() < SCORE_DURATION {
+            match port.read(&mut buffer) {
+                Ok(n) => {
+                    for &byte in &buffer[..n] {
+                        decoder.feed(byte);
+                    }
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(_) => break,
+            }
+        }
+        watchdog::device_active(decoder.info());
+        if let Some(ratio) = decoder.frame_stats().valid_ratio()
+            && best.is_none_or(|(_, best_ratio)| ratio > best_ratio)
+        {
+            best = Some((rate, ratio));
+        }
+    }
+    // Don't carry a frame or touch from the last rate tried over to the chosen one,
+    // nor a range learned from frames at the wrong rates
+    decoder.disconnect();
+    decoder.reset_stats();
+    decoder.reset_range();
+    best
+}
+
+/// A running serial reader. Dropping it leaves the reader running, `stop`
+/// ends it.
+pub struct SerialReader {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<()>,
+}
+
+impl SerialReader {
+    /// Ask the reader to end. It notices within the port's read timeout, a
+    /// touch still held is released first. Returns the thread to join if the
+    /// caller wants to wait for it.
+    pub fn stop(self) -> thread::JoinHandle<()> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.handle
+    }
+}
+
+/// Read the serial touchscreen on its own thread. The first two seconds of
+/// frames are scored, and with `baud_sweep` a rate that mostly produces
+/// garbage is replaced by the best of `SWEEP_RATES`. The outcome is sent as
+/// `AppEvent::SerialStatus`.
+pub fn spawn_reader(tx: Sender<AppEvent>, baud_sweep: bool) -> Result<SerialReader> {
     if !has_serial_touchscreen() {
         return Err(eyre!(
             "serial touch reader can only be spawned on DatorBärbarFält model"
@@ -82,49 +320,125 @@ pub fn spawn_reader(tx: Sender<AppEvent>) -> Result<std::thread::JoinHandle<()>>
 
     let _tx = tx.clone();
 
-    let path = "/dev/ttyS3";
-    let baud = 19200;
+    let path = SERIAL_PORT;
     let timeout_ms = 1000;
 
+    let stop = Arc::new(AtomicBool::new(false));
+    let stopped = stop.clone();
+    let stopping = move || stopped.load(Ordering::Relaxed);
+
     let handle = thread::spawn(move || {
         let mut attempts = 0usize;
-        loop {
-            match serialport::new(path, baud)
-                .timeout(Duration::from_millis(timeout_ms))
-                .data_bits(serialport::DataBits::Eight)
-                .parity(serialport::Parity::None)
-                .stop_bits(serialport::StopBits::One)
-                .flow_control(serialport::FlowControl::None)
-                .open()
-            {
+        // The decoder outlives a lost port so the touch state can be carried over
+        let mut decoder = Decoder::new(path);
+        let mut baud = DEFAULT_BAUD;
+        // Scored once per session, a reconnect doesn't change the panel's rate
+        let mut scored = false;
+        let mut opened_before = false;
+        // Reopening at a swept rate isn't a lost port
+        let mut rate_changed = false;
+        let mut reconnects = 0u32;
+        while !stopping() {
+            match open_port(path, baud, Duration::from_millis(timeout_ms)) {
                 Ok(mut port) => {
-                    let mut decoder = Decoder::new();
+                    // The reader only gets here again after losing the port,
+                    // or after a sweep
+                    if opened_before && !rate_changed {
+                        reconnects += 1;
+                        let _ = _tx.send(AppEvent::DeviceFault {
+                            info: Arc::clone(&decoder.info),
+                            reason: format!(
+                                "serial port reconnected ({reconnects} times this session)"
+                            ),
+                        });
+                    }
+                    opened_before = true;
+                    rate_changed = false;
+                    decoder.reset_stats();
+                    let opened = Instant::now();
+
                     let mut buffer = [0u8; 256];
                     loop {
+                        if stopping() {
+                            if let Some(release) = decoder.disconnect() {
+                                let _ = _tx.send(release);
+                            }
+                            break;
+                        }
                         match port.read(&mut buffer) {
                             Ok(n) if n > 0 => {
+                                watchdog::device_active(&decoder.info);
                                 for &byte in &buffer[..n] {
                                     if let Some(event) = decoder.feed(byte) {
                                         let _ = _tx.send(event);
                                     }
                                 }
+                                if let Some(info) = decoder.take_update() {
+                                    let _ = _tx.send(AppEvent::DeviceUpdated { info });
+                                }
                             }
                             Ok(_) => {
                                 thread::sleep(Duration::from_millis(2));
                             }
                             Err(e) => {
                                 if e.kind() != std::io::ErrorKind::TimedOut {
-                                    eprintln!("Error reading from serial port {}: {}", path, e);
+                                    logging::warn(&format!(
+                                        "Error reading from serial port {}: {}",
+                                        path, e
+                                    ));
+                                    if let Some(release) = decoder.disconnect() {
+                                        let _ = _tx.send(release);
+                                    }
                                     break; // Exit inner loop to attempt reopening
                                 }
                             }
                         }
+
+                        if scored || opened.elapsed() < SCORE_DURATION {
+                            continue;
+                        }
+                        let Some(ratio) = decoder.frame_stats().valid_ratio() else {
+                            continue;
+                        };
+                        scored = true;
+                        if ratio >= MIN_VALID_RATIO || !baud_sweep {
+                            let _ = _tx.send(AppEvent::SerialStatus {
+                                info: Arc::clone(&decoder.info),
+                                baud,
+                                valid_ratio: ratio,
+                                swept: false,
+                            });
+                            continue;
+                        }
+
+                        logging::warn(&format!(
+                            "Serial touch frames {:.0}% valid at {baud} baud, sweeping rates",
+                            ratio * 100.0
+                        ));
+                        if let Some(release) = decoder.disconnect() {
+                            let _ = _tx.send(release);
+                        }
+                        drop(port);
+                        let (best, best_ratio) =
+                            sweep_baud(path, &mut decoder).unwrap_or((baud, ratio));
+                        baud = best;
+                        rate_changed = true;
+                        let _ = _tx.send(AppEvent::SerialStatus {
+                            info: Arc::clone(&decoder.info),
+                            baud,
+                            valid_ratio: best_ratio,
+                            swept: true,
+                        });
+                        break; // Reopen at the chosen rate
                     }
                 }
                 Err(e) => {
                     attempts += 1;
-                    if attempts % 10 == 0 {
-                        eprintln!("Failed to open serial port {}: {}. Retrying...", path, e);
+                    if attempts.is_multiple_of(10) {
+                        logging::warn(&format!(
+                            "Failed to open serial port {}: {}. Retrying...",
+                            path, e
+                        ));
                     }
                     thread::sleep(Duration::from_secs(1));
                 }
@@ -132,5 +446,87 @@ pub fn spawn_reader(tx: Sender<AppEvent>) -> Result<std::thread::JoinHandle<()>>
         }
     });
 
-    Ok(handle)
+    Ok(SerialReader { stop, handle })
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    /// The five bytes of a coordinate frame at (`x`, `y`), 14 bits each
+    fn frame(x: u16, y: u16) -> [u8; 5] {
+        [
+            0xFF,
+            (y >> 7) as u8 & 0x7F,
+            y as u8 & 0x7F,
+            (x >> 7) as u8 & 0x7F,
+            x as u8 & 0x7F,
+        ]
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(512))]
+
+        #[test]
+        fn the_decoder_resyncs_on_the_frame_after_garbage(
+            garbage in proptest::collection::vec(any::<u8>(), 0..256),
+            x in 0..0x4000u16,
+            y in 0..0x4000u16,
+        ) {
+            let mut decoder = Decoder::new("/dev/ttyS0");
+            for byte in garbage {
+                decoder.feed(byte);
+            }
+
+            // Nothing until the last byte, then exactly the frame sent
+            let [header @ .., last] = frame(x, y);
+            for byte in header {
+                prop_assert!(decoder.feed(byte).is_none());
+            }
+            let Some(AppEvent::Touch { x: sent_x, y: sent_y, .. }) = decoder.feed(last) else {
+                panic!("no touch for the frame after the garbage");
+            };
+            prop_assert_eq!((sent_x, sent_y), (x, y));
+            prop_assert!(decoder.frame_stats().valid_bytes <= decoder.frame_stats().bytes);
+        }
+    }
+
+    /// x, y and released of a touch event
+    fn touch(event: Option<AppEvent>) -> (u16, u16, bool) {
+        match event {
+            Some(AppEvent::Touch { x, y, released, .. }) => (x, y, released),
+            _ => panic!("not a touch event"),
+        }
+    }
+
+    #[test]
+    fn a_disconnect_mid_frame_releases_the_touch_and_the_next_frame_decodes() {
+        let mut decoder = Decoder::new("/dev/ttyS0");
+        // A touch down is a toggle frame
+        let mut down = frame(300, 400);
+        down[0] = 0xBF;
+        let mut last = None;
+        for byte in down {
+            last = decoder.feed(byte);
+        }
+        assert_eq!(touch(last), (300, 400, false));
+
+        // Half of the next frame, then the port is lost
+        for byte in &frame(310, 410)[..3] {
+            assert!(decoder.feed(*byte).is_none());
+        }
+        assert_eq!(touch(decoder.disconnect()), (300, 400, true));
+        // Released once only
+        assert!(decoder.disconnect().is_none());
+
+        // The bytes after the reconnect are a frame of their own, not the rest
+        // of the lost one, and coordinates mean the panel is pressed
+        let [header @ .., last] = frame(1200, 900);
+        for byte in header {
+            assert!(decoder.feed(byte).is_none());
+        }
+        assert_eq!(touch(decoder.feed(last)), (1200, 900, false));
+    }
 }
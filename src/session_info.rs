@@ -0,0 +1,291 @@
+use evdev::KeyCode;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout},
+    style::Stylize,
+    symbols::border,
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+
+use crate::{
+    Nav, Screen, ScreenId,
+    event_handler::AppEvent,
+    machine_detect::read_product_serial,
+    report::SessionMetadata,
+    text_input::{Routed, TextInput},
+    touch_nav::{TouchAction, TouchButtons},
+};
+
+const FIELD_MAX_LEN: usize = 40;
+
+/// Startup prompt for the operator ID and unit serial that go into the report header
+pub struct SessionInfoScreen {
+    // Operator ID, then unit serial. At most one has the focus, none after Esc.
+    fields: [TextInput; 2],
+    confirmed: Option<SessionMetadata>,
+    // Skip only: the fields need a keyboard, a touch-only unit has to go past them
+    touch_buttons: TouchButtons,
+}
+
+impl SessionInfoScreen {
+    /// Fields filled with `current`, the serial falling back to the one in DMI
+    pub fn new(current: &SessionMetadata) -> Self {
+        let serial = current.unit_serial.clone().or_else(read_product_serial);
+        let mut fields = [
+            TextInput::new(current.operator_id.as_deref().unwrap_or(""), FIELD_MAX_LEN),
+            TextInput::new(serial.as_deref().unwrap_or(""), FIELD_MAX_LEN),
+        ];
+        fields[0].focus();
+        SessionInfoScreen {
+            fields,
+            confirmed: None,
+            touch_buttons: TouchButtons::new(&[TouchAction::Skip]),
+        }
+    }
+
+    fn focused(&self) -> Option<usize> {
+        self.fields.iter().position(TextInput::is_focused)
+    }
+
+    fn focus(&mut self, index: usize) {
+        for (i, field) in self.fields.iter_mut().enumerate() {
+            if i == index {
+                field.focus();
+            } else {
+                field.unfocus();
+            }
+        }
+    }
+
+    fn confirm(&mut self) -> Nav {
+        let value = |field: &TextInput| {
+            let text = field.text().trim().to_string();
+            (!text.is_empty()).then_some(text)
+        };
+        self.confirmed = Some(SessionMetadata {
+            operator_id: value(&self.fields[0]),
+            unit_serial: value(&self.fields[1]),
+        });
+        Nav::To(ScreenId::Home)
+    }
+}
+
+impl Screen for SessionInfoScreen {
+    fn id(&self) -> ScreenId {
+        ScreenId::SessionInfo
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        let skip = if self.focused().is_some() {
+            " leave field, again to skip   "
+        } else {
+            " skip   "
+        };
+        let footer = Line::from(vec![
+            " Tab/↑/↓".bold().yellow(),
+            " switch field   ".into(),
+            "Enter".bold().yellow(),
+            " next / done   ".into(),
+            "Esc".bold().yellow(),
+            skip.into(),
+            "F1".bold().yellow(),
+            " help ".into(),
+        ]);
+        let block = Block::bordered()
+            .title(Line::from(" Session Info ".bold().cyan()).centered())
+            .title_bottom(footer.centered())
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let [form] = Layout::horizontal([Constraint::Length(FIELD_MAX_LEN as u16 + 4)])
+            .flex(Flex::Center)
+            .areas(inner);
+        let [intro, operator, serial] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(3),
+            Constraint::Length(3),
+        ])
+        .flex(Flex::Center)
+        .areas(form);
+
+        frame.render_widget(
+            Paragraph::new("Who is testing which unit, for the report:"),
+            intro,
+        );
+        self.fields[0].draw(frame, operator, "Operator ID");
+        self.fields[1].draw(frame, serial, "Unit serial");
+        self.touch_buttons.draw(frame);
+    }
+
+    fn handle_event(&mut self, event: AppEvent) -> Nav {
+        if let AppEvent::Touch { .. } = event {
+            if self.touch_buttons.handle_raw_touch(&event) == Some(TouchAction::Skip) {
+                return Nav::To(ScreenId::Home);
+            }
+            return Nav::Stay;
+        }
+        let AppEvent::Key { code, .. } = event else {
+            return Nav::Stay;
+        };
+
+        // The focused field goes first, it types Q and takes the first Esc
+        let focused = self.focused();
+        if let Some(index) = focused
+            && self.fields[index].route(code) != Routed::Unused
+        {
+            return Nav::Stay;
+        }
+
+        match code {
+            KeyCode::KEY_ESC | KeyCode::KEY_Q => return Nav::To(ScreenId::Home),
+            // Two fields, so every direction switches to the other one
+            KeyCode::KEY_TAB | KeyCode::KEY_DOWN | KeyCode::KEY_UP => {
+                self.focus(focused.map_or(0, |index| (index + 1) % 2));
+            }
+            KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => {
+                if focused == Some(0) {
+                    self.focus(1);
+                } else {
+                    return self.confirm();
+                }
+            }
+            _ => {}
+        }
+
+        Nav::Stay
+    }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("Tab/↑/↓", "Switch between the fields"),
+            ("←/→ Home End", "Move the cursor"),
+            ("Backspace/Del", "Delete before / at the cursor"),
+            ("Enter", "Next field, on the last one save and continue"),
+            ("Esc", "Leave the field, Q is typed while in one"),
+            (
+                "Esc/Q, tap Skip",
+                "Outside the fields: continue without saving",
+            ),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "Enter your operator ID and the serial number of the unit under test, both end up in \
+         the report. Letters are always typed in upper case. The serial is filled in from the \
+         firmware when it is readable."
+    }
+
+    fn wants_raw_keys(&self) -> bool {
+        // Keypad digits type digits here instead of moving the cursor
+        true
+    }
+
+    fn text_focused(&self) -> bool {
+        self.focused().is_some()
+    }
+
+    fn take_metadata(&mut self) -> Option<SessionMetadata> {
+        self.confirmed.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::press;
+
+    fn screen() -> SessionInfoScreen {
+        // A serial given, so the machine's DMI isn't read
+        SessionInfoScreen::new(&SessionMetadata {
+            operator_id: None,
+            unit_serial: Some("SN1".to_string()),
+        })
+    }
+
+    fn keys(screen: &mut SessionInfoScreen, keys: &[KeyCode]) -> Vec<Nav> {
+        keys.iter()
+            .map(|code| screen.handle_event(press(*code, 0)))
+            .collect()
+    }
+
+    fn stays(navs: &[Nav]) -> bool {
+        navs.iter().all(|nav| matches!(nav, Nav::Stay))
+    }
+
+    #[test]
+    fn q_is_typed_in_the_field_and_the_second_esc_leaves() {
+        let mut screen = screen();
+        assert_eq!(screen.focused(), Some(0));
+        assert!(stays(&keys(&mut screen, &[KeyCode::KEY_Q, KeyCode::KEY_A])));
+        assert_eq!(screen.fields[0].text(), "QA");
+
+        // The first Esc leaves the field, the screen stays
+        assert!(stays(&keys(&mut screen, &[KeyCode::KEY_ESC])));
+        assert_eq!(screen.focused(), None);
+        assert!(!screen.text_focused());
+
+        let nav = screen.handle_event(press(KeyCode::KEY_ESC, 0));
+        assert!(matches!(nav, Nav::To(ScreenId::Home)));
+        // Leaving without Enter saves nothing
+        assert!(screen.take_metadata().is_none());
+    }
+
+    #[test]
+    fn q_outside_the_fields_leaves() {
+        let mut screen = screen();
+        keys(&mut screen, &[KeyCode::KEY_ESC]);
+        let nav = screen.handle_event(press(KeyCode::KEY_Q, 0));
+        assert!(matches!(nav, Nav::To(ScreenId::Home)));
+        assert_eq!(screen.fields[0].text(), "");
+    }
+
+    #[test]
+    fn tab_after_esc_focuses_the_first_field_again() {
+        let mut screen = screen();
+        keys(&mut screen, &[KeyCode::KEY_TAB]);
+        assert_eq!(screen.focused(), Some(1));
+        keys(&mut screen, &[KeyCode::KEY_ESC, KeyCode::KEY_TAB]);
+        assert_eq!(screen.focused(), Some(0));
+        keys(&mut screen, &[KeyCode::KEY_UP, KeyCode::KEY_DOWN]);
+        assert_eq!(screen.focused(), Some(0));
+    }
+
+    #[test]
+    fn enter_moves_on_then_saves_the_trimmed_fields() {
+        let mut screen = screen();
+        let navs = keys(
+            &mut screen,
+            &[
+                KeyCode::KEY_O,
+                KeyCode::KEY_P,
+                KeyCode::KEY_SPACE,
+                KeyCode::KEY_ENTER,
+            ],
+        );
+        assert!(stays(&navs));
+        assert_eq!(screen.focused(), Some(1));
+        assert!(screen.take_metadata().is_none());
+
+        keys(&mut screen, &[KeyCode::KEY_BACKSPACE; 3]);
+        let nav = screen.handle_event(press(KeyCode::KEY_ENTER, 0));
+        assert!(matches!(nav, Nav::To(ScreenId::Home)));
+        let metadata = screen.take_metadata().unwrap();
+        assert_eq!(metadata.operator_id.as_deref(), Some("OP"));
+        // An emptied field is left out
+        assert_eq!(metadata.unit_serial, None);
+    }
+
+    #[test]
+    fn enter_outside_the_fields_saves() {
+        let mut screen = screen();
+        keys(&mut screen, &[KeyCode::KEY_ESC]);
+        let nav = screen.handle_event(press(KeyCode::KEY_KPENTER, 0));
+        assert!(matches!(nav, Nav::To(ScreenId::Home)));
+        let metadata = screen.take_metadata().unwrap();
+        assert_eq!(metadata.unit_serial.as_deref(), Some("SN1"));
+    }
+}
@@ -0,0 +1,375 @@
+//! Synthetic input devices for developing without the field hardware.
+//!
+//! Each generator is deterministic for a given seed and produces events
+//! together with the delay until the next one, so the same sequences can be
+//! replayed without threads (e.g. when rendering screens offline).
+
+use crossbeam_channel::Sender;
+use evdev::{BusType, KeyCode};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::{thread, time::Duration};
+
+use crate::device_class::DeviceClass;
+use crate::event_handler::{AppEvent, DeviceInfo};
+use crate::keyboard_layouts::LAYOUT_OPTIONS;
+use crate::watchdog;
+
+const SIM_TOUCH_MAX: u16 = 4095;
+const SIM_TOUCH_MARGIN: u16 = 100;
+const SIM_TOUCH_INTERVAL_MS: u64 = 20;
+const SIM_PRESSURE_MAX: u16 = 255;
+
+// Keys that drive navigation somewhere in the UI. The fake keyboard skips them so
+// the demo doesn't wander off the current screen on its own.
+const NAVIGATION_KEYS: &[KeyCode] = &[
+    KeyCode::KEY_A,
+    KeyCode::KEY_ESC,
+    KeyCode::KEY_Q,
+    KeyCode::KEY_ENTER,
+    KeyCode::KEY_KPENTER,
+    KeyCode::KEY_KP8,
+    KeyCode::KEY_KP2,
+    KeyCode::KEY_KP4,
+    KeyCode::KEY_KP6,
+    KeyCode::KEY_UP,
+    KeyCode::KEY_DOWN,
+    KeyCode::KEY_LEFT,
+    KeyCode::KEY_RIGHT,
+    KeyCode::KEY_SPACE,
+    KeyCode::KEY_F1,
+    KeyCode::KEY_SLASH,
+    KeyCode::KEY_LEFTCTRL,
+    KeyCode::KEY_RIGHTCTRL,
+    KeyCode::KEY_1,
+    KeyCode::KEY_2,
+    KeyCode::KEY_3,
+    KeyCode::KEY_4,
+    KeyCode::KEY_5,
+    KeyCode::KEY_6,
+    KeyCode::KEY_7,
+    KeyCode::KEY_8,
+    KeyCode::KEY_9,
+    KeyCode::KEY_C,
+    KeyCode::KEY_F,
+    KeyCode::KEY_G,
+    KeyCode::KEY_J,
+    KeyCode::KEY_L,
+    KeyCode::KEY_P,
+    KeyCode::KEY_R,
+    KeyCode::KEY_T,
+];
+
+/// Small xorshift generator, good enough for jitter and random taps
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // xorshift must never be seeded with zero
+        Rng(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// Uniform value in `lo..=hi`
+    pub fn range(&mut self, lo: u16, hi: u16) -> u16 {
+        lo + (self.next_u64() % (hi.saturating_sub(lo) as u64 + 1)) as u16
+    }
+}
+
+pub(crate) fn sim_device(path: &str, name: &str, abs_max: Option<i32>) -> DeviceInfo {
+    DeviceInfo {
+        path: path.to_string(),
+        name: name.to_string(),
+        abs_x_max: abs_max,
+        abs_y_max: abs_max,
+        multitouch: false,
+        phys: None,
+        vendor: 0,
+        product: 0,
+        bus: BusType::BUS_VIRTUAL,
+        pressure_max: None,
+        resolution: None,
+        class: DeviceClass::Other,
+        learned_range: None,
+        swapped_axes: false,
+        ids: Arc::default(),
+    }
+}
+
+/// Fake touchscreen: holds each corner long enough to calibrate, then
+/// alternates between a zig-zag sweep over the panel and random taps.
+pub struct SimTouchscreen {
+    rng: Rng,
+    clock_ms: u128,
+    phase: usize,
+    // (x, y, released, delay after this sample in ms)
+    queue: VecDeque<(u16, u16, bool, u64)>,
+    info: Arc<DeviceInfo>,
+}
+
+impl SimTouchscreen {
+    pub fn new(seed: u64) -> Self {
+        SimTouchscreen {
+            rng: Rng::new(seed),
+            clock_ms: 0,
+            phase: 0,
+            queue: VecDeque::new(),
+            info: Arc::new(DeviceInfo {
+                pressure_max: Some(SIM_PRESSURE_MAX as i32),
+                // A 4095 unit axis over a 10" 16:10 panel
+                resolution: Some((19, 30)),
+                class: DeviceClass::Touchscreen,
+                ..sim_device(
+                    "sim://touchscreen",
+                    "Simulated touchscreen",
+                    Some(SIM_TOUCH_MAX as i32),
+                )
+            }),
+        }
+    }
+
+    pub fn next_event(&mut self) -> (AppEvent, Duration) {
+        if self.queue.is_empty() {
+            self.refill();
+        }
+
+        let (x, y, released, delay) = self.queue.pop_front().unwrap_or((0, 0, true, 100));
+        self.clock_ms += delay as u128;
+
+        // Mostly firm contact with the odd feather-light sample, like a worn resistive panel
+        let pressure = if released {
+            0
+        } else if self.rng.range(0, 9) == 0 {
+            self.rng.range(1, SIM_PRESSURE_MAX / 8)
+        } else {
+            self.rng.range(SIM_PRESSURE_MAX / 3, SIM_PRESSURE_MAX)
+        };
+
+        (
+            AppEvent::Touch {
+                x,
+                y,
+                timestamp: self.clock_ms,
+                released,
+                pressure: Some(pressure as i32),
+                info: Some(Arc::clone(&self.info)),
+                keepalive: false,
+            },
+            Duration::from_millis(delay),
+        )
+    }
+
+    fn refill(&mut self) {
+        let lo = SIM_TOUCH_MARGIN;
+        let hi = SIM_TOUCH_MAX - SIM_TOUCH_MARGIN;
+
+        match self.phase % 3 {
+            0 => {
+                // Calibration corners in the order the calibration asks for them
+                for (cx, cy) in [(lo, lo), (hi, lo), (hi, hi), (lo, hi)] {
+                    for _ in 0..75 {
+                        let x = cx + self.rng.range(0, 10) - 5;
+                        let y = cy + self.rng.range(0, 10) - 5;
+                        self.queue.push_back((x, y, false, SIM_TOUCH_INTERVAL_MS));
+                    }
+                    self.queue.push_back((cx, cy, true, 600));
+                }
+            }
+            1 => {
+                // One continuous zig-zag stroke over the whole panel
+                let rows = 8u16;
+                for row in 0..=rows {
+                    let y = lo + (hi - lo) / rows * row;
+                    for step in 0..=40u16 {
+                        let t = (hi - lo) / 40 * step;
+                        let x = if row % 2 == 0 { lo + t } else { hi - t };
+                        self.queue.push_back((x, y, false, SIM_TOUCH_INTERVAL_MS));
+                    }
+                }
+                self.queue.push_back((lo, hi, true, 500));
+            }
+            _ => {
+                // Taps stay clear of the middle column where the home menu sits,
+                // so the demo doesn't start tests on its own
+                let band = (hi - lo) / 3;
+                for _ in 0..20 {
+                    let x = match self.rng.range(0, 1) {
+                        0 => self.rng.range(lo, lo + band),
+                        _ => self.rng.range(hi - band, hi),
+                    };
+                    let y = self.rng.range(lo, hi);
+                    for _ in 0..5 {
+                        self.queue.push_back((x, y, false, SIM_TOUCH_INTERVAL_MS));
+                    }
+                    self.queue.push_back((x, y, true, 300));
+                }
+            }
+        }
+
+        self.phase += 1;
+    }
+}
+
+/// Fake keyboard pressing the keys of the default layout in reading order
+pub struct SimKeyboard {
+    rng: Rng,
+    clock_ms: u128,
+    keys: Vec<KeyCode>,
+    next: usize,
+    // Key that is down, released by the next event
+    held: Option<KeyCode>,
+    info: Arc<DeviceInfo>,
+}
+
+impl SimKeyboard {
+    pub fn new(seed: u64) -> Self {
+        let keys = LAYOUT_OPTIONS[0]
+            .1
+            .iter()
+            .flat_map(|section| section.iter())
+            .flat_map(|block| block.iter())
+            .flat_map(|row| row.iter())
+            .filter(|key| !key.is_continuation())
+            .flat_map(|key| key.codes.iter().copied())
+            .filter(|code| !NAVIGATION_KEYS.contains(code))
+            .collect();
+
+        SimKeyboard {
+            rng: Rng::new(seed),
+            clock_ms: 0,
+            keys,
+            next: 0,
+            held: None,
+            info: Arc::new(sim_device("sim://keyboard", "Simulated keyboard", None)),
+        }
+    }
+
+    pub fn next_event(&mut self) -> (AppEvent, Duration) {
+        if let Some(code) = self.held.take() {
+            let delay = self.rng.range(100, 250) as u64;
+            self.clock_ms += delay as u128;
+            return (
+                AppEvent::KeyReleased {
+                    code,
+                    info: Arc::clone(&self.info),
+                    timestamp: self.clock_ms,
+                },
+                Duration::from_millis(delay),
+            );
+        }
+
+        let code = self.keys[self.next % self.keys.len()];
+        self.next += 1;
+        self.held = Some(code);
+
+        // Held briefly, so a press and its release take 150-350 ms together
+        let delay = self.rng.range(50, 100) as u64;
+        self.clock_ms += delay as u128;
+        (
+            AppEvent::Key {
+                code,
+                info: Arc::clone(&self.info),
+                repeat: false,
+                timestamp: self.clock_ms,
+            },
+            Duration::from_millis(delay),
+        )
+    }
+}
+
+/// Fake mouse moving in circles and clicking once per lap
+pub struct SimMouse {
+    angle: f32,
+    // Fractional movement not yet sent as a whole relative step
+    remainder: (f32, f32),
+    clock_ms: u128,
+    info: Arc<DeviceInfo>,
+}
+
+impl SimMouse {
+    const RADIUS: f32 = 60.0;
+    const STEP: f32 = 0.08;
+
+    pub fn new() -> Self {
+        SimMouse {
+            angle: 0.0,
+            remainder: (0.0, 0.0),
+            clock_ms: 0,
+            info: Arc::new(sim_device("sim://mouse", "Simulated mouse", None)),
+        }
+    }
+
+    pub fn next_event(&mut self) -> (AppEvent, Duration) {
+        let next_angle = self.angle + Self::STEP;
+        self.clock_ms += 20;
+
+        // Completed a lap, click instead of moving this time
+        if next_angle >= std::f32::consts::TAU {
+            self.angle = next_angle - std::f32::consts::TAU;
+            return (
+                AppEvent::Key {
+                    code: KeyCode::BTN_LEFT,
+                    info: Arc::clone(&self.info),
+                    repeat: false,
+                    timestamp: self.clock_ms,
+                },
+                Duration::from_millis(20),
+            );
+        }
+
+        let dx = Self::RADIUS * (next_angle.cos() - self.angle.cos()) + self.remainder.0;
+        let dy = Self::RADIUS * (next_angle.sin() - self.angle.sin()) + self.remainder.1;
+        self.angle = next_angle;
+
+        let (x, y) = (dx.round(), dy.round());
+        self.remainder = (dx - x, dy - y);
+
+        (
+            AppEvent::Mouse {
+                x: x as i16,
+                y: y as i16,
+                info: Arc::clone(&self.info),
+            },
+            Duration::from_millis(20),
+        )
+    }
+}
+
+/// Spawn one thread per simulated device, feeding the same channel the real listeners use
+pub fn spawn_generators(tx: &Sender<AppEvent>, seed: u64) {
+    let mut touchscreen = SimTouchscreen::new(seed);
+    spawn_generator(tx, move || touchscreen.next_event());
+
+    let mut keyboard = SimKeyboard::new(seed.wrapping_add(1));
+    spawn_generator(tx, move || keyboard.next_event());
+
+    let mut mouse = SimMouse::new();
+    spawn_generator(tx, move || mouse.next_event());
+}
+
+fn spawn_generator(
+    tx: &Sender<AppEvent>,
+    mut next_event: impl FnMut() -> (AppEvent, Duration) + Send + 'static,
+) {
+    let tx = tx.clone();
+    thread::spawn(move || {
+        loop {
+            let (event, delay) = next_event();
+            if let Some(info) = event.device() {
+                watchdog::device_active(info);
+            }
+            if tx.send(event).is_err() {
+                break;
+            }
+            thread::sleep(delay);
+        }
+    });
+}
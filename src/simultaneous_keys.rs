@@ -0,0 +1,310 @@
+//! Most keys held down at once per keyboard, the anti-ghosting figure a spec
+//! sheet quotes. Presses and releases keep a set of held keys per physical
+//! keyboard; its largest size over the session is the figure, with the keys
+//! that made it. A key held far longer than anyone holds a key while pressing
+//! others lost its release (or is stuck) and drops out, so it can't inflate
+//! the count for the rest of the session. Phantom presses that arrive while
+//! several keys are held are the ghosting the figure is about, and are listed
+//! with it.
+
+use std::collections::{BTreeSet, HashMap};
+
+use evdev::KeyCode;
+
+use crate::event_handler::DeviceInfo;
+use crate::key_dedup::PhysicalDevices;
+use crate::key_quirks;
+use crate::report::SimultaneousKeysResult;
+
+/// A key held longer than this counts as stuck and stops counting as held
+pub const STUCK_AFTER_MS: u128 = 5000;
+
+// Keys already held for a phantom press to count as ghosting
+const GHOSTING_HELD: usize = 2;
+
+/// Held keys of one physical keyboard
+#[derive(Default)]
+struct Keyboard {
+    name: String,
+    // Held key to the time it went down
+    held: HashMap<KeyCode, u128>,
+    max: usize,
+    max_keys: Vec<KeyCode>,
+    stuck: BTreeSet<KeyCode>,
+    // Dropped as stuck and not released since
+    still_stuck: BTreeSet<KeyCode>,
+    phantoms: BTreeSet<KeyCode>,
+    // Keys the keyboard never sends a release for, see `key_quirks`
+    no_release: &'static [KeyCode],
+}
+
+impl Keyboard {
+    /// Drop keys held since before `STUCK_AFTER_MS` ago
+    fn drop_stuck(&mut self, now: u128) {
+        let stuck: Vec<KeyCode> = self
+            .held
+            .iter()
+            .filter(|(_, down)| now.saturating_sub(**down) > STUCK_AFTER_MS)
+            .map(|(code, _)| *code)
+            .collect();
+        for code in stuck {
+            self.held.remove(&code);
+            self.stuck.insert(code);
+            self.still_stuck.insert(code);
+        }
+    }
+}
+
+/// Most simultaneous keys of every keyboard seen
+#[derive(Default)]
+pub struct SimultaneousKeys {
+    keyboards: PhysicalDevices<Keyboard>,
+}
+
+impl SimultaneousKeys {
+    pub fn new() -> Self {
+        SimultaneousKeys::default()
+    }
+
+    fn keyboard(&mut self, info: &DeviceInfo) -> &mut Keyboard {
+        self.keyboards.get_or_insert_with(info, || Keyboard {
+            name: info.name.clone(),
+            no_release: key_quirks::quirk_for(info).map_or(&[], |quirk| quirk.no_release),
+            ..Keyboard::default()
+        })
+    }
+
+    /// A key went down at `timestamp` (ms). Autorepeat isn't a press.
+    pub fn press(&mut self, info: &DeviceInfo, code: KeyCode, timestamp: u128) {
+        if is_button(code) {
+            return;
+        }
+        let keyboard = self.keyboard(info);
+        keyboard.drop_stuck(timestamp);
+        // Held for a moment only, its release never comes
+        if keyboard.no_release.contains(&code) {
+            return;
+        }
+        keyboard.held.entry(code).or_insert(timestamp);
+        if keyboard.held.len() > keyboard.max {
+            keyboard.max = keyboard.held.len();
+            let mut keys: Vec<KeyCode> = keyboard.held.keys().copied().collect();
+            keys.sort_by_key(|code| code.0);
+            keyboard.max_keys = keys;
+        }
+    }
+
+    /// A key came up, including one that was dropped as stuck
+    pub fn release(&mut self, info: &DeviceInfo, code: KeyCode) {
+        if is_button(code) {
+            return;
+        }
+        let keyboard = self.keyboard(info);
+        keyboard.held.remove(&code);
+        keyboard.still_stuck.remove(&code);
+    }
+
+    /// `code` is held on some keyboard since before `STUCK_AFTER_MS` before
+    /// `now` (ms), for the keyboard test to show it
+    pub fn is_stuck(&self, code: KeyCode, now: u128) -> bool {
+        self.keyboards.values().any(|keyboard| {
+            keyboard.still_stuck.contains(&code)
+                || keyboard
+                    .held
+                    .get(&code)
+                    .is_some_and(|down| now.saturating_sub(*down) > STUCK_AFTER_MS)
+        })
+    }
+
+    /// A press a keyboard quirk explains, which isn't counted as held
+    pub fn phantom(&mut self, info: &DeviceInfo, code: KeyCode, timestamp: u128) {
+        let keyboard = self.keyboard(info);
+        keyboard.drop_stuck(timestamp);
+        if keyboard.held.len() >= GHOSTING_HELD {
+            keyboard.phantoms.insert(code);
+        }
+    }
+
+    /// Highest figure of any keyboard, for the header
+    pub fn max(&self) -> usize {
+        self.keyboards
+            .values()
+            .map(|keyboard| keyboard.max)
+            .max()
+            .unwrap_or(0)
+    }
+
+    pub fn clear(&mut self) {
+        self.keyboards.clear();
+    }
+
+    pub fn results(&self) -> Vec<SimultaneousKeysResult> {
+        self.keyboards
+            .values()
+            .filter(|keyboard| keyboard.max > 0)
+            .map(|keyboard| SimultaneousKeysResult {
+                device: keyboard.name.clone(),
+                max_held: keyboard.max,
+                keys: code_names(&keyboard.max_keys),
+                stuck_keys: code_names(&keyboard.stuck),
+                phantom_keys: code_names(&keyboard.phantoms),
+            })
+            .collect()
+    }
+}
+
+/// Mouse, touch and pen buttons (BTN_MISC up to the pen buttons) aren't keys
+pub fn is_button(code: KeyCode) -> bool {
+    (0x100..0x160).contains(&code.0)
+}
+
+fn code_names<'a>(codes: impl IntoIterator<Item = &'a KeyCode>) -> Vec<String> {
+    codes.into_iter().map(|code| format!("{code:?}")).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use evdev::BusType;
+
+    use super::*;
+    use crate::sim::sim_device;
+
+    fn keyboard() -> DeviceInfo {
+        sim_device("/dev/input/event3", "USB keyboard", None)
+    }
+
+    fn figure(keys: &SimultaneousKeys) -> (usize, Vec<String>, Vec<String>) {
+        let [result] = keys.results().try_into().unwrap();
+        (result.max_held, result.keys, result.stuck_keys)
+    }
+
+    #[test]
+    fn the_figure_is_the_most_keys_held_with_the_keys_that_made_it() {
+        let info = keyboard();
+        let mut keys = SimultaneousKeys::new();
+        keys.press(&info, KeyCode::KEY_A, 0);
+        keys.press(&info, KeyCode::KEY_S, 40);
+        keys.press(&info, KeyCode::KEY_D, 80);
+        keys.release(&info, KeyCode::KEY_S);
+        keys.press(&info, KeyCode::KEY_F, 120);
+        assert_eq!(keys.max(), 3);
+        // The first set of three stands, the later one only equals it
+        assert_eq!(
+            figure(&keys),
+            (
+                3,
+                vec!["KEY_A".into(), "KEY_S".into(), "KEY_D".into()],
+                vec![]
+            )
+        );
+
+        keys.press(&info, KeyCode::KEY_G, 160);
+        assert_eq!(figure(&keys).0, 4);
+        assert_eq!(figure(&keys).1, ["KEY_A", "KEY_D", "KEY_F", "KEY_G"]);
+        // Autorepeat of a held key isn't one more
+        keys.press(&info, KeyCode::KEY_G, 700);
+        assert_eq!(keys.max(), 4);
+    }
+
+    #[test]
+    fn a_missed_release_drops_out_without_inflating_the_figure() {
+        let info = keyboard();
+        let mut keys = SimultaneousKeys::new();
+        keys.press(&info, KeyCode::KEY_A, 0);
+        assert!(!keys.is_stuck(KeyCode::KEY_A, STUCK_AFTER_MS));
+        assert!(keys.is_stuck(KeyCode::KEY_A, STUCK_AFTER_MS + 1));
+
+        // Typed long after, A's release never came
+        let later = STUCK_AFTER_MS + 500;
+        keys.press(&info, KeyCode::KEY_S, later);
+        keys.press(&info, KeyCode::KEY_D, later + 40);
+        assert_eq!(
+            figure(&keys),
+            (
+                2,
+                vec!["KEY_S".into(), "KEY_D".into()],
+                vec!["KEY_A".into()]
+            )
+        );
+        assert!(keys.is_stuck(KeyCode::KEY_A, later));
+
+        // The release comes at last: no longer stuck, but the report keeps it
+        keys.release(&info, KeyCode::KEY_A);
+        assert!(!keys.is_stuck(KeyCode::KEY_A, later + 100));
+        assert_eq!(figure(&keys).2, ["KEY_A"]);
+
+        keys.clear();
+        assert_eq!(keys.max(), 0);
+        assert!(keys.results().is_empty());
+    }
+
+    #[test]
+    fn phantoms_count_with_two_keys_held() {
+        let info = keyboard();
+        let mut keys = SimultaneousKeys::new();
+        keys.press(&info, KeyCode::KEY_A, 0);
+        keys.phantom(&info, KeyCode::KEY_UNKNOWN, 10);
+        keys.press(&info, KeyCode::KEY_S, 20);
+        keys.phantom(&info, KeyCode::KEY_FN, 30);
+        let [result] = keys.results().try_into().unwrap();
+        assert_eq!(result.phantom_keys, ["KEY_FN"]);
+        // Phantoms aren't held
+        assert_eq!(result.max_held, 2);
+    }
+
+    #[test]
+    fn buttons_are_not_keys() {
+        let info = keyboard();
+        let mut keys = SimultaneousKeys::new();
+        for code in [KeyCode::BTN_LEFT, KeyCode::BTN_TOUCH, KeyCode::BTN_STYLUS] {
+            keys.press(&info, code, 0);
+        }
+        assert_eq!(keys.max(), 0);
+        assert!(keys.results().is_empty());
+        assert!(is_button(KeyCode(0x100)) && is_button(KeyCode(0x15f)));
+        assert!(!is_button(KeyCode(0xff)) && !is_button(KeyCode(0x160)));
+    }
+
+    #[test]
+    fn a_key_without_a_release_is_never_held() {
+        let at = DeviceInfo {
+            bus: BusType::BUS_I8042,
+            vendor: 0x0001,
+            ..sim_device("/dev/input/event2", "AT Translated Set 2 keyboard", None)
+        };
+        let mut keys = SimultaneousKeys::new();
+        keys.press(&at, KeyCode::KEY_PAUSE, 0);
+        keys.press(&at, KeyCode::KEY_A, 100);
+        assert!(!keys.is_stuck(KeyCode::KEY_PAUSE, 10 * STUCK_AFTER_MS));
+        keys.press(&at, KeyCode::KEY_S, 10 * STUCK_AFTER_MS);
+        keys.press(&at, KeyCode::KEY_D, 10 * STUCK_AFTER_MS + 40);
+        assert_eq!(
+            figure(&keys),
+            (
+                2,
+                vec!["KEY_S".into(), "KEY_D".into()],
+                vec!["KEY_A".into()]
+            )
+        );
+    }
+
+    #[test]
+    fn keyboards_are_counted_apart() {
+        let first = keyboard();
+        let second = sim_device("/dev/input/event4", "Second keyboard", None);
+        let mut keys = SimultaneousKeys::new();
+        keys.press(&first, KeyCode::KEY_A, 0);
+        keys.press(&second, KeyCode::KEY_B, 10);
+        keys.press(&second, KeyCode::KEY_C, 20);
+        let results = keys.results();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            (results[0].device.as_str(), results[0].max_held),
+            ("USB keyboard", 1)
+        );
+        assert_eq!(
+            (results[1].device.as_str(), results[1].max_held),
+            ("Second keyboard", 2)
+        );
+    }
+}
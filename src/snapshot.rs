@@ -0,0 +1,215 @@
+//! Snapshot tests of the screens' rendering. A screen is drawn into a ratatui
+//! `TestBackend` of a fixed size and the text of the buffer is compared with
+//! the file stored under `tests/snapshots`, so layout regressions (overlapping
+//! key labels, overlays off-screen) show up without the hardware. Colors
+//! aren't compared, except in the snapshots of `render_colors`. After an intended change, run the tests with
+//! `UPDATE_SNAPSHOTS=1` to write the files again and review their diff.
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use evdev::KeyCode;
+use ratatui::{Frame, Terminal, backend::TestBackend, layout::Rect, style::Color};
+use unicode_width::UnicodeWidthStr;
+
+use crate::Screen;
+use crate::device_class::DeviceClass;
+use crate::event_handler::{AppEvent, DeviceInfo};
+use crate::glyphs;
+use crate::sim::sim_device;
+
+/// The console of the field units and a large desktop terminal
+pub const SIZES: [(u16, u16); 2] = [(80, 24), (200, 50)];
+
+/// Sizes the layout invariants are checked at, from a cramped console to a
+/// wall display
+pub const LAYOUT_SIZES: [(u16, u16); 8] = [
+    (20, 8),
+    (40, 12),
+    (64, 20),
+    (80, 24),
+    (100, 30),
+    (132, 43),
+    (200, 50),
+    (320, 90),
+];
+
+/// Every rect of `rects` with an area lies within `area` and no two overlap.
+/// Empty rects are what didn't fit and aren't drawn.
+pub fn assert_within_and_apart(what: &str, area: Rect, rects: &[Rect]) {
+    let shown: Vec<&Rect> = rects.iter().filter(|rect| !rect.is_empty()).collect();
+    for (i, rect) in shown.iter().enumerate() {
+        assert!(
+            area.contains(rect.as_position())
+                && rect.right() <= area.right()
+                && rect.bottom() <= area.bottom(),
+            "{what} at {}x{}: {rect:?} outside {area:?}",
+            area.width,
+            area.height
+        );
+        if let Some(other) = shown[i + 1..]
+            .iter()
+            .find(|other| rect.intersects(***other))
+        {
+            panic!(
+                "{what} at {}x{}: {rect:?} overlaps {other:?}",
+                area.width, area.height
+            );
+        }
+    }
+}
+
+/// Text of a `width`×`height` frame drawn by `draw`, one line per row with
+/// the trailing blanks cut
+pub fn render(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let frame = terminal.draw(draw).unwrap();
+    let buffer = frame.buffer;
+
+    let mut text = String::new();
+    for y in 0..height {
+        let mut line = String::new();
+        // Cells under the right half of a wide character are left out
+        let mut covered = 0;
+        for x in 0..width {
+            if covered > 0 {
+                covered -= 1;
+                continue;
+            }
+            let symbol = buffer[(x, y)].symbol();
+            covered = symbol.width().saturating_sub(1);
+            line.push_str(symbol);
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// `render` with every cell on a background color shown as a letter for the
+/// color instead of its symbol, and the letters listed below the frame. For
+/// what only differs in color, e.g. the theme.
+pub fn render_colors(width: u16, height: u16, draw: impl FnOnce(&mut Frame)) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let frame = terminal.draw(draw).unwrap();
+    let buffer = frame.buffer;
+
+    let mut colors: Vec<Color> = Vec::new();
+    let mut text = String::new();
+    for y in 0..height {
+        let mut line = String::new();
+        for x in 0..width {
+            let cell = &buffer[(x, y)];
+            if cell.bg == Color::Reset {
+                line.push_str(cell.symbol());
+                continue;
+            }
+            let index = colors
+                .iter()
+                .position(|color| *color == cell.bg)
+                .unwrap_or_else(|| {
+                    colors.push(cell.bg);
+                    colors.len() - 1
+                });
+            line.push(char::from(b'A' + index as u8));
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    for (i, color) in colors.iter().enumerate() {
+        text.push_str(&format!("{}: {color}\n", char::from(b'A' + i as u8)));
+    }
+    text
+}
+
+/// Compare `text` with snapshot `name`, or write it with `UPDATE_SNAPSHOTS` set
+pub fn assert_snapshot(name: &str, text: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{name}.txt"));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, text).unwrap();
+        return;
+    }
+    let stored = fs::read_to_string(&path).unwrap_or_else(|e| {
+        panic!(
+            "no snapshot {} ({e}), run with UPDATE_SNAPSHOTS=1",
+            path.display()
+        )
+    });
+    assert!(
+        stored == text,
+        "{name} differs from its snapshot\n--- stored\n{stored}--- rendered\n{text}"
+    );
+}
+
+/// Draw `screen` at every size in `SIZES` and compare with the snapshots
+/// `name_80x24` and so on
+pub fn assert_screen(name: &str, screen: &dyn Screen) {
+    for (width, height) in SIZES {
+        let text = render(width, height, |frame| screen.draw(frame));
+        assert_snapshot(&format!("{name}_{width}x{height}"), &text);
+    }
+}
+
+/// `assert_screen` with the frames rewritten to the ASCII glyph set, compared
+/// with the snapshots `name_ascii_80x24` and so on. Every character left has
+/// to be one the console font has: ASCII or Latin-1.
+pub fn assert_screen_ascii(name: &str, screen: &dyn Screen) {
+    for (width, height) in SIZES {
+        let text = render(width, height, |frame| {
+            screen.draw(frame);
+            glyphs::to_ascii(frame.buffer_mut());
+        });
+        if let Some(c) = text.chars().find(|c| *c as u32 > 0xFF) {
+            panic!("{name} at {width}x{height} keeps {c:?} in ASCII\n{text}");
+        }
+        assert_snapshot(&format!("{name}_ascii_{width}x{height}"), &text);
+    }
+}
+
+/// The keyboard the scripted key events come from
+pub fn keyboard() -> Arc<DeviceInfo> {
+    Arc::new(sim_device("sim://keyboard", "Snapshot keyboard", None))
+}
+
+/// A press of `code` at `timestamp` (ms)
+pub fn press(code: KeyCode, timestamp: u128) -> AppEvent {
+    AppEvent::Key {
+        code,
+        info: keyboard(),
+        repeat: false,
+        timestamp,
+    }
+}
+
+pub fn release(code: KeyCode, timestamp: u128) -> AppEvent {
+    AppEvent::KeyReleased {
+        code,
+        info: keyboard(),
+        timestamp,
+    }
+}
+
+/// A touch panel with a 0-4095 range on both axes
+pub fn panel() -> Arc<DeviceInfo> {
+    Arc::new(DeviceInfo {
+        class: DeviceClass::Touchscreen,
+        ..sim_device("sim://panel", "Snapshot panel", Some(4095))
+    })
+}
+
+/// A raw sample of `info` at (`x`, `y`)
+pub fn touch(info: &Arc<DeviceInfo>, x: u16, y: u16, released: bool) -> AppEvent {
+    AppEvent::Touch {
+        x,
+        y,
+        timestamp: 0,
+        released,
+        pressure: None,
+        info: Some(Arc::clone(info)),
+        keepalive: false,
+    }
+}
@@ -0,0 +1,160 @@
+//! Unattended soak runs of the touchscreen test, e.g. 8 hours in the climate
+//! chamber with a robot finger tapping the panel. Every interval the counters
+//! gained since the last snapshot are recorded with a timestamp, building a time
+//! series for the report. Each snapshot is appended to the soak log the moment
+//! it is taken, so a crash late in the run keeps everything before it.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::{
+    report::{SoakResult, SoakSnapshot},
+    writer::{self, WriterHandle},
+};
+
+/// Time between snapshots, unless `--soak` says otherwise
+pub const DEFAULT_SOAK_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+const TREND_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Running totals of the touchscreen test that snapshots take the difference of
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SoakCounters {
+    pub samples: u64,
+    pub jumps: u64,
+    pub saturated: u64,
+}
+
+/// One soak run: snapshots taken so far and the counters at the last one
+pub struct SoakRecorder {
+    interval: Duration,
+    started: Instant,
+    // When the soak was stopped, None while it runs
+    stopped: Option<Instant>,
+    interval_start: Instant,
+    baseline: SoakCounters,
+    snapshots: Vec<SoakSnapshot>,
+    log: Option<WriterHandle>,
+}
+
+impl SoakRecorder {
+    /// Start a soak with the counters at `totals`, flushing snapshots to `log`
+    pub fn start(interval: Duration, totals: SoakCounters, log: Option<WriterHandle>) -> Self {
+        let now = Instant::now();
+        SoakRecorder {
+            interval,
+            started: now,
+            stopped: None,
+            interval_start: now,
+            baseline: totals,
+            snapshots: Vec::new(),
+            log,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.stopped.is_none()
+    }
+
+    /// The counters went back to zero, e.g. the statistics were reset
+    pub fn rebase(&mut self) {
+        self.baseline = SoakCounters::default();
+    }
+
+    /// Take a snapshot once the interval is up
+    pub fn tick(&mut self, totals: SoakCounters) {
+        if self.is_running() && self.interval_start.elapsed() >= self.interval {
+            self.snapshot(totals);
+        }
+    }
+
+    /// End the soak, the snapshots so far stay for the report
+    pub fn stop(&mut self) {
+        self.stopped.get_or_insert_with(Instant::now);
+    }
+
+    fn snapshot(&mut self, totals: SoakCounters) {
+        let now = Instant::now();
+        let seconds = now.duration_since(self.interval_start).as_secs_f32();
+        let samples = totals.samples.saturating_sub(self.baseline.samples);
+        let snapshot = SoakSnapshot {
+            unix_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            elapsed_secs: now.duration_since(self.started).as_secs(),
+            samples,
+            sample_rate: samples as f32 / seconds.max(f32::EPSILON),
+            // Recounting with another threshold can lower the total
+            jumps: totals.jumps.saturating_sub(self.baseline.jumps),
+            saturated_samples: totals.saturated.saturating_sub(self.baseline.saturated),
+        };
+
+        if let Some(log) = &self.log
+            && let Ok(json) = serde_json::to_string(&snapshot)
+        {
+            log.append(&writer::SOAK_LOG, json + "\n");
+        }
+        self.snapshots.push(snapshot);
+        self.baseline = totals;
+        self.interval_start = now;
+    }
+
+    /// Time since the start, up to the stop once stopped
+    pub fn elapsed(&self) -> Duration {
+        self.stopped
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started)
+    }
+
+    /// Time until the next snapshot
+    pub fn next_snapshot(&self) -> Duration {
+        self.interval.saturating_sub(self.interval_start.elapsed())
+    }
+
+    pub fn snapshot_count(&self) -> usize {
+        self.snapshots.len()
+    }
+
+    /// Jumps per minute in each interval, oldest first
+    pub fn jump_rates(&self) -> Vec<f32> {
+        let minutes = self.interval.as_secs_f32() / 60.0;
+        self.snapshots
+            .iter()
+            .map(|snapshot| snapshot.jumps as f32 / minutes.max(f32::EPSILON))
+            .collect()
+    }
+
+    pub fn result(&self) -> SoakResult {
+        SoakResult {
+            interval_secs: self.interval.as_secs(),
+            elapsed_secs: self.elapsed().as_secs(),
+            snapshots: self.snapshots.clone(),
+        }
+    }
+}
+
+/// The last `width` values as a one-line bar chart scaled to the largest of them
+pub fn trend(values: &[f32], width: usize) -> String {
+    let values = &values[values.len().saturating_sub(width)..];
+    let max = values.iter().copied().fold(0.0, f32::max);
+    values
+        .iter()
+        .map(|value| {
+            if max <= 0.0 {
+                return TREND_BLOCKS[0];
+            }
+            let level = (value / max * (TREND_BLOCKS.len() - 1) as f32).round() as usize;
+            TREND_BLOCKS[level.min(TREND_BLOCKS.len() - 1)]
+        })
+        .collect()
+}
+
+/// e.g. "2h 05m" or "14m 30s"
+pub fn format_elapsed(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!("{}h {:02}m", seconds / 3600, seconds % 3600 / 60)
+    } else {
+        format!("{}m {:02}s", seconds / 60, seconds % 60)
+    }
+}
@@ -0,0 +1,213 @@
+//! Jitter filter for the touchscreen test's display. The OS driver smooths the
+//! touch data before applications see it, so a trail that looks rough here can
+//! look fine on the desktop. With a filter selected the canvas shows its
+//! crosshair next to the raw one, and the jumps the filtered position makes
+//! are counted beside the raw jumps, telling how much of the panel's jitter a
+//! filter would hide. Only the display and that count see filtered positions,
+//! coverage, corners and the rest of the statistics stay on the raw samples.
+
+use color_eyre::{Result, eyre::eyre};
+
+/// Weight of the newest sample in the moving average unless configured
+pub const DEFAULT_ALPHA: f32 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Filter {
+    /// Exponential moving average, `alpha` (0-1] the weight of the newest sample
+    Ema { alpha: f32 },
+    /// Per-axis median of the last three samples, a despiker: a single wild
+    /// sample never shows, at the cost of one sample of lag
+    Median3,
+}
+
+impl Filter {
+    /// The filter named in the config, "ema" or "median3"
+    pub fn from_name(name: &str, alpha: f32) -> Result<Self> {
+        match name {
+            "ema" if alpha > 0.0 && alpha <= 1.0 => Ok(Filter::Ema { alpha }),
+            "ema" => Err(eyre!("the moving average weight has to be within (0, 1]")),
+            "median3" => Ok(Filter::Median3),
+            _ => Err(eyre!("unknown filter '{name}', use ema or median3")),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Filter::Ema { .. } => "ema",
+            Filter::Median3 => "median3",
+        }
+    }
+
+    /// For the overlay, e.g. "EMA 0.30"
+    pub fn label(&self) -> String {
+        match self {
+            Filter::Ema { alpha } => format!("EMA {alpha:.2}"),
+            Filter::Median3 => "median of 3".to_string(),
+        }
+    }
+
+    pub fn alpha(&self) -> Option<f32> {
+        match self {
+            Filter::Ema { alpha } => Some(*alpha),
+            Filter::Median3 => None,
+        }
+    }
+}
+
+/// A filter with the state of the stroke it follows
+pub struct Stabilizer {
+    filter: Filter,
+    // Moving average so far
+    average: Option<(f32, f32)>,
+    // The last three samples, newest last, and how many of them are real
+    recent: [(f32, f32); 3],
+    len: usize,
+}
+
+impl Stabilizer {
+    pub fn new(filter: Filter) -> Self {
+        Stabilizer {
+            filter,
+            average: None,
+            recent: [(0.0, 0.0); 3],
+            len: 0,
+        }
+    }
+
+    pub fn filter(&self) -> Filter {
+        self.filter
+    }
+
+    /// The finger lifted, the next sample starts a stroke of its own
+    pub fn reset(&mut self) {
+        self.average = None;
+        self.len = 0;
+    }
+
+    /// Take the sample at (`x`, `y`) and return the filtered position
+    pub fn apply(&mut self, x: f32, y: f32) -> (f32, f32) {
+        match self.filter {
+            Filter::Ema { alpha } => {
+                let (ax, ay) = self.average.unwrap_or((x, y));
+                let average = (ax + alpha * (x - ax), ay + alpha * (y - ay));
+                self.average = Some(average);
+                average
+            }
+            Filter::Median3 => {
+                self.recent.rotate_left(1);
+                self.recent[2] = (x, y);
+                self.len = (self.len + 1).min(3);
+                // Until there are three a spike can't be told from a move
+                if self.len < 3 {
+                    return (x, y);
+                }
+                let [a, b, c] = self.recent;
+                (median(a.0, b.0, c.0), median(a.1, b.1, c.1))
+            }
+        }
+    }
+}
+
+fn median(a: f32, b: f32, c: f32) -> f32 {
+    a.min(b).max(a.max(b).min(c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// X of the filtered positions of a stroke resting at 0, then at 100 from
+    /// sample `at` on, Y held at 50
+    fn step_response(filter: Filter, at: usize, samples: usize) -> Vec<f32> {
+        let mut stabilizer = Stabilizer::new(filter);
+        (0..samples)
+            .map(|i| {
+                let x = if i < at { 0.0 } else { 100.0 };
+                let (fx, fy) = stabilizer.apply(x, 50.0);
+                assert_eq!(fy, 50.0);
+                fx
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_moving_average_closes_in_without_overshoot() {
+        let response = step_response(Filter::Ema { alpha: 0.3 }, 5, 30);
+        assert!(response[..5].iter().all(|x| *x == 0.0));
+        // The gap shrinks to 70% each sample: 30, 51, 65.7 ...
+        for (n, x) in response[5..].iter().enumerate() {
+            let expected = 100.0 * (1.0 - 0.7f32.powi(n as i32 + 1));
+            assert!(
+                (x - expected).abs() < 0.01,
+                "sample {n}: {x}, not {expected}"
+            );
+        }
+        assert!(response.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert!(response.iter().all(|x| *x <= 100.0));
+        // Within a unit 13 samples after the step
+        assert!(100.0 - response[5 + 12] < 1.0);
+        assert!(100.0 - response[5 + 11] >= 1.0);
+    }
+
+    #[test]
+    fn a_full_weight_average_is_no_filter() {
+        let response = step_response(Filter::Ema { alpha: 1.0 }, 3, 8);
+        assert_eq!(response, [0.0, 0.0, 0.0, 100.0, 100.0, 100.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn the_median_follows_a_step_one_sample_late() {
+        let response = step_response(Filter::Median3, 4, 8);
+        assert_eq!(response, [0.0, 0.0, 0.0, 0.0, 0.0, 100.0, 100.0, 100.0]);
+        // Before there are three samples they go through as they are
+        let response = step_response(Filter::Median3, 1, 4);
+        assert_eq!(response, [0.0, 100.0, 100.0, 100.0]);
+    }
+
+    #[test]
+    fn the_median_hides_a_single_spike_but_not_two() {
+        let run = |xs: &[f32]| {
+            let mut stabilizer = Stabilizer::new(Filter::Median3);
+            xs.iter()
+                .map(|x| stabilizer.apply(*x, *x).0)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(run(&[0.0, 0.0, 900.0, 0.0, 0.0]), [0.0; 5]);
+        assert_eq!(
+            run(&[0.0, 0.0, 900.0, 900.0, 0.0, 0.0]),
+            [0.0, 0.0, 0.0, 900.0, 900.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn a_new_stroke_starts_where_it_lands() {
+        for filter in [Filter::Ema { alpha: 0.3 }, Filter::Median3] {
+            let mut stabilizer = Stabilizer::new(filter);
+            for _ in 0..5 {
+                stabilizer.apply(0.0, 0.0);
+            }
+            stabilizer.reset();
+            assert_eq!(stabilizer.apply(800.0, 600.0), (800.0, 600.0), "{filter:?}");
+            assert_eq!(stabilizer.apply(800.0, 600.0), (800.0, 600.0), "{filter:?}");
+        }
+    }
+
+    #[test]
+    fn filters_are_read_by_name() {
+        assert_eq!(
+            Filter::from_name("ema", 0.5).unwrap(),
+            Filter::Ema { alpha: 0.5 }
+        );
+        assert_eq!(Filter::from_name("median3", 0.0).unwrap(), Filter::Median3);
+        for alpha in [0.0, -0.2, 1.5] {
+            assert!(Filter::from_name("ema", alpha).is_err(), "{alpha}");
+        }
+        assert!(Filter::from_name("kalman", 0.3).is_err());
+        let ema = Filter::Ema { alpha: 0.3 };
+        assert_eq!(
+            (ema.name(), ema.label(), ema.alpha()),
+            ("ema", "EMA 0.30".to_string(), Some(0.3))
+        );
+        assert_eq!(Filter::Median3.alpha(), None);
+    }
+}
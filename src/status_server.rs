@@ -0,0 +1,243 @@
+//! Read-only status endpoint, so the line controller can poll every unit's
+//! progress instead of someone walking the line. Off unless the config sets
+//! `status.listen`, a Unix socket ("unix:/run/keyboard_test.sock") or a TCP
+//! address ("127.0.0.1:7070").
+//!
+//! Every request is answered with the current screen, the progress of the open
+//! test, the outcome of each test finished so far and the session metadata,
+//! as JSON or, when the request line mentions "text" (`GET /text`), as
+//! plain text. A request starting with GET gets an HTTP response, anything
+//! else the bare body. There are no commands. The run loop publishes the
+//! status, the server thread only reads it.
+
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixListener;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use serde::Serialize;
+
+use crate::ScreenId;
+use crate::logging;
+use crate::report::SessionReport;
+
+static STATUS: Lazy<Mutex<Status>> = Lazy::new(|| Mutex::new(Status::default()));
+
+// Set while a server runs, publishing is skipped otherwise
+static SERVING: AtomicBool = AtomicBool::new(false);
+
+// How often the listener checks for a shutdown between connections
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+// A client that doesn't send its request line in this time gets nothing
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(1);
+// Longest request line read, the rest is ignored
+const MAX_REQUEST: u64 = 4096;
+
+/// What the endpoint answers with
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Status {
+    pub screen: String,
+    /// Percent done of the open test, None on other screens
+    pub progress: Option<u8>,
+    /// Tests finished so far, in the report's order
+    pub tests: Vec<TestStatus>,
+    pub operator_id: Option<String>,
+    pub unit_serial: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestStatus {
+    pub title: String,
+    pub progress: Option<u8>,
+    /// None for tests without targets, see `ScreenResult::passed`
+    pub passed: Option<bool>,
+    pub summary: String,
+}
+
+impl Status {
+    /// One "key: value" line per field and one line per test
+    fn text(&self) -> String {
+        let shown = |progress: Option<u8>| progress.map_or("-".to_string(), |p| format!("{p}%"));
+        let mut text = format!(
+            "screen: {}\nprogress: {}\noperator: {}\nunit: {}\n",
+            self.screen,
+            shown(self.progress),
+            self.operator_id.as_deref().unwrap_or("-"),
+            self.unit_serial.as_deref().unwrap_or("-"),
+        );
+        for test in &self.tests {
+            let verdict = match test.passed {
+                Some(true) => "passed",
+                Some(false) => "FAILED",
+                None => "done",
+            };
+            text.push_str(&format!(
+                "{}: {} {verdict}, {}\n",
+                test.title,
+                shown(test.progress),
+                test.summary
+            ));
+        }
+        text
+    }
+}
+
+fn percent(share: f32) -> u8 {
+    (share.clamp(0.0, 1.0) * 100.0).round() as u8
+}
+
+/// Replace the published status, called by the run loop. Does nothing while
+/// no server runs.
+pub fn publish(screen: ScreenId, progress: Option<f32>, report: &SessionReport) {
+    if !SERVING.load(Ordering::Relaxed) {
+        return;
+    }
+    let status = Status {
+        screen: format!("{screen:?}"),
+        progress: progress.map(percent),
+        tests: report
+            .results
+            .iter()
+            .map(|result| TestStatus {
+                title: result.title().to_string(),
+                progress: result.progress().map(percent),
+                passed: result.passed(),
+                summary: result.summary(),
+            })
+            .collect(),
+        operator_id: report.metadata.operator_id.clone(),
+        unit_serial: report.metadata.unit_serial.clone(),
+    };
+    if let Ok(mut published) = STATUS.lock() {
+        *published = status;
+    }
+}
+
+enum Listener {
+    Unix(UnixListener),
+    Tcp(TcpListener),
+}
+
+/// Owner of the server thread
+pub struct StatusServer {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    // Removed again on shutdown
+    socket: Option<PathBuf>,
+}
+
+impl StatusServer {
+    /// Listen on `address`, "unix:<path>" or a TCP address. A failure to bind
+    /// is logged and returns None, the tool runs without the endpoint then.
+    pub fn spawn(address: &str) -> Option<Self> {
+        let (listener, socket) = match bind(address) {
+            Ok(bound) => bound,
+            Err(e) => {
+                logging::warn(&format!("Status endpoint not started on {address}: {e}"));
+                return None;
+            }
+        };
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        let thread = thread::Builder::new()
+            .name("status".to_string())
+            .spawn(move || serve(listener, &stopped))
+            .ok()?;
+        SERVING.store(true, Ordering::Relaxed);
+        Some(StatusServer {
+            stop,
+            thread,
+            socket,
+        })
+    }
+
+    /// Stop answering and remove the socket file
+    pub fn shutdown(self) {
+        SERVING.store(false, Ordering::Relaxed);
+        self.stop.store(true, Ordering::Relaxed);
+        let _ = self.thread.join();
+        if let Some(socket) = self.socket {
+            let _ = fs::remove_file(socket);
+        }
+    }
+}
+
+fn bind(address: &str) -> io::Result<(Listener, Option<PathBuf>)> {
+    let (listener, socket) = match address.strip_prefix("unix:") {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            // A socket left behind by a crashed run would make the bind fail
+            if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+                fs::remove_file(&path)?;
+            }
+            let listener = UnixListener::bind(&path)?;
+            listener.set_nonblocking(true)?;
+            (Listener::Unix(listener), Some(path))
+        }
+        None => {
+            let listener = TcpListener::bind(address)?;
+            listener.set_nonblocking(true)?;
+            (Listener::Tcp(listener), None)
+        }
+    };
+    Ok((listener, socket))
+}
+
+/// Answer connections one at a time until `stop` is set
+fn serve(listener: Listener, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        let answered = match &listener {
+            Listener::Unix(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+                answer(stream)
+            }),
+            Listener::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+                stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+                answer(stream)
+            }),
+        };
+        // A client that hung up or timed out only costs itself the answer
+        if answered.is_err_and(|e| e.kind() == io::ErrorKind::WouldBlock) {
+            thread::sleep(ACCEPT_POLL);
+        }
+    }
+}
+
+fn answer(mut stream: impl Read + Write) -> io::Result<()> {
+    let mut request = String::new();
+    BufReader::new((&mut stream).take(MAX_REQUEST)).read_line(&mut request)?;
+
+    let status = STATUS
+        .lock()
+        .map(|status| status.clone())
+        .unwrap_or_default();
+    let (body, content_type) = if request.contains("text") {
+        (status.text(), "text/plain")
+    } else {
+        let json = serde_json::to_string_pretty(&status).map_err(io::Error::other)?;
+        (json + "\n", "application/json")
+    };
+
+    if request.starts_with("GET") {
+        write!(
+            stream,
+            "HTTP/1.0 200 OK\r\nContent-Type: {content_type}; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            body.len()
+        )?;
+    }
+    stream.write_all(body.as_bytes())?;
+    stream.flush()
+}
@@ -0,0 +1,320 @@
+//! Touch strokes: everything between a touch going down and lifting again.
+
+use crate::report::StrokeSummary;
+use crate::units::MmScale;
+
+#[derive(Debug, Clone, Copy)]
+pub struct TouchPoint {
+    pub x: u16,
+    pub y: u16,
+    pub timestamp: u128, // Milliseconds since the unix epoch
+}
+
+/// Speed over one step of a stroke
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpeedSample {
+    /// Milliseconds since the stroke began, at the end of the step
+    pub at_ms: u64,
+    /// Calibrated units per second
+    pub speed: f32,
+}
+
+/// One down → samples → up sequence in calibrated coordinates.
+///
+/// Derived properties are updated as points arrive, so they survive
+/// `discard_points` when old strokes are trimmed to bound memory.
+#[derive(Debug, Clone)]
+pub struct Stroke {
+    pub points: Vec<TouchPoint>,
+    pub finished: bool,
+    samples: u32,
+    length: f32,
+    start: u128,
+    end: u128,
+    // (min_x, min_y, max_x, max_y)
+    bounds: (u16, u16, u16, u16),
+    // Speed between consecutive samples, dropped with the points
+    speeds: Vec<SpeedSample>,
+    // Start of the next speed step. Samples sharing a millisecond are merged
+    // into one step, their speed can't be measured.
+    step_start: TouchPoint,
+    max_speed: f32,
+    // (dx, dy, seconds) of the fastest step, for converting it to mm/s
+    max_speed_step: (f32, f32, f32),
+    // Largest speed change per second, speeding up or slowing down
+    max_acceleration: f32,
+}
+
+impl Stroke {
+    pub fn new(first: TouchPoint) -> Self {
+        Stroke {
+            points: vec![first],
+            finished: false,
+            samples: 1,
+            length: 0.0,
+            start: first.timestamp,
+            end: first.timestamp,
+            bounds: (first.x, first.y, first.x, first.y),
+            speeds: Vec::new(),
+            step_start: first,
+            max_speed: 0.0,
+            max_speed_step: (0.0, 0.0, 0.0),
+            max_acceleration: 0.0,
+        }
+    }
+
+    pub fn push(&mut self, point: TouchPoint) {
+        if let Some(last) = self.points.last() {
+            let dx = point.x as f32 - last.x as f32;
+            let dy = point.y as f32 - last.y as f32;
+            self.length += (dx * dx + dy * dy).sqrt();
+        }
+
+        let (min_x, min_y, max_x, max_y) = self.bounds;
+        self.bounds = (
+            min_x.min(point.x),
+            min_y.min(point.y),
+            max_x.max(point.x),
+            max_y.max(point.y),
+        );
+        self.end = self.end.max(point.timestamp);
+        self.samples += 1;
+        self.points.push(point);
+        self.record_speed(point);
+    }
+
+    fn record_speed(&mut self, point: TouchPoint) {
+        let from = self.step_start;
+        if point.timestamp <= from.timestamp {
+            return;
+        }
+        let seconds = (point.timestamp - from.timestamp) as f32 / 1000.0;
+        let dx = point.x as f32 - from.x as f32;
+        let dy = point.y as f32 - from.y as f32;
+        let speed = (dx * dx + dy * dy).sqrt() / seconds;
+
+        if let Some(previous) = self.speeds.last() {
+            let acceleration = (speed - previous.speed).abs() / seconds;
+            self.max_acceleration = self.max_acceleration.max(acceleration);
+        }
+        if speed > self.max_speed {
+            self.max_speed = speed;
+            self.max_speed_step = (dx, dy, seconds);
+        }
+        self.speeds.push(SpeedSample {
+            at_ms: (point.timestamp - self.start) as u64,
+            speed,
+        });
+        self.step_start = point;
+    }
+
+    /// Drop the stored points but keep the derived properties
+    pub fn discard_points(&mut self) {
+        self.points = Vec::new();
+        self.speeds = Vec::new();
+    }
+
+    pub fn end_time(&self) -> u128 {
+        self.end
+    }
+
+    pub fn duration_ms(&self) -> u128 {
+        self.end - self.start
+    }
+
+    /// Path length in calibrated units
+    pub fn length(&self) -> f32 {
+        self.length
+    }
+
+    pub fn sample_count(&self) -> u32 {
+        self.samples
+    }
+
+    pub fn bounding_box(&self) -> (u16, u16, u16, u16) {
+        self.bounds
+    }
+
+    /// Calibrated units per second, zero for taps without measurable duration
+    pub fn average_speed(&self) -> f32 {
+        match self.duration_ms() {
+            0 => 0.0,
+            ms => self.length / (ms as f32 / 1000.0),
+        }
+    }
+
+    /// Speed over the stroke's duration, empty once the points are discarded
+    pub fn speed_profile(&self) -> &[SpeedSample] {
+        &self.speeds
+    }
+
+    /// Fastest step in calibrated units per second
+    pub fn max_speed(&self) -> f32 {
+        self.max_speed
+    }
+
+    /// Fastest step in mm/s
+    pub fn max_speed_mm(&self, scale: MmScale) -> f32 {
+        let (dx, dy, seconds) = self.max_speed_step;
+        if seconds > 0.0 {
+            scale.distance_mm(dx, dy) / seconds
+        } else {
+            0.0
+        }
+    }
+
+    /// Largest change of speed between consecutive steps, calibrated units
+    /// per second squared
+    pub fn max_acceleration(&self) -> f32 {
+        self.max_acceleration
+    }
+
+    pub fn summary(&self) -> StrokeSummary {
+        StrokeSummary {
+            samples: self.samples,
+            duration_ms: self.duration_ms() as u64,
+            length: self.length,
+            average_speed: self.average_speed(),
+            max_speed: self.max_speed,
+            max_acceleration: self.max_acceleration,
+            bounding_box: self.bounding_box(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: u16, y: u16, timestamp: u128) -> TouchPoint {
+        TouchPoint { x, y, timestamp }
+    }
+
+    /// A stroke through `points`, the first one starting it
+    fn stroke(points: impl IntoIterator<Item = TouchPoint>) -> Stroke {
+        let mut points = points.into_iter();
+        let mut stroke = Stroke::new(points.next().unwrap());
+        for point in points {
+            stroke.push(point);
+        }
+        stroke
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() <= 1e-3 * b.abs().max(1.0)
+    }
+
+    #[test]
+    fn a_constant_speed_stroke_has_one_speed_throughout() {
+        // 3-4-5 steps every 10 ms: 500 units/s
+        let stroke =
+            stroke((0..=20).map(|i| point(100 + i * 3, 200 + i * 4, 1_000 + i as u128 * 10)));
+
+        assert_eq!(stroke.sample_count(), 21);
+        assert_eq!(stroke.duration_ms(), 200);
+        assert!(close(stroke.length(), 100.0), "{}", stroke.length());
+        assert_eq!(stroke.bounding_box(), (100, 200, 160, 280));
+        assert!(close(stroke.average_speed(), 500.0));
+        assert!(close(stroke.max_speed(), 500.0));
+        assert!(
+            stroke.max_acceleration() < 1e-2,
+            "{}",
+            stroke.max_acceleration()
+        );
+
+        let profile = stroke.speed_profile();
+        assert_eq!(profile.len(), 20);
+        assert!(profile.iter().all(|sample| close(sample.speed, 500.0)));
+        let times: Vec<u64> = profile.iter().map(|sample| sample.at_ms).collect();
+        assert_eq!(times, (1..=20).map(|i| i * 10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn a_steadily_accelerating_stroke_reports_its_acceleration() {
+        // x = a t² / 2 with a = 2000 units/s², a sample every 100 ms: the steps
+        // go at 100, 300, 500, ... units/s
+        let stroke = stroke((0..=10u16).map(|k| point(10 * k * k, 50, k as u128 * 100)));
+
+        let speeds: Vec<f32> = stroke.speed_profile().iter().map(|s| s.speed).collect();
+        let expected: Vec<f32> = (1..=10).map(|k| 100.0 * (2 * k - 1) as f32).collect();
+        assert!(
+            speeds.iter().zip(&expected).all(|(a, b)| close(*a, *b)),
+            "{speeds:?}"
+        );
+        assert!(close(stroke.max_speed(), 1900.0));
+        assert!(
+            close(stroke.max_acceleration(), 2000.0),
+            "{}",
+            stroke.max_acceleration()
+        );
+        // 1000 units in a second
+        assert!(close(stroke.average_speed(), 1000.0));
+    }
+
+    #[test]
+    fn slowing_down_counts_as_acceleration() {
+        let stroke = stroke((0..=10u16).map(|k| {
+            let left = 10 - k;
+            point(1000 - 10 * left * left, 50, k as u128 * 100)
+        }));
+        assert!(close(stroke.max_speed(), 1900.0));
+        assert!(
+            close(stroke.max_acceleration(), 2000.0),
+            "{}",
+            stroke.max_acceleration()
+        );
+    }
+
+    #[test]
+    fn samples_in_one_millisecond_are_one_step() {
+        let stroke = stroke([
+            point(0, 0, 0),
+            point(5, 0, 10),
+            point(10, 0, 10),
+            point(15, 0, 20),
+        ]);
+        assert_eq!(stroke.sample_count(), 4);
+        assert!(close(stroke.length(), 15.0));
+        let speeds: Vec<(u64, f32)> = stroke
+            .speed_profile()
+            .iter()
+            .map(|s| (s.at_ms, s.speed))
+            .collect();
+        // The second step runs from the first sample at 10 ms
+        assert_eq!(speeds, [(10, 500.0), (20, 1000.0)]);
+    }
+
+    #[test]
+    fn a_tap_has_no_speed() {
+        let stroke = stroke([point(40, 40, 500), point(40, 40, 500)]);
+        assert_eq!(stroke.duration_ms(), 0);
+        assert_eq!(stroke.average_speed(), 0.0);
+        assert_eq!(stroke.max_speed(), 0.0);
+        assert!(stroke.speed_profile().is_empty());
+        assert_eq!(stroke.max_speed_mm(MmScale { x: 0.1, y: 0.1 }), 0.0);
+    }
+
+    #[test]
+    fn the_fastest_step_converts_to_mm() {
+        // 30 units across and 20 down in 10 ms, at 0.1 mm across and 0.2 mm
+        // down a unit: 3 and 4 mm, 5 mm in 10 ms
+        let stroke = stroke([point(0, 0, 0), point(30, 20, 10), point(31, 20, 1_000)]);
+        let mm_per_s = stroke.max_speed_mm(MmScale { x: 0.1, y: 0.2 });
+        assert!(close(mm_per_s, 500.0), "{mm_per_s}");
+    }
+
+    #[test]
+    fn discarding_the_points_keeps_the_summary() {
+        let mut stroke = stroke((0..=20).map(|i| point(i * 3, i * 4, i as u128 * 10)));
+        let before = stroke.summary();
+        stroke.discard_points();
+        assert!(stroke.points.is_empty());
+        assert!(stroke.speed_profile().is_empty());
+        let after = stroke.summary();
+        assert_eq!(after.samples, before.samples);
+        assert_eq!(after.duration_ms, before.duration_ms);
+        assert_eq!(after.length, before.length);
+        assert_eq!(after.max_speed, before.max_speed);
+        assert_eq!(after.bounding_box, before.bounding_box);
+    }
+}
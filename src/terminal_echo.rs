@@ -0,0 +1,339 @@
+//! Keystrokes leaking into the shell behind the program. Run over SSH without
+//! a tty, or in a multiplexer with odd settings, raw mode may not take: the
+//! line discipline keeps collecting what the keyboard under test types and
+//! hands it to the shell after exit, where a line of test keys can run as a
+//! command.
+//!
+//! Two checks: at startup the terminal's settings are read back with `stty`
+//! (canonical input, echo and signals must be off), and during the keyboard
+//! test the terminal's own input is read alongside evdev. Letters the tested
+//! keyboard typed that come back on the terminal held back until a line is
+//! complete, or at all when raw mode couldn't be confirmed, mean the terminal
+//! isn't raw.
+
+use std::collections::VecDeque;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use evdev::KeyCode;
+use once_cell::sync::OnceCell;
+
+// Raw state found at startup, see `record_raw_state`
+static RAW_STATE: OnceCell<RawState> = OnceCell::new();
+
+/// Terminal settings that must be off in raw mode
+const COOKED_FLAGS: [&str; 3] = ["icanon", "echo", "isig"];
+
+/// A terminal character arriving this much later than its key press was held
+/// back by the line discipline; raw input arrives within milliseconds
+const HELD_BACK: Duration = Duration::from_millis(250);
+
+/// Key presses still waiting for their character on the terminal
+const ECHO_WINDOW: Duration = Duration::from_secs(10);
+
+/// Characters matched before a leak is reported, one stray match isn't enough
+const LEAK_MATCHES: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RawState {
+    Raw,
+    /// Settings still on that raw mode turns off
+    Cooked(Vec<&'static str>),
+    /// stty couldn't read the settings, e.g. stdin isn't a terminal
+    Unknown,
+}
+
+impl RawState {
+    /// Read the settings of the terminal on stdin
+    pub fn check() -> Self {
+        let output = Command::new("stty")
+            .arg("-a")
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::null())
+            .output();
+        match output {
+            Ok(output) if output.status.success() => {
+                RawState::parse(&String::from_utf8_lossy(&output.stdout))
+            }
+            _ => RawState::Unknown,
+        }
+    }
+
+    /// The state from `stty -a` output, where a flag is listed as `echo` when
+    /// it's on and `-echo` when it's off
+    pub fn parse(stty: &str) -> Self {
+        let words: Vec<&str> = stty.split_whitespace().collect();
+        let listed = |flag: &str| {
+            words
+                .iter()
+                .any(|word| word.trim_start_matches('-') == flag)
+        };
+        if !COOKED_FLAGS.iter().all(|flag| listed(flag)) {
+            return RawState::Unknown;
+        }
+        let on: Vec<&'static str> = COOKED_FLAGS
+            .into_iter()
+            .filter(|flag| words.contains(flag))
+            .collect();
+        if on.is_empty() {
+            RawState::Raw
+        } else {
+            RawState::Cooked(on)
+        }
+    }
+
+    /// "icanon, echo still on" for the warnings
+    pub fn describe(&self) -> String {
+        match self {
+            RawState::Raw => "raw".to_string(),
+            RawState::Cooked(flags) => format!("{} still on", flags.join(", ")),
+            RawState::Unknown => "settings unreadable, stdin may not be a terminal".to_string(),
+        }
+    }
+}
+
+/// Keep the state found after the terminal was taken over for the screens
+pub fn record_raw_state(state: RawState) {
+    let _ = RAW_STATE.set(state);
+}
+
+/// The state found at startup, Unknown when it wasn't checked
+pub fn raw_state() -> &'static RawState {
+    RAW_STATE.get().unwrap_or(&RawState::Unknown)
+}
+
+/// Matches the tested keyboard's presses against the terminal's input
+pub struct EchoDetector {
+    // Raw mode was confirmed at startup, so only held-back characters count
+    raw: bool,
+    pressed: VecDeque<(char, Instant)>,
+    matched: u32,
+    held_back: u32,
+}
+
+impl EchoDetector {
+    pub fn new(raw: bool) -> Self {
+        EchoDetector {
+            raw,
+            pressed: VecDeque::new(),
+            matched: 0,
+            held_back: 0,
+        }
+    }
+
+    /// A press on the tested keyboard, only letters and digits are followed
+    pub fn key(&mut self, code: KeyCode, now: Instant) {
+        self.expire(now);
+        if let Some(c) = key_char(code) {
+            self.pressed.push_back((c, now));
+        }
+    }
+
+    /// A character read from the terminal
+    pub fn terminal(&mut self, c: char, now: Instant) {
+        self.expire(now);
+        let c = c.to_ascii_lowercase();
+        if let Some(index) = self.pressed.iter().position(|(pressed, _)| *pressed == c) {
+            let (_, at) = self.pressed.remove(index).expect("index was just found");
+            self.matched += 1;
+            if now.duration_since(at) >= HELD_BACK {
+                self.held_back += 1;
+            }
+        }
+    }
+
+    /// Enough of the keyboard's characters reached the terminal in a way raw
+    /// mode doesn't deliver them
+    pub fn leaking(&self) -> bool {
+        let counted = if self.raw {
+            self.held_back
+        } else {
+            self.matched
+        };
+        counted >= LEAK_MATCHES
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while self
+            .pressed
+            .front()
+            .is_some_and(|(_, at)| now.duration_since(*at) > ECHO_WINDOW)
+        {
+            self.pressed.pop_front();
+        }
+    }
+}
+
+/// The character a key types without modifiers, for the keys every layout
+/// puts in the same place
+fn key_char(code: KeyCode) -> Option<char> {
+    const KEYS: [(KeyCode, char); 36] = [
+        (KeyCode::KEY_A, 'a'),
+        (KeyCode::KEY_B, 'b'),
+        (KeyCode::KEY_C, 'c'),
+        (KeyCode::KEY_D, 'd'),
+        (KeyCode::KEY_E, 'e'),
+        (KeyCode::KEY_F, 'f'),
+        (KeyCode::KEY_G, 'g'),
+        (KeyCode::KEY_H, 'h'),
+        (KeyCode::KEY_I, 'i'),
+        (KeyCode::KEY_J, 'j'),
+        (KeyCode::KEY_K, 'k'),
+        (KeyCode::KEY_L, 'l'),
+        (KeyCode::KEY_M, 'm'),
+        (KeyCode::KEY_N, 'n'),
+        (KeyCode::KEY_O, 'o'),
+        (KeyCode::KEY_P, 'p'),
+        (KeyCode::KEY_Q, 'q'),
+        (KeyCode::KEY_R, 'r'),
+        (KeyCode::KEY_S, 's'),
+        (KeyCode::KEY_T, 't'),
+        (KeyCode::KEY_U, 'u'),
+        (KeyCode::KEY_V, 'v'),
+        (KeyCode::KEY_W, 'w'),
+        (KeyCode::KEY_X, 'x'),
+        (KeyCode::KEY_Y, 'y'),
+        (KeyCode::KEY_Z, 'z'),
+        (KeyCode::KEY_0, '0'),
+        (KeyCode::KEY_1, '1'),
+        (KeyCode::KEY_2, '2'),
+        (KeyCode::KEY_3, '3'),
+        (KeyCode::KEY_4, '4'),
+        (KeyCode::KEY_5, '5'),
+        (KeyCode::KEY_6, '6'),
+        (KeyCode::KEY_7, '7'),
+        (KeyCode::KEY_8, '8'),
+        (KeyCode::KEY_9, '9'),
+    ];
+    KEYS.iter().find(|(key, _)| *key == code).map(|(_, c)| *c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `stty -a` of an xterm as a shell leaves it
+    const COOKED: &str = r"speed 38400 baud; rows 50; columns 160; line = 0;
+intr = ^C; quit = ^\; erase = ^?; kill = ^U; eof = ^D; eol = <undef>;
+eol2 = <undef>; swtch = <undef>; start = ^Q; stop = ^S; susp = ^Z; rprnt = ^R;
+werase = ^W; lnext = ^V; discard = ^O; min = 1; time = 0;
+-parenb -parodd -cmspar cs8 -hupcl -cstopb cread -clocal -crtscts
+-ignbrk -brkint -ignpar -parmrk -inpck -istrip -inlcr -igncr icrnl ixon -ixoff
+-iuclc -ixany -imaxbel iutf8
+opost -olcuc -ocrnl onlcr -onocr -onlret -ofill -ofdel nl0 cr0 tab0 bs0 vt0 ff0
+isig icanon iexten echo echoe echok -echonl -noflsh -xcase -tostop -echoprt
+echoctl echoke -flusho -extproc
+";
+
+    #[test]
+    fn stty_output_tells_raw_from_cooked() {
+        assert_eq!(
+            RawState::parse(COOKED),
+            RawState::Cooked(vec!["icanon", "echo", "isig"])
+        );
+
+        // What raw mode leaves, echoe and echok don't count as echo
+        let raw = COOKED
+            .replace("isig icanon", "-isig -icanon")
+            .replace(" echo echoe", " -echo echoe");
+        assert_eq!(RawState::parse(&raw), RawState::Raw);
+        assert_eq!(RawState::parse(&raw).describe(), "raw");
+
+        // Raw mode that only half took
+        let half = COOKED.replace("isig icanon", "-isig -icanon");
+        assert_eq!(RawState::parse(&half), RawState::Cooked(vec!["echo"]));
+        assert_eq!(RawState::parse(&half).describe(), "echo still on");
+
+        // Not stty's output at all
+        assert_eq!(RawState::parse(""), RawState::Unknown);
+        assert_eq!(
+            RawState::parse("stty: 'standard input': Inappropriate ioctl for device"),
+            RawState::Unknown
+        );
+        assert_eq!(
+            RawState::parse("-icanon -echo"),
+            RawState::Unknown,
+            "isig isn't listed"
+        );
+    }
+
+    #[test]
+    fn keys_coming_back_on_the_terminal_are_a_leak() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // Not raw: any of the keyboard's letters on the terminal count, the
+        // terminal's own case doesn't matter
+        let mut detector = EchoDetector::new(false);
+        for (i, (code, c)) in [
+            (KeyCode::KEY_Q, 'q'),
+            (KeyCode::KEY_W, 'W'),
+            (KeyCode::KEY_1, '1'),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let pressed = at(i as u64 * 100);
+            detector.key(code, pressed);
+            assert!(!detector.leaking());
+            detector.terminal(c, pressed + Duration::from_millis(5));
+        }
+        assert!(detector.leaking());
+
+        // Characters no tested key typed, keys without a character and each
+        // press matched once only
+        let mut detector = EchoDetector::new(false);
+        detector.key(KeyCode::KEY_A, at(0));
+        detector.key(KeyCode::KEY_LEFTSHIFT, at(10));
+        detector.key(KeyCode::KEY_F1, at(20));
+        for c in ['a', 'a', 'b', 'x', '\r'] {
+            detector.terminal(c, at(30));
+        }
+        assert_eq!(detector.matched, 1);
+        assert!(!detector.leaking());
+
+        // A press too long ago is forgotten
+        let mut detector = EchoDetector::new(false);
+        detector.key(KeyCode::KEY_A, at(0));
+        detector.terminal('a', at(0) + ECHO_WINDOW + Duration::from_millis(1));
+        assert_eq!(detector.matched, 0);
+    }
+
+    #[test]
+    fn in_raw_mode_only_held_back_characters_count() {
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // The program reads its own keys straight away in raw mode
+        let mut detector = EchoDetector::new(true);
+        for (i, code) in [
+            KeyCode::KEY_A,
+            KeyCode::KEY_S,
+            KeyCode::KEY_D,
+            KeyCode::KEY_F,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            detector.key(code, at(i as u64 * 100));
+        }
+        for (i, c) in "asdf".chars().enumerate() {
+            detector.terminal(c, at(i as u64 * 100 + 5));
+        }
+        assert_eq!(detector.matched, 4);
+        assert!(!detector.leaking());
+
+        // A line handed over on Enter arrives long after its keys
+        for (i, code) in [KeyCode::KEY_L, KeyCode::KEY_S, KeyCode::KEY_X]
+            .into_iter()
+            .enumerate()
+        {
+            detector.key(code, at(1000 + i as u64 * 100));
+        }
+        for c in "lsx".chars() {
+            detector.terminal(c, at(1200) + HELD_BACK);
+        }
+        assert_eq!(detector.held_back, 3);
+        assert!(detector.leaking());
+    }
+}
@@ -0,0 +1,181 @@
+//! Time spent in one test, for the headers, the report and minimum test
+//! durations: a site can require e.g. a keyboard burn-in of at least 5 minutes,
+//! and a test that passes earlier doesn't advance by itself until then. Time
+//! spent on other screens while a preserved test waits to be continued doesn't
+//! count.
+
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use ratatui::{style::Stylize, text::Span};
+
+use crate::report::TestTiming;
+
+pub struct TestTimer {
+    // Unix time in ms the test started
+    started_at_ms: u64,
+    // Time counted before the current stretch on the screen
+    counted: Duration,
+    // Start of the current stretch, None while on another screen
+    since: Option<Instant>,
+    min: Duration,
+}
+
+impl TestTimer {
+    /// Running from now, without a minimum
+    pub fn start() -> Self {
+        TestTimer {
+            started_at_ms: unix_time_ms(),
+            counted: Duration::ZERO,
+            since: Some(Instant::now()),
+            min: Duration::ZERO,
+        }
+    }
+
+    pub fn with_min(mut self, min: Duration) -> Self {
+        self.min = min;
+        self
+    }
+
+    pub fn set_min(&mut self, min: Duration) {
+        self.min = min;
+    }
+
+    /// A test starts over, keeping the minimum
+    pub fn restart(&mut self) {
+        *self = TestTimer::start().with_min(self.min);
+    }
+
+    /// The screen was left, stop counting until `resume`
+    pub fn pause(&mut self) {
+        if let Some(since) = self.since.take() {
+            self.counted += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.since.get_or_insert_with(Instant::now);
+    }
+
+    /// Count `by` as if it had passed on the screen, for tests of what waits
+    /// for the minimum
+    #[cfg(test)]
+    pub fn advance(&mut self, by: Duration) {
+        self.counted += by;
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.counted + self.since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    /// The test ran for its minimum duration, always true without one
+    pub fn min_met(&self) -> bool {
+        self.elapsed() >= self.min
+    }
+
+    /// "elapsed 03:42" for the headers, with the minimum while it isn't reached
+    pub fn label(&self) -> Span<'static> {
+        let elapsed = self.elapsed();
+        if self.min_met() {
+            format!("elapsed {}", format_elapsed(elapsed)).gray()
+        } else {
+            format!(
+                "elapsed {} of {}",
+                format_elapsed(elapsed),
+                format_elapsed(self.min)
+            )
+            .yellow()
+        }
+    }
+
+    pub fn timing(&self) -> TestTiming {
+        TestTiming {
+            started_at_ms: self.started_at_ms,
+            ended_at_ms: unix_time_ms(),
+            duration_ms: self.elapsed().as_millis() as u64,
+            min_duration_ms: self.min.as_millis() as u64,
+        }
+    }
+}
+
+/// e.g. "03:42", or "1:03:42" from an hour on
+pub fn format_elapsed(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds % 3600 / 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+fn unix_time_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn elapsed_time_reads_minutes_and_seconds_then_hours() {
+        let secs = Duration::from_secs;
+        assert_eq!(format_elapsed(Duration::ZERO), "00:00");
+        assert_eq!(format_elapsed(Duration::from_millis(59_999)), "00:59");
+        assert_eq!(format_elapsed(secs(3 * 60 + 42)), "03:42");
+        assert_eq!(format_elapsed(secs(59 * 60 + 59)), "59:59");
+        assert_eq!(format_elapsed(secs(3600)), "1:00:00");
+        assert_eq!(format_elapsed(secs(3600 + 3 * 60 + 42)), "1:03:42");
+        assert_eq!(format_elapsed(secs(26 * 3600 + 5)), "26:00:05");
+    }
+
+    #[test]
+    fn time_on_other_screens_is_not_counted() {
+        let mut timer = TestTimer::start();
+        // Three minutes on the screen, then the test is left
+        timer.since = Some(Instant::now() - Duration::from_secs(180));
+        timer.pause();
+        let paused = timer.elapsed();
+        assert!(paused >= Duration::from_secs(180));
+        assert!(paused < Duration::from_secs(181));
+
+        // Time away changes nothing
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(timer.elapsed(), paused);
+        timer.pause();
+        assert_eq!(timer.elapsed(), paused);
+
+        // Back on the screen it counts on from there
+        timer.resume();
+        assert!(timer.since.is_some());
+        timer.since = Some(Instant::now() - Duration::from_secs(42));
+        assert_eq!(timer.label().content, "elapsed 03:42");
+        assert!(timer.elapsed() >= paused + Duration::from_secs(42));
+    }
+
+    #[test]
+    fn the_minimum_holds_until_it_is_reached() {
+        let mut timer = TestTimer::start().with_min(Duration::from_secs(300));
+        timer.pause();
+        assert!(!timer.min_met());
+        assert_eq!(timer.label().content, "elapsed 00:00 of 05:00");
+
+        timer.advance(Duration::from_secs(299));
+        assert!(!timer.min_met());
+        timer.advance(Duration::from_secs(1));
+        assert!(timer.min_met());
+        assert_eq!(timer.label().content, "elapsed 05:00");
+        assert_eq!(timer.timing().min_duration_ms, 300_000);
+        assert_eq!(timer.timing().duration_ms, 300_000);
+
+        // A restart starts over, with the same minimum
+        timer.restart();
+        assert!(!timer.min_met());
+        assert!(TestTimer::start().min_met());
+    }
+}
@@ -0,0 +1,319 @@
+//! Single line text field edited with raw key codes.
+//!
+//! Only key presses reach the app, never releases, so Shift can't be tracked:
+//! letters are always upper case and shifted symbols can't be typed. That covers
+//! operator IDs and serial numbers.
+//!
+//! A field owns its focus. While focused it takes every key it can type, Q
+//! included, and Esc only gives the focus up; the screen sees the keys after
+//! that, so a second Esc leaves it. See [`TextInput::route`].
+
+use evdev::KeyCode;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
+};
+
+pub struct TextInput {
+    text: Vec<char>,
+    // Insert position, 0..=text.len()
+    cursor: usize,
+    max_len: usize,
+    focused: bool,
+}
+
+/// What a field made of a key, see [`TextInput::route`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Routed {
+    /// Typed or used for editing, the screen ignores it
+    Consumed,
+    /// Esc took the focus away, the screen ignores it
+    Unfocused,
+    /// Not for the field (unfocused, or Enter, Tab, ...), the screen handles it
+    Unused,
+}
+
+impl TextInput {
+    /// Field holding `initial` (cut to `max_len`), with the cursor at the end
+    pub fn new(initial: &str, max_len: usize) -> Self {
+        let text: Vec<char> = initial.chars().take(max_len).collect();
+        TextInput {
+            cursor: text.len(),
+            text,
+            max_len,
+            focused: false,
+        }
+    }
+
+    pub fn focus(&mut self) {
+        self.focused = true;
+    }
+
+    pub fn unfocus(&mut self) {
+        self.focused = false;
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Give a key to the field if it has the focus. Screens route every key
+    /// through this before their own shortcuts.
+    pub fn route(&mut self, code: KeyCode) -> Routed {
+        if !self.focused {
+            return Routed::Unused;
+        }
+        if code == KeyCode::KEY_ESC {
+            self.focused = false;
+            return Routed::Unfocused;
+        }
+        if self.handle_key(code) {
+            Routed::Consumed
+        } else {
+            Routed::Unused
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.text.iter().collect()
+    }
+
+    /// Apply an editing key. Returns false for keys the field doesn't use, so the
+    /// caller can handle them (Enter, Tab, arrows up/down, ...).
+    fn handle_key(&mut self, code: KeyCode) -> bool {
+        match code {
+            KeyCode::KEY_BACKSPACE => {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.text.remove(self.cursor);
+                }
+            }
+            KeyCode::KEY_DELETE => {
+                if self.cursor < self.text.len() {
+                    self.text.remove(self.cursor);
+                }
+            }
+            KeyCode::KEY_LEFT => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::KEY_RIGHT => self.cursor = (self.cursor + 1).min(self.text.len()),
+            KeyCode::KEY_HOME => self.cursor = 0,
+            KeyCode::KEY_END => self.cursor = self.text.len(),
+            code => {
+                let Some(c) = key_char(code) else {
+                    return false;
+                };
+                if self.text.len() < self.max_len {
+                    self.text.insert(self.cursor, c);
+                    self.cursor += 1;
+                }
+            }
+        }
+        true
+    }
+
+    /// Bordered field with `title`, the cursor shown as a highlighted cell when focused
+    pub fn draw(&self, frame: &mut Frame, area: Rect, title: &str) {
+        let focused = self.focused;
+        let border = if focused {
+            Style::default().yellow()
+        } else {
+            Style::default().dark_gray()
+        };
+
+        let mut spans: Vec<Span> = Vec::new();
+        let (before, after) = self.text.split_at(self.cursor);
+        spans.push(before.iter().collect::<String>().into());
+        if focused {
+            let under_cursor = after.first().copied().unwrap_or(' ');
+            spans.push(Span::styled(
+                under_cursor.to_string(),
+                Style::default().fg(Color::Black).bg(Color::Yellow),
+            ));
+            spans.push(after.iter().skip(1).collect::<String>().into());
+        } else {
+            spans.push(after.iter().collect::<String>().into());
+        }
+
+        let field = Paragraph::new(Line::from(spans)).block(
+            Block::bordered()
+                .title(format!(" {title} "))
+                .border_style(border),
+        );
+        frame.render_widget(field, area);
+    }
+}
+
+/// Character a key types without modifiers, letters in upper case
+pub fn key_char(code: KeyCode) -> Option<char> {
+    const LETTERS: [KeyCode; 26] = [
+        KeyCode::KEY_A,
+        KeyCode::KEY_B,
+        KeyCode::KEY_C,
+        KeyCode::KEY_D,
+        KeyCode::KEY_E,
+        KeyCode::KEY_F,
+        KeyCode::KEY_G,
+        KeyCode::KEY_H,
+        KeyCode::KEY_I,
+        KeyCode::KEY_J,
+        KeyCode::KEY_K,
+        KeyCode::KEY_L,
+        KeyCode::KEY_M,
+        KeyCode::KEY_N,
+        KeyCode::KEY_O,
+        KeyCode::KEY_P,
+        KeyCode::KEY_Q,
+        KeyCode::KEY_R,
+        KeyCode::KEY_S,
+        KeyCode::KEY_T,
+        KeyCode::KEY_U,
+        KeyCode::KEY_V,
+        KeyCode::KEY_W,
+        KeyCode::KEY_X,
+        KeyCode::KEY_Y,
+        KeyCode::KEY_Z,
+    ];
+    const DIGITS: [(KeyCode, KeyCode); 10] = [
+        (KeyCode::KEY_0, KeyCode::KEY_KP0),
+        (KeyCode::KEY_1, KeyCode::KEY_KP1),
+        (KeyCode::KEY_2, KeyCode::KEY_KP2),
+        (KeyCode::KEY_3, KeyCode::KEY_KP3),
+        (KeyCode::KEY_4, KeyCode::KEY_KP4),
+        (KeyCode::KEY_5, KeyCode::KEY_KP5),
+        (KeyCode::KEY_6, KeyCode::KEY_KP6),
+        (KeyCode::KEY_7, KeyCode::KEY_KP7),
+        (KeyCode::KEY_8, KeyCode::KEY_KP8),
+        (KeyCode::KEY_9, KeyCode::KEY_KP9),
+    ];
+
+    if let Some(i) = LETTERS.iter().position(|letter| *letter == code) {
+        return Some((b'A' + i as u8) as char);
+    }
+    if let Some(i) = DIGITS
+        .iter()
+        .position(|(digit, keypad)| *digit == code || *keypad == code)
+    {
+        return Some((b'0' + i as u8) as char);
+    }
+
+    match code {
+        KeyCode::KEY_SPACE => Some(' '),
+        KeyCode::KEY_MINUS | KeyCode::KEY_KPMINUS => Some('-'),
+        KeyCode::KEY_DOT | KeyCode::KEY_KPDOT => Some('.'),
+        KeyCode::KEY_SLASH | KeyCode::KEY_KPSLASH => Some('/'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn typed(field: &mut TextInput, keys: &[KeyCode]) -> Vec<Routed> {
+        keys.iter().map(|code| field.route(*code)).collect()
+    }
+
+    #[test]
+    fn an_unfocused_field_leaves_every_key_to_the_screen() {
+        let mut field = TextInput::new("AB", 10);
+        let routed = typed(
+            &mut field,
+            &[KeyCode::KEY_Q, KeyCode::KEY_ESC, KeyCode::KEY_1],
+        );
+        assert_eq!(routed, [Routed::Unused; 3]);
+        assert_eq!(field.text(), "AB");
+        assert!(!field.is_focused());
+    }
+
+    #[test]
+    fn esc_only_takes_the_focus_the_second_goes_to_the_screen() {
+        let mut field = TextInput::new("", 10);
+        field.focus();
+        let routed = typed(
+            &mut field,
+            &[
+                KeyCode::KEY_Q,
+                KeyCode::KEY_ESC,
+                KeyCode::KEY_ESC,
+                KeyCode::KEY_Q,
+            ],
+        );
+        assert_eq!(
+            routed,
+            [
+                Routed::Consumed,
+                Routed::Unfocused,
+                Routed::Unused,
+                Routed::Unused
+            ]
+        );
+        // Q is typed while focused, not after
+        assert_eq!(field.text(), "Q");
+
+        field.focus();
+        assert_eq!(field.route(KeyCode::KEY_W), Routed::Consumed);
+        assert_eq!(field.text(), "QW");
+    }
+
+    #[test]
+    fn keys_the_field_has_no_use_for_pass_through_while_focused() {
+        let mut field = TextInput::new("", 10);
+        field.focus();
+        let routed = typed(
+            &mut field,
+            &[
+                KeyCode::KEY_ENTER,
+                KeyCode::KEY_TAB,
+                KeyCode::KEY_UP,
+                KeyCode::KEY_F1,
+            ],
+        );
+        assert_eq!(routed, [Routed::Unused; 4]);
+        assert!(field.is_focused());
+        assert_eq!(field.text(), "");
+    }
+
+    #[test]
+    fn editing_keys_move_and_delete_at_the_cursor() {
+        let mut field = TextInput::new("", 5);
+        field.focus();
+        typed(
+            &mut field,
+            &[
+                KeyCode::KEY_A,
+                KeyCode::KEY_C,
+                KeyCode::KEY_LEFT,
+                KeyCode::KEY_B,
+                KeyCode::KEY_HOME,
+                KeyCode::KEY_KP1,
+                KeyCode::KEY_END,
+                KeyCode::KEY_MINUS,
+                // Full at 5
+                KeyCode::KEY_Z,
+            ],
+        );
+        assert_eq!(field.text(), "1ABC-");
+        typed(
+            &mut field,
+            &[
+                KeyCode::KEY_BACKSPACE,
+                KeyCode::KEY_HOME,
+                KeyCode::KEY_DELETE,
+                KeyCode::KEY_LEFT,
+                KeyCode::KEY_BACKSPACE,
+            ],
+        );
+        assert_eq!(field.text(), "ABC");
+    }
+
+    #[test]
+    fn initial_text_is_cut_to_the_length() {
+        let mut field = TextInput::new("SERIAL-123456", 6);
+        assert_eq!(field.text(), "SERIAL");
+        field.focus();
+        field.route(KeyCode::KEY_BACKSPACE);
+        assert_eq!(field.text(), "SERIA");
+    }
+}
@@ -0,0 +1,288 @@
+//! How test states look. The standard colors tell OK (green) from warnings
+//! (red, magenta) by hue alone, which red-green color blind operators can't
+//! see; the color-blind theme uses blue for OK, orange for warnings and white
+//! for what to press. Either way the warning states also carry a cue that
+//! doesn't need color: chattering keys get a `!` after the label, missed and
+//! mismatched guided keys a `?` and `~`, stuck keys blink, excluded keys are
+//! hatched, and jumps in the touch trail are drawn with `JUMP_CHAR`.
+//!
+//! Screens get a state's style and cue from `Theme::key` instead of picking
+//! colors themselves, and `KeyState::decoration` names every state, so a new
+//! one can't slip in with a color only.
+
+use ratatui::style::{Color, Modifier, Style, Stylize};
+
+use crate::press_colors::PressPalette;
+
+/// Trail cells of a step longer than the jump threshold
+pub const JUMP_CHAR: char = '#';
+
+// The color-blind palette
+const BLUE: Color = Color::Rgb(40, 110, 220);
+const ORANGE: Color = Color::Rgb(240, 150, 30);
+const WHITE: Color = Color::Rgb(235, 235, 235);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Theme {
+    #[default]
+    Standard,
+    ColorBlind,
+}
+
+impl Theme {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "standard" => Some(Theme::Standard),
+            "color-blind" => Some(Theme::ColorBlind),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Theme::Standard => "standard",
+            Theme::ColorBlind => "color-blind",
+        }
+    }
+
+    /// Style and cue of a key in `state`, pressed keys colored by `palette`
+    pub fn key(self, state: KeyState, palette: PressPalette) -> (Style, Decoration) {
+        let style = match (self, state) {
+            (_, KeyState::Untested) => Style::default(),
+            (_, KeyState::Excluded) => Style::default().dark_gray(),
+            (Theme::Standard, KeyState::Target) => Style::default().bg(Color::Cyan).black().bold(),
+            (Theme::Standard, KeyState::Missing | KeyState::Stuck) => {
+                Style::default().bg(Color::Red).white()
+            }
+            (Theme::Standard, KeyState::Mismatched | KeyState::Chatter) => {
+                Style::default().bg(Color::Magenta).white()
+            }
+            (Theme::Standard, KeyState::Partial) => Style::default().fg(Color::Yellow).bold(),
+            (Theme::Standard, KeyState::Pressed(count)) => {
+                Style::default().bg(palette.color(count)).black()
+            }
+            (Theme::ColorBlind, KeyState::Target) => Style::default().bg(WHITE).black().bold(),
+            (
+                Theme::ColorBlind,
+                KeyState::Missing | KeyState::Mismatched | KeyState::Chatter | KeyState::Stuck,
+            ) => Style::default().bg(ORANGE).black().bold(),
+            (Theme::ColorBlind, KeyState::Partial) => Style::default().fg(BLUE).bold(),
+            (Theme::ColorBlind, KeyState::Pressed(count)) => {
+                Style::default().bg(palette.safe_color(count)).black()
+            }
+        };
+        (style, state.decoration())
+    }
+
+    /// Labels and colors of the press count legend
+    pub fn press_legend(self, palette: PressPalette) -> Vec<(String, Color)> {
+        match self {
+            Theme::Standard => palette.legend(),
+            Theme::ColorBlind => palette.safe_legend(),
+        }
+    }
+
+    /// The selected entry of a menu, in the color of what to press
+    pub fn highlight(self) -> Style {
+        match self {
+            Theme::Standard => Style::default().black().on_yellow().bold(),
+            Theme::ColorBlind => Style::default().bg(WHITE).black().bold(),
+        }
+    }
+
+    /// Color of the jump cells of the touch trail
+    pub fn jump_color(self) -> Color {
+        match self {
+            Theme::Standard => Color::Red,
+            Theme::ColorBlind => ORANGE,
+        }
+    }
+}
+
+/// What a key of the keyboard test can show, most important first where a
+/// key is in several
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyState {
+    Excluded,
+    /// The key a guided run asks for
+    Target,
+    /// Not pressed in time in a guided run
+    Missing,
+    /// Another key came when a guided run asked for this one
+    Mismatched,
+    /// Held far longer than anyone holds a key, see `simultaneous_keys`
+    Stuck,
+    /// Pressed again right after its release, a bouncing switch
+    Chatter,
+    Untested,
+    /// Some of its codes fired but not all, e.g. only the Fn layer
+    Partial,
+    /// Pressed this many times
+    Pressed(usize),
+}
+
+impl KeyState {
+    /// The cue of the state that doesn't rely on color
+    pub fn decoration(self) -> Decoration {
+        let none = Decoration::default();
+        match self {
+            KeyState::Excluded => Decoration {
+                hatch: true,
+                ..none
+            },
+            KeyState::Missing => Decoration {
+                suffix: Some('?'),
+                ..none
+            },
+            KeyState::Mismatched => Decoration {
+                suffix: Some('~'),
+                ..none
+            },
+            KeyState::Stuck => Decoration {
+                blink: true,
+                ..none
+            },
+            KeyState::Chatter => Decoration {
+                suffix: Some('!'),
+                ..none
+            },
+            KeyState::Target | KeyState::Untested | KeyState::Partial | KeyState::Pressed(_) => {
+                none
+            }
+        }
+    }
+}
+
+/// A state's cue besides its color
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Decoration {
+    /// Appended to the key label
+    pub suffix: Option<char>,
+    /// The key blinks
+    pub blink: bool,
+    /// The key is filled with a hatch pattern
+    pub hatch: bool,
+}
+
+impl Decoration {
+    /// `style` at the blink's current phase. Blinking is done by reversing
+    /// the colors every half second, the console ignores the blink attribute.
+    pub fn apply(&self, style: Style) -> Style {
+        if self.blink && blink_phase() {
+            style.add_modifier(Modifier::REVERSED)
+        } else {
+            style
+        }
+    }
+}
+
+// The second half of every second
+fn blink_phase() -> bool {
+    let millis = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    millis % 1000 >= 500
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATES: [KeyState; 9] = [
+        KeyState::Excluded,
+        KeyState::Target,
+        KeyState::Missing,
+        KeyState::Mismatched,
+        KeyState::Stuck,
+        KeyState::Chatter,
+        KeyState::Untested,
+        KeyState::Partial,
+        KeyState::Pressed(3),
+    ];
+
+    /// Whether `state` is something wrong with the key. Exhaustive, so a new
+    /// state has to be sorted here and in turn checked for its cue.
+    fn is_warning(state: KeyState) -> bool {
+        match state {
+            KeyState::Excluded
+            | KeyState::Missing
+            | KeyState::Mismatched
+            | KeyState::Stuck
+            | KeyState::Chatter => true,
+            KeyState::Target | KeyState::Untested | KeyState::Partial | KeyState::Pressed(_) => {
+                false
+            }
+        }
+    }
+
+    #[test]
+    fn every_warning_has_a_cue_of_its_own() {
+        let mut cues = Vec::new();
+        for state in STATES {
+            let decoration = state.decoration();
+            if is_warning(state) {
+                assert_ne!(decoration, Decoration::default(), "{state:?} has no cue");
+                assert!(
+                    !cues.contains(&decoration),
+                    "{state:?} looks like another warning without color"
+                );
+                cues.push(decoration);
+            } else {
+                assert_eq!(decoration, Decoration::default(), "{state:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn both_themes_give_the_state_its_cue() {
+        let palette = PressPalette::default();
+        for theme in [Theme::Standard, Theme::ColorBlind] {
+            for state in STATES {
+                let (style, decoration) = theme.key(state, palette);
+                assert_eq!(decoration, state.decoration(), "{theme:?} {state:?}");
+                if is_warning(state) && state != KeyState::Excluded {
+                    assert_ne!(style, Style::default(), "{theme:?} {state:?}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn the_color_blind_theme_has_no_red_or_green() {
+        let palette = PressPalette::default();
+        let colors = STATES.iter().flat_map(|state| {
+            let (style, _) = Theme::ColorBlind.key(*state, palette);
+            [style.fg, style.bg]
+        });
+        for color in colors.flatten().chain([Theme::ColorBlind.jump_color()]) {
+            assert!(
+                !matches!(
+                    color,
+                    Color::Red | Color::LightRed | Color::Green | Color::LightGreen
+                ),
+                "{color:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn only_blinking_changes_with_the_clock() {
+        let style = Style::default().bg(Color::Red);
+        assert_eq!(Decoration::default().apply(style), style);
+        let chatter = KeyState::Chatter.decoration();
+        assert_eq!(chatter.apply(style), style);
+
+        // A blinking key is the same style reversed or not
+        let stuck = KeyState::Stuck.decoration().apply(style);
+        assert!(stuck == style || stuck == style.add_modifier(Modifier::REVERSED));
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for theme in [Theme::Standard, Theme::ColorBlind] {
+            assert_eq!(Theme::from_name(theme.name()), Some(theme));
+        }
+        assert_eq!(Theme::from_name("high-contrast"), None);
+    }
+}
@@ -0,0 +1,255 @@
+//! Corner checks of the touchscreen test. Dead corners are the most common
+//! resistive panel failure, and the cell grid can be mostly covered while a
+//! 5 mm strip in a corner never responds. Each corner, and with
+//! `--edge-midpoints` the middle of each edge, needs a touch-down within the
+//! margin of its edges to pass. With a region of interest the targets are the
+//! corners and edges of the region.
+
+use crate::{
+    report::{CornerResult, CornerTarget},
+    touch_roi::Roi,
+    units::MmScale,
+};
+
+/// Distance from the edges a corner touch-down has to land within
+pub const DEFAULT_CORNER_MARGIN_MM: f32 = 5.0;
+
+// Margin without a known panel size, 2.5% of the calibrated range
+const FALLBACK_MARGIN_UNITS: f32 = 25.0;
+
+// An edge midpoint counts within this distance of the middle, along the edge
+const MIDPOINT_SPAN: f32 = 100.0;
+
+const CALIBRATED_MAX: f32 = 999.0;
+const CALIBRATED_MIDDLE: f32 = CALIBRATED_MAX / 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Target {
+    const CORNERS: [Target; 4] = [
+        Target::TopLeft,
+        Target::TopRight,
+        Target::BottomLeft,
+        Target::BottomRight,
+    ];
+    const MIDPOINTS: [Target; 4] = [Target::Top, Target::Bottom, Target::Left, Target::Right];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Target::TopLeft => "top-left",
+            Target::TopRight => "top-right",
+            Target::BottomLeft => "bottom-left",
+            Target::BottomRight => "bottom-right",
+            Target::Top => "top",
+            Target::Bottom => "bottom",
+            Target::Left => "left",
+            Target::Right => "right",
+        }
+    }
+
+    /// Where the target sits in calibrated space on the whole panel, (0-999, 0-999)
+    pub fn position(&self) -> (f32, f32) {
+        match self {
+            Target::TopLeft => (0.0, 0.0),
+            Target::TopRight => (CALIBRATED_MAX, 0.0),
+            Target::BottomLeft => (0.0, CALIBRATED_MAX),
+            Target::BottomRight => (CALIBRATED_MAX, CALIBRATED_MAX),
+            Target::Top => (CALIBRATED_MIDDLE, 0.0),
+            Target::Bottom => (CALIBRATED_MIDDLE, CALIBRATED_MAX),
+            Target::Left => (0.0, CALIBRATED_MIDDLE),
+            Target::Right => (CALIBRATED_MAX, CALIBRATED_MIDDLE),
+        }
+    }
+
+    /// A touch-down at (x, y) lands within `margin` of the target's edges,
+    /// the target placed on `roi`
+    fn covers(&self, x: f32, y: f32, margin: (f32, f32), roi: &Roi) -> bool {
+        let (tx, ty) = roi.place(self.position());
+        let within = |value: f32, target: f32, limit: f32| (value - target).abs() <= limit;
+        match self {
+            Target::Top | Target::Bottom => within(x, tx, MIDPOINT_SPAN) && within(y, ty, margin.1),
+            Target::Left | Target::Right => within(x, tx, margin.0) && within(y, ty, MIDPOINT_SPAN),
+            _ => within(x, tx, margin.0) && within(y, ty, margin.1),
+        }
+    }
+}
+
+/// Corner targets of one session and which of them got a touch-down
+pub struct CornerChecks {
+    margin_mm: f32,
+    // Largest margin in calibrated units, the outermost cell of the grid
+    max_margin: (f32, f32),
+    targets: Vec<(Target, bool)>,
+    roi: Roi,
+}
+
+impl CornerChecks {
+    /// Corners with `margin_mm`, plus the edge midpoints if asked. `cell` is the
+    /// size of a grid cell in calibrated units, a touch-down has to land in the
+    /// outermost one whatever the margin.
+    pub fn new(margin_mm: f32, midpoints: bool, cell: (f32, f32)) -> Self {
+        let mut targets: Vec<Target> = Target::CORNERS.to_vec();
+        if midpoints {
+            targets.extend(Target::MIDPOINTS);
+        }
+        CornerChecks {
+            margin_mm,
+            max_margin: cell,
+            targets: targets.into_iter().map(|target| (target, false)).collect(),
+            roi: Roi::FULL,
+        }
+    }
+
+    /// Put the targets on the corners and edges of `roi`, the touch-downs so
+    /// far forgotten
+    pub fn set_roi(&mut self, roi: Roi) {
+        self.roi = roi;
+        self.reset();
+    }
+
+    /// Where `target` sits in calibrated space, on the region of interest
+    pub fn position(&self, target: Target) -> (f32, f32) {
+        self.roi.place(target.position())
+    }
+
+    pub fn reset(&mut self) {
+        for (_, touched) in &mut self.targets {
+            *touched = false;
+        }
+    }
+
+    /// Margin in calibrated units on each axis: the millimetres when the panel
+    /// size is known, else a fixed share of the calibrated range
+    pub fn margin_units(&self, scale: Option<MmScale>) -> (f32, f32) {
+        let (x, y) = match scale {
+            Some(scale) => (self.margin_mm / scale.x, self.margin_mm / scale.y),
+            None => (FALLBACK_MARGIN_UNITS, FALLBACK_MARGIN_UNITS),
+        };
+        (x.min(self.max_margin.0), y.min(self.max_margin.1))
+    }
+
+    /// The first sample of a touch, at calibrated (x, y)
+    pub fn touch_down(&mut self, x: u16, y: u16, scale: Option<MmScale>) {
+        let margin = self.margin_units(scale);
+        for (target, touched) in &mut self.targets {
+            if target.covers(x as f32, y as f32, margin, &self.roi) {
+                *touched = true;
+            }
+        }
+    }
+
+    pub fn targets(&self) -> &[(Target, bool)] {
+        &self.targets
+    }
+
+    pub fn satisfied(&self) -> usize {
+        self.targets.iter().filter(|(_, touched)| *touched).count()
+    }
+
+    pub fn passed(&self) -> bool {
+        self.satisfied() == self.targets.len()
+    }
+
+    pub fn result(&self, scale: Option<MmScale>) -> CornerResult {
+        CornerResult {
+            margin_mm: scale.map(|_| self.margin_mm),
+            margin_units: self.margin_units(scale),
+            targets: self
+                .targets
+                .iter()
+                .map(|(target, touched)| CornerTarget {
+                    name: target.name().to_string(),
+                    touched: *touched,
+                })
+                .collect(),
+            passed: self.passed(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The cells of the 16 x 12 grid
+    const CELL: (f32, f32) = (CALIBRATED_MAX / 16.0, CALIBRATED_MAX / 12.0);
+
+    fn checks(roi: Roi) -> CornerChecks {
+        let mut checks = CornerChecks::new(DEFAULT_CORNER_MARGIN_MM, true, CELL);
+        checks.set_roi(roi);
+        checks
+    }
+
+    fn touched(checks: &CornerChecks) -> Vec<&'static str> {
+        checks
+            .targets()
+            .iter()
+            .filter(|(_, touched)| *touched)
+            .map(|(target, _)| target.name())
+            .collect()
+    }
+
+    #[test]
+    fn a_corner_region_is_passed_on_its_own_corners() {
+        let mut checks = checks(Roi::new([0.0, 0.0, 0.25, 0.25]).unwrap());
+        // The panel corners the bezel covers
+        for (x, y) in [(999, 0), (0, 999), (999, 999)] {
+            checks.touch_down(x, y, None);
+        }
+        assert!(touched(&checks).is_empty());
+
+        for (x, y) in [(0, 0), (249, 0), (0, 249), (249, 249)] {
+            checks.touch_down(x, y, None);
+        }
+        assert_eq!(checks.satisfied(), 4);
+        assert!(!checks.passed());
+        for (x, y) in [(125, 0), (125, 249), (0, 125), (249, 125)] {
+            checks.touch_down(x, y, None);
+        }
+        assert!(checks.passed());
+    }
+
+    #[test]
+    fn an_edge_region_moves_the_inner_targets_only() {
+        let roi = Roi::new([0.75, 0.0, 1.0, 1.0]).unwrap();
+        let mut checks = checks(roi);
+        assert_eq!(checks.position(Target::TopRight), (CALIBRATED_MAX, 0.0));
+        let (left, middle) = checks.position(Target::Left);
+        assert!((left - 749.25).abs() < 0.01 && (middle - 499.5).abs() < 0.01);
+
+        // Within the fallback margin of the region's left edge, or not
+        checks.touch_down(700, 500, None);
+        assert!(touched(&checks).is_empty());
+        checks.touch_down(770, 500, None);
+        assert_eq!(touched(&checks), ["left"]);
+        // The top midpoint moved to the middle of the strip
+        checks.touch_down(500, 0, None);
+        assert_eq!(touched(&checks), ["left"]);
+        checks.touch_down(874, 0, None);
+        assert_eq!(touched(&checks), ["top", "left"]);
+
+        // A new region starts the targets over
+        checks.set_roi(Roi::FULL);
+        assert_eq!(checks.satisfied(), 0);
+        checks.touch_down(0, 500, None);
+        assert_eq!(touched(&checks), ["left"]);
+    }
+
+    #[test]
+    fn the_margin_never_reaches_past_the_outermost_cell() {
+        let checks = checks(Roi::FULL);
+        assert_eq!(checks.margin_units(None), (25.0, 25.0));
+        let coarse = MmScale { x: 0.01, y: 0.01 };
+        assert_eq!(checks.margin_units(Some(coarse)), CELL);
+    }
+}
@@ -0,0 +1,390 @@
+//! On-screen keypads for prompts that would otherwise need a keyboard, which the
+//! GPS tablet variant doesn't have: Yes/No for confirmations and a numeric pad.
+//! A [`TouchPad`] draws its question and keys as a centered box and hit-tests
+//! taps against the same layout, the way [`TouchButtons`](crate::touch_nav::TouchButtons)
+//! does for the corner buttons.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Frame,
+    layout::{Margin, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+};
+
+use crate::touch_nav::{TAP_MAX_DURATION, cell_at};
+
+// A second tap of the same key this soon is a bounce or a nervous double tap,
+// and so is a tap this soon after the pad opened
+const DOUBLE_TAP_GUARD: Duration = Duration::from_millis(400);
+
+const KEY_HEIGHT: u16 = 3;
+
+/// A key of a [`TouchPad`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PadKey {
+    Yes,
+    No,
+    Digit(u8),
+    Backspace,
+    Enter,
+}
+
+impl PadKey {
+    fn label(self) -> String {
+        match self {
+            PadKey::Yes => "Yes".to_string(),
+            PadKey::No => "No".to_string(),
+            PadKey::Digit(digit) => digit.to_string(),
+            PadKey::Backspace => "⌫".to_string(),
+            PadKey::Enter => "OK".to_string(),
+        }
+    }
+}
+
+/// A question with a grid of tappable keys below it. A key counts when the
+/// finger goes down and comes up on it within a short tap.
+pub struct TouchPad {
+    question: Vec<String>,
+    rows: Vec<Vec<PadKey>>,
+    key_width: u16,
+    // Frame area of the last draw, hit testing lays the pad out in it again
+    area: Cell<Rect>,
+    // Key under the finger when the current touch went down
+    pressed: Option<(PadKey, Instant)>,
+    touching: bool,
+    last_tap: Option<(PadKey, Instant)>,
+    opened: Instant,
+}
+
+impl TouchPad {
+    fn new(question: &[&str], rows: Vec<Vec<PadKey>>, key_width: u16) -> Self {
+        TouchPad {
+            question: question.iter().map(|line| line.to_string()).collect(),
+            rows,
+            key_width,
+            area: Cell::new(Rect::default()),
+            pressed: None,
+            touching: false,
+            last_tap: None,
+            opened: Instant::now(),
+        }
+    }
+
+    /// Yes and No under the question
+    pub fn yes_no(question: &[&str]) -> Self {
+        TouchPad::new(question, vec![vec![PadKey::Yes, PadKey::No]], 10)
+    }
+
+    /// Digits 1-9 in rows of three, then backspace, 0 and OK
+    #[allow(dead_code)] // No prompt takes numbers by touch yet
+    pub fn numeric(question: &[&str]) -> Self {
+        let mut rows: Vec<Vec<PadKey>> = (0..3)
+            .map(|row| (1..=3).map(|col| PadKey::Digit(row * 3 + col)).collect())
+            .collect();
+        rows.push(vec![PadKey::Backspace, PadKey::Digit(0), PadKey::Enter]);
+        TouchPad::new(question, rows, 7)
+    }
+
+    /// The box centered in `area` and every key's cell in it
+    pub fn layout(&self, area: Rect) -> (Rect, Vec<(PadKey, Rect)>) {
+        let widest_row = self.rows.iter().map(Vec::len).max().unwrap_or(0) as u16;
+        let keys_width = widest_row * (self.key_width + 1);
+        let text_width = self
+            .question
+            .iter()
+            .map(|line| line.chars().count() as u16)
+            .max()
+            .unwrap_or(0);
+        let width = (keys_width.max(text_width) + 4).min(area.width);
+        let height = (self.question.len() as u16 + 1 + self.rows.len() as u16 * KEY_HEIGHT + 2)
+            .min(area.height);
+        let rect = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        // Keys that don't fit are cut at the border, not drawn over it
+        let inner = rect.inner(Margin::new(1, 1));
+        let mut keys = Vec::new();
+        let mut y = rect.y + 1 + self.question.len() as u16 + 1;
+        for row in &self.rows {
+            let row_width = row.len() as u16 * (self.key_width + 1) - 1;
+            let mut x = rect.x + rect.width.saturating_sub(row_width) / 2;
+            for key in row {
+                let cell = Rect {
+                    x,
+                    y,
+                    width: self.key_width,
+                    height: KEY_HEIGHT,
+                }
+                .intersection(inner);
+                if !cell.is_empty() {
+                    keys.push((*key, cell));
+                }
+                x += self.key_width + 1;
+            }
+            y += KEY_HEIGHT;
+        }
+        (rect, keys)
+    }
+
+    /// Draw the pad over the middle of the screen, the key under the finger highlighted
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        self.area.set(area);
+        let (rect, keys) = self.layout(area);
+
+        let lines: Vec<Line> = self
+            .question
+            .iter()
+            .map(|line| Line::from(line.clone().bold()).centered())
+            .collect();
+        frame.render_widget(Clear, rect);
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().border_style(Style::default().yellow()))
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+            rect,
+        );
+
+        let pressed = self.pressed.map(|(key, _)| key);
+        for (key, cell) in keys {
+            let style = if Some(key) == pressed {
+                Style::default().bg(Color::Yellow).fg(Color::Black).bold()
+            } else {
+                Style::default().bold()
+            };
+            frame.render_widget(
+                Paragraph::new(key.label())
+                    .centered()
+                    .block(Block::bordered().border_style(Style::default().yellow()))
+                    .style(style),
+                cell,
+            );
+        }
+    }
+
+    /// Touch at a position in `0..=max` on each axis spread over `area`, e.g.
+    /// calibrated coordinates over the canvas they are drawn on
+    pub fn handle_scaled_touch(
+        &mut self,
+        (x, y): (u16, u16),
+        max: (u32, u32),
+        area: Rect,
+        released: bool,
+    ) -> Option<PadKey> {
+        let cell = cell_at(x as u32, y as u32, max, area);
+        self.handle_touch(Some(cell), released)
+    }
+
+    /// Touch down or up at a terminal cell. Returns the key when a tap started
+    /// and ended on it, unless the same key was tapped a moment ago or the pad
+    /// only just opened, e.g. under the second tap of the one that opened it.
+    pub fn handle_touch(&mut self, cell: Option<(u16, u16)>, released: bool) -> Option<PadKey> {
+        let key = cell.and_then(|cell| {
+            self.layout(self.area.get())
+                .1
+                .into_iter()
+                .find(|(_, rect)| rect.contains(cell.into()))
+                .map(|(key, _)| key)
+        });
+
+        if !released {
+            if !self.touching {
+                self.touching = true;
+                self.pressed = key.map(|key| (key, Instant::now()));
+            }
+            return None;
+        }
+
+        self.touching = false;
+        let (pressed, since) = self.pressed.take()?;
+        if key != Some(pressed) || since.elapsed() > TAP_MAX_DURATION {
+            return None;
+        }
+        let bounced = self.opened.elapsed() < DOUBLE_TAP_GUARD
+            || self
+                .last_tap
+                .is_some_and(|(last, at)| last == pressed && at.elapsed() < DOUBLE_TAP_GUARD);
+        self.last_tap = Some((pressed, Instant::now()));
+        (!bounced).then_some(pressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::snapshot::{LAYOUT_SIZES, assert_within_and_apart};
+
+    const AREA: Rect = Rect {
+        x: 0,
+        y: 0,
+        width: 80,
+        height: 24,
+    };
+
+    /// `pad` as if drawn in `AREA` long enough ago to take taps
+    fn opened(mut pad: TouchPad) -> TouchPad {
+        pad.area.set(AREA);
+        pad.opened = ago(Duration::from_secs(1));
+        pad
+    }
+
+    fn ago(duration: Duration) -> Instant {
+        Instant::now().checked_sub(duration).unwrap()
+    }
+
+    /// Middle cell of `key` in the pad's layout
+    fn center(pad: &TouchPad, key: PadKey) -> (u16, u16) {
+        let (_, keys) = pad.layout(pad.area.get());
+        let (_, rect) = keys.into_iter().find(|(k, _)| *k == key).unwrap();
+        (rect.x + rect.width / 2, rect.y + rect.height / 2)
+    }
+
+    fn tap_key(pad: &mut TouchPad, key: PadKey) -> Option<PadKey> {
+        let cell = center(pad, key);
+        tap(pad, cell)
+    }
+
+    fn tap(pad: &mut TouchPad, cell: (u16, u16)) -> Option<PadKey> {
+        pad.handle_touch(Some(cell), false);
+        pad.handle_touch(Some(cell), true)
+    }
+
+    #[test]
+    fn keys_stay_inside_the_box_and_apart() {
+        for pad in [
+            TouchPad::yes_no(&["Did the LED light?"]),
+            TouchPad::numeric(&["PIN"]),
+        ] {
+            for (width, height) in LAYOUT_SIZES {
+                // Off the origin, as in a split screen
+                let area = Rect::new(3, 2, width, height);
+                let (rect, keys) = pad.layout(area);
+                assert_within_and_apart("pad", area, &[rect]);
+                let cells: Vec<Rect> = keys.iter().map(|(_, cell)| *cell).collect();
+                // Inside the border, below the question
+                let inner = Rect {
+                    y: rect.y + 1 + pad.question.len() as u16,
+                    height: rect.height.saturating_sub(2 + pad.question.len() as u16),
+                    ..rect
+                };
+                assert_within_and_apart("pad keys", inner, &cells);
+            }
+        }
+    }
+
+    #[test]
+    fn the_box_is_centered_and_the_keys_read_in_order() {
+        let pad = TouchPad::numeric(&["Unit number?"]);
+        let (rect, keys) = pad.layout(AREA);
+        assert_eq!(rect.x - AREA.x, AREA.right() - rect.right());
+        assert!((rect.y - AREA.y).abs_diff(AREA.bottom() - rect.bottom()) <= 1);
+
+        let order: Vec<PadKey> = keys.iter().map(|(key, _)| *key).collect();
+        let mut expected: Vec<PadKey> = (1..=9).map(PadKey::Digit).collect();
+        expected.extend([PadKey::Backspace, PadKey::Digit(0), PadKey::Enter]);
+        assert_eq!(order, expected);
+        // Three to a row, left to right and top to bottom
+        for row in keys.chunks(3) {
+            assert!(row.windows(2).all(|pair| pair[0].1.x < pair[1].1.x));
+            assert!(row.iter().all(|(_, cell)| cell.y == row[0].1.y));
+        }
+        assert!(keys.chunks(3).map(|row| row[0].1.y).is_sorted());
+    }
+
+    #[test]
+    fn keys_that_do_not_fit_are_left_out() {
+        let pad = TouchPad::numeric(&["Unit number?"]);
+        let (_, keys) = pad.layout(Rect::new(0, 0, 20, 8));
+        assert!(keys.len() < 12);
+        assert!(keys.iter().all(|(_, cell)| !cell.is_empty()));
+    }
+
+    #[test]
+    fn a_tap_on_a_key_returns_it() {
+        let mut pad = opened(TouchPad::yes_no(&["Did the LED light?"]));
+        assert_eq!(tap_key(&mut pad, PadKey::Yes), Some(PadKey::Yes));
+        assert_eq!(tap_key(&mut pad, PadKey::No), Some(PadKey::No));
+
+        let mut pad = opened(TouchPad::numeric(&["PIN"]));
+        for key in [
+            PadKey::Digit(7),
+            PadKey::Digit(0),
+            PadKey::Backspace,
+            PadKey::Enter,
+        ] {
+            assert_eq!(tap_key(&mut pad, key), Some(key));
+        }
+    }
+
+    #[test]
+    fn taps_between_or_off_keys_return_nothing() {
+        let mut pad = opened(TouchPad::yes_no(&["Did the LED light?"]));
+        let (rect, _) = pad.layout(AREA);
+        // The question, the gap between the keys and outside the box
+        let (yes, no) = (center(&pad, PadKey::Yes), center(&pad, PadKey::No));
+        for cell in [
+            (rect.x + 2, rect.y + 1),
+            ((yes.0 + no.0) / 2, yes.1),
+            (0, 0),
+        ] {
+            assert_eq!(tap(&mut pad, cell), None, "{cell:?}");
+        }
+    }
+
+    #[test]
+    fn sliding_off_the_key_or_holding_cancels() {
+        let mut pad = opened(TouchPad::yes_no(&["Did the LED light?"]));
+        pad.handle_touch(Some(center(&pad, PadKey::Yes)), false);
+        assert_eq!(pad.handle_touch(Some(center(&pad, PadKey::No)), true), None);
+
+        pad.handle_touch(Some(center(&pad, PadKey::Yes)), false);
+        pad.pressed = pad
+            .pressed
+            .map(|(key, _)| (key, ago(TAP_MAX_DURATION + Duration::from_millis(100))));
+        assert_eq!(
+            pad.handle_touch(Some(center(&pad, PadKey::Yes)), true),
+            None
+        );
+    }
+
+    #[test]
+    fn double_taps_and_taps_right_after_opening_are_ignored() {
+        let mut pad = TouchPad::yes_no(&["Start a new session?"]);
+        pad.area.set(AREA);
+        // The tap that opened the pad, still bouncing
+        assert_eq!(tap_key(&mut pad, PadKey::Yes), None);
+
+        let mut pad = opened(TouchPad::yes_no(&["Start a new session?"]));
+        let yes = center(&pad, PadKey::Yes);
+        assert_eq!(tap(&mut pad, yes), Some(PadKey::Yes));
+        assert_eq!(tap(&mut pad, yes), None);
+        // Another key right away is a decision, not a bounce
+        assert_eq!(tap_key(&mut pad, PadKey::No), Some(PadKey::No));
+        pad.last_tap = Some((PadKey::Yes, ago(DOUBLE_TAP_GUARD)));
+        assert_eq!(tap(&mut pad, yes), Some(PadKey::Yes));
+    }
+
+    #[test]
+    fn scaled_touches_land_on_the_key_drawn_there() {
+        let mut pad = opened(TouchPad::yes_no(&["Did the LED light?"]));
+        let (col, row) = center(&pad, PadKey::No);
+        // Middle of that cell in a 0-4095 range
+        let scale =
+            |cell: u16, cells: u16| ((cell as u32 * 2 + 1) * 4096 / (cells as u32 * 2)) as u16;
+        let position = (scale(col, AREA.width), scale(row, AREA.height));
+        let max = (4095, 4095);
+        assert_eq!(pad.handle_scaled_touch(position, max, AREA, false), None);
+        assert_eq!(
+            pad.handle_scaled_touch(position, max, AREA, true),
+            Some(PadKey::No)
+        );
+    }
+}
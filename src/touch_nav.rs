@@ -0,0 +1,279 @@
+//! Touch equivalents of the keyboard-only actions, for units whose only input
+//! device is the touchscreen (the GPS tablet variant has no keyboard at all).
+//!
+//! Screens draw a row of [`TouchButtons`] in the top right corner, the same
+//! spot everywhere, and the run loop watches for a [`CornerEscape`]: a long
+//! press in the top left corner of the panel goes back to the home menu, even
+//! before the touchscreen is calibrated.
+
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Style, Stylize},
+    widgets::{Block, Clear, Paragraph},
+};
+
+use crate::event_handler::{AppEvent, DeviceInfo};
+
+/// How long a finger has to stay in the corner to go home
+pub const CORNER_ESCAPE_HOLD: Duration = Duration::from_secs(3);
+// Size of the escape corner, as a fraction of the axis range
+const CORNER_FRACTION: f32 = 0.08;
+// A touch held on a button longer than this is a stroke passing by, not a tap
+pub(crate) const TAP_MAX_DURATION: Duration = Duration::from_millis(600);
+
+/// A raw touch and the axis range the device reports, X and Y turned the
+/// screen's way by the device's `swapped_axes` hint. None for devices without a
+/// range (e.g. the serial panel), whose raw touches can't be placed on the
+/// screen. Only for placing touches without a calibration, the touchscreen
+/// test's calibration works the orientation out itself.
+pub fn screen_position(
+    x: u16,
+    y: u16,
+    info: Option<&DeviceInfo>,
+) -> Option<((u32, u32), (u32, u32))> {
+    let info = info?;
+    let max_x = info.abs_x_max.filter(|max| *max > 0)? as u32;
+    let max_y = info.abs_y_max.filter(|max| *max > 0)? as u32;
+    let (x, y) = (x as u32, y as u32);
+    Some(if info.swapped_axes {
+        ((y, x), (max_y, max_x))
+    } else {
+        ((x, y), (max_x, max_y))
+    })
+}
+
+/// Terminal cell under a position in `0..=max` on each axis
+pub fn cell_at(x: u32, y: u32, (max_x, max_y): (u32, u32), area: Rect) -> (u16, u16) {
+    let col = area.x + (x.min(max_x) * area.width as u32 / (max_x + 1)) as u16;
+    let row = area.y + (y.min(max_y) * area.height as u32 / (max_y + 1)) as u16;
+    (col, row)
+}
+
+/// Detects a finger held still in the top left corner of the panel, in raw
+/// coordinates against the range the device reports
+#[derive(Default)]
+pub struct CornerEscape {
+    held_since: Option<Instant>,
+    // Fired for the current touch, nothing more until the finger lifts
+    fired: bool,
+}
+
+impl CornerEscape {
+    pub fn new() -> Self {
+        CornerEscape::default()
+    }
+
+    /// Feed every event, ticks included so a finger held without moving still
+    /// counts. Returns true once per touch when the hold completes.
+    pub fn observe(&mut self, event: &AppEvent) -> bool {
+        if let AppEvent::Touch {
+            x,
+            y,
+            released,
+            info,
+            ..
+        } = event
+        {
+            let in_corner =
+                screen_position(*x, *y, info.as_deref()).is_some_and(|((x, y), (max_x, max_y))| {
+                    x <= (max_x as f32 * CORNER_FRACTION) as u32
+                        && y <= (max_y as f32 * CORNER_FRACTION) as u32
+                });
+
+            if *released || !in_corner {
+                self.held_since = None;
+                if *released {
+                    self.fired = false;
+                }
+                return false;
+            }
+            self.held_since.get_or_insert_with(Instant::now);
+        }
+
+        let held = self
+            .held_since
+            .is_some_and(|since| since.elapsed() >= CORNER_ESCAPE_HOLD);
+        if held && !self.fired {
+            self.fired = true;
+            return true;
+        }
+        false
+    }
+}
+
+/// What a touch button does, the same action as the screen's key for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TouchAction {
+    Back,
+    Skip,
+    Next,
+    Select,
+    Accept,
+    Recalibrate,
+    Reset,
+    Resume,
+    Discard,
+    NewSession,
+}
+
+impl TouchAction {
+    fn label(self) -> &'static str {
+        match self {
+            TouchAction::Back => "Back",
+            TouchAction::Skip => "Skip",
+            TouchAction::Next => "Next",
+            TouchAction::Select => "Select",
+            TouchAction::Accept => "Accept",
+            TouchAction::Recalibrate => "Recalibrate",
+            TouchAction::Reset => "Reset",
+            TouchAction::Resume => "Resume",
+            TouchAction::Discard => "Start over",
+            TouchAction::NewSession => "New unit",
+        }
+    }
+}
+
+/// A row of tappable buttons in the top right corner of the screen. A button
+/// runs when the finger goes down and comes up on it within a short tap.
+pub struct TouchButtons {
+    buttons: Vec<TouchAction>,
+    // Frame area of the last draw, hit testing lays the buttons out in it again
+    area: Cell<Rect>,
+    // Button under the finger when the current touch went down
+    pressed: Option<(TouchAction, Instant)>,
+    touching: bool,
+    // The current touch started on a button, so the screen should ignore it
+    captured: bool,
+}
+
+impl TouchButtons {
+    pub fn new(buttons: &[TouchAction]) -> Self {
+        TouchButtons {
+            buttons: buttons.to_vec(),
+            area: Cell::new(Rect::default()),
+            pressed: None,
+            touching: false,
+            captured: false,
+        }
+    }
+
+    /// Frame area of the last draw
+    pub fn frame_area(&self) -> Rect {
+        self.area.get()
+    }
+
+    /// Whether the touch last handled, its release included, started on a
+    /// button. Such touches belong to the buttons and not to the screen below.
+    pub fn captured(&self) -> bool {
+        self.captured
+    }
+
+    /// Where each button goes in a frame of `area`, in button order, right
+    /// aligned in the top right corner. Buttons that don't fit are left out
+    /// from the left.
+    pub fn layout(&self, area: Rect) -> Vec<(TouchAction, Rect)> {
+        let mut rects = Vec::new();
+        let mut right = area.right();
+        for action in self.buttons.iter().rev() {
+            let width = action.label().chars().count() as u16 + 4;
+            let Some(x) = right.checked_sub(width + 1).filter(|x| *x >= area.x) else {
+                break;
+            };
+            rects.push((
+                *action,
+                Rect {
+                    x,
+                    y: area.y,
+                    width,
+                    height: 3.min(area.height),
+                },
+            ));
+            right = x;
+        }
+        // Placed right to left, returned in button order
+        rects.reverse();
+        rects
+    }
+
+    /// Draw the buttons over whatever is in the top right corner
+    pub fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+        self.area.set(area);
+
+        for (action, rect) in self.layout(area) {
+            frame.render_widget(Clear, rect);
+            frame.render_widget(
+                Paragraph::new(action.label())
+                    .centered()
+                    .block(Block::bordered().border_style(Style::default().yellow()))
+                    .style(Style::default().bold()),
+                rect,
+            );
+        }
+    }
+
+    /// Touch at a raw device position, placed on the screen with the device's
+    /// reported range. Touches from devices without one are ignored.
+    pub fn handle_raw_touch(&mut self, event: &AppEvent) -> Option<TouchAction> {
+        let AppEvent::Touch {
+            x,
+            y,
+            released,
+            info,
+            ..
+        } = event
+        else {
+            return None;
+        };
+        let ((x, y), range) = screen_position(*x, *y, info.as_deref())?;
+        let cell = cell_at(x, y, range, self.area.get());
+        self.handle_touch(Some(cell), *released)
+    }
+
+    /// Touch at a position in `0..=max` on each axis spread over `area`, e.g.
+    /// calibrated coordinates over the canvas they are drawn on
+    pub fn handle_scaled_touch(
+        &mut self,
+        (x, y): (u16, u16),
+        max: (u32, u32),
+        area: Rect,
+        released: bool,
+    ) -> Option<TouchAction> {
+        let cell = cell_at(x as u32, y as u32, max, area);
+        self.handle_touch(Some(cell), released)
+    }
+
+    /// Touch down or up at a terminal cell. Returns the button's action when a
+    /// tap started and ended on it.
+    pub fn handle_touch(
+        &mut self,
+        cell: Option<(u16, u16)>,
+        released: bool,
+    ) -> Option<TouchAction> {
+        let button = cell.and_then(|cell| {
+            self.layout(self.area.get())
+                .into_iter()
+                .find(|(_, rect)| rect.contains(cell.into()))
+                .map(|(action, _)| action)
+        });
+
+        if released {
+            self.touching = false;
+            self.captured = self.pressed.is_some();
+            let (pressed, since) = self.pressed.take()?;
+            return (button == Some(pressed) && since.elapsed() <= TAP_MAX_DURATION)
+                .then_some(pressed);
+        }
+
+        if !self.touching {
+            self.touching = true;
+            self.pressed = button.map(|button| (button, Instant::now()));
+            self.captured = self.pressed.is_some();
+        }
+        None
+    }
+}
@@ -0,0 +1,232 @@
+//! Region of interest of the touchscreen test, for installations that mask
+//! part of the panel behind a bezel. Coverage and the corner checks only look
+//! inside it, touch-downs outside are counted on their own to show the bezel
+//! really blocks them. Kept as fractions of the calibrated space, so it
+//! doesn't depend on the device's axis range.
+
+use std::fmt;
+
+use color_eyre::{Result, eyre::eyre};
+
+const CALIBRATED_MAX: f32 = 999.0;
+
+// A region dragged smaller than this share of either axis is taken as a slip
+const MIN_SIZE: f32 = 0.05;
+
+/// Left, top, right and bottom edges as fractions (0-1) of the calibrated space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Roi {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Default for Roi {
+    fn default() -> Self {
+        Roi::FULL
+    }
+}
+
+impl Roi {
+    /// The whole panel
+    pub const FULL: Roi = Roi {
+        left: 0.0,
+        top: 0.0,
+        right: 1.0,
+        bottom: 1.0,
+    };
+
+    /// A region from its edges, which have to lie within 0-1 with some room
+    /// between them
+    pub fn new([left, top, right, bottom]: [f32; 4]) -> Result<Self> {
+        let within = |value: f32| (0.0..=1.0).contains(&value);
+        if ![left, top, right, bottom].into_iter().all(within) {
+            return Err(eyre!("region edges have to be within 0-1"));
+        }
+        if right - left < MIN_SIZE || bottom - top < MIN_SIZE {
+            return Err(eyre!(
+                "region is empty or too small, right has to exceed left and bottom top"
+            ));
+        }
+        Ok(Roi {
+            left,
+            top,
+            right,
+            bottom,
+        })
+    }
+
+    /// The region between two opposite corners in calibrated units, dragged in
+    /// either direction. None for a region too small to mean anything.
+    pub fn from_corners(a: (u16, u16), b: (u16, u16)) -> Option<Self> {
+        let fraction = |value: u16| (value as f32 / CALIBRATED_MAX).min(1.0);
+        Roi::new([
+            fraction(a.0.min(b.0)),
+            fraction(a.1.min(b.1)),
+            fraction(a.0.max(b.0)),
+            fraction(a.1.max(b.1)),
+        ])
+        .ok()
+    }
+
+    pub fn is_full(&self) -> bool {
+        *self == Roi::FULL
+    }
+
+    pub fn edges(&self) -> [f32; 4] {
+        [self.left, self.top, self.right, self.bottom]
+    }
+
+    /// Whether calibrated (x, y) lies inside, edges included
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        let (x, y) = (x / CALIBRATED_MAX, y / CALIBRATED_MAX);
+        (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+    }
+
+    /// Whether the middle of grid cell (`col`, `row`) of a `cols` x `rows`
+    /// grid over the calibrated space lies inside
+    pub fn contains_cell(&self, col: usize, row: usize, cols: usize, rows: usize) -> bool {
+        let middle = |index: usize, count: usize| (index as f32 + 0.5) / count as f32;
+        (self.left..=self.right).contains(&middle(col, cols))
+            && (self.top..=self.bottom).contains(&middle(row, rows))
+    }
+
+    /// Calibrated (x, y) of the whole panel moved onto the region, e.g. the
+    /// panel's top-right corner to the region's
+    pub fn place(&self, (x, y): (f32, f32)) -> (f32, f32) {
+        let along = |value: f32, low: f32, high: f32| {
+            (low + value / CALIBRATED_MAX * (high - low)) * CALIBRATED_MAX
+        };
+        (
+            along(x, self.left, self.right),
+            along(y, self.top, self.bottom),
+        )
+    }
+}
+
+/// The four edges separated by spaces, left, top, right and bottom
+impl fmt::Display for Roi {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.3} {:.3} {:.3} {:.3}",
+            self.left, self.top, self.right, self.bottom
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A strip along the right edge, the top-left corner and the bottom-right
+    // quarter, as a bezel would leave them
+    fn right_strip() -> Roi {
+        Roi::new([0.75, 0.0, 1.0, 1.0]).unwrap()
+    }
+
+    fn top_left() -> Roi {
+        Roi::new([0.0, 0.0, 0.25, 0.25]).unwrap()
+    }
+
+    fn bottom_right() -> Roi {
+        Roi::new([0.5, 0.5, 1.0, 1.0]).unwrap()
+    }
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 0.01 && (a.1 - b.1).abs() < 0.01
+    }
+
+    #[test]
+    fn edges_outside_the_panel_or_too_close_are_refused() {
+        for edges in [
+            [-0.1, 0.0, 1.0, 1.0],
+            [0.0, 0.0, 1.0, 1.2],
+            [0.5, 0.0, 0.4, 1.0],
+            [0.0, 0.6, 1.0, 0.6],
+            [0.0, 0.0, 0.01, 1.0],
+        ] {
+            assert!(Roi::new(edges).is_err(), "{edges:?}");
+        }
+        assert_eq!(Roi::new([0.0, 0.0, 1.0, 1.0]).unwrap(), Roi::FULL);
+        assert!(Roi::FULL.is_full() && !right_strip().is_full());
+        assert_eq!(right_strip().to_string(), "0.750 0.000 1.000 1.000");
+    }
+
+    #[test]
+    fn a_drag_spans_the_region_either_way() {
+        let dragged = Roi::from_corners((999, 999), (749, 0)).unwrap();
+        assert_eq!(Roi::from_corners((749, 0), (999, 999)), Some(dragged));
+        assert_eq!(dragged.edges()[1..], [0.0, 1.0, 1.0]);
+        assert!((dragged.left - 0.75).abs() < 0.001);
+        // Past the calibrated range is the edge
+        assert_eq!(Roi::from_corners((0, 0), (1200, 1200)), Some(Roi::FULL));
+        // A slip of the finger
+        assert_eq!(Roi::from_corners((500, 500), (520, 900)), None);
+    }
+
+    #[test]
+    fn hits_include_the_edges_of_regions_at_the_panel_edges() {
+        let strip = right_strip();
+        for (x, y) in [(999.0, 0.0), (999.0, 999.0), (750.0, 500.0)] {
+            assert!(strip.contains(x, y), "({x}, {y})");
+        }
+        assert!(!strip.contains(749.0, 500.0));
+
+        let corner = top_left();
+        assert!(corner.contains(0.0, 0.0));
+        assert!(corner.contains(249.0, 249.0));
+        for (x, y) in [(250.0, 0.0), (0.0, 250.0), (999.0, 999.0)] {
+            assert!(!corner.contains(x, y), "({x}, {y})");
+        }
+
+        let quarter = bottom_right();
+        assert!(quarter.contains(999.0, 999.0) && quarter.contains(500.0, 500.0));
+        assert!(!quarter.contains(498.0, 999.0) && !quarter.contains(999.0, 498.0));
+    }
+
+    #[test]
+    fn cells_count_by_their_middles() {
+        let inside = |roi: Roi| {
+            (0..12)
+                .flat_map(|row| (0..16).map(move |col| (col, row)))
+                .filter(|&(col, row)| roi.contains_cell(col, row, 16, 12))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(inside(Roi::FULL).len(), 192);
+
+        // Columns 12-15 of 16
+        let strip = inside(right_strip());
+        assert_eq!(strip.len(), 4 * 12);
+        assert!(strip.iter().all(|(col, _)| *col >= 12));
+
+        // Columns 0-3 and rows 0-2
+        let corner = inside(top_left());
+        assert_eq!(corner.len(), 4 * 3);
+        assert!(corner.contains(&(0, 0)) && corner.contains(&(3, 2)));
+
+        let quarter = inside(bottom_right());
+        assert_eq!(quarter.len(), 8 * 6);
+        assert!(quarter.contains(&(15, 11)) && !quarter.contains(&(7, 11)));
+    }
+
+    #[test]
+    fn the_panel_corners_are_placed_on_the_region_corners() {
+        assert!(close(Roi::FULL.place((999.0, 0.0)), (999.0, 0.0)));
+
+        let corner = top_left();
+        assert!(close(corner.place((0.0, 0.0)), (0.0, 0.0)));
+        assert!(close(corner.place((999.0, 0.0)), (249.75, 0.0)));
+        assert!(close(corner.place((999.0, 999.0)), (249.75, 249.75)));
+
+        let strip = right_strip();
+        assert!(close(strip.place((0.0, 999.0)), (749.25, 999.0)));
+        assert!(close(strip.place((499.5, 499.5)), (874.125, 499.5)));
+        // Placed points are hits
+        for point in [(0.0, 0.0), (999.0, 999.0), (0.0, 999.0)] {
+            let (x, y) = strip.place(point);
+            assert!(strip.contains(x, y));
+        }
+    }
+}
@@ -1,24 +1,54 @@
 use evdev::KeyCode;
 use ratatui::{
     Frame,
-    layout::Rect,
+    buffer::Buffer,
+    layout::{Constraint, Layout, Margin, Position, Rect},
     style::{Color, Style, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Paragraph},
+    widgets::{Block, Clear, Paragraph, Sparkline},
 };
+use std::cell::RefCell;
 use std::collections::VecDeque;
-use std::u16;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
 
 use crate::{
-    Nav, Screen, ScreenId,
-    event_handler::{AppEvent, DeviceInfo},
+    Degradation, Nav, Screen, ScreenId,
+    calibration_matrix::{AxisSpan, CalibrationMatrix, LinearMapping},
+    cli::Options,
+    device_class::DeviceClass,
+    duplicate_touch::DuplicateTouchDetector,
+    event_handler::{AppEvent, DeviceInfo, TouchContact},
+    key_dedup::physical_device_key,
+    listener_health::{self, ListenerState},
+    pen_hover::{DEFAULT_HOVER_OFFSET_MM, PenHover},
+    power::PowerMonitor,
+    report::{
+        DeviceStats, SaturationResult, ScreenResult, SerialResult, StabilizerResult, StrokeTotals,
+        TouchscreenResult,
+    },
+    sample_stats::{self, CornerCapture, MIN_CAPTURE_SAMPLES},
+    saturation::{AxisLimits, SaturationDetector},
+    soak::{self, DEFAULT_SOAK_INTERVAL, SoakCounters, SoakRecorder},
+    stabilizer::{DEFAULT_ALPHA, Filter, Stabilizer},
+    stroke::{SpeedSample, Stroke, TouchPoint},
+    test_timer::TestTimer,
+    theme::{JUMP_CHAR, Theme},
+    touch_corners::{CornerChecks, DEFAULT_CORNER_MARGIN_MM, Target},
+    touch_keypad::{PadKey, TouchPad},
+    touch_nav::{TouchAction, TouchButtons},
+    touch_roi::Roi,
+    units::{self, MmScale},
+    wake_latency::WakeLatency,
+    writer::WriterHandle,
 };
 
 // Conservative raw-unit thresholds; tweak to your device scale if needed:
 static MIN_SPAN_X: u16 = 100; // require at least this many raw units across X
 static MIN_SPAN_Y: u16 = 100; // require at least this many raw units across Y
-static MIN_CORNER_DIST2: u32 = 50 * 50; // squared distance; avoid identical points (~100 raw units apart)
-static MIN_DIAGONAL2: u32 = 1000; // squared distance; reject near-degenerate rectangles (~1000 units)
+static MIN_CORNER_DIST2: u64 = 50 * 50; // squared distance; avoid identical points (~100 raw units apart)
+static MIN_DIAGONAL2: u64 = 1000; // squared distance; reject near-degenerate rectangles (~1000 units)
 
 static COLS: u16 = 16;
 static ROWS: u16 = 12;
@@ -28,8 +58,21 @@ static CALIBRATED_MAX_Y: u16 = 999;
 
 // Trail and statistics configuration
 const MAX_TRAIL_LENGTH: usize = 200;
+// Trail length while frames are over budget
+const DEGRADED_TRAIL_LENGTH: usize = MAX_TRAIL_LENGTH / 4;
 const TRAIL_LIFETIME_MS: u128 = 2000; // Trail points disappear after 2 seconds
-const JUMP_THRESHOLD: f32 = 50.0; // Distance in units to consider a "jump"
+pub const DEFAULT_JUMP_THRESHOLD: f32 = 50.0; // Distance in units to consider a "jump"
+const JUMP_THRESHOLD_STEP: f32 = 5.0; // ←/→ step in the jump threshold view
+// One histogram bucket per calibrated unit, up to the diagonal of the 0-999 square
+const DISTANCE_BUCKETS: usize = 1415;
+const REQUIRED_HOLD_MS: u128 = 1000; // Calibration corners need a still hold this long
+const MAX_RECORDED_POINTS: usize = 20_000; // Touch path points kept for the report
+const MAX_STROKES: usize = 5_000; // Stroke summaries kept, older ones only count in totals
+const MAX_DETAILED_SESSIONS: usize = 3; // Earlier sessions keeping their paths and strokes
+const MAX_ARCHIVED_SESSIONS: usize = 100; // Earlier sessions kept at all, as totals past the detailed ones
+const PRESSURE_HISTORY: usize = 400; // Pressure samples kept for the bar graph
+// Cell height / width when the terminal doesn't report its pixel size
+const DEFAULT_CELL_ASPECT: f32 = 2.0;
 
 struct AsciiCanvas {
     w: u16,
@@ -58,27 +101,8 @@ impl AsciiCanvas {
         }
     }
     fn line(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, ch: char) {
-        // Bresenham
-        let (mut x0, mut y0) = (x0, y0);
-        let dx = (x1 - x0).abs();
-        let sx = if x0 < x1 { 1 } else { -1 };
-        let dy = -(y1 - y0).abs();
-        let sy = if y0 < y1 { 1 } else { -1 };
-        let mut err = dx + dy;
-        loop {
-            self.put(x0, y0, ch);
-            if x0 == x1 && y0 == y1 {
-                break;
-            }
-            let e2 = 2 * err;
-            if e2 >= dy {
-                err += dy;
-                x0 += sx;
-            }
-            if e2 <= dx {
-                err += dx;
-                y0 += sy;
-            }
+        for (x, y) in line_cells(x0, y0, x1, y1) {
+            self.put(x, y, ch);
         }
     }
     fn arrow(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, shaft: char) {
@@ -101,6 +125,178 @@ impl AsciiCanvas {
     }
 }
 
+/// Height / width of one terminal cell, from the pixel size when the terminal reports it
+fn terminal_cell_aspect() -> f32 {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => {
+            (size.height as f32 / size.rows as f32) / (size.width as f32 / size.columns as f32)
+        }
+        _ => DEFAULT_CELL_ASPECT,
+    }
+}
+
+/// Largest centered part of `area` with the given physical width / height when
+/// cells are `cell_aspect` times taller than wide
+fn letterbox(area: Rect, panel_aspect: f32, cell_aspect: f32) -> Rect {
+    let width_for_height = |rows: u16| (rows as f32 * cell_aspect * panel_aspect).round() as u16;
+
+    let (width, height) = if width_for_height(area.height) <= area.width {
+        (width_for_height(area.height), area.height)
+    } else {
+        let rows = (area.width as f32 / (cell_aspect * panel_aspect)).round() as u16;
+        (area.width, rows.min(area.height))
+    };
+
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width: width.max(1),
+        height: height.max(1),
+    }
+}
+
+/// Cells of the trail canvas, kept between frames. Each frame only clears the
+/// cells the previous one painted (trail and crosshair) and paints the new ones,
+/// the whole grid is only rebuilt when the terminal size changes or the trail
+/// is cleared. The corner markers are part of the background and never redrawn.
+#[derive(Default)]
+struct TrailGrid {
+    width: usize,
+    height: usize,
+    chars: Vec<char>,
+    // Stroke age of every trail cell, used to color the latest stroke brightest
+    ages: Vec<Option<usize>>,
+    // What an unpainted cell shows, blank apart from the corner markers
+    background: Vec<(usize, char)>,
+    // Cells painted since the last `begin_frame`
+    painted: Vec<usize>,
+    // Size and trail cutoff the grid was built for
+    built_for: Option<(usize, usize, u128)>,
+}
+
+impl TrailGrid {
+    /// Get ready to paint a frame: rebuild everything if the size or the trail
+    /// cutoff changed, else only wipe what the previous frame painted
+    fn begin_frame(&mut self, width: usize, height: usize, trail_cutoff: u128) {
+        if self.built_for != Some((width, height, trail_cutoff)) {
+            self.rebuild(width, height);
+            self.built_for = Some((width, height, trail_cutoff));
+            return;
+        }
+
+        for index in std::mem::take(&mut self.painted) {
+            self.chars[index] = ' ';
+            self.ages[index] = None;
+        }
+        for &(index, ch) in &self.background {
+            self.chars[index] = ch;
+        }
+    }
+
+    fn rebuild(&mut self, width: usize, height: usize) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.chars = vec![' '; width * height];
+            self.ages = vec![None; width * height];
+            // Shrinking the terminal shouldn't keep the larger buffers around
+            self.chars.shrink_to_fit();
+            self.ages.shrink_to_fit();
+        } else {
+            self.chars.fill(' ');
+            self.ages.fill(None);
+        }
+        self.painted.clear();
+
+        // Corner markers to show the calibrated area
+        self.background.clear();
+        if width > 2 && height > 2 {
+            let (right, bottom) = (width - 1, height - 1);
+            self.background.extend([
+                (0, '┌'),
+                (1, '─'),
+                (width, '│'),
+                (right, '┐'),
+                (right - 1, '─'),
+                (width + right, '│'),
+                (bottom * width, '└'),
+                (bottom * width + 1, '─'),
+                ((bottom - 1) * width, '│'),
+                (bottom * width + right, '┘'),
+                (bottom * width + right - 1, '─'),
+                ((bottom - 1) * width + right, '│'),
+            ]);
+        }
+        for &(index, ch) in &self.background {
+            self.chars[index] = ch;
+        }
+    }
+
+    fn set(&mut self, x: usize, y: usize, ch: char) {
+        let index = y * self.width + x;
+        self.chars[index] = ch;
+        self.painted.push(index);
+    }
+
+    fn set_trail(&mut self, x: usize, y: usize, ch: char, age: usize) {
+        let index = y * self.width + x;
+        self.chars[index] = ch;
+        self.ages[index] = Some(age);
+        self.painted.push(index);
+    }
+
+    /// Draw the grid into the frame buffer at `area`. The frame starts out blank,
+    /// so only the background and the painted cells need their character set.
+    /// Without `colored` every stroke is white, jumps are told apart by
+    /// `JUMP_CHAR` then and drawn in `jump_color` otherwise.
+    fn render(&self, buffer: &mut Buffer, area: Rect, colored: bool, jump_color: Color) {
+        let stroke_color = |index: usize| match self.ages[index] {
+            _ if !colored => Color::White,
+            Some(_) if self.chars[index] == JUMP_CHAR => jump_color,
+            None | Some(0) => Color::White,
+            Some(1) => Color::Gray,
+            Some(_) => Color::DarkGray,
+        };
+
+        buffer.set_style(area, Style::default().bg(Color::Black).fg(Color::White));
+        let background = self.background.iter().map(|(index, _)| index);
+        for &index in background.chain(&self.painted) {
+            let x = area.x + (index % self.width) as u16;
+            let y = area.y + (index / self.width) as u16;
+            if let Some(cell) = buffer.cell_mut((x, y)) {
+                cell.set_char(self.chars[index]).set_fg(stroke_color(index));
+            }
+        }
+    }
+}
+
+// Cells on the line between two points, both ends included (Bresenham)
+fn line_cells(x0: i32, y0: i32, x1: i32, y1: i32) -> Vec<(i32, i32)> {
+    let mut cells = Vec::new();
+    let (mut x0, mut y0) = (x0, y0);
+    let dx = (x1 - x0).abs();
+    let sx = if x0 < x1 { 1 } else { -1 };
+    let dy = -(y1 - y0).abs();
+    let sy = if y0 < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        cells.push((x0, y0));
+        if x0 == x1 && y0 == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y0 += sy;
+        }
+    }
+    cells
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CalibrationStep {
     DeviceSelection,
@@ -108,9 +304,105 @@ enum CalibrationStep {
     TopRight,
     BottomRight,
     BottomLeft,
+    /// The corners are taken, the orientation they show waits for the operator
+    ConfirmOrientation,
     Done,
 }
 
+/// How a calibration corner is taken once the finger has been held still long enough
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CaptureMode {
+    /// Hold, then lift to take the point
+    #[default]
+    OnRelease,
+    /// The point is taken as soon as the hold completes, lifting only arms the next corner.
+    /// Easier with gloves.
+    Auto,
+}
+
+impl CaptureMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "release" => Some(CaptureMode::OnRelease),
+            "auto" => Some(CaptureMode::Auto),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            CaptureMode::OnRelease => "release",
+            CaptureMode::Auto => "auto",
+        }
+    }
+}
+
+/// Geometry of the test view in one frame
+struct TestLayout {
+    /// Where calibrated coordinates are drawn: letterboxed in the trail view
+    /// with true aspect, the whole frame otherwise
+    canvas: Rect,
+    /// Corner target markers on the canvas edges, with whether they were touched
+    corners: Vec<(Target, bool, Position)>,
+}
+
+/// Info box centered at the top of `area`, growing with its `lines` but never
+/// hiding more than half the canvas
+fn overlay_rect(area: Rect, width: u16, lines: usize) -> Rect {
+    Rect {
+        x: (area.width.saturating_sub(width)) / 2,
+        y: 1,
+        width,
+        height: (lines as u16 + 2).min(area.height / 2),
+    }
+}
+
+/// Size of a grid cell in calibrated units
+fn cell_size() -> (f32, f32) {
+    (
+        CALIBRATED_MAX_X as f32 / COLS as f32,
+        CALIBRATED_MAX_Y as f32 / ROWS as f32,
+    )
+}
+
+/// "420/s" or "420/s (35.2 mm/s)" depending on whether a scale is known
+fn format_speed(units: f32, mm: Option<f32>) -> String {
+    match mm {
+        Some(mm) => format!("{units:.0}/s ({mm:.1} mm/s)"),
+        None => format!("{units:.0}/s"),
+    }
+}
+
+/// A speed profile spread over `columns` by time, the fastest step of each
+/// column; a column without a step repeats the one before
+fn speed_columns(profile: &[SpeedSample], columns: usize) -> Vec<u64> {
+    let Some(last) = profile.last() else {
+        return Vec::new();
+    };
+    let columns = columns.max(1);
+    let ms_per_column = (last.at_ms as f32 / columns as f32).max(1.0);
+    let mut data: Vec<Option<u64>> = vec![None; columns];
+    for sample in profile {
+        let column = ((sample.at_ms as f32 / ms_per_column) as usize).min(columns - 1);
+        let speed = sample.speed.round() as u64;
+        data[column] = Some(data[column].map_or(speed, |max| max.max(speed)));
+    }
+    let mut previous = 0;
+    data.into_iter()
+        .map(|speed| {
+            previous = speed.unwrap_or(previous);
+            previous
+        })
+        .collect()
+}
+
+fn unix_millis() -> u128 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+}
+
 #[derive(Clone, Debug)]
 struct Calibration {
     step: CalibrationStep,
@@ -125,27 +417,45 @@ struct Calibration {
     max_y: u16,
     invert_x: bool,
     invert_y: bool,
+    // Raw X and Y swapped, seeded from the device's hint and detected from the
+    // first corner pair
+    swap_axes: bool,
+    // How the panel lies in plain words, when it isn't upright or isn't what
+    // the model profile expects
+    orientation: Option<String>,
+    // Corner calibrations wait in ConfirmOrientation for such an orientation
+    confirms_orientation: bool,
     scale_x: f32,
     scale_y: f32,
 
     is_touching: bool,
     error: Option<String>,
 
+    capture_mode: CaptureMode,
+    // Auto mode took the point, nothing happens until the finger lifts
+    captured: bool,
+
     // Hold tracking for calibration
     touch_start_time: Option<u128>,
     touch_start_pos: Option<(u16, u16)>,
     hold_duration_ms: u128,
     touch_samples: Vec<(u16, u16)>, // Collect samples during hold
+    // How each corner taken so far was captured, same order as pts
+    captures: [Option<CornerCapture>; 4],
+    // Why the last hold wasn't taken, until a corner is
+    capture_warning: Option<String>,
 
     // Device selection
     available_devices: Vec<DeviceInfo>,
     selected_device_index: usize,
     selected_device_path: Option<String>,
     selected_device_info: Option<DeviceInfo>,
+    // Paths marked with Space, two of them are compared
+    marked_devices: Vec<String>,
 }
 
 impl Calibration {
-    fn new() -> Self {
+    fn new(capture_mode: CaptureMode) -> Self {
         Self {
             step: CalibrationStep::DeviceSelection,
             pts: [(0, 0); 4],
@@ -156,131 +466,228 @@ impl Calibration {
             max_y: u16::MAX,
             invert_x: false,
             invert_y: false,
+            swap_axes: false,
+            orientation: None,
+            confirms_orientation: true,
             scale_x: 1.0,
             scale_y: 1.0,
             is_touching: false,
             error: None,
+            capture_mode,
+            captured: false,
             touch_start_time: None,
             touch_start_pos: None,
             hold_duration_ms: 0,
             touch_samples: Vec::new(),
+            captures: [None; 4],
+            capture_warning: None,
             available_devices: Vec::new(),
             selected_device_index: 0,
             selected_device_path: None,
             selected_device_info: None,
+            marked_devices: Vec::new(),
+        }
+    }
+
+    /// Calibration of a device picked outside the device selection, which
+    /// takes whatever orientation the corners show
+    fn for_device(capture_mode: CaptureMode, info: DeviceInfo) -> Self {
+        let mut calibration = Self {
+            selected_device_path: Some(info.path.clone()),
+            selected_device_info: Some(info),
+            confirms_orientation: false,
+            ..Self::new(capture_mode)
+        };
+        calibration.restart_corners();
+        calibration
+    }
+
+    /// Take the corners from the first again, axes as the device's hint has them
+    fn restart_corners(&mut self) {
+        self.step = CalibrationStep::TopLeft;
+        self.count = 0;
+        self.captures = [None; 4];
+        self.swap_axes = self.swap_hint();
+        self.orientation = None;
+    }
+
+    /// The model profile says the selected device reports its axes swapped
+    fn swap_hint(&self) -> bool {
+        self.selected_device_info
+            .as_ref()
+            .is_some_and(|info| info.swapped_axes)
+    }
+
+    /// The operator agreed with the orientation the corners showed
+    fn accept_orientation(&mut self) {
+        if self.step == CalibrationStep::ConfirmOrientation {
+            self.step = CalibrationStep::Done;
         }
     }
 
     fn record_touch(&mut self, touch_event: &AppEvent) {
-        if let AppEvent::Touch {
-            x,
-            y,
-            timestamp: _,
-            released,
-            info: _,
-        } = touch_event
-        {
-            if let CalibrationStep::Done = self.step {
-                return;
+        if let AppEvent::Touch { x, y, released, .. } = touch_event {
+            self.on_sample(*x, *y, *released, unix_millis());
+        }
+    }
+
+    /// Feed one touch sample taken at `now` (ms). All capture decisions happen here
+    /// and in `advance_hold`, so they can be driven with synthetic times.
+    fn on_sample(&mut self, x: u16, y: u16, released: bool, now: u128) {
+        if let CalibrationStep::ConfirmOrientation | CalibrationStep::Done = self.step {
+            return;
+        }
+        let (x, y) = self.orient(x, y);
+
+        if released {
+            if self.capture_mode == CaptureMode::OnRelease && self.hold_complete() {
+                self.capture_point();
             }
+            self.is_touching = false;
 
-            const REQUIRED_HOLD_MS: u128 = 1000; // 1 second
-            const MOVEMENT_TOLERANCE_PERCENT: f32 = 0.025; // 2.5% of device max coordinate
-            const MIN_TOLERANCE: i32 = 100; // Minimum tolerance fallback
-
-            if !released {
-                // Touch started or continuing
-                self.is_touching = true;
-
-                if self.touch_start_time.is_none() {
-                    // First touch - record start time and position using system time
-                    let current_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-                    self.touch_start_time = Some(current_time);
-                    self.touch_start_pos = Some((*x, *y));
-                    self.hold_duration_ms = 0;
-                    self.touch_samples.clear();
-                    self.touch_samples.push((*x, *y));
-                } else {
-                    // Continuing touch - check if moved too much
-                    if let Some((start_x, start_y)) = self.touch_start_pos {
-                        let dx = (*x as i32 - start_x as i32).abs();
-                        let dy = (*y as i32 - start_y as i32).abs();
-
-                        // Calculate adaptive threshold based on device's reported maximum coordinates
-                        // This is much more reliable than observing coordinates during calibration
-                        let max_movement = if let Some(device_info) = &self.selected_device_info {
-                            // Use the larger of X or Y max, and apply percentage tolerance
-                            let device_max = device_info.abs_x_max.max(device_info.abs_y_max).unwrap_or(1000);
-                            ((device_max as f32) * MOVEMENT_TOLERANCE_PERCENT).max(MIN_TOLERANCE as f32) as i32
-                        } else {
-                            MIN_TOLERANCE // Fallback if device info not available
-                        };
-
-                        if dx > max_movement || dy > max_movement {
-                            // Moved too much - reset the timer
-                            let current_time = std::time::SystemTime::now()
-                                .duration_since(std::time::UNIX_EPOCH)
-                                .unwrap()
-                                .as_millis();
-                            self.touch_start_time = Some(current_time);
-                            self.touch_start_pos = Some((*x, *y));
-                            self.hold_duration_ms = 0;
-                            self.touch_samples.clear();
-                            self.touch_samples.push((*x, *y));
-                        } else {
-                            // Still within acceptable range - add sample
-                            self.touch_samples.push((*x, *y));
-                        }
-                    }
-                }
-                // Hold duration will be updated by update_hold_duration() called on Tick events
+            // Lifting always arms the next corner
+            self.captured = false;
+            self.reset_hold();
+            return;
+        }
+
+        self.is_touching = true;
+
+        // Auto-captured, waiting for the finger to lift
+        if self.captured {
+            return;
+        }
+
+        self.track_hold(x, y, now);
+        self.advance_hold(now);
+    }
+
+    // Start the hold on first contact, restart it if the finger wandered off, else collect the sample
+    fn track_hold(&mut self, x: u16, y: u16, now: u128) {
+        const MOVEMENT_TOLERANCE_PERCENT: f32 = 0.025; // 2.5% of device max coordinate
+        const MIN_TOLERANCE: i32 = 100; // Minimum tolerance fallback
+
+        if let Some((start_x, start_y)) = self.touch_start_pos {
+            let dx = (x as i32 - start_x as i32).abs();
+            let dy = (y as i32 - start_y as i32).abs();
+
+            // Calculate adaptive threshold based on device's reported maximum coordinates
+            // This is much more reliable than observing coordinates during calibration
+            let max_movement = if let Some(device_info) = &self.selected_device_info {
+                // Use the larger of X or Y max, and apply percentage tolerance
+                let device_max = device_info
+                    .abs_x_max
+                    .max(device_info.abs_y_max)
+                    .unwrap_or(1000);
+                ((device_max as f32) * MOVEMENT_TOLERANCE_PERCENT).max(MIN_TOLERANCE as f32) as i32
+            } else {
+                MIN_TOLERANCE // Fallback if device info not available
+            };
+
+            if dx <= max_movement && dy <= max_movement {
+                // Still within acceptable range - add sample
+                self.touch_samples.push((x, y));
                 return;
             }
+        }
 
-            // Touch released
-            self.is_touching = false;
+        // First touch, or moved too much - (re)start the timer here
+        self.touch_start_time = Some(now);
+        self.touch_start_pos = Some((x, y));
+        self.hold_duration_ms = 0;
+        self.touch_samples.clear();
+        self.touch_samples.push((x, y));
+    }
 
-            // Check if hold was long enough
-            if self.hold_duration_ms >= REQUIRED_HOLD_MS {
-                // Calculate average of all samples for better accuracy
-                if !self.touch_samples.is_empty() {
-                    let sum_x: u32 = self.touch_samples.iter().map(|(x, _)| *x as u32).sum();
-                    let sum_y: u32 = self.touch_samples.iter().map(|(_, y)| *y as u32).sum();
-                    let count = self.touch_samples.len() as u32;
-
-                    let avg_x = (sum_x / count) as u16;
-                    let avg_y = (sum_y / count) as u16;
-
-                    self.pts[self.count] = (avg_x, avg_y);
-                    self.count += 1;
-                    self.step = match self.step {
-                        CalibrationStep::DeviceSelection => CalibrationStep::DeviceSelection, // Should not get touches during device selection
-                        CalibrationStep::TopLeft => CalibrationStep::TopRight,
-                        CalibrationStep::TopRight => CalibrationStep::BottomRight,
-                        CalibrationStep::BottomRight => CalibrationStep::BottomLeft,
-                        CalibrationStep::BottomLeft => CalibrationStep::Done,
-                        CalibrationStep::Done => CalibrationStep::Done,
-                    };
-                    if let CalibrationStep::Done = self.step {
-                        self.finalize();
-                        if self.error.is_some() {
-                            // Reset to try again
-                            self.step = CalibrationStep::TopLeft;
-                            self.count = 0;
-                        }
-                    }
-                }
+    /// Update the hold duration to `now`, capturing right away in auto mode
+    fn advance_hold(&mut self, now: u128) {
+        if !self.is_touching || self.captured {
+            return;
+        }
+        if let Some(start_time) = self.touch_start_time {
+            self.hold_duration_ms = now.saturating_sub(start_time);
+        }
+
+        if self.capture_mode == CaptureMode::Auto && self.hold_complete() {
+            // A rejected hold starts over while the finger stays down
+            self.captured = self.capture_point();
+            self.reset_hold();
+        }
+    }
+
+    /// Give up the hold in progress, the finger's device went away
+    fn abandon_hold(&mut self) {
+        self.is_touching = false;
+        self.captured = false;
+        self.reset_hold();
+    }
+
+    fn reset_hold(&mut self) {
+        self.touch_start_time = None;
+        self.touch_start_pos = None;
+        self.hold_duration_ms = 0;
+        self.touch_samples.clear();
+    }
+
+    /// Record the current corner from the trimmed mean of the hold samples.
+    /// Returns false when the hold produced too few samples to be trusted.
+    fn capture_point(&mut self) -> bool {
+        let capture = match sample_stats::capture(&self.touch_samples) {
+            Ok(capture) => capture,
+            Err(samples) => {
+                self.capture_warning = Some(format!(
+                    "Held too briefly or panel not reporting ({samples} of {MIN_CAPTURE_SAMPLES} samples), try again"
+                ));
+                return false;
             }
+        };
+        self.capture_warning = None;
+
+        self.pts[self.count] = (capture.x, capture.y);
+        self.captures[self.count] = Some(capture);
+        self.count += 1;
+        self.step = match self.step {
+            CalibrationStep::DeviceSelection => CalibrationStep::DeviceSelection, // Should not get touches during device selection
+            CalibrationStep::TopLeft => CalibrationStep::TopRight,
+            CalibrationStep::TopRight => CalibrationStep::BottomRight,
+            CalibrationStep::BottomRight => CalibrationStep::BottomLeft,
+            CalibrationStep::BottomLeft => CalibrationStep::Done,
+            step => step,
+        };
+        if self.count == 2 && axes_swapped(self.pts[0], self.pts[1]) {
+            self.swap_corners();
+        }
+        if let CalibrationStep::Done = self.step {
+            self.finalize();
+            if self.error.is_some() {
+                // Reset to try again
+                self.restart_corners();
+            } else if self.orientation.is_some() && self.confirms_orientation {
+                self.step = CalibrationStep::ConfirmOrientation;
+            }
+        }
+        true
+    }
 
-            // Reset hold tracking
-            self.touch_start_time = None;
-            self.touch_start_pos = None;
-            self.hold_duration_ms = 0;
-            self.touch_samples.clear();
+    /// Moving to the top right corner went along the other axis than the one
+    /// taken as X, so the axes are the other way round from what the device's
+    /// hint said. The corners taken so far are swapped to match and every later
+    /// sample is swapped on the way in.
+    fn swap_corners(&mut self) {
+        self.swap_axes = !self.swap_axes;
+        for pt in &mut self.pts[..self.count] {
+            *pt = (pt.1, pt.0);
         }
+        for capture in self.captures.iter_mut().flatten() {
+            std::mem::swap(&mut capture.x, &mut capture.y);
+            capture.spread = (capture.spread.1, capture.spread.0);
+        }
+    }
+
+    /// Raw sample in panel orientation, X along the top edge
+    #[inline]
+    fn orient(&self, x: u16, y: u16) -> (u16, u16) {
+        if self.swap_axes { (y, x) } else { (x, y) }
     }
 
     fn finalize(&mut self) {
@@ -313,10 +720,10 @@ impl Calibration {
         }
 
         // 3) corner uniqueness & diagonal sanity
-        let d2 = |a: (u16, u16), b: (u16, u16)| -> u32 {
-            let dx = a.0 as i32 - b.0 as i32;
-            let dy = a.1 as i32 - b.1 as i32;
-            (dx * dx + dy * dy) as u32
+        let d2 = |a: (u16, u16), b: (u16, u16)| -> u64 {
+            let dx = a.0.abs_diff(b.0) as u64;
+            let dy = a.1.abs_diff(b.1) as u64;
+            dx * dx + dy * dy
         };
         let (tl, tr, br, bl) = (self.pts[0], self.pts[1], self.pts[2], self.pts[3]);
 
@@ -340,6 +747,11 @@ impl Calibration {
             return;
         }
 
+        if let Err(err) = check_geometry(self.pts) {
+            self.error = Some(format!("Calibration failed: {err}"));
+            return;
+        }
+
         self.min_x = min_x;
         self.max_x = max_x;
         self.min_y = min_y;
@@ -354,6 +766,26 @@ impl Calibration {
         let bottom_y = (bl.1 as u32 + br.1 as u32) / 2;
         self.invert_y = (bottom_y as i64) < (top_y as i64);
 
+        // The edges agree on the directions (check_geometry), what's left to
+        // check is whether the result is what the operator and the model
+        // profile expect. A swap the profile knows about is how that model's
+        // panel is upright.
+        let hint = self.swap_hint();
+        let words = orientation_words(self.swap_axes != hint, self.invert_x, self.invert_y);
+        self.orientation = if self.swap_axes != hint {
+            let expected = if hint {
+                "X and Y swapped"
+            } else {
+                "X and Y as they are"
+            };
+            Some(format!(
+                "Panel appears {}, the model profile expects {expected}",
+                words.unwrap_or("upright")
+            ))
+        } else {
+            words.map(|words| format!("Panel appears {words}"))
+        };
+
         // Avoid div by zero
         let dx = (self.max_x as i32 - self.min_x as i32).max(1) as f32;
         let dy = (self.max_y as i32 - self.min_y as i32).max(1) as f32;
@@ -369,8 +801,55 @@ impl Calibration {
         matches!(self.step, CalibrationStep::Done)
     }
 
+    /// Raw range spanned by the calibration corners, the best guess at the axis
+    /// limits for devices that don't report them
+    fn raw_limits(&self) -> AxisLimits {
+        let (x, y) = ((self.min_x, self.max_x), (self.min_y, self.max_y));
+        if self.swap_axes {
+            AxisLimits { x: y, y: x }
+        } else {
+            AxisLimits { x, y }
+        }
+    }
+
+    /// The corner window, inversions and swap as plain numbers, for the libinput matrix
+    fn linear_mapping(&self) -> LinearMapping {
+        LinearMapping {
+            swap_axes: self.swap_axes,
+            x: AxisSpan {
+                min: self.min_x as f32,
+                max: self.max_x as f32,
+                invert: self.invert_x,
+            },
+            y: AxisSpan {
+                min: self.min_y as f32,
+                max: self.max_y as f32,
+                invert: self.invert_y,
+            },
+        }
+    }
+
+    /// Take `mapping` as the calibration instead of the corner holds, e.g. the
+    /// OS's matrix to check it. The window edges are rounded to raw units, and a
+    /// window reaching past the raw range is cut at it while the scale is kept.
+    fn seed(&mut self, mapping: &LinearMapping) {
+        let raw = |value: f32| value.round().clamp(0.0, u16::MAX as f32) as u16;
+        self.swap_axes = mapping.swap_axes;
+        self.min_x = raw(mapping.x.min);
+        self.max_x = raw(mapping.x.max);
+        self.min_y = raw(mapping.y.min);
+        self.max_y = raw(mapping.y.max);
+        self.invert_x = mapping.x.invert;
+        self.invert_y = mapping.y.invert;
+        self.orientation = None;
+        self.scale_x = CALIBRATED_MAX_X as f32 / (mapping.x.max - mapping.x.min).max(1.0);
+        self.scale_y = CALIBRATED_MAX_Y as f32 / (mapping.y.max - mapping.y.min).max(1.0);
+        self.count = 4;
+        self.error = None;
+        self.step = CalibrationStep::Done;
+    }
+
     fn get_hold_progress(&self) -> f32 {
-        const REQUIRED_HOLD_MS: u128 = 1000;
         if self.is_touching && self.hold_duration_ms > 0 {
             (self.hold_duration_ms as f32 / REQUIRED_HOLD_MS as f32).min(1.0)
         } else {
@@ -378,26 +857,17 @@ impl Calibration {
         }
     }
 
-    fn is_hold_complete(&self) -> bool {
-        const REQUIRED_HOLD_MS: u128 = 1000;
+    fn hold_complete(&self) -> bool {
         self.is_touching && self.hold_duration_ms >= REQUIRED_HOLD_MS
     }
 
     fn update_hold_duration(&mut self) {
-        // Update hold duration based on current time
-        if self.is_touching {
-            if let Some(start_time) = self.touch_start_time {
-                let current_time = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_millis();
-                self.hold_duration_ms = current_time.saturating_sub(start_time);
-            }
-        }
+        self.advance_hold(unix_millis());
     }
 
     #[inline]
     fn map(&self, raw_x: u16, raw_y: u16) -> (u16, u16) {
+        let (raw_x, raw_y) = self.orient(raw_x, raw_y);
         let nx = ((raw_x as i32 - self.min_x as i32) as f32 * self.scale_x)
             .clamp(0.0, CALIBRATED_MAX_X as f32);
         let ny = ((raw_y as i32 - self.min_y as i32) as f32 * self.scale_y)
@@ -416,94 +886,1610 @@ impl Calibration {
     }
 }
 
-#[derive(Clone)]
-struct TouchPoint {
-    x: u16,
-    y: u16,
-    timestamp: u128, // Changed to u128 to match SystemTime milliseconds
+/// How raw axes with `swap_axes`, `invert_x` and `invert_y` lie on the screen,
+/// in plain words. None for an upright panel.
+fn orientation_words(swap_axes: bool, invert_x: bool, invert_y: bool) -> Option<&'static str> {
+    match (swap_axes, invert_x, invert_y) {
+        (false, false, false) => None,
+        (false, true, true) => Some("rotated 180°"),
+        (false, true, false) => Some("mirrored left to right"),
+        (false, false, true) => Some("mirrored top to bottom"),
+        // Raw X runs down the screen, raw Y leftwards
+        (true, true, false) => Some("rotated 90° clockwise"),
+        (true, false, true) => Some("rotated 90° counter-clockwise"),
+        (true, false, false) => Some("mirrored across the diagonal (X and Y swapped)"),
+        (true, true, true) => Some("mirrored across the other diagonal"),
+    }
+}
+
+/// Whether the move from the top left to the top right corner went along raw Y
+fn axes_swapped(tl: (u16, u16), tr: (u16, u16)) -> bool {
+    let dx = (tr.0 as i32 - tl.0 as i32).abs();
+    let dy = (tr.1 as i32 - tl.1 as i32).abs();
+    dy > dx
+}
+
+/// Check that the corners, in order TL, TR, BR, BL, form a rectangle: the top and
+/// bottom edges run along X, the sides along Y, and opposite edges point the same way
+fn check_geometry(pts: [(u16, u16); 4]) -> Result<(), String> {
+    let [tl, tr, br, bl] = pts.map(|(x, y)| (x as i32, y as i32));
+    let along_x = |a: (i32, i32), b: (i32, i32)| (b.0 - a.0).abs() > (b.1 - a.1).abs();
+
+    if !along_x(tl, tr) || !along_x(bl, br) {
+        return Err("top and bottom edges don't run along the same axis, \
+             were the corners touched in order?"
+            .to_string());
+    }
+    if along_x(tl, bl) || along_x(tr, br) {
+        return Err("left and right edges don't run along the same axis, \
+             were the corners touched in order?"
+            .to_string());
+    }
+    if (tr.0 - tl.0).signum() != (br.0 - bl.0).signum() {
+        return Err("top and bottom edges point in opposite X directions".to_string());
+    }
+    if (bl.1 - tl.1).signum() != (br.1 - tr.1).signum() {
+        return Err("left and right edges point in opposite Y directions".to_string());
+    }
+    Ok(())
 }
 
 struct TouchStatistics {
     max_jump: f32,
     total_jumps: u32,
     total_samples: u32,
+
+    // Inter-sample distances, bucketed by the distance rounded up, so jumps can be
+    // recounted exactly for any whole-unit threshold
+    distances: Vec<u32>,
+    max_distance: f32,
+    // (dx, dy) of the longest step, for converting it to millimetres
+    max_step: (f32, f32),
+
+    // Pinch playground: extremes of the two-finger distance (calibrated units)
+    min_pinch: Option<f32>,
+    max_pinch: Option<f32>,
+
+    // Time spent touching and the last sample of the current stroke, for the rate
+    touch_ms: u128,
+    last_sample_ms: Option<u128>,
+
+    // Fastest step and largest speed change of any stroke, see `Stroke`
+    max_speed: f32,
+    max_speed_mm: Option<f32>,
+    max_acceleration: f32,
 }
 
 impl TouchStatistics {
     fn new() -> Self {
+        Self::with_buckets(DISTANCE_BUCKETS)
+    }
+
+    /// Statistics bucketing distances up to `buckets` - 1 exactly, enough for
+    /// the diagonal of the units they're recorded in
+    fn with_buckets(buckets: usize) -> Self {
         Self {
             max_jump: 0.0,
             total_jumps: 0,
             total_samples: 0,
+            distances: vec![0; buckets],
+            max_distance: 0.0,
+            max_step: (0.0, 0.0),
+            min_pinch: None,
+            max_pinch: None,
+            touch_ms: 0,
+            last_sample_ms: None,
+            max_speed: 0.0,
+            max_speed_mm: None,
+            max_acceleration: 0.0,
+        }
+    }
+
+    /// Count a sample with its timestamp (ms)
+    fn record_sample(&mut self, timestamp: u128, released: bool) {
+        self.total_samples += 1;
+        if let Some(last) = self.last_sample_ms {
+            self.touch_ms += timestamp.saturating_sub(last);
         }
+        self.last_sample_ms = (!released).then_some(timestamp);
+    }
+
+    /// Samples per second while touching
+    fn rate(&self) -> Option<f32> {
+        (self.touch_ms > 0).then(|| self.total_samples as f32 * 1000.0 / self.touch_ms as f32)
+    }
+
+    /// Mean distance between consecutive samples that aren't jumps
+    fn jitter(&self, threshold: f32) -> Option<f32> {
+        let last = (threshold as usize).min(self.distances.len() - 1);
+        let steps: u32 = self.distances[..=last].iter().sum();
+        let total: f32 = self.distances[..=last]
+            .iter()
+            .enumerate()
+            .map(|(distance, count)| distance as f32 * *count as f32)
+            .sum();
+        (steps > 0).then(|| total / steps as f32)
     }
 
     fn reset(&mut self) {
-        *self = Self::new();
+        *self = Self::with_buckets(self.distances.len());
+    }
+
+    fn record_step(&mut self, dx: f32, dy: f32, threshold: f32) {
+        let distance = (dx * dx + dy * dy).sqrt();
+        let bucket = (distance.ceil() as usize).min(self.distances.len() - 1);
+        self.distances[bucket] += 1;
+        if distance > self.max_distance {
+            self.max_distance = distance;
+            self.max_step = (dx, dy);
+        }
+
+        if distance > threshold {
+            self.total_jumps += 1;
+            self.max_jump = self.max_jump.max(distance);
+        }
+    }
+
+    /// Take the speed maxima of a stroke that grew
+    fn record_speeds(&mut self, stroke: &Stroke, scale: Option<MmScale>) {
+        if stroke.max_speed() > self.max_speed {
+            self.max_speed = stroke.max_speed();
+            self.max_speed_mm = scale.map(|scale| stroke.max_speed_mm(scale));
+        }
+        self.max_acceleration = self.max_acceleration.max(stroke.max_acceleration());
+    }
+
+    // Recount jumps against a new threshold from the recorded distances
+    fn recount_jumps(&mut self, threshold: f32) {
+        let first = (threshold as usize + 1).min(self.distances.len());
+        self.total_jumps = self.distances[first..].iter().sum();
+        self.max_jump = if self.max_distance > threshold {
+            self.max_distance
+        } else {
+            0.0
+        };
     }
 }
 
-pub struct TouchscreenTestScreen {
-    is_touched: Vec<bool>,
-    last_touch: Option<AppEvent>,
+/// The second device of a comparison, e.g. the USB HID path of a panel next to
+/// its serial path. Both see the same touches, so it's calibrated alongside the
+/// selected device and counted on its own, with its own trail. The rest of the
+/// test (cells, corners, strokes) only follows the selected device.
+struct ComparedDevice {
     calibration: Calibration,
-    touching_idx: Option<usize>,
-
-    // New high-precision features
-    trail: VecDeque<TouchPoint>,
-    current_touch: Option<TouchPoint>,
     statistics: TouchStatistics,
+    // Recent calibrated points, newest last
+    trail: VecDeque<TouchPoint>,
     last_position: Option<(u16, u16)>,
 }
 
-impl TouchscreenTestScreen {
-    #[inline]
-    fn idx(&self, c: usize, r: usize) -> usize {
-        r * (COLS as usize) + c
-    }
-
-    pub fn new() -> Self {
-        TouchscreenTestScreen {
-            is_touched: vec![false; (COLS * ROWS) as usize],
-            last_touch: None,
-            calibration: Calibration::new(),
-            touching_idx: None,
-            trail: VecDeque::with_capacity(MAX_TRAIL_LENGTH),
-            current_touch: None,
+impl ComparedDevice {
+    fn new(capture_mode: CaptureMode, info: DeviceInfo) -> Self {
+        ComparedDevice {
+            calibration: Calibration::for_device(capture_mode, info),
             statistics: TouchStatistics::new(),
+            trail: VecDeque::new(),
             last_position: None,
         }
     }
 
-    // Map (raw) -> (calibrated logical)
-    fn map_raw(&self, x: u16, y: u16) -> (u16, u16) {
-        if self.calibration.is_done() {
-            self.calibration.map(x, y)
+    fn info(&self) -> Option<&DeviceInfo> {
+        self.calibration.selected_device_info.as_ref()
+    }
+
+    fn is_source(&self, info: Option<&DeviceInfo>) -> bool {
+        info.is_some_and(|info| self.calibration.selected_device_path.as_ref() == Some(&info.path))
+    }
+
+    fn on_sample(
+        &mut self,
+        (x, y): (u16, u16),
+        timestamp: u128,
+        released: bool,
+        keepalive: bool,
+        jump_threshold: f32,
+    ) {
+        if !self.calibration.is_done() {
+            self.calibration.on_sample(x, y, released, unix_millis());
+            return;
+        }
+        if keepalive {
+            return;
+        }
+
+        let (mx, my) = self.calibration.map(x, y);
+        self.statistics.record_sample(timestamp, released);
+        if let Some((last_x, last_y)) = self.last_position {
+            self.statistics.record_step(
+                mx as f32 - last_x as f32,
+                my as f32 - last_y as f32,
+                jump_threshold,
+            );
+        }
+
+        if released {
+            self.last_position = None;
         } else {
-            // During calibration just clamp to logical space so header can display something sane
-            (x.min(CALIBRATED_MAX_X), y.min(CALIBRATED_MAX_Y))
+            self.last_position = Some((mx, my));
+            if self.trail.len() == MAX_TRAIL_LENGTH {
+                self.trail.pop_front();
+            }
+            self.trail.push_back(TouchPoint {
+                x: mx,
+                y: my,
+                timestamp: unix_millis(),
+            });
         }
     }
 
-    fn mark(&mut self, x: u16, y: u16) {
-        let col = (x * COLS / CALIBRATED_MAX_X).min(COLS - 1);
-        let row = (y * ROWS / CALIBRATED_MAX_Y).min(ROWS - 1);
+    fn reset_statistics(&mut self) {
+        self.statistics.reset();
+        self.trail.clear();
+        self.last_position = None;
+    }
 
-        let index = self.idx(col as usize, row as usize);
-        if index < self.is_touched.len() {
-            self.is_touched[index] = true;
+    /// The panel is the selected device's, so `panel_mm` is too
+    fn stats(&self, jump_threshold: f32, panel_mm: Option<(f32, f32)>) -> DeviceStats {
+        device_stats(
+            self.info(),
+            self.calibration.is_done(),
+            &self.statistics,
+            jump_threshold,
+            calibrated_mm_scale(&self.calibration, panel_mm),
+        )
+    }
+}
+
+fn device_stats(
+    info: Option<&DeviceInfo>,
+    calibrated: bool,
+    statistics: &TouchStatistics,
+    jump_threshold: f32,
+    scale: Option<MmScale>,
+) -> DeviceStats {
+    let jitter = statistics.jitter(jump_threshold);
+    DeviceStats {
+        device: info.map_or_else(String::new, |info| info.name.clone()),
+        calibrated,
+        samples: statistics.total_samples,
+        jumps: statistics.total_jumps,
+        max_jump: statistics.max_jump,
+        rate_hz: statistics.rate(),
+        jitter,
+        jitter_mm: jitter
+            .zip(scale)
+            .map(|(jitter, scale)| scale.length_mm(jitter)),
+    }
+}
+
+/// Millimetres per calibrated unit of a finished calibration, from the device's
+/// axis resolution or else the panel size
+fn calibrated_mm_scale(calibration: &Calibration, panel_mm: Option<(f32, f32)>) -> Option<MmScale> {
+    if !calibration.is_done() {
+        return None;
+    }
+
+    calibration
+        .selected_device_info
+        .as_ref()
+        .and_then(|info| info.resolution)
+        .and_then(|resolution| {
+            MmScale::from_resolution(resolution, (calibration.scale_x, calibration.scale_y))
+        })
+        .or_else(|| {
+            panel_mm
+                .map(|panel| MmScale::from_panel_size(panel, (CALIBRATED_MAX_X, CALIBRATED_MAX_Y)))
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TestView {
+    Trail,
+    Pinch,
+    Pressure,
+    Jumps,
+}
+
+// Pressure threshold tuning for resistive panels that ghost-touch at light pressure
+struct PressureTuning {
+    // Most recent raw pressure values, oldest first
+    history: VecDeque<i32>,
+    // Candidate threshold, samples below it are left out of the trail and statistics
+    threshold: i32,
+    // Samples dropped by the threshold since the last reset
+    filtered: u32,
+}
+
+impl PressureTuning {
+    fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(PRESSURE_HISTORY),
+            threshold: 0,
+            filtered: 0,
         }
     }
 
-    fn handle_touch(&mut self, touch_event: AppEvent) {
-        if let AppEvent::Touch {
+    fn record(&mut self, pressure: i32) {
+        self.history.push_back(pressure);
+        if self.history.len() > PRESSURE_HISTORY {
+            self.history.pop_front();
+        }
+    }
+
+    // Move the threshold by about 2% of the axis range
+    fn adjust(&mut self, up: bool, max: i32) {
+        let step = (max / 50).max(1);
+        self.threshold = if up {
+            (self.threshold + step).min(max)
+        } else {
+            (self.threshold - step).max(0)
+        };
+    }
+}
+
+// Two-finger gesture state for the pinch/rotate playground
+struct PinchGesture {
+    // Calibrated positions of the two tracked contacts
+    contacts: Option<[(u16, u16); 2]>,
+    // Distance and angle when the second finger went down
+    base: Option<(f32, f32)>,
+}
+
+impl PinchGesture {
+    fn new() -> Self {
+        Self {
+            contacts: None,
+            base: None,
+        }
+    }
+
+    fn distance(&self) -> Option<f32> {
+        self.contacts.map(|[a, b]| {
+            let dx = b.0 as f32 - a.0 as f32;
+            let dy = b.1 as f32 - a.1 as f32;
+            (dx * dx + dy * dy).sqrt()
+        })
+    }
+
+    // Angle of the line from the first to the second contact, in degrees
+    fn angle(&self) -> Option<f32> {
+        self.contacts.map(|[a, b]| {
+            let dx = b.0 as f32 - a.0 as f32;
+            let dy = b.1 as f32 - a.1 as f32;
+            dy.atan2(dx).to_degrees()
+        })
+    }
+
+    // Scale and rotation (degrees) relative to the start of the gesture
+    fn transform(&self) -> Option<(f32, f32)> {
+        let (base_dist, base_angle) = self.base?;
+        let scale = self.distance()? / base_dist.max(1.0);
+        let rotation = self.angle()? - base_angle;
+        Some((scale, rotation))
+    }
+}
+
+/// Touchscreen test: device selection, four corner calibration, then a drawing
+/// canvas counting jumps and coverage
+pub struct TouchscreenTestScreen {
+    is_touched: Vec<bool>,
+    // Touch-downs in the corners, a pass criterion next to the grid
+    corners: CornerChecks,
+    last_touch: Option<AppEvent>,
+    calibration: Calibration,
+    // Second device when two were marked in the device selection
+    compared: Option<ComparedDevice>,
+    touching_idx: Option<usize>,
+
+    // New high-precision features
+    strokes: VecDeque<Stroke>,
+    // Strokes evicted past MAX_STROKES, so a whole shift of testing stays bounded
+    evicted_strokes: StrokeTotals,
+    stroke_open: bool,
+    // Trail only shows points newer than this (ms since epoch)
+    trail_cutoff: u128,
+    // Connect consecutive samples of a stroke instead of plotting single points
+    draw_lines: bool,
+    // Speed graph of the latest stroke over the trail, toggled with V
+    show_speed: bool,
+    // Draw the trail with the panel's physical proportions instead of filling the terminal
+    true_aspect: bool,
+    // No calibration: raw coordinates go onto the canvas over the axis range for
+    // display only, the statistics count raw units. Chosen with W.
+    raw_mode: bool,
+    // Part of the panel coverage and corners are checked in, the rest is
+    // behind the bezel. From the config or dragged out after O.
+    roi: Roi,
+    // O was pressed, the next drag sets the region. The corners of the drag so far.
+    roi_setup: bool,
+    roi_drag: Option<((u16, u16), (u16, u16))>,
+    // Touch-downs outside the region, which the bezel should have blocked
+    masked_touches: usize,
+    // Jitter filter shown next to the raw samples, picked with B, and the
+    // weight B gives the moving average
+    stabilizer: Option<Stabilizer>,
+    stabilizer_alpha: f32,
+    // Filtered position of the current touch, and its steps counted against
+    // the jump threshold like the raw ones
+    stabilized_touch: Option<(f32, f32)>,
+    stabilized_statistics: TouchStatistics,
+    // Trackpad-class devices are left out unless the operator asks for them
+    include_trackpads: bool,
+    // Last first-finger position of an included trackpad, for its release
+    trackpad_position: Option<(u16, u16)>,
+    current_touch: Option<TouchPoint>,
+    statistics: TouchStatistics,
+    last_position: Option<(u16, u16)>,
+
+    view: TestView,
+    pinch: PinchGesture,
+    pressure: PressureTuning,
+    // Physical panel size, used for millimetres when the device has no axis resolution
+    panel_mm: Option<(f32, f32)>,
+    // Distance between consecutive samples above which a jump is counted
+    jump_threshold: f32,
+    duplicates: DuplicateTouchDetector,
+    saturation: SaturationDetector,
+    wake: WakeLatency,
+    // Pen hover-to-touch offsets of the selected device
+    hover: PenHover,
+    // Latest fault reported for the selected device
+    device_fault: Option<String>,
+    // SYN_DROPPED overflows of the selected device this session
+    syn_drops: u32,
+    // Baud rate the serial reader settled on, by device path. It can settle
+    // before the panel is selected.
+    serial_status: Option<(String, SerialResult)>,
+    // OS calibration to check, taken as the calibration of the first device selected
+    seed_matrix: Option<CalibrationMatrix>,
+    // Where to write the matrix of a finished calibration
+    matrix_out: Option<PathBuf>,
+    // libinput matrix of the current calibration, and how writing or seeding it went
+    matrix: Option<CalibrationMatrix>,
+    matrix_note: Option<String>,
+
+    // Points currently held by `strokes`, bounded by MAX_RECORDED_POINTS
+    recorded_points: usize,
+    trail_grid: RefCell<TrailGrid>,
+    // Touch equivalents of the keys, one set per stage
+    selection_buttons: TouchButtons,
+    calibration_buttons: TouchButtons,
+    orientation_buttons: TouchButtons,
+    test_buttons: TouchButtons,
+
+    // Kiosk testing one unit after another: each unit gets its own session
+    session: u32,
+    // Open while asking whether to start the next session
+    confirm_new_session: Option<TouchPad>,
+    earlier_sessions: VecDeque<TouchscreenResult>,
+    dropped_sessions: u32,
+    // Name of the selected panel after it disappeared, a panel of that name showing
+    // up again means the unit was swapped
+    removed_panel: Option<String>,
+    // The selected device went away before the calibration finished, the corners
+    // taken so far wait for it to come back
+    disconnected: Option<DeviceInfo>,
+    power: PowerMonitor,
+    // Runs from the calibration and restarts with the statistics, paused on other screens
+    timer: TestTimer,
+
+    // Unattended soak run, see `soak`
+    soak: Option<SoakRecorder>,
+    soak_interval: Duration,
+    // Start the soak as soon as the panel is calibrated
+    soak_on_start: bool,
+    log: Option<WriterHandle>,
+    // Color of the trail's jumps
+    theme: Theme,
+    // Rendering given up because frames are over budget
+    degradations: Vec<Degradation>,
+    // Unix time in ms the trail points are stamped and expired with
+    clock: fn() -> u128,
+}
+
+impl TouchscreenTestScreen {
+    #[inline]
+    fn idx(&self, c: usize, r: usize) -> usize {
+        r * (COLS as usize) + c
+    }
+
+    /// Start on the device selection. `panel_mm` is the panel size for devices that
+    /// don't report their axis resolution.
+    pub fn new(capture_mode: CaptureMode, panel_mm: Option<(f32, f32)>) -> Self {
+        TouchscreenTestScreen {
+            is_touched: vec![false; (COLS * ROWS) as usize],
+            corners: CornerChecks::new(DEFAULT_CORNER_MARGIN_MM, false, cell_size()),
+            last_touch: None,
+            calibration: Calibration::new(capture_mode),
+            compared: None,
+            touching_idx: None,
+            strokes: VecDeque::new(),
+            evicted_strokes: StrokeTotals::default(),
+            stroke_open: false,
+            trail_cutoff: 0,
+            draw_lines: false,
+            show_speed: false,
+            true_aspect: false,
+            raw_mode: false,
+            roi: Roi::FULL,
+            roi_setup: false,
+            roi_drag: None,
+            masked_touches: 0,
+            stabilizer: None,
+            stabilizer_alpha: DEFAULT_ALPHA,
+            stabilized_touch: None,
+            stabilized_statistics: TouchStatistics::new(),
+            include_trackpads: false,
+            trackpad_position: None,
+            current_touch: None,
+            statistics: TouchStatistics::new(),
+            last_position: None,
+            view: TestView::Trail,
+            pinch: PinchGesture::new(),
+            pressure: PressureTuning::new(),
+            panel_mm,
+            jump_threshold: DEFAULT_JUMP_THRESHOLD,
+            duplicates: DuplicateTouchDetector::new(),
+            saturation: SaturationDetector::new(),
+            wake: WakeLatency::new(),
+            hover: PenHover::new(DEFAULT_HOVER_OFFSET_MM),
+            device_fault: None,
+            syn_drops: 0,
+            serial_status: None,
+            seed_matrix: None,
+            matrix_out: None,
+            matrix: None,
+            matrix_note: None,
+            recorded_points: 0,
+            trail_grid: RefCell::new(TrailGrid::default()),
+            selection_buttons: TouchButtons::new(&[
+                TouchAction::Next,
+                TouchAction::Select,
+                TouchAction::Back,
+            ]),
+            calibration_buttons: TouchButtons::new(&[TouchAction::Recalibrate, TouchAction::Back]),
+            orientation_buttons: TouchButtons::new(&[
+                TouchAction::Accept,
+                TouchAction::Recalibrate,
+                TouchAction::Back,
+            ]),
+            test_buttons: TouchButtons::new(&[
+                TouchAction::NewSession,
+                TouchAction::Reset,
+                TouchAction::Recalibrate,
+                TouchAction::Back,
+            ]),
+            session: 1,
+            confirm_new_session: None,
+            earlier_sessions: VecDeque::new(),
+            dropped_sessions: 0,
+            removed_panel: None,
+            disconnected: None,
+            power: PowerMonitor::new(),
+            timer: TestTimer::start(),
+            soak: None,
+            soak_interval: DEFAULT_SOAK_INTERVAL,
+            soak_on_start: false,
+            log: None,
+            theme: Theme::default(),
+            degradations: Vec::new(),
+            clock: unix_millis,
+        }
+    }
+
+    /// Start a soak with snapshots every `interval` once calibrated
+    pub fn with_soak(mut self, interval: Duration) -> Self {
+        self.soak_interval = interval;
+        self.soak_on_start = true;
+        self
+    }
+
+    /// Require corner touch-downs within `margin_mm` of the edges, and the edge
+    /// midpoints as well if `midpoints`
+    pub fn with_corners(mut self, margin_mm: f32, midpoints: bool) -> Self {
+        self.corners = CornerChecks::new(margin_mm, midpoints, cell_size());
+        self
+    }
+
+    /// Flag pen-downs landing more than `mm` from the last hover position
+    pub fn with_hover_threshold(mut self, mm: f32) -> Self {
+        self.hover = PenHover::new(mm);
+        self
+    }
+
+    /// Calibrate the first device selected with `seed` instead of the corner
+    /// holds, and write the matrix of every finished calibration to `out`
+    pub fn with_calibration_matrix(
+        mut self,
+        seed: Option<CalibrationMatrix>,
+        out: Option<PathBuf>,
+    ) -> Self {
+        self.seed_matrix = seed;
+        self.matrix_out = out;
+        self
+    }
+
+    /// Count steps longer than `threshold` calibrated units as jumps, until ←/→
+    /// in the jump view tune it
+    pub fn with_jump_threshold(mut self, threshold: f32) -> Self {
+        self.jump_threshold = threshold.clamp(JUMP_THRESHOLD_STEP, (DISTANCE_BUCKETS - 1) as f32);
+        self
+    }
+
+    /// Shortest time a session has to run to pass
+    pub fn with_min_duration(mut self, min: Duration) -> Self {
+        self.timer.set_min(min);
+        self
+    }
+
+    /// Flush soak snapshots to the soak log in `log`'s directory as they're taken
+    pub fn with_log(mut self, log: Option<WriterHandle>) -> Self {
+        self.log = log;
+        self
+    }
+
+    /// Check coverage and corners only within `roi`, e.g. from the config
+    pub fn with_roi(mut self, roi: Option<Roi>) -> Self {
+        self.set_roi(roi.unwrap_or_default());
+        self
+    }
+
+    /// Show `filter`'s crosshair next to the raw one from the start, B picks
+    /// the moving average with `alpha`
+    pub fn with_stabilizer(mut self, filter: Option<Filter>, alpha: f32) -> Self {
+        self.stabilizer = filter.map(Stabilizer::new);
+        self.stabilizer_alpha = alpha;
+        self
+    }
+
+    /// Draw trail jumps in `theme`'s warning color
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    fn touch_buttons(&self) -> &TouchButtons {
+        if self.calibration.step == CalibrationStep::DeviceSelection {
+            &self.selection_buttons
+        } else if self.calibration.step == CalibrationStep::ConfirmOrientation {
+            &self.orientation_buttons
+        } else if !self.calibration.is_done() {
+            &self.calibration_buttons
+        } else {
+            &self.test_buttons
+        }
+    }
+
+    /// Pass a touch to the buttons of the current stage. Returns the tapped
+    /// button's action, and whether the touch was the buttons' and not the panel's.
+    fn touch_button(&mut self, event: &AppEvent) -> (Option<TouchAction>, bool) {
+        let AppEvent::Touch {
+            x,
+            y,
+            released,
+            info,
+            ..
+        } = event
+        else {
+            return (None, false);
+        };
+
+        if self.calibration.step == CalibrationStep::DeviceSelection {
+            let action = self.selection_buttons.handle_raw_touch(event);
+            return (action, self.selection_buttons.captured());
+        }
+
+        // Past the selection only the selected panel presses buttons
+        let selected = self.calibration.selected_device_path.as_ref();
+        if info.as_ref().map(|info| &info.path) != selected {
+            return (None, false);
+        }
+
+        if self.calibration.step == CalibrationStep::ConfirmOrientation {
+            let action = self.orientation_buttons.handle_raw_touch(event);
+            return (action, self.orientation_buttons.captured());
+        }
+
+        if !self.calibration.is_done() {
+            // Not calibrated yet, so placed with the range the panel reports
+            let action = self.calibration_buttons.handle_raw_touch(event);
+            return (action, self.calibration_buttons.captured());
+        }
+
+        // Calibrated coordinates span the canvas, letterboxed in the trail view
+        let area = self.test_layout(self.test_buttons.frame_area()).canvas;
+        let position = self.calibration.map(*x, *y);
+        let max = (CALIBRATED_MAX_X as u32, CALIBRATED_MAX_Y as u32);
+        let action = self
+            .test_buttons
+            .handle_scaled_touch(position, max, area, *released);
+        (action, self.test_buttons.captured())
+    }
+
+    fn select_next_device(&mut self) {
+        let count = self.calibration.available_devices.len();
+        if count > 0 {
+            self.calibration.selected_device_index =
+                (self.calibration.selected_device_index + 1) % count;
+        }
+    }
+
+    fn select_device(&mut self, index: usize) {
+        if index < self.calibration.available_devices.len() {
+            self.calibration.selected_device_index = index;
+            let selected = &self.calibration.available_devices[index];
+            self.calibration.selected_device_path = Some(selected.path.clone());
+            self.calibration.selected_device_info = Some(selected.clone());
+            self.calibration.restart_corners();
+            if self.raw_mode {
+                self.use_raw_range();
+            } else if let Some(matrix) = self.seed_matrix.take() {
+                self.seed_calibration(matrix);
+            }
+        }
+    }
+
+    /// The calibration is skipped and the statistics are in raw units
+    fn raw_active(&self) -> bool {
+        self.raw_mode && self.calibration.is_done()
+    }
+
+    /// Raw units per calibrated unit in raw mode, the mean of both axes
+    fn raw_units_per_unit(&self) -> f32 {
+        (1.0 / self.calibration.scale_x + 1.0 / self.calibration.scale_y) / 2.0
+    }
+
+    /// Skip the calibration: raw coordinates go onto the canvas over the
+    /// device's axis range as they come, not swapped or mirrored. The
+    /// statistics start over in raw units, the jump threshold rescaled to them.
+    fn use_raw_range(&mut self) {
+        let Some(limits) = self.selected_limits() else {
+            self.raw_mode = false;
+            self.calibration.error = Some(
+                "Raw mode needs the axis range, the device reports none and none was learned yet"
+                    .to_string(),
+            );
+            return;
+        };
+        let span = |(min, max): (u16, u16)| AxisSpan {
+            min: min as f32,
+            max: max as f32,
+            invert: false,
+        };
+        self.calibration.seed(&LinearMapping {
+            swap_axes: false,
+            x: span(limits.x),
+            y: span(limits.y),
+        });
+        self.raw_mode = true;
+        // Corners were never taken, so there is nothing to compare or write out
+        self.compared = None;
+        self.matrix = None;
+        self.matrix_note = None;
+
+        let (span_x, span_y) = (
+            limits.x.1.saturating_sub(limits.x.0) as f32,
+            limits.y.1.saturating_sub(limits.y.0) as f32,
+        );
+        let diagonal = (span_x * span_x + span_y * span_y).sqrt().ceil() as usize;
+        self.statistics = TouchStatistics::with_buckets(diagonal.max(DISTANCE_BUCKETS - 1) + 1);
+        self.jump_threshold = (self.jump_threshold * self.raw_units_per_unit()).round();
+        self.reset_statistics();
+        self.is_touched.fill(false);
+        self.corners.reset();
+        self.touching_idx = None;
+        self.timer.restart();
+    }
+
+    /// Back from raw units to calibrated ones, the jump threshold with them
+    fn leave_raw_units(&mut self) {
+        if self.raw_active() {
+            self.jump_threshold = (self.jump_threshold / self.raw_units_per_unit())
+                .round()
+                .clamp(JUMP_THRESHOLD_STEP, (DISTANCE_BUCKETS - 1) as f32);
+            self.statistics = TouchStatistics::new();
+        }
+    }
+
+    /// W in the test: into raw mode, or out of it to the corner calibration
+    fn toggle_raw_mode(&mut self) {
+        if self.raw_active() {
+            self.leave_raw_units();
+            self.raw_mode = false;
+            self.reset_statistics();
+            self.calibration.restart_corners();
+        } else {
+            self.raw_mode = true;
+            self.use_raw_range();
+        }
+    }
+
+    /// Raw axis range of the selected device: the reported one, else the one
+    /// learned from its samples
+    fn selected_limits(&self) -> Option<AxisLimits> {
+        let info = self.calibration.selected_device_info.as_ref()?;
+        AxisLimits::from_device(info).or(info.learned_range)
+    }
+
+    /// The selected device's learned range, once it spreads as far as a corner
+    /// calibration has to
+    fn usable_learned_range(&self) -> Option<AxisLimits> {
+        let limits = self
+            .calibration
+            .selected_device_info
+            .as_ref()?
+            .learned_range?;
+        let span_x = limits.x.1.saturating_sub(limits.x.0);
+        let span_y = limits.y.1.saturating_sub(limits.y.0);
+        let diagonal2 = span_x as u64 * span_x as u64 + span_y as u64 * span_y as u64;
+        (span_x >= MIN_SPAN_X && span_y >= MIN_SPAN_Y && diagonal2 >= MIN_DIAGONAL2)
+            .then_some(limits)
+    }
+
+    /// Calibrate to the learned range instead of the corner holds. The range
+    /// doesn't tell which way the axes run, they are taken as the device's hint
+    /// has them.
+    fn use_learned_range(&mut self) {
+        let Some(limits) = self.usable_learned_range() else {
+            return;
+        };
+        let span = |(min, max): (u16, u16)| AxisSpan {
+            min: min as f32,
+            max: max as f32,
+            invert: false,
+        };
+        let swap_axes = self.calibration.swap_hint();
+        let (x, y) = if swap_axes {
+            (limits.y, limits.x)
+        } else {
+            (limits.x, limits.y)
+        };
+        self.calibration.seed(&LinearMapping {
+            swap_axes,
+            x: span(x),
+            y: span(y),
+        });
+        self.on_calibrated();
+    }
+
+    /// Calibrate the selected device from an OS matrix, falling back to the
+    /// corner holds when it doesn't apply
+    fn seed_calibration(&mut self, matrix: CalibrationMatrix) {
+        let limits = self.selected_limits();
+        let Some(limits) = limits else {
+            self.matrix_note = Some(
+                "Matrix not applied: device reports no axis range, calibrate by the corners"
+                    .to_string(),
+            );
+            return;
+        };
+        match matrix.to_mapping(limits) {
+            Ok(mapping) => {
+                self.calibration.seed(&mapping);
+                self.matrix = Some(matrix);
+                self.matrix_note = Some("Checking the given matrix".to_string());
+            }
+            Err(e) => self.matrix_note = Some(format!("Matrix not applied: {e}")),
+        }
+    }
+
+    /// The operator agreed with the orientation the corners showed, which
+    /// finishes the calibration
+    fn accept_orientation(&mut self) {
+        if self.calibration.step == CalibrationStep::ConfirmOrientation {
+            self.calibration.accept_orientation();
+            self.on_calibrated();
+        }
+    }
+
+    /// The corner holds finished a calibration: work out its matrix and write it out
+    fn on_calibrated(&mut self) {
+        self.timer.restart();
+        self.update_matrix();
+    }
+
+    /// The libinput matrix of the calibration, written out with the region of
+    /// interest and the jump threshold
+    fn update_matrix(&mut self) {
+        let Some(limits) = self.selected_limits() else {
+            self.matrix = None;
+            self.matrix_note = Some("No matrix, the device reports no axis range".to_string());
+            return;
+        };
+        let matrix = CalibrationMatrix::from_mapping(&self.calibration.linear_mapping(), limits);
+        self.matrix = Some(matrix);
+        // A matrix over a learned range only means something with the range
+        let learned = self
+            .calibration
+            .selected_device_info
+            .as_ref()
+            .is_some_and(|info| AxisLimits::from_device(info).is_none())
+            .then_some(limits);
+        self.matrix_note = self.matrix_out.as_ref().map(|path| {
+            match matrix.write(path, learned, self.roi, self.jump_threshold) {
+                Ok(()) => format!("Matrix written to {}", path.display()),
+                Err(e) => format!("Matrix not written: {e}"),
+            }
+        });
+    }
+
+    /// Enter in the device selection: the two marked devices if two are marked,
+    /// the first of them in the list being the selected one, else the highlighted device
+    fn confirm_selection(&mut self) {
+        let marked: Vec<usize> = self
+            .calibration
+            .available_devices
+            .iter()
+            .enumerate()
+            .filter(|(_, device)| self.calibration.marked_devices.contains(&device.path))
+            .map(|(index, _)| index)
+            .collect();
+        // Raw mode takes no corners, which the second device would calibrate from
+        let pair = if self.raw_mode { &[][..] } else { &marked[..] };
+        let [first, second] = *pair else {
+            // A device whose listener died sends nothing, reopen it instead
+            let index = self.calibration.selected_device_index;
+            if let Some(device) = self.calibration.available_devices.get(index)
+                && listener_health::request_restart(&device.path)
+            {
+                return;
+            }
+            self.select_device(index);
+            return;
+        };
+
+        let info = self.calibration.available_devices[second].clone();
+        self.compared = Some(ComparedDevice::new(self.calibration.capture_mode, info));
+        self.select_device(first);
+    }
+
+    /// Space in the device selection, at most two devices are marked
+    fn toggle_mark(&mut self) {
+        let Some(device) = self
+            .calibration
+            .available_devices
+            .get(self.calibration.selected_device_index)
+        else {
+            return;
+        };
+        let marked = &mut self.calibration.marked_devices;
+        if let Some(position) = marked.iter().position(|path| *path == device.path) {
+            marked.remove(position);
+        } else if marked.len() < 2 {
+            marked.push(device.path.clone());
+        }
+    }
+
+    fn reset_statistics(&mut self) {
+        // Strokes count towards the statistics
+        self.statistics.reset();
+        self.stabilized_statistics.reset();
+        if let Some(compared) = &mut self.compared {
+            compared.reset_statistics();
+        }
+        self.saturation.reset();
+        self.wake.reset();
+        self.hover.reset();
+        self.pressure.filtered = 0;
+        self.masked_touches = 0;
+        self.clear_strokes();
+        self.power.start();
+        self.timer.restart();
+        if let Some(soak) = &mut self.soak {
+            soak.rebase();
+        }
+    }
+
+    /// Check coverage and corners within `roi` from now on. The corners start
+    /// over, they moved; cells touched inside the region still count.
+    fn set_roi(&mut self, roi: Roi) {
+        self.roi = roi;
+        self.corners.set_roi(roi);
+        self.masked_touches = 0;
+        if self.calibration.is_done() && !self.raw_mode {
+            self.update_matrix();
+        }
+    }
+
+    /// Run the sample at calibrated (`x`, `y`) through the jitter filter and
+    /// count the filtered position's step
+    fn stabilize(&mut self, x: u16, y: u16, released: bool) {
+        let Some(stabilizer) = &mut self.stabilizer else {
+            return;
+        };
+        let (fx, fy) = stabilizer.apply(x as f32, y as f32);
+        if let Some((last_x, last_y)) = self.stabilized_touch {
+            self.stabilized_statistics
+                .record_step(fx - last_x, fy - last_y, self.jump_threshold);
+        }
+        self.stabilized_touch = Some((fx, fy));
+        if released {
+            self.lift_stabilizer();
+        }
+    }
+
+    /// The finger is gone, the filter starts over with the next touch
+    fn lift_stabilizer(&mut self) {
+        self.stabilized_touch = None;
+        if let Some(stabilizer) = &mut self.stabilizer {
+            stabilizer.reset();
+        }
+    }
+
+    /// B: the moving average, the median-of-3 despiker, no filter, round
+    /// again. The filtered jumps start over with each.
+    fn cycle_stabilizer(&mut self) {
+        let next = match self.stabilizer.as_ref().map(Stabilizer::filter) {
+            None => Some(Filter::Ema {
+                alpha: self.stabilizer_alpha,
+            }),
+            Some(Filter::Ema { .. }) => Some(Filter::Median3),
+            Some(Filter::Median3) => None,
+        };
+        self.stabilizer = next.map(Stabilizer::new);
+        self.stabilized_touch = None;
+        self.stabilized_statistics.reset();
+    }
+
+    /// O: the next drag sets the region, O again before dragging covers the
+    /// whole panel again
+    fn toggle_roi_setup(&mut self) {
+        if self.roi_setup {
+            self.roi_setup = false;
+            self.roi_drag = None;
+            self.set_roi(Roi::FULL);
+        } else {
+            self.roi_setup = true;
+            self.finish_stroke();
+            self.current_touch = None;
+            self.last_position = None;
+            self.lift_stabilizer();
+        }
+    }
+
+    /// A touch while the region is being set: the drag from touch-down to
+    /// release spans it
+    fn drag_roi(&mut self, x: u16, y: u16, released: bool) {
+        let start = self.roi_drag.map_or((x, y), |(start, _)| start);
+        self.roi_drag = Some((start, (x, y)));
+        if !released {
+            return;
+        }
+        self.roi_setup = false;
+        self.roi_drag = None;
+        // Too small a drag is a slip, the region stays as it was
+        if let Some(roi) = Roi::from_corners(start, (x, y)) {
+            self.set_roi(roi);
+        }
+    }
+
+    /// Grid cells inside the region, touched and in total
+    fn coverage(&self) -> (usize, usize) {
+        let inside = (0..ROWS as usize)
+            .flat_map(|row| (0..COLS as usize).map(move |col| (col, row)))
+            .filter(|&(col, row)| {
+                self.roi
+                    .contains_cell(col, row, COLS as usize, ROWS as usize)
+            })
+            .map(|(col, row)| self.is_touched[self.idx(col, row)]);
+        inside.fold((0, 0), |(touched, total), cell| {
+            (touched + cell as usize, total + 1)
+        })
+    }
+
+    fn soak_counters(&self) -> SoakCounters {
+        SoakCounters {
+            samples: self.statistics.total_samples as u64,
+            jumps: self.statistics.total_jumps as u64,
+            saturated: self
+                .saturation
+                .detected()
+                .iter()
+                .map(|(_, samples)| samples)
+                .sum(),
+        }
+    }
+
+    /// Start a soak, or stop the running one keeping its snapshots
+    fn toggle_soak(&mut self) {
+        match &mut self.soak {
+            Some(soak) if soak.is_running() => soak.stop(),
+            _ => {
+                self.soak = Some(SoakRecorder::start(
+                    self.soak_interval,
+                    self.soak_counters(),
+                    self.log.clone(),
+                ));
+            }
+        }
+    }
+
+    fn degraded(&self, step: Degradation) -> bool {
+        self.degradations.contains(&step)
+    }
+
+    fn selected_serial_status(&self) -> Option<&SerialResult> {
+        let (path, status) = self.serial_status.as_ref()?;
+        (self.calibration.selected_device_path.as_ref() == Some(path)).then_some(status)
+    }
+
+    /// Result of the current session alone, without the earlier ones
+    fn session_result(&self) -> TouchscreenResult {
+        TouchscreenResult {
+            device: self
+                .calibration
+                .selected_device_info
+                .as_ref()
+                .map(|info| info.name.clone()),
+            total_samples: self.statistics.total_samples,
+            total_jumps: self.statistics.total_jumps,
+            max_jump: self.statistics.max_jump,
+            max_jump_mm: self.max_jump_mm(),
+            jump_threshold: self.jump_threshold,
+            jump_threshold_mm: self.jump_threshold_mm(),
+            true_aspect: self.true_aspect && self.mm_scale().is_some(),
+            raw_mode: self.raw_active(),
+            min_pinch: self.statistics.min_pinch,
+            max_pinch: self.statistics.max_pinch,
+            pressure_threshold: self.pressure_max().map(|_| self.pressure.threshold),
+            duplicate_devices: self.duplicates.detected().cloned(),
+            saturation: self
+                .saturation
+                .detected()
+                .into_iter()
+                .map(|(edge, samples)| SaturationResult {
+                    edge: edge.name().to_string(),
+                    samples,
+                })
+                .collect(),
+            cells_touched: self.coverage().0,
+            cells_total: self.coverage().1,
+            roi: (!self.roi.is_full()).then(|| self.roi.edges()),
+            masked_touches: self.masked_touches,
+            paths: self
+                .strokes
+                .iter()
+                .filter(|stroke| !stroke.points.is_empty())
+                .map(|stroke| stroke.points.iter().map(|p| (p.x, p.y)).collect())
+                .collect(),
+            strokes: self.strokes.iter().map(Stroke::summary).collect(),
+            evicted_strokes: self.evicted_strokes.clone(),
+            session: self.session,
+            earlier_sessions: Vec::new(),
+            dropped_sessions: 0,
+            power: self.power.result(),
+            timing: Some(self.timer.timing()),
+            soak: self.soak.as_ref().map(SoakRecorder::result),
+            corners: (!self.raw_mode).then(|| self.corners.result(self.mm_scale())),
+            serial: self.selected_serial_status().cloned(),
+            syn_drops: self.syn_drops,
+            wake: Some(Box::new(self.wake.result())),
+            hover: self.hover.result().map(Box::new),
+            max_speed: self.statistics.max_speed,
+            max_speed_mm: self.statistics.max_speed_mm,
+            max_acceleration: self.statistics.max_acceleration,
+            max_acceleration_mm: self
+                .mm_scale()
+                .map(|scale| scale.length_mm(self.statistics.max_acceleration)),
+            stabilizer: self.stabilizer.as_ref().map(|stabilizer| {
+                Box::new(StabilizerResult {
+                    filter: stabilizer.filter().name().to_string(),
+                    alpha: stabilizer.filter().alpha(),
+                    jumps: self.stabilized_statistics.total_jumps,
+                    max_jump: self.stabilized_statistics.max_jump,
+                })
+            }),
+            comparison: self.compared.as_ref().map(|compared| {
+                vec![
+                    device_stats(
+                        self.calibration.selected_device_info.as_ref(),
+                        self.calibration.is_done(),
+                        &self.statistics,
+                        self.jump_threshold,
+                        self.mm_scale(),
+                    ),
+                    compared.stats(self.jump_threshold, self.panel_mm),
+                ]
+            }),
+        }
+    }
+
+    /// Archive the current session and start the next one with clean statistics,
+    /// keeping the calibration. An untouched session isn't worth archiving.
+    fn new_session(&mut self) {
+        self.finish_stroke();
+        if self.statistics.total_samples > 0 {
+            self.earlier_sessions.push_back(self.session_result());
+            self.session += 1;
+
+            // Older sessions are reduced to totals, the oldest dropped entirely
+            if let Some(index) = self
+                .earlier_sessions
+                .len()
+                .checked_sub(MAX_DETAILED_SESSIONS + 1)
+            {
+                let old = &mut self.earlier_sessions[index];
+                old.paths.clear();
+                for stroke in old.strokes.drain(..) {
+                    old.evicted_strokes.add(&stroke);
+                }
+            }
+            if self.earlier_sessions.len() > MAX_ARCHIVED_SESSIONS {
+                self.earlier_sessions.pop_front();
+                self.dropped_sessions += 1;
+            }
+        }
+
+        self.reset_statistics();
+        self.is_touched.fill(false);
+        self.corners.reset();
+        self.touching_idx = None;
+        self.current_touch = None;
+        self.last_position = None;
+        self.lift_stabilizer();
+        self.pinch = PinchGesture::new();
+        self.duplicates = DuplicateTouchDetector::new();
+        self.device_fault = None;
+        self.syn_drops = 0;
+        // A running soak goes on with the next unit, as its own series
+        if self.soak.as_ref().is_some_and(SoakRecorder::is_running) {
+            self.soak = None;
+            self.toggle_soak();
+        } else {
+            self.soak = None;
+        }
+    }
+
+    fn ask_new_session(&mut self) {
+        self.confirm_new_session = Some(TouchPad::yes_no(&[
+            "Start a new session for the next unit?",
+            "This unit's results stay in the report.",
+        ]));
+    }
+
+    /// Keys and touches go to the open new session prompt: Y, Enter or Yes
+    /// starts it, N, Esc or No closes the prompt. Returns whether the event was
+    /// the prompt's, everything but input still reaches the screen.
+    fn handle_confirmation(&mut self, event: &AppEvent) -> bool {
+        let answer = match event {
+            AppEvent::Key { code, .. } => match *code {
+                KeyCode::KEY_Y | KeyCode::KEY_ENTER => Some(PadKey::Yes),
+                KeyCode::KEY_N | KeyCode::KEY_ESC => Some(PadKey::No),
+                _ => None,
+            },
+            AppEvent::Touch {
+                x,
+                y,
+                released,
+                info,
+                ..
+            } => {
+                let selected = self.calibration.selected_device_path.as_ref();
+                if info.as_ref().map(|info| &info.path) != selected {
+                    return true;
+                }
+                let area = self.test_layout(self.test_buttons.frame_area()).canvas;
+                let position = self.calibration.map(*x, *y);
+                let max = (CALIBRATED_MAX_X as u32, CALIBRATED_MAX_Y as u32);
+                self.confirm_new_session
+                    .as_mut()
+                    .and_then(|pad| pad.handle_scaled_touch(position, max, area, *released))
+            }
+            AppEvent::MultiTouch { .. } | AppEvent::Trackpad { .. } => return true,
+            _ => return false,
+        };
+
+        match answer {
+            Some(PadKey::Yes) => {
+                self.confirm_new_session = None;
+                self.new_session();
+            }
+            Some(_) => self.confirm_new_session = None,
+            None => {}
+        }
+        true
+    }
+
+    /// Throw away the calibration and start over from the first corner
+    fn recalibrate(&mut self) {
+        self.leave_raw_units();
+        self.calibration = Calibration::new(self.calibration.capture_mode);
+        self.disconnected = None;
+        self.matrix = None;
+        self.matrix_note = None;
+        self.compared = None;
+        self.clear_strokes();
+        self.statistics.reset();
+        self.current_touch = None;
+        self.last_position = None;
+        self.lift_stabilizer();
+        self.view = TestView::Trail;
+        self.pinch = PinchGesture::new();
+        self.pressure = PressureTuning::new();
+    }
+
+    /// Millimetres per calibrated unit, from the device's axis resolution or
+    /// else the configured panel size
+    fn mm_scale(&self) -> Option<MmScale> {
+        calibrated_mm_scale(&self.calibration, self.panel_mm)
+    }
+
+    /// Where the test view puts things in a frame of `area`, shared by drawing
+    /// and the touch buttons placing calibrated touches
+    fn test_layout(&self, area: Rect) -> TestLayout {
+        let canvas = if self.view == TestView::Trail {
+            self.canvas_area(area)
+        } else {
+            area
+        };
+        let corners = if canvas.width == 0 || canvas.height == 0 {
+            Vec::new()
+        } else {
+            self.corners
+                .targets()
+                .iter()
+                .map(|(target, touched)| {
+                    let (x, y) = self.corners.position(*target);
+                    let position = Position {
+                        x: canvas.x
+                            + (x / CALIBRATED_MAX_X as f32 * (canvas.width - 1) as f32) as u16,
+                        y: canvas.y
+                            + (y / CALIBRATED_MAX_Y as f32 * (canvas.height - 1) as f32) as u16,
+                    };
+                    (*target, *touched, position)
+                })
+                .collect()
+        };
+        TestLayout { canvas, corners }
+    }
+
+    /// Where the trail canvas goes: the whole frame, or in true aspect mode the part
+    /// of it with the panel's proportions. Needs the panel size in mm for that.
+    fn canvas_area(&self, area: Rect) -> Rect {
+        if !self.true_aspect {
+            return area;
+        }
+        match self.mm_scale() {
+            Some(scale) => {
+                let panel_aspect =
+                    (scale.x * CALIBRATED_MAX_X as f32) / (scale.y * CALIBRATED_MAX_Y as f32);
+                letterbox(area, panel_aspect, terminal_cell_aspect())
+            }
+            None => area,
+        }
+    }
+
+    /// Millimetres per unit of the jump statistics: calibrated units, or raw
+    /// ones in raw mode
+    fn step_mm_scale(&self) -> Option<MmScale> {
+        if !self.raw_active() {
+            return self.mm_scale();
+        }
+        let limits = self.selected_limits()?;
+        self.calibration
+            .selected_device_info
+            .as_ref()
+            .and_then(|info| info.resolution)
+            .and_then(|resolution| MmScale::from_resolution(resolution, (1.0, 1.0)))
+            .or_else(|| {
+                self.panel_mm.map(|panel| {
+                    MmScale::from_panel_size(
+                        panel,
+                        (
+                            limits.x.1.saturating_sub(limits.x.0),
+                            limits.y.1.saturating_sub(limits.y.0),
+                        ),
+                    )
+                })
+            })
+    }
+
+    fn max_step_mm(&self) -> Option<f32> {
+        let (dx, dy) = self.statistics.max_step;
+        self.step_mm_scale().map(|scale| scale.distance_mm(dx, dy))
+    }
+
+    fn max_jump_mm(&self) -> Option<f32> {
+        // The longest jump is always the longest step, or there is no jump
+        match self.statistics.total_jumps {
+            0 => self.step_mm_scale().map(|_| 0.0),
+            _ => self.max_step_mm(),
+        }
+    }
+
+    fn jump_threshold_mm(&self) -> Option<f32> {
+        self.step_mm_scale()
+            .map(|scale| scale.length_mm(self.jump_threshold))
+    }
+
+    fn record_stroke_point(&mut self, point: TouchPoint) {
+        let scale = self.mm_scale();
+        match self.strokes.back_mut() {
+            Some(stroke) if self.stroke_open => {
+                stroke.push(point);
+                self.statistics.record_speeds(stroke, scale);
+            }
+            _ => {
+                self.strokes.push_back(Stroke::new(point));
+                self.stroke_open = true;
+            }
+        }
+        self.recorded_points += 1;
+
+        if self.strokes.len() > MAX_STROKES
+            && let Some(oldest) = self.strokes.pop_front()
+        {
+            self.recorded_points -= oldest.points.len();
+            self.evicted_strokes.add(&oldest.summary());
+        }
+
+        // Past the limit the oldest strokes give up their points, their summaries stay
+        if self.recorded_points > MAX_RECORDED_POINTS
+            && let Some(oldest) = self.strokes.iter_mut().find(|s| !s.points.is_empty())
+        {
+            self.recorded_points -= oldest.points.len();
+            oldest.discard_points();
+        }
+    }
+
+    fn finish_stroke(&mut self) {
+        if self.stroke_open
+            && let Some(stroke) = self.strokes.back_mut()
+        {
+            stroke.finished = true;
+        }
+        self.stroke_open = false;
+    }
+
+    fn clear_strokes(&mut self) {
+        self.strokes.clear();
+        self.evicted_strokes = StrokeTotals::default();
+        self.stroke_open = false;
+        self.recorded_points = 0;
+    }
+
+    /// Recent points for the trail, oldest first, tagged with how many strokes ago
+    /// they were drawn (0 = the latest stroke)
+    fn trail_points(&self) -> Vec<(usize, TouchPoint)> {
+        let max_length = if self.degraded(Degradation::TrailLength) {
+            DEGRADED_TRAIL_LENGTH
+        } else {
+            MAX_TRAIL_LENGTH
+        };
+        let mut points = Vec::new();
+
+        'strokes: for (age, stroke) in self.strokes.iter().rev().enumerate() {
+            if stroke.end_time() < self.trail_cutoff {
+                break;
+            }
+            for point in stroke.points.iter().rev() {
+                if point.timestamp < self.trail_cutoff || points.len() >= max_length {
+                    break 'strokes;
+                }
+                points.push((age, *point));
+            }
+        }
+
+        points.reverse();
+        points
+    }
+
+    fn supports_multitouch(&self) -> bool {
+        self.calibration
+            .selected_device_info
+            .as_ref()
+            .is_some_and(|info| info.multitouch)
+    }
+
+    // Pressure range of the selected device, None if it has no pressure axis
+    fn pressure_max(&self) -> Option<i32> {
+        self.calibration
+            .selected_device_info
+            .as_ref()
+            .and_then(|info| info.pressure_max)
+            .filter(|max| *max > 0)
+    }
+
+    fn handle_multitouch(&mut self, contacts: &[TouchContact], info: &DeviceInfo) {
+        if self.view != TestView::Pinch || !self.calibration.is_done() {
+            return;
+        }
+
+        if self.calibration.selected_device_path.as_deref() != Some(info.path.as_str()) {
+            return;
+        }
+
+        if contacts.len() < 2 {
+            // Gesture ends as soon as one finger lifts
+            self.pinch = PinchGesture::new();
+            return;
+        }
+
+        let a = self.map_raw(contacts[0].x, contacts[0].y);
+        let b = self.map_raw(contacts[1].x, contacts[1].y);
+        self.pinch.contacts = Some([a, b]);
+
+        if let (Some(distance), Some(angle)) = (self.pinch.distance(), self.pinch.angle()) {
+            if self.pinch.base.is_none() {
+                self.pinch.base = Some((distance, angle));
+            }
+
+            let stats = &mut self.statistics;
+            stats.min_pinch = Some(stats.min_pinch.map_or(distance, |m| m.min(distance)));
+            stats.max_pinch = Some(stats.max_pinch.map_or(distance, |m| m.max(distance)));
+        }
+    }
+
+    // An included trackpad is tested like a panel: its first finger is the
+    // touch, all of them feed the pinch playground
+    fn handle_trackpad(
+        &mut self,
+        contacts: Vec<TouchContact>,
+        timestamp: u128,
+        info: Arc<DeviceInfo>,
+    ) {
+        let (x, y, released) = match contacts.first() {
+            Some(contact) => (contact.x, contact.y, false),
+            None => match self.trackpad_position.take() {
+                Some((x, y)) => (x, y, true),
+                None => return,
+            },
+        };
+        if !released {
+            self.trackpad_position = Some((x, y));
+        }
+
+        self.handle_multitouch(&contacts, &info);
+        self.handle_touch(AppEvent::Touch {
+            x,
+            y,
+            timestamp,
+            released,
+            pressure: None,
+            info: Some(info),
+            keepalive: false,
+        });
+    }
+
+    // Map (raw) -> (calibrated logical)
+    fn map_raw(&self, x: u16, y: u16) -> (u16, u16) {
+        if self.calibration.is_done() {
+            self.calibration.map(x, y)
+        } else {
+            // During calibration just clamp to logical space so header can display something sane
+            (x.min(CALIBRATED_MAX_X), y.min(CALIBRATED_MAX_Y))
+        }
+    }
+
+    fn mark(&mut self, x: u16, y: u16) {
+        let col = (x * COLS / CALIBRATED_MAX_X).min(COLS - 1);
+        let row = (y * ROWS / CALIBRATED_MAX_Y).min(ROWS - 1);
+
+        let index = self.idx(col as usize, row as usize);
+        if index < self.is_touched.len() {
+            self.is_touched[index] = true;
+        }
+    }
+
+    fn handle_touch(&mut self, touch_event: AppEvent) {
+        if let AppEvent::Touch {
             x,
             y,
             timestamp,
             released,
+            pressure,
             ref info,
+            keepalive,
         } = touch_event
         {
+            // Watch all devices, one panel showing up twice is exactly what the filter hides
+            if let Some(device_info) = info
+                && !released
+                && !keepalive
+            {
+                self.duplicates.observe(device_info, x, y, timestamp);
+            }
+
             // During device selection, collect device info from touch events
             if self.calibration.step == CalibrationStep::DeviceSelection {
                 if let Some(device_info) = info {
@@ -514,49 +2500,86 @@ impl TouchscreenTestScreen {
                         .iter()
                         .any(|d| d.path == device_info.path)
                     {
-                        self.calibration.available_devices.push(device_info.clone());
+                        self.calibration
+                            .available_devices
+                            .push(DeviceInfo::clone(device_info));
                     }
                 }
                 return;
             }
 
-            // After device selection, filter by selected device
-            if let Some(selected_path) = &self.calibration.selected_device_path {
-                if let Some(device_info) = info {
-                    if &device_info.path != selected_path {
-                        // Ignore touches from other devices
+            if let Some(compared) = &mut self.compared
+                && compared.is_source(info.as_deref())
+            {
+                compared.on_sample((x, y), timestamp, released, keepalive, self.jump_threshold);
+                return;
+            }
+
+            // After device selection, only the selected device counts. Events without a
+            // device identity can't be attributed, so they are dropped as well.
+            if let Some(selected_path) = &self.calibration.selected_device_path
+                && info
+                    .as_ref()
+                    .is_none_or(|device_info| &device_info.path != selected_path)
+            {
+                return;
+            }
+
+            if self.calibration.is_done() {
+                // Only calibration holds need the resting finger repeated, the
+                // statistics count what the panel sent
+                if keepalive {
+                    return;
+                }
+
+                // Samples below the candidate threshold are dropped, as the driver would
+                if let Some(pressure) = pressure
+                    && !released
+                {
+                    self.pressure.record(pressure);
+                    if pressure < self.pressure.threshold {
+                        self.pressure.filtered += 1;
                         return;
                     }
                 }
-            }
 
-            if self.calibration.is_done() {
+                let limits = info
+                    .as_deref()
+                    .and_then(AxisLimits::from_device)
+                    .unwrap_or_else(|| self.calibration.raw_limits());
+                self.saturation.observe(x, y, released, limits);
+
                 let (mx, my) = self.map_raw(x, y);
+                // Setting the region, the drag isn't part of the test
+                if self.roi_setup {
+                    self.drag_roi(mx, my, released);
+                    return;
+                }
+                self.wake.observe(mx, my, released, timestamp);
 
                 // Update statistics
-                self.statistics.total_samples += 1;
+                self.statistics.record_sample(timestamp, released);
 
-                // Detect jumps
+                // Detect jumps, between raw samples in raw mode
+                let position = if self.raw_mode { (x, y) } else { (mx, my) };
                 if let Some((last_x, last_y)) = self.last_position {
-                    let dx = mx as f32 - last_x as f32;
-                    let dy = my as f32 - last_y as f32;
-                    let distance = (dx * dx + dy * dy).sqrt();
+                    let dx = position.0 as f32 - last_x as f32;
+                    let dy = position.1 as f32 - last_y as f32;
 
-                    if distance > JUMP_THRESHOLD {
-                        self.statistics.total_jumps += 1;
-                        self.statistics.max_jump = self.statistics.max_jump.max(distance);
-                    }
+                    self.statistics.record_step(dx, dy, self.jump_threshold);
+                }
+                if !self.raw_mode {
+                    self.stabilize(mx, my, released);
                 }
 
                 if released {
                     self.current_touch = None;
                     self.last_position = None;
+                    self.pinch = PinchGesture::new();
+                    self.finish_stroke();
                 } else {
-                    // Update current touch position and add to trail
-                    let current_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
+                    // Update current touch position and add to the current stroke
+                    let current_time = (self.clock)();
 
                     let point = TouchPoint {
                         x: mx,
@@ -564,19 +2587,27 @@ impl TouchscreenTestScreen {
                         timestamp: current_time,
                     };
 
-                    // Add to trail on each touch event
-                    self.trail.push_back(point.clone());
-                    if self.trail.len() > MAX_TRAIL_LENGTH {
-                        self.trail.pop_front();
+                    if !self.stroke_open {
+                        let inside = self.roi.contains(mx as f32, my as f32);
+                        if !self.raw_mode && inside {
+                            self.corners.touch_down(mx, my, self.mm_scale());
+                        }
+                        if !self.raw_mode && !inside {
+                            self.masked_touches += 1;
+                        }
+                        self.hover.touch_down(mx, my, self.mm_scale());
                     }
+                    self.record_stroke_point(point);
 
                     self.current_touch = Some(point);
-                    self.last_position = Some((mx, my));
+                    self.last_position = Some(position);
                 }
 
-                // Legacy grid marking
-                self.mark(mx, my);
-                if released {
+                // Legacy grid marking, the cells are calibrated space
+                if !self.raw_mode {
+                    self.mark(mx, my);
+                }
+                if released || self.raw_mode {
                     self.touching_idx = None;
                 } else {
                     let col = (mx * COLS / CALIBRATED_MAX_X).min(COLS - 1);
@@ -590,11 +2621,16 @@ impl TouchscreenTestScreen {
                     y,
                     timestamp,
                     released,
+                    pressure,
                     info: info.clone(),
+                    keepalive,
                 });
             } else {
                 self.calibration.record_touch(&touch_event);
                 self.last_touch = Some(touch_event);
+                if self.calibration.is_done() {
+                    self.on_calibrated();
+                }
             }
         }
     }
@@ -609,71 +2645,410 @@ impl TouchscreenTestScreen {
             )])
             .centered(),
             Line::from(""),
-            Line::from("Select which touchscreen/touchpad device to use:")
+            Line::from("Select which touchscreen device to use:")
+                .centered()
+                .yellow(),
+            Line::from(""),
+        ];
+
+        // Display the list of available devices
+        if self.calibration.available_devices.is_empty() {
+            info_lines.push(
+                Line::from(vec![Span::styled(
+                    "No touch devices detected from touch events.",
+                    Style::default().red(),
+                )])
+                .centered(),
+            );
+            info_lines.push(Line::from(""));
+            info_lines.push(
+                Line::from("Try touching the screen to detect devices...")
+                    .centered()
+                    .gray(),
+            );
+        } else {
+            // Checkboxes only once the operator starts marking devices to compare
+            let marking = !self.calibration.marked_devices.is_empty();
+            for (idx, device) in self.calibration.available_devices.iter().enumerate() {
+                let is_selected = idx == self.calibration.selected_device_index;
+                let marker = if is_selected { "► " } else { "  " };
+                let checkbox = match (
+                    marking,
+                    self.calibration.marked_devices.contains(&device.path),
+                ) {
+                    (false, _) => "",
+                    (true, true) => "[x] ",
+                    (true, false) => "[ ] ",
+                };
+
+                let line = Line::from(vec![
+                    Span::styled(marker, Style::default().yellow().bold()),
+                    Span::styled(checkbox, Style::default().cyan()),
+                    Span::styled(
+                        if device.class == DeviceClass::Trackpad {
+                            format!("{}. {} (trackpad)", idx + 1, device.name)
+                        } else {
+                            format!("{}. {}", idx + 1, device.name)
+                        },
+                        if is_selected {
+                            Style::default().bold().yellow()
+                        } else {
+                            Style::default().white()
+                        },
+                    ),
+                ]);
+
+                info_lines.push(line.centered());
+
+                // Listed because it was seen, but nothing comes from it any more
+                if let Some(status) = listener_health::status(&device.path)
+                    && status.state != ListenerState::Running
+                {
+                    info_lines.push(
+                        Line::from(format!("listener {}, Enter to restart", status.describe()))
+                            .centered()
+                            .red(),
+                    );
+                }
+            }
+        }
+
+        info_lines.push(Line::from(""));
+        info_lines.push(Line::from(""));
+
+        if !self.calibration.available_devices.is_empty() {
+            info_lines.push(
+                Line::from(vec![
+                    Span::styled("↑/↓", Style::default().bold().yellow()),
+                    Span::raw(" to navigate   "),
+                    Span::styled("Enter", Style::default().bold().yellow()),
+                    Span::raw(" to select   "),
+                ])
+                .centered(),
+            );
+            info_lines.push(
+                Line::from(vec![
+                    Span::styled("1-9", Style::default().bold().yellow()),
+                    Span::raw(" for quick select"),
+                ])
+                .centered(),
+            );
+            info_lines.push(
+                Line::from(vec![
+                    Span::styled("Space", Style::default().bold().yellow()),
+                    Span::raw(" marks two devices to compare"),
+                ])
+                .centered(),
+            );
+            info_lines.push(Line::from(""));
+        }
+
+        info_lines.push(
+            Line::from(vec![
+                Span::styled("I", Style::default().bold().yellow()),
+                Span::raw(if self.include_trackpads {
+                    " trackpads: included"
+                } else {
+                    " trackpads: left out"
+                }),
+            ])
+            .centered(),
+        );
+        info_lines.push(
+            Line::from(vec![
+                Span::styled("W", Style::default().bold().yellow()),
+                Span::raw(if self.raw_mode {
+                    " raw mode: on, no calibration"
+                } else {
+                    " raw mode: off"
+                }),
+            ])
+            .centered(),
+        );
+        info_lines.push(
+            Line::from(vec![
+                Span::styled("Q/Esc", Style::default().bold().yellow()),
+                Span::raw(" to exit"),
+            ])
+            .centered(),
+        );
+
+        let info_height = info_lines.len() as u16 + 2;
+        let info_width = 60u16.min(area.width - 4);
+
+        let info_rect = Rect {
+            x: (area.width.saturating_sub(info_width)) / 2,
+            y: (area.height.saturating_sub(info_height)) / 2,
+            width: info_width,
+            height: info_height,
+        };
+
+        let info_widget = Paragraph::new(info_lines)
+            .block(Block::bordered())
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+
+        f.render_widget(info_widget, info_rect);
+    }
+
+    fn draw_calibration(&self, f: &mut Frame) {
+        let area = f.area();
+
+        // Fill entire screen with canvas (like the test screen)
+        let w = area.width;
+        let h = area.height;
+        let mut ac = AsciiCanvas::new(w, h);
+
+        // Handle device selection separately
+        use CalibrationStep::*;
+        if self.calibration.step == DeviceSelection {
+            self.draw_device_selection(f);
+            return;
+        }
+
+        // Determine which corner to highlight
+        let (target_x, target_y) = match self.calibration.step {
+            DeviceSelection => return, // Already handled above
+            TopLeft => (0i32, 0i32),
+            TopRight => ((w - 1) as i32, 0i32),
+            BottomRight => ((w - 1) as i32, (h - 1) as i32),
+            BottomLeft => (0i32, (h - 1) as i32),
+            ConfirmOrientation | Done => (w as i32 / 2, h as i32 / 2), // Center if done
+        };
+        let corner = !matches!(self.calibration.step, ConfirmOrientation | Done);
+
+        // Draw arrow from center to the target corner (only if not done)
+        if corner {
+            let cx = (w as i32) / 2;
+            let cy = (h as i32) / 2;
+            ac.arrow(cx, cy, target_x, target_y, '*');
+        }
+
+        // Draw large corner marker at the target corner (after arrow so it overlays)
+        if corner {
+            let size = 7i32;
+            for dx in -size..=size {
+                ac.put(target_x + dx, target_y, '═');
+            }
+            for dy in -size..=size {
+                ac.put(target_x, target_y + dy, '║');
+            }
+            ac.put(target_x, target_y, '╬');
+        }
+
+        // Render the full-screen canvas
+        let canvas_widget =
+            Paragraph::new(ac.to_text()).style(Style::default().bg(Color::Black).fg(Color::White));
+        f.render_widget(canvas_widget, area);
+
+        // Overlay instruction box at top center
+        let msg = match self.calibration.step {
+            DeviceSelection => "Select a device", // Should not reach here
+            ConfirmOrientation => "Check the panel orientation",
+            Done => "Calibration complete!",
+            TopLeft => "Touch the TOP-LEFT corner of your screen",
+            TopRight => "Touch the TOP-RIGHT corner of your screen",
+            BottomRight => "Touch the BOTTOM-RIGHT corner of your screen",
+            BottomLeft => "Touch the BOTTOM-LEFT corner of your screen",
+        };
+
+        let mut info_lines = vec![
+            Line::from(vec![Span::styled(
+                "Touchscreen Calibration",
+                Style::default().bold().cyan(),
+            )])
+            .centered(),
+            Line::from(""),
+            Line::from(msg).centered().bold().yellow(),
+            Line::from(""),
+        ];
+
+        // Show hold progress if touching
+        let hold_progress = self.calibration.get_hold_progress();
+        let hold_complete = self.calibration.hold_complete();
+
+        if self.calibration.captured {
+            // Auto mode already took the point
+            info_lines.push(
+                Line::from(vec![Span::styled(
+                    "Captured - lift your finger",
+                    Style::default().bold().green(),
+                )])
+                .centered(),
+            );
+            info_lines.push(Line::from(""));
+        } else if hold_complete {
+            // Timer complete - show "Release" message
+            info_lines.push(
+                Line::from(vec![Span::styled(
+                    ">>> RELEASE NOW <<<",
+                    Style::default().bold().green(),
+                )])
+                .centered(),
+            );
+            info_lines.push(Line::from(""));
+        } else if hold_progress > 0.0 {
+            // Still holding - show progress bar
+            let progress_pct = (hold_progress * 100.0) as u8;
+            let bar_width = 30;
+            let filled = ((hold_progress * bar_width as f32) as usize).min(bar_width);
+            let empty = bar_width - filled;
+
+            let progress_bar = format!(
+                "[{}{}] {}%",
+                "█".repeat(filled),
+                "░".repeat(empty),
+                progress_pct
+            );
+
+            info_lines.push(
+                Line::from(vec![
+                    Span::styled("Hold: ", Style::default().bold()),
+                    Span::styled(progress_bar, Style::default().yellow()),
+                ])
+                .centered(),
+            );
+            info_lines.push(Line::from(""));
+        } else {
+            // Not holding - keep space reserved so the box doesn't resize
+            info_lines.push(Line::from(""));
+            info_lines.push(Line::from(""));
+        }
+
+        let hold_hint = match self.calibration.capture_mode {
+            CaptureMode::OnRelease => "Touch and HOLD for 1 second ",
+            CaptureMode::Auto => "Touch and keep holding until the bar is full ",
+        };
+        info_lines.push(Line::from(vec![Span::styled(hold_hint, Style::default())]).centered());
+        info_lines.push(
+            Line::from(vec![
+                Span::styled("Touch the ", Style::default()),
+                Span::styled("EDGE OF THE SCREEN", Style::default().bold().yellow()),
+            ])
+            .centered(),
+        );
+        info_lines.push(
+            Line::from("Touch as close to the physical screen edge as possible")
                 .centered()
-                .yellow(),
-            Line::from(""),
-        ];
+                .gray(),
+        );
 
-        // Display the list of available devices
-        if self.calibration.available_devices.is_empty() {
+        if let Some(AppEvent::Touch { x, y, .. }) = &self.last_touch {
             info_lines.push(
-                Line::from(vec![Span::styled(
-                    "No touch devices detected from touch events.",
-                    Style::default().red(),
-                )])
+                Line::from(vec![
+                    Span::raw("Touch: "),
+                    Span::styled(format!("({}, {})", x, y), Style::default().yellow()),
+                    Span::raw(format!(
+                        "  {} samples held",
+                        self.calibration.touch_samples.len()
+                    ))
+                    .gray(),
+                ])
                 .centered(),
             );
-            info_lines.push(Line::from(""));
+        }
+
+        // Corners taken so far: trimmed mean, sample count and spread of the kept samples
+        for (name, capture) in ["TL", "TR", "BR", "BL"]
+            .iter()
+            .zip(&self.calibration.captures)
+        {
+            if let Some(capture) = capture {
+                info_lines.push(
+                    Line::from(vec![
+                        Span::styled(format!("{name} "), Style::default().bold()),
+                        Span::styled(
+                            format!("({}, {})", capture.x, capture.y),
+                            Style::default().cyan(),
+                        ),
+                        Span::raw(format!(
+                            "  {} samples  spread {}x{}",
+                            capture.samples, capture.spread.0, capture.spread.1
+                        ))
+                        .gray(),
+                    ])
+                    .centered(),
+                );
+            }
+        }
+
+        if let (ConfirmOrientation, Some(orientation)) =
+            (self.calibration.step, &self.calibration.orientation)
+        {
+            info_lines.push(Line::from(orientation.clone()).centered().bold().yellow());
             info_lines.push(
-                Line::from("Try touching the screen to detect devices...")
-                    .centered()
-                    .gray(),
+                Line::from(
+                    "Y or tap Accept if that is how it is mounted, N to take the corners again",
+                )
+                .centered(),
             );
-        } else {
-            for (idx, device) in self.calibration.available_devices.iter().enumerate() {
-                let is_selected = idx == self.calibration.selected_device_index;
-                let marker = if is_selected { "► " } else { "  " };
+        } else if self.calibration.swap_axes {
+            let note = if self.calibration.count < 2 {
+                "Axes taken as swapped for this model, the first two corners check it"
+            } else {
+                "Axis swap detected and compensated"
+            };
+            info_lines.push(Line::from(note).centered().cyan());
+        }
 
-                let line = Line::from(vec![
-                    Span::styled(marker, Style::default().yellow().bold()),
+        // The compared device takes the same corners from the same touches
+        if let Some(compared) = &self.compared {
+            let state = if compared.calibration.is_done() {
+                match &compared.calibration.orientation {
+                    Some(orientation) => format!("calibrated, {}", orientation.to_lowercase()),
+                    None => "calibrated".to_string(),
+                }
+            } else {
+                format!("{}/4 corners", compared.calibration.count)
+            };
+            info_lines.push(
+                Line::from(vec![
+                    Span::styled("Compared: ", Style::default().bold()),
                     Span::styled(
-                        format!("{}. {}", idx + 1, device.name),
-                        if is_selected {
-                            Style::default().bold().yellow()
-                        } else {
-                            Style::default().white()
-                        },
+                        compared
+                            .info()
+                            .map_or("", |info| info.name.as_str())
+                            .to_string(),
+                        Style::default().cyan(),
                     ),
-                ]);
-
-                info_lines.push(line.centered());
+                    Span::raw(format!("  {state}")).gray(),
+                ])
+                .centered(),
+            );
+            if let Some(err) = &compared.calibration.error {
+                info_lines.push(Line::from(format!("Compared: {err}")).centered().red());
             }
         }
 
-        info_lines.push(Line::from(""));
-        info_lines.push(Line::from(""));
+        if let Some(warning) = &self.calibration.capture_warning {
+            info_lines.push(Line::from(warning.clone()).centered().yellow().bold());
+        }
 
-        if !self.calibration.available_devices.is_empty() {
+        if let Some(limits) = self.usable_learned_range() {
             info_lines.push(
                 Line::from(vec![
-                    Span::styled("↑/↓", Style::default().bold().yellow()),
-                    Span::raw(" to navigate   "),
-                    Span::styled("Enter", Style::default().bold().yellow()),
-                    Span::raw(" to select   "),
+                    Span::styled("U", Style::default().bold().yellow()),
+                    Span::raw(format!(
+                        " to use the learned range X {}-{}, Y {}-{}",
+                        limits.x.0, limits.x.1, limits.y.0, limits.y.1
+                    )),
                 ])
                 .centered(),
             );
+        }
+
+        // Show error if present
+        if let Some(err) = &self.calibration.error {
+            info_lines.push(Line::from(""));
             info_lines.push(
                 Line::from(vec![
-                    Span::styled("1-9", Style::default().bold().yellow()),
-                    Span::raw(" for quick select"),
+                    Span::styled("Error: ", Style::default().bold().red()),
+                    Span::styled(err.clone(), Style::default().red()),
                 ])
                 .centered(),
             );
-            info_lines.push(Line::from(""));
         }
 
+        info_lines.push(Line::from(""));
         info_lines.push(
             Line::from(vec![
                 Span::styled("Q/Esc", Style::default().bold().yellow()),
@@ -687,7 +3062,7 @@ impl TouchscreenTestScreen {
 
         let info_rect = Rect {
             x: (area.width.saturating_sub(info_width)) / 2,
-            y: (area.height.saturating_sub(info_height)) / 2,
+            y: 1,
             width: info_width,
             height: info_height,
         };
@@ -699,171 +3074,449 @@ impl TouchscreenTestScreen {
         f.render_widget(info_widget, info_rect);
     }
 
-    fn draw_calibration(&self, f: &mut Frame) {
-        let area = f.area();
+    fn draw_test(&self, f: &mut Frame) {
+        if self.view == TestView::Pinch {
+            self.draw_pinch_canvas(f, f.area());
+            self.draw_pinch_overlay(f);
+            return;
+        }
 
-        // Fill entire screen with canvas (like the test screen)
+        if self.view == TestView::Pressure {
+            self.draw_pressure_graph(f, f.area());
+            self.draw_pressure_overlay(f);
+            return;
+        }
+
+        if self.view == TestView::Jumps {
+            self.draw_jump_histogram(f, f.area());
+            self.draw_jump_overlay(f);
+            return;
+        }
+
+        // Draw canvas filling the ENTIRE screen first, or letterboxed to the
+        // panel's physical aspect with the unused margins dimmed
+        let layout = self.test_layout(f.area());
+        if layout.canvas != f.area() {
+            f.render_widget(
+                Block::new().style(Style::default().bg(Color::DarkGray)),
+                f.area(),
+            );
+        }
+        self.draw_high_precision_canvas(f, layout.canvas);
+        self.draw_roi_mask(f, layout.canvas);
+        self.draw_corner_indicators(f, &layout.corners);
+
+        // Overlay UI elements on top of the canvas
+        self.draw_overlay_ui(f);
+        if self.show_speed {
+            self.draw_speed_panel(f);
+        }
+    }
+
+    /// Dim the canvas outside the region of interest, or outside the region
+    /// being dragged out
+    fn draw_roi_mask(&self, f: &mut Frame, canvas: Rect) {
+        let roi = match self.roi_drag {
+            Some((start, end)) if self.roi_setup => {
+                Roi::from_corners(start, end).unwrap_or(self.roi)
+            }
+            _ => self.roi,
+        };
+        if self.raw_mode || roi.is_full() || canvas.width < 2 || canvas.height < 2 {
+            return;
+        }
+        let buffer = f.buffer_mut();
+        for row in 0..canvas.height {
+            let y = row as f32 / (canvas.height - 1) as f32 * CALIBRATED_MAX_Y as f32;
+            for col in 0..canvas.width {
+                let x = col as f32 / (canvas.width - 1) as f32 * CALIBRATED_MAX_X as f32;
+                if roi.contains(x, y) {
+                    continue;
+                }
+                if let Some(cell) = buffer.cell_mut((canvas.x + col, canvas.y + row)) {
+                    cell.set_bg(Color::DarkGray);
+                }
+            }
+        }
+    }
+
+    /// A marker on each corner target of the canvas, green once it got a touch-down
+    fn draw_corner_indicators(&self, f: &mut Frame, corners: &[(Target, bool, Position)]) {
+        for (target, touched, position) in corners {
+            let symbol = match target {
+                Target::TopLeft => "◤",
+                Target::TopRight => "◥",
+                Target::BottomLeft => "◣",
+                Target::BottomRight => "◢",
+                Target::Top => "▲",
+                Target::Bottom => "▼",
+                Target::Left => "◀",
+                Target::Right => "▶",
+            };
+            let color = if *touched { Color::Green } else { Color::Red };
+            f.buffer_mut().set_string(
+                position.x,
+                position.y,
+                symbol,
+                Style::default().fg(color).bold(),
+            );
+        }
+    }
+
+    fn draw_pinch_canvas(&self, f: &mut Frame, area: Rect) {
         let w = area.width;
         let h = area.height;
         let mut ac = AsciiCanvas::new(w, h);
 
-        // Handle device selection separately
-        use CalibrationStep::*;
-        if self.calibration.step == DeviceSelection {
-            self.draw_device_selection(f);
-            return;
+        if let Some([a, b]) = self.pinch.contacts {
+            let to_cell = |p: (u16, u16)| -> (i32, i32) {
+                (
+                    (p.0 as f32 / CALIBRATED_MAX_X as f32 * (w.max(1) - 1) as f32) as i32,
+                    (p.1 as f32 / CALIBRATED_MAX_Y as f32 * (h.max(1) - 1) as f32) as i32,
+                )
+            };
+            let (ax, ay) = to_cell(a);
+            let (bx, by) = to_cell(b);
+
+            // Rectangle following the pinch scale and rotation, centered between the fingers.
+            // Terminal cells are about twice as tall as wide, so rotate in a square space.
+            if let Some((scale, rotation)) = self.pinch.transform() {
+                let cx = (ax + bx) as f32 / 2.0;
+                let cy = (ay + by) as f32 / 2.0;
+                let half_w = (w as f32 / 8.0) * scale;
+                let half_h = (w as f32 / 16.0) * scale;
+                let (sin, cos) = rotation.to_radians().sin_cos();
+
+                let corners: Vec<(i32, i32)> = [
+                    (-half_w, -half_h),
+                    (half_w, -half_h),
+                    (half_w, half_h),
+                    (-half_w, half_h),
+                ]
+                .iter()
+                .map(|&(dx, dy)| {
+                    let rx = dx * cos - dy * sin;
+                    let ry = dx * sin + dy * cos;
+                    ((cx + rx).round() as i32, (cy + ry / 2.0).round() as i32)
+                })
+                .collect();
+
+                for i in 0..corners.len() {
+                    let (x0, y0) = corners[i];
+                    let (x1, y1) = corners[(i + 1) % corners.len()];
+                    ac.line(x0, y0, x1, y1, '#');
+                }
+            }
+
+            // Connecting line between the two contacts, drawn on top of the rectangle
+            ac.line(ax, ay, bx, by, '.');
+            ac.put(ax, ay, '1');
+            ac.put(bx, by, '2');
         }
 
-        // Determine which corner to highlight
-        let (target_x, target_y) = match self.calibration.step {
-            DeviceSelection => return, // Already handled above
-            TopLeft => (0i32, 0i32),
-            TopRight => ((w - 1) as i32, 0i32),
-            BottomRight => ((w - 1) as i32, (h - 1) as i32),
-            BottomLeft => (0i32, (h - 1) as i32),
-            Done => (w as i32 / 2, h as i32 / 2), // Center if done
+        let canvas_widget =
+            Paragraph::new(ac.to_text()).style(Style::default().bg(Color::Black).fg(Color::White));
+        f.render_widget(canvas_widget, area);
+    }
+
+    fn draw_pinch_overlay(&self, f: &mut Frame) {
+        let area = f.area();
+
+        let info_width = 50u16.min(area.width.saturating_sub(4));
+        let info_height = 8u16.min(area.height / 3);
+
+        let info_rect = Rect {
+            x: (area.width.saturating_sub(info_width)) / 2,
+            y: 1,
+            width: info_width,
+            height: info_height,
         };
 
-        // Draw arrow from center to the target corner (only if not done)
-        if self.calibration.step != Done {
-            let cx = (w as i32) / 2;
-            let cy = (h as i32) / 2;
-            ac.arrow(cx, cy, target_x, target_y, '*');
+        let mut lines = vec![];
+
+        if !self.supports_multitouch() {
+            lines.push(Line::from(
+                "Multi-touch not supported by this device".red().bold(),
+            ));
+        } else if let (Some(distance), Some(angle)) = (self.pinch.distance(), self.pinch.angle()) {
+            lines.push(Line::from(vec![
+                "Distance: ".bold(),
+                format!("{:.0}  ", distance).green(),
+                "Angle: ".bold(),
+                format!("{:.1}°", angle).green(),
+            ]));
+        } else {
+            lines.push(Line::from("Touch the screen with two fingers...".gray()));
         }
 
-        // Draw large corner marker at the target corner (after arrow so it overlays)
-        if self.calibration.step != Done {
-            let size = 7i32;
-            for dx in -size..=size {
-                ac.put(target_x + dx, target_y, '═');
+        lines.push(Line::from(""));
+
+        let fmt_pinch = |v: Option<f32>| v.map_or("-".to_string(), |v| format!("{:.0}", v));
+        lines.push(Line::from(vec![
+            "Min pinch: ".into(),
+            fmt_pinch(self.statistics.min_pinch).yellow(),
+            "  Max pinch: ".into(),
+            fmt_pinch(self.statistics.max_pinch).yellow(),
+        ]));
+
+        lines.push(Line::from(vec![
+            "P".bold().yellow(),
+            ":Trail mode ".into(),
+            "R".bold().yellow(),
+            ":Reset ".into(),
+            "Q".bold().yellow(),
+            ":Quit".into(),
+        ]));
+
+        let info_widget = Paragraph::new(lines)
+            .block(Block::bordered().title("Pinch / Rotate"))
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+
+        f.render_widget(info_widget, info_rect);
+    }
+
+    fn draw_pressure_graph(&self, f: &mut Frame, area: Rect) {
+        let w = area.width;
+        let h = area.height;
+        let mut ac = AsciiCanvas::new(w, h);
+
+        // Graph below the overlay box, newest sample in the rightmost column
+        let top = 2 + 8u16.min(h / 3);
+        let graph_h = h.saturating_sub(top + 1);
+
+        if let Some(max) = self.pressure_max()
+            && graph_h > 0
+        {
+            let bottom = (top + graph_h) as i32;
+            let bar_height = |p: i32| (p.clamp(0, max) as i64 * graph_h as i64 / max as i64) as i32;
+
+            for (col, &p) in self
+                .pressure
+                .history
+                .iter()
+                .rev()
+                .take(w as usize)
+                .enumerate()
+            {
+                let x = w as i32 - 1 - col as i32;
+                // Filled bars pass the threshold, shaded bars would be filtered out
+                let ch = if p >= self.pressure.threshold {
+                    '█'
+                } else {
+                    '░'
+                };
+                for dy in 0..bar_height(p) {
+                    ac.put(x, bottom - dy, ch);
+                }
             }
-            for dy in -size..=size {
-                ac.put(target_x, target_y + dy, '║');
+
+            let threshold_y = bottom - bar_height(self.pressure.threshold);
+            for x in 0..w as i32 {
+                if ac.idx(x, threshold_y).is_some_and(|i| ac.buf[i] == ' ') {
+                    ac.put(x, threshold_y, '─');
+                }
             }
-            ac.put(target_x, target_y, '╬');
         }
 
-        // Render the full-screen canvas
         let canvas_widget =
             Paragraph::new(ac.to_text()).style(Style::default().bg(Color::Black).fg(Color::White));
         f.render_widget(canvas_widget, area);
+    }
 
-        // Overlay instruction box at top center
-        let msg = match self.calibration.step {
-            DeviceSelection => "Select a device", // Should not reach here
-            Done => "Calibration complete!",
-            TopLeft => "Touch the TOP-LEFT corner of your screen",
-            TopRight => "Touch the TOP-RIGHT corner of your screen",
-            BottomRight => "Touch the BOTTOM-RIGHT corner of your screen",
-            BottomLeft => "Touch the BOTTOM-LEFT corner of your screen",
+    fn draw_pressure_overlay(&self, f: &mut Frame) {
+        let area = f.area();
+
+        let info_width = 50u16.min(area.width.saturating_sub(4));
+        let info_height = 8u16.min(area.height / 3);
+
+        let info_rect = Rect {
+            x: (area.width.saturating_sub(info_width)) / 2,
+            y: 1,
+            width: info_width,
+            height: info_height,
         };
 
-        let mut info_lines = vec![
-            Line::from(vec![Span::styled(
-                "Touchscreen Calibration",
-                Style::default().bold().cyan(),
-            )])
-            .centered(),
-            Line::from(""),
-            Line::from(msg).centered().bold().yellow(),
-            Line::from(""),
-        ];
+        let mut lines = vec![];
 
-        // Show hold progress if touching
-        let hold_progress = self.calibration.get_hold_progress();
-        let hold_complete = self.calibration.is_hold_complete();
+        if let Some(max) = self.pressure_max() {
+            let last = self
+                .pressure
+                .history
+                .back()
+                .map_or("-".to_string(), |p| p.to_string());
+            lines.push(Line::from(vec![
+                "Pressure: ".bold(),
+                format!("{} / {}  ", last, max).green(),
+                "Threshold: ".bold(),
+                format!("{}", self.pressure.threshold).yellow(),
+            ]));
+            lines.push(Line::from(""));
+            lines.push(Line::from(vec![
+                "Filtered: ".into(),
+                format!("{}  ", self.pressure.filtered).red(),
+                "Kept: ".into(),
+                format!("{}", self.statistics.total_samples).yellow(),
+            ]));
+        } else {
+            lines.push(Line::from(
+                "Pressure not supported by this device".red().bold(),
+            ));
+            lines.push(Line::from(""));
+        }
 
-        if hold_complete {
-            // Timer complete - show "Release" message
-            info_lines.push(
-                Line::from(vec![Span::styled(
-                    ">>> RELEASE NOW <<<",
-                    Style::default().bold().green(),
-                )])
-                .centered(),
-            );
-            info_lines.push(Line::from(""));
-        } else if hold_progress > 0.0 {
-            // Still holding - show progress bar
-            let progress_pct = (hold_progress * 100.0) as u8;
-            let bar_width = 30;
-            let filled = ((hold_progress * bar_width as f32) as usize).min(bar_width);
-            let empty = bar_width - filled;
+        lines.push(Line::from(vec![
+            "↑/↓".bold().yellow(),
+            ":Threshold ".into(),
+            "F".bold().yellow(),
+            ":Trail mode ".into(),
+            "R".bold().yellow(),
+            ":Reset ".into(),
+            "Q".bold().yellow(),
+            ":Quit".into(),
+        ]));
+
+        let info_widget = Paragraph::new(lines)
+            .block(Block::bordered().title("Pressure Threshold"))
+            .style(Style::default().bg(Color::Black).fg(Color::White));
+
+        f.render_widget(info_widget, info_rect);
+    }
+
+    fn draw_jump_histogram(&self, f: &mut Frame, area: Rect) {
+        let w = area.width;
+        let h = area.height;
+        let mut ac = AsciiCanvas::new(w, h);
+
+        // Histogram below the overlay box, distance 0 in the leftmost column
+        let top = 2 + 8u16.min(h / 3);
+        let graph_h = h.saturating_sub(top + 1);
 
-            let progress_bar = format!(
-                "[{}{}] {}%",
-                "█".repeat(filled),
-                "░".repeat(empty),
-                progress_pct
-            );
+        if graph_h > 0 && w > 0 {
+            let bottom = (top + graph_h) as i32;
 
-            info_lines.push(
-                Line::from(vec![
-                    Span::styled("Hold: ", Style::default().bold()),
-                    Span::styled(progress_bar, Style::default().yellow()),
-                ])
-                .centered(),
-            );
-            info_lines.push(Line::from(""));
-        } else {
-            // Not holding - keep space reserved so the box doesn't resize
-            info_lines.push(Line::from(""));
-            info_lines.push(Line::from(""));
+            // Fit the range to the data, but always keep the threshold in view
+            let range = (self.statistics.max_distance.max(self.jump_threshold) * 1.2)
+                .clamp(100.0, self.statistics.distances.len() as f32);
+            let units_per_col = range / w as f32;
+            let column_of = |distance: f32| (distance / units_per_col) as i32;
+
+            let mut columns = vec![0u32; w as usize];
+            for (bucket, &count) in self.statistics.distances.iter().enumerate() {
+                if let Some(column) = columns.get_mut(column_of(bucket as f32) as usize) {
+                    *column += count;
+                }
+            }
+
+            // Log scale, a handful of jumps would be invisible next to thousands of
+            // ordinary samples otherwise
+            let peak = columns.iter().copied().max().unwrap_or(0);
+            let scale = (peak as f32).ln_1p().max(f32::EPSILON);
+            let bar_height = |count: u32| {
+                if count == 0 {
+                    0
+                } else {
+                    ((count as f32).ln_1p() / scale * graph_h as f32).ceil() as i32
+                }
+            };
+
+            let threshold_x = column_of(self.jump_threshold);
+            for (x, &count) in columns.iter().enumerate() {
+                let x = x as i32;
+                // Filled bars count as jumps, shaded bars are ordinary movement
+                let ch = if x > threshold_x { '█' } else { '░' };
+                for dy in 0..bar_height(count) {
+                    ac.put(x, bottom - dy, ch);
+                }
+            }
+
+            for y in top as i32 + 1..=bottom {
+                ac.put(threshold_x, y, '│');
+            }
         }
 
-        info_lines.push(
-            Line::from(vec![Span::styled(
-                "Touch and HOLD for 1 second ",
-                Style::default(),
-            )])
-            .centered(),
-        );
-        info_lines.push(
-            Line::from(vec![
-                Span::styled("Touch the ", Style::default()),
-                Span::styled("EDGE OF THE SCREEN", Style::default().bold().yellow()),
-            ])
-            .centered(),
-        );
-        info_lines.push(
-            Line::from("Touch as close to the physical screen edge as possible")
-                .centered()
-                .gray(),
+        let canvas_widget =
+            Paragraph::new(ac.to_text()).style(Style::default().bg(Color::Black).fg(Color::White));
+        f.render_widget(canvas_widget, area);
+    }
+
+    /// Speed over the latest stroke as a sparkline, with its maxima and the
+    /// session's, at the bottom of the trail view
+    fn draw_speed_panel(&self, f: &mut Frame) {
+        let area = f.area();
+        let width = 60u16.min(area.width.saturating_sub(4));
+        let height = 9u16.min(area.height / 2);
+        let rect = Rect {
+            x: (area.width.saturating_sub(width)) / 2,
+            y: area.height.saturating_sub(height + 1),
+            width,
+            height,
+        };
+
+        let stroke = self
+            .strokes
+            .iter()
+            .rev()
+            .find(|stroke| !stroke.speed_profile().is_empty());
+        let scale = self.mm_scale();
+
+        let [graph, text] = Layout::vertical([Constraint::Min(1), Constraint::Length(3)]).areas(
+            rect.inner(Margin {
+                horizontal: 1,
+                vertical: 1,
+            }),
         );
 
-        if let Some(AppEvent::Touch { x, y, .. }) = &self.last_touch {
-            info_lines.push(
-                Line::from(vec![
-                    Span::raw("Touch: "),
-                    Span::styled(format!("({}, {})", x, y), Style::default().yellow()),
-                ])
-                .centered(),
-            );
+        let mut lines = Vec::new();
+        match stroke {
+            Some(stroke) => lines.push(Line::from(vec![
+                "Stroke: ".bold(),
+                format!(
+                    "max {}  ",
+                    format_speed(
+                        stroke.max_speed(),
+                        scale.map(|scale| stroke.max_speed_mm(scale))
+                    )
+                )
+                .green(),
+                format!("accel {:.0}/s²", stroke.max_acceleration()).yellow(),
+            ])),
+            None => lines.push(Line::from("Draw a stroke...".gray())),
         }
+        lines.push(Line::from(vec![
+            "Session: ".bold(),
+            format!(
+                "max {}  ",
+                format_speed(self.statistics.max_speed, self.statistics.max_speed_mm)
+            )
+            .green(),
+            format!("accel {:.0}/s²", self.statistics.max_acceleration).yellow(),
+        ]));
+        lines.push(Line::from(vec![
+            "V".bold().yellow(),
+            ":Hide ".into(),
+            "R".bold().yellow(),
+            ":Reset".into(),
+        ]));
 
-        // Show error if present
-        if let Some(err) = &self.calibration.error {
-            info_lines.push(Line::from(""));
-            info_lines.push(
-                Line::from(vec![
-                    Span::styled("Error: ", Style::default().bold().red()),
-                    Span::styled(err.clone(), Style::default().red()),
-                ])
-                .centered(),
-            );
+        f.render_widget(Clear, rect);
+        f.render_widget(
+            Block::bordered()
+                .title("Speed, latest stroke")
+                .style(Style::default().bg(Color::Black).fg(Color::White)),
+            rect,
+        );
+        if let Some(stroke) = stroke {
+            let data = speed_columns(stroke.speed_profile(), graph.width as usize);
+            f.render_widget(Sparkline::default().data(&data).cyan(), graph);
         }
+        f.render_widget(Paragraph::new(lines), text);
+    }
 
-        info_lines.push(Line::from(""));
-        info_lines.push(
-            Line::from(vec![
-                Span::styled("Q/Esc", Style::default().bold().yellow()),
-                Span::raw(" to exit"),
-            ])
-            .centered(),
-        );
+    fn draw_jump_overlay(&self, f: &mut Frame) {
+        let area = f.area();
 
-        let info_height = info_lines.len() as u16 + 2;
-        let info_width = 60u16.min(area.width - 4);
+        let info_width = 50u16.min(area.width.saturating_sub(4));
+        let info_height = 8u16.min(area.height / 3);
 
         let info_rect = Rect {
             x: (area.width.saturating_sub(info_width)) / 2,
@@ -872,19 +3525,150 @@ impl TouchscreenTestScreen {
             height: info_height,
         };
 
-        let info_widget = Paragraph::new(info_lines)
-            .block(Block::bordered())
+        let lines = vec![
+            Line::from(vec![
+                "Threshold: ".bold(),
+                format!(
+                    "{}  ",
+                    units::format_distance(self.jump_threshold, self.jump_threshold_mm())
+                )
+                .yellow(),
+                "Jumps: ".bold(),
+                format!("{}", self.statistics.total_jumps).red(),
+            ]),
+            Line::from(""),
+            Line::from(vec![
+                "Samples: ".into(),
+                format!("{}  ", self.statistics.total_samples).yellow(),
+                "Longest step: ".into(),
+                units::format_distance(self.statistics.max_distance, self.max_step_mm()).green(),
+            ]),
+            Line::from(vec![
+                "←/→".bold().yellow(),
+                ":Threshold ".into(),
+                "J".bold().yellow(),
+                ":Trail mode ".into(),
+                "R".bold().yellow(),
+                ":Reset ".into(),
+                "Q".bold().yellow(),
+                ":Quit".into(),
+            ]),
+        ];
+
+        let info_widget = Paragraph::new(lines)
+            .block(Block::bordered().title("Jump Threshold"))
             .style(Style::default().bg(Color::Black).fg(Color::White));
 
         f.render_widget(info_widget, info_rect);
     }
 
-    fn draw_test(&self, f: &mut Frame) {
-        // Draw canvas filling the ENTIRE screen first
-        self.draw_high_precision_canvas(f, f.area());
+    fn draw_duplicate_warning(&self, f: &mut Frame) {
+        let Some((first, second)) = self.duplicates.detected() else {
+            return;
+        };
+        // Comparing the two paths of one panel is what the operator asked for
+        if self.compared.is_some() {
+            return;
+        }
+        let area = f.area();
 
-        // Overlay UI elements on top of the canvas
-        self.draw_overlay_ui(f);
+        let lines = vec![
+            Line::from("The same panel is reported by two devices:".bold()).centered(),
+            Line::from(format!("{first}  and  {second}").bold()).centered(),
+            Line::from("Every touch is counted twice. Select only one of them").centered(),
+            Line::from("in the device selection (T restarts it).").centered(),
+        ];
+
+        let width = 64u16.min(area.width.saturating_sub(2));
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let rect = Rect {
+            x: (area.width.saturating_sub(width)) / 2,
+            y: area.height.saturating_sub(height + 1),
+            width,
+            height,
+        };
+
+        let warning = Paragraph::new(lines)
+            .block(Block::bordered().title(" WARNING: duplicate touch devices ".bold()))
+            .style(Style::default().bg(Color::Red).fg(Color::White));
+
+        f.render_widget(Clear, rect);
+        f.render_widget(warning, rect);
+    }
+
+    fn draw_disconnected(&self, f: &mut Frame) {
+        let Some(device) = &self.disconnected else {
+            return;
+        };
+        let area = f.area();
+
+        let lines = vec![
+            Line::from(device.name.clone().bold()).centered(),
+            Line::from(format!(
+                "{} of 4 corners taken, waiting for reconnection.",
+                self.calibration.count
+            ))
+            .centered(),
+            Line::from("Plug it back in to continue where you left off,").centered(),
+            Line::from("or press any key to select another device.").centered(),
+        ];
+
+        let width = 64u16.min(area.width.saturating_sub(2));
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let rect = Rect {
+            x: (area.width.saturating_sub(width)) / 2,
+            y: (area.height.saturating_sub(height)) / 2,
+            width,
+            height,
+        };
+
+        let message = Paragraph::new(lines)
+            .block(Block::bordered().title(" Selected device disconnected ".bold()))
+            .style(Style::default().bg(Color::Yellow).fg(Color::Black));
+
+        f.render_widget(Clear, rect);
+        f.render_widget(message, rect);
+    }
+
+    /// Two columns of rate, jumps and jitter: the selected device (A, white
+    /// trail) and the compared one (B, cyan trail)
+    fn comparison_lines(&self, compared: &ComparedDevice) -> Vec<Line<'static>> {
+        let a = device_stats(
+            self.calibration.selected_device_info.as_ref(),
+            true,
+            &self.statistics,
+            self.jump_threshold,
+            self.mm_scale(),
+        );
+        let b = compared.stats(self.jump_threshold, self.panel_mm);
+        let optional = |value: Option<f32>, unit: &str| {
+            value.map_or("-".to_string(), |value| format!("{value:.1}{unit}"))
+        };
+        let row = |label: &str, a: String, b: String| {
+            Line::from(vec![
+                format!("{label:<8}").into(),
+                format!("{a:<16}").white(),
+                b.cyan(),
+            ])
+        };
+
+        let mut lines = vec![row("", "A".to_string(), format!("B {}", b.device))];
+        if !b.calibrated {
+            lines.push(row(
+                "",
+                String::new(),
+                format!("calibrating, {}/4", compared.calibration.count),
+            ));
+            return lines;
+        }
+        lines.push(row(
+            "Rate",
+            optional(a.rate_hz, "/s"),
+            optional(b.rate_hz, "/s"),
+        ));
+        lines.push(row("Jumps", a.jumps.to_string(), b.jumps.to_string()));
+        lines.push(row("Jitter", a.jitter_text(), b.jitter_text()));
+        lines
     }
 
     fn draw_overlay_ui(&self, f: &mut Frame) {
@@ -892,22 +3676,26 @@ impl TouchscreenTestScreen {
 
         // Create a small info box in the top-center
         let info_width = 50u16.min(area.width - 4);
-        let info_height = 8u16.min(area.height / 3);
-
-        let info_rect = Rect {
-            x: (area.width.saturating_sub(info_width)) / 2,
-            y: 1,
-            width: info_width,
-            height: info_height,
-        };
 
         let mut lines = vec![];
 
-        // Current touch info
+        if self.raw_mode {
+            lines.push(Line::from(
+                "RAW MODE: uncalibrated, raw units, no coverage or corners"
+                    .red()
+                    .bold(),
+            ));
+        }
+
+        // Current touch info, as the device reports it in raw mode
         if let Some(ref touch) = self.current_touch {
+            let (x, y) = match self.last_position {
+                Some(raw) if self.raw_mode => raw,
+                _ => (touch.x, touch.y),
+            };
             lines.push(Line::from(vec![
                 "Touch: ".bold(),
-                format!("({},{}) ", touch.x, touch.y).green(),
+                format!("({x},{y}) ").green(),
             ]));
         } else {
             lines.push(Line::from("Touch the screen...".gray()));
@@ -915,28 +3703,264 @@ impl TouchscreenTestScreen {
 
         lines.push(Line::from(""));
 
+        if self.session > 1 || self.removed_panel.is_some() {
+            let mut session = vec!["Session: ".into(), format!("{}", self.session).cyan()];
+            if self.removed_panel.is_some() {
+                session.push("  panel removed, waiting for the next unit".yellow());
+            }
+            lines.push(Line::from(session));
+        }
+
+        lines.push(Line::from(vec!["Time: ".into(), self.timer.label()]));
+
+        if let Some(state) = self.power.current() {
+            let mut power = vec!["Power: ".into(), state.label().cyan()];
+            if self.power.changed() {
+                power.push("  SOURCE CHANGED during the test".red().bold());
+            }
+            lines.push(Line::from(power));
+        }
+
+        if let Some(soak) = &self.soak {
+            let mut status = vec![
+                "Soak: ".into(),
+                soak::format_elapsed(soak.elapsed()).cyan(),
+                format!("  {} snapshots", soak.snapshot_count()).into(),
+            ];
+            if soak.is_running() {
+                status.push(
+                    format!(", next in {}", soak::format_elapsed(soak.next_snapshot())).gray(),
+                );
+            } else {
+                status.push("  stopped".yellow());
+            }
+            lines.push(Line::from(status));
+
+            let rates = soak.jump_rates();
+            if let Some(last) = rates.last() {
+                lines.push(Line::from(vec![
+                    "Jumps/min: ".into(),
+                    soak::trend(&rates, 30).red(),
+                    format!(" {last:.1}").into(),
+                ]));
+            }
+        }
+
         // Statistics
         lines.push(Line::from(vec![
             "Samples: ".into(),
             format!("{}  ", self.statistics.total_samples).yellow(),
             "Jumps: ".into(),
-            format!("{} ", self.statistics.total_jumps).red(),
+            format!("{}  ", self.statistics.total_jumps).red(),
+            "Strokes: ".into(),
+            format!(
+                "{}",
+                self.strokes.len() as u32 + self.evicted_strokes.strokes
+            )
+            .cyan(),
+        ]));
+
+        if let Some(compared) = &self.compared {
+            lines.extend(self.comparison_lines(compared));
+        }
+
+        if self.statistics.total_jumps > 0 {
+            lines.push(Line::from(vec![
+                "Max jump: ".into(),
+                units::format_distance(self.statistics.max_jump, self.max_jump_mm()).red(),
+            ]));
+        }
+
+        // Jumps of the filtered crosshair next to those of the raw samples
+        if let Some(stabilizer) = &self.stabilizer {
+            lines.push(Line::from(vec![
+                "Stabilizer: ".into(),
+                format!("{}  ", stabilizer.filter().label()).cyan(),
+                "Jumps: ".into(),
+                format!("{}", self.stabilized_statistics.total_jumps).red(),
+                format!(" (raw {})", self.statistics.total_jumps).gray(),
+            ]));
+        }
+
+        let missed: Vec<&str> = self
+            .corners
+            .targets()
+            .iter()
+            .filter(|(_, touched)| !touched)
+            .map(|(target, _)| target.name())
+            .collect();
+        let mut corners = vec![
+            "Corners: ".into(),
+            format!(
+                "{}/{}",
+                self.corners.satisfied(),
+                self.corners.targets().len()
+            )
+            .cyan(),
+        ];
+        if missed.is_empty() {
+            corners.push("  all touched".green());
+        } else {
+            corners.push(format!("  missing {}", missed.join(", ")).gray());
+        }
+        if !self.raw_mode {
+            lines.push(Line::from(corners));
+        }
+        if self.roi_setup {
+            lines.push(Line::from(
+                "Drag across the uncovered area, O: whole panel".yellow(),
+            ));
+        } else if !self.raw_mode && !self.roi.is_full() {
+            lines.push(Line::from(vec![
+                "Region: ".into(),
+                self.roi.to_string().cyan(),
+                format!("  {} masked-area touches", self.masked_touches).gray(),
+            ]));
+        }
+
+        if let Some(stroke) = self.strokes.back() {
+            lines.push(Line::from(vec![
+                "Last: ".into(),
+                format!(
+                    "{} pts  {} ms  len {:.0}  {:.0}/s",
+                    stroke.sample_count(),
+                    stroke.duration_ms(),
+                    stroke.length(),
+                    stroke.average_speed()
+                )
+                .cyan(),
+            ]));
+        }
+
+        if let Some(reason) = &self.device_fault {
+            lines.push(Line::from(vec![
+                "Device: ".into(),
+                reason.clone().red().bold(),
+            ]));
+        }
+        if let Some(matrix) = &self.matrix {
+            lines.push(Line::from(vec![
+                "Matrix: ".into(),
+                matrix.to_string().cyan(),
+            ]));
+        }
+        if let Some(note) = &self.matrix_note {
+            lines.push(Line::from(note.clone().gray()));
+        }
+        lines.push(Line::from(vec![
+            "Hover: ".into(),
+            if self.hover.flagged() {
+                format!("{}, OVER THE THRESHOLD", self.hover.label())
+                    .red()
+                    .bold()
+            } else {
+                self.hover.label().into()
+            },
         ]));
+        if let Some(worst) = self.wake.worst_wake_interval() {
+            let text = format!(
+                "worst {worst} ms to the second sample over {} touches after idle",
+                self.wake.wakes()
+            );
+            lines.push(Line::from(vec![
+                "Wake: ".into(),
+                if self.wake.flagged() {
+                    format!("{text}, FIRST SAMPLES LOST").red().bold()
+                } else {
+                    text.into()
+                },
+            ]));
+        }
+        if self.syn_drops > 0 {
+            lines.push(Line::from(vec![
+                "Kernel: ".into(),
+                format!(
+                    "events dropped {} times, input read too slowly",
+                    self.syn_drops
+                )
+                .yellow(),
+            ]));
+        }
+        if !self.degradations.is_empty() {
+            let off: Vec<&str> = self
+                .degradations
+                .iter()
+                .map(|step| step.description())
+                .collect();
+            lines.push(Line::from(vec![
+                "Render: ".into(),
+                format!("slow frames, {} off", off.join(", ")).yellow(),
+            ]));
+        }
+        if let Some(serial) = self.selected_serial_status()
+            && serial.swept
+        {
+            lines.push(Line::from(vec![
+                "Serial: ".into(),
+                format!("default rate unreadable, using {} baud", serial.baud).yellow(),
+            ]));
+        }
+
+        for (edge, samples) in self.saturation.detected() {
+            lines.push(Line::from(
+                format!("{} saturation detected, {samples} samples", edge.name())
+                    .red()
+                    .bold(),
+            ));
+        }
+
+        if self.true_aspect {
+            let aspect = if self.mm_scale().is_some() {
+                "true panel aspect".cyan()
+            } else {
+                "true aspect needs the panel size (--panel-mm)".red()
+            };
+            lines.push(Line::from(vec!["Aspect: ".into(), aspect]));
+        }
+
+        if self.pressure.threshold > 0 {
+            lines.push(Line::from(vec![
+                "Pressure threshold: ".into(),
+                format!("{}  ", self.pressure.threshold).yellow(),
+                "Filtered: ".into(),
+                format!("{}", self.pressure.filtered).red(),
+            ]));
+        }
 
         // Controls
         lines.push(Line::from(vec![
             "R".bold().yellow(),
             ":Reset ".into(),
+            "N".bold().yellow(),
+            ":New ".into(),
             "C".bold().yellow(),
             ":Clear ".into(),
             "T".bold().yellow(),
             ":Recalibrate ".into(),
+            "P".bold().yellow(),
+            ":Pinch".into(),
+        ]));
+        lines.push(Line::from(vec![
+            "L".bold().yellow(),
+            ":Lines ".into(),
+            "A".bold().yellow(),
+            ":Aspect ".into(),
+            "F".bold().yellow(),
+            ":Pressure ".into(),
             "Q".bold().yellow(),
-            ":Quit".into(),
+            ":Quit ".into(),
+            "F1".bold().yellow(),
+            ":Help".into(),
         ]));
 
+        let info_rect = overlay_rect(area, info_width, lines.len());
+
         let info_widget = Paragraph::new(lines)
-            .block(Block::bordered().title("Touch Test"))
+            .block(Block::bordered().title(if self.raw_mode {
+                "Touch Test - RAW MODE"
+            } else {
+                "Touch Test"
+            }))
             .style(Style::default().bg(Color::Black).fg(Color::White));
 
         f.render_widget(info_widget, info_rect);
@@ -948,53 +3972,56 @@ impl TouchscreenTestScreen {
         let canvas_w = area.width;
         let canvas_h = area.height;
 
-        let mut canvas = vec![vec![' '; canvas_w as usize]; canvas_h as usize];
-
-        // Draw corner markers to show calibrated area
-        // Top-left
-        if canvas_w > 2 && canvas_h > 2 {
-            canvas[0][0] = '┌';
-            canvas[0][1] = '─';
-            canvas[1][0] = '│';
-
-            // Top-right
-            let tr_x = (canvas_w - 1) as usize;
-            canvas[0][tr_x] = '┐';
-            canvas[0][tr_x - 1] = '─';
-            canvas[1][tr_x] = '│';
-
-            // Bottom-left
-            let br_y = (canvas_h - 1) as usize;
-            canvas[br_y][0] = '└';
-            canvas[br_y][1] = '─';
-            canvas[br_y - 1][0] = '│';
-
-            // Bottom-right
-            canvas[br_y][tr_x] = '┘';
-            canvas[br_y][tr_x - 1] = '─';
-            canvas[br_y - 1][tr_x] = '│';
-        }
+        let mut grid = self.trail_grid.borrow_mut();
+        grid.begin_frame(canvas_w as usize, canvas_h as usize, self.trail_cutoff);
 
         // Draw trail with fading
-        let trail_len = self.trail.len();
-        for (i, point) in self.trail.iter().enumerate() {
+        let draw_lines = self.draw_lines && !self.degraded(Degradation::TrailLines);
+        let trail = self.trail_points();
+        let trail_len = trail.len();
+        let to_cell = |point: &TouchPoint| -> (usize, usize) {
             let x = ((point.x as f32 / CALIBRATED_MAX_X as f32 * (canvas_w - 1) as f32) as usize)
                 .min(canvas_w as usize - 1);
             let y = ((point.y as f32 / CALIBRATED_MAX_Y as f32 * (canvas_h - 1) as f32) as usize)
                 .min(canvas_h as usize - 1);
+            (x, y)
+        };
 
-            if x < canvas_w as usize && y < canvas_h as usize {
-                // Fade trail: older points use lighter characters
-                let age_ratio = i as f32 / trail_len as f32;
-                let ch = if age_ratio > 0.8 {
-                    'O' // Recent
-                } else if age_ratio > 0.5 {
-                    'o'
-                } else {
-                    '.' // Old
-                };
-                canvas[y][x] = ch;
+        for (i, (age, point)) in trail.iter().enumerate() {
+            let (x, y) = to_cell(point);
+
+            // Fade trail: older points use lighter characters
+            let age_ratio = i as f32 / trail_len as f32;
+            let ch = if age_ratio > 0.8 {
+                'O' // Recent
+            } else if age_ratio > 0.5 {
+                'o'
+            } else {
+                '.' // Old
+            };
+
+            // Lines only join samples of the same stroke, never a lift to the next touch
+            let prev = i
+                .checked_sub(1)
+                .and_then(|prev| trail.get(prev))
+                .filter(|(prev_age, _)| prev_age == age)
+                .map(|(_, prev)| prev);
+            // A step past the jump threshold gets its own character, not only a color
+            let jumped = prev.is_some_and(|prev| {
+                let dx = point.x as f32 - prev.x as f32;
+                let dy = point.y as f32 - prev.y as f32;
+                (dx * dx + dy * dy).sqrt() > self.jump_threshold
+            });
+            let ch = if jumped { JUMP_CHAR } else { ch };
+            if draw_lines && let Some(prev) = prev {
+                let (px, py) = to_cell(prev);
+                // Both ends are on the canvas, so every cell between them is too
+                for (lx, ly) in line_cells(px as i32, py as i32, x as i32, y as i32) {
+                    grid.set_trail(lx as usize, ly as usize, ch, *age);
+                }
             }
+
+            grid.set_trail(x, y, ch, *age);
         }
 
         // Draw current touch with crosshair
@@ -1013,33 +4040,60 @@ impl TouchscreenTestScreen {
                 for dx in -size..=size {
                     let x = cx + dx;
                     if x >= 0 && x < canvas_w as i32 && cy >= 0 && cy < canvas_h as i32 {
-                        canvas[cy as usize][x as usize] = '─';
+                        grid.set(x as usize, cy as usize, '─');
                     }
                 }
                 for dy in -size..=size {
                     let y = cy + dy;
                     if y >= 0 && y < canvas_h as i32 && cx >= 0 && cx < canvas_w as i32 {
-                        canvas[y as usize][cx as usize] = '│';
+                        grid.set(cx as usize, y as usize, '│');
                     }
                 }
                 // Center marker
-                canvas[cy as usize][cx as usize] = '┼';
+                grid.set(cx as usize, cy as usize, '┼');
             }
         }
 
-        // Convert canvas to string
-        let canvas_text: String = canvas
-            .iter()
-            .map(|row| row.iter().collect::<String>())
-            .collect::<Vec<String>>()
-            .join("\n");
-
-        let style = Style::default().bg(Color::Black).fg(Color::White);
+        // The filtered position on top, smaller and in characters of its own
+        if let Some((sx, sy)) = self.stabilized_touch {
+            let cx = ((sx / CALIBRATED_MAX_X as f32 * (canvas_w - 1) as f32) as i32)
+                .clamp(0, canvas_w as i32 - 1);
+            let cy = ((sy / CALIBRATED_MAX_Y as f32 * (canvas_h - 1) as f32) as i32)
+                .clamp(0, canvas_h as i32 - 1);
+            for d in -2i32..=2 {
+                if (0..canvas_w as i32).contains(&(cx + d)) {
+                    grid.set((cx + d) as usize, cy as usize, '=');
+                }
+                if (0..canvas_h as i32).contains(&(cy + d)) {
+                    grid.set(cx as usize, (cy + d) as usize, ':');
+                }
+            }
+            grid.set(cx as usize, cy as usize, '#');
+        }
 
-        // No border - use full area so touch position matches visual position
-        let canvas_widget = Paragraph::new(canvas_text).style(style);
+        // Straight into the buffer, building lines and spans for a full screen
+        // of cells every frame costs more than everything else here
+        grid.render(
+            frame.buffer_mut(),
+            area,
+            !self.degraded(Degradation::TrailColors),
+            self.theme.jump_color(),
+        );
 
-        frame.render_widget(canvas_widget, area);
+        // The compared device's trail on top, in its own color
+        if let Some(compared) = &self.compared {
+            let buffer = frame.buffer_mut();
+            for point in compared
+                .trail
+                .iter()
+                .filter(|point| point.timestamp >= self.trail_cutoff)
+            {
+                let (x, y) = to_cell(point);
+                if let Some(cell) = buffer.cell_mut((area.x + x as u16, area.y + y as u16)) {
+                    cell.set_char('x').set_fg(Color::Cyan);
+                }
+            }
+        }
     }
 }
 
@@ -1054,47 +4108,192 @@ impl Screen for TouchscreenTestScreen {
         } else {
             self.draw_calibration(frame);
         }
+        self.touch_buttons().draw(frame);
+        self.draw_duplicate_warning(frame);
+        self.draw_disconnected(frame);
+        if let Some(pad) = &self.confirm_new_session {
+            pad.draw(frame);
+        }
     }
 
     fn handle_event(&mut self, event: AppEvent) -> Nav {
+        if self.confirm_new_session.is_some() && self.handle_confirmation(&event) {
+            return Nav::Stay;
+        }
+
         match event {
             AppEvent::Touch { .. } => {
-                self.handle_touch(event);
+                let (action, captured) = self.touch_button(&event);
+                match action {
+                    Some(TouchAction::Back) => return Nav::To(ScreenId::Home),
+                    Some(TouchAction::Next) => self.select_next_device(),
+                    Some(TouchAction::Select) => self.confirm_selection(),
+                    Some(TouchAction::Recalibrate) => self.recalibrate(),
+                    Some(TouchAction::Accept) => self.accept_orientation(),
+                    Some(TouchAction::Reset) => self.reset_statistics(),
+                    Some(TouchAction::NewSession) => self.ask_new_session(),
+                    _ => {}
+                }
+                if !captured {
+                    self.handle_touch(event);
+                }
+            }
+            AppEvent::MultiTouch {
+                ref contacts,
+                ref info,
+                ..
+            } => {
+                self.handle_multitouch(contacts, info);
+            }
+            AppEvent::Trackpad {
+                contacts,
+                timestamp,
+                info,
+                ..
+            } if self.include_trackpads => {
+                self.handle_trackpad(contacts, timestamp, info);
+            }
+            // Only the panel under test matters here, e.g. a flapping serial port
+            AppEvent::DeviceFault { info, reason }
+                if self
+                    .calibration
+                    .selected_device_info
+                    .as_ref()
+                    .is_some_and(|selected| selected.path == info.path) =>
+            {
+                self.device_fault = Some(reason);
+            }
+            AppEvent::EventsDropped { info, .. }
+                if self
+                    .calibration
+                    .selected_device_info
+                    .as_ref()
+                    .is_some_and(|selected| selected.path == info.path) =>
+            {
+                self.syn_drops += 1;
+            }
+            AppEvent::PenHover { x, y, left, info }
+                if self.calibration.is_done()
+                    && self.calibration.selected_device_path.as_ref() == Some(&info.path) =>
+            {
+                let (x, y) = self.map_raw(x, y);
+                self.hover.hover(x, y, left);
+            }
+            AppEvent::SerialStatus {
+                info,
+                baud,
+                valid_ratio,
+                swept,
+            } => {
+                self.serial_status = Some((
+                    info.path.clone(),
+                    SerialResult {
+                        baud,
+                        valid_ratio,
+                        swept,
+                    },
+                ));
+            }
+            // Unplugged between corners: hold on to the corners taken until it's back
+            AppEvent::DeviceRemoved { info }
+                if !self.calibration.is_done()
+                    && self.calibration.step != CalibrationStep::DeviceSelection
+                    && self.calibration.selected_device_path.as_ref() == Some(&info.path) =>
+            {
+                self.calibration.abandon_hold();
+                self.disconnected = Some(Arc::unwrap_or_clone(info));
+            }
+            // The same device (vendor, product and port) continues the calibration,
+            // another one of its kind means the panel was swapped and starts over.
+            // Devices of other kinds, e.g. a keyboard plugged in meanwhile, don't count.
+            AppEvent::DeviceAdded { info }
+                if self
+                    .disconnected
+                    .as_ref()
+                    .is_some_and(|removed| removed.class == info.class) =>
+            {
+                let removed = self.disconnected.take();
+                if removed.is_some_and(|removed| {
+                    physical_device_key(&removed) == physical_device_key(&info)
+                }) {
+                    self.calibration.selected_device_path = Some(info.path.clone());
+                    self.calibration.selected_device_info = Some(Arc::unwrap_or_clone(info));
+                } else {
+                    self.recalibrate();
+                }
+            }
+            AppEvent::DeviceRemoved { info }
+                if self.calibration.is_done()
+                    && self.calibration.selected_device_path.as_ref() == Some(&info.path) =>
+            {
+                self.finish_stroke();
+                self.current_touch = None;
+                self.removed_panel = Some(info.name.clone());
+            }
+            // The panel came back, on a new unit at the kiosk, so the new unit gets its
+            // own session. The node may have a new path, the calibration is kept.
+            AppEvent::DeviceAdded { info } if self.removed_panel.as_ref() == Some(&info.name) => {
+                self.removed_panel = None;
+                self.calibration.selected_device_path = Some(info.path.clone());
+                self.calibration.selected_device_info = Some(Arc::unwrap_or_clone(info));
+                self.new_session();
+            }
+            AppEvent::DeviceUpdated { info } => {
+                for device in &mut self.calibration.available_devices {
+                    if device.path == info.path {
+                        *device = DeviceInfo::clone(&info);
+                    }
+                }
+                if let Some(selected) = &mut self.calibration.selected_device_info
+                    && selected.path == info.path
+                {
+                    *selected = Arc::unwrap_or_clone(info);
+                }
             }
             AppEvent::Key { code, .. } => {
                 if code == KeyCode::KEY_Q || code == KeyCode::KEY_ESC {
                     return Nav::To(ScreenId::Home);
                 }
 
+                // Not waiting for the device to come back, pick another one
+                if self.disconnected.take().is_some() {
+                    self.recalibrate();
+                    return Nav::Stay;
+                }
+
                 // Handle device selection screen
                 if self.calibration.step == CalibrationStep::DeviceSelection {
                     match code {
-                        KeyCode::KEY_UP => {
-                            if !self.calibration.available_devices.is_empty() {
-                                self.calibration.selected_device_index =
-                                    (self.calibration.selected_device_index
-                                        + self.calibration.available_devices.len()
-                                        - 1)
-                                        % self.calibration.available_devices.len();
+                        KeyCode::KEY_I => {
+                            self.include_trackpads = !self.include_trackpads;
+                            if !self.include_trackpads {
+                                let devices = &mut self.calibration.available_devices;
+                                devices.retain(|device| device.class != DeviceClass::Trackpad);
+                                let paths: Vec<&String> =
+                                    devices.iter().map(|device| &device.path).collect();
+                                self.calibration
+                                    .marked_devices
+                                    .retain(|path| paths.contains(&path));
+                                self.calibration.selected_device_index = self
+                                    .calibration
+                                    .selected_device_index
+                                    .min(devices.len().saturating_sub(1));
                             }
                         }
-                        KeyCode::KEY_DOWN => {
-                            if !self.calibration.available_devices.is_empty() {
-                                self.calibration.selected_device_index =
-                                    (self.calibration.selected_device_index + 1)
-                                        % self.calibration.available_devices.len();
-                            }
+                        KeyCode::KEY_UP if !self.calibration.available_devices.is_empty() => {
+                            self.calibration.selected_device_index =
+                                (self.calibration.selected_device_index
+                                    + self.calibration.available_devices.len()
+                                    - 1)
+                                    % self.calibration.available_devices.len();
                         }
+                        KeyCode::KEY_DOWN => self.select_next_device(),
                         KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER => {
-                            if !self.calibration.available_devices.is_empty() {
-                                // Select the device and move to calibration
-                                let selected = &self.calibration.available_devices
-                                    [self.calibration.selected_device_index];
-                                self.calibration.selected_device_path = Some(selected.path.clone());
-                                self.calibration.selected_device_info = Some(selected.clone());
-                                self.calibration.step = CalibrationStep::TopLeft;
-                            }
+                            // Select the device, or the marked pair, and move to calibration
+                            self.confirm_selection();
                         }
+                        KeyCode::KEY_SPACE => self.toggle_mark(),
+                        KeyCode::KEY_W => self.raw_mode = !self.raw_mode,
                         KeyCode::KEY_1
                         | KeyCode::KEY_2
                         | KeyCode::KEY_3
@@ -1117,49 +4316,131 @@ impl Screen for TouchscreenTestScreen {
                                 KeyCode::KEY_9 => 8,
                                 _ => return Nav::Stay,
                             };
-                            if idx < self.calibration.available_devices.len() {
-                                self.calibration.selected_device_index = idx;
-                                let selected = &self.calibration.available_devices[idx];
-                                self.calibration.selected_device_path = Some(selected.path.clone());
-                                self.calibration.selected_device_info = Some(selected.clone());
-                                self.calibration.step = CalibrationStep::TopLeft;
-                            }
+                            self.select_device(idx);
                         }
                         _ => {}
                     }
+                } else if self.calibration.step == CalibrationStep::ConfirmOrientation
+                    && (code == KeyCode::KEY_Y || code == KeyCode::KEY_N)
+                {
+                    if code == KeyCode::KEY_Y {
+                        self.accept_orientation();
+                    } else {
+                        self.calibration.restart_corners();
+                    }
                 } else if code == KeyCode::KEY_R && self.calibration.is_done() {
-                    // Reset statistics
-                    self.statistics.reset();
+                    self.reset_statistics();
+                } else if code == KeyCode::KEY_N && self.calibration.is_done() {
+                    self.ask_new_session();
+                } else if code == KeyCode::KEY_S && self.calibration.is_done() {
+                    self.toggle_soak();
                 } else if code == KeyCode::KEY_C && self.calibration.is_done() {
-                    // Clear trail
-                    self.trail.clear();
+                    // Clear trail, the strokes themselves are kept for the report
+                    self.trail_cutoff = (self.clock)();
+                } else if code == KeyCode::KEY_L && self.calibration.is_done() {
+                    // Toggle connecting lines between samples
+                    self.draw_lines = !self.draw_lines;
+                } else if code == KeyCode::KEY_V && self.calibration.is_done() {
+                    // Toggle the speed graph of the latest stroke
+                    self.show_speed = !self.show_speed;
+                } else if code == KeyCode::KEY_A && self.calibration.is_done() {
+                    // Toggle between filling the terminal and the panel's true aspect
+                    self.true_aspect = !self.true_aspect;
+                } else if code == KeyCode::KEY_P && self.calibration.is_done() {
+                    // Toggle between the trail view and the pinch/rotate playground
+                    self.view = if self.view == TestView::Pinch {
+                        TestView::Trail
+                    } else {
+                        TestView::Pinch
+                    };
+                    self.pinch = PinchGesture::new();
+                } else if code == KeyCode::KEY_F && self.calibration.is_done() {
+                    // Toggle between the trail view and the pressure threshold graph
+                    self.view = if self.view == TestView::Pressure {
+                        TestView::Trail
+                    } else {
+                        TestView::Pressure
+                    };
+                } else if (code == KeyCode::KEY_UP || code == KeyCode::KEY_DOWN)
+                    && self.view == TestView::Pressure
+                {
+                    if let Some(max) = self.pressure_max() {
+                        self.pressure.adjust(code == KeyCode::KEY_UP, max);
+                    }
+                } else if code == KeyCode::KEY_J && self.calibration.is_done() {
+                    // Toggle between the trail view and the jump threshold histogram
+                    self.view = if self.view == TestView::Jumps {
+                        TestView::Trail
+                    } else {
+                        TestView::Jumps
+                    };
+                } else if (code == KeyCode::KEY_LEFT || code == KeyCode::KEY_RIGHT)
+                    && self.view == TestView::Jumps
+                {
+                    // The same step on the panel in raw units
+                    let unit = if self.raw_active() {
+                        self.raw_units_per_unit().round().max(1.0)
+                    } else {
+                        1.0
+                    };
+                    let step = if code == KeyCode::KEY_RIGHT {
+                        JUMP_THRESHOLD_STEP * unit
+                    } else {
+                        -JUMP_THRESHOLD_STEP * unit
+                    };
+                    let max = (self.statistics.distances.len() - 1) as f32;
+                    self.jump_threshold =
+                        (self.jump_threshold + step).clamp(JUMP_THRESHOLD_STEP * unit, max);
+                    self.statistics.recount_jumps(self.jump_threshold);
+                    self.stabilized_statistics
+                        .recount_jumps(self.jump_threshold);
+                    if let Some(compared) = &mut self.compared {
+                        compared.statistics.recount_jumps(self.jump_threshold);
+                    }
+                    // The tuned threshold goes out with the matrix, raw mode has none
+                    if self.matrix.is_some() {
+                        self.update_matrix();
+                    }
+                } else if code == KeyCode::KEY_W && self.calibration.is_done() {
+                    self.roi_setup = false;
+                    self.roi_drag = None;
+                    self.toggle_raw_mode();
+                } else if code == KeyCode::KEY_O && self.calibration.is_done() && !self.raw_mode {
+                    self.toggle_roi_setup();
+                } else if code == KeyCode::KEY_B && self.calibration.is_done() && !self.raw_mode {
+                    self.cycle_stabilizer();
                 } else if code == KeyCode::KEY_T {
-                    // Recalibrate - reset calibration to start over
-                    self.calibration = Calibration::new();
-                    self.trail.clear();
-                    self.statistics.reset();
-                    self.current_touch = None;
-                    self.last_position = None;
+                    self.recalibrate();
+                } else if code == KeyCode::KEY_U && !self.calibration.is_done() {
+                    self.use_learned_range();
                 }
             }
             AppEvent::Tick => {
+                self.power.tick();
+                if let Some(compared) = &mut self.compared
+                    && !compared.calibration.is_done()
+                {
+                    compared.calibration.update_hold_duration();
+                }
                 // Update calibration hold duration on each tick
                 if !self.calibration.is_done() {
                     self.calibration.update_hold_duration();
                 } else {
-                    // Remove old trail points based on time
-                    let current_time = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-
-                    while let Some(front) = self.trail.front() {
-                        if current_time.saturating_sub(front.timestamp) > TRAIL_LIFETIME_MS {
-                            self.trail.pop_front();
-                        } else {
-                            break;
-                        }
+                    if self.soak_on_start {
+                        self.soak_on_start = false;
+                        self.toggle_soak();
                     }
+                    let counters = self.soak_counters();
+                    if let Some(soak) = &mut self.soak {
+                        soak.tick(counters);
+                    }
+
+                    // Hide old trail points based on time
+                    let current_time = (self.clock)();
+
+                    self.trail_cutoff = self
+                        .trail_cutoff
+                        .max(current_time.saturating_sub(TRAIL_LIFETIME_MS));
                 }
             }
             _ => {}
@@ -1167,4 +4448,1117 @@ impl Screen for TouchscreenTestScreen {
 
         Nav::Stay
     }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        if self.calibration.step == CalibrationStep::DeviceSelection {
+            return vec![
+                ("↑/↓", "Select a touch device"),
+                (
+                    "Enter",
+                    "Use the selected device, or the two marked ones; restarts a dead listener",
+                ),
+                ("1-9", "Quick select a device"),
+                (
+                    "Space",
+                    "Mark a device, two marked devices are tested side by side",
+                ),
+                (
+                    "I",
+                    "Include trackpads in the list, for panels detected as one",
+                ),
+                (
+                    "W",
+                    "Raw mode: skip the calibration and measure in raw device units",
+                ),
+                ("Q/Esc, tap Back", "Back to the home menu"),
+                (
+                    "Tap Next/Select",
+                    "Same as ↓ and Enter, touch the panel first to list it",
+                ),
+            ];
+        }
+
+        if self.calibration.step == CalibrationStep::ConfirmOrientation {
+            return vec![
+                (
+                    "Y, tap Accept",
+                    "The panel is mounted the way shown, calibration done",
+                ),
+                ("N", "Take the corners again"),
+                ("T, tap Recalibrate", "Restart calibration"),
+                ("Q/Esc, tap Back", "Back to the home menu"),
+            ];
+        }
+
+        if !self.calibration.is_done() {
+            return vec![
+                ("Touch & hold", "Record the highlighted corner"),
+                (
+                    "U",
+                    "Calibrate to the range learned from the touches so far (serial panel)",
+                ),
+                ("T, tap Recalibrate", "Restart calibration"),
+                ("Q/Esc, tap Back", "Back to the home menu"),
+            ];
+        }
+
+        vec![
+            ("R", "Reset statistics"),
+            (
+                "S",
+                "Start or stop an unattended soak with periodic snapshots",
+            ),
+            (
+                "N, tap New unit",
+                "New session for the next unit after a Yes/No prompt, the current one goes to the report",
+            ),
+            ("C", "Clear the trail"),
+            ("L", "Toggle lines between samples"),
+            ("V", "Toggle the speed graph of the latest stroke"),
+            ("A", "Toggle filling the terminal / true panel aspect"),
+            ("P", "Toggle the pinch/rotate playground"),
+            ("F", "Toggle the pressure threshold graph"),
+            ("↑/↓", "Adjust the pressure threshold (pressure graph)"),
+            ("J", "Toggle the jump threshold histogram"),
+            ("←/→", "Adjust the jump threshold (jump histogram)"),
+            (
+                "W",
+                "Toggle raw mode: no calibration, raw units, no coverage or corners",
+            ),
+            (
+                "O",
+                "Set the region of interest by dragging corner to corner, O again for the whole panel",
+            ),
+            (
+                "B",
+                "Cycle the jitter filter shown next to the raw touch: moving average, median of 3, off",
+            ),
+            ("T, tap Recalibrate", "Recalibrate"),
+            ("Q/Esc, tap Back", "Back to the home menu"),
+            ("Tap Reset", "Same as R"),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "Select the touch device, calibrate by holding each corner for a second, then draw over \
+         the whole screen. The trail should follow your finger without gaps, and jumps between \
+         consecutive samples are counted as a sign of a faulty panel. Leaving and coming back \
+         keeps the calibration and statistics, R and T start over."
+    }
+
+    fn repeats_navigation(&self) -> bool {
+        self.calibration.step == CalibrationStep::DeviceSelection
+    }
+
+    fn preserve_on_exit(&self) -> bool {
+        // Worth coming back to once calibrated, R and T start over
+        self.calibration.is_done()
+    }
+
+    fn on_enter(&mut self) {
+        self.timer.resume();
+        // Touches made on other screens don't continue the last stroke
+        self.finish_stroke();
+        self.current_touch = None;
+        self.last_position = None;
+        self.lift_stabilizer();
+        self.pinch = PinchGesture::new();
+    }
+
+    fn on_exit(&mut self) {
+        // Time on other screens doesn't count towards the session
+        self.timer.pause();
+    }
+
+    fn set_degradations(&mut self, active: &[Degradation]) {
+        self.degradations = active.to_vec();
+    }
+
+    fn reconfigure(&mut self, options: &Options) {
+        // The trail and calibration are kept, only how jumps are shown changes
+        self.theme = options.theme;
+        self.timer
+            .set_min(Duration::from_secs(options.min_durations.touchscreen_secs));
+    }
+
+    fn progress(&self) -> Option<f32> {
+        if !self.calibration.is_done() {
+            return Some(0.0);
+        }
+        // Coverage is counted in calibrated space
+        if self.raw_mode {
+            return None;
+        }
+        let (touched, total) = self.coverage();
+        Some(touched as f32 / total.max(1) as f32)
+    }
+
+    fn take_results(&mut self) -> Option<ScreenResult> {
+        // Without a finished calibration there are no meaningful numbers
+        if !self.calibration.is_done() {
+            return None;
+        }
+
+        let mut result = self.session_result();
+        result.earlier_sessions = self.earlier_sessions.iter().cloned().collect();
+        result.dropped_sessions = self.dropped_sessions;
+        Some(ScreenResult::Touchscreen(result))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Instant;
+
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::event_ring::{self, EventRing};
+    use crate::sim::sim_device;
+    use crate::snapshot::{
+        LAYOUT_SIZES, assert_screen, assert_screen_ascii, assert_within_and_apart, panel, press,
+        render, touch,
+    };
+
+    // Raw corners of the panel, inset from the 0-4095 range
+    const CORNERS: [(u16, u16); 4] = [(100, 100), (3995, 100), (3995, 3995), (100, 3995)];
+
+    // No battery readout and a stopped clock, whatever machine the tests run on
+    fn screen() -> TouchscreenTestScreen {
+        let mut screen = TouchscreenTestScreen::new(CaptureMode::OnRelease, None);
+        screen.power = PowerMonitor::with_dir("/nonexistent");
+        screen.clock = || 1_000_000;
+        screen
+    }
+
+    /// The panel touched once and selected with 1
+    fn selected() -> TouchscreenTestScreen {
+        let mut screen = screen();
+        screen.handle_event(touch(&panel(), 2000, 2000, false));
+        screen.handle_event(press(KeyCode::KEY_1, 0));
+        screen
+    }
+
+    /// Hold still at (`x`, `y`) from `start` for a second with a sample every
+    /// 100 ms, then lift
+    fn hold(calibration: &mut Calibration, (x, y): (u16, u16), start: u128) {
+        for i in 0..=10 {
+            calibration.on_sample(x, y, false, start + i * 100);
+        }
+        calibration.on_sample(x, y, true, start + 1000);
+    }
+
+    /// A shift of drawing on a panel compared with its second path: a million
+    /// samples in strokes of 20, recorded into the F9 ring on the way like the
+    /// run loop does
+    #[test]
+    #[ignore = "a million events, run with --ignored"]
+    fn a_million_touch_events_stay_within_the_bounds() {
+        let mut screen = selected();
+        let second = Arc::new(DeviceInfo {
+            class: DeviceClass::Touchscreen,
+            ..sim_device("sim://panel-hid", "Snapshot panel HID", Some(4095))
+        });
+        let mut compared = ComparedDevice::new(CaptureMode::OnRelease, DeviceInfo::clone(&second));
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut screen.calibration, corner, i as u128 * 2000);
+            hold(&mut compared.calibration, corner, i as u128 * 2000);
+        }
+        screen.compared = Some(compared);
+
+        let panel = panel();
+        let mut ring = EventRing::new();
+        const STROKE: u32 = 20;
+        const SAMPLES: u32 = 500_000;
+        for i in 0..SAMPLES {
+            let step = i % STROKE;
+            let row = i / STROKE % 20;
+            for info in [&panel, &second] {
+                let event = AppEvent::Touch {
+                    x: 200 + step as u16 * 150,
+                    y: 200 + row as u16 * 150,
+                    timestamp: 1_000_000 + i as u128 * 8,
+                    released: step == STROKE - 1,
+                    pressure: None,
+                    info: Some(Arc::clone(info)),
+                    keepalive: false,
+                };
+                ring.record(&event);
+                screen.handle_event(event);
+            }
+        }
+
+        let strokes = (SAMPLES / STROKE) as usize;
+        assert_eq!(screen.strokes.len(), MAX_STROKES);
+        assert_eq!(
+            screen.evicted_strokes.strokes as usize,
+            strokes - MAX_STROKES
+        );
+        let points: usize = screen
+            .strokes
+            .iter()
+            .map(|stroke| stroke.points.len())
+            .sum();
+        assert_eq!(points, screen.recorded_points);
+        assert!(screen.recorded_points <= MAX_RECORDED_POINTS);
+        assert!(screen.trail_points().len() <= MAX_TRAIL_LENGTH);
+        assert_eq!(
+            screen.compared.as_ref().unwrap().trail.len(),
+            MAX_TRAIL_LENGTH
+        );
+        assert_eq!(ring.dump().lines().count(), event_ring::MAX_EVENTS);
+    }
+
+    #[test]
+    fn device_selection_snapshots() {
+        let mut screen = screen();
+        screen.handle_event(touch(&panel(), 2000, 2000, false));
+        assert_screen("touchscreen_selection", &screen);
+    }
+
+    #[test]
+    fn calibration_hold_snapshots() {
+        let mut screen = selected();
+        screen.calibration.on_sample(100, 100, false, 10_000);
+        screen.calibration.on_sample(100, 100, false, 10_500);
+        assert_eq!(screen.calibration.get_hold_progress(), 0.5);
+        assert_screen("touchscreen_calibration_half_hold", &screen);
+        assert_screen_ascii("touchscreen_calibration_half_hold", &screen);
+    }
+
+    #[test]
+    fn canvas_trail_snapshots() {
+        let mut screen = selected();
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut screen.calibration, corner, i as u128 * 2000);
+        }
+        assert!(screen.calibration.is_done());
+
+        let panel = panel();
+        for step in 0..=20 {
+            let x = 500 + step * 150;
+            let y = 1000 + step * 100;
+            screen.handle_event(touch(&panel, x, y, false));
+        }
+        screen.handle_event(touch(&panel, 3500, 3000, true));
+        // The test time goes by the wall clock
+        screen.timer.pause();
+        assert_screen("touchscreen_canvas_trail", &screen);
+        assert_screen_ascii("touchscreen_canvas_trail", &screen);
+    }
+
+    #[test]
+    fn canvas_layout_stays_inside() {
+        // Letterboxed wide and tall, and the whole frame without a panel size
+        for panel_mm in [None, Some((250.0, 150.0)), Some((90.0, 300.0))] {
+            let mut screen = selected();
+            screen.panel_mm = panel_mm;
+            screen.true_aspect = true;
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(&mut screen.calibration, corner, i as u128 * 2000);
+            }
+            for (width, height) in LAYOUT_SIZES {
+                let area = Rect::new(0, 0, width, height);
+                let layout = screen.test_layout(area);
+                assert_within_and_apart("canvas", area, &[layout.canvas]);
+                assert_eq!(layout.corners.len(), 4);
+                for (target, _, position) in &layout.corners {
+                    assert!(
+                        layout.canvas.contains(*position),
+                        "{target:?} at {width}x{height}: {position:?} off {:?}",
+                        layout.canvas
+                    );
+                }
+            }
+        }
+    }
+
+    /// A calibration waiting for its first corner
+    fn calibration(mode: CaptureMode) -> Calibration {
+        let mut calibration = Calibration::new(mode);
+        calibration.restart_corners();
+        calibration
+    }
+
+    /// Samples at (`x`, `y`) every 100 ms over `start..=end`, finger staying down
+    fn samples(calibration: &mut Calibration, (x, y): (u16, u16), start: u128, end: u128) {
+        for now in (start..=end).step_by(100) {
+            calibration.on_sample(x, y, false, now);
+        }
+    }
+
+    #[test]
+    fn on_release_captures_when_lifted_after_the_hold() {
+        let mut calibration = calibration(CaptureMode::OnRelease);
+        samples(&mut calibration, (100, 100), 0, 1000);
+        // Held long enough, but nothing is taken before the lift
+        assert!(calibration.hold_complete());
+        assert_eq!(calibration.count, 0);
+        calibration.on_sample(100, 100, true, 1050);
+        assert_eq!(calibration.count, 1);
+        assert_eq!(calibration.step, CalibrationStep::TopRight);
+    }
+
+    #[test]
+    fn on_release_ignores_short_holds() {
+        let mut calibration = calibration(CaptureMode::OnRelease);
+        samples(&mut calibration, (100, 100), 0, 900);
+        calibration.on_sample(100, 100, true, 950);
+        assert_eq!(calibration.count, 0);
+        assert_eq!(calibration.get_hold_progress(), 0.0);
+    }
+
+    #[test]
+    fn auto_captures_at_the_threshold_while_held() {
+        let mut calibration = calibration(CaptureMode::Auto);
+        samples(&mut calibration, (100, 100), 0, 900);
+        assert_eq!(calibration.count, 0);
+        calibration.on_sample(100, 100, false, 1000);
+        assert_eq!(calibration.count, 1);
+        assert!(calibration.captured);
+
+        // Still down: the next corner waits for the lift, however long the hold
+        samples(&mut calibration, (3995, 100), 1100, 3000);
+        assert_eq!(calibration.count, 1);
+        calibration.on_sample(3995, 100, true, 3050);
+        assert!(!calibration.captured);
+        samples(&mut calibration, (3995, 100), 4000, 5000);
+        assert_eq!(calibration.count, 2);
+    }
+
+    #[test]
+    fn moving_off_restarts_the_hold_in_both_modes() {
+        for mode in [CaptureMode::OnRelease, CaptureMode::Auto] {
+            let mut calibration = calibration(mode);
+            samples(&mut calibration, (100, 100), 0, 800);
+            assert!((calibration.get_hold_progress() - 0.8).abs() < 1e-6);
+            // Past the 100 unit tolerance of a device without a range
+            calibration.on_sample(300, 100, false, 900);
+            assert_eq!(calibration.get_hold_progress(), 0.0);
+            samples(&mut calibration, (300, 100), 1000, 1400);
+            assert!((calibration.get_hold_progress() - 0.5).abs() < 1e-6);
+            calibration.on_sample(300, 100, true, 1450);
+            assert_eq!(calibration.count, 0, "{mode:?}");
+        }
+    }
+
+    #[test]
+    fn too_few_samples_are_rejected() {
+        let mut calibration = calibration(CaptureMode::OnRelease);
+        // A panel reporting twice over the whole second
+        calibration.on_sample(100, 100, false, 0);
+        calibration.on_sample(100, 100, false, 1000);
+        calibration.on_sample(100, 100, true, 1000);
+        assert_eq!(calibration.count, 0);
+        assert!(calibration.capture_warning.is_some());
+    }
+
+    // The trail canvas at 200×50 with a typical update: a short stroke moving
+    // along and the crosshair around its end
+    const GRID: (usize, usize) = (200, 50);
+
+    fn paint(grid: &mut TrailGrid, cutoff: u128, step: usize) {
+        grid.begin_frame(GRID.0, GRID.1, cutoff);
+        for i in 0..30 {
+            grid.set_trail(20 + step % 100 + i, 25, 'O', 0);
+        }
+        let end = 50 + step % 100;
+        for d in 0..7 {
+            grid.set(end + d - 3, 25, '─');
+            grid.set(end, 22 + d, '│');
+        }
+    }
+
+    #[test]
+    fn incremental_frames_match_a_rebuild() {
+        let mut incremental = TrailGrid::default();
+        for step in 0..20 {
+            paint(&mut incremental, 0, step);
+        }
+        // A new cutoff forces the full rebuild
+        let mut rebuilt = TrailGrid::default();
+        paint(&mut rebuilt, 1, 19);
+        assert!(incremental.chars == rebuilt.chars);
+        assert!(incremental.ages == rebuilt.ages);
+    }
+
+    #[test]
+    fn incremental_frames_cost_a_tenth_of_a_rebuild() {
+        // Best of several runs, the tests share the machine
+        let time = |rebuild: bool| {
+            let mut grid = TrailGrid::default();
+            (0..5)
+                .map(|_| {
+                    let start = Instant::now();
+                    for step in 0..200 {
+                        let cutoff = if rebuild { step as u128 } else { 0 };
+                        paint(&mut grid, cutoff, step);
+                    }
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+        let incremental = time(false);
+        let rebuild = time(true);
+        assert!(
+            rebuild >= incremental * 10,
+            "200 frames: {incremental:?} incremental, {rebuild:?} rebuilt"
+        );
+    }
+
+    /// Every (swap, invert X, invert Y) a panel can be mounted with
+    const ORIENTATIONS: [(bool, bool, bool); 8] = [
+        (false, false, false),
+        (false, false, true),
+        (false, true, false),
+        (false, true, true),
+        (true, false, false),
+        (true, false, true),
+        (true, true, false),
+        (true, true, true),
+    ];
+
+    /// What a panel mounted as `orientation` reports for a touch at (`x`, `y`)
+    /// of the upright 0-4095 panel
+    fn raw((x, y): (u16, u16), (swap, invert_x, invert_y): (bool, bool, bool)) -> (u16, u16) {
+        let x = if invert_x { 4095 - x } else { x };
+        let y = if invert_y { 4095 - y } else { y };
+        if swap { (y, x) } else { (x, y) }
+    }
+
+    /// The corners as the calibration holds them once it knows about a swap:
+    /// inverted, never swapped
+    fn raw_corners(orientation: (bool, bool, bool)) -> [(u16, u16); 4] {
+        CORNERS.map(|corner| raw(corner, (false, orientation.1, orientation.2)))
+    }
+
+    #[test]
+    fn the_first_corner_pair_tells_a_swap() {
+        for orientation in ORIENTATIONS {
+            let mut calibration = calibration(CaptureMode::OnRelease);
+            hold(&mut calibration, raw(CORNERS[0], orientation), 0);
+            hold(&mut calibration, raw(CORNERS[1], orientation), 2000);
+            assert_eq!(calibration.swap_axes, orientation.0, "{orientation:?}");
+            // The corners taken so far are in panel orientation from here on
+            assert_eq!(calibration.pts[..2], raw_corners(orientation)[..2]);
+        }
+    }
+
+    #[test]
+    fn every_orientation_calibrates_upright() {
+        let targets = [
+            (0, 0),
+            (CALIBRATED_MAX_X, 0),
+            (CALIBRATED_MAX_X, CALIBRATED_MAX_Y),
+            (0, CALIBRATED_MAX_Y),
+        ];
+        for orientation in ORIENTATIONS {
+            let mut calibration = calibration(CaptureMode::OnRelease);
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(&mut calibration, raw(corner, orientation), i as u128 * 2000);
+            }
+            calibration.accept_orientation();
+            assert!(
+                calibration.is_done(),
+                "{orientation:?}: {:?}",
+                calibration.error
+            );
+            assert_eq!(
+                (calibration.invert_x, calibration.invert_y),
+                (orientation.1, orientation.2)
+            );
+
+            for (corner, target) in CORNERS.into_iter().zip(targets) {
+                let (raw_x, raw_y) = raw(corner, orientation);
+                let (x, y) = calibration.map(raw_x, raw_y);
+                assert!(
+                    x.abs_diff(target.0) <= 1 && y.abs_diff(target.1) <= 1,
+                    "{orientation:?}: {corner:?} mapped to ({x}, {y}), not {target:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn a_matrix_seeds_the_calibration_it_came_from() {
+        let limits = AxisLimits {
+            x: (0, 4095),
+            y: (0, 4095),
+        };
+        for orientation in ORIENTATIONS {
+            let mut calibrated = calibration(CaptureMode::OnRelease);
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(&mut calibrated, raw(corner, orientation), i as u128 * 2000);
+            }
+            calibrated.accept_orientation();
+
+            let matrix = CalibrationMatrix::from_mapping(&calibrated.linear_mapping(), limits);
+            let matrix = CalibrationMatrix::parse(&matrix.to_string()).unwrap();
+            let mut seeded = Calibration::new(CaptureMode::OnRelease);
+            seeded.seed(&matrix.to_mapping(limits).unwrap());
+            assert!(seeded.is_done());
+
+            for point in [(100, 100), (2000, 3000), (3995, 700), (4095, 4095)] {
+                let (raw_x, raw_y) = raw(point, orientation);
+                let (expected, actual) = (calibrated.map(raw_x, raw_y), seeded.map(raw_x, raw_y));
+                assert!(
+                    expected.0.abs_diff(actual.0) <= 1 && expected.1.abs_diff(actual.1) <= 1,
+                    "{orientation:?}: {point:?} mapped to {actual:?}, not {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn orientation_words_match_the_geometry() {
+        const UPRIGHT: (bool, bool, bool) = (false, false, false);
+        let words = |orientation: (bool, bool, bool)| {
+            orientation_words(orientation.0, orientation.1, orientation.2)
+        };
+        let of = |text: &str| {
+            ORIENTATIONS
+                .into_iter()
+                .find(|orientation| words(*orientation) == Some(text))
+                .unwrap_or_else(|| panic!("no orientation is {text:?}"))
+        };
+        assert_eq!(words(UPRIGHT), None);
+        let mut named: Vec<&str> = ORIENTATIONS.into_iter().filter_map(words).collect();
+        named.sort();
+        named.dedup();
+        assert_eq!(named.len(), 7, "{named:?}");
+
+        let turned = |point: (u16, u16), orientation, times| {
+            (0..times).fold(point, |point, _| raw(point, orientation))
+        };
+        let (clockwise, counter) = (
+            of("rotated 90° clockwise"),
+            of("rotated 90° counter-clockwise"),
+        );
+        for point in [(100, 900), (4000, 20), (2048, 3000)] {
+            // A quarter turn twice is a half turn, four times none, and the
+            // two directions undo each other
+            assert_eq!(turned(point, clockwise, 2), raw(point, of("rotated 180°")));
+            assert_eq!(turned(point, clockwise, 4), point);
+            assert_eq!(raw(raw(point, clockwise), counter), point);
+            assert_eq!(turned(point, counter, 2), raw(point, of("rotated 180°")));
+            // Mirrors undo themselves
+            for mirror in [
+                "mirrored left to right",
+                "mirrored top to bottom",
+                "mirrored across the diagonal (X and Y swapped)",
+                "mirrored across the other diagonal",
+            ] {
+                assert_eq!(turned(point, of(mirror), 2), point, "{mirror}");
+            }
+        }
+        // Left to right keeps Y, top to bottom keeps X
+        assert_eq!(raw((100, 900), of("mirrored left to right")).1, 900);
+        assert_eq!(raw((100, 900), of("mirrored top to bottom")).0, 100);
+    }
+
+    #[test]
+    fn a_mounting_other_than_upright_waits_for_confirmation() {
+        for orientation in ORIENTATIONS {
+            let mut calibration = calibration(CaptureMode::OnRelease);
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(&mut calibration, raw(corner, orientation), i as u128 * 2000);
+            }
+            let Some(words) = orientation_words(orientation.0, orientation.1, orientation.2) else {
+                assert!(calibration.is_done(), "upright: {:?}", calibration.step);
+                assert_eq!(calibration.orientation, None);
+                continue;
+            };
+
+            assert_eq!(calibration.step, CalibrationStep::ConfirmOrientation);
+            let told = calibration.orientation.clone().unwrap_or_default();
+            assert!(told.contains(words), "{orientation:?}: {told}");
+            // Touches wait for the answer
+            hold(&mut calibration, CORNERS[0], 10_000);
+            assert_eq!(calibration.step, CalibrationStep::ConfirmOrientation);
+            assert_eq!(calibration.count, 4);
+
+            calibration.accept_orientation();
+            assert!(calibration.is_done());
+        }
+    }
+
+    #[test]
+    fn a_device_picked_elsewhere_takes_the_orientation_unasked() {
+        let turned = (true, true, false);
+        let mut calibration =
+            Calibration::for_device(CaptureMode::OnRelease, DeviceInfo::clone(&panel()));
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut calibration, raw(corner, turned), i as u128 * 2000);
+        }
+        assert!(calibration.is_done());
+        assert!(calibration.orientation.is_some());
+    }
+
+    #[test]
+    fn the_model_hint_seeds_the_swap_and_the_corners_decide() {
+        let hinted = |mode| {
+            let mut calibration = calibration(mode);
+            calibration.selected_device_info = Some(DeviceInfo {
+                swapped_axes: true,
+                ..DeviceInfo::clone(&panel())
+            });
+            calibration.restart_corners();
+            calibration
+        };
+        let targets = [(0, 0), (CALIBRATED_MAX_X, CALIBRATED_MAX_Y)];
+
+        // A panel swapped the way the profile says is upright for that model
+        let mut calibration = hinted(CaptureMode::OnRelease);
+        assert!(calibration.swap_axes);
+        let swapped = (true, false, false);
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut calibration, raw(corner, swapped), i as u128 * 2000);
+        }
+        assert!(calibration.is_done());
+        assert!(calibration.swap_axes);
+        assert_eq!(calibration.orientation, None);
+        for (corner, target) in [CORNERS[0], CORNERS[2]].into_iter().zip(targets) {
+            let (x, y) = raw(corner, swapped);
+            let (x, y) = calibration.map(x, y);
+            assert!(x.abs_diff(target.0) <= 1 && y.abs_diff(target.1) <= 1);
+        }
+
+        // A panel that isn't swapped after all wins over the hint, and the
+        // operator is asked about the difference
+        let mut calibration = hinted(CaptureMode::Auto);
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut calibration, corner, i as u128 * 2000);
+        }
+        assert!(!calibration.swap_axes);
+        assert_eq!(calibration.step, CalibrationStep::ConfirmOrientation);
+        let told = calibration.orientation.clone().unwrap_or_default();
+        assert!(told.contains("expects X and Y swapped"), "{told}");
+        calibration.accept_orientation();
+        for (corner, target) in [CORNERS[0], CORNERS[2]].into_iter().zip(targets) {
+            let (x, y) = calibration.map(corner.0, corner.1);
+            assert!(x.abs_diff(target.0) <= 1 && y.abs_diff(target.1) <= 1);
+        }
+
+        // Starting over seeds the hint again
+        calibration.restart_corners();
+        assert!(calibration.swap_axes);
+    }
+
+    #[test]
+    fn y_accepts_the_orientation_and_n_takes_the_corners_again() {
+        for (key, done) in [(KeyCode::KEY_Y, true), (KeyCode::KEY_N, false)] {
+            let mut screen = selected();
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(
+                    &mut screen.calibration,
+                    raw(corner, (false, true, true)),
+                    i as u128 * 2000,
+                );
+            }
+            assert_eq!(screen.calibration.step, CalibrationStep::ConfirmOrientation);
+            screen.handle_event(press(key, 20_000));
+            assert_eq!(screen.calibration.is_done(), done, "{key:?}");
+            if done {
+                assert!(screen.matrix.is_some());
+            } else {
+                assert_eq!(screen.calibration.step, CalibrationStep::TopLeft);
+                assert_eq!(screen.calibration.count, 0);
+            }
+        }
+    }
+
+    #[test]
+    fn the_tuned_jump_threshold_is_written_with_the_matrix() {
+        let path = std::env::temp_dir().join(format!(
+            "keyboard_test-jump-threshold-{}.env",
+            std::process::id()
+        ));
+        let mut screen = screen()
+            .with_jump_threshold(40.0)
+            .with_calibration_matrix(None, Some(path.clone()));
+        screen.handle_event(touch(&panel(), 2000, 2000, false));
+        screen.handle_event(press(KeyCode::KEY_1, 0));
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut screen.calibration, corner, i as u128 * 2000);
+        }
+        screen.on_calibrated();
+        let written = || fs::read_to_string(&path).unwrap();
+        assert!(
+            written().ends_with("TOUCH_JUMP_THRESHOLD=\"40\"\n"),
+            "{}",
+            written()
+        );
+
+        // Two steps up in the jump view
+        screen.handle_event(press(KeyCode::KEY_J, 10_000));
+        screen.handle_event(press(KeyCode::KEY_RIGHT, 10_100));
+        screen.handle_event(press(KeyCode::KEY_RIGHT, 10_200));
+        assert!(
+            written().ends_with("TOUCH_JUMP_THRESHOLD=\"50\"\n"),
+            "{}",
+            written()
+        );
+        let _ = fs::remove_file(&path);
+
+        // Out of range thresholds are held to what the histogram shows
+        let threshold = |threshold: f32| {
+            TouchscreenTestScreen::new(CaptureMode::OnRelease, None)
+                .with_jump_threshold(threshold)
+                .jump_threshold
+        };
+        assert_eq!(threshold(0.5), JUMP_THRESHOLD_STEP);
+        assert_eq!(threshold(5000.0), (DISTANCE_BUCKETS - 1) as f32);
+    }
+
+    /// A USB panel at `path`, `product` tells models apart
+    fn usb_panel(path: &str, product: u16) -> Arc<DeviceInfo> {
+        Arc::new(DeviceInfo {
+            path: path.to_string(),
+            vendor: 0x0eef,
+            product,
+            phys: Some("usb-0000:00:14.0-3/input0".to_string()),
+            ..DeviceInfo::clone(&panel())
+        })
+    }
+
+    /// `usb_panel` selected, two corners taken and the finger holding the
+    /// third when it's unplugged
+    fn unplugged_mid_hold() -> TouchscreenTestScreen {
+        let device = usb_panel("/dev/input/event5", 0x0001);
+        let mut screen = screen();
+        screen.handle_event(touch(&device, 2000, 2000, false));
+        screen.handle_event(press(KeyCode::KEY_1, 0));
+        for (i, corner) in CORNERS[..2].iter().enumerate() {
+            hold(&mut screen.calibration, *corner, i as u128 * 2000);
+        }
+        for i in 0..5 {
+            let (x, y) = CORNERS[2];
+            screen.calibration.on_sample(x, y, false, 4000 + i * 100);
+        }
+
+        screen.handle_event(AppEvent::DeviceRemoved { info: device });
+        assert!(screen.disconnected.is_some());
+        assert!(!screen.calibration.is_touching);
+        assert!(screen.calibration.touch_samples.is_empty());
+        let shown = render(80, 24, |frame| screen.draw(frame));
+        assert!(shown.contains("2 of 4 corners taken"), "{shown}");
+        screen
+    }
+
+    #[test]
+    fn the_same_panel_plugged_back_in_keeps_its_corners() {
+        let mut screen = unplugged_mid_hold();
+
+        // A keyboard plugged in meanwhile doesn't count
+        let keyboard = Arc::new(sim_device("/dev/input/event9", "Keyboard", None));
+        screen.handle_event(AppEvent::DeviceAdded { info: keyboard });
+        assert!(screen.disconnected.is_some());
+
+        // Back on another node, same vendor, product and port
+        let back = usb_panel("/dev/input/event7", 0x0001);
+        screen.handle_event(AppEvent::DeviceAdded { info: back });
+        assert!(screen.disconnected.is_none());
+        assert_eq!(screen.calibration.count, 2);
+        assert_eq!(
+            screen.calibration.selected_device_path.as_deref(),
+            Some("/dev/input/event7")
+        );
+
+        // The third corner is held again from the start and the rest follow
+        for (i, corner) in CORNERS[2..].iter().enumerate() {
+            hold(&mut screen.calibration, *corner, 10_000 + i as u128 * 2000);
+        }
+        assert!(screen.calibration.is_done());
+        assert_eq!(screen.calibration.pts[..2], CORNERS[..2]);
+    }
+
+    #[test]
+    fn another_panel_plugged_in_starts_over() {
+        let mut screen = unplugged_mid_hold();
+
+        let other = usb_panel("/dev/input/event5", 0x0002);
+        screen.handle_event(AppEvent::DeviceAdded { info: other });
+        assert!(screen.disconnected.is_none());
+        assert_eq!(screen.calibration.count, 0);
+        assert_eq!(screen.calibration.step, CalibrationStep::DeviceSelection);
+        assert_eq!(screen.calibration.selected_device_path, None);
+    }
+
+    #[test]
+    fn a_key_while_waiting_gives_up_the_corners() {
+        let mut screen = unplugged_mid_hold();
+
+        assert!(matches!(
+            screen.handle_event(press(KeyCode::KEY_SPACE, 0)),
+            Nav::Stay
+        ));
+        assert!(screen.disconnected.is_none());
+        assert_eq!(screen.calibration.count, 0);
+        assert_eq!(screen.calibration.step, CalibrationStep::DeviceSelection);
+
+        // The panel coming back now is a device like any other
+        let back = usb_panel("/dev/input/event7", 0x0001);
+        screen.handle_event(AppEvent::DeviceAdded { info: back });
+        assert_eq!(screen.calibration.count, 0);
+    }
+
+    /// Raw sample at calibrated (`x`, `y`) once calibrated on `CORNERS`
+    fn raw_at((x, y): (u16, u16)) -> (u16, u16) {
+        let raw = |value: u16| 100 + (value as u32 * 3895 / 999) as u16;
+        (raw(x), raw(y))
+    }
+
+    fn tap(screen: &mut TouchscreenTestScreen, point: (u16, u16)) {
+        let (x, y) = raw_at(point);
+        screen.handle_event(touch(&panel(), x, y, false));
+        screen.handle_event(touch(&panel(), x, y, true));
+    }
+
+    #[test]
+    fn a_dragged_region_bounds_the_criteria_and_counts_masked_touches() {
+        let mut screen = selected();
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut screen.calibration, corner, i as u128 * 2000);
+        }
+
+        // O, then a drag over the bottom-right quarter
+        screen.handle_event(press(KeyCode::KEY_O, 0));
+        for point in [(500, 500), (700, 800), (999, 999)] {
+            let (x, y) = raw_at(point);
+            screen.handle_event(touch(&panel(), x, y, false));
+        }
+        let (x, y) = raw_at((999, 999));
+        screen.handle_event(touch(&panel(), x, y, true));
+        let edges = screen.roi.edges();
+        assert!(
+            edges
+                .iter()
+                .zip([0.5, 0.5, 1.0, 1.0])
+                .all(|(edge, expected)| (edge - expected).abs() < 0.005),
+            "{edges:?}"
+        );
+        // The drag itself isn't part of the test
+        assert_eq!(screen.coverage(), (0, 48));
+        assert_eq!(screen.strokes.len(), 0);
+
+        // Touch-downs outside count once each, however long they drag
+        tap(&mut screen, (100, 100));
+        for point in [(900, 100), (950, 200), (990, 300)] {
+            let (x, y) = raw_at(point);
+            screen.handle_event(touch(&panel(), x, y, false));
+        }
+        let (x, y) = raw_at((990, 300));
+        screen.handle_event(touch(&panel(), x, y, true));
+        assert_eq!(screen.masked_touches, 2);
+        assert_eq!(screen.corners.satisfied(), 0);
+
+        // The region's corners pass the corner checks, on four of its cells
+        for point in [(510, 510), (995, 510), (510, 995), (995, 995)] {
+            tap(&mut screen, point);
+        }
+        assert!(screen.corners.passed());
+        assert_eq!(screen.masked_touches, 2);
+        assert_eq!(screen.coverage(), (4, 48));
+
+        let result = screen.session_result();
+        assert_eq!(result.roi, Some(screen.roi.edges()));
+        assert_eq!(result.masked_touches, 2);
+        assert_eq!((result.cells_touched, result.cells_total), (4, 48));
+
+        // O twice covers the whole panel again, with the cells touched outside
+        screen.handle_event(press(KeyCode::KEY_O, 0));
+        screen.handle_event(press(KeyCode::KEY_O, 0));
+        assert!(screen.roi.is_full());
+        assert_eq!(screen.coverage(), (8, 192));
+        assert_eq!(screen.session_result().roi, None);
+    }
+
+    #[test]
+    fn a_configured_corner_region_survives_the_calibration() {
+        let roi = Roi::new([0.0, 0.0, 0.25, 0.25]).unwrap();
+        let mut screen = screen().with_roi(Some(roi));
+        screen.handle_event(touch(&panel(), 2000, 2000, false));
+        screen.handle_event(press(KeyCode::KEY_1, 0));
+        for (i, corner) in CORNERS.into_iter().enumerate() {
+            hold(&mut screen.calibration, corner, i as u128 * 2000);
+        }
+        assert_eq!(screen.roi, roi);
+        assert_eq!(screen.coverage(), (0, 12));
+
+        // The panel's far corners are behind the bezel
+        for point in [(995, 5), (5, 995), (995, 995)] {
+            tap(&mut screen, point);
+        }
+        assert_eq!(screen.masked_touches, 3);
+        for point in [(5, 5), (245, 5), (5, 245), (245, 245)] {
+            tap(&mut screen, point);
+        }
+        assert!(screen.corners.passed());
+        assert_eq!(screen.masked_touches, 3);
+        assert_eq!(screen.coverage(), (4, 12));
+    }
+
+    #[test]
+    fn corners_out_of_order_are_reported() {
+        let mut calibration = calibration(CaptureMode::OnRelease);
+        // Bottom left and bottom right the other way round
+        for (i, corner) in [CORNERS[0], CORNERS[1], CORNERS[3], CORNERS[2]]
+            .into_iter()
+            .enumerate()
+        {
+            hold(&mut calibration, corner, i as u128 * 2000);
+        }
+        let error = calibration.error.as_deref().unwrap_or_default();
+        assert!(error.contains("opposite X directions"), "{error}");
+        // Back to the first corner for another try
+        assert_eq!(calibration.step, CalibrationStep::TopLeft);
+        assert_eq!(calibration.count, 0);
+    }
+
+    /// A calibration step: a sample `dt` ms after the last, or a tick
+    #[derive(Debug, Clone, Copy)]
+    enum Step {
+        Sample {
+            x: u16,
+            y: u16,
+            released: bool,
+            dt: u128,
+        },
+        Tick {
+            dt: u128,
+        },
+    }
+
+    /// Runs of samples resting near a corner or anywhere, so holds complete
+    /// as well as break off, with ticks in between and a lift or not at the end
+    fn steps() -> impl Strategy<Value = Vec<Step>> {
+        let near_corner = (0..4usize, -50..50i32, -50..50i32).prop_map(|(corner, dx, dy)| {
+            let (x, y) = CORNERS[corner];
+            ((x as i32 + dx) as u16, (y as i32 + dy) as u16)
+        });
+        let position = prop_oneof![3 => near_corner, 1 => (any::<u16>(), any::<u16>())];
+        let run = (
+            position,
+            proptest::collection::vec((any::<bool>(), 0..300u128), 1..20),
+            any::<bool>(),
+        )
+            .prop_map(|((x, y), steps, lift)| {
+                let mut run: Vec<Step> = steps
+                    .into_iter()
+                    .map(|(tick, dt)| match tick {
+                        true => Step::Tick { dt },
+                        false => Step::Sample {
+                            x,
+                            y,
+                            released: false,
+                            dt,
+                        },
+                    })
+                    .collect();
+                if lift {
+                    run.push(Step::Sample {
+                        x,
+                        y,
+                        released: true,
+                        dt: 10,
+                    });
+                }
+                run
+            });
+        proptest::collection::vec(run, 0..24).prop_map(|runs| runs.concat())
+    }
+
+    /// Touches of the selected panel anywhere in the u16 range, or ticks
+    fn touches() -> impl Strategy<Value = Vec<Option<(u16, u16, bool)>>> {
+        let touch = (any::<u16>(), any::<u16>(), proptest::bool::weighted(0.2));
+        proptest::collection::vec(proptest::option::weighted(0.9, touch), 0..200)
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(256))]
+
+        #[test]
+        fn calibration_takes_at_most_four_corners(
+            steps in steps(),
+            auto in any::<bool>(),
+        ) {
+            let mode = if auto { CaptureMode::Auto } else { CaptureMode::OnRelease };
+            let mut calibration = calibration(mode);
+            let mut now = 0;
+            for step in steps {
+                match step {
+                    Step::Sample { x, y, released, dt } => {
+                        now += dt;
+                        calibration.on_sample(x, y, released, now);
+                    }
+                    Step::Tick { dt } => {
+                        now += dt;
+                        calibration.advance_hold(now);
+                    }
+                }
+                prop_assert!(calibration.count <= 4, "{} corners", calibration.count);
+                prop_assert!(calibration.get_hold_progress() <= 1.0);
+                if calibration.is_done() {
+                    prop_assert_eq!(calibration.count, 4);
+                }
+            }
+        }
+
+        #[test]
+        fn touched_cells_stay_on_the_grid(touches in touches(), calibrated in any::<bool>()) {
+            let mut screen = selected();
+            if calibrated {
+                for (i, corner) in CORNERS.into_iter().enumerate() {
+                    hold(&mut screen.calibration, corner, i as u128 * 2000);
+                }
+            }
+            let panel = panel();
+            for touch_or_tick in touches {
+                let event = match touch_or_tick {
+                    Some((x, y, released)) => touch(&panel, x, y, released),
+                    None => AppEvent::Tick,
+                };
+                screen.handle_event(event);
+
+                prop_assert!(screen.calibration.count <= 4);
+                prop_assert_eq!(screen.is_touched.len(), (COLS * ROWS) as usize);
+                if let Some(index) = screen.touching_idx {
+                    prop_assert!(index < screen.is_touched.len(), "cell {}", index);
+                }
+            }
+        }
+
+        #[test]
+        fn strokes_split_at_every_lift(touches in touches()) {
+            let mut screen = selected();
+            for (i, corner) in CORNERS.into_iter().enumerate() {
+                hold(&mut screen.calibration, corner, i as u128 * 2000);
+            }
+            let panel = panel();
+            // Samples of each down run, the open run last
+            let mut runs: Vec<u32> = Vec::new();
+            let mut open = false;
+            for (x, y, released) in touches.into_iter().flatten() {
+                screen.handle_event(touch(&panel, x, y, released));
+                match (released, open) {
+                    (true, _) => open = false,
+                    (false, true) => *runs.last_mut().unwrap() += 1,
+                    (false, false) => {
+                        runs.push(1);
+                        open = true;
+                    }
+                }
+            }
+
+            prop_assert_eq!(screen.strokes.len(), runs.len());
+            prop_assert_eq!(screen.stroke_open, open);
+            for (index, (stroke, samples)) in screen.strokes.iter().zip(&runs).enumerate() {
+                prop_assert_eq!(stroke.sample_count(), *samples);
+                let last = index + 1 == runs.len();
+                prop_assert_eq!(stroke.finished, !(last && open));
+            }
+        }
+    }
 }
@@ -0,0 +1,550 @@
+use evdev::KeyCode;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::border,
+    text::Line,
+    widgets::{Block, Paragraph},
+};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::{
+    Nav, Screen, ScreenId,
+    app::digit_index,
+    event_handler::{AppEvent, DeviceInfo, MAX_TOUCH_SLOTS, TouchContact},
+    power::PowerMonitor,
+    report::{ScreenResult, TrackpadResult},
+    test_timer::TestTimer,
+    touch_nav::{TouchAction, TouchButtons},
+};
+
+// A single finger down and up within this time, without moving, counts as a tap
+const TAP_MAX_DURATION: Duration = Duration::from_millis(250);
+// Allowed movement during a tap, as a fraction of the pad size
+const TAP_MAX_MOVEMENT: f32 = 0.03;
+// A contact unchanged this long while the pad senses no finger had its lift missed
+const STUCK_SLOT_TIMEOUT: Duration = Duration::from_secs(2);
+
+// Single finger touch that may still turn out to be a tap
+struct TapCandidate {
+    started: Instant,
+    start: (u16, u16),
+    moved: bool,
+}
+
+/// Trackpad test: pick the pad, then fingers shown per slot, clicks, taps and
+/// palm detection
+pub struct TrackpadTestScreen {
+    // Trackpads seen so far, offered until one is selected
+    available_devices: Vec<Arc<DeviceInfo>>,
+    selected_index: usize,
+    device: Option<Arc<DeviceInfo>>,
+    contacts: Vec<TouchContact>,
+    // The pad senses a finger at all, see `AppEvent::Trackpad`
+    touching: bool,
+    // When each slot's contact last changed, for spotting stuck slots
+    slot_changed: [Option<Instant>; MAX_TOUCH_SLOTS],
+    // Contacts cleared by hand or as stuck, hidden while the pad keeps repeating them
+    cleared: [Option<TouchContact>; MAX_TOUCH_SLOTS],
+    cleared_slots: u64,
+    slot_samples: [u64; MAX_TOUCH_SLOTS],
+    palm_slots: [bool; MAX_TOUCH_SLOTS],
+    max_fingers: usize,
+    clicks: u64,
+    taps: u64,
+    palm_events: u64,
+    tap: Option<TapCandidate>,
+    selection_buttons: TouchButtons,
+    test_buttons: TouchButtons,
+    power: PowerMonitor,
+    // Restarted with the counters
+    timer: TestTimer,
+}
+
+impl TrackpadTestScreen {
+    pub fn new() -> Self {
+        TrackpadTestScreen {
+            available_devices: Vec::new(),
+            selected_index: 0,
+            device: None,
+            contacts: Vec::new(),
+            touching: false,
+            slot_changed: [None; MAX_TOUCH_SLOTS],
+            cleared: [None; MAX_TOUCH_SLOTS],
+            cleared_slots: 0,
+            slot_samples: [0; MAX_TOUCH_SLOTS],
+            palm_slots: [false; MAX_TOUCH_SLOTS],
+            max_fingers: 0,
+            clicks: 0,
+            taps: 0,
+            palm_events: 0,
+            tap: None,
+            selection_buttons: TouchButtons::new(&[
+                TouchAction::Next,
+                TouchAction::Select,
+                TouchAction::Back,
+            ]),
+            test_buttons: TouchButtons::new(&[TouchAction::Reset, TouchAction::Back]),
+            power: PowerMonitor::new(),
+            timer: TestTimer::start(),
+        }
+    }
+
+    /// Shortest time the test has to run to pass
+    pub fn with_min_duration(mut self, min: Duration) -> Self {
+        self.timer.set_min(min);
+        self
+    }
+
+    fn touch_buttons(&self) -> &TouchButtons {
+        if self.device.is_none() {
+            &self.selection_buttons
+        } else {
+            &self.test_buttons
+        }
+    }
+
+    fn select_next_device(&mut self) {
+        let count = self.available_devices.len();
+        if count > 0 {
+            self.selected_index = (self.selected_index + 1) % count;
+        }
+    }
+
+    fn select_device(&mut self, index: usize) {
+        if let Some(info) = self.available_devices.get(index) {
+            self.selected_index = index;
+            self.device = Some(Arc::clone(info));
+            self.reset();
+        }
+    }
+
+    /// Back to the device selection, starting over with the next pad
+    fn deselect_device(&mut self) {
+        self.device = None;
+        self.reset();
+    }
+
+    /// Forget everything counted so far, keeping the selected device
+    fn reset(&mut self) {
+        self.contacts.clear();
+        self.touching = false;
+        self.slot_changed = [None; MAX_TOUCH_SLOTS];
+        self.cleared = [None; MAX_TOUCH_SLOTS];
+        self.cleared_slots = 0;
+        self.slot_samples = [0; MAX_TOUCH_SLOTS];
+        self.palm_slots = [false; MAX_TOUCH_SLOTS];
+        self.max_fingers = 0;
+        self.clicks = 0;
+        self.taps = 0;
+        self.palm_events = 0;
+        self.tap = None;
+        self.power.start();
+        self.timer.restart();
+    }
+
+    fn is_selected(&self, info: &DeviceInfo) -> bool {
+        self.device
+            .as_ref()
+            .is_some_and(|device| device.path == info.path)
+    }
+
+    /// Drop the shown contacts whose slot matches `stuck`, e.g. after a missed lift.
+    /// The pad keeps reporting them, so they stay hidden until they move or go away.
+    fn clear_slots(&mut self, stuck: impl Fn(&TouchContact) -> bool) {
+        let (cleared, kept): (Vec<TouchContact>, Vec<TouchContact>) =
+            self.contacts.iter().partition(|contact| stuck(contact));
+        for contact in cleared {
+            self.cleared[contact.slot] = Some(contact);
+            self.slot_changed[contact.slot] = None;
+            self.cleared_slots += 1;
+            // A stuck slot ending isn't a tap
+            self.tap = None;
+        }
+        self.contacts = kept;
+    }
+
+    /// Clear contacts that haven't changed for a while although the pad senses no finger
+    fn clear_stuck_slots(&mut self) {
+        if self.touching {
+            return;
+        }
+        let slot_changed = self.slot_changed;
+        self.clear_slots(|contact| {
+            slot_changed[contact.slot]
+                .is_some_and(|changed| changed.elapsed() >= STUCK_SLOT_TIMEOUT)
+        });
+    }
+
+    fn handle_contacts(
+        &mut self,
+        contacts: Vec<TouchContact>,
+        touching: bool,
+        info: Arc<DeviceInfo>,
+    ) {
+        self.touching = touching;
+
+        // A cleared contact stays hidden while the pad repeats it unchanged
+        for slot in 0..MAX_TOUCH_SLOTS {
+            if self.cleared[slot].is_some_and(|cleared| !contacts.contains(&cleared)) {
+                self.cleared[slot] = None;
+            }
+        }
+        let contacts: Vec<TouchContact> = contacts
+            .into_iter()
+            .filter(|contact| self.cleared[contact.slot] != Some(*contact))
+            .collect();
+
+        let now = Instant::now();
+        let mut slot_changed = [None; MAX_TOUCH_SLOTS];
+        for contact in &contacts {
+            let previous = self.contacts.iter().find(|c| c.slot == contact.slot);
+            slot_changed[contact.slot] = match previous {
+                Some(previous) if previous == contact => self.slot_changed[contact.slot],
+                _ => Some(now),
+            };
+        }
+        self.slot_changed = slot_changed;
+
+        let max_movement = |max: Option<i32>| max.unwrap_or(1000) as f32 * TAP_MAX_MOVEMENT;
+        let (tolerance_x, tolerance_y) =
+            (max_movement(info.abs_x_max), max_movement(info.abs_y_max));
+
+        for contact in &contacts {
+            self.slot_samples[contact.slot] += 1;
+
+            // Count a palm once per contact, not once per frame
+            if contact.palm && !self.palm_slots[contact.slot] {
+                self.palm_events += 1;
+            }
+        }
+        self.palm_slots = [false; MAX_TOUCH_SLOTS];
+        for contact in contacts.iter().filter(|c| c.palm) {
+            self.palm_slots[contact.slot] = true;
+        }
+
+        self.max_fingers = self.max_fingers.max(contacts.len());
+
+        match (self.contacts.len(), contacts.as_slice()) {
+            // First finger down
+            (0, [contact]) => {
+                self.tap = Some(TapCandidate {
+                    started: Instant::now(),
+                    start: (contact.x, contact.y),
+                    moved: false,
+                });
+            }
+            // Finger moving
+            (_, [contact]) => {
+                if let Some(tap) = self.tap.as_mut() {
+                    let dx = (contact.x as f32 - tap.start.0 as f32).abs();
+                    let dy = (contact.y as f32 - tap.start.1 as f32).abs();
+                    tap.moved |= dx > tolerance_x || dy > tolerance_y;
+                }
+            }
+            // All fingers lifted
+            (_, []) => {
+                if let Some(tap) = self.tap.take()
+                    && !tap.moved
+                    && tap.started.elapsed() <= TAP_MAX_DURATION
+                {
+                    self.taps += 1;
+                }
+            }
+            // A second finger makes it a gesture, not a tap
+            _ => self.tap = None,
+        }
+
+        self.contacts = contacts;
+        self.device = Some(info);
+    }
+
+    fn draw_device_selection(&self, frame: &mut Frame, area: Rect) {
+        let mut lines = vec![
+            Line::from("Select which trackpad to test:".yellow()).centered(),
+            Line::from(""),
+        ];
+        if self.available_devices.is_empty() {
+            lines.push(Line::from("No trackpads detected yet.".red()).centered());
+            lines.push(Line::from(""));
+            lines.push(Line::from("Touch each pad to list it...".gray()).centered());
+        }
+        for (i, device) in self.available_devices.iter().enumerate() {
+            let line = if i == self.selected_index {
+                Line::from(format!("► {}. {}", i + 1, device.name).bold().yellow())
+            } else {
+                Line::from(format!("  {}. {}", i + 1, device.name))
+            };
+            lines.push(line.centered());
+        }
+
+        let [list] = Layout::vertical([Constraint::Length(lines.len() as u16)])
+            .flex(Flex::Center)
+            .areas(area);
+        frame.render_widget(Paragraph::new(lines), list);
+    }
+
+    fn draw_pad(&self, frame: &mut Frame, area: Rect) {
+        let block = Block::bordered().title(" Pad ");
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if inner.width == 0 || inner.height == 0 {
+            return;
+        }
+
+        let mut grid = vec![vec![' '; inner.width as usize]; inner.height as usize];
+
+        if let Some(info) = &self.device {
+            let max_x = info.abs_x_max.unwrap_or(1000).max(1) as f32;
+            let max_y = info.abs_y_max.unwrap_or(1000).max(1) as f32;
+
+            for contact in &self.contacts {
+                let col = ((contact.x as f32 / max_x) * (inner.width - 1) as f32) as usize;
+                let row = ((contact.y as f32 / max_y) * (inner.height - 1) as f32) as usize;
+                let ch = if contact.palm {
+                    'P'
+                } else {
+                    char::from_digit(contact.slot as u32, 10).unwrap_or('*')
+                };
+                grid[row.min(inner.height as usize - 1)][col.min(inner.width as usize - 1)] = ch;
+            }
+        }
+
+        let lines: Vec<Line> = grid
+            .into_iter()
+            .map(|row| Line::from(row.into_iter().collect::<String>()))
+            .collect();
+
+        frame.render_widget(
+            Paragraph::new(lines).style(Style::default().fg(Color::Yellow).bold()),
+            inner,
+        );
+    }
+
+    fn draw_stats(&self, frame: &mut Frame, area: Rect) {
+        let mut lines = vec![
+            Line::from(vec![
+                "Fingers: ".into(),
+                format!("{}", self.contacts.len()).yellow(),
+                "  Max: ".into(),
+                format!("{}", self.max_fingers).yellow(),
+            ]),
+            Line::from(vec!["Clicks: ".into(), format!("{}", self.clicks).green()]),
+            Line::from(vec!["Taps: ".into(), format!("{}", self.taps).green()]),
+            Line::from(vec![
+                "Palm events: ".into(),
+                format!("{}", self.palm_events).red(),
+            ]),
+            Line::from(vec![
+                "Stuck slots cleared: ".into(),
+                format!("{}", self.cleared_slots).red(),
+            ]),
+            Line::from(""),
+            Line::from("Samples per slot:".bold()),
+        ];
+
+        for (slot, samples) in self.slot_samples.iter().enumerate() {
+            if *samples > 0 {
+                lines.push(Line::from(format!("  {slot}: {samples}")));
+            }
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Statistics ")),
+            area,
+        );
+    }
+}
+
+impl Default for TrackpadTestScreen {
+    fn default() -> Self {
+        TrackpadTestScreen::new()
+    }
+}
+
+impl Screen for TrackpadTestScreen {
+    fn id(&self) -> ScreenId {
+        ScreenId::TrackpadTest
+    }
+
+    fn draw(&self, frame: &mut Frame) {
+        let area = frame.area();
+
+        let device = self
+            .device
+            .as_ref()
+            .map_or("(select a trackpad)".to_string(), |info| info.name.clone());
+        let mut title = vec![
+            " Trackpad Test ".bold().cyan(),
+            format!("| {device} ").into(),
+        ];
+        if self.device.is_some() {
+            title.extend(["| ".into(), self.timer.label(), " ".into()]);
+        }
+        let title = Line::from(title);
+        let footer = if self.device.is_none() {
+            Line::from(vec![
+                " ↑/↓".bold().yellow(),
+                " navigate   ".into(),
+                "Enter".bold().yellow(),
+                " select   ".into(),
+                "Q/Esc".bold().yellow(),
+                " exit   ".into(),
+                "F1".bold().yellow(),
+                " help ".into(),
+            ])
+        } else {
+            Line::from(vec![
+                " R".bold().yellow(),
+                " reset   ".into(),
+                "C".bold().yellow(),
+                " clear fingers   ".into(),
+                "D".bold().yellow(),
+                " device   ".into(),
+                "Q/Esc".bold().yellow(),
+                " exit   ".into(),
+                "F1".bold().yellow(),
+                " help ".into(),
+            ])
+        };
+
+        let block = Block::bordered()
+            .title(title.centered())
+            .title_bottom(footer.centered())
+            .border_set(border::THICK);
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        if self.device.is_none() {
+            self.draw_device_selection(frame, inner);
+        } else {
+            let [pad, stats] =
+                Layout::horizontal([Constraint::Min(20), Constraint::Length(28)]).areas(inner);
+            self.draw_pad(frame, pad);
+            self.draw_stats(frame, stats);
+        }
+        self.touch_buttons().draw(frame);
+    }
+
+    fn handle_event(&mut self, event: AppEvent) -> Nav {
+        let selecting = self.device.is_none();
+        match event {
+            AppEvent::Key { code, ref info, .. } => match code {
+                KeyCode::KEY_ESC | KeyCode::KEY_Q => return Nav::To(ScreenId::Home),
+                // Clicks of other pads and mice don't count
+                KeyCode::BTN_LEFT | KeyCode::BTN_RIGHT | KeyCode::BTN_MIDDLE
+                    if self.is_selected(info) =>
+                {
+                    self.clicks += 1;
+                }
+                KeyCode::KEY_UP if selecting && !self.available_devices.is_empty() => {
+                    let count = self.available_devices.len();
+                    self.selected_index = (self.selected_index + count - 1) % count;
+                }
+                KeyCode::KEY_DOWN if selecting => self.select_next_device(),
+                KeyCode::KEY_ENTER | KeyCode::KEY_KPENTER if selecting => {
+                    self.select_device(self.selected_index);
+                }
+                KeyCode::KEY_R if !selecting => self.reset(),
+                KeyCode::KEY_C if !selecting => self.clear_slots(|_| true),
+                KeyCode::KEY_D if !selecting => self.deselect_device(),
+                code if selecting => {
+                    // Quick select by number
+                    if let Some(index) = digit_index(code) {
+                        self.select_device(index);
+                    }
+                }
+                _ => {}
+            },
+            AppEvent::Trackpad {
+                contacts,
+                touching,
+                info,
+                ..
+            } => {
+                if selecting {
+                    if !self.available_devices.iter().any(|d| d.path == info.path) {
+                        self.available_devices.push(info);
+                    }
+                } else if self.is_selected(&info) {
+                    self.handle_contacts(contacts, touching, info);
+                }
+            }
+            AppEvent::Tick => {
+                self.power.tick();
+                self.clear_stuck_slots();
+            }
+            AppEvent::Touch { .. } => {
+                let buttons = if selecting {
+                    &mut self.selection_buttons
+                } else {
+                    &mut self.test_buttons
+                };
+                match buttons.handle_raw_touch(&event) {
+                    Some(TouchAction::Back) => return Nav::To(ScreenId::Home),
+                    Some(TouchAction::Next) => self.select_next_device(),
+                    Some(TouchAction::Select) => self.select_device(self.selected_index),
+                    Some(TouchAction::Reset) => self.reset(),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        Nav::Stay
+    }
+
+    fn help(&self) -> Vec<(&'static str, &'static str)> {
+        if self.device.is_none() {
+            return vec![
+                ("↑/↓", "Select a trackpad"),
+                ("Enter", "Test the selected trackpad"),
+                ("1-9", "Quick select a trackpad"),
+                ("Q/Esc, tap Back", "Back to the home menu"),
+                (
+                    "Tap Next/Select",
+                    "Same as ↓ and Enter, touch the pad first to list it",
+                ),
+            ];
+        }
+
+        vec![
+            ("Fingers", "Shown on the pad by slot number, P for a palm"),
+            ("Click", "Physical clicks are counted"),
+            ("Tap", "Short single-finger taps are counted"),
+            ("R, tap Reset", "Reset all counters"),
+            (
+                "C",
+                "Clear all fingers, for slots stuck after a missed lift",
+            ),
+            ("D", "Back to the trackpad selection"),
+            ("Q/Esc, tap Back", "Back to the home menu"),
+        ]
+    }
+
+    fn help_text(&self) -> &'static str {
+        "Touch the pad to test so it's listed and select it; other pads are ignored from then on. \
+         Move one and then several fingers over the trackpad and check that every finger shows up \
+         where it touches. Click and tap a few times and check that the counters follow. Fingers \
+         left on the pad after lifting are cleared after 2 s, or right away with C."
+    }
+
+    fn take_results(&mut self) -> Option<ScreenResult> {
+        // Nothing was measured before a pad was selected
+        self.device.as_ref()?;
+        Some(ScreenResult::Trackpad(TrackpadResult {
+            device: self.device.as_ref().map(|info| info.name.clone()),
+            max_fingers: self.max_fingers,
+            clicks: self.clicks,
+            taps: self.taps,
+            slot_samples: self.slot_samples.to_vec(),
+            palm_events: self.palm_events,
+            cleared_slots: self.cleared_slots,
+            power: self.power.result(),
+            timing: Some(self.timer.timing()),
+        }))
+    }
+}
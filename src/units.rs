@@ -0,0 +1,133 @@
+//! Conversion of calibrated touch distances (0-999 on both axes) into
+//! millimetres on the physical panel.
+
+/// Millimetres per calibrated unit on each axis
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MmScale {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl MmScale {
+    /// From the device's axis resolution in raw units per millimetre and the
+    /// calibration's scale in calibrated units per raw unit. None when either
+    /// is missing or zero.
+    pub fn from_resolution(resolution: (i32, i32), calibrated_per_raw: (f32, f32)) -> Option<Self> {
+        let per_unit =
+            |res: i32, scale: f32| (res > 0 && scale > 0.0).then(|| 1.0 / (res as f32 * scale));
+        Some(MmScale {
+            x: per_unit(resolution.0, calibrated_per_raw.0)?,
+            y: per_unit(resolution.1, calibrated_per_raw.1)?,
+        })
+    }
+
+    /// From the physical panel size, assuming the calibrated range spans the panel
+    pub fn from_panel_size(panel_mm: (f32, f32), calibrated_max: (u16, u16)) -> Self {
+        MmScale {
+            x: panel_mm.0 / calibrated_max.0.max(1) as f32,
+            y: panel_mm.1 / calibrated_max.1.max(1) as f32,
+        }
+    }
+
+    /// Length in mm of a calibrated (dx, dy) step
+    pub fn distance_mm(&self, dx: f32, dy: f32) -> f32 {
+        let (x, y) = (dx * self.x, dy * self.y);
+        (x * x + y * y).sqrt()
+    }
+
+    /// Length in mm of a calibrated distance without a direction, e.g. a
+    /// threshold. Uses the mean of both axes, so it's approximate when the
+    /// panel's units aren't square.
+    pub fn length_mm(&self, units: f32) -> f32 {
+        units * (self.x + self.y) / 2.0
+    }
+}
+
+/// "42" or "42 (4.2 mm)" depending on whether a scale is known
+pub fn format_distance(units: f32, mm: Option<f32>) -> String {
+    match mm {
+        Some(mm) => format!("{units:.0} ({mm:.1} mm)"),
+        None => format!("{units:.0}"),
+    }
+}
+
+/// Parse a panel size given as "217x136" (width x height in mm)
+pub fn parse_panel_size(text: &str) -> Option<(f32, f32)> {
+    let (width, height) = text.split_once(['x', 'X'])?;
+    let width: f32 = width.trim().parse().ok()?;
+    let height: f32 = height.trim().parse().ok()?;
+    let valid = |mm: f32| mm > 0.0 && mm.is_finite();
+    (valid(width) && valid(height)).then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn a_resolution_gives_mm_per_calibrated_unit() {
+        // 10 raw units per mm, 0.25 calibrated units per raw unit: 2.5 per mm
+        let scale = MmScale::from_resolution((10, 20), (0.25, 0.25)).unwrap();
+        assert!(close(scale.x, 0.4));
+        assert!(close(scale.y, 0.2));
+
+        // Devices that report no resolution, or a calibration without a range
+        assert_eq!(MmScale::from_resolution((0, 20), (0.25, 0.25)), None);
+        assert_eq!(MmScale::from_resolution((10, -1), (0.25, 0.25)), None);
+        assert_eq!(MmScale::from_resolution((10, 20), (0.0, 0.25)), None);
+        assert_eq!(MmScale::from_resolution((10, 20), (0.25, -0.5)), None);
+    }
+
+    #[test]
+    fn a_panel_size_spans_the_calibrated_range() {
+        let scale = MmScale::from_panel_size((217.0, 136.0), (999, 999));
+        assert!(close(scale.x * 999.0, 217.0));
+        assert!(close(scale.y * 999.0, 136.0));
+
+        // A zero range doesn't divide by zero
+        let scale = MmScale::from_panel_size((217.0, 136.0), (0, 0));
+        assert_eq!(scale, MmScale { x: 217.0, y: 136.0 });
+    }
+
+    #[test]
+    fn distances_follow_each_axis_and_lengths_their_mean() {
+        let scale = MmScale { x: 0.2, y: 0.1 };
+        // 3 mm across, 4 mm down
+        assert!(close(scale.distance_mm(15.0, 40.0), 5.0));
+        assert!(close(scale.distance_mm(-15.0, -40.0), 5.0));
+        assert_eq!(scale.distance_mm(0.0, 0.0), 0.0);
+        assert!(close(scale.length_mm(10.0), 1.5));
+    }
+
+    #[test]
+    fn distances_show_mm_when_known() {
+        assert_eq!(format_distance(42.4, Some(4.24)), "42 (4.2 mm)");
+        assert_eq!(format_distance(42.6, None), "43");
+    }
+
+    #[test]
+    fn panel_sizes_are_width_x_height() {
+        assert_eq!(parse_panel_size("217x136"), Some((217.0, 136.0)));
+        assert_eq!(parse_panel_size("217X136"), Some((217.0, 136.0)));
+        assert_eq!(parse_panel_size(" 217.5 x 136 "), Some((217.5, 136.0)));
+
+        for bad in [
+            "",
+            "217",
+            "217x",
+            "x136",
+            "axb",
+            "217x136x2",
+            "0x136",
+            "-1x2",
+            "infx136",
+            "NaNx136",
+        ] {
+            assert_eq!(parse_panel_size(bad), None, "{bad:?}");
+        }
+    }
+}
@@ -0,0 +1,172 @@
+//! First touch after idle. Controllers that power-save lose or delay the first
+//! touch after a quiet period, reported as "the first tap doesn't register".
+//! A touch-down more than `IDLE_GAP` after the device's previous event is a
+//! wake touch. For every touch-down the time to the touch's next sample is
+//! kept in a histogram, wake touches apart from the others, and a touch-down
+//! far from where the following samples settle means the first samples of the
+//! touch were lost while the controller woke up.
+
+use crate::report::WakeResult;
+
+/// Silence after which a touch-down counts as waking the controller
+pub const IDLE_GAP_MS: u128 = 5000;
+
+/// Upper bounds of the histogram buckets in ms, the last bucket is open
+pub const BUCKET_BOUNDS_MS: [u32; 6] = [10, 20, 50, 100, 200, 500];
+
+// Samples after the touch-down averaged for where the finger settled
+const SETTLE_SAMPLES: std::ops::Range<usize> = 2..5;
+
+// Touch-down this far from the settled position lost its start, calibrated units
+const MISSED_START_DISTANCE: f32 = 50.0;
+
+// Wake touches needed before the device is flagged
+const MIN_WAKES: u32 = 2;
+
+/// Time to the second sample and start position of the touch that is down
+struct PendingTouch {
+    wake: bool,
+    down_ms: u128,
+    // Calibrated samples since the touch-down, the touch-down first
+    samples: Vec<(u16, u16)>,
+    first_interval: Option<u128>,
+}
+
+/// Counts of one kind of touch-down, wake or normal
+#[derive(Debug, Clone, Default)]
+struct TouchKind {
+    touches: u32,
+    histogram: [u32; BUCKET_BOUNDS_MS.len() + 1],
+    missed_starts: u32,
+    worst_interval: Option<u128>,
+    worst_offset: f32,
+}
+
+impl TouchKind {
+    fn record(&mut self, interval: Option<u128>, offset: Option<f32>) {
+        self.touches += 1;
+        if let Some(interval) = interval {
+            let bucket = BUCKET_BOUNDS_MS
+                .iter()
+                .position(|bound| interval < *bound as u128)
+                .unwrap_or(BUCKET_BOUNDS_MS.len());
+            self.histogram[bucket] += 1;
+            self.worst_interval = self.worst_interval.max(Some(interval));
+        }
+        if let Some(offset) = offset {
+            if offset > MISSED_START_DISTANCE {
+                self.missed_starts += 1;
+            }
+            self.worst_offset = self.worst_offset.max(offset);
+        }
+    }
+
+    fn missed_share(&self) -> f32 {
+        self.missed_starts as f32 / self.touches.max(1) as f32
+    }
+}
+
+/// Wake and normal touch-downs of the selected device
+#[derive(Default)]
+pub struct WakeLatency {
+    last_event_ms: Option<u128>,
+    pending: Option<PendingTouch>,
+    wake: TouchKind,
+    normal: TouchKind,
+}
+
+impl WakeLatency {
+    pub fn new() -> Self {
+        WakeLatency::default()
+    }
+
+    /// Feed one calibrated sample, not the keep-alive repeats
+    pub fn observe(&mut self, x: u16, y: u16, released: bool, timestamp: u128) {
+        let gap = self
+            .last_event_ms
+            .map(|last| timestamp.saturating_sub(last));
+        self.last_event_ms = Some(timestamp);
+
+        if released {
+            self.finish_touch();
+            return;
+        }
+
+        match &mut self.pending {
+            Some(touch) => {
+                if touch.first_interval.is_none() {
+                    touch.first_interval = Some(timestamp.saturating_sub(touch.down_ms));
+                }
+                if touch.samples.len() < SETTLE_SAMPLES.end {
+                    touch.samples.push((x, y));
+                }
+            }
+            None => {
+                self.pending = Some(PendingTouch {
+                    // The very first touch has nothing to be idle after
+                    wake: gap.is_some_and(|gap| gap > IDLE_GAP_MS),
+                    down_ms: timestamp,
+                    samples: vec![(x, y)],
+                    first_interval: None,
+                });
+            }
+        }
+    }
+
+    /// Count the touch that just lifted
+    fn finish_touch(&mut self) {
+        let Some(touch) = self.pending.take() else {
+            return;
+        };
+        // Only touches long enough to settle tell whether the start was lost
+        let offset = touch.samples.get(SETTLE_SAMPLES).map(|settled| {
+            let count = settled.len() as f32;
+            let x = settled.iter().map(|p| p.0 as f32).sum::<f32>() / count;
+            let y = settled.iter().map(|p| p.1 as f32).sum::<f32>() / count;
+            let (down_x, down_y) = touch.samples[0];
+            ((down_x as f32 - x).powi(2) + (down_y as f32 - y).powi(2)).sqrt()
+        });
+        let kind = if touch.wake {
+            &mut self.wake
+        } else {
+            &mut self.normal
+        };
+        kind.record(touch.first_interval, offset);
+    }
+
+    /// Wake touches lose their start in most cases, and more often than other touches do
+    pub fn flagged(&self) -> bool {
+        self.wake.touches >= MIN_WAKES
+            && self.wake.missed_share() > 0.5
+            && self.wake.missed_share() > self.normal.missed_share()
+    }
+
+    pub fn wakes(&self) -> u32 {
+        self.wake.touches
+    }
+
+    /// Longest time from a wake touch-down to its next sample, in ms
+    pub fn worst_wake_interval(&self) -> Option<u128> {
+        self.wake.worst_interval
+    }
+
+    pub fn reset(&mut self) {
+        *self = WakeLatency::default();
+    }
+
+    pub fn result(&self) -> WakeResult {
+        WakeResult {
+            idle_gap_ms: IDLE_GAP_MS as u32,
+            bucket_bounds_ms: BUCKET_BOUNDS_MS.to_vec(),
+            wake_touches: self.wake.touches,
+            wake_histogram: self.wake.histogram.to_vec(),
+            wake_missed_starts: self.wake.missed_starts,
+            normal_touches: self.normal.touches,
+            normal_histogram: self.normal.histogram.to_vec(),
+            normal_missed_starts: self.normal.missed_starts,
+            worst_wake_ms: self.wake.worst_interval.map(|ms| ms as u32),
+            worst_wake_offset: self.wake.worst_offset,
+            flagged: self.flagged(),
+        }
+    }
+}
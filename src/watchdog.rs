@@ -0,0 +1,201 @@
+//! Detects a wedged event pipeline: ticks keep being sent but the UI stops
+//! receiving anything, e.g. because a driver blocked `fetch_events` while
+//! something held up the channel. Instead of a silent freeze, a diagnostic dump
+//! goes to the crash log, and optionally the tool restores the terminal and exits.
+//!
+//! The tick thread, the device readers and the run loop stamp a shared heartbeat,
+//! a monitor thread compares the stamps.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::Receiver;
+use once_cell::sync::Lazy;
+
+use crate::event_handler::{AppEvent, DeviceInfo};
+use crate::listener_health;
+use crate::logging;
+use crate::writer::{self, WriterHandle};
+
+/// Nothing received for this long while ticks are still sent counts as a stall
+const STALL_TIMEOUT: Duration = Duration::from_secs(5);
+const CHECK_INTERVAL: Duration = Duration::from_secs(1);
+// Give the writer thread a moment to land the dump before exiting
+const DUMP_GRACE: Duration = Duration::from_millis(500);
+
+static HEARTBEAT: Lazy<Heartbeat> = Lazy::new(Heartbeat::new);
+
+struct Heartbeat {
+    start: Instant,
+    // Milliseconds since `start`, 0 for never
+    last_tick_sent: AtomicU64,
+    last_received: AtomicU64,
+    // Name and last time each event source produced something, by path
+    sources: Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl Heartbeat {
+    fn new() -> Self {
+        Heartbeat {
+            start: Instant::now(),
+            last_tick_sent: AtomicU64::new(0),
+            last_received: AtomicU64::new(0),
+            sources: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn now_ms(&self) -> u64 {
+        self.start.elapsed().as_millis() as u64 + 1
+    }
+}
+
+/// The tick thread sent a tick
+pub fn tick_sent() {
+    HEARTBEAT
+        .last_tick_sent
+        .store(HEARTBEAT.now_ms(), Ordering::Relaxed);
+}
+
+/// The run loop received an event
+pub fn received() {
+    HEARTBEAT
+        .last_received
+        .store(HEARTBEAT.now_ms(), Ordering::Relaxed);
+}
+
+/// A device reader got events from its device
+pub fn device_active(info: &DeviceInfo) {
+    let Ok(mut sources) = HEARTBEAT.sources.lock() else {
+        return;
+    };
+    // Called for every batch a reader gets, only a new source is copied
+    match sources.get_mut(&info.path) {
+        Some((_, last)) => *last = Instant::now(),
+        None => {
+            sources.insert(info.path.clone(), (info.name.clone(), Instant::now()));
+        }
+    }
+}
+
+/// What to do when the pipeline stalls
+pub struct WatchdogConfig {
+    /// Where the dump goes, besides stderr when exiting
+    pub log: Option<WriterHandle>,
+    /// Restore the terminal and exit instead of waiting for the pipeline to recover
+    pub exit_on_stall: bool,
+}
+
+/// Start the monitor thread. `rx` is only used to read the channel length.
+pub fn spawn(rx: Receiver<AppEvent>, config: WatchdogConfig) {
+    let monitor = thread::Builder::new().name("watchdog".to_string());
+    let _ = monitor.spawn(move || {
+        // Report each stall once, until events flow again
+        let mut reported = false;
+        loop {
+            thread::sleep(CHECK_INTERVAL);
+
+            let now = HEARTBEAT.now_ms();
+            let since = |stamp: &AtomicU64| match stamp.load(Ordering::Relaxed) {
+                0 => None,
+                stamp => Some(Duration::from_millis(now.saturating_sub(stamp))),
+            };
+            let (Some(tick_age), Some(receive_age)) = (
+                since(&HEARTBEAT.last_tick_sent),
+                since(&HEARTBEAT.last_received),
+            ) else {
+                continue;
+            };
+
+            // Ticks still going out but nothing coming in
+            let stalled = tick_age < STALL_TIMEOUT && receive_age >= STALL_TIMEOUT;
+            if !stalled {
+                reported = false;
+                continue;
+            }
+            if reported {
+                continue;
+            }
+            reported = true;
+
+            let dump = diagnostic_dump(receive_age, tick_age, rx.len());
+            if let Some(log) = &config.log {
+                log.append(&writer::CRASH_LOG, dump.clone());
+            }
+
+            if config.exit_on_stall {
+                thread::sleep(DUMP_GRACE);
+                ratatui::restore();
+                logging::warn(&format!(
+                    "The event pipeline stopped, exiting instead of hanging.\n{dump}"
+                ));
+                std::process::exit(1);
+            }
+        }
+    });
+}
+
+fn diagnostic_dump(receive_age: Duration, tick_age: Duration, channel_len: usize) -> String {
+    let mut dump = String::new();
+    let _ = writeln!(
+        dump,
+        "event pipeline stalled: nothing received for {:.1} s",
+        receive_age.as_secs_f32()
+    );
+    let _ = writeln!(
+        dump,
+        "  last tick sent: {:.1} s ago",
+        tick_age.as_secs_f32()
+    );
+    let _ = writeln!(dump, "  events queued: {channel_len}");
+
+    // State D (uninterruptible sleep) points at a thread stuck in a driver
+    let _ = writeln!(dump, "  threads (name, state):");
+    for (name, state) in thread_states() {
+        let _ = writeln!(dump, "    {name}: {state}");
+    }
+
+    let _ = writeln!(dump, "  last event per device:");
+    if let Ok(sources) = HEARTBEAT.sources.lock() {
+        let mut sources: Vec<_> = sources.iter().collect();
+        sources.sort();
+        for (path, (name, last)) in sources {
+            let _ = writeln!(
+                dump,
+                "    {path} ({name}): {:.1} s ago",
+                last.elapsed().as_secs_f32()
+            );
+        }
+    }
+
+    // A listener that exited looks the same as a device gone quiet above
+    let _ = writeln!(dump, "  listeners:");
+    for (path, status) in listener_health::all() {
+        let _ = writeln!(dump, "    {path} ({}): {}", status.name, status.describe());
+    }
+    dump
+}
+
+// Name and scheduler state of every thread of this process, from /proc
+fn thread_states() -> Vec<(String, String)> {
+    let Ok(tasks) = fs::read_dir("/proc/self/task") else {
+        return Vec::new();
+    };
+
+    let mut threads: Vec<(String, String)> = tasks
+        .filter_map(Result::ok)
+        .filter_map(|task| {
+            let name = fs::read_to_string(task.path().join("comm")).ok()?;
+            let stat = fs::read_to_string(task.path().join("stat")).ok()?;
+            // The state follows the parenthesized name, which may itself contain spaces
+            let state = stat.rsplit_once(')')?.1.split_whitespace().next()?;
+            Some((name.trim().to_string(), state.to_string()))
+        })
+        .collect();
+    threads.sort();
+    threads
+}
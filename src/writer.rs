@@ -0,0 +1,520 @@
+//! Background file writer shared by everything that logs or saves to disk, so the
+//! UI thread never blocks on a slow disk.
+//!
+//! Every write goes through one thread that appends to a file per topic in the
+//! output directory. When the queue backs up, low priority topics are dropped
+//! first so autosaves and crash logs still land. State files are replaced as a
+//! whole instead, through a temporary file so a power cut leaves the old one.
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use color_eyre::{Result, eyre::eyre};
+use crossbeam_channel::{Receiver, Sender, unbounded};
+
+/// Queued writes at which low priority topics start being dropped. Normal
+/// priority gets twice this and high priority four times.
+pub const DEFAULT_QUEUE_LIMIT: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Nice to have, dropped first (event log)
+    Low,
+    Normal,
+    /// Must land if at all possible (autosave, crash log)
+    High,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Leave it to the OS, files are still flushed whenever the queue runs empty
+    Never,
+    /// fsync after every append, for data that has to survive a power cut
+    EveryWrite,
+}
+
+/// A destination file and how it is written
+#[derive(Debug, Clone, Copy)]
+pub struct Topic {
+    /// File name without extension, the file is `<name>.<extension>` in the
+    /// output directory
+    pub name: &'static str,
+    /// "log" for the text logs, "json" for the state files
+    pub extension: &'static str,
+    pub priority: Priority,
+    pub fsync: FsyncPolicy,
+    /// Rotate once the file would grow past this, 0 never rotates
+    pub max_bytes: u64,
+    /// Rotated files kept as `<name>.1.<extension>` (newest) to `<name>.N.<extension>`
+    pub keep: usize,
+}
+
+/// Every input event, one line each
+pub const EVENT_LOG: Topic = Topic {
+    name: "events",
+    extension: "log",
+    priority: Priority::Low,
+    fsync: FsyncPolicy::Never,
+    max_bytes: 8 * 1024 * 1024,
+    keep: 3,
+};
+
+/// Device faults reported by the listeners, e.g. flooding keyboards
+pub const FAULT_LOG: Topic = Topic {
+    name: "faults",
+    extension: "log",
+    priority: Priority::Normal,
+    fsync: FsyncPolicy::Never,
+    max_bytes: 1024 * 1024,
+    keep: 1,
+};
+
+/// Test results as JSON lines the moment a test is left, so a crash or power
+/// cut before the report is written loses nothing
+pub const RESULT_LOG: Topic = Topic {
+    name: "results",
+    extension: "log",
+    priority: Priority::High,
+    fsync: FsyncPolicy::EveryWrite,
+    max_bytes: 0,
+    keep: 0,
+};
+
+/// Soak snapshots as JSON lines the moment they are taken, so a crash hours
+/// into a soak keeps the series so far
+pub const SOAK_LOG: Topic = Topic {
+    name: "soak",
+    extension: "log",
+    priority: Priority::High,
+    fsync: FsyncPolicy::EveryWrite,
+    max_bytes: 0,
+    keep: 0,
+};
+
+/// Diagnostic dumps written when the tool gets stuck, e.g. by the watchdog
+pub const CRASH_LOG: Topic = Topic {
+    name: "crash",
+    extension: "log",
+    priority: Priority::High,
+    fsync: FsyncPolicy::EveryWrite,
+    max_bytes: 0,
+    keep: 0,
+};
+
+/// The session so far as JSON, replaced after every finished test so an
+/// interrupted session can be resumed, removed when it ends cleanly
+pub const SESSION_STATE: Topic = Topic {
+    name: "session",
+    extension: "json",
+    priority: Priority::High,
+    fsync: FsyncPolicy::EveryWrite,
+    max_bytes: 0,
+    keep: 0,
+};
+
+impl Topic {
+    /// The topic's current file in `dir`
+    pub fn path(&self, dir: &Path) -> PathBuf {
+        dir.join(format!("{}.{}", self.name, self.extension))
+    }
+}
+
+enum Command {
+    Append {
+        topic: Topic,
+        bytes: Vec<u8>,
+    },
+    // None removes the file
+    Replace {
+        topic: Topic,
+        bytes: Option<Vec<u8>>,
+    },
+    Shutdown,
+}
+
+/// Cheap to clone, every thread that writes files gets its own
+#[derive(Clone)]
+pub struct WriterHandle {
+    tx: Sender<Command>,
+    limit: usize,
+    pending: Arc<AtomicUsize>,
+    dropped: Arc<Mutex<HashMap<&'static str, u64>>>,
+}
+
+impl WriterHandle {
+    /// Queue `bytes` to be appended to the topic's file. Returns false when the
+    /// write was dropped because the queue is over the topic's limit.
+    pub fn append(&self, topic: &Topic, bytes: impl Into<Vec<u8>>) -> bool {
+        let limit = match topic.priority {
+            Priority::Low => self.limit,
+            Priority::Normal => self.limit * 2,
+            Priority::High => self.limit * 4,
+        };
+
+        if self.pending.load(Ordering::Relaxed) >= limit {
+            self.count_drop(topic.name);
+            return false;
+        }
+
+        self.pending.fetch_add(1, Ordering::Relaxed);
+        let command = Command::Append {
+            topic: *topic,
+            bytes: bytes.into(),
+        };
+        if self.tx.send(command).is_err() {
+            // Writer already shut down
+            self.pending.fetch_sub(1, Ordering::Relaxed);
+            self.count_drop(topic.name);
+            return false;
+        }
+        true
+    }
+
+    /// Queue replacing the topic's file with `bytes`. Replacements are never
+    /// dropped for a full queue, there is only ever one file to write.
+    pub fn replace(&self, topic: &Topic, bytes: impl Into<Vec<u8>>) {
+        self.send_replace(topic, Some(bytes.into()));
+    }
+
+    /// Queue removing the topic's file
+    pub fn remove(&self, topic: &Topic) {
+        self.send_replace(topic, None);
+    }
+
+    fn send_replace(&self, topic: &Topic, bytes: Option<Vec<u8>>) {
+        let command = Command::Replace {
+            topic: *topic,
+            bytes,
+        };
+        if self.tx.send(command).is_err() {
+            self.count_drop(topic.name);
+        }
+    }
+
+    /// Writes dropped per topic so far, including failed writes
+    pub fn dropped(&self) -> Vec<(&'static str, u64)> {
+        let dropped = self.dropped.lock().unwrap();
+        let mut counts: Vec<_> = dropped.iter().map(|(name, n)| (*name, *n)).collect();
+        counts.sort();
+        counts
+    }
+
+    fn count_drop(&self, name: &'static str) {
+        *self.dropped.lock().unwrap().entry(name).or_insert(0) += 1;
+    }
+}
+
+/// Owner of the writer thread. Dropping it without `shutdown` leaves queued
+/// writes to the mercy of process exit.
+pub struct Writer {
+    handle: WriterHandle,
+    thread: JoinHandle<()>,
+}
+
+impl Writer {
+    /// Start the writer thread, creating `dir` if needed
+    pub fn spawn(dir: &Path, queue_limit: usize) -> Result<Self> {
+        fs::create_dir_all(dir)
+            .map_err(|e| eyre!("cannot create log directory {}: {e}", dir.display()))?;
+
+        let (tx, rx) = unbounded();
+        let handle = WriterHandle {
+            tx,
+            limit: queue_limit.max(1),
+            pending: Arc::new(AtomicUsize::new(0)),
+            dropped: Arc::new(Mutex::new(HashMap::new())),
+        };
+
+        let mut files = TopicFiles {
+            dir: dir.to_path_buf(),
+            open: HashMap::new(),
+        };
+        let pending = handle.pending.clone();
+        let dropped = handle.dropped.clone();
+        let thread = thread::spawn(move || files.run(&rx, &pending, &dropped));
+
+        Ok(Writer { handle, thread })
+    }
+
+    pub fn handle(&self) -> WriterHandle {
+        self.handle.clone()
+    }
+
+    /// Write everything queued so far, flush and fsync all files and stop the thread
+    pub fn shutdown(self) {
+        // Commands are processed in order, so everything queued before this lands
+        let _ = self.handle.tx.send(Command::Shutdown);
+        let _ = self.thread.join();
+    }
+}
+
+struct OpenFile {
+    out: BufWriter<File>,
+    size: u64,
+}
+
+struct TopicFiles {
+    dir: PathBuf,
+    open: HashMap<&'static str, OpenFile>,
+}
+
+impl TopicFiles {
+    fn run(
+        &mut self,
+        rx: &Receiver<Command>,
+        pending: &AtomicUsize,
+        dropped: &Mutex<HashMap<&'static str, u64>>,
+    ) {
+        while let Ok(command) = rx.recv() {
+            match command {
+                Command::Append { topic, bytes } => {
+                    pending.fetch_sub(1, Ordering::Relaxed);
+                    if self.append(&topic, &bytes).is_err() {
+                        *dropped.lock().unwrap().entry(topic.name).or_insert(0) += 1;
+                    }
+                }
+                Command::Replace { topic, bytes } => {
+                    if self.replace(&topic, bytes.as_deref()).is_err() {
+                        *dropped.lock().unwrap().entry(topic.name).or_insert(0) += 1;
+                    }
+                }
+                Command::Shutdown => break,
+            }
+
+            // Keep the files readable while the session runs, without a flush per line
+            if rx.is_empty() {
+                self.flush(false);
+            }
+        }
+
+        self.flush(true);
+    }
+
+    fn path(&self, topic: &Topic, index: usize) -> PathBuf {
+        let (name, extension) = (topic.name, topic.extension);
+        match index {
+            0 => self.dir.join(format!("{name}.{extension}")),
+            n => self.dir.join(format!("{name}.{n}.{extension}")),
+        }
+    }
+
+    fn append(&mut self, topic: &Topic, bytes: &[u8]) -> std::io::Result<()> {
+        let needs_rotation = self.open.get(topic.name).is_some_and(|file| {
+            topic.max_bytes > 0 && file.size > 0 && file.size + bytes.len() as u64 > topic.max_bytes
+        });
+        if needs_rotation {
+            self.rotate(topic)?;
+        }
+
+        if !self.open.contains_key(topic.name) {
+            let path = self.path(topic, 0);
+            let file = OpenOptions::new().create(true).append(true).open(&path)?;
+            let size = file.metadata()?.len();
+            self.open.insert(
+                topic.name,
+                OpenFile {
+                    out: BufWriter::new(file),
+                    size,
+                },
+            );
+        }
+
+        let file = self.open.get_mut(topic.name).unwrap();
+        file.out.write_all(bytes)?;
+        file.size += bytes.len() as u64;
+
+        if topic.fsync == FsyncPolicy::EveryWrite {
+            file.out.flush()?;
+            file.out.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn replace(&mut self, topic: &Topic, bytes: Option<&[u8]>) -> std::io::Result<()> {
+        self.open.remove(topic.name);
+        let path = self.path(topic, 0);
+
+        let Some(bytes) = bytes else {
+            return match fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        };
+
+        let temporary = path.with_extension(format!("{}.tmp", topic.extension));
+        let mut file = File::create(&temporary)?;
+        file.write_all(bytes)?;
+        if topic.fsync == FsyncPolicy::EveryWrite {
+            file.sync_data()?;
+        }
+        fs::rename(&temporary, &path)
+    }
+
+    // Shift <name>.log → <name>.1.log → ... dropping the oldest beyond `keep`
+    fn rotate(&mut self, topic: &Topic) -> std::io::Result<()> {
+        if let Some(mut file) = self.open.remove(topic.name) {
+            file.out.flush()?;
+        }
+
+        if topic.keep == 0 {
+            return fs::remove_file(self.path(topic, 0));
+        }
+
+        let _ = fs::remove_file(self.path(topic, topic.keep));
+        for index in (0..topic.keep).rev() {
+            let from = self.path(topic, index);
+            if from.exists() {
+                fs::rename(&from, self.path(topic, index + 1))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self, sync: bool) {
+        for file in self.open.values_mut() {
+            let _ = file.out.flush();
+            if sync {
+                let _ = file.out.get_ref().sync_all();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An empty directory of its own for each test
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "keyboard_test-writer-{name}-{}",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    const SMALL: Topic = Topic {
+        name: "small",
+        extension: "log",
+        priority: Priority::Low,
+        fsync: FsyncPolicy::Never,
+        max_bytes: 100,
+        keep: 2,
+    };
+
+    /// A handle whose queue is never worked off, for the backpressure
+    fn stalled(limit: usize) -> (WriterHandle, Receiver<Command>) {
+        let (tx, rx) = unbounded();
+        let handle = WriterHandle {
+            tx,
+            limit,
+            pending: Arc::new(AtomicUsize::new(0)),
+            dropped: Arc::new(Mutex::new(HashMap::new())),
+        };
+        (handle, rx)
+    }
+
+    #[test]
+    fn appends_land_in_order() {
+        let dir = scratch("order");
+        let writer = Writer::spawn(&dir, DEFAULT_QUEUE_LIMIT).unwrap();
+        let handle = writer.handle();
+        let lines: Vec<String> = (0..1000).map(|i| format!("line {i}\n")).collect();
+        for line in &lines {
+            assert!(handle.append(&EVENT_LOG, line.as_str()));
+        }
+        writer.shutdown();
+
+        let written = fs::read_to_string(EVENT_LOG.path(&dir)).unwrap();
+        assert_eq!(written, lines.concat());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn shutdown_flushes_what_was_queued() {
+        let dir = scratch("shutdown");
+        let writer = Writer::spawn(&dir, DEFAULT_QUEUE_LIMIT).unwrap();
+        let handle = writer.handle();
+        // Neither topic syncs per write, the buffers only go out on flush
+        handle.append(&FAULT_LOG, "fault\n");
+        handle.append(&EVENT_LOG, "event\n");
+        handle.replace(&SESSION_STATE, "{}");
+        writer.shutdown();
+
+        assert_eq!(fs::read_to_string(FAULT_LOG.path(&dir)).unwrap(), "fault\n");
+        assert_eq!(fs::read_to_string(EVENT_LOG.path(&dir)).unwrap(), "event\n");
+        assert_eq!(fs::read_to_string(dir.join("session.json")).unwrap(), "{}");
+
+        // Nothing to write to any more, counted as dropped
+        assert!(!handle.append(&EVENT_LOG, "late\n"));
+        assert_eq!(handle.dropped(), [("events", 1)]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn files_rotate_at_the_size_limit() {
+        let dir = scratch("rotation");
+        let writer = Writer::spawn(&dir, DEFAULT_QUEUE_LIMIT).unwrap();
+        let handle = writer.handle();
+        // 10 bytes each, ten to a file, the last one half full
+        let lines: Vec<String> = (0..45).map(|i| format!("line {i:04}\n")).collect();
+        for line in &lines {
+            handle.append(&SMALL, line.as_str());
+        }
+        writer.shutdown();
+
+        let read = |index: usize| {
+            let path = match index {
+                0 => dir.join("small.log"),
+                n => dir.join(format!("small.{n}.log")),
+            };
+            fs::read_to_string(path)
+        };
+        // The oldest beyond `keep` are gone, the rest still reads in order
+        assert!(read(3).is_err());
+        let kept = [read(2), read(1), read(0)].map(Result::unwrap);
+        assert!(kept.iter().all(|file| file.len() as u64 <= SMALL.max_bytes));
+        assert_eq!(kept.concat(), lines[20..].concat());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn low_priority_is_dropped_first() {
+        let (handle, _rx) = stalled(2);
+        let low: Vec<bool> = (0..3).map(|_| handle.append(&EVENT_LOG, "e")).collect();
+        assert_eq!(low, [true, true, false]);
+        let normal: Vec<bool> = (0..3).map(|_| handle.append(&FAULT_LOG, "f")).collect();
+        assert_eq!(normal, [true, true, false]);
+        let high: Vec<bool> = (0..5).map(|_| handle.append(&CRASH_LOG, "c")).collect();
+        assert_eq!(high, [true, true, true, true, false]);
+        assert_eq!(
+            handle.dropped(),
+            [("crash", 1), ("events", 1), ("faults", 1)]
+        );
+    }
+
+    #[test]
+    fn replace_swaps_the_whole_file() {
+        let dir = scratch("replace");
+        let writer = Writer::spawn(&dir, DEFAULT_QUEUE_LIMIT).unwrap();
+        let handle = writer.handle();
+        handle.replace(&SESSION_STATE, "first");
+        handle.replace(&SESSION_STATE, "second");
+        writer.shutdown();
+        let path = SESSION_STATE.path(&dir);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+        assert!(!path.with_extension("json.tmp").exists());
+
+        let writer = Writer::spawn(&dir, DEFAULT_QUEUE_LIMIT).unwrap();
+        writer.handle().remove(&SESSION_STATE);
+        writer.shutdown();
+        assert!(!path.exists());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
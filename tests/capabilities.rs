@@ -0,0 +1,21 @@
+//! `--print-capabilities` run as the imaging pipeline runs it: the binary
+//! prints JSON and exits without touching the terminal or any device.
+
+use std::process::Command;
+
+use input_device_test::capabilities::Capabilities;
+
+#[test]
+fn the_binary_prints_the_collected_capabilities() {
+    let output = Command::new(env!("CARGO_BIN_EXE_input_device_test"))
+        .arg("--print-capabilities")
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+
+    let printed: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    let collected = serde_json::to_value(Capabilities::collect()).unwrap();
+    assert_eq!(printed, collected);
+    assert!(!printed["layouts"].as_array().unwrap().is_empty());
+    assert!(!printed["models"].as_array().unwrap().is_empty());
+}
@@ -0,0 +1,419 @@
+//! End-to-end tests of the device listeners against virtual devices. Each test
+//! creates its devices through uinput, scopes `spawn_device_listeners` to them
+//! by name, replays an event sequence and compares what arrives on the channel.
+//! They need /dev/uinput and the rights to use it, so they only run on demand:
+//! `cargo test --test uinput -- --ignored` as root.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam_channel::{Receiver, unbounded};
+use evdev::uinput::VirtualDevice;
+use evdev::{
+    AbsInfo, AbsoluteAxisCode, AttributeSet, EventType, InputEvent, KeyCode, PropType,
+    RelativeAxisCode, UinputAbsSetup,
+};
+
+use input_device_test::RateLimit;
+use input_device_test::event_handler::{self, AppEvent, DeviceInfo, DeviceSource};
+
+// Longest wait for an event, hotplug scans every 2 s
+const RECV_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn key(code: KeyCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::KEY.0, code.0, value)
+}
+
+fn abs(code: AbsoluteAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::ABSOLUTE.0, code.0, value)
+}
+
+fn rel(code: RelativeAxisCode, value: i32) -> InputEvent {
+    InputEvent::new(EventType::RELATIVE.0, code.0, value)
+}
+
+/// A direct-touch panel with a 0-4095 range, a pen digitizer with `pen`
+fn touch_panel(name: &str, pen: bool) -> VirtualDevice {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    keys.insert(KeyCode::BTN_TOUCH);
+    if pen {
+        keys.insert(KeyCode::BTN_TOOL_PEN);
+    }
+    let mut props = AttributeSet::<PropType>::new();
+    props.insert(PropType::DIRECT);
+    let range = AbsInfo::new(0, 0, 4095, 0, 0, 0);
+    VirtualDevice::builder()
+        .expect("/dev/uinput")
+        .name(name)
+        .with_keys(&keys)
+        .unwrap()
+        .with_properties(&props)
+        .unwrap()
+        .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisCode::ABS_X, range))
+        .unwrap()
+        .with_absolute_axis(&UinputAbsSetup::new(AbsoluteAxisCode::ABS_Y, range))
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+fn mouse(name: &str) -> VirtualDevice {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    keys.insert(KeyCode::BTN_LEFT);
+    let mut axes = AttributeSet::<RelativeAxisCode>::new();
+    axes.insert(RelativeAxisCode::REL_X);
+    axes.insert(RelativeAxisCode::REL_Y);
+    VirtualDevice::builder()
+        .expect("/dev/uinput")
+        .name(name)
+        .with_keys(&keys)
+        .unwrap()
+        .with_relative_axes(&axes)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+fn keyboard(name: &str) -> VirtualDevice {
+    let mut keys = AttributeSet::<KeyCode>::new();
+    keys.insert(KeyCode::KEY_A);
+    keys.insert(KeyCode::KEY_B);
+    VirtualDevice::builder()
+        .expect("/dev/uinput")
+        .name(name)
+        .with_keys(&keys)
+        .unwrap()
+        .build()
+        .unwrap()
+}
+
+/// Wait for udev to create the device's event node
+fn wait_for_node(device: &mut VirtualDevice) {
+    let deadline = Instant::now() + RECV_TIMEOUT;
+    while Instant::now() < deadline {
+        let nodes = device.enumerate_dev_nodes_blocking().unwrap();
+        if nodes.flatten().any(|path| path.exists()) {
+            return;
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+    panic!("no event node for the virtual device");
+}
+
+/// Listeners for the devices whose name starts with `prefix` only
+fn listen(prefix: &'static str) -> Receiver<AppEvent> {
+    listen_with_keepalive(prefix, None)
+}
+
+/// `listen`, repeating resting touches every `keepalive`
+fn listen_with_keepalive(prefix: &'static str, keepalive: Option<Duration>) -> Receiver<AppEvent> {
+    let (tx, rx) = unbounded();
+    let source = DeviceSource::system().with_filter(move |info| info.name.starts_with(prefix));
+    event_handler::spawn_device_listeners(&tx, source, RateLimit::default(), keepalive, false)
+        .expect("listeners");
+    // Past the listeners' stagger, so the first events aren't raced
+    thread::sleep(Duration::from_millis(300));
+    rx
+}
+
+/// The event in a form that compares, without the timestamps. None for the
+/// ticks, which come whenever they come.
+fn describe(event: &AppEvent) -> Option<String> {
+    let name = |info: &DeviceInfo| info.name.clone();
+    Some(match event {
+        AppEvent::Tick => return None,
+        AppEvent::Key {
+            code, info, repeat, ..
+        } => format!("{} key {code:?} repeat={repeat}", name(info)),
+        AppEvent::KeyReleased { code, info, .. } => format!("{} release {code:?}", name(info)),
+        AppEvent::Mouse { x, y, info } => format!("{} mouse {x},{y}", name(info)),
+        AppEvent::Touch {
+            x,
+            y,
+            released,
+            info,
+            keepalive,
+            ..
+        } => format!(
+            "{} touch {x},{y} released={released} keepalive={keepalive}",
+            info.as_deref().map(name).unwrap_or_default()
+        ),
+        AppEvent::PenHover { x, y, left, info } => {
+            format!("{} hover {x},{y} left={left}", name(info))
+        }
+        AppEvent::DeviceAdded { info } => format!("{} added", name(info)),
+        AppEvent::DeviceRemoved { info } => format!("{} removed", name(info)),
+        other => format!("{other:?}"),
+    })
+}
+
+/// The next `count` events other than ticks
+fn receive(rx: &Receiver<AppEvent>, count: usize) -> Vec<String> {
+    let mut seen = Vec::new();
+    while seen.len() < count {
+        match rx.recv_timeout(RECV_TIMEOUT) {
+            Ok(event) => seen.extend(describe(&event)),
+            Err(_) => panic!("only got {seen:?}"),
+        }
+    }
+    seen
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn touch_frames_are_assembled_on_syn_report() {
+    let name = "uinput-e2e touch";
+    let mut panel = touch_panel(name, false);
+    wait_for_node(&mut panel);
+    let rx = listen(name);
+
+    panel
+        .emit(&[
+            key(KeyCode::BTN_TOUCH, 1),
+            abs(AbsoluteAxisCode::ABS_X, 100),
+            abs(AbsoluteAxisCode::ABS_Y, 200),
+        ])
+        .unwrap();
+    // Either axis alone still makes one sample with the other's last value
+    panel.emit(&[abs(AbsoluteAxisCode::ABS_X, 150)]).unwrap();
+    panel.emit(&[abs(AbsoluteAxisCode::ABS_Y, 250)]).unwrap();
+    panel.emit(&[key(KeyCode::BTN_TOUCH, 0)]).unwrap();
+
+    assert_eq!(
+        receive(&rx, 4),
+        [
+            format!("{name} touch 100,200 released=false keepalive=false"),
+            format!("{name} touch 150,200 released=false keepalive=false"),
+            format!("{name} touch 150,250 released=false keepalive=false"),
+            format!("{name} touch 150,250 released=true keepalive=false"),
+        ]
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn repeated_taps_on_the_same_spot_each_press() {
+    let name = "uinput-e2e tap";
+    let mut panel = touch_panel(name, false);
+    wait_for_node(&mut panel);
+    let rx = listen(name);
+
+    panel
+        .emit(&[
+            key(KeyCode::BTN_TOUCH, 1),
+            abs(AbsoluteAxisCode::ABS_X, 300),
+            abs(AbsoluteAxisCode::ABS_Y, 400),
+        ])
+        .unwrap();
+    panel.emit(&[key(KeyCode::BTN_TOUCH, 0)]).unwrap();
+    // The controller reports the same spot again, so the axes don't change
+    // and the kernel sends no ABS events at all
+    for _ in 0..2 {
+        panel.emit(&[key(KeyCode::BTN_TOUCH, 1)]).unwrap();
+        panel.emit(&[key(KeyCode::BTN_TOUCH, 0)]).unwrap();
+    }
+
+    let press = format!("{name} touch 300,400 released=false keepalive=false");
+    let lift = format!("{name} touch 300,400 released=true keepalive=false");
+    assert_eq!(
+        receive(&rx, 6),
+        [press.as_str(), &lift, &press, &lift, &press, &lift]
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn resting_touches_are_kept_alive() {
+    let name = "uinput-e2e keepalive";
+    let mut panel = touch_panel(name, false);
+    wait_for_node(&mut panel);
+    let rx = listen_with_keepalive(name, Some(Duration::from_millis(100)));
+
+    panel
+        .emit(&[
+            key(KeyCode::BTN_TOUCH, 1),
+            abs(AbsoluteAxisCode::ABS_X, 500),
+            abs(AbsoluteAxisCode::ABS_Y, 600),
+        ])
+        .unwrap();
+    // Still, no events for a while
+    thread::sleep(Duration::from_millis(350));
+    panel.emit(&[key(KeyCode::BTN_TOUCH, 0)]).unwrap();
+
+    let mut seen = receive(&rx, 1);
+    while !seen.last().unwrap().contains("released=true") {
+        seen.extend(receive(&rx, 1));
+    }
+    let repeat = format!("{name} touch 500,600 released=false keepalive=true");
+    let (first, rest) = seen.split_first().unwrap();
+    let (last, repeats) = rest.split_last().unwrap();
+    assert_eq!(
+        *first,
+        format!("{name} touch 500,600 released=false keepalive=false")
+    );
+    assert_eq!(
+        *last,
+        format!("{name} touch 500,600 released=true keepalive=false")
+    );
+    // About one every 100 ms of the 350 ms rest
+    assert!(
+        (2..=4).contains(&repeats.len()) && repeats.iter().all(|event| *event == repeat),
+        "{seen:?}"
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn touch_state_recovers_after_the_kernel_drops_events() {
+    let name = "uinput-e2e overflow";
+    let mut panel = touch_panel(name, false);
+    wait_for_node(&mut panel);
+    let rx = listen(name);
+
+    // A stroke of far more frames than the kernel buffers for a reader, in one
+    // write so the listener can't keep up, ending with the finger lifted
+    let mut burst = vec![key(KeyCode::BTN_TOUCH, 1)];
+    for i in 0..20_000 {
+        burst.extend([
+            abs(AbsoluteAxisCode::ABS_X, 100 + i % 3000),
+            abs(AbsoluteAxisCode::ABS_Y, 100 + i % 2000),
+            InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0),
+        ]);
+    }
+    burst.extend([
+        abs(AbsoluteAxisCode::ABS_X, 1234),
+        abs(AbsoluteAxisCode::ABS_Y, 2345),
+        InputEvent::new(EventType::SYNCHRONIZATION.0, 0, 0),
+        key(KeyCode::BTN_TOUCH, 0),
+    ]);
+    panel.emit(&burst).unwrap();
+    thread::sleep(Duration::from_millis(200));
+
+    // A tap after the resync comes through as any other
+    panel
+        .emit(&[
+            key(KeyCode::BTN_TOUCH, 1),
+            abs(AbsoluteAxisCode::ABS_X, 700),
+            abs(AbsoluteAxisCode::ABS_Y, 800),
+        ])
+        .unwrap();
+    panel.emit(&[key(KeyCode::BTN_TOUCH, 0)]).unwrap();
+
+    let tap_press = format!("{name} touch 700,800 released=false keepalive=false");
+    let tap_lift = format!("{name} touch 700,800 released=true keepalive=false");
+    let mut seen = receive(&rx, 1);
+    while *seen.last().unwrap() != tap_lift {
+        seen.extend(receive(&rx, 1));
+    }
+
+    assert!(
+        seen.iter().any(|event| event.starts_with("EventsDropped")),
+        "no drop reported in {} events",
+        seen.len()
+    );
+    let touches: Vec<&String> = seen
+        .iter()
+        .filter(|event| event.contains(" touch "))
+        .collect();
+    assert!(touches.len() < 20_000, "nothing was dropped");
+    // The stroke ended where the finger lifted, resynced or not, and the tap
+    // after it is a press and a release of its own
+    let [.., lifted, pressed, released] = touches.as_slice() else {
+        panic!("too few touches: {touches:?}");
+    };
+    assert_eq!(
+        **lifted,
+        format!("{name} touch 1234,2345 released=true keepalive=false")
+    );
+    assert_eq!((*pressed, *released), (&tap_press, &tap_lift));
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn pen_leaving_range_releases_the_touch() {
+    let name = "uinput-e2e pen";
+    let mut pen = touch_panel(name, true);
+    wait_for_node(&mut pen);
+    let rx = listen(name);
+
+    pen.emit(&[
+        key(KeyCode::BTN_TOOL_PEN, 1),
+        abs(AbsoluteAxisCode::ABS_X, 100),
+        abs(AbsoluteAxisCode::ABS_Y, 200),
+    ])
+    .unwrap();
+    pen.emit(&[key(KeyCode::BTN_TOUCH, 1)]).unwrap();
+    pen.emit(&[abs(AbsoluteAxisCode::ABS_X, 110)]).unwrap();
+    // Out of range without BTN_TOUCH going up first
+    pen.emit(&[key(KeyCode::BTN_TOOL_PEN, 0)]).unwrap();
+
+    assert_eq!(
+        receive(&rx, 5),
+        [
+            format!("{name} hover 100,200 left=false"),
+            format!("{name} touch 100,200 released=false keepalive=false"),
+            format!("{name} touch 110,200 released=false keepalive=false"),
+            format!("{name} touch 110,200 released=true keepalive=false"),
+            format!("{name} hover 110,200 left=true"),
+        ]
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn relative_motion_becomes_mouse_events() {
+    let name = "uinput-e2e mouse";
+    let mut device = mouse(name);
+    wait_for_node(&mut device);
+    let rx = listen(name);
+
+    device
+        .emit(&[
+            rel(RelativeAxisCode::REL_X, 5),
+            rel(RelativeAxisCode::REL_Y, -3),
+        ])
+        .unwrap();
+    device.emit(&[rel(RelativeAxisCode::REL_X, -7)]).unwrap();
+
+    assert_eq!(
+        receive(&rx, 3),
+        [
+            format!("{name} mouse 5,0"),
+            format!("{name} mouse 0,-3"),
+            format!("{name} mouse -7,0"),
+        ]
+    );
+}
+
+#[test]
+#[ignore = "needs /dev/uinput, run with --ignored as root"]
+fn devices_created_after_startup_are_picked_up() {
+    let prefix = "uinput-e2e hotplug";
+    let first = format!("{prefix} first");
+    let second = format!("{prefix} second");
+    // The listeners refuse to start without any device
+    let mut present = keyboard(&first);
+    wait_for_node(&mut present);
+    let rx = listen(prefix);
+
+    let mut added = keyboard(&second);
+    wait_for_node(&mut added);
+    assert_eq!(receive(&rx, 1), [format!("{second} added")]);
+    thread::sleep(Duration::from_millis(300));
+
+    added.emit(&[key(KeyCode::KEY_B, 1)]).unwrap();
+    added.emit(&[key(KeyCode::KEY_B, 0)]).unwrap();
+    assert_eq!(
+        receive(&rx, 2),
+        [
+            format!("{second} key KEY_B repeat=false"),
+            format!("{second} release KEY_B"),
+        ]
+    );
+    // The device there from the start still has its listener
+    present.emit(&[key(KeyCode::KEY_A, 1)]).unwrap();
+    assert_eq!(receive(&rx, 1), [format!("{first} key KEY_A repeat=false")]);
+
+    drop(added);
+    assert_eq!(receive(&rx, 1), [format!("{second} removed")]);
+}